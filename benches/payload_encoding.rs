@@ -0,0 +1,48 @@
+//! Produce-path payload encoding: `String` + `IggyMessage::from_str` versus
+//! serializing straight into the `Bytes` payload ([`event_message`]).
+//!
+//! ```bash
+//! cargo bench --bench payload_encoding
+//! ```
+
+#![allow(clippy::unwrap_used)]
+
+use std::hint::black_box;
+use std::str::FromStr;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use iggy::prelude::IggyMessage;
+use iggy_sample::iggy_client::event_message;
+use iggy_sample::models::{Event, EventPayload};
+
+/// Event whose serialized form is roughly `size` bytes.
+fn event_of_size(size: usize) -> Event {
+    Event::new(
+        "bench.payload",
+        EventPayload::Generic(serde_json::json!({ "data": "x".repeat(size) })),
+    )
+}
+
+fn payload_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("payload_encoding");
+
+    for (label, size) in [("1KB", 1024), ("64KB", 64 * 1024)] {
+        let event = event_of_size(size);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("string", label), &event, |b, event| {
+            b.iter(|| {
+                let payload = serde_json::to_string(black_box(event)).unwrap();
+                IggyMessage::from_str(&payload).unwrap()
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("bytes", label), &event, |b, event| {
+            b.iter(|| event_message(black_box(event)).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, payload_encoding);
+criterion_main!(benches);
@@ -0,0 +1,209 @@
+//! Produce-path costs outside the network round trip: event JSON
+//! serialization, event → `IggyMessage` conversion, batch assembly, and
+//! the `RwLock` vs `ArcSwap` access patterns for the shared client.
+//!
+//! ```bash
+//! cargo bench --bench produce_path
+//! cargo bench --bench produce_path -- client_access
+//! ```
+
+#![allow(clippy::unwrap_used)]
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use iggy::prelude::IggyMessage;
+use iggy_sample::AppResult;
+use iggy_sample::iggy_client::{PayloadCompression, batch_message, event_message};
+use iggy_sample::models::{Event, EventPayload};
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Payload sizes measured, in bytes of the `data` field.
+const SIZES: [(&str, usize); 3] = [("128B", 128), ("1KB", 1024), ("64KB", 64 * 1024)];
+
+/// Batch sizes measured; 1000 is the default `BATCH_MAX_SIZE`.
+const BATCH_SIZES: [usize; 3] = [10, 100, 1000];
+
+/// Concurrent readers in the contended client-access benchmarks.
+const READERS: usize = 8;
+
+/// Client accesses per reader per iteration.
+const READS_PER_READER: usize = 1000;
+
+/// Order-like event whose `data` field is `size` bytes, with the optional
+/// fields a client typically sets.
+fn event_of_size(size: usize) -> Event {
+    let mut event = Event::new(
+        "order.created",
+        EventPayload::Generic(serde_json::json!({
+            "order_id": "ORD-2024-000123",
+            "customer_id": "CUST-42",
+            "total": "129.99",
+            "data": "x".repeat(size),
+        })),
+    );
+    event.correlation_id = Some(Uuid::new_v4());
+    event
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event_serialization");
+
+    for (label, size) in SIZES {
+        let event = event_of_size(size);
+        let json = serde_json::to_vec(&event).unwrap();
+        group.throughput(Throughput::Bytes(json.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("to_vec", label), &event, |b, event| {
+            b.iter(|| serde_json::to_vec(black_box(event)).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("from_slice", label), &json, |b, json| {
+            b.iter(|| serde_json::from_slice::<Event>(black_box(json)).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
+fn message_conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message_conversion");
+
+    for (label, size) in SIZES {
+        let event = event_of_size(size);
+        group.throughput(Throughput::Elements(1));
+
+        group.bench_with_input(BenchmarkId::new("single", label), &event, |b, event| {
+            b.iter(|| event_message(black_box(event)).unwrap());
+        });
+        for compression in [
+            PayloadCompression::None,
+            PayloadCompression::Gzip,
+            PayloadCompression::Zstd,
+        ] {
+            let id = format!("batch_{compression}");
+            group.bench_with_input(BenchmarkId::new(id, label), &event, |b, event| {
+                b.iter(|| batch_message(black_box(event), compression, 0).unwrap());
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn batch_assembly(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_assembly");
+
+    for batch_size in BATCH_SIZES {
+        let events: Vec<Event> = (0..batch_size).map(|_| event_of_size(1024)).collect();
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        // What `send_events_batch_partitioned` does before the send
+        group.bench_with_input(
+            BenchmarkId::from_parameter(batch_size),
+            &events,
+            |b, events| {
+                b.iter(|| {
+                    black_box(events)
+                        .iter()
+                        .map(|event| batch_message(event, PayloadCompression::None, 0))
+                        .collect::<AppResult<Vec<IggyMessage>>>()
+                        .unwrap()
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Stand-in for the SDK client held by `IggyClientWrapper`.
+struct Client {
+    address: String,
+}
+
+fn client() -> Client {
+    Client {
+        address: "127.0.0.1:8090".to_string(),
+    }
+}
+
+/// Read the client `READS_PER_READER` times from each of `READERS` tasks.
+///
+/// Both contended variants pay the same boxing and `Arc` clone per read, so
+/// the difference between them is the access itself.
+async fn concurrent_reads<F>(read: F)
+where
+    F: Fn() -> futures_util::future::BoxFuture<'static, usize> + Send + Sync + 'static,
+{
+    let read = Arc::new(read);
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let read = Arc::clone(&read);
+            tokio::spawn(async move {
+                for _ in 0..READS_PER_READER {
+                    black_box(read().await);
+                }
+            })
+        })
+        .collect();
+    for reader in readers {
+        reader.await.unwrap();
+    }
+}
+
+fn client_access(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let rwlock = Arc::new(RwLock::new(client()));
+    let swap = Arc::new(ArcSwap::from_pointee(client()));
+    let mut group = c.benchmark_group("client_access");
+
+    group.throughput(Throughput::Elements(1));
+    group.bench_function(BenchmarkId::new("rwlock", "uncontended"), |b| {
+        b.to_async(&runtime)
+            .iter(|| async { rwlock.read().await.address.len() });
+    });
+    group.bench_function(BenchmarkId::new("arc_swap", "uncontended"), |b| {
+        b.iter(|| swap.load().address.len());
+    });
+
+    group.throughput(Throughput::Elements((READERS * READS_PER_READER) as u64));
+    group.bench_function(
+        BenchmarkId::new("rwlock", format!("{READERS}_readers")),
+        |b| {
+            b.to_async(&runtime).iter(|| {
+                let rwlock = Arc::clone(&rwlock);
+                concurrent_reads(move || {
+                    let rwlock = Arc::clone(&rwlock);
+                    Box::pin(async move { rwlock.read().await.address.len() })
+                })
+            });
+        },
+    );
+    group.bench_function(
+        BenchmarkId::new("arc_swap", format!("{READERS}_readers")),
+        |b| {
+            b.to_async(&runtime).iter(|| {
+                let swap = Arc::clone(&swap);
+                concurrent_reads(move || {
+                    let swap = Arc::clone(&swap);
+                    Box::pin(async move { swap.load().address.len() })
+                })
+            });
+        },
+    );
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    serialization,
+    message_conversion,
+    batch_assembly,
+    client_access
+);
+criterion_main!(benches);
@@ -0,0 +1,156 @@
+//! Route polled events to per-domain handlers by `event_type`.
+//!
+//! Event types follow `domain.action` (`user.created`, `inventory.reserved`,
+//! `payment.captured`, ...). This example polls the default topic through
+//! [`ApiClient`] and dispatches each event on its domain prefix, the way a
+//! downstream service owning several domains would.
+//!
+//! ```bash
+//! API_URL=http://localhost:8000 cargo run --example domain_router --features client
+//! ```
+//!
+//! `API_KEY` is sent when set. Offsets are auto-committed, so a restart
+//! resumes after the last polled batch.
+
+use std::time::Duration;
+
+use iggy_sample::client::{ApiClient, ClientError};
+use iggy_sample::models::{
+    Event, EventPayload, InventoryEvent, OrderEvent, PaymentEvent, PollQuery, UserEvent,
+};
+
+/// Consumer ID the example commits offsets under.
+const CONSUMER_ID: u32 = 7;
+
+/// Wait between polls that return nothing.
+const IDLE_BACKOFF: Duration = Duration::from_secs(1);
+
+#[tokio::main]
+async fn main() -> Result<(), ClientError> {
+    let url = std::env::var("API_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let mut client = ApiClient::new(&url)?;
+    if let Ok(key) = std::env::var("API_KEY") {
+        client = client.with_api_key(key);
+    }
+
+    let query = PollQuery {
+        consumer_id: CONSUMER_ID,
+        count: 100,
+        auto_commit: true,
+        ..PollQuery::default()
+    };
+
+    loop {
+        let polled = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            polled = client.poll(&query) => polled?,
+        };
+        if polled.messages.is_empty() {
+            tokio::time::sleep(IDLE_BACKOFF).await;
+            continue;
+        }
+        for message in polled.messages {
+            route(&message.event);
+        }
+    }
+}
+
+/// Dispatch on the `domain` part of `domain.action`.
+///
+/// The payload variant is checked too: an event whose type and payload
+/// disagree is reported rather than handled by the wrong domain.
+fn route(event: &Event) {
+    let domain = event.event_type.split('.').next().unwrap_or_default();
+    match (domain, &event.payload) {
+        ("user", EventPayload::User(user)) => handle_user(event, user),
+        ("order", EventPayload::Order(order)) => handle_order(event, order),
+        ("inventory", EventPayload::Inventory(inventory)) => handle_inventory(event, inventory),
+        ("payment", EventPayload::Payment(payment)) => handle_payment(event, payment),
+        (_, EventPayload::Generic(_)) => {
+            println!(
+                "[generic] {} ({}) left unhandled",
+                event.event_type, event.id
+            );
+        }
+        _ => eprintln!(
+            "[unrouted] {} ({}) does not match its payload type",
+            event.event_type, event.id
+        ),
+    }
+}
+
+fn handle_user(event: &Event, user: &UserEvent) {
+    match user {
+        UserEvent::Created { user_id, email, .. } => {
+            println!("[user] welcome mail to {email} for {user_id}");
+        }
+        UserEvent::Deleted { user_id } => println!("[user] purge data of {user_id}"),
+        other => println!("[user] {} audited: {other:?}", event.id),
+    }
+}
+
+fn handle_order(event: &Event, order: &OrderEvent) {
+    match order {
+        OrderEvent::Created {
+            order_id,
+            total_amount,
+            ..
+        } => println!("[order] {order_id} placed for {total_amount}"),
+        OrderEvent::Cancelled { order_id, reason } => {
+            println!("[order] {order_id} cancelled: {reason}");
+        }
+        other => println!("[order] {} tracked: {other:?}", event.id),
+    }
+}
+
+fn handle_inventory(_event: &Event, inventory: &InventoryEvent) {
+    match inventory {
+        InventoryEvent::StockAdjusted {
+            sku,
+            warehouse_id,
+            quantity_on_hand,
+            ..
+        } => println!("[inventory] {sku}@{warehouse_id} now {quantity_on_hand} on hand"),
+        InventoryEvent::Reserved {
+            sku,
+            quantity,
+            order_id,
+            expires_at,
+            ..
+        } => println!("[inventory] {quantity} x {sku} held for {order_id} until {expires_at}"),
+        InventoryEvent::Released {
+            sku,
+            quantity,
+            reason,
+            ..
+        } => println!("[inventory] {quantity} x {sku} released: {reason}"),
+    }
+}
+
+fn handle_payment(_event: &Event, payment: &PaymentEvent) {
+    match payment {
+        PaymentEvent::Authorized {
+            payment_id,
+            amount,
+            currency,
+            ..
+        } => println!("[payment] {payment_id} authorized for {amount} {currency}"),
+        PaymentEvent::Captured {
+            payment_id,
+            amount,
+            currency,
+        } => println!("[payment] {payment_id} captured {amount} {currency}"),
+        PaymentEvent::Refunded {
+            payment_id,
+            amount,
+            currency,
+            reason,
+            ..
+        } => println!("[payment] {payment_id} refunded {amount} {currency}: {reason}"),
+        PaymentEvent::Failed {
+            payment_id,
+            failure_code,
+            ..
+        } => eprintln!("[payment] {payment_id} failed: {failure_code}"),
+    }
+}
@@ -0,0 +1,46 @@
+//! Fuzz the consume-path decoding of message payloads: optional
+//! decompression, then JSON into an `Event`.
+//!
+//! Decoding arbitrary bytes must fail cleanly, and any payload accepted as
+//! an `Event` must survive a serialize/deserialize round trip unchanged.
+//!
+//! ```bash
+//! cargo +nightly fuzz run fuzz_event_deserialize
+//! ```
+
+#![no_main]
+
+use iggy_sample::iggy_client::decompress_payload;
+use iggy_sample::models::Event;
+use iggy_sample::validation::validate_event_type;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the content-encoding the payload is tagged with
+    let Some((&encoding, payload)) = data.split_first() else {
+        return;
+    };
+    let decoded = match encoding % 3 {
+        0 => payload.to_vec(),
+        1 => match decompress_payload("gzip", payload) {
+            Ok(decoded) => decoded,
+            Err(_) => return,
+        },
+        _ => match decompress_payload("zstd", payload) {
+            Ok(decoded) => decoded,
+            Err(_) => return,
+        },
+    };
+
+    let Ok(event) = serde_json::from_slice::<Event>(&decoded) else {
+        return;
+    };
+    let _ = validate_event_type(&event.event_type);
+
+    let encoded = serde_json::to_value(&event).expect("a decoded event serializes");
+    let reparsed: Event = serde_json::from_value(encoded.clone()).expect("round trip parses");
+    assert_eq!(
+        serde_json::to_value(&reparsed).expect("a decoded event serializes"),
+        encoded
+    );
+});
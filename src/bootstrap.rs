@@ -0,0 +1,551 @@
+//! Declarative stream/topic bootstrap.
+//!
+//! Beyond the default stream and topic, a bootstrap spec lists the streams
+//! and topics a deployment needs, with their partition counts, message
+//! expiry and size limits. `initialize_defaults()` creates whatever is
+//! missing at startup; `GET /admin/bootstrap/status` reports where the
+//! server differs from the spec.
+//!
+//! # Format
+//!
+//! JSON (`BOOTSTRAP_SPEC`, or a `BOOTSTRAP_SPEC_FILE`) or TOML (a
+//! `BOOTSTRAP_SPEC_FILE` ending in `.toml`):
+//!
+//! ```json
+//! {
+//!   "templates": {
+//!     "events": { "partitions": 6, "message_expiry_secs": 604800 }
+//!   },
+//!   "streams": [
+//!     {
+//!       "name": "orders",
+//!       "topics": [
+//!         { "name": "created", "template": "events" },
+//!         { "name": "archive", "template": "events", "max_size_bytes": 10737418240 },
+//!         { "name": "commands", "partitions": 1 }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! A template holds topic settings shared by several topics; settings on
+//! the topic itself override the template's. Partitions default to 1;
+//! unset expiry and size limits use the server defaults.
+//!
+//! # Retention
+//!
+//! A topic (or template) may carry a `retention` policy, which unlike the
+//! settings above is kept up on existing topics too, by the retention
+//! manager (see [`crate::services::RetentionManager`]):
+//!
+//! ```json
+//! { "name": "clicks", "retention": { "max_age_secs": 86400, "purge_interval_secs": 3600 } }
+//! ```
+//!
+//! `max_age_secs` and `max_size_bytes` are set as the topic's message
+//! expiry and size limit, and take precedence over `message_expiry_secs`
+//! and `max_size_bytes`; `purge_interval_secs` deletes every message of the
+//! topic that often, for scratch topics.
+//!
+//! # Idempotence
+//!
+//! Existing streams and topics are left as they are: the spec only creates
+//! what is missing. A topic whose settings differ from the spec (e.g. fewer
+//! partitions) is reported as drifted, not changed, except for the
+//! settings a retention policy keeps up.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::validation::{NamingPolicy, validate_partition_count, validate_resource_name};
+
+/// Topic settings, as given by a template or a topic entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSettings {
+    /// Number of partitions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitions: Option<u32>,
+    /// Age after which messages are deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_expiry_secs: Option<u64>,
+    /// Size after which the oldest segments are deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    /// Retention kept up on the existing topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionPolicy>,
+}
+
+/// Retention of a topic, enforced by the retention manager.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Message expiry kept on the topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+    /// Size limit kept on the topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    /// Delete every message of the topic this often
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purge_interval_secs: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Check that the policy sets something, and nothing to zero.
+    fn validate(&self, owner: &str) -> AppResult<()> {
+        let values = [
+            self.max_age_secs,
+            self.max_size_bytes,
+            self.purge_interval_secs,
+        ];
+        if values.iter().all(Option::is_none) {
+            return Err(AppError::ConfigError(format!(
+                "Invalid bootstrap spec: {owner} has an empty retention policy"
+            )));
+        }
+        if values.contains(&Some(0)) {
+            return Err(AppError::ConfigError(format!(
+                "Invalid bootstrap spec: {owner} has a retention value of 0"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl TopicSettings {
+    /// These settings, with unset ones taken from `fallback`.
+    fn or(&self, fallback: &TopicSettings) -> TopicSettings {
+        TopicSettings {
+            partitions: self.partitions.or(fallback.partitions),
+            message_expiry_secs: self.message_expiry_secs.or(fallback.message_expiry_secs),
+            max_size_bytes: self.max_size_bytes.or(fallback.max_size_bytes),
+            retention: self.retention.or(fallback.retention),
+        }
+    }
+}
+
+/// A topic of a [`StreamSpec`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSpec {
+    /// Topic name
+    pub name: String,
+    /// Template to take unset settings from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Settings overriding the template's
+    #[serde(flatten)]
+    pub settings: TopicSettings,
+}
+
+/// A stream and its topics.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamSpec {
+    /// Stream name
+    pub name: String,
+    /// Topics to create in the stream
+    #[serde(default)]
+    pub topics: Vec<TopicSpec>,
+}
+
+/// Streams and topics to create at startup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapSpec {
+    /// Named topic settings, referenced by `template`
+    #[serde(default)]
+    pub templates: BTreeMap<String, TopicSettings>,
+    /// Streams to create
+    #[serde(default)]
+    pub streams: Vec<StreamSpec>,
+}
+
+/// A topic of the spec with its template applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedTopic {
+    pub stream: String,
+    pub topic: String,
+    pub partitions: u32,
+    /// `None` = server default
+    pub message_expiry_secs: Option<u64>,
+    /// `None` = server default
+    pub max_size_bytes: Option<u64>,
+    /// Retention kept up by the retention manager
+    pub retention: Option<RetentionPolicy>,
+}
+
+/// Settings of a topic as it exists on the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActualTopic {
+    pub partitions: u32,
+    /// `None` = never expires
+    pub message_expiry_secs: Option<u64>,
+    /// `None` = unlimited
+    pub max_size_bytes: Option<u64>,
+}
+
+impl BootstrapSpec {
+    /// Parse and validate a JSON spec.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` if the spec does not parse or is
+    /// invalid (see [`BootstrapSpec::validate`]).
+    pub fn from_json(text: &str) -> AppResult<Self> {
+        let spec: Self = serde_json::from_str(text)
+            .map_err(|e| AppError::ConfigError(format!("Invalid bootstrap spec: {e}")))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Parse and validate a TOML spec.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` if the spec does not parse or is
+    /// invalid (see [`BootstrapSpec::validate`]).
+    pub fn from_toml(text: &str) -> AppResult<Self> {
+        let spec: Self = toml::from_str(text)
+            .map_err(|e| AppError::ConfigError(format!("Invalid bootstrap spec: {e}")))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Read a spec file: TOML if its name ends in `.toml`, JSON otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` if the file cannot be read, does not
+    /// parse, or is invalid.
+    pub fn from_file(path: &Path) -> AppResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            AppError::ConfigError(format!(
+                "Cannot read bootstrap spec '{}': {e}",
+                path.display()
+            ))
+        })?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            Self::from_toml(&text)
+        } else {
+            Self::from_json(&text)
+        }
+    }
+
+    /// Check names, partition counts and template references, and that no
+    /// stream or topic is listed twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` naming the first problem found.
+    pub fn validate(&self) -> AppResult<()> {
+        for (name, template) in &self.templates {
+            if let Some(partitions) = template.partitions {
+                validate_partition_count(partitions, &format!("Template '{name}'"))
+                    .map_err(invalid)?;
+            }
+            if let Some(retention) = &template.retention {
+                retention.validate(&format!("template '{name}'"))?;
+            }
+        }
+
+        let mut streams = HashSet::new();
+        for stream in &self.streams {
+            validate_resource_name(&stream.name, "Stream").map_err(invalid)?;
+            if !streams.insert(&stream.name) {
+                return Err(AppError::ConfigError(format!(
+                    "Invalid bootstrap spec: stream '{}' is listed twice",
+                    stream.name
+                )));
+            }
+
+            let mut topics = HashSet::new();
+            for topic in &stream.topics {
+                validate_resource_name(&topic.name, "Topic").map_err(invalid)?;
+                if !topics.insert(&topic.name) {
+                    return Err(AppError::ConfigError(format!(
+                        "Invalid bootstrap spec: topic '{}/{}' is listed twice",
+                        stream.name, topic.name
+                    )));
+                }
+                if let Some(template) = &topic.template
+                    && !self.templates.contains_key(template)
+                {
+                    return Err(AppError::ConfigError(format!(
+                        "Invalid bootstrap spec: topic '{}/{}' uses unknown template '{template}'",
+                        stream.name, topic.name
+                    )));
+                }
+                if let Some(partitions) = topic.settings.partitions {
+                    validate_partition_count(partitions, "Topic").map_err(invalid)?;
+                }
+                if let Some(retention) = &topic.settings.retention {
+                    retention.validate(&format!("topic '{}/{}'", stream.name, topic.name))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check every stream and topic name against the naming policy, like
+    /// the create endpoints do.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` naming the first violation.
+    pub fn check_naming(&self, policy: &NamingPolicy) -> AppResult<()> {
+        for stream in &self.streams {
+            policy.check_stream(&stream.name).map_err(invalid)?;
+            for topic in &stream.topics {
+                policy.check_topic(&topic.name).map_err(invalid)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every topic of the spec with its template applied, in spec order.
+    pub fn planned_topics(&self) -> Vec<PlannedTopic> {
+        let mut planned = Vec::new();
+        for stream in &self.streams {
+            for topic in &stream.topics {
+                let settings = match topic
+                    .template
+                    .as_ref()
+                    .and_then(|name| self.templates.get(name))
+                {
+                    Some(template) => topic.settings.or(template),
+                    None => topic.settings.clone(),
+                };
+                planned.push(PlannedTopic {
+                    stream: stream.name.clone(),
+                    topic: topic.name.clone(),
+                    partitions: settings.partitions.unwrap_or(1),
+                    message_expiry_secs: settings
+                        .retention
+                        .and_then(|r| r.max_age_secs)
+                        .or(settings.message_expiry_secs),
+                    max_size_bytes: settings
+                        .retention
+                        .and_then(|r| r.max_size_bytes)
+                        .or(settings.max_size_bytes),
+                    retention: settings.retention,
+                });
+            }
+        }
+        planned
+    }
+
+    /// The topics of the spec with a retention policy, in spec order.
+    pub fn retention_topics(&self) -> Vec<PlannedTopic> {
+        self.planned_topics()
+            .into_iter()
+            .filter(|topic| topic.retention.is_some())
+            .collect()
+    }
+}
+
+impl PlannedTopic {
+    /// Differences between this topic and how it exists on the server, one
+    /// description per setting. Settings left to the server default are
+    /// not compared.
+    pub fn drift(&self, actual: &ActualTopic) -> Vec<String> {
+        let mut drift = Vec::new();
+        if actual.partitions != self.partitions {
+            drift.push(format!(
+                "partitions: spec {}, server {}",
+                self.partitions, actual.partitions
+            ));
+        }
+        if let Some(expiry) = self.message_expiry_secs
+            && actual.message_expiry_secs != Some(expiry)
+        {
+            drift.push(format!(
+                "message_expiry_secs: spec {expiry}, server {}",
+                describe(actual.message_expiry_secs, "never")
+            ));
+        }
+        if let Some(size) = self.max_size_bytes
+            && actual.max_size_bytes != Some(size)
+        {
+            drift.push(format!(
+                "max_size_bytes: spec {size}, server {}",
+                describe(actual.max_size_bytes, "unlimited")
+            ));
+        }
+        drift
+    }
+}
+
+/// Turn a validation error into a config error about the spec.
+fn invalid(error: AppError) -> AppError {
+    match error {
+        AppError::BadRequest(message) => {
+            AppError::ConfigError(format!("Invalid bootstrap spec: {message}"))
+        }
+        other => other,
+    }
+}
+
+fn describe(value: Option<u64>, none: &str) -> String {
+    value.map_or_else(|| none.to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"{
+        "templates": { "events": { "partitions": 6, "message_expiry_secs": 3600 } },
+        "streams": [
+            {
+                "name": "orders",
+                "topics": [
+                    { "name": "created", "template": "events" },
+                    { "name": "archive", "template": "events", "partitions": 2 },
+                    { "name": "commands" }
+                ]
+            },
+            { "name": "empty" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_planned_topics_apply_templates_and_overrides() {
+        let spec = BootstrapSpec::from_json(SPEC).unwrap();
+        let planned = spec.planned_topics();
+        assert_eq!(planned.len(), 3);
+        assert_eq!(
+            (planned[0].partitions, planned[0].message_expiry_secs),
+            (6, Some(3600))
+        );
+        assert_eq!(
+            (planned[1].partitions, planned[1].message_expiry_secs),
+            (2, Some(3600))
+        );
+        assert_eq!(
+            (planned[2].partitions, planned[2].message_expiry_secs),
+            (1, None)
+        );
+        assert_eq!(spec.streams[1].topics.len(), 0);
+    }
+
+    #[test]
+    fn test_toml_spec_matches_json() {
+        let toml = r#"
+            [templates.events]
+            partitions = 6
+            message_expiry_secs = 3600
+
+            [[streams]]
+            name = "orders"
+
+            [[streams.topics]]
+            name = "created"
+            template = "events"
+
+            [[streams.topics]]
+            name = "archive"
+            template = "events"
+            partitions = 2
+
+            [[streams.topics]]
+            name = "commands"
+
+            [[streams]]
+            name = "empty"
+        "#;
+        assert_eq!(
+            BootstrapSpec::from_toml(toml).unwrap(),
+            BootstrapSpec::from_json(SPEC).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_templates_and_duplicates() {
+        let unknown = r#"{"streams": [{"name": "s", "topics": [{"name": "t", "template": "x"}]}]}"#;
+        let err = BootstrapSpec::from_json(unknown).unwrap_err();
+        assert!(err.to_string().contains("unknown template"));
+
+        let duplicate = r#"{"streams": [{"name": "s"}, {"name": "s"}]}"#;
+        let err = BootstrapSpec::from_json(duplicate).unwrap_err();
+        assert!(err.to_string().contains("listed twice"));
+
+        let zero = r#"{"streams": [{"name": "s", "topics": [{"name": "t", "partitions": 0}]}]}"#;
+        assert!(BootstrapSpec::from_json(zero).is_err());
+    }
+
+    #[test]
+    fn test_retention_policies_are_inherited_and_validated() {
+        let spec = r#"{
+            "templates": { "scratch": { "retention": { "purge_interval_secs": 60 } } },
+            "streams": [{
+                "name": "s",
+                "topics": [
+                    { "name": "a", "template": "scratch" },
+                    {
+                        "name": "b",
+                        "message_expiry_secs": 60,
+                        "retention": { "max_age_secs": 3600 }
+                    },
+                    { "name": "c" }
+                ]
+            }]
+        }"#;
+        let topics = BootstrapSpec::from_json(spec).unwrap().retention_topics();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].retention.unwrap().purge_interval_secs, Some(60));
+        // The policy's age wins over the creation setting
+        assert_eq!(topics[1].message_expiry_secs, Some(3600));
+
+        let empty = r#"{"streams": [{"name": "s", "topics": [{"name": "t", "retention": {}}]}]}"#;
+        let err = BootstrapSpec::from_json(empty).unwrap_err();
+        assert!(err.to_string().contains("empty retention policy"));
+
+        let zero = r#"{"templates": {"x": {"retention": {"max_size_bytes": 0}}}}"#;
+        let err = BootstrapSpec::from_json(zero).unwrap_err();
+        assert!(err.to_string().contains("retention value of 0"));
+    }
+
+    #[test]
+    fn test_check_naming_applies_policy() {
+        let spec = BootstrapSpec::from_json(SPEC).unwrap();
+        assert!(spec.check_naming(&NamingPolicy::default()).is_ok());
+
+        let policy = NamingPolicy::new(None, Some("[a-z]+"), vec!["empty".to_string()]).unwrap();
+        let err = spec.check_naming(&policy).unwrap_err();
+        assert!(matches!(err, AppError::ConfigError(m) if m.contains("reserved prefix 'empty'")));
+    }
+
+    #[test]
+    fn test_drift_compares_only_specified_settings() {
+        let spec = BootstrapSpec::from_json(SPEC).unwrap();
+        let planned = spec.planned_topics();
+        let actual = ActualTopic {
+            partitions: 6,
+            message_expiry_secs: Some(3600),
+            max_size_bytes: Some(1024),
+        };
+        assert!(planned[0].drift(&actual).is_empty());
+
+        let drift = planned[1].drift(&ActualTopic {
+            message_expiry_secs: None,
+            ..actual.clone()
+        });
+        assert_eq!(
+            drift,
+            vec![
+                "partitions: spec 2, server 6".to_string(),
+                "message_expiry_secs: spec 3600, server never".to_string(),
+            ]
+        );
+
+        // Nothing but partitions is pinned for "commands"
+        let drift = planned[2].drift(&ActualTopic {
+            partitions: 1,
+            ..actual
+        });
+        assert!(drift.is_empty());
+    }
+}
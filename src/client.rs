@@ -0,0 +1,930 @@
+//! Typed Rust client for this service's HTTP API (`client` feature).
+//!
+//! Wraps `reqwest` and reuses the [`crate::models`] wire types, so a Rust
+//! service integrating with the API gets compile-checked requests and
+//! responses instead of hand-written JSON.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use iggy_sample::client::ApiClient;
+//! use iggy_sample::models::{Event, EventPayload, PollQuery};
+//!
+//! # async fn demo() -> Result<(), iggy_sample::client::ClientError> {
+//! let client = ApiClient::new("http://localhost:8000")?.with_api_key("secret");
+//!
+//! let event = Event::new("user.created", EventPayload::Generic(serde_json::json!({})));
+//! client.send(&event, None).await?;
+//!
+//! let polled = client.poll(&PollQuery::default()).await?;
+//! println!("received {} messages", polled.tail.count);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Errors
+//!
+//! Non-2xx responses decode the server's JSON error body into
+//! [`ClientError::Api`], keeping the `retryable` / `retry_after_ms` hints so
+//! callers can drive their own backoff.
+
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::middleware::admin::ADMIN_KEY_HEADER;
+use crate::middleware::auth::API_KEY_HEADER;
+use crate::middleware::timeout::REQUEST_TIMEOUT_HEADER;
+use crate::models::{
+    AckOffset, AckRequest, AckResponse, AuditLogResponse, AuditQuery, BenchmarkRequest,
+    BenchmarkResponse, BootstrapStatusResponse, BulkCreateTopicsResponse, ChangePasswordRequest,
+    ConsumerInfo, ConsumerLagResponse, CreatePipelineRequest, CreateScheduleRequest,
+    CreateStreamRequest, CreateTopicRequest, CreateUserRequest, Event, EventTypeInfo,
+    HealthResponse, NackRequest, NackResponse, PeekQuery, PipelineInfo, PollMessagesResponse,
+    PollQuery, ReceivedMessage, RenameRequest, RetentionStatusResponse, ScheduleInfo,
+    ScheduledMessage, SearchQuery, SearchResponse, SendBatchRequest, SendMessageRequest,
+    SendMessageResponse, ServerInfoResponse, StatsResponse, StreamInfo, TopTalkersResponse,
+    TopicInfo, TopicStatsResponse, UpdatePermissionsRequest, UpdateScheduleRequest,
+    UserPermissions, UserResponse,
+};
+
+/// Error body returned by the API on non-2xx responses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiErrorBody {
+    /// Machine-readable error code (e.g. `not_found`, `circuit_open`)
+    pub error: String,
+    /// Human-readable message
+    pub message: String,
+    /// Whether the request may be retried unchanged
+    #[serde(default)]
+    pub retryable: bool,
+    /// Server-computed backoff hint in milliseconds
+    #[serde(default)]
+    pub retry_after_ms: Option<u64>,
+    /// `X-Request-Id` of the failed request, for finding it in server logs
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+/// Errors returned by [`ApiClient`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// The base URL could not be parsed or cannot carry a path.
+    #[error("Invalid base URL '{0}'")]
+    InvalidUrl(String),
+
+    /// Transport failure or undecodable success body.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The API answered with a non-2xx status.
+    #[error("API error {status}: {} ({})", body.message, body.error)]
+    Api {
+        status: StatusCode,
+        body: ApiErrorBody,
+    },
+}
+
+impl ClientError {
+    /// Whether retrying the same request may succeed.
+    ///
+    /// API errors use the server's `retryable` flag; transport-level
+    /// connect and timeout failures are treated as retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::InvalidUrl(_) => false,
+            ClientError::Http(e) => e.is_connect() || e.is_timeout(),
+            ClientError::Api { body, .. } => body.retryable,
+        }
+    }
+
+    /// The server's retry hint, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ClientError::Api { body, .. } => body.retry_after_ms.map(Duration::from_millis),
+            _ => None,
+        }
+    }
+}
+
+/// Typed client for the iggy_sample HTTP API.
+///
+/// Cheap to clone: the underlying `reqwest::Client` pools connections.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    api_key: Option<String>,
+    admin_key: Option<String>,
+    request_timeout: Option<Duration>,
+}
+
+impl ApiClient {
+    /// Create a client for the service at `base_url` (e.g. `http://localhost:8000`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::InvalidUrl`] if the URL does not parse or
+    /// cannot be a base (e.g. `mailto:`).
+    pub fn new(base_url: &str) -> Result<Self, ClientError> {
+        let parsed =
+            Url::parse(base_url).map_err(|_| ClientError::InvalidUrl(base_url.to_string()))?;
+        if parsed.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(base_url.to_string()));
+        }
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: parsed,
+            api_key: None,
+            admin_key: None,
+            request_timeout: None,
+        })
+    }
+
+    /// Send `X-API-Key` with every request.
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Send `X-Admin-Key` with every request (needed for `/admin/users`).
+    #[must_use]
+    pub fn with_admin_key(mut self, admin_key: impl Into<String>) -> Self {
+        self.admin_key = Some(admin_key.into());
+        self
+    }
+
+    /// Send `X-Request-Timeout` with every request.
+    ///
+    /// The server clamps the value to its own operation timeout, so this
+    /// can only shorten server-side work.
+    #[must_use]
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Use a preconfigured `reqwest::Client` (TLS, proxies, pool tuning).
+    #[must_use]
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    // =========================================================================
+    // Health & Monitoring
+    // =========================================================================
+
+    /// `GET /health`
+    pub async fn health(&self) -> Result<HealthResponse, ClientError> {
+        self.json(self.request(Method::GET, &["health"])).await
+    }
+
+    /// `GET /ready` - `true` when the service reports ready (200).
+    pub async fn ready(&self) -> Result<bool, ClientError> {
+        let response = self.request(Method::GET, &["ready"]).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    /// `GET /stats`
+    pub async fn stats(&self) -> Result<StatsResponse, ClientError> {
+        self.json(self.request(Method::GET, &["stats"])).await
+    }
+
+    /// `GET /event-types`
+    pub async fn event_types(&self) -> Result<Vec<EventTypeInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["event-types"])).await
+    }
+
+    /// `GET /admin/server-info`
+    pub async fn server_info(&self) -> Result<ServerInfoResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "server-info"]))
+            .await
+    }
+
+    /// `GET /admin/bootstrap/status`
+    pub async fn bootstrap_status(&self) -> Result<BootstrapStatusResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "bootstrap", "status"]))
+            .await
+    }
+
+    /// `GET /admin/retention`
+    pub async fn retention_status(&self) -> Result<RetentionStatusResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "retention"]))
+            .await
+    }
+
+    // =========================================================================
+    // Messages
+    // =========================================================================
+
+    /// `POST /messages` - send one event to the default stream/topic.
+    pub async fn send(
+        &self,
+        event: &Event,
+        partition_key: Option<&str>,
+    ) -> Result<SendMessageResponse, ClientError> {
+        self.json(
+            self.request(Method::POST, &["messages"])
+                .json(&send_request(event, partition_key)),
+        )
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics/{topic}/messages`
+    pub async fn send_to(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partition_key: Option<&str>,
+    ) -> Result<SendMessageResponse, ClientError> {
+        self.json(
+            self.request(
+                Method::POST,
+                &["streams", stream, "topics", topic, "messages"],
+            )
+            .json(&send_request(event, partition_key)),
+        )
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics/{topic}/messages` with `deliver_at` or
+    /// `delay_ms` set: the event is held for delayed delivery.
+    pub async fn schedule_to(
+        &self,
+        stream: &str,
+        topic: &str,
+        request: &SendMessageRequest,
+    ) -> Result<ScheduledMessage, ClientError> {
+        self.json(
+            self.request(
+                Method::POST,
+                &["streams", stream, "topics", topic, "messages"],
+            )
+            .json(request),
+        )
+        .await
+    }
+
+    /// `GET /scheduled`
+    pub async fn scheduled(&self) -> Result<Vec<ScheduledMessage>, ClientError> {
+        self.json(self.request(Method::GET, &["scheduled"])).await
+    }
+
+    /// `DELETE /scheduled/{id}`
+    pub async fn cancel_scheduled(&self, id: Uuid) -> Result<ScheduledMessage, ClientError> {
+        let id = id.to_string();
+        self.json(self.request(Method::DELETE, &["scheduled", &id]))
+            .await
+    }
+
+    /// `POST /schedules` - register (or replace) a recurring schedule.
+    pub async fn create_schedule(
+        &self,
+        request: &CreateScheduleRequest,
+    ) -> Result<ScheduleInfo, ClientError> {
+        self.json(self.request(Method::POST, &["schedules"]).json(request))
+            .await
+    }
+
+    /// `GET /schedules`
+    pub async fn schedules(&self) -> Result<Vec<ScheduleInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["schedules"])).await
+    }
+
+    /// `GET /schedules/{name}`
+    pub async fn schedule(&self, name: &str) -> Result<ScheduleInfo, ClientError> {
+        self.json(self.request(Method::GET, &["schedules", name]))
+            .await
+    }
+
+    /// `PUT /schedules/{name}/enabled`
+    pub async fn set_schedule_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+    ) -> Result<ScheduleInfo, ClientError> {
+        self.json(
+            self.request(Method::PUT, &["schedules", name, "enabled"])
+                .json(&UpdateScheduleRequest { enabled }),
+        )
+        .await
+    }
+
+    /// `DELETE /schedules/{name}`
+    pub async fn delete_schedule(&self, name: &str) -> Result<ScheduleInfo, ClientError> {
+        self.json(self.request(Method::DELETE, &["schedules", name]))
+            .await
+    }
+
+    /// `POST /pipelines` - define (or replace) a pipeline.
+    pub async fn create_pipeline(
+        &self,
+        request: &CreatePipelineRequest,
+    ) -> Result<PipelineInfo, ClientError> {
+        self.json(self.request(Method::POST, &["pipelines"]).json(request))
+            .await
+    }
+
+    /// `GET /pipelines`
+    pub async fn pipelines(&self) -> Result<Vec<PipelineInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["pipelines"])).await
+    }
+
+    /// `GET /pipelines/{name}`
+    pub async fn pipeline(&self, name: &str) -> Result<PipelineInfo, ClientError> {
+        self.json(self.request(Method::GET, &["pipelines", name]))
+            .await
+    }
+
+    /// `POST /pipelines/{name}/pause`
+    pub async fn pause_pipeline(&self, name: &str) -> Result<PipelineInfo, ClientError> {
+        self.json(self.request(Method::POST, &["pipelines", name, "pause"]))
+            .await
+    }
+
+    /// `POST /pipelines/{name}/resume`
+    pub async fn resume_pipeline(&self, name: &str) -> Result<PipelineInfo, ClientError> {
+        self.json(self.request(Method::POST, &["pipelines", name, "resume"]))
+            .await
+    }
+
+    /// `DELETE /pipelines/{name}`
+    pub async fn delete_pipeline(&self, name: &str) -> Result<PipelineInfo, ClientError> {
+        self.json(self.request(Method::DELETE, &["pipelines", name]))
+            .await
+    }
+
+    /// `POST /streams/{stream}/topics/{topic}/messages` with a full request,
+    /// e.g. to choose a [`PartitioningStrategy`](crate::models::PartitioningStrategy).
+    pub async fn send_request_to(
+        &self,
+        stream: &str,
+        topic: &str,
+        request: &SendMessageRequest,
+    ) -> Result<SendMessageResponse, ClientError> {
+        self.json(
+            self.request(
+                Method::POST,
+                &["streams", stream, "topics", topic, "messages"],
+            )
+            .json(request),
+        )
+        .await
+    }
+
+    /// `POST /messages/batch` - send events to the default stream/topic.
+    pub async fn send_batch(
+        &self,
+        events: Vec<Event>,
+        partition_key: Option<&str>,
+    ) -> Result<Vec<SendMessageResponse>, ClientError> {
+        let body = SendBatchRequest {
+            events,
+            partition_key: partition_key.map(str::to_string),
+            partitioning: None,
+        };
+        self.json(
+            self.request(Method::POST, &["messages", "batch"])
+                .json(&body),
+        )
+        .await
+    }
+
+    /// `GET /messages` - poll the default stream/topic.
+    pub async fn poll(&self, query: &PollQuery) -> Result<PollMessagesResponse, ClientError> {
+        self.json(self.request(Method::GET, &["messages"]).query(query))
+            .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/messages`
+    pub async fn poll_from(
+        &self,
+        stream: &str,
+        topic: &str,
+        query: &PollQuery,
+    ) -> Result<PollMessagesResponse, ClientError> {
+        self.json(
+            self.request(
+                Method::GET,
+                &["streams", stream, "topics", topic, "messages"],
+            )
+            .query(query),
+        )
+        .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/messages/peek` - read without
+    /// committing or moving any consumer's offset.
+    pub async fn peek(
+        &self,
+        stream: &str,
+        topic: &str,
+        query: &PeekQuery,
+    ) -> Result<PollMessagesResponse, ClientError> {
+        self.json(
+            self.request(
+                Method::GET,
+                &["streams", stream, "topics", topic, "messages", "peek"],
+            )
+            .query(query),
+        )
+        .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/partitions/{partition}/messages/{offset}`
+    pub async fn message_at(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_id: u32,
+        offset: u64,
+    ) -> Result<ReceivedMessage, ClientError> {
+        let partition_id = partition_id.to_string();
+        let offset = offset.to_string();
+        self.json(self.request(
+            Method::GET,
+            &[
+                "streams",
+                stream,
+                "topics",
+                topic,
+                "partitions",
+                &partition_id,
+                "messages",
+                &offset,
+            ],
+        ))
+        .await
+    }
+
+    /// `GET /messages/by-id/{id}` - find a recent event of the default
+    /// topic (needs `MESSAGE_INDEX_TTL_SECS`).
+    pub async fn message_by_id(&self, id: Uuid) -> Result<ReceivedMessage, ClientError> {
+        let id = id.to_string();
+        self.json(self.request(Method::GET, &["messages", "by-id", &id]))
+            .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/search` - scan a partition for
+    /// matching events.
+    pub async fn search(
+        &self,
+        stream: &str,
+        topic: &str,
+        query: &SearchQuery,
+    ) -> Result<SearchResponse, ClientError> {
+        self.json(
+            self.request(Method::GET, &["streams", stream, "topics", topic, "search"])
+                .query(query),
+        )
+        .await
+    }
+
+    // =========================================================================
+    // Streams
+    // =========================================================================
+
+    /// `GET /streams`
+    pub async fn list_streams(&self) -> Result<Vec<StreamInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["streams"])).await
+    }
+
+    /// `GET /streams/{name}`
+    pub async fn get_stream(&self, name: &str) -> Result<StreamInfo, ClientError> {
+        self.json(self.request(Method::GET, &["streams", name]))
+            .await
+    }
+
+    /// `POST /streams`
+    pub async fn create_stream(&self, name: &str) -> Result<(), ClientError> {
+        let body = CreateStreamRequest {
+            name: name.to_string(),
+        };
+        self.empty(self.request(Method::POST, &["streams"]).json(&body))
+            .await
+    }
+
+    /// `DELETE /streams/{name}`
+    pub async fn delete_stream(&self, name: &str) -> Result<(), ClientError> {
+        self.empty(self.request(Method::DELETE, &["streams", name]))
+            .await
+    }
+
+    /// `PATCH /streams/{name}`
+    pub async fn rename_stream(&self, name: &str, new_name: &str) -> Result<(), ClientError> {
+        let body = RenameRequest {
+            name: new_name.to_string(),
+        };
+        self.empty(self.request(Method::PATCH, &["streams", name]).json(&body))
+            .await
+    }
+
+    // =========================================================================
+    // Topics
+    // =========================================================================
+
+    /// `GET /streams/{stream}/topics`
+    pub async fn list_topics(&self, stream: &str) -> Result<Vec<TopicInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["streams", stream, "topics"]))
+            .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}`
+    pub async fn get_topic(&self, stream: &str, topic: &str) -> Result<TopicInfo, ClientError> {
+        self.json(self.request(Method::GET, &["streams", stream, "topics", topic]))
+            .await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/stats`
+    pub async fn topic_stats(
+        &self,
+        stream: &str,
+        topic: &str,
+    ) -> Result<TopicStatsResponse, ClientError> {
+        self.json(self.request(Method::GET, &["streams", stream, "topics", topic, "stats"]))
+            .await
+    }
+
+    /// `GET /consumers`
+    pub async fn consumers(&self) -> Result<Vec<ConsumerInfo>, ClientError> {
+        self.json(self.request(Method::GET, &["consumers"])).await
+    }
+
+    /// `GET /streams/{stream}/topics/{topic}/consumers/{id}/lag`
+    pub async fn consumer_lag(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+    ) -> Result<ConsumerLagResponse, ClientError> {
+        let id = consumer_id.to_string();
+        self.json(self.request(
+            Method::GET,
+            &["streams", stream, "topics", topic, "consumers", &id, "lag"],
+        ))
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics/{topic}/consumers/{id}/ack`
+    pub async fn ack(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        offsets: Vec<AckOffset>,
+    ) -> Result<AckResponse, ClientError> {
+        let id = consumer_id.to_string();
+        self.json(
+            self.request(
+                Method::POST,
+                &["streams", stream, "topics", topic, "consumers", &id, "ack"],
+            )
+            .json(&AckRequest { offsets }),
+        )
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics/{topic}/consumers/{id}/nack`
+    pub async fn nack(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        offsets: Vec<AckOffset>,
+    ) -> Result<NackResponse, ClientError> {
+        let id = consumer_id.to_string();
+        self.json(
+            self.request(
+                Method::POST,
+                &["streams", stream, "topics", topic, "consumers", &id, "nack"],
+            )
+            .json(&NackRequest { offsets }),
+        )
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics`
+    pub async fn create_topic(
+        &self,
+        stream: &str,
+        name: &str,
+        partitions: u32,
+    ) -> Result<(), ClientError> {
+        let body = CreateTopicRequest {
+            name: name.to_string(),
+            partitions,
+        };
+        self.empty(
+            self.request(Method::POST, &["streams", stream, "topics"])
+                .json(&body),
+        )
+        .await
+    }
+
+    /// `POST /streams/{stream}/topics/bulk`
+    pub async fn create_topics_bulk(
+        &self,
+        stream: &str,
+        topics: &[CreateTopicRequest],
+    ) -> Result<BulkCreateTopicsResponse, ClientError> {
+        self.json(
+            self.request(Method::POST, &["streams", stream, "topics", "bulk"])
+                .json(topics),
+        )
+        .await
+    }
+
+    /// `DELETE /streams/{stream}/topics/{topic}`
+    pub async fn delete_topic(&self, stream: &str, topic: &str) -> Result<(), ClientError> {
+        self.empty(self.request(Method::DELETE, &["streams", stream, "topics", topic]))
+            .await
+    }
+
+    /// `PATCH /streams/{stream}/topics/{topic}`
+    pub async fn rename_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        new_name: &str,
+    ) -> Result<(), ClientError> {
+        let body = RenameRequest {
+            name: new_name.to_string(),
+        };
+        self.empty(
+            self.request(Method::PATCH, &["streams", stream, "topics", topic])
+                .json(&body),
+        )
+        .await
+    }
+
+    // =========================================================================
+    // Users (admin scope)
+    // =========================================================================
+
+    /// `GET /admin/users`
+    pub async fn list_users(&self) -> Result<Vec<UserResponse>, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "users"]))
+            .await
+    }
+
+    /// `GET /admin/users/{username}`
+    pub async fn get_user(&self, username: &str) -> Result<UserResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "users", username]))
+            .await
+    }
+
+    /// `POST /admin/users`
+    pub async fn create_user(
+        &self,
+        request: &CreateUserRequest,
+    ) -> Result<UserResponse, ClientError> {
+        self.json(
+            self.request(Method::POST, &["admin", "users"])
+                .json(request),
+        )
+        .await
+    }
+
+    /// `PUT /admin/users/{username}/permissions`
+    pub async fn update_user_permissions(
+        &self,
+        username: &str,
+        permissions: Option<UserPermissions>,
+    ) -> Result<(), ClientError> {
+        let body = UpdatePermissionsRequest { permissions };
+        self.empty(
+            self.request(Method::PUT, &["admin", "users", username, "permissions"])
+                .json(&body),
+        )
+        .await
+    }
+
+    /// `PUT /admin/users/{username}/password`
+    pub async fn change_user_password(
+        &self,
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), ClientError> {
+        let body = ChangePasswordRequest {
+            current_password: current_password.to_string(),
+            new_password: new_password.to_string(),
+        };
+        self.empty(
+            self.request(Method::PUT, &["admin", "users", username, "password"])
+                .json(&body),
+        )
+        .await
+    }
+
+    /// `DELETE /admin/users/{username}`
+    pub async fn delete_user(&self, username: &str) -> Result<(), ClientError> {
+        self.empty(self.request(Method::DELETE, &["admin", "users", username]))
+            .await
+    }
+
+    /// `GET /admin/audit`
+    pub async fn audit_log(&self, query: &AuditQuery) -> Result<AuditLogResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "audit"]).query(query))
+            .await
+    }
+
+    /// `GET /admin/top-talkers`
+    pub async fn top_talkers(&self) -> Result<TopTalkersResponse, ClientError> {
+        self.json(self.request(Method::GET, &["admin", "top-talkers"]))
+            .await
+    }
+
+    /// `POST /admin/benchmark`; responds after the run's `duration_secs`.
+    pub async fn benchmark(
+        &self,
+        request: &BenchmarkRequest,
+    ) -> Result<BenchmarkResponse, ClientError> {
+        self.json(
+            self.request(Method::POST, &["admin", "benchmark"])
+                .json(request),
+        )
+        .await
+    }
+
+    // =========================================================================
+    // Internals
+    // =========================================================================
+
+    /// Build a request for `segments`, percent-encoding each one so names
+    /// can never alter the route.
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        // new() rejects cannot-be-a-base URLs, so this always succeeds.
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+
+        let mut builder = self.http.request(method, url);
+        if let Some(key) = &self.api_key {
+            builder = builder.header(API_KEY_HEADER, key);
+        }
+        if let Some(key) = &self.admin_key {
+            builder = builder.header(ADMIN_KEY_HEADER, key);
+        }
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.header(REQUEST_TIMEOUT_HEADER, timeout.as_millis().to_string());
+        }
+        builder
+    }
+
+    async fn json<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T, ClientError> {
+        let response = check_status(builder.send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    async fn empty(&self, builder: RequestBuilder) -> Result<(), ClientError> {
+        check_status(builder.send().await?).await?;
+        Ok(())
+    }
+}
+
+fn send_request(event: &Event, partition_key: Option<&str>) -> SendMessageRequest {
+    SendMessageRequest {
+        event: event.clone(),
+        partition_key: partition_key.map(str::to_string),
+        partitioning: None,
+        deliver_at: None,
+        delay_ms: None,
+    }
+}
+
+/// Turn non-2xx responses into [`ClientError::Api`].
+///
+/// Bodies that are not the standard error JSON (e.g. a proxy's HTML page)
+/// still yield an `Api` error, with the status reason as the message.
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let bytes = response.bytes().await?;
+    let body = serde_json::from_slice(&bytes).unwrap_or_else(|_| ApiErrorBody {
+        error: "http_error".to_string(),
+        message: status
+            .canonical_reason()
+            .unwrap_or("Unexpected status")
+            .to_string(),
+        retryable: status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        retry_after_ms: None,
+        request_id: None,
+    });
+    Err(ClientError::Api { status, body })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::{HeaderMap, Uri};
+    use axum::routing::get;
+
+    /// Serve `router` on an ephemeral port and return its base URL.
+    async fn spawn(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_base_url() {
+        assert!(matches!(
+            ApiClient::new("not a url"),
+            Err(ClientError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            ApiClient::new("mailto:ops@example.com"),
+            Err(ClientError::InvalidUrl(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_headers_and_encoded_path() {
+        let router = Router::new().fallback(get(|uri: Uri, headers: HeaderMap| async move {
+            axum::Json(serde_json::json!({
+                "path": uri.path(),
+                "key": headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()),
+                "timeout": headers.get(REQUEST_TIMEOUT_HEADER).and_then(|v| v.to_str().ok()),
+            }))
+        }));
+        let client = ApiClient::new(&spawn(router).await)
+            .unwrap()
+            .with_api_key("secret")
+            .with_request_timeout(Duration::from_millis(1500));
+
+        let echoed: serde_json::Value = client
+            .json(client.request(Method::GET, &["streams", "a/b"]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            echoed,
+            serde_json::json!({"path": "/streams/a%2Fb", "key": "secret", "timeout": "1500"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_body_decoded_with_retry_hint() {
+        let router = Router::new().route(
+            "/streams/{name}",
+            get(|| async {
+                (
+                    axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                    axum::Json(serde_json::json!({
+                        "error": "circuit_open",
+                        "message": "Service is temporarily unavailable",
+                        "retryable": true,
+                        "retry_after_ms": 2500
+                    })),
+                )
+            }),
+        );
+        let client = ApiClient::new(&spawn(router).await).unwrap();
+
+        let err = client.get_stream("orders").await.unwrap_err();
+
+        match &err {
+            ClientError::Api { status, body } => {
+                assert_eq!(*status, StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(body.error, "circuit_open");
+            }
+            other => panic!("expected Api error, got {other:?}"),
+        }
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(2500)));
+    }
+
+    #[tokio::test]
+    async fn test_non_json_error_body_still_maps_to_api_error() {
+        let router = Router::new().route(
+            "/health",
+            get(|| async {
+                (
+                    axum::http::StatusCode::BAD_GATEWAY,
+                    "<html>bad gateway</html>",
+                )
+            }),
+        );
+        let client = ApiClient::new(&spawn(router).await).unwrap();
+
+        let err = client.health().await.unwrap_err();
+
+        assert!(matches!(err, ClientError::Api { ref body, .. } if body.error == "http_error"));
+        assert!(err.is_retryable(), "5xx without a body is retryable");
+    }
+}
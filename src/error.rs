@@ -1,8 +1,13 @@
+use std::time::Duration;
+
 use axum::http::StatusCode;
+use axum::http::header::RETRY_AFTER;
 use axum::response::{IntoResponse, Response};
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::middleware::request_id::current_request_id;
+
 /// Application-wide error types with appropriate HTTP status codes.
 ///
 /// # Connection Errors
@@ -13,6 +18,43 @@ use thiserror::Error;
 /// - `ConnectionFailed` - Initial connection or reconnection failed
 /// - `Disconnected` - Lost connection during operation (triggers reconnection)
 /// - `ConnectionReset` - Connection was reset by peer (triggers reconnection)
+/// - `TlsError` - The server was reachable but the TLS handshake failed
+///   (untrusted certificate, domain mismatch); not retried
+/// - `AuthenticationFailed` - The server rejected this service's Iggy
+///   credentials (login after connect, or an unauthenticated session)
+///
+/// # Error Code Catalog
+///
+/// Every variant maps to a stable machine-readable `error` code (see
+/// [`AppError::code`]) and a retryability class (see
+/// [`AppError::is_retryable`]):
+///
+/// | Code                     | Status | Retryable |
+/// |--------------------------|--------|-----------|
+/// | `connection_failed`      | 503    | yes       |
+/// | `disconnected`           | 503    | yes       |
+/// | `connection_reset`       | 503    | yes       |
+/// | `circuit_open`           | 503    | yes       |
+/// | `retry_budget_exhausted` | 503    | yes       |
+/// | `shutting_down`          | 503    | yes       |
+/// | `tls_error`              | 503    | no        |
+/// | `authentication_failed`  | 503    | no        |
+/// | `timeout`                | 504    | yes       |
+/// | `stream_error`           | 500    | no        |
+/// | `topic_error`            | 500    | no        |
+/// | `send_error`             | 500    | no        |
+/// | `poll_error`             | 500    | no        |
+/// | `internal_error`         | 500    | no        |
+/// | `config_error`           | 500    | no        |
+/// | `serialization_error`    | 400    | no        |
+/// | `not_found`              | 404    | no        |
+/// | `bad_request`            | 400    | no        |
+/// | `forbidden`              | 403    | no        |
+/// | `conflict`               | 409    | no        |
+/// | `insufficient_storage`   | 507    | no        |
+///
+/// Rate-limit rejections (429, `too_many_requests`) are produced by the
+/// middleware rather than this type but carry the same retry fields.
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Failed to connect to Iggy server: {0}")]
@@ -24,6 +66,14 @@ pub enum AppError {
     #[error("Connection reset: {0}")]
     ConnectionReset(String),
 
+    /// TLS handshake with the Iggy server failed (certificate validation).
+    #[error("TLS error connecting to Iggy server: {0}")]
+    TlsError(String),
+
+    /// The Iggy server rejected this service's credentials.
+    #[error("Iggy authentication failed: {0}")]
+    AuthenticationFailed(String),
+
     #[error("Stream operation failed: {0}")]
     StreamError(String),
 
@@ -45,6 +95,20 @@ pub enum AppError {
     #[error("Invalid request: {0}")]
     BadRequest(String),
 
+    /// The caller is authenticated but lacks the required scope.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// The request clashes with an existing resource (e.g. a rename onto a
+    /// name in use).
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Produces are refused while a storage threshold is exceeded (see
+    /// `STORAGE_REJECT_PRODUCES`).
+    #[error("Insufficient storage: {0}")]
+    InsufficientStorage(String),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 
@@ -56,114 +120,311 @@ pub enum AppError {
 
     #[error("Circuit breaker open: {0}")]
     CircuitOpen(String),
-}
 
-/// Error response body for API endpoints.
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    details: Option<String>,
+    /// The process-wide retry budget had no retry left for this operation.
+    #[error("Retry budget exhausted: {0}")]
+    RetryBudgetExhausted(String),
+
+    /// The operation needed a reconnect, which shutdown aborted.
+    #[error("Shutting down: {0}")]
+    ShuttingDown(String),
+
+    /// A retryable error annotated with a server-computed retry hint.
+    ///
+    /// Built by [`AppError::with_retry_after`] where the hint is known
+    /// (circuit-breaker open window, reconnect backoff); the response is the
+    /// inner error's, plus `retry_after_ms` and a `Retry-After` header.
+    #[error("{inner}")]
+    WithRetryHint {
+        inner: Box<AppError>,
+        retry_after: Duration,
+    },
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        // Log the full error details server-side for debugging
-        // but only expose sanitized messages to clients
-        tracing::error!(error = %self, "Request failed");
+impl AppError {
+    /// Attach a retry hint to this error.
+    ///
+    /// Non-retryable errors are returned unchanged: a hint on a 400/404
+    /// would invite clients to hammer a request that can never succeed.
+    #[must_use]
+    pub fn with_retry_after(self, retry_after: Duration) -> Self {
+        if !self.is_retryable() {
+            return self;
+        }
+        match self {
+            // Re-annotating replaces the hint instead of nesting wrappers.
+            AppError::WithRetryHint { inner, .. } => AppError::WithRetryHint { inner, retry_after },
+            other => AppError::WithRetryHint {
+                inner: Box::new(other),
+                retry_after,
+            },
+        }
+    }
+
+    /// The error with any retry-hint annotation stripped.
+    pub fn kind(&self) -> &AppError {
+        match self {
+            AppError::WithRetryHint { inner, .. } => inner.kind(),
+            other => other,
+        }
+    }
+
+    /// Whether a client may retry the request unchanged.
+    ///
+    /// True for the transient availability classes (broker connection
+    /// loss, open circuit, exhausted retry budget, shutdown, timeout);
+    /// false for everything the client or the server configuration must
+    /// fix first.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            AppError::ConnectionFailed(_)
+                | AppError::Disconnected(_)
+                | AppError::ConnectionReset(_)
+                | AppError::CircuitOpen(_)
+                | AppError::RetryBudgetExhausted(_)
+                | AppError::ShuttingDown(_)
+                | AppError::OperationTimeout(_)
+        )
+    }
+
+    /// The server-computed retry hint, if one was attached.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AppError::WithRetryHint { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// A copy of this error, for reporting one failure to several callers
+    /// (e.g. every request in a coalesced batch send).
+    ///
+    /// `SerializationError` wraps a non-`Clone` `serde_json::Error` and is
+    /// copied as `Internal` with the same message; every other variant,
+    /// including the retry hint, is preserved.
+    pub fn duplicate(&self) -> AppError {
+        match self {
+            AppError::ConnectionFailed(m) => AppError::ConnectionFailed(m.clone()),
+            AppError::Disconnected(m) => AppError::Disconnected(m.clone()),
+            AppError::ConnectionReset(m) => AppError::ConnectionReset(m.clone()),
+            AppError::TlsError(m) => AppError::TlsError(m.clone()),
+            AppError::AuthenticationFailed(m) => AppError::AuthenticationFailed(m.clone()),
+            AppError::StreamError(m) => AppError::StreamError(m.clone()),
+            AppError::TopicError(m) => AppError::TopicError(m.clone()),
+            AppError::SendError(m) => AppError::SendError(m.clone()),
+            AppError::PollError(m) => AppError::PollError(m.clone()),
+            AppError::SerializationError(e) => AppError::Internal(e.to_string()),
+            AppError::NotFound(m) => AppError::NotFound(m.clone()),
+            AppError::BadRequest(m) => AppError::BadRequest(m.clone()),
+            AppError::Forbidden(m) => AppError::Forbidden(m.clone()),
+            AppError::Conflict(m) => AppError::Conflict(m.clone()),
+            AppError::InsufficientStorage(m) => AppError::InsufficientStorage(m.clone()),
+            AppError::Internal(m) => AppError::Internal(m.clone()),
+            AppError::ConfigError(m) => AppError::ConfigError(m.clone()),
+            AppError::OperationTimeout(m) => AppError::OperationTimeout(m.clone()),
+            AppError::CircuitOpen(m) => AppError::CircuitOpen(m.clone()),
+            AppError::RetryBudgetExhausted(m) => AppError::RetryBudgetExhausted(m.clone()),
+            AppError::ShuttingDown(m) => AppError::ShuttingDown(m.clone()),
+            AppError::WithRetryHint { inner, retry_after } => AppError::WithRetryHint {
+                inner: Box::new(inner.duplicate()),
+                retry_after: *retry_after,
+            },
+        }
+    }
+
+    /// Stable machine-readable error code (the `error` field of the body).
+    pub fn code(&self) -> &'static str {
+        match self.kind() {
+            AppError::ConnectionFailed(_) => "connection_failed",
+            AppError::Disconnected(_) => "disconnected",
+            AppError::ConnectionReset(_) => "connection_reset",
+            AppError::TlsError(_) => "tls_error",
+            AppError::AuthenticationFailed(_) => "authentication_failed",
+            AppError::StreamError(_) => "stream_error",
+            AppError::TopicError(_) => "topic_error",
+            AppError::SendError(_) => "send_error",
+            AppError::PollError(_) => "poll_error",
+            AppError::SerializationError(_) => "serialization_error",
+            AppError::NotFound(_) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Conflict(_) => "conflict",
+            AppError::InsufficientStorage(_) => "insufficient_storage",
+            AppError::Internal(_) => "internal_error",
+            AppError::ConfigError(_) => "config_error",
+            AppError::OperationTimeout(_) => "timeout",
+            AppError::CircuitOpen(_) => "circuit_open",
+            AppError::RetryBudgetExhausted(_) => "retry_budget_exhausted",
+            AppError::ShuttingDown(_) => "shutting_down",
+            // kind() never returns the wrapper
+            AppError::WithRetryHint { .. } => "internal_error",
+        }
+    }
 
-        let (status, error_type, message) = match &self {
+    /// HTTP status and client-facing message for this error.
+    ///
+    /// Internal details are never exposed: only user-input errors echo
+    /// their message back.
+    fn status_and_message(&self) -> (StatusCode, String) {
+        let (status, message) = match self.kind() {
             // Service availability errors - don't leak connection details
             // All connection-related errors return 503 to signal temporary unavailability
             AppError::ConnectionFailed(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
-                "connection_failed",
                 "Message broker is temporarily unavailable. Please try again later.",
             ),
             AppError::Disconnected(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
-                "disconnected",
                 "Connection to message broker was lost. Please try again.",
             ),
             AppError::ConnectionReset(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
-                "connection_reset",
                 "Connection to message broker was reset. Please try again.",
             ),
+            AppError::TlsError(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Secure connection to message broker failed. Please contact support.",
+            ),
+            AppError::AuthenticationFailed(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Message broker rejected the service credentials. Please contact support.",
+            ),
 
             // Internal errors - never expose internal details to clients
             AppError::StreamError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "stream_error",
                 "Stream operation failed. Please contact support if the issue persists.",
             ),
             AppError::TopicError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "topic_error",
                 "Topic operation failed. Please contact support if the issue persists.",
             ),
             AppError::SendError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "send_error",
                 "Failed to send message. Please try again.",
             ),
             AppError::PollError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "poll_error",
                 "Failed to retrieve messages. Please try again.",
             ),
-            AppError::Internal(_) => (
+            AppError::Internal(_) | AppError::WithRetryHint { .. } => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "internal_error",
                 "An internal error occurred. Please contact support if the issue persists.",
             ),
             AppError::ConfigError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "config_error",
                 "Service configuration error. Please contact support.",
             ),
 
             // Timeout errors - client can retry
             AppError::OperationTimeout(_) => (
                 StatusCode::GATEWAY_TIMEOUT,
-                "timeout",
                 "Operation timed out. Please try again.",
             ),
 
             // Circuit breaker open - service is protecting itself from cascading failures
             AppError::CircuitOpen(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
-                "circuit_open",
                 "Service is temporarily unavailable due to recent failures. Please retry later.",
             ),
 
-            // Client errors - safe to show the message as it's user-facing
+            // Retry budget exhausted - the broker is struggling, back off
+            AppError::RetryBudgetExhausted(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Message broker is temporarily unavailable. Please retry later.",
+            ),
+
+            // Shutdown - another instance may serve the retry
+            AppError::ShuttingDown(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service is shutting down. Please retry.",
+            ),
+
+            // Client errors - safe to show the message as it's user-facing.
+            // Serde errors can be helpful for clients debugging their payload
+            // but are sanitized to avoid leaking internal type names.
             AppError::SerializationError(e) => {
-                // Serde errors can be helpful for clients debugging their payload
-                // but sanitize to avoid leaking internal type names
-                let sanitized = sanitize_serde_error(e);
-                return (
-                    StatusCode::BAD_REQUEST,
-                    axum::Json(ErrorResponse {
-                        error: "serialization_error".to_string(),
-                        message: sanitized,
-                        details: None,
-                    }),
-                )
-                    .into_response();
+                return (StatusCode::BAD_REQUEST, sanitize_serde_error(e));
             }
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg.as_str()),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg.as_str()),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.as_str()),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.as_str()),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.as_str()),
+            AppError::InsufficientStorage(msg) => (StatusCode::INSUFFICIENT_STORAGE, msg.as_str()),
         };
+        (status, message.to_string())
+    }
+}
+
+/// Error response body for API endpoints.
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<String>,
+    /// Whether the client may retry the request unchanged.
+    retryable: bool,
+    /// Server-computed backoff hint, when one is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+    /// `X-Request-Id` of the failed request, for matching it to server logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
 
-        let body = ErrorResponse {
-            error: error_type.to_string(),
-            message: message.to_string(),
+impl ErrorResponse {
+    fn new(error: &str, message: String, retryable: bool, retry_after_ms: Option<u64>) -> Self {
+        Self {
+            error: error.to_string(),
+            message,
             details: None, // Never expose internal details to clients
-        };
+            retryable,
+            retry_after_ms,
+            request_id: current_request_id(),
+        }
+    }
+}
+
+/// JSON error body in the [`AppError`] format, for middleware that builds
+/// its own responses (e.g. to add rate-limit headers).
+pub(crate) fn error_body(
+    error: &str,
+    message: &str,
+    retryable: bool,
+    retry_after_ms: Option<u64>,
+) -> String {
+    let body = ErrorResponse::new(error, message.to_string(), retryable, retry_after_ms);
+    // Strings, a bool and a number always serialize
+    serde_json::to_string(&body).unwrap_or_default()
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        // Log the full error details server-side for debugging
+        // but only expose sanitized messages to clients
+        tracing::error!(error = %self, "Request failed");
+
+        let (status, message) = self.status_and_message();
+        let retry_after = self.retry_after();
 
-        (status, axum::Json(body)).into_response()
+        let body = ErrorResponse::new(
+            self.code(),
+            message,
+            self.is_retryable(),
+            retry_after.map(|d| d.as_millis() as u64),
+        );
+
+        let mut response = (status, axum::Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            // Retry-After is whole seconds; round up so clients honoring the
+            // header never retry before the hint.
+            let secs = retry_after.as_millis().div_ceil(1000).max(1);
+            if let Ok(value) = secs.to_string().parse() {
+                response.headers_mut().insert(RETRY_AFTER, value);
+            }
+        }
+        response
     }
 }
 
@@ -205,3 +466,109 @@ fn sanitize_serde_error(e: &serde_json::Error) -> String {
 
 /// Convenience type alias for Results with AppError.
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body readable");
+        serde_json::from_slice(&bytes).expect("body is JSON")
+    }
+
+    #[test]
+    fn test_retryable_classes() {
+        assert!(AppError::ConnectionFailed("x".into()).is_retryable());
+        assert!(AppError::Disconnected("x".into()).is_retryable());
+        assert!(AppError::ConnectionReset("x".into()).is_retryable());
+        assert!(AppError::CircuitOpen("x".into()).is_retryable());
+        assert!(AppError::RetryBudgetExhausted("x".into()).is_retryable());
+        assert!(AppError::ShuttingDown("x".into()).is_retryable());
+        assert!(AppError::OperationTimeout("x".into()).is_retryable());
+
+        assert!(!AppError::BadRequest("x".into()).is_retryable());
+        assert!(!AppError::Forbidden("x".into()).is_retryable());
+        assert!(!AppError::TlsError("x".into()).is_retryable());
+        assert!(!AppError::AuthenticationFailed("x".into()).is_retryable());
+        assert!(!AppError::NotFound("x".into()).is_retryable());
+        assert!(!AppError::SendError("x".into()).is_retryable());
+        assert!(!AppError::InsufficientStorage("x".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_retry_hint_ignored_on_non_retryable_errors() {
+        let error = AppError::BadRequest("bad".into()).with_retry_after(Duration::from_secs(5));
+        assert!(matches!(error, AppError::BadRequest(_)));
+        assert_eq!(error.retry_after(), None);
+    }
+
+    #[test]
+    fn test_duplicate_preserves_code_and_retry_hint() {
+        let error = AppError::CircuitOpen("open".into()).with_retry_after(Duration::from_secs(3));
+        let copy = error.duplicate();
+        assert_eq!(copy.code(), "circuit_open");
+        assert_eq!(copy.retry_after(), Some(Duration::from_secs(3)));
+        assert_eq!(copy.to_string(), error.to_string());
+    }
+
+    #[test]
+    fn test_retry_hint_replaces_instead_of_nesting() {
+        let error = AppError::CircuitOpen("open".into())
+            .with_retry_after(Duration::from_secs(5))
+            .with_retry_after(Duration::from_secs(2));
+
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(2)));
+        assert!(matches!(error.kind(), AppError::CircuitOpen(_)));
+        assert_eq!(error.code(), "circuit_open");
+    }
+
+    #[tokio::test]
+    async fn test_response_carries_retry_fields_and_header() {
+        let response = AppError::CircuitOpen("open".into())
+            .with_retry_after(Duration::from_millis(1500))
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(RETRY_AFTER).unwrap(),
+            "2",
+            "Retry-After rounds up to whole seconds"
+        );
+        let body = body_json(response).await;
+        assert_eq!(body.get("error"), Some(&serde_json::json!("circuit_open")));
+        assert_eq!(body.get("retryable"), Some(&serde_json::json!(true)));
+        assert_eq!(body.get("retry_after_ms"), Some(&serde_json::json!(1500)));
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_response_omits_hint() {
+        let response = AppError::NotFound("Stream 'x' not found".into()).into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(RETRY_AFTER).is_none());
+        let body = body_json(response).await;
+        assert_eq!(body.get("error"), Some(&serde_json::json!("not_found")));
+        assert_eq!(body.get("retryable"), Some(&serde_json::json!(false)));
+        assert!(body.get("retry_after_ms").is_none());
+        assert!(body.get("request_id").is_none(), "no request in scope");
+    }
+
+    #[test]
+    fn test_error_body_matches_app_error_format() {
+        let body: serde_json::Value =
+            serde_json::from_str(&error_body("overloaded", "Busy \"now\"", true, Some(1000)))
+                .unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": "overloaded",
+                "message": "Busy \"now\"",
+                "retryable": true,
+                "retry_after_ms": 1000,
+            })
+        );
+    }
+}
@@ -0,0 +1,829 @@
+//! Administrative passthrough endpoints for the backing Iggy server.
+//!
+//! # Endpoints
+//!
+//! - `GET /admin/server-info` - Iggy server version, uptime, client count,
+//!   and resource usage
+//! - `GET /admin/bootstrap/status` - Drift between the bootstrap spec and
+//!   the server
+//! - `GET /admin/retention` - Retention policies of the bootstrap spec and
+//!   when each was last enforced
+//! - `GET /admin/replication` - Progress of the topics replicated to a
+//!   remote cluster
+//! - `GET /admin/migrations` - Migrations of the persisted state and
+//!   whether one blocks readiness
+//! - `GET /admin/audit` - Audit log of stream, topic and user changes
+//!   (admin scope)
+//! - `GET /admin/top-talkers` - Clients sending the most request body
+//!   bytes (admin scope)
+//! - `GET /admin/ordering-report` - Per-key ordering violations of
+//!   `ORDERING_CHECK_TOPIC` (admin scope)
+//! - `GET /admin/tap` - Live, sampled stream of sent messages as
+//!   server-sent events (admin scope)
+//! - `POST /admin/benchmark` - Load test against the Iggy server, reporting
+//!   throughput and latency percentiles (admin scope)
+//! - `GET /admin/chaos`, `POST /admin/chaos` - Fault injection rules
+//!   (`chaos` feature, admin scope)
+//! - `GET /admin/wasm-modules`, `GET|PUT|DELETE /admin/wasm-modules/{name}`,
+//!   `POST /admin/wasm-modules/{name}/dry-run` - Pipeline transform modules
+//!   (`wasm` feature, admin scope)
+//! - `GET /admin/internals` - Sizes of internal collections, for spotting
+//!   leaks in soak tests (admin scope)
+//! - `POST /admin/snapshot`, `POST /admin/restore` - Export gateway-side
+//!   state to a JSON bundle and load it into another instance (admin scope)
+//!
+//! These let operators of this gateway inspect the backing server without
+//! separate tooling. `server-info`, `bootstrap/status`, `retention`,
+//! `replication` and `migrations` are regular authenticated routes: when
+//! `API_KEY` is set, the key is required like for any other endpoint. The
+//! audit log, top talkers, ordering report, tap, benchmark, internals,
+//! snapshots, chaos rules and WASM modules also require the `X-Admin-Key`
+//! header.
+
+use std::convert::Infallible;
+
+use axum::Json;
+#[cfg(feature = "wasm")]
+use axum::body::Bytes;
+#[cfg(feature = "wasm")]
+use axum::extract::Path;
+use axum::extract::{Query, State};
+#[cfg(feature = "wasm")]
+use axum::http::StatusCode;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use tracing::{info, instrument, warn};
+
+use super::pipelines::define_pipeline;
+use super::schedules::define_schedule;
+use super::util::AdminKey;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::RequestTimeout;
+#[cfg(feature = "chaos")]
+use crate::models::ChaosConfig;
+use crate::models::{
+    AuditLogResponse, AuditQuery, BenchmarkRequest, BenchmarkResponse, BootstrapState,
+    BootstrapStatusResponse, GatewaySnapshot, InternalsResponse, MigrationsResponse,
+    OrderingReportResponse, ReplicationStatusResponse, RestoreFailure, RestoreResponse,
+    RetentionStatusResponse, ServerInfoResponse, TapQuery, TopTalkersResponse,
+};
+#[cfg(feature = "wasm")]
+use crate::models::{
+    WasmDryRunRequest, WasmDryRunResponse, WasmDryRunResult, WasmModuleInfo, WasmModuleVersion,
+};
+use crate::services::{TapFilter, TapItem};
+use crate::state::AppState;
+use crate::validation::{validate_poll_count, validate_resource_name};
+
+/// Get information about the backing Iggy server.
+///
+/// Combines the server's statistics with a ping measured from this gateway.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "version": "0.8.0",
+///   "hostname": "iggy-0",
+///   "os": "Linux 6.1",
+///   "process_id": 1,
+///   "uptime_seconds": 3600,
+///   "ping_latency_ms": 0.42,
+///   "clients_count": 3,
+///   "streams_count": 2,
+///   ...
+/// }
+/// ```
+#[instrument(skip(state, timeout))]
+pub async fn server_info(
+    State(state): State<AppState>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<ServerInfoResponse>> {
+    let client = state.iggy_scoped(timeout);
+    let stats = client.server_stats().await?;
+    let ping_latency = client.ping().await?;
+
+    Ok(Json(ServerInfoResponse {
+        version: stats.iggy_server_version,
+        hostname: stats.hostname,
+        os: format!("{} {}", stats.os_name, stats.os_version),
+        process_id: stats.process_id,
+        uptime_seconds: stats.run_time.get_duration().as_secs(),
+        ping_latency_ms: ping_latency.as_secs_f64() * 1000.0,
+        clients_count: stats.clients_count,
+        consumer_groups_count: stats.consumer_groups_count,
+        streams_count: stats.streams_count,
+        topics_count: stats.topics_count,
+        partitions_count: stats.partitions_count,
+        segments_count: stats.segments_count,
+        messages_count: stats.messages_count,
+        cpu_usage: stats.cpu_usage,
+        memory_usage_bytes: stats.memory_usage.as_bytes_u64(),
+        total_memory_bytes: stats.total_memory.as_bytes_u64(),
+        available_memory_bytes: stats.available_memory.as_bytes_u64(),
+    }))
+}
+
+/// Compare the bootstrap spec with the server.
+///
+/// Lists every stream and topic of the spec as `in_sync`, `drifted` (with
+/// the differing settings) or `missing`. Without a spec, `configured` is
+/// false and the list is empty.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "configured": true,
+///   "in_sync": false,
+///   "resources": [
+///     { "stream": "orders", "state": "in_sync" },
+///     {
+///       "stream": "orders",
+///       "topic": "created",
+///       "state": "drifted",
+///       "drift": ["partitions: spec 6, server 3"]
+///     }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state, timeout))]
+pub async fn bootstrap_status(
+    State(state): State<AppState>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<BootstrapStatusResponse>> {
+    let Some(spec) = &state.config.bootstrap else {
+        return Ok(Json(BootstrapStatusResponse {
+            configured: false,
+            in_sync: true,
+            resources: Vec::new(),
+        }));
+    };
+
+    let resources = state.iggy_scoped(timeout).bootstrap_status(spec).await?;
+    Ok(Json(BootstrapStatusResponse {
+        configured: true,
+        in_sync: resources
+            .iter()
+            .all(|resource| resource.state == BootstrapState::InSync),
+        resources,
+    }))
+}
+
+/// Report the retention policies of the bootstrap spec.
+///
+/// Lists each topic with a `retention` policy, and when the retention
+/// manager last enforced it, changed its settings and purged it. Times are
+/// absent until the first pass; `last_error` is set while the topic fails.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "enabled": true,
+///   "interval_secs": 300,
+///   "topics": [
+///     {
+///       "stream": "analytics",
+///       "topic": "clicks",
+///       "policy": { "max_age_secs": 86400, "purge_interval_secs": 3600 },
+///       "last_enforced_at": "2024-01-15T10:30:00Z",
+///       "next_purge_at": "2024-01-15T11:25:00Z"
+///     }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn retention_status(State(state): State<AppState>) -> Json<RetentionStatusResponse> {
+    Json(RetentionStatusResponse {
+        enabled: state.config.retention_enabled(),
+        interval_secs: state.config.retention_interval.as_secs(),
+        topics: state.retention.status(),
+    })
+}
+
+/// Report the replication of local topics to the remote cluster.
+///
+/// Lists each `REPLICATION_TOPICS` mapping with its next offset per
+/// partition, the messages it had left when its last run started, and its
+/// counters since startup. `last_error` is set while the mapping fails;
+/// `connection_error` while the remote cluster cannot be reached.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "enabled": true,
+///   "connected": true,
+///   "origin": "west",
+///   "consumer_id": 512884733,
+///   "interval_ms": 1000,
+///   "topics": [
+///     {
+///       "source_stream": "orders",
+///       "source_topic": "created",
+///       "target_stream": "orders",
+///       "target_topic": "created",
+///       "offsets": { "0": 1200, "1": 1187 },
+///       "lag": 0,
+///       "replicated": 2387,
+///       "skipped": 0,
+///       "failures": 0,
+///       "last_run_at": "2024-01-15T10:30:00Z"
+///     }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn replication_status(State(state): State<AppState>) -> Json<ReplicationStatusResponse> {
+    let interval_ms = state.config.replication_interval.as_millis() as u64;
+    Json(state.replicator.status(interval_ms))
+}
+
+/// Report the migrations of the persisted state.
+///
+/// Lists each migration of this build with whether it was applied (and
+/// when), is pending, or failed on the last start. `blocking` is set while
+/// an incompatible migration is not applied; `/ready` then answers 503.
+/// With `STATE_BACKEND=memory` every migration stays pending and nothing
+/// blocks.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "backend": "sqlite",
+///   "blocking": false,
+///   "migrations": [
+///     {
+///       "version": 1,
+///       "name": "Record the initial layout of schedules and pipelines",
+///       "compatible": true,
+///       "state": "applied",
+///       "applied_at": "2024-01-15T10:30:00Z"
+///     }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn migrations(State(state): State<AppState>) -> Json<MigrationsResponse> {
+    Json(state.migrations.status())
+}
+
+/// Read the audit log.
+///
+/// Scans `count` entries from `offset` (default 0 and 100) and returns those
+/// matching the optional `action` and `outcome` filters; pass `next_offset`
+/// back as `offset` to page forward.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "entries": [
+///     {
+///       "id": "550e8400-e29b-41d4-a716-446655440000",
+///       "timestamp": "2024-01-15T10:30:00Z",
+///       "action": "delete_topic",
+///       "resource": "orders/events",
+///       "actor": "admin",
+///       "client_ip": "10.0.0.7",
+///       "request_id": "9f2c1d1e-8a4b-4c55-9a0e-3b7d2f1e6c42",
+///       "outcome": "success",
+///       "offset": 12
+///     }
+///   ],
+///   "next_offset": 13
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> AppResult<Json<AuditLogResponse>> {
+    validate_poll_count(query.count)?;
+
+    Ok(Json(state.audit.query(&query).await?))
+}
+
+/// Clients sending the most request body bytes over the last one to two
+/// `TOP_TALKERS_WINDOW_SECS` windows, largest first.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "window_secs": 300,
+///   "talkers": [
+///     {
+///       "client_ip": "10.0.0.7",
+///       "requests": 1200,
+///       "total_bytes": 73400320,
+///       "largest_bytes": 1048576,
+///       "last_seen": "2024-01-15T10:30:00Z"
+///     }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn top_talkers(State(state): State<AppState>) -> AppResult<Json<TopTalkersResponse>> {
+    if !state.top_talkers.is_enabled() {
+        return Err(AppError::BadRequest(
+            "Top talkers report is disabled (TOP_TALKERS_LIMIT=0)".to_string(),
+        ));
+    }
+
+    Ok(Json(state.top_talkers.report()))
+}
+
+/// Per-key ordering violations of `ORDERING_CHECK_TOPIC` within the last
+/// `ORDERING_CHECK_WINDOW_SECS`, most recent first.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "orders",
+///   "as_of": "2024-01-15T10:30:00Z",
+///   "window_secs": 3600,
+///   "messages_checked": 48210,
+///   "unkeyed_messages": 0,
+///   "keys": 912,
+///   "violation_counts": { "key_split": 1 },
+///   "violations": [
+///     {
+///       "type": "key_split",
+///       "key": "customer-42",
+///       "partition_id": 2,
+///       "offset": 1187,
+///       "other_partitions": [1]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Keys are read from the `sequence_key` header, so producers need
+/// `KEY_SEQUENCING` enabled; messages without it count as unkeyed.
+///
+/// # Errors
+///
+/// `400 Bad Request` while the check is disabled.
+#[instrument(skip(state))]
+pub async fn ordering_report(
+    State(state): State<AppState>,
+) -> AppResult<Json<OrderingReportResponse>> {
+    state.ordering.report().map(Json).ok_or_else(|| {
+        AppError::BadRequest("Ordering check is disabled (ORDERING_CHECK_TOPIC unset)".to_string())
+    })
+}
+
+/// Stream a sampled copy of the messages sent through this instance, as
+/// server-sent events, until the client disconnects.
+///
+/// Nothing is consumed from Iggy and no offset moves: the tap sees sends
+/// as they succeed, from the moment the stream opens.
+///
+/// # Query Parameters
+///
+/// - `stream`: Only messages sent to this stream
+/// - `topic`: Only messages sent to this topic
+/// - `sample`: Share of the matching messages streamed, in (0, 1]
+///   (default: 1)
+///
+/// # Events
+///
+/// ```text
+/// event: message
+/// data: {"stream":"orders","topic":"created","event":{...},"sent_at":"2024-01-15T10:30:00Z"}
+///
+/// event: lagged
+/// data: {"skipped":120}
+/// ```
+///
+/// `lagged` reports messages skipped because the client read too slowly.
+#[instrument(skip(state))]
+pub async fn tap(
+    State(state): State<AppState>,
+    Query(query): Query<TapQuery>,
+) -> AppResult<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>> {
+    if !(query.sample > 0.0 && query.sample <= 1.0) {
+        return Err(AppError::BadRequest(format!(
+            "sample must be in (0, 1], got {}",
+            query.sample
+        )));
+    }
+    if let Some(stream) = &query.stream {
+        validate_resource_name(stream, "Stream")?;
+    }
+    if let Some(topic) = &query.topic {
+        validate_resource_name(topic, "Topic")?;
+    }
+
+    info!(
+        stream = ?query.stream,
+        topic = ?query.topic,
+        sample = query.sample,
+        "Message tap opened"
+    );
+    let items = state.tap.subscribe(TapFilter {
+        stream: query.stream,
+        topic: query.topic,
+        sample: query.sample,
+    });
+    Ok(Sse::new(items.map(|item| Ok(tap_event(&item)))).keep_alive(KeepAlive::default()))
+}
+
+/// Encode one tap item as a server-sent event.
+fn tap_event(item: &TapItem) -> SseEvent {
+    let event = match item {
+        TapItem::Message(message) => SseEvent::default()
+            .event("message")
+            .json_data(message.as_ref()),
+        TapItem::Lagged(skipped) => SseEvent::default()
+            .event("lagged")
+            .json_data(serde_json::json!({ "skipped": skipped })),
+    };
+    // Serializing a message cannot fail; an empty event keeps the stream alive
+    event.unwrap_or_default()
+}
+
+/// Run a load test against the Iggy server and report the throughput and
+/// send latency percentiles.
+///
+/// Sends synthetic events to `BENCHMARK_TOPIC` for `duration_secs`, then
+/// responds; one run at a time. Disabled unless
+/// `BENCHMARK_MAX_DURATION_SECS` is set.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "event_size": 1024,
+///   "rate": 5000,
+///   "duration_secs": 30,
+///   "batch_size": 50,
+///   "concurrency": 4
+/// }
+/// ```
+///
+/// Every field is optional; `rate` 0 (the default) sends as fast as
+/// possible.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "benchmark",
+///   "event_bytes": 1163,
+///   "elapsed_secs": 30.01,
+///   "events_sent": 150000,
+///   "sends": 3000,
+///   "failed_sends": 0,
+///   "events_per_second": 4998.3,
+///   "bytes_per_second": 5813022.9,
+///   "latency": { "min_ms": 0.8, "p50_ms": 1.9, "p99_ms": 7.2, "max_ms": 31.4, ... },
+///   ...
+/// }
+/// ```
+#[instrument(skip(state))]
+pub async fn benchmark(
+    State(state): State<AppState>,
+    Json(request): Json<BenchmarkRequest>,
+) -> AppResult<Json<BenchmarkResponse>> {
+    Ok(Json(state.benchmark.run(request).await?))
+}
+
+/// Sizes of the internal collections that could leak.
+///
+/// `GET /admin/internals` (admin scope)
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "open_tasks": 6,
+///   "outbox_depth": 0,
+///   "rate_limiter_keys": 412,
+///   "consumer_registry_size": 18,
+///   "poll_dedup_ids": 0,
+///   "ordering_check_keys": 0,
+///   "suspected_leaks": ["rate_limiter_keys"]
+/// }
+/// ```
+///
+/// `suspected_leaks` lists the counters that rose at each of the last
+/// `LEAK_CHECK_WINDOW` samples of the self-check (`LEAK_CHECK_INTERVAL_SECS`).
+/// Under steady soak load every counter should level off.
+pub async fn internals(State(state): State<AppState>) -> Json<InternalsResponse> {
+    Json(state.internals())
+}
+
+/// Format version of [`GatewaySnapshot`] bundles.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Export the gateway-side state of this instance.
+///
+/// `POST /admin/snapshot` (admin scope)
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "service": "iggy-sample",
+///   "created_at": "2024-01-15T10:30:00Z",
+///   "schedules": [{ "name": "heartbeat", "cron": "*/5 * * * *", ... }],
+///   "pipelines": [{ "name": "big-orders", "source_topic": "orders", ... }],
+///   "consumers": [{ "stream": "orders", "topic": "created", "consumer_id": 7, ... }]
+/// }
+/// ```
+///
+/// The bundle holds what lives only in this process: recurring schedule and
+/// pipeline definitions (each pipeline with its consumer ID, so it resumes
+/// from its checkpoint) and the consumer registry. Run counters and
+/// pipeline metrics are not carried over. Event schemas are compiled in and
+/// WASM modules are uploaded separately, so neither is included.
+#[instrument(skip(state))]
+pub async fn snapshot(State(state): State<AppState>) -> Json<GatewaySnapshot> {
+    Json(GatewaySnapshot {
+        version: SNAPSHOT_VERSION,
+        service: state.config.service_name.clone(),
+        created_at: Utc::now(),
+        schedules: state.schedules.list().into_iter().map(Into::into).collect(),
+        pipelines: state.pipelines.list().into_iter().map(Into::into).collect(),
+        consumers: state.consumer_registry.list(),
+    })
+}
+
+/// Load a bundle from `POST /admin/snapshot` into this instance.
+///
+/// `POST /admin/restore` (admin scope)
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "schedules": 1,
+///   "pipelines": 1,
+///   "consumers": 12,
+///   "failures": [
+///     { "kind": "pipeline", "name": "masked", "error": "WASM module 'mask' not found" }
+///   ]
+/// }
+/// ```
+///
+/// Schedules and pipelines are registered as with `POST /schedules` and
+/// `POST /pipelines`, replacing any with the same name; one that fails
+/// validation (e.g. a pipeline whose WASM module was not uploaded first) is
+/// reported in `failures` without stopping the rest. Consumers already in
+/// the registry keep their live entry.
+///
+/// # Errors
+///
+/// Returns 400 Bad Request for a bundle of another `version`.
+#[instrument(skip(state, bundle), fields(service = %bundle.service))]
+pub async fn restore(
+    State(state): State<AppState>,
+    Json(bundle): Json<GatewaySnapshot>,
+) -> AppResult<Json<RestoreResponse>> {
+    if bundle.version != SNAPSHOT_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported snapshot version {} (expected {SNAPSHOT_VERSION})",
+            bundle.version
+        )));
+    }
+
+    // Admin scope: system topics may be targeted, as with the admin key
+    let admin = AdminKey(true);
+    let mut failures = Vec::new();
+    let mut failed = |kind: &str, name: String, error: AppError| {
+        warn!(kind, name = %name, error = %error, "Failed to restore snapshot item");
+        failures.push(RestoreFailure {
+            kind: kind.to_string(),
+            name,
+            error: error.to_string(),
+        });
+    };
+
+    let mut schedules = 0;
+    for schedule in bundle.schedules {
+        let name = schedule.name.clone();
+        match define_schedule(&state, admin, schedule).await {
+            Ok(_) => schedules += 1,
+            Err(e) => failed("schedule", name, e),
+        }
+    }
+    let mut pipelines = 0;
+    for pipeline in bundle.pipelines {
+        let name = pipeline.name.clone();
+        match define_pipeline(&state, admin, pipeline).await {
+            Ok(_) => pipelines += 1,
+            Err(e) => failed("pipeline", name, e),
+        }
+    }
+    let consumers = state.consumer_registry.restore(bundle.consumers);
+
+    info!(
+        schedules,
+        pipelines,
+        consumers,
+        failures = failures.len(),
+        "Restored gateway snapshot"
+    );
+    Ok(Json(RestoreResponse {
+        schedules,
+        pipelines,
+        consumers,
+        failures,
+    }))
+}
+
+/// Current fault injection rules.
+///
+/// `GET /admin/chaos` (`chaos` feature, admin scope)
+#[cfg(feature = "chaos")]
+pub async fn get_chaos(State(state): State<AppState>) -> Json<ChaosConfig> {
+    Json(state.chaos.config())
+}
+
+/// Replace the fault injection rules; an empty list turns injection off.
+///
+/// `POST /admin/chaos` (`chaos` feature, admin scope)
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "rules": [
+///     { "route": "/messages", "probability": 0.2, "fault": { "type": "latency", "ms": 750 } },
+///     { "route": "/streams/*", "probability": 0.05, "fault": { "type": "error", "status": 503 } },
+///     { "route": "*", "probability": 0.01, "fault": { "type": "disconnect" } }
+///   ]
+/// }
+/// ```
+///
+/// Responds with the rules now in effect.
+#[cfg(feature = "chaos")]
+#[instrument(skip(state))]
+pub async fn set_chaos(
+    State(state): State<AppState>,
+    Json(config): Json<ChaosConfig>,
+) -> AppResult<Json<ChaosConfig>> {
+    state.chaos.set(config)?;
+    Ok(Json(state.chaos.config()))
+}
+
+/// Most events one dry run transforms.
+#[cfg(feature = "wasm")]
+const MAX_DRY_RUN_EVENTS: usize = 100;
+
+/// Uploaded WASM modules with their kept versions, ordered by name.
+///
+/// `GET /admin/wasm-modules` (`wasm` feature, admin scope)
+#[cfg(feature = "wasm")]
+pub async fn list_wasm_modules(State(state): State<AppState>) -> Json<Vec<WasmModuleInfo>> {
+    Json(state.wasm_modules.list())
+}
+
+/// One WASM module with its kept versions.
+///
+/// `GET /admin/wasm-modules/{name}` (`wasm` feature, admin scope)
+#[cfg(feature = "wasm")]
+pub async fn get_wasm_module(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<WasmModuleInfo>> {
+    Ok(Json(state.wasm_modules.get(&name)?))
+}
+
+/// Upload a WASM module as the next version of `name`.
+///
+/// `PUT /admin/wasm-modules/{name}` (`wasm` feature, admin scope)
+///
+/// The body is the binary module (`application/wasm`), at most
+/// `WASM_MAX_MODULE_BYTES`. It is compiled and its exports checked before
+/// it is stored; pipelines already using the module keep their version.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "version": 2,
+///   "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+///   "size_bytes": 48213,
+///   "uploaded_at": "2024-01-15T10:30:00Z"
+/// }
+/// ```
+#[cfg(feature = "wasm")]
+#[instrument(skip(state, body), fields(size = body.len()))]
+pub async fn upload_wasm_module(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> AppResult<(StatusCode, Json<WasmModuleVersion>)> {
+    validate_resource_name(&name, "Module")?;
+    let version = state.wasm_modules.upload(&name, &body)?;
+    info!(module = %name, version = version.version, "WASM module uploaded");
+    Ok((StatusCode::CREATED, Json(version)))
+}
+
+/// Remove a WASM module with all its versions and return it.
+///
+/// `DELETE /admin/wasm-modules/{name}` (`wasm` feature, admin scope)
+///
+/// Fails with 409 while a pipeline uses the module.
+#[cfg(feature = "wasm")]
+#[instrument(skip(state))]
+pub async fn delete_wasm_module(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<WasmModuleInfo>> {
+    let users: Vec<String> = state
+        .pipelines
+        .list()
+        .into_iter()
+        .filter(|pipeline| {
+            pipeline
+                .wasm
+                .as_ref()
+                .is_some_and(|wasm| wasm.module == name)
+        })
+        .map(|pipeline| pipeline.name)
+        .collect();
+    if !users.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "WASM module '{name}' is used by pipelines: {}",
+            users.join(", ")
+        )));
+    }
+    Ok(Json(state.wasm_modules.remove(&name)?))
+}
+
+/// Run a WASM module against sample events without producing anything.
+///
+/// `POST /admin/wasm-modules/{name}/dry-run` (`wasm` feature, admin scope)
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "version": 2,
+///   "events": [
+///     { "id": "550e8400-e29b-41d4-a716-446655440000", "event_type": "order.created",
+///       "timestamp": "2024-01-15T10:30:00Z", "payload": { "type": "Generic", "data": {} } }
+///   ]
+/// }
+/// ```
+///
+/// Each event runs under the same fuel and memory limits as in a pipeline;
+/// one result per event reports its output, whether it was dropped, the
+/// fuel consumed and any trap. At most 100 events per call.
+#[cfg(feature = "wasm")]
+#[instrument(skip(state, request), fields(events = request.events.len()))]
+pub async fn dry_run_wasm_module(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<WasmDryRunRequest>,
+) -> AppResult<Json<WasmDryRunResponse>> {
+    if request.events.len() > MAX_DRY_RUN_EVENTS {
+        return Err(AppError::BadRequest(format!(
+            "A dry run takes at most {MAX_DRY_RUN_EVENTS} events"
+        )));
+    }
+    let module = state.wasm_modules.resolve(&name, request.version)?;
+    let version = module.info().version;
+    let results = tokio::task::spawn_blocking(move || {
+        request
+            .events
+            .iter()
+            .map(|event| match module.run(event) {
+                Ok((output, fuel_consumed)) => WasmDryRunResult {
+                    event_id: event.id,
+                    dropped: output.is_none(),
+                    output,
+                    fuel_consumed,
+                    error: None,
+                },
+                Err(error) => WasmDryRunResult {
+                    event_id: event.id,
+                    output: None,
+                    dropped: false,
+                    fuel_consumed: 0,
+                    error: Some(error),
+                },
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Dry run failed: {e}")))?;
+
+    Ok(Json(WasmDryRunResponse {
+        module: name,
+        version,
+        results,
+    }))
+}
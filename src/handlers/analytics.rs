@@ -0,0 +1,48 @@
+//! Analytics endpoints.
+//!
+//! # Endpoints
+//!
+//! - `GET /analytics/event-counts` - Rolling counts of a topic's events by
+//!   type (`EVENT_COUNTS_TOPIC`)
+
+use axum::Json;
+use axum::extract::State;
+use tracing::instrument;
+
+use crate::error::{AppError, AppResult};
+use crate::models::EventCountsResponse;
+use crate::state::AppState;
+
+/// Count the recent events of `EVENT_COUNTS_TOPIC` by type.
+///
+/// Counts come from the background counter (see
+/// [`crate::services::EventCounter`]), which trails the topic by up to
+/// `EVENT_COUNTS_INTERVAL_SECS`.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "events",
+///   "as_of": "2024-01-15T10:30:00Z",
+///   "windows": [
+///     {
+///       "window": "1m",
+///       "window_secs": 60,
+///       "total": 3,
+///       "counts": { "order.created": 2, "order.paid": 1 }
+///     }
+///   ]
+/// }
+/// ```
+///
+/// # Errors
+///
+/// `400 Bad Request` while counting is disabled.
+#[instrument(skip(state))]
+pub async fn event_counts(State(state): State<AppState>) -> AppResult<Json<EventCountsResponse>> {
+    state.event_counts.counts().map(Json).ok_or_else(|| {
+        AppError::BadRequest("Event counts are disabled (EVENT_COUNTS_TOPIC unset)".to_string())
+    })
+}
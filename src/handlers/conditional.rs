@@ -0,0 +1,150 @@
+//! Conditional GET for endpoints polled by dashboards (`/stats`,
+//! `/streams`).
+//!
+//! Responses carry an `ETag` derived from their content and the
+//! `Cache-Control` configured by `CACHE_MAX_AGE_SECS`. A request whose
+//! `If-None-Match` lists the current tag gets `304 Not Modified` with no
+//! body.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+
+use axum::Json;
+use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+/// Entity tag of a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag {
+    opaque: String,
+    /// Weak tags cover the meaningful content of a body whose other fields
+    /// (such as uptime) change on every request
+    weak: bool,
+}
+
+impl ETag {
+    /// Strong tag of a body serialized from `value`.
+    pub fn strong(value: &impl Serialize) -> Self {
+        Self {
+            opaque: digest(value),
+            weak: false,
+        }
+    }
+
+    /// Weak tag of the part of a body serialized from `value`.
+    pub fn weak(value: &impl Serialize) -> Self {
+        Self {
+            opaque: digest(value),
+            weak: true,
+        }
+    }
+
+    /// Header value: `"<hex>"`, prefixed with `W/` if weak.
+    fn header_value(&self) -> String {
+        if self.weak {
+            format!("W/\"{}\"", self.opaque)
+        } else {
+            format!("\"{}\"", self.opaque)
+        }
+    }
+
+    /// Whether `If-None-Match` in `headers` lists this tag or `*`, using
+    /// the weak comparison RFC 9110 prescribes for it.
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get_all(IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/").trim_matches('"') == self.opaque)
+    }
+}
+
+/// Hex digest of `value`'s JSON serialization.
+///
+/// Stable across instances of the same build, which is what a dashboard
+/// behind a load balancer needs.
+fn digest(value: &impl Serialize) -> String {
+    let mut hasher = DefaultHasher::new();
+    // Serializing plain response models cannot fail; an empty digest would
+    // only make every request a cache miss.
+    serde_json::to_vec(value)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// `Cache-Control` for a maximum age: `no-cache` (always revalidate) for
+/// zero, otherwise `private, max-age=<secs>`.
+fn cache_control(max_age: Duration) -> HeaderValue {
+    if max_age.is_zero() {
+        HeaderValue::from_static("no-cache")
+    } else {
+        HeaderValue::from_str(&format!("private, max-age={}", max_age.as_secs()))
+            .unwrap_or_else(|_| HeaderValue::from_static("no-cache"))
+    }
+}
+
+/// `body` as JSON, or `304 Not Modified` if the request's `If-None-Match`
+/// lists `etag`; both with `ETag` and `Cache-Control` headers.
+pub fn conditional_json<T: Serialize>(
+    request_headers: &HeaderMap,
+    etag: &ETag,
+    max_age: Duration,
+    body: T,
+) -> Response {
+    let mut response = if etag.matches(request_headers) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body).into_response()
+    };
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag.header_value()) {
+        headers.insert(ETAG, value);
+    }
+    headers.insert(CACHE_CONTROL, cache_control(max_age));
+    response
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_matching_tag_answers_not_modified_with_headers() {
+        let etag = ETag::strong(&["orders", "payments"]);
+        let tag = etag.header_value();
+
+        let fresh = conditional_json(&HeaderMap::new(), &etag, Duration::ZERO, ["orders"]);
+        assert_eq!(fresh.status(), StatusCode::OK);
+        assert_eq!(fresh.headers()[ETAG], tag.as_str());
+        assert_eq!(fresh.headers()[CACHE_CONTROL], "no-cache");
+
+        let headers = if_none_match(&format!("\"other\", {tag}"));
+        let cached = conditional_json(&headers, &etag, Duration::from_secs(5), ["orders"]);
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.headers()[ETAG], tag.as_str());
+        assert_eq!(cached.headers()[CACHE_CONTROL], "private, max-age=5");
+    }
+
+    #[test]
+    fn test_weak_comparison_and_wildcard() {
+        let etag = ETag::weak(&(2, 5));
+        assert!(etag.header_value().starts_with("W/\""));
+        assert!(etag.matches(&if_none_match(&etag.header_value())));
+        assert!(etag.matches(&if_none_match(&format!("\"{}\"", etag.opaque))));
+        assert!(etag.matches(&if_none_match("*")));
+        assert!(!etag.matches(&if_none_match("W/\"0000000000000000\"")));
+        assert_ne!(ETag::weak(&(2, 6)), etag);
+    }
+}
@@ -0,0 +1,242 @@
+//! Consumer monitoring endpoints.
+//!
+//! # Endpoints
+//!
+//! - `GET /consumers` - Consumers seen polling through this instance, with
+//!   their last poll time and committed offsets
+//! - `GET /streams/{stream}/topics/{topic}/consumers/{id}/lag` - Per-partition
+//!   lag (latest offset − committed offset) for a standalone consumer
+//! - `POST /streams/{stream}/topics/{topic}/consumers/{id}/ack` - Commit the
+//!   offsets of processed messages (at-least-once consumption)
+//! - `POST /streams/{stream}/topics/{topic}/consumers/{id}/nack` - Redeliver
+//!   messages, dead-lettering them after `MAX_REDELIVERIES`
+
+use axum::Json;
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::util::AdminKey;
+use crate::error::{AppError, AppResult};
+use crate::middleware::RequestTimeout;
+use crate::models::{
+    AckRequest, AckResponse, ConsumerInfo, ConsumerLagResponse, NackRequest, NackResponse,
+};
+use crate::state::AppState;
+use crate::validation::{validate_consumer_id, validate_resource_name};
+
+/// Path parameters for consumer-scoped operations.
+#[derive(Debug, Deserialize)]
+pub struct ConsumerPath {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Consumer ID
+    pub id: u32,
+}
+
+/// List the consumers that have polled through this instance.
+///
+/// Served from the in-memory consumer registry, so it costs no Iggy round
+/// trip. With `CONSUMER_IDLE_TTL_SECS` set, consumers drop off the list
+/// (and their offsets are deleted) once idle for longer than the TTL.
+///
+/// # Response Body
+///
+/// ```json
+/// [
+///   {
+///     "stream": "sample-stream",
+///     "topic": "events",
+///     "consumer_id": 1,
+///     "last_poll_at": "2024-01-15T10:30:00Z",
+///     "idle_seconds": 12,
+///     "partitions": [{ "partition_id": 1, "committed_offset": 41 }]
+///   }
+/// ]
+/// ```
+#[instrument(skip(state))]
+pub async fn list_consumers(State(state): State<AppState>) -> Json<Vec<ConsumerInfo>> {
+    Json(state.consumer_registry.list())
+}
+
+/// Get a consumer's lag on every partition of a topic.
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "events",
+///   "consumer_id": 1,
+///   "total_lag": 5,
+///   "partitions": [
+///     { "partition_id": 0, "current_offset": 9, "committed_offset": 4, "lag": 5 }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state, timeout))]
+pub async fn consumer_lag(
+    State(state): State<AppState>,
+    Path(path): Path<ConsumerPath>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<ConsumerLagResponse>> {
+    // Validate path parameters before use
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    validate_consumer_id(path.id)?;
+
+    let lag = state
+        .consumer_scoped(timeout)
+        .consumer_lag(&path.stream, &path.topic, path.id)
+        .await?;
+
+    Ok(Json(lag))
+}
+
+/// Acknowledge processed messages by committing their offsets.
+///
+/// Poll with `auto_commit=false`, process the messages, then ack the offset
+/// of the last one handled per partition. Messages that were never acked
+/// are delivered again on the next poll.
+///
+/// # Request Body
+///
+/// ```json
+/// { "offsets": [{ "partition_id": 0, "offset": 41 }] }
+/// ```
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "events",
+///   "consumer_id": 1,
+///   "committed": [{ "partition_id": 0, "offset": 41 }]
+/// }
+/// ```
+///
+/// Offsets of system topics other than `NACK_RETRY_TOPIC` are only
+/// committed with the admin key.
+#[instrument(skip(state, timeout, admin, payload))]
+pub async fn ack_messages(
+    State(state): State<AppState>,
+    Path(path): Path<ConsumerPath>,
+    timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    Json(payload): Json<AckRequest>,
+) -> AppResult<Json<AckResponse>> {
+    // Validate path parameters before use
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    guard_consumed_topic(&state, admin, &path)?;
+    validate_consumer_id(path.id)?;
+    if payload.offsets.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one offset must be acknowledged".to_string(),
+        ));
+    }
+
+    let committed = state
+        .consumer_scoped(timeout)
+        .ack(&path.stream, &path.topic, path.id, &payload.offsets)
+        .await?;
+
+    Ok(Json(AckResponse {
+        stream: path.stream,
+        topic: path.topic,
+        consumer_id: path.id,
+        committed,
+    }))
+}
+
+/// Negatively acknowledge messages so they are delivered again.
+///
+/// A copy of each message is appended to the tail of the topic (or
+/// `NACK_RETRY_TOPIC`) with an incremented `redelivery_count` header; once
+/// that exceeds `MAX_REDELIVERIES`, the copy goes to `DLQ_TOPIC` instead.
+/// The consumer's offset is left as-is.
+///
+/// # Request Body
+///
+/// ```json
+/// { "offsets": [{ "partition_id": 0, "offset": 41 }] }
+/// ```
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "sample-stream",
+///   "topic": "events",
+///   "consumer_id": 1,
+///   "messages": [
+///     {
+///       "partition_id": 0,
+///       "offset": 41,
+///       "redelivery_count": 1,
+///       "topic": "events",
+///       "dead_lettered": false
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Nacking appends to the topic, so system topics other than
+/// `NACK_RETRY_TOPIC` need the admin key.
+#[instrument(skip(state, timeout, admin, payload))]
+pub async fn nack_messages(
+    State(state): State<AppState>,
+    Path(path): Path<ConsumerPath>,
+    timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    Json(payload): Json<NackRequest>,
+) -> AppResult<Json<NackResponse>> {
+    // Validate path parameters before use
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    guard_consumed_topic(&state, admin, &path)?;
+    validate_consumer_id(path.id)?;
+    if payload.offsets.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one offset must be nacked".to_string(),
+        ));
+    }
+    // Every nacked message costs a read and a send
+    let max_batch_size = state.config.batch_max_size;
+    if payload.offsets.len() > max_batch_size {
+        return Err(AppError::BadRequest(format!(
+            "Cannot nack more than {} messages at once",
+            max_batch_size
+        )));
+    }
+
+    let messages = state
+        .consumer_scoped(timeout)
+        .nack(
+            &path.stream,
+            &path.topic,
+            path.id,
+            &payload.offsets,
+            &state.config.redelivery_policy(),
+        )
+        .await?;
+
+    Ok(Json(NackResponse {
+        stream: path.stream,
+        topic: path.topic,
+        consumer_id: path.id,
+        messages,
+    }))
+}
+
+/// Reject acking or nacking a system topic without the admin key. The nack
+/// retry topic is consumed like any other, so it is exempt.
+fn guard_consumed_topic(state: &AppState, admin: AdminKey, path: &ConsumerPath) -> AppResult<()> {
+    if state.config.nack_retry_topic.as_deref() == Some(path.topic.as_str()) {
+        return Ok(());
+    }
+    admin.guard_system_resource(state, &path.stream, Some(&path.topic))
+}
@@ -0,0 +1,35 @@
+//! Event catalog endpoint.
+//!
+//! # Endpoints
+//!
+//! - `GET /event-types` - Payload variants with the JSON Schema of their data
+
+use axum::Json;
+use tracing::instrument;
+
+use crate::models::{EventPayload, EventTypeInfo};
+
+/// List the known event payload variants.
+///
+/// Schemas are generated from the payload types, so they always match what
+/// `POST /messages` accepts.
+///
+/// # Response Body
+///
+/// ```json
+/// [
+///   {
+///     "name": "User",
+///     "description": "User-related events",
+///     "schema": {
+///       "$schema": "https://json-schema.org/draft/2020-12/schema",
+///       "title": "UserEvent",
+///       "oneOf": [ ... ]
+///     }
+///   }
+/// ]
+/// ```
+#[instrument]
+pub async fn list_event_types() -> Json<Vec<EventTypeInfo>> {
+    Json(EventPayload::catalog())
+}
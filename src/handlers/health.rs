@@ -2,35 +2,44 @@
 //!
 //! # Endpoints
 //!
-//! - `GET /health` - Health check with Iggy connection status
+//! - `GET /health` - Health check with Iggy connection and circuit breaker status
 //! - `GET /ready` - Kubernetes-compatible readiness probe
 //! - `GET /stats` - Service statistics (uses background cache)
 //!
 //! # Health vs Readiness
 //!
 //! - **Health** (`/health`): Returns 200 even if degraded, includes details
-//! - **Readiness** (`/ready`): Returns 503 if not ready to serve traffic
+//! - **Readiness** (`/ready`): Returns 503 if not ready to serve traffic,
+//!   judged by a live ping of the Iggy server
 //!
 //! # Statistics Caching
 //!
 //! The `/stats` endpoint uses a background-refreshed cache to avoid
 //! expensive Iggy queries on every request. Cache TTL is configurable
-//! via `STATS_CACHE_TTL_SECS`.
+//! via `STATS_CACHE_TTL_SECS`. Responses carry a weak `ETag` of the cached
+//! counts, so dashboards polling with `If-None-Match` get `304 Not
+//! Modified` until the next refresh changes them.
 
 use axum::Json;
 use axum::extract::State;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
 use chrono::Utc;
 use tracing::instrument;
 
-use crate::error::AppResult;
-use crate::models::{HealthResponse, StatsResponse};
+use super::conditional::{ETag, conditional_json};
+use crate::iggy_client::{CircuitState, IggyClientWrapper, OperationClass};
+use crate::models::{
+    CircuitBreakerStates, EnabledFeatures, HealthResponse, ReadConnectionHealth, StatsResponse,
+};
 use crate::state::AppState;
 
 /// Health check endpoint.
 ///
-/// Returns service health status including Iggy connection state.
-/// Always returns 200 OK with status details in the body.
+/// Returns service health status including Iggy connection state, the Iggy
+/// server in use and the state of each circuit breaker; `degraded` while
+/// disconnected or while any breaker is not closed. Always returns 200 OK
+/// with status details in the body.
 ///
 /// # Response Body
 ///
@@ -38,31 +47,93 @@ use crate::state::AppState;
 /// {
 ///   "status": "healthy",
 ///   "iggy_connected": true,
+///   "iggy_endpoint": "localhost:8090",
+///   "circuit_breakers": { "send": "closed", "poll": "closed", "admin": "closed" },
 ///   "version": "0.1.0",
 ///   "timestamp": "2024-01-15T10:30:00Z"
 /// }
 /// ```
+///
+/// With `IGGY_READ_CONNECTION_STRING` set, `iggy_read` reports the read
+/// connection the same way, and it counts toward `degraded` too.
+///
+/// With `MAX_TOTAL_SIZE_BYTES` or `MAX_TOPIC_SIZE_BYTES` set,
+/// `storage_pressure` reports stored bytes against those thresholds as of
+/// the last stats refresh; an exceeded threshold is `degraded` as well.
+///
+/// With `LEADER_ELECTION_LEASE_SECS` set, `leadership` tells whether this
+/// replica runs the singleton background tasks, and which candidate holds
+/// the lease. Not leading is not `degraded`.
 #[instrument(skip(state))]
 pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
-    let iggy_connected = state.iggy_client.is_connected();
+    let client = &state.iggy_client;
+    let iggy_connected = client.is_connected();
+    let (circuit_breakers, breakers_closed) = breaker_states(client).await;
 
-    Json(HealthResponse {
-        status: if iggy_connected {
-            "healthy"
-        } else {
-            "degraded"
+    let mut healthy = iggy_connected && breakers_closed;
+    let iggy_read = match &state.read_client {
+        Some(read_client) => {
+            let (circuit_breakers, breakers_closed) = breaker_states(read_client).await;
+            healthy &= read_client.is_connected() && breakers_closed;
+            Some(ReadConnectionHealth {
+                connected: read_client.is_connected(),
+                endpoint: read_client.active_endpoint(),
+                circuit_breakers,
+            })
         }
-        .to_string(),
+        None => None,
+    };
+
+    let storage_pressure = state.storage.is_enabled().then(|| state.storage.pressure());
+    healthy &= !storage_pressure
+        .as_ref()
+        .is_some_and(|pressure| pressure.exceeded);
+
+    Json(HealthResponse {
+        status: if healthy { "healthy" } else { "degraded" }.to_string(),
         iggy_connected,
+        iggy_endpoint: client.active_endpoint(),
+        circuit_breakers,
+        iggy_read,
+        storage_pressure,
+        leadership: state.leader.is_enabled().then(|| state.leader.status()),
+        features: EnabledFeatures {
+            produce_api: state.config.enable_produce_api,
+            consume_api: state.config.enable_consume_api,
+            admin_api: state.config.enable_admin_api,
+        },
         version: env!("CARGO_PKG_VERSION").to_string(),
         timestamp: Utc::now(),
     })
 }
 
+/// State of each circuit breaker of `client`, and whether all are closed.
+async fn breaker_states(client: &IggyClientWrapper) -> (CircuitBreakerStates, bool) {
+    let (send, poll, admin) = (
+        client.circuit_breaker_state(OperationClass::Send).await,
+        client.circuit_breaker_state(OperationClass::Poll).await,
+        client.circuit_breaker_state(OperationClass::Admin).await,
+    );
+    let closed = [send, poll, admin]
+        .iter()
+        .all(|&breaker| breaker == CircuitState::Closed);
+    let states = CircuitBreakerStates {
+        send: send.to_string(),
+        poll: poll.to_string(),
+        admin: admin.to_string(),
+    };
+    (states, closed)
+}
+
 /// Readiness check endpoint for Kubernetes probes.
 ///
-/// Returns 200 OK if the service is ready to accept traffic,
-/// 503 Service Unavailable otherwise.
+/// Pings the Iggy server (bounded by `HEALTH_CHECK_TIMEOUT_MS`) rather
+/// than trusting the last known connection state, which can stay connected
+/// long after the TCP connection silently died. Returns 200 OK if the
+/// server answered, 503 Service Unavailable otherwise; a failed ping also
+/// marks the connection down for `/health`. With a read connection, both
+/// servers must answer. Returns 503 without pinging while an incompatible
+/// state migration is pending (see `GET /admin/migrations`).
 ///
 /// # Usage
 ///
@@ -77,7 +148,14 @@ pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse>
 /// ```
 #[instrument(skip(state))]
 pub async fn readiness_check(State(state): State<AppState>) -> Result<StatusCode, StatusCode> {
-    if state.iggy_client.is_connected() {
+    if state.migrations.is_blocking() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let read_ready = match &state.read_client {
+        Some(read_client) => read_client.health_check().await,
+        None => true,
+    };
+    if state.iggy_client.health_check().await && read_ready {
         Ok(StatusCode::OK)
     } else {
         Err(StatusCode::SERVICE_UNAVAILABLE)
@@ -97,16 +175,45 @@ pub async fn readiness_check(State(state): State<AppState>) -> Result<StatusCode
 ///   "topics_count": 5,
 ///   "total_messages": 12345,
 ///   "total_size_bytes": 1048576,
-///   "uptime_seconds": 3600
+///   "uptime_seconds": 3600,
+///   "event_time_lag": [
+///     {
+///       "stream": "sample-stream",
+///       "topic": "events",
+///       "samples": 1024,
+///       "p50_seconds": 0.42,
+///       "p99_seconds": 3.8
+///     }
+///   ],
+///   "anomalies": [
+///     {
+///       "stream": "sample-stream",
+///       "topic": "events",
+///       "kind": "drop",
+///       "rate_per_sec": 1.5,
+///       "expected_per_sec": 240.0,
+///       "deviations": -6.2,
+///       "since": "2024-01-15T10:30:00Z"
+///     }
+///   ]
 /// }
 /// ```
 ///
+/// `event_time_lag` covers the last 1024 events polled from each topic
+/// through this instance (see [`crate::services::EventTimeLag`]).
+/// `anomalies` lists the topics whose throughput currently deviates from
+/// its average (see [`crate::services::ThroughputAnomalies`]); it is
+/// empty unless `THROUGHPUT_ANOMALY_THRESHOLD` is set.
+///
 /// # Caching
 ///
 /// Statistics are refreshed in the background at the interval configured
-/// by `STATS_CACHE_TTL_SECS` (default: 5 seconds).
-#[instrument(skip(state))]
-pub async fn stats(State(state): State<AppState>) -> AppResult<Json<StatsResponse>> {
+/// by `STATS_CACHE_TTL_SECS` (default: 5 seconds). The weak `ETag` covers
+/// the counts and which anomalies are active only, not `uptime_seconds`,
+/// the cache age or the lag, so a matching `If-None-Match` gets
+/// `304 Not Modified` until a refresh changes them.
+#[instrument(skip(state, headers))]
+pub async fn stats(State(state): State<AppState>, headers: HeaderMap) -> Response {
     let cached = state.cached_stats().await;
     let ttl = state.config.stats_cache_ttl;
 
@@ -117,8 +224,19 @@ pub async fn stats(State(state): State<AppState>) -> AppResult<Json<StatsRespons
         .unwrap_or(u64::MAX); // Never updated = infinitely old
 
     let cache_stale = cached.is_stale(ttl);
+    let anomalies = state.anomalies.active();
 
-    Ok(Json(StatsResponse {
+    let etag = ETag::weak(&(
+        cached.streams_count,
+        cached.topics_count,
+        cached.total_messages,
+        cached.total_size_bytes,
+        anomalies
+            .iter()
+            .map(|anomaly| (&anomaly.stream, &anomaly.topic, anomaly.kind.as_str()))
+            .collect::<Vec<_>>(),
+    ));
+    let body = StatsResponse {
         streams_count: cached.streams_count,
         topics_count: cached.topics_count,
         total_messages: cached.total_messages,
@@ -126,5 +244,8 @@ pub async fn stats(State(state): State<AppState>) -> AppResult<Json<StatsRespons
         uptime_seconds: state.uptime_seconds(),
         cache_age_seconds,
         cache_stale,
-    }))
+        event_time_lag: state.consumer.event_lag().summary(),
+        anomalies,
+    };
+    conditional_json(&headers, &etag, state.config.cache_max_age, body)
 }
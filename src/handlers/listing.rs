@@ -0,0 +1,279 @@
+//! Filtering, sorting and pagination of the stream and topic listings.
+//!
+//! Listings are filtered by `name_contains`, sorted by `sort` (ties broken
+//! by name, then ID, so pages are stable) and cut to the requested page.
+//! Responses carry the filtered total in `X-Total-Count` and, when paged,
+//! `first`/`prev`/`next`/`last` links in an RFC 8288 `Link` header that
+//! keep the request's other query parameters.
+
+use std::cmp::Ordering;
+
+use axum::http::header::LINK;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Uri};
+use chrono::{DateTime, Utc};
+
+use crate::error::{AppError, AppResult};
+use crate::models::{ListQuery, SortKey, StreamInfo, TopicInfo};
+
+/// Header carrying the number of items matching the filter.
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Page size used when only `page` is given.
+pub const DEFAULT_PER_PAGE: u32 = 100;
+
+/// Largest accepted `per_page`.
+pub const MAX_PER_PAGE: u32 = 1000;
+
+/// An item of a listing.
+pub trait Listed {
+    fn name(&self) -> &str;
+    fn id(&self) -> u32;
+    fn created_at(&self) -> DateTime<Utc>;
+    fn messages_count(&self) -> u64;
+    fn size_bytes(&self) -> u64;
+}
+
+impl Listed for StreamInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    fn messages_count(&self) -> u64 {
+        self.messages_count
+    }
+    fn size_bytes(&self) -> u64 {
+        self.size_bytes
+    }
+}
+
+impl Listed for TopicInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    fn messages_count(&self) -> u64 {
+        self.messages_count
+    }
+    fn size_bytes(&self) -> u64 {
+        self.size_bytes
+    }
+}
+
+/// Apply `query` to `items`: the requested page and its response headers.
+///
+/// `uri` is the request's, for the `Link` header. A page past the end is
+/// empty, not an error.
+///
+/// # Errors
+///
+/// Returns `AppError::BadRequest` for a `page` of 0 or a `per_page`
+/// outside 1 - 1000.
+pub fn paginate<T: Listed>(
+    mut items: Vec<T>,
+    query: &ListQuery,
+    uri: &Uri,
+) -> AppResult<(Vec<T>, HeaderMap)> {
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(AppError::BadRequest("page must be at least 1".to_string()));
+    }
+    if let Some(per_page) = query.per_page
+        && !(1..=MAX_PER_PAGE).contains(&per_page)
+    {
+        return Err(AppError::BadRequest(format!(
+            "per_page must be between 1 and {MAX_PER_PAGE}, got {per_page}"
+        )));
+    }
+
+    if let Some(needle) = &query.name_contains {
+        let needle = needle.to_lowercase();
+        items.retain(|item| item.name().to_lowercase().contains(&needle));
+    }
+    items.sort_by(|a, b| {
+        let order = compare(a, b, query.sort.key);
+        let order = if query.sort.descending {
+            order.reverse()
+        } else {
+            order
+        };
+        order
+            .then_with(|| a.name().cmp(b.name()))
+            .then_with(|| a.id().cmp(&b.id()))
+    });
+
+    let total = items.len();
+    let mut headers = HeaderMap::new();
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+
+    let paged = query.page.is_some() || query.per_page.is_some();
+    if !paged {
+        return Ok((items, headers));
+    }
+
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    let per_page_items = per_page as usize;
+    let last = total.div_ceil(per_page_items).max(1);
+    let start = (page as usize - 1).saturating_mul(per_page_items);
+    let items: Vec<T> = items.into_iter().skip(start).take(per_page_items).collect();
+
+    if let Ok(links) = HeaderValue::from_str(&links(uri, page as usize, per_page, last)) {
+        headers.insert(LINK, links);
+    }
+    Ok((items, headers))
+}
+
+fn compare<T: Listed>(a: &T, b: &T, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Name => a.name().cmp(b.name()),
+        SortKey::Id => a.id().cmp(&b.id()),
+        SortKey::CreatedAt => a.created_at().cmp(&b.created_at()),
+        SortKey::MessagesCount => a.messages_count().cmp(&b.messages_count()),
+        SortKey::SizeBytes => a.size_bytes().cmp(&b.size_bytes()),
+    }
+}
+
+/// `Link` header value for `page` of `last`, keeping the other query
+/// parameters of `uri` as sent.
+fn links(uri: &Uri, page: usize, per_page: u32, last: usize) -> String {
+    let kept: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            !pair.is_empty() && !pair.starts_with("page=") && !pair.starts_with("per_page=")
+        })
+        .collect();
+    let link = |target: usize, rel: &str| {
+        let mut query = kept.join("&");
+        if !query.is_empty() {
+            query.push('&');
+        }
+        format!(
+            "<{}?{query}page={target}&per_page={per_page}>; rel=\"{rel}\"",
+            uri.path()
+        )
+    };
+
+    let mut links = vec![link(1, "first")];
+    if page > 1 {
+        links.push(link((page - 1).min(last), "prev"));
+    }
+    if page < last {
+        links.push(link(page + 1, "next"));
+    }
+    links.push(link(last, "last"));
+    links.join(", ")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+    use crate::models::ListSort;
+
+    fn stream(id: u32, name: &str, messages_count: u64) -> StreamInfo {
+        StreamInfo {
+            id,
+            name: name.to_string(),
+            created_at: DateTime::from_timestamp(i64::from(id), 0).unwrap(),
+            topics_count: 1,
+            size_bytes: 0,
+            messages_count,
+        }
+    }
+
+    fn streams() -> Vec<StreamInfo> {
+        vec![
+            stream(1, "orders", 50),
+            stream(2, "Payments", 10),
+            stream(3, "orders-dlq", 10),
+            stream(4, "audit", 0),
+        ]
+    }
+
+    fn names(items: &[StreamInfo]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_unpaged_listing_is_filtered_and_sorted_by_name() {
+        let query = ListQuery {
+            name_contains: Some("ORDERS".to_string()),
+            ..ListQuery::default()
+        };
+        let uri: Uri = "/streams?name_contains=ORDERS".parse().unwrap();
+        let (items, headers) = paginate(streams(), &query, &uri).unwrap();
+
+        assert_eq!(names(&items), ["orders", "orders-dlq"]);
+        assert_eq!(headers[TOTAL_COUNT_HEADER], "2");
+        assert!(headers.get(LINK).is_none());
+    }
+
+    #[test]
+    fn test_descending_sort_breaks_ties_by_name() {
+        let query = ListQuery {
+            sort: "-messages_count".parse::<ListSort>().unwrap(),
+            ..ListQuery::default()
+        };
+        let (items, _) = paginate(streams(), &query, &"/streams".parse().unwrap()).unwrap();
+        assert_eq!(names(&items), ["orders", "Payments", "orders-dlq", "audit"]);
+    }
+
+    #[test]
+    fn test_pages_carry_links_keeping_other_parameters() {
+        let query = ListQuery {
+            page: Some(2),
+            per_page: Some(1),
+            sort: "id".parse().unwrap(),
+            ..ListQuery::default()
+        };
+        let uri: Uri = "/streams?sort=id&page=2&per_page=1".parse().unwrap();
+        let (items, headers) = paginate(streams(), &query, &uri).unwrap();
+
+        assert_eq!(names(&items), ["Payments"]);
+        assert_eq!(headers[TOTAL_COUNT_HEADER], "4");
+        assert_eq!(
+            headers[LINK],
+            "</streams?sort=id&page=1&per_page=1>; rel=\"first\", \
+             </streams?sort=id&page=1&per_page=1>; rel=\"prev\", \
+             </streams?sort=id&page=3&per_page=1>; rel=\"next\", \
+             </streams?sort=id&page=4&per_page=1>; rel=\"last\""
+        );
+
+        let past_end = ListQuery {
+            page: Some(9),
+            ..query
+        };
+        let (items, _) = paginate(streams(), &past_end, &uri).unwrap();
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_page_parameters_are_rejected() {
+        let uri: Uri = "/streams".parse().unwrap();
+        for query in [
+            ListQuery {
+                page: Some(0),
+                ..ListQuery::default()
+            },
+            ListQuery {
+                per_page: Some(MAX_PER_PAGE + 1),
+                ..ListQuery::default()
+            },
+        ] {
+            let result = paginate(streams(), &query, &uri);
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+        assert!("-bogus".parse::<ListSort>().is_err());
+    }
+}
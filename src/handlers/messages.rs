@@ -3,30 +3,73 @@
 //! # Endpoints
 //!
 //! - `POST /messages` - Send a single message to default stream/topic
+//!   (now, or later with `deliver_at` / `delay_ms`)
 //! - `GET /messages` - Poll messages from default stream/topic
 //! - `POST /messages/batch` - Send multiple messages in one request
+//!   (`?atomic=true`: in confirmed chunks, compensated on partial failure)
+//! - `POST /messages/fanout` - Send one message to several topics
 //! - `POST /streams/{stream}/topics/{topic}/messages` - Send to specific location
 //! - `GET /streams/{stream}/topics/{topic}/messages` - Poll from specific location
+//! - `GET /streams/{stream}/topics/{topic}/messages/peek` - Read at an offset
+//!   without committing or moving any consumer's offset (`?order=desc`:
+//!   newest first)
+//! - `GET /streams/{stream}/topics/{topic}/search` - Scan a partition for
+//!   events containing a substring or with a value at a JSONPath
+//! - `GET /streams/{stream}/topics/{topic}/partitions/{partition}/messages/{offset}` -
+//!   Read the message at one offset
+//! - `GET /messages/by-id/{id}` - Find a recent event of the default topic
+//!   by ID (`MESSAGE_INDEX_TTL_SECS`)
 //!
 //! # Configurable Limits
 //!
 //! - `BATCH_MAX_SIZE` - Maximum messages per batch send (default: 1000)
+//! - `ATOMIC_BATCH_CHUNK_SIZE` - Messages per chunk of an atomic batch
+//!   (default: 100)
+//! - `FANOUT_MAX_DESTINATIONS` - Maximum destinations per fan-out
+//!   (default: 16)
 //! - `POLL_MAX_COUNT` - Maximum messages per poll (default: 100)
+//! - `POLL_STREAM_THRESHOLD` - Polls for more messages than this are
+//!   streamed as they are serialized (default: 0 = never)
+//! - `SEARCH_MAX_SCAN` / `SEARCH_TIMEOUT_MS` - Messages read and time spent
+//!   by one search (default: 10000 and 5000 ms)
+//!
+//! # Expressions
+//!
+//! Polls take a `filter` and fan-out destinations a `when`, both Rhai
+//! predicates over the event (see [`crate::services::Expressions`]).
+
+use std::time::Instant;
 
 use axum::Json;
+use axum::body::Body;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use tracing::instrument;
+use uuid::Uuid;
 
+use super::util::{AdminKey, delivery_time};
 use crate::error::{AppError, AppResult};
 use crate::iggy_client::PollParams;
-use crate::middleware::RequestTimeout;
-use crate::models::{Event, PollMessagesResponse, SendMessageRequest, SendMessageResponse};
+use crate::middleware::{CorrelationId, RequestTimeout};
+use crate::models::{
+    FanoutRequest, FanoutResponse, MessageOrder, PeekQuery, PollMessagesResponse, PollWarning,
+    ReceivedMessage, ScheduledMessage, SearchQuery, SearchResponse, SendMessageRequest,
+    SendMessageResponse,
+};
+use crate::services::{
+    FanoutRoute, Predicate, ProducerService, SearchLimits, SearchMatcher, SpooledSend, fan_out,
+    search,
+};
+// Wire types live in `models` (shared with the typed client); re-exported
+// here so existing `handlers::messages::*` paths keep resolving.
+pub use crate::models::{PollQuery, SendBatchQuery, SendBatchRequest};
 use crate::state::AppState;
 use crate::validation::{
     validate_consumer_id, validate_event_type, validate_partition_id, validate_poll_count,
-    validate_resource_name,
+    validate_resource_name, validate_target_version,
 };
 
 /// Send a single message to the default stream/topic.
@@ -41,34 +84,70 @@ use crate::validation::{
 ///     "timestamp": "2024-01-15T10:30:00Z",
 ///     "payload": { "type": "Generic", "data": {} }
 ///   },
-///   "partition_key": "optional-key"
+///   "partition_key": "optional-key",
+///   "partitioning": "sticky"
 /// }
 /// ```
-#[instrument(skip(state, timeout, payload))]
+///
+/// `partitioning` is optional: `balanced`, `key`, `partition_id:<n>`, or
+/// `sticky` (see [`crate::models::PartitioningStrategy`]).
+///
+/// With `deliver_at` (RFC 3339) or `delay_ms`, the event is held by the
+/// scheduler instead and `202 Accepted` returns the
+/// [`ScheduledMessage`] (cancel it via `DELETE /scheduled/{id}`).
+///
+/// An event without `correlation_id` takes the request's `X-Correlation-Id`
+/// (or its request ID; see [`CorrelationId`]), echoed in the response.
+///
+/// With `SPOOL_DIR` set, a send made while Iggy is unreachable is written
+/// to the local spool for later delivery and answered with `202 Accepted`
+/// and `"spooled": true` (see [`crate::services::Spool`]); with
+/// `OUTBOX_CAPACITY` set, one made while the send circuit is open is held
+/// in memory instead (see [`crate::services::Outbox`]).
+///
+/// With `STORAGE_REJECT_PRODUCES`, sends are refused with `507 Insufficient
+/// Storage` while a storage threshold is exceeded (see
+/// [`crate::services::StorageAlarm`]).
+#[instrument(skip(state, timeout, correlation, payload))]
 pub async fn send_message(
     State(state): State<AppState>,
     timeout: Option<RequestTimeout>,
+    correlation: CorrelationId,
     Json(payload): Json<SendMessageRequest>,
-) -> AppResult<(StatusCode, Json<SendMessageResponse>)> {
+) -> AppResult<Response> {
     // Validate event type before processing
     validate_event_type(&payload.event.event_type)?;
+    state
+        .storage
+        .check_produce(&state.config.default_stream, &state.config.default_topic)?;
+
+    if let Some(deliver_at) = delivery_time(&payload, Utc::now())? {
+        let stream = state.config.default_stream.clone();
+        let topic = state.config.default_topic.clone();
+        return schedule(
+            &state,
+            stream,
+            topic,
+            payload,
+            deliver_at,
+            correlation.get(),
+        )
+        .await;
+    }
 
-    let response = state
+    let producer = state
         .producer_scoped(timeout)
-        .send(&payload.event, payload.partition_key.as_deref())
-        .await?;
-
-    Ok((StatusCode::CREATED, Json(response)))
-}
+        .with_correlation_id(correlation.get());
+    let send = SpooledSend {
+        stream: state.config.default_stream.clone(),
+        topic: state.config.default_topic.clone(),
+        event: payload.event,
+        partition_key: payload.partition_key,
+        partitioning: payload.partitioning,
+    };
+    let response = buffered_send(&state, &producer, send).await?;
 
-/// Request body for sending a batch of messages.
-#[derive(Debug, Deserialize)]
-pub struct SendBatchRequest {
-    /// List of events to send
-    pub events: Vec<Event>,
-    /// Optional partition key for all messages in the batch
-    #[serde(default)]
-    pub partition_key: Option<String>,
+    Ok((send_status(response.spooled), Json(response)).into_response())
 }
 
 /// Send multiple messages in a batch.
@@ -81,6 +160,18 @@ pub struct SendBatchRequest {
 /// - Maximum batch size: configured via `BATCH_MAX_SIZE` (default: 1000)
 /// - Empty batch: returns 400 Bad Request
 ///
+/// Spooled or held in the outbox whole, and refused on storage pressure,
+/// like [`send_message`].
+///
+/// # Atomic Batches
+///
+/// With `?atomic=true` the batch is never spooled: it is sent in chunks of
+/// `ATOMIC_BATCH_CHUNK_SIZE`, each confirmed by Iggy before the next. The
+/// response is an [`AtomicBatchReport`](crate::models::AtomicBatchReport),
+/// `200 OK` only if every chunk was confirmed. Otherwise the rest is not
+/// sent, the batch is compensated (`ATOMIC_BATCH_COMPENSATION`) and the
+/// report comes with `500 Internal Server Error`.
+///
 /// # Request Body
 ///
 /// ```json
@@ -92,12 +183,17 @@ pub struct SendBatchRequest {
 ///   "partition_key": "optional-key"
 /// }
 /// ```
-#[instrument(skip(state, timeout, payload), fields(batch_size = payload.events.len()))]
+#[instrument(
+    skip(state, timeout, correlation, payload),
+    fields(batch_size = payload.events.len(), atomic = query.atomic)
+)]
 pub async fn send_batch(
     State(state): State<AppState>,
     timeout: Option<RequestTimeout>,
+    correlation: CorrelationId,
+    Query(query): Query<SendBatchQuery>,
     Json(payload): Json<SendBatchRequest>,
-) -> AppResult<(StatusCode, Json<Vec<SendMessageResponse>>)> {
+) -> AppResult<Response> {
     let max_batch_size = state.config.batch_max_size;
 
     if payload.events.is_empty() {
@@ -120,39 +216,178 @@ pub async fn send_batch(
             .map_err(|e| AppError::BadRequest(format!("Event at index {}: {}", index, e)))?;
     }
 
-    let responses = state
+    let (stream, topic) = (&state.config.default_stream, &state.config.default_topic);
+    state.storage.check_produce(stream, topic)?;
+    let producer = state
         .producer_scoped(timeout)
-        .send_batch(&payload.events, payload.partition_key.as_deref())
-        .await?;
+        .with_correlation_id(correlation.get());
+    if query.atomic {
+        let report = producer
+            .send_batch_atomic_to(
+                stream,
+                topic,
+                &payload.events,
+                payload.partition_key.as_deref(),
+                payload.partitioning,
+            )
+            .await?;
+        let status = if report.success {
+            StatusCode::OK
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        return Ok((status, Json(report)).into_response());
+    }
+    let responses = if state.outbox.is_enabled() {
+        state
+            .outbox
+            .send_batch(
+                &producer,
+                stream,
+                topic,
+                payload.events,
+                payload.partition_key,
+                payload.partitioning,
+            )
+            .await?
+    } else {
+        state
+            .spool
+            .send_batch(
+                &producer,
+                stream,
+                topic,
+                payload.events,
+                payload.partition_key,
+                payload.partitioning,
+            )
+            .await?
+    };
 
-    Ok((StatusCode::CREATED, Json(responses)))
+    let spooled = responses.iter().any(|response| response.spooled);
+    Ok((send_status(spooled), Json(responses)).into_response())
 }
 
-/// Query parameters for polling messages.
-#[derive(Debug, Deserialize)]
-pub struct PollQuery {
-    /// Partition ID to poll from (default: 0, Iggy uses 0-indexed partitions)
-    #[serde(default)]
-    pub partition_id: u32,
-    /// Consumer ID for offset tracking (default: 1)
-    #[serde(default = "default_consumer")]
-    pub consumer_id: u32,
-    /// Starting offset (optional, defaults to next uncommitted)
-    pub offset: Option<u64>,
-    /// Number of messages to poll (default: 10, capped by POLL_MAX_COUNT)
-    #[serde(default = "default_count")]
-    pub count: u32,
-    /// Whether to auto-commit offset after polling
-    #[serde(default)]
-    pub auto_commit: bool,
-}
+/// Send one event to several topics.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "event": { "id": "...", "event_type": "order.created", ... },
+///   "destinations": [
+///     { "stream": "orders", "topic": "created" },
+///     { "stream": "analytics", "topic": "orders" },
+///     { "stream": "risk", "topic": "large-orders", "when": "payload.total >= 1000" }
+///   ],
+///   "partition_key": "optional-key"
+/// }
+/// ```
+///
+/// Instead of `destinations`, `"group": "<name>"` sends to a group of
+/// `FANOUT_GROUPS`. Destinations are sent to concurrently, never spooled,
+/// and each gets its own result (see [`crate::services::fan_out`]). A
+/// destination whose `when` does not hold is `skipped`. The response is
+/// `200 OK` only if every destination not skipped confirmed the event, and
+/// `500 Internal Server Error` with the same body otherwise.
+/// System topics, whether listed or in a group, need the admin key.
+///
+/// # Errors
+///
+/// `400 Bad Request` for an invalid event type, both or neither of
+/// `destinations` and `group`, an unknown group, duplicate destinations, or
+/// more than `FANOUT_MAX_DESTINATIONS`; `403 Forbidden` for a system topic
+/// without the admin key; `507 Insufficient Storage` if any destination is
+/// refused on storage pressure.
+#[instrument(
+    skip(state, timeout, correlation, admin, payload),
+    fields(event_id = %payload.event.id)
+)]
+pub async fn send_fanout(
+    State(state): State<AppState>,
+    timeout: Option<RequestTimeout>,
+    correlation: CorrelationId,
+    admin: AdminKey,
+    Json(payload): Json<FanoutRequest>,
+) -> AppResult<Response> {
+    validate_event_type(&payload.event.event_type)?;
+    let destinations = match (&payload.group, payload.destinations.is_empty()) {
+        (Some(name), true) => state
+            .config
+            .fanout_groups
+            .iter()
+            .find(|group| &group.name == name)
+            .map(|group| group.destinations.clone())
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown fanout group '{name}'")))?,
+        (None, false) => payload.destinations,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Exactly one of 'destinations' and 'group' is required".to_string(),
+            ));
+        }
+    };
 
-fn default_consumer() -> u32 {
-    1
-}
+    let max_destinations = state.config.fanout_max_destinations;
+    if destinations.len() > max_destinations {
+        return Err(AppError::BadRequest(format!(
+            "Fan-out to {} destinations exceeds maximum of {max_destinations}",
+            destinations.len()
+        )));
+    }
+    for (index, destination) in destinations.iter().enumerate() {
+        validate_resource_name(&destination.stream, "Stream")?;
+        validate_resource_name(&destination.topic, "Topic")?;
+        admin.guard_system_resource(&state, &destination.stream, Some(&destination.topic))?;
+        if destinations.iter().take(index).any(|earlier| {
+            (&earlier.stream, &earlier.topic) == (&destination.stream, &destination.topic)
+        }) {
+            return Err(AppError::BadRequest(format!(
+                "Destination {}/{} is listed more than once",
+                destination.stream, destination.topic
+            )));
+        }
+        state
+            .storage
+            .check_produce(&destination.stream, &destination.topic)?;
+    }
 
-fn default_count() -> u32 {
-    10
+    let routes = destinations
+        .into_iter()
+        .map(|destination| {
+            let when = destination
+                .when
+                .as_deref()
+                .map(|source| state.expressions.compile(source))
+                .transpose()?;
+            Ok(FanoutRoute { destination, when })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let producer = state
+        .producer_scoped(timeout)
+        .with_correlation_id(correlation.get());
+    let results = fan_out(
+        &producer,
+        &payload.event,
+        &routes,
+        payload.partition_key.as_deref(),
+    )
+    .await;
+
+    let response = FanoutResponse {
+        success: results
+            .iter()
+            .all(|result| result.success || result.skipped),
+        event_id: payload.event.id,
+        correlation_id: payload.event.correlation_id.or(correlation.get()),
+        results,
+    };
+    let status = if response.success {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    Ok((status, Json(response)).into_response())
 }
 
 /// Poll messages from the default stream/topic.
@@ -164,38 +399,173 @@ fn default_count() -> u32 {
 /// - `offset` - Starting offset (optional)
 /// - `count` - Number of messages to return (default: 10, max: POLL_MAX_COUNT)
 /// - `auto_commit` - Auto-commit offset after polling (default: false)
+/// - `target_version` - Upcast older events to this schema version
+///   (optional; see [`crate::models::UpcasterRegistry`])
+/// - `continuation` - Token of an earlier response, resuming after it on
+///   any replica; replaces `partition_id`, `consumer_id` and `offset`
+///   (optional; see [`crate::services::ContinuationTokens`])
+/// - `filter` - Rhai predicate the returned events must match (optional).
+///   Events it rejects are still consumed: `current_offset`, commits and
+///   the continuation move past them. An event it fails on is left out
+///   with a `filter_failed` warning.
+/// - `order` - `asc` (default) or `desc`: the newest `count` messages of the
+///   partition, newest first, up to `offset` when given. A `desc` poll reads
+///   like a peek: nothing is consumed or committed, so it takes neither
+///   `auto_commit`, `continuation` nor `target_version`. Page back with
+///   `offset` one below the last returned message.
 ///
 /// # Example
 ///
 /// ```bash
 /// curl "http://localhost:8000/messages?partition_id=1&count=10&offset=0"
 /// ```
+///
+/// Polls for more than `POLL_STREAM_THRESHOLD` messages get the same JSON
+/// as a streamed body (see [`crate::services::ConsumerService::poll_streamed`]),
+/// unless they have a `filter`.
 #[instrument(skip(state, timeout))]
 pub async fn poll_messages(
     State(state): State<AppState>,
     timeout: Option<RequestTimeout>,
     Query(query): Query<PollQuery>,
-) -> AppResult<Json<PollMessagesResponse>> {
+) -> AppResult<Response> {
     // Validate poll parameters
     validate_partition_id(query.partition_id)?;
     validate_consumer_id(query.consumer_id)?;
     validate_poll_count(query.count)?;
+    validate_target_version(query.target_version)?;
 
-    let max_count = state.config.poll_max_count;
-    let count = query.count.min(max_count);
+    let (stream, topic) = (&state.config.default_stream, &state.config.default_topic);
+    if query.order == MessageOrder::Desc {
+        return poll_newest(&state, timeout, &query, stream, topic).await;
+    }
+    let params = poll_params(&state, &query, stream, topic)?;
+    let filter = poll_filter(&state, &query)?;
 
-    let params = PollParams::new(query.partition_id, query.consumer_id)
-        .with_count(count)
-        .with_auto_commit(query.auto_commit);
+    let consumer = state.consumer_scoped(timeout);
+    if filter.is_none() && state.config.streams_poll(params.count) {
+        return Ok(json_stream(consumer.poll_streamed(params).await?));
+    }
+    let mut response = consumer.poll(params).await?;
+    if let Some(filter) = filter {
+        response = filter_messages(&state, response, filter).await?;
+    }
 
-    let params = match query.offset {
-        Some(offset) => params.with_offset(offset),
-        None => params,
-    };
+    Ok(Json(response).into_response())
+}
 
-    let response = state.consumer_scoped(timeout).poll(params).await?;
+/// Poll for `order=desc`: the newest messages of the partition, newest
+/// first, read without consuming.
+async fn poll_newest(
+    state: &AppState,
+    timeout: Option<RequestTimeout>,
+    query: &PollQuery,
+    stream: &str,
+    topic: &str,
+) -> AppResult<Response> {
+    if query.auto_commit || query.continuation.is_some() || query.target_version.is_some() {
+        return Err(AppError::BadRequest(
+            "order=desc reads without consuming; auto_commit, continuation and \
+             target_version are not supported"
+                .to_string(),
+        ));
+    }
+    let filter = poll_filter(state, query)?;
+    let count = query.count.min(state.config.poll_max_count);
+    let mut response = state
+        .consumer_scoped(timeout)
+        .peek_newest(stream, topic, query.partition_id, query.offset, count)
+        .await?;
+    if let Some(filter) = filter {
+        response = filter_messages(state, response, filter).await?;
+    }
+    Ok(Json(response).into_response())
+}
 
-    Ok(Json(response))
+/// The compiled `filter` of `query`, if it has one.
+fn poll_filter(state: &AppState, query: &PollQuery) -> AppResult<Option<Predicate>> {
+    query
+        .filter
+        .as_deref()
+        .map(|source| state.expressions.compile(source))
+        .transpose()
+}
+
+/// Keep the polled messages `filter` matches; one it fails on is left out
+/// with a warning.
+///
+/// Evaluations run on a blocking thread and share
+/// `EXPRESSION_POLL_BUDGET_MS`: messages left once it is spent fail.
+async fn filter_messages(
+    state: &AppState,
+    mut response: PollMessagesResponse,
+    filter: Predicate,
+) -> AppResult<PollMessagesResponse> {
+    let budget = state.config.expression_poll_budget;
+    tokio::task::spawn_blocking(move || {
+        let deadline = Instant::now() + budget;
+        let mut warnings = Vec::new();
+        response.messages.retain(|message| {
+            let result = if Instant::now() < deadline {
+                filter.matches_before(&message.event, deadline)
+            } else {
+                Err(format!(
+                    "Filter evaluation budget of {} ms exhausted",
+                    budget.as_millis()
+                ))
+            };
+            match result {
+                Ok(matches) => matches,
+                Err(error) => {
+                    warnings.push(PollWarning::FilterFailed {
+                        offset: message.offset,
+                        error,
+                    });
+                    false
+                }
+            }
+        });
+        response.tail.warnings.extend(warnings);
+        response.tail.count = response.messages.len();
+        response
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Poll filter failed: {e}")))
+}
+
+/// Poll parameters of `query` on `stream`/`topic`, with the partition,
+/// consumer and offset of its `continuation` token when it has one.
+fn poll_params(
+    state: &AppState,
+    query: &PollQuery,
+    stream: &str,
+    topic: &str,
+) -> AppResult<PollParams> {
+    let (partition_id, consumer_id, offset) = match &query.continuation {
+        Some(_) if query.offset.is_some() => {
+            return Err(AppError::BadRequest(
+                "Use either offset or continuation, not both".to_string(),
+            ));
+        }
+        Some(token) => {
+            let resumed = state.continuations.resume(token, stream, topic)?;
+            (
+                resumed.partition_id,
+                resumed.consumer_id,
+                Some(resumed.offset),
+            )
+        }
+        None => (query.partition_id, query.consumer_id, query.offset),
+    };
+
+    let params = PollParams::new(partition_id, consumer_id)
+        .with_count(query.count.min(state.config.poll_max_count))
+        .with_auto_commit(query.auto_commit)
+        .with_target_version(query.target_version);
+    Ok(match offset {
+        Some(offset) => params.with_offset(offset),
+        None => params,
+    })
 }
 
 /// Path parameters for stream/topic-specific message operations.
@@ -213,30 +583,111 @@ pub struct StreamTopicPath {
 ///
 /// - `stream` - Target stream name
 /// - `topic` - Target topic name
-#[instrument(skip(state, timeout, payload))]
+///
+/// Accepts `deliver_at` / `delay_ms`, and is spooled and refused on storage
+/// pressure like [`send_message`].
+/// System topics (dead-letter, audit, ...) only accept sends with the admin
+/// key.
+#[instrument(skip(state, timeout, correlation, admin, payload))]
 pub async fn send_message_to(
     State(state): State<AppState>,
     Path(path): Path<StreamTopicPath>,
     timeout: Option<RequestTimeout>,
+    correlation: CorrelationId,
+    admin: AdminKey,
     Json(payload): Json<SendMessageRequest>,
-) -> AppResult<(StatusCode, Json<SendMessageResponse>)> {
+) -> AppResult<Response> {
     // Validate path parameters before use
     validate_resource_name(&path.stream, "Stream")?;
     validate_resource_name(&path.topic, "Topic")?;
+    admin.guard_system_resource(&state, &path.stream, Some(&path.topic))?;
     // Validate event type before processing
     validate_event_type(&payload.event.event_type)?;
+    state.storage.check_produce(&path.stream, &path.topic)?;
+
+    if let Some(deliver_at) = delivery_time(&payload, Utc::now())? {
+        return schedule(
+            &state,
+            path.stream,
+            path.topic,
+            payload,
+            deliver_at,
+            correlation.get(),
+        )
+        .await;
+    }
 
-    let response = state
+    let producer = state
         .producer_scoped(timeout)
-        .send_to(
-            &path.stream,
-            &path.topic,
-            &payload.event,
-            payload.partition_key.as_deref(),
-        )
-        .await?;
+        .with_correlation_id(correlation.get());
+    let send = SpooledSend {
+        stream: path.stream,
+        topic: path.topic,
+        event: payload.event,
+        partition_key: payload.partition_key,
+        partitioning: payload.partitioning,
+    };
+    let response = buffered_send(&state, &producer, send).await?;
+
+    Ok((send_status(response.spooled), Json(response)).into_response())
+}
 
-    Ok((StatusCode::CREATED, Json(response)))
+/// Send through the outbox or the spool, whichever is enabled (a disabled
+/// spool sends directly).
+async fn buffered_send(
+    state: &AppState,
+    producer: &ProducerService,
+    send: SpooledSend,
+) -> AppResult<SendMessageResponse> {
+    if state.outbox.is_enabled() {
+        state.outbox.send(producer, send).await
+    } else {
+        state.spool.send(producer, send).await
+    }
+}
+
+/// `201 Created` for a send that reached Iggy, `202 Accepted` for one held
+/// for later delivery.
+fn send_status(spooled: bool) -> StatusCode {
+    if spooled {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::CREATED
+    }
+}
+
+/// Hand a send to the scheduler for delivery at `deliver_at`.
+async fn schedule(
+    state: &AppState,
+    stream: String,
+    topic: String,
+    mut payload: SendMessageRequest,
+    deliver_at: DateTime<Utc>,
+    correlation_id: Option<Uuid>,
+) -> AppResult<Response> {
+    if !state.config.scheduling_enabled() {
+        return Err(AppError::BadRequest(
+            "Delayed delivery is disabled (SCHEDULED_MAX_PENDING=0)".to_string(),
+        ));
+    }
+
+    // Delivery happens outside this request, so take its correlation now
+    if payload.event.correlation_id.is_none() {
+        payload.event.correlation_id = correlation_id;
+    }
+
+    let scheduled = ScheduledMessage {
+        id: Uuid::new_v4(),
+        stream,
+        topic,
+        deliver_at,
+        event: payload.event,
+        partition_key: payload.partition_key,
+        partitioning: payload.partitioning,
+    };
+    state.scheduler.schedule(scheduled.clone()).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(scheduled)).into_response())
 }
 
 /// Poll messages from a specific stream and topic.
@@ -245,13 +696,16 @@ pub async fn send_message_to(
 ///
 /// - `stream` - Source stream name
 /// - `topic` - Source topic name
+///
+/// Filters, streams large polls and takes `order=desc` like
+/// [`poll_messages`].
 #[instrument(skip(state, timeout))]
 pub async fn poll_messages_from(
     State(state): State<AppState>,
     Path(path): Path<StreamTopicPath>,
     timeout: Option<RequestTimeout>,
     Query(query): Query<PollQuery>,
-) -> AppResult<Json<PollMessagesResponse>> {
+) -> AppResult<Response> {
     // Validate path parameters before use
     validate_resource_name(&path.stream, "Stream")?;
     validate_resource_name(&path.topic, "Topic")?;
@@ -260,23 +714,244 @@ pub async fn poll_messages_from(
     validate_partition_id(query.partition_id)?;
     validate_consumer_id(query.consumer_id)?;
     validate_poll_count(query.count)?;
+    validate_target_version(query.target_version)?;
 
-    let max_count = state.config.poll_max_count;
-    let count = query.count.min(max_count);
+    if query.order == MessageOrder::Desc {
+        return poll_newest(&state, timeout, &query, &path.stream, &path.topic).await;
+    }
+    let params = poll_params(&state, &query, &path.stream, &path.topic)?;
+    let filter = poll_filter(&state, &query)?;
+
+    let consumer = state.consumer_scoped(timeout);
+    if filter.is_none() && state.config.streams_poll(params.count) {
+        let body = consumer
+            .poll_streamed_from(&path.stream, &path.topic, params)
+            .await?;
+        return Ok(json_stream(body));
+    }
+    let mut response = consumer
+        .poll_from(&path.stream, &path.topic, params)
+        .await?;
+    if let Some(filter) = filter {
+        response = filter_messages(&state, response, filter).await?;
+    }
 
-    let params = PollParams::new(query.partition_id, query.consumer_id)
-        .with_count(count)
-        .with_auto_commit(query.auto_commit);
+    Ok(Json(response).into_response())
+}
 
-    let params = match query.offset {
-        Some(offset) => params.with_offset(offset),
-        None => params,
+/// Peek at messages of a specific stream and topic.
+///
+/// Returns up to `count` messages of `partition_id` from `offset` in the
+/// poll response format, read with a throwaway consumer that never
+/// commits: no consumer's offset moves and the peek does not show up in
+/// `/consumers`, so messages can be inspected without disturbing the
+/// consumers reading them.
+///
+/// # Query Parameters
+///
+/// - `partition_id` - Partition to read, 0-indexed (default: 0)
+/// - `offset` - Offset of the first message (default: 0)
+/// - `count` - Messages to return (default: 10, capped by `POLL_MAX_COUNT`)
+/// - `order` - `asc` (default) or `desc`: newest first, from `offset`
+///   (default: the partition's last message) backwards. The start offset
+///   is computed from the partition's last offset, so operators see the
+///   latest messages without knowing where the partition ends.
+#[instrument(skip(state, timeout))]
+pub async fn peek_messages(
+    State(state): State<AppState>,
+    Path(path): Path<StreamTopicPath>,
+    timeout: Option<RequestTimeout>,
+    Query(query): Query<PeekQuery>,
+) -> AppResult<Json<PollMessagesResponse>> {
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    validate_partition_id(query.partition_id)?;
+    validate_poll_count(query.count)?;
+
+    let count = query.count.min(state.config.poll_max_count);
+    let consumer = state.consumer_scoped(timeout);
+    let response = match query.order {
+        MessageOrder::Asc => {
+            consumer
+                .peek_from(
+                    &path.stream,
+                    &path.topic,
+                    query.partition_id,
+                    query.offset.unwrap_or(0),
+                    count,
+                )
+                .await?
+        }
+        MessageOrder::Desc => {
+            consumer
+                .peek_newest(
+                    &path.stream,
+                    &path.topic,
+                    query.partition_id,
+                    query.offset,
+                    count,
+                )
+                .await?
+        }
+    };
+    Ok(Json(response))
+}
+
+/// Search a partition's messages.
+///
+/// Scans `partition_id` from `from_offset` with peeks (no consumer's offset
+/// moves) and returns the events matching `q` or `path`/`equals` (see
+/// [`crate::services::SearchMatcher`]). The scan stops at the end of the
+/// partition, after `limit` matches, `max_scan` messages or
+/// `SEARCH_TIMEOUT_MS`, as reported in `stopped`; pass `next_offset` as
+/// `from_offset` to continue.
+///
+/// # Query Parameters
+///
+/// - `q` - Substring of the event's JSON, or
+/// - `path` and `equals` - JSONPath into the event and the value there
+/// - `partition_id` - Partition to scan, 0-indexed (default: 0)
+/// - `from_offset` - Offset the scan starts at (default: 0)
+/// - `max_scan` - Most messages read (default and cap: `SEARCH_MAX_SCAN`)
+/// - `limit` - Most matches returned (default: 10, capped by
+///   `POLL_MAX_COUNT`)
+///
+/// # Example
+///
+/// ```bash
+/// curl "http://localhost:8000/streams/orders/topics/created/search?q=c-42&max_scan=5000"
+/// ```
+///
+/// # Errors
+///
+/// `400 Bad Request` while search is disabled (`SEARCH_MAX_SCAN=0`), or
+/// for a query without exactly one of `q` and `path`/`equals`.
+#[instrument(skip(state, timeout))]
+pub async fn search_messages(
+    State(state): State<AppState>,
+    Path(path): Path<StreamTopicPath>,
+    timeout: Option<RequestTimeout>,
+    Query(query): Query<SearchQuery>,
+) -> AppResult<Json<SearchResponse>> {
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    validate_partition_id(query.partition_id)?;
+    validate_poll_count(query.limit)?;
+    let config = &state.config;
+    if !config.search_enabled() {
+        return Err(AppError::BadRequest(
+            "Message search is disabled (SEARCH_MAX_SCAN=0)".to_string(),
+        ));
+    }
+    if query.max_scan == Some(0) {
+        return Err(AppError::BadRequest(
+            "max_scan must be greater than 0".to_string(),
+        ));
+    }
+    let matcher = SearchMatcher::new(
+        query.q.as_deref(),
+        query.path.as_deref(),
+        query.equals.as_deref(),
+    )?;
+
+    let max_scan = query.max_scan.map_or(config.search_max_scan, |max_scan| {
+        max_scan.min(config.search_max_scan)
+    });
+    let limits = SearchLimits {
+        limit: query.limit.min(config.poll_max_count) as usize,
+        max_scan: u64::from(max_scan),
+        batch: config.poll_max_count,
+        timeout: config.search_timeout,
     };
+    let response = search(
+        &state.consumer_scoped(timeout),
+        &path.stream,
+        &path.topic,
+        query.partition_id,
+        query.from_offset,
+        &matcher,
+        limits,
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+/// Path parameters for reading the message at one offset.
+#[derive(Debug, Deserialize)]
+pub struct MessageOffsetPath {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Partition holding the message
+    pub partition: u32,
+    /// Offset of the message within the partition
+    pub offset: u64,
+}
+
+/// Read the message at one offset of a partition.
+///
+/// Returns the message as it appears in poll responses, read like a peek:
+/// no consumer's offset moves. `404 Not Found` if the partition holds no
+/// message at that offset (not yet written, removed by retention, or not
+/// an event).
+#[instrument(skip(state, timeout))]
+pub async fn message_at_offset(
+    State(state): State<AppState>,
+    Path(path): Path<MessageOffsetPath>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<ReceivedMessage>> {
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    validate_partition_id(path.partition)?;
 
-    let response = state
+    let message = state
         .consumer_scoped(timeout)
-        .poll_from(&path.stream, &path.topic, params)
+        .message_at_offset(&path.stream, &path.topic, path.partition, path.offset)
         .await?;
+    Ok(Json(message))
+}
 
-    Ok(Json(response))
+/// Find a recent event of the default stream/topic by its ID.
+///
+/// Looks the ID up in the message index (see
+/// [`crate::services::MessageIndex`]) and reads the message it points to.
+/// The index trails the topic by up to `MESSAGE_INDEX_INTERVAL_SECS` and
+/// forgets events `MESSAGE_INDEX_TTL_SECS` after their timestamp, so a
+/// `404 Not Found` means the event was not seen recently, not that it was
+/// never sent.
+///
+/// # Errors
+///
+/// `400 Bad Request` while the index is disabled.
+#[instrument(skip(state, timeout))]
+pub async fn message_by_id(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<ReceivedMessage>> {
+    let index = &state.message_index;
+    if !index.is_enabled() {
+        return Err(AppError::BadRequest(
+            "Message index is disabled (MESSAGE_INDEX_TTL_SECS=0)".to_string(),
+        ));
+    }
+    let not_found = || AppError::NotFound(format!("Event {id} not found in the message index"));
+    let location = index.lookup(id).ok_or_else(not_found)?;
+
+    let (stream, topic) = index.source();
+    let message = state
+        .consumer_scoped(timeout)
+        .message_at_offset(stream, topic, location.partition_id, location.offset)
+        .await?;
+    // The partition may have been purged and rewritten since indexing
+    if message.event.id != id {
+        return Err(not_found());
+    }
+    Ok(Json(message))
+}
+
+/// Response for a streamed JSON body.
+fn json_stream(body: Body) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
 }
@@ -1,10 +1,38 @@
+pub mod admin;
+mod analytics;
+mod conditional;
+mod consumers;
+mod event_types;
 mod health;
+mod listing;
 pub mod messages;
+mod pipelines;
+mod scheduled;
+mod schedules;
 mod streams;
 mod topics;
+mod users;
 mod util;
 
+pub use analytics::event_counts;
+pub use consumers::{ack_messages, consumer_lag, list_consumers, nack_messages};
+pub use event_types::list_event_types;
 pub use health::{health_check, readiness_check, stats};
-pub use messages::{poll_messages, send_batch, send_message};
-pub use streams::{create_stream, delete_stream, get_stream, list_streams};
-pub use topics::{create_topic, delete_topic, get_topic, list_topics};
+pub use messages::{poll_messages, send_batch, send_fanout, send_message};
+pub use pipelines::{
+    create_pipeline, delete_pipeline, get_pipeline, list_pipelines, pause_pipeline, resume_pipeline,
+};
+pub use scheduled::{cancel_scheduled, list_scheduled};
+pub use schedules::{
+    create_schedule, delete_schedule, get_schedule, list_schedules, set_schedule_enabled,
+};
+pub use streams::{
+    create_stream, delete_stream, get_stream, list_streams, rename_stream, stream_stats,
+};
+pub use topics::{
+    create_topic, create_topics_bulk, delete_topic, get_topic, list_topics, rename_topic,
+    topic_profile, topic_stats,
+};
+pub use users::{
+    change_user_password, create_user, delete_user, get_user, list_users, update_user_permissions,
+};
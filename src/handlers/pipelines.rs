@@ -0,0 +1,226 @@
+//! Pipeline endpoints.
+//!
+//! A pipeline copies the events of a source topic that match its filter to
+//! a sink topic, reshaping each payload on the way (see
+//! [`crate::services::Pipelines`]).
+//!
+//! # Endpoints
+//!
+//! - `POST /pipelines` - Define (or replace) a pipeline
+//! - `GET /pipelines` - Pipelines with their offsets and counters
+//! - `GET /pipelines/{name}` - One pipeline
+//! - `POST /pipelines/{name}/pause` - Stop running a pipeline
+//! - `POST /pipelines/{name}/resume` - Resume a pipeline from its checkpoint
+//! - `DELETE /pipelines/{name}` - Remove a pipeline
+//!
+//! With the `wasm` feature, a pipeline may also run an uploaded WASM module
+//! on each event (see `PUT /admin/wasm-modules/{name}`).
+
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use tracing::instrument;
+
+use super::util::AdminKey;
+use crate::error::{AppError, AppResult};
+use crate::models::{CreatePipelineRequest, PipelineInfo};
+use crate::services::{CustomTransform, PIPELINES_NAMESPACE};
+use crate::state::AppState;
+use crate::validation::{validate_consumer_id, validate_resource_name};
+
+/// Define a pipeline, replacing any with the same name.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "name": "big-orders",
+///   "source_topic": "orders",
+///   "filter": "event_type == order.created && payload.total >= 100",
+///   "expression": "payload.items.len() > 1",
+///   "transform": {
+///     "rename": { "total": "amount" },
+///     "set": { "tier": "big" },
+///     "drop": ["card"]
+///   },
+///   "wasm": { "module": "mask-cards" },
+///   "sink_topic": "big-orders"
+/// }
+/// ```
+///
+/// `source_stream` and `sink_stream` default to the configured stream. The
+/// pipeline starts at its committed offsets (see `consumer_id`), or at the
+/// first message of each source partition. Reading or producing to a
+/// system topic (dead-letter, audit, ...) requires the admin key.
+///
+/// `wasm` runs an uploaded module after `transform` (`wasm` feature); its
+/// version defaults to the latest and is pinned in the pipeline.
+#[instrument(skip(state, admin, payload), fields(name = %payload.name))]
+pub async fn create_pipeline(
+    State(state): State<AppState>,
+    admin: AdminKey,
+    Json(payload): Json<CreatePipelineRequest>,
+) -> AppResult<(StatusCode, Json<PipelineInfo>)> {
+    let pipeline = define_pipeline(&state, admin, payload).await?;
+    Ok((StatusCode::CREATED, Json(pipeline)))
+}
+
+/// Validate, define and journal `payload`, as `POST /pipelines` does (also
+/// used by `POST /admin/restore`).
+pub(crate) async fn define_pipeline(
+    state: &AppState,
+    admin: AdminKey,
+    mut payload: CreatePipelineRequest,
+) -> AppResult<PipelineInfo> {
+    if !state.config.pipelines_enabled() {
+        return Err(AppError::BadRequest(
+            "Pipelines are disabled (MAX_PIPELINES=0)".to_string(),
+        ));
+    }
+
+    validate_resource_name(&payload.name, "Pipeline")?;
+    let source_stream = payload
+        .source_stream
+        .clone()
+        .unwrap_or_else(|| state.config.default_stream.clone());
+    let sink_stream = payload
+        .sink_stream
+        .clone()
+        .unwrap_or_else(|| state.config.default_stream.clone());
+    validate_resource_name(&source_stream, "Stream")?;
+    validate_resource_name(&payload.source_topic, "Topic")?;
+    validate_resource_name(&sink_stream, "Stream")?;
+    validate_resource_name(&payload.sink_topic, "Topic")?;
+    if let Some(consumer_id) = payload.consumer_id {
+        validate_consumer_id(consumer_id)?;
+    }
+    admin.guard_system_resource(state, &source_stream, Some(&payload.source_topic))?;
+    admin.guard_system_resource(state, &sink_stream, Some(&payload.sink_topic))?;
+    let custom = wasm_transform(state, &mut payload)?;
+
+    let pipeline = state
+        .pipelines
+        .register(payload, source_stream, sink_stream, custom)?;
+    journal(state, &pipeline).await;
+    Ok(pipeline)
+}
+
+/// Resolve the pipeline's WASM module, pinning its version in `payload`.
+#[cfg(feature = "wasm")]
+fn wasm_transform(
+    state: &AppState,
+    payload: &mut CreatePipelineRequest,
+) -> AppResult<Option<Arc<dyn CustomTransform>>> {
+    let Some(wasm) = &mut payload.wasm else {
+        return Ok(None);
+    };
+    let module = state.wasm_modules.resolve(&wasm.module, wasm.version)?;
+    wasm.version = Some(module.info().version);
+    let module: Arc<dyn CustomTransform> = module;
+    Ok(Some(module))
+}
+
+/// Without the `wasm` feature a pipeline cannot name a WASM module.
+#[cfg(not(feature = "wasm"))]
+fn wasm_transform(
+    _state: &AppState,
+    payload: &mut CreatePipelineRequest,
+) -> AppResult<Option<Arc<dyn CustomTransform>>> {
+    if payload.wasm.is_some() {
+        return Err(AppError::BadRequest(
+            "WASM transforms need a build with the `wasm` feature".to_string(),
+        ));
+    }
+    Ok(None)
+}
+
+/// List pipelines, ordered by name.
+///
+/// # Response Body
+///
+/// ```json
+/// [
+///   {
+///     "name": "big-orders",
+///     "source_stream": "sample-stream",
+///     "source_topic": "orders",
+///     "filter": "event_type == order.created && payload.total >= 100",
+///     "transform": { "rename": { "total": "amount" } },
+///     "sink_stream": "sample-stream",
+///     "sink_topic": "big-orders",
+///     "consumer_id": 184467301,
+///     "paused": false,
+///     "created_at": "2024-01-15T10:00:00Z",
+///     "offsets": { "0": 1204, "1": 1187, "2": 1232 },
+///     "metrics": {
+///       "processed": 3623,
+///       "filtered": 3301,
+///       "produced": 322,
+///       "failures": 0,
+///       "last_run_at": "2024-01-15T10:30:00.871Z"
+///     }
+///   }
+/// ]
+/// ```
+///
+/// `offsets` holds the next source offset of each partition read since the
+/// pipeline was defined.
+#[instrument(skip(state))]
+pub async fn list_pipelines(State(state): State<AppState>) -> Json<Vec<PipelineInfo>> {
+    Json(state.pipelines.list())
+}
+
+/// Get one pipeline.
+#[instrument(skip(state))]
+pub async fn get_pipeline(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<PipelineInfo>> {
+    Ok(Json(state.pipelines.get(&name)?))
+}
+
+/// Pause a pipeline. A run in progress finishes its current pass.
+#[instrument(skip(state))]
+pub async fn pause_pipeline(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<PipelineInfo>> {
+    let pipeline = state.pipelines.set_paused(&name, true)?;
+    journal(&state, &pipeline).await;
+    Ok(Json(pipeline))
+}
+
+/// Resume a paused pipeline from its checkpoint; what was appended while it
+/// was paused is processed at the next run.
+#[instrument(skip(state))]
+pub async fn resume_pipeline(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<PipelineInfo>> {
+    let pipeline = state.pipelines.set_paused(&name, false)?;
+    journal(&state, &pipeline).await;
+    Ok(Json(pipeline))
+}
+
+/// Remove a pipeline and return it. Its committed offsets are kept, so a
+/// pipeline defined again under the same name resumes where it stopped.
+#[instrument(skip(state))]
+pub async fn delete_pipeline(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<PipelineInfo>> {
+    let pipeline = state.pipelines.remove(&name)?;
+    state.state_store.delete(PIPELINES_NAMESPACE, &name).await;
+    Ok(Json(pipeline))
+}
+
+/// Persist the definition of `pipeline` (`STATE_BACKEND`).
+async fn journal(state: &AppState, pipeline: &PipelineInfo) {
+    let definition = CreatePipelineRequest::from(pipeline.clone());
+    state
+        .state_store
+        .put(PIPELINES_NAMESPACE, &pipeline.name, &definition)
+        .await;
+}
@@ -0,0 +1,50 @@
+//! Delayed delivery endpoints.
+//!
+//! Sends with `deliver_at` or `delay_ms` are held by the scheduler (see
+//! [`crate::services::Scheduler`]) until due.
+//!
+//! # Endpoints
+//!
+//! - `GET /scheduled` - Messages waiting for delivery, earliest first
+//! - `DELETE /scheduled/{id}` - Cancel a pending message
+
+use axum::Json;
+use axum::extract::{Path, State};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::models::ScheduledMessage;
+use crate::state::AppState;
+
+/// List messages waiting for delayed delivery.
+///
+/// # Response Body
+///
+/// ```json
+/// [
+///   {
+///     "id": "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+///     "stream": "sample-stream",
+///     "topic": "events",
+///     "deliver_at": "2024-01-15T11:00:00Z",
+///     "event": { "id": "...", "event_type": "...", "payload": { ... } }
+///   }
+/// ]
+/// ```
+#[instrument(skip(state))]
+pub async fn list_scheduled(State(state): State<AppState>) -> Json<Vec<ScheduledMessage>> {
+    Json(state.scheduler.list())
+}
+
+/// Cancel a pending delayed message and return it.
+///
+/// Returns 404 if the message has already been delivered or cancelled.
+#[instrument(skip(state))]
+pub async fn cancel_scheduled(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ScheduledMessage>> {
+    let cancelled = state.scheduler.cancel(id).await?;
+    Ok(Json(cancelled))
+}
@@ -0,0 +1,160 @@
+//! Recurring schedule endpoints.
+//!
+//! A schedule produces a fresh event from its template on every tick of a
+//! cron expression (see [`crate::services::RecurringSchedules`]).
+//!
+//! # Endpoints
+//!
+//! - `POST /schedules` - Register (or replace) a schedule
+//! - `GET /schedules` - Schedules with their next and last run
+//! - `GET /schedules/{name}` - One schedule
+//! - `PUT /schedules/{name}/enabled` - Enable or disable a schedule
+//! - `DELETE /schedules/{name}` - Remove a schedule
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use tracing::instrument;
+
+use super::util::AdminKey;
+use crate::error::{AppError, AppResult};
+use crate::models::{CreateScheduleRequest, ScheduleInfo, UpdateScheduleRequest};
+use crate::services::SCHEDULES_NAMESPACE;
+use crate::state::AppState;
+use crate::validation::{validate_event_type, validate_resource_name};
+
+/// Register a recurring schedule, replacing any with the same name.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "name": "heartbeat",
+///   "cron": "*/5 * * * *",
+///   "event_type": "system.heartbeat",
+///   "payload": { "type": "Generic", "data": { "service": "billing" } },
+///   "jitter_ms": 2000
+/// }
+/// ```
+///
+/// `cron` takes five fields (`min hour day month weekday`) or six with a
+/// leading seconds field, evaluated in UTC. `stream` and `topic` default to
+/// the configured ones. Targeting a system topic (dead-letter, audit, ...)
+/// requires the admin key.
+#[instrument(skip(state, admin, payload), fields(name = %payload.name))]
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    admin: AdminKey,
+    Json(payload): Json<CreateScheduleRequest>,
+) -> AppResult<(StatusCode, Json<ScheduleInfo>)> {
+    let schedule = define_schedule(&state, admin, payload).await?;
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// Validate, register and journal `payload`, as `POST /schedules` does
+/// (also used by `POST /admin/restore`).
+pub(crate) async fn define_schedule(
+    state: &AppState,
+    admin: AdminKey,
+    payload: CreateScheduleRequest,
+) -> AppResult<ScheduleInfo> {
+    if !state.config.schedules_enabled() {
+        return Err(AppError::BadRequest(
+            "Recurring schedules are disabled (MAX_SCHEDULES=0)".to_string(),
+        ));
+    }
+
+    validate_resource_name(&payload.name, "Schedule")?;
+    validate_event_type(&payload.event_type)?;
+    let stream = payload
+        .stream
+        .clone()
+        .unwrap_or_else(|| state.config.default_stream.clone());
+    let topic = payload
+        .topic
+        .clone()
+        .unwrap_or_else(|| state.config.default_topic.clone());
+    validate_resource_name(&stream, "Stream")?;
+    validate_resource_name(&topic, "Topic")?;
+    admin.guard_system_resource(state, &stream, Some(&topic))?;
+
+    let schedule = state.schedules.register(payload, stream, topic)?;
+    journal(state, &schedule).await;
+    Ok(schedule)
+}
+
+/// List recurring schedules, ordered by name.
+///
+/// # Response Body
+///
+/// ```json
+/// [
+///   {
+///     "name": "heartbeat",
+///     "cron": "*/5 * * * *",
+///     "stream": "sample-stream",
+///     "topic": "events",
+///     "event_type": "system.heartbeat",
+///     "payload": { "type": "Generic", "data": { "service": "billing" } },
+///     "jitter_ms": 2000,
+///     "enabled": true,
+///     "created_at": "2024-01-15T10:00:00Z",
+///     "next_run_at": "2024-01-15T10:35:01.204Z",
+///     "last_run": {
+///       "at": "2024-01-15T10:30:00.871Z",
+///       "success": true,
+///       "event_id": "550e8400-e29b-41d4-a716-446655440000"
+///     },
+///     "run_count": 6,
+///     "failure_count": 0
+///   }
+/// ]
+/// ```
+#[instrument(skip(state))]
+pub async fn list_schedules(State(state): State<AppState>) -> Json<Vec<ScheduleInfo>> {
+    Json(state.schedules.list())
+}
+
+/// Get one recurring schedule.
+#[instrument(skip(state))]
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<ScheduleInfo>> {
+    Ok(Json(state.schedules.get(&name)?))
+}
+
+/// Enable or disable a recurring schedule.
+///
+/// Re-enabling resumes at the next tick; runs missed while disabled are not
+/// caught up.
+#[instrument(skip(state))]
+pub async fn set_schedule_enabled(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateScheduleRequest>,
+) -> AppResult<Json<ScheduleInfo>> {
+    let schedule = state.schedules.set_enabled(&name, payload.enabled)?;
+    journal(&state, &schedule).await;
+    Ok(Json(schedule))
+}
+
+/// Remove a recurring schedule and return it.
+#[instrument(skip(state))]
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<ScheduleInfo>> {
+    let schedule = state.schedules.remove(&name)?;
+    state.state_store.delete(SCHEDULES_NAMESPACE, &name).await;
+    Ok(Json(schedule))
+}
+
+/// Persist the definition of `schedule` (`STATE_BACKEND`).
+async fn journal(state: &AppState, schedule: &ScheduleInfo) {
+    let definition = CreateScheduleRequest::from(schedule.clone());
+    state
+        .state_store
+        .put(SCHEDULES_NAMESPACE, &schedule.name, &definition)
+        .await;
+}
@@ -1,21 +1,34 @@
 use axum::Json;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::extract::{OriginalUri, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
 use tracing::instrument;
 
-use super::util::parse_timestamp_with_context;
+use super::conditional::{ETag, conditional_json};
+use super::listing::paginate;
+use super::util::{AdminKey, parse_timestamp_with_context};
 use crate::error::AppResult;
 use crate::middleware::RequestTimeout;
-use crate::models::{CreateStreamRequest, StreamInfo};
+use crate::models::{
+    AuditAction, CreateStreamRequest, ListQuery, RenameRequest, StreamInfo, StreamStatsResponse,
+};
+use crate::services::AuditContext;
 use crate::state::AppState;
 use crate::validation::validate_resource_name;
 
-/// List all streams.
-#[instrument(skip(state, timeout))]
+/// List streams, optionally filtered, sorted and paged (see [`ListQuery`]).
+///
+/// Carries `X-Total-Count`, a `Link` header when paged, and an `ETag` of
+/// the returned page; a matching `If-None-Match` gets `304 Not Modified`
+/// without a body.
+#[instrument(skip(state, timeout, uri, headers))]
 pub async fn list_streams(
     State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+    OriginalUri(uri): OriginalUri,
     timeout: Option<RequestTimeout>,
-) -> AppResult<Json<Vec<StreamInfo>>> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
     let streams = state.iggy_scoped(timeout).list_streams().await?;
 
     let stream_infos: Vec<StreamInfo> = streams
@@ -33,8 +46,12 @@ pub async fn list_streams(
             }
         })
         .collect();
+    let (stream_infos, page_headers) = paginate(stream_infos, &query, &uri)?;
 
-    Ok(Json(stream_infos))
+    let etag = ETag::strong(&stream_infos);
+    let mut response = conditional_json(&headers, &etag, state.config.cache_max_age, stream_infos);
+    response.headers_mut().extend(page_headers);
+    Ok(response)
 }
 
 /// Get a specific stream by name.
@@ -62,34 +79,106 @@ pub async fn get_stream(
     }))
 }
 
-/// Create a new stream.
-#[instrument(skip(state, timeout, payload))]
+/// Get statistics for a stream with per-topic detail.
+///
+/// Served from the stats cache, which the background refresh keeps current
+/// every `STATS_CACHE_TTL_SECS`; `cache_age_seconds` reports the time since
+/// the entry was last confirmed.
+#[instrument(skip(state, timeout))]
+pub async fn stream_stats(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<StreamStatsResponse>> {
+    // Validate path parameter before use
+    validate_resource_name(&name, "Stream")?;
+
+    let client = state.iggy_scoped(timeout);
+    let stats = state.stream_stats(&client, &name).await?;
+
+    Ok(Json(stats))
+}
+
+/// Create a new stream (audited).
+#[instrument(skip(state, timeout, audit, payload))]
 pub async fn create_stream(
     State(state): State<AppState>,
     timeout: Option<RequestTimeout>,
+    audit: AuditContext,
     Json(payload): Json<CreateStreamRequest>,
 ) -> AppResult<StatusCode> {
     validate_resource_name(&payload.name, "Stream")?;
+    state.config.naming_policy.check_stream(&payload.name)?;
 
-    state
+    let result = state
         .iggy_scoped(timeout)
         .create_stream(&payload.name)
-        .await?;
+        .await;
+    state
+        .audit
+        .record(&audit, AuditAction::CreateStream, &payload.name, &result)
+        .await;
+    result?;
 
     Ok(StatusCode::CREATED)
 }
 
-/// Delete a stream by name.
-#[instrument(skip(state, timeout))]
+/// Rename a stream (audited).
+///
+/// Renaming the default stream, or onto its name, needs the admin key. A
+/// name in use is a `409 Conflict`.
+#[instrument(skip(state, timeout, admin, audit))]
+pub async fn rename_stream(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    audit: AuditContext,
+    Json(payload): Json<RenameRequest>,
+) -> AppResult<StatusCode> {
+    // Validate path parameter before use
+    validate_resource_name(&name, "Stream")?;
+    validate_resource_name(&payload.name, "Stream")?;
+    state.config.naming_policy.check_stream(&payload.name)?;
+    admin.guard_system_resource(&state, &name, None)?;
+    admin.guard_system_resource(&state, &payload.name, None)?;
+
+    let result = state
+        .iggy_scoped(timeout)
+        .rename_stream(&name, &payload.name)
+        .await;
+    let resource = format!("{name} -> {}", payload.name);
+    state
+        .audit
+        .record(&audit, AuditAction::RenameStream, &resource, &result)
+        .await;
+    result?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a stream by name (audited).
+///
+/// The default stream holds the service's own topics and is only deleted
+/// with the admin key.
+#[instrument(skip(state, timeout, admin, audit))]
 pub async fn delete_stream(
     State(state): State<AppState>,
     Path(name): Path<String>,
     timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    audit: AuditContext,
 ) -> AppResult<StatusCode> {
     // Validate path parameter before use
     validate_resource_name(&name, "Stream")?;
+    admin.guard_system_resource(&state, &name, None)?;
 
-    state.iggy_scoped(timeout).delete_stream(&name).await?;
+    let result = state.iggy_scoped(timeout).delete_stream(&name).await;
+    state
+        .audit
+        .record(&audit, AuditAction::DeleteStream, &name, &result)
+        .await;
+    result?;
 
     Ok(StatusCode::NO_CONTENT)
 }
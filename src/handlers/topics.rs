@@ -1,16 +1,24 @@
 use axum::Json;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::extract::{OriginalUri, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use serde::Deserialize;
 use tracing::instrument;
 
-use super::util::parse_timestamp_with_context;
-use crate::error::AppResult;
+use super::listing::paginate;
+use super::util::{AdminKey, parse_timestamp_with_context};
+use crate::error::{AppError, AppResult};
 use crate::middleware::RequestTimeout;
-use crate::models::{CreateTopicRequest, TopicInfo};
+use crate::models::{
+    AuditAction, BulkCreateTopicsResponse, BulkTopicResult, BulkTopicStatus, CreateTopicRequest,
+    ListQuery, ProfileQuery, RenameRequest, TopicInfo, TopicProfile, TopicStatsResponse,
+};
+use crate::services::{AuditContext, MAX_PROFILE_SAMPLE, profile};
 use crate::state::AppState;
 use crate::validation::{validate_partition_count, validate_resource_name};
 
+/// Most topics accepted by one bulk creation.
+pub const MAX_BULK_TOPICS: usize = 100;
+
 /// Path parameters for topic operations.
 #[derive(Debug, Deserialize)]
 pub struct StreamPath {
@@ -24,13 +32,18 @@ pub struct TopicPath {
     pub topic: String,
 }
 
-/// List all topics in a stream.
-#[instrument(skip(state, timeout))]
+/// List the topics in a stream, optionally filtered, sorted and paged (see
+/// [`ListQuery`]).
+///
+/// Carries `X-Total-Count`, and a `Link` header when paged.
+#[instrument(skip(state, timeout, uri))]
 pub async fn list_topics(
     State(state): State<AppState>,
     Path(path): Path<StreamPath>,
+    Query(query): Query<ListQuery>,
+    OriginalUri(uri): OriginalUri,
     timeout: Option<RequestTimeout>,
-) -> AppResult<Json<Vec<TopicInfo>>> {
+) -> AppResult<(HeaderMap, Json<Vec<TopicInfo>>)> {
     // Validate path parameter before use
     validate_resource_name(&path.stream, "Stream")?;
 
@@ -54,8 +67,9 @@ pub async fn list_topics(
             }
         })
         .collect();
+    let (topic_infos, headers) = paginate(topic_infos, &query, &uri)?;
 
-    Ok(Json(topic_infos))
+    Ok((headers, Json(topic_infos)))
 }
 
 /// Get a specific topic by name.
@@ -87,43 +101,269 @@ pub async fn get_topic(
     }))
 }
 
-/// Create a new topic in a stream.
-#[instrument(skip(state, timeout, payload))]
+/// Get statistics for a topic with per-partition detail.
+///
+/// Served from a per-topic cache that is refreshed at most once per
+/// `STATS_CACHE_TTL_SECS`; `cache_age_seconds` reports the entry's age.
+#[instrument(skip(state, timeout))]
+pub async fn topic_stats(
+    State(state): State<AppState>,
+    Path(path): Path<TopicPath>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<TopicStatsResponse>> {
+    // Validate path parameters before use
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+
+    let client = state.iggy_scoped(timeout);
+    let stats = state
+        .topic_stats(&client, &path.stream, &path.topic)
+        .await?;
+
+    Ok(Json(stats))
+}
+
+/// Profile a topic from a sample of its newest messages.
+///
+/// Reads about `sample` recent messages (default 200, at most
+/// [`MAX_PROFILE_SAMPLE`]) spread across partitions, with peeks, and
+/// reports their size distribution, top event types, payload field
+/// frequencies and compression estimates (see [`crate::services::profile`]).
+///
+/// # Response Body
+///
+/// ```json
+/// {
+///   "stream": "orders",
+///   "topic": "created",
+///   "sampled": 200,
+///   "oldest": "2024-01-15T10:25:00Z",
+///   "newest": "2024-01-15T10:30:00Z",
+///   "payload_bytes": {
+///     "min": 310, "max": 2288, "mean": 512.4, "p50": 480, "p90": 730, "p99": 2100
+///   },
+///   "event_types": [{ "value": "order.created", "count": 180, "share": 0.9 }],
+///   "fields": [{ "value": "$.payload.data.items[].sku", "count": 176, "share": 0.88 }],
+///   "json_bytes": 102480,
+///   "compression": [
+///     { "algorithm": "gzip", "bytes": 61210, "ratio": 1.67, "compressed_messages": 12 },
+///     { "algorithm": "zstd", "bytes": 58900, "ratio": 1.74, "compressed_messages": 12 }
+///   ]
+/// }
+/// ```
+#[instrument(skip(state, timeout))]
+pub async fn topic_profile(
+    State(state): State<AppState>,
+    Path(path): Path<TopicPath>,
+    timeout: Option<RequestTimeout>,
+    Query(query): Query<ProfileQuery>,
+) -> AppResult<Json<TopicProfile>> {
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    if query.sample == 0 || query.sample > MAX_PROFILE_SAMPLE {
+        return Err(AppError::BadRequest(format!(
+            "sample must be between 1 and {MAX_PROFILE_SAMPLE}"
+        )));
+    }
+
+    let messages = state
+        .consumer_scoped(timeout)
+        .sample_newest(
+            &path.stream,
+            &path.topic,
+            query.sample,
+            state.config.poll_max_count,
+        )
+        .await?;
+    Ok(Json(profile(
+        &path.stream,
+        &path.topic,
+        &messages,
+        state.config.compression_threshold_bytes,
+    )))
+}
+
+/// Create a new topic in a stream (audited).
+#[instrument(skip(state, timeout, audit, payload))]
 pub async fn create_topic(
     State(state): State<AppState>,
     Path(path): Path<StreamPath>,
     timeout: Option<RequestTimeout>,
+    audit: AuditContext,
     Json(payload): Json<CreateTopicRequest>,
 ) -> AppResult<StatusCode> {
     // Validate path parameter before use
     validate_resource_name(&path.stream, "Stream")?;
     // Validate request body
-    validate_resource_name(&payload.name, "Topic")?;
-    validate_partition_count(payload.partitions, "Topic")?;
+    validate_new_topic(&state, &payload)?;
 
-    state
+    let result = state
         .iggy_scoped(timeout)
         .create_topic(&path.stream, &payload.name, payload.partitions)
-        .await?;
+        .await;
+    let resource = format!("{}/{}", path.stream, payload.name);
+    state
+        .audit
+        .record(&audit, AuditAction::CreateTopic, &resource, &result)
+        .await;
+    result?;
 
     Ok(StatusCode::CREATED)
 }
 
-/// Delete a topic from a stream.
-#[instrument(skip(state, timeout))]
-pub async fn delete_topic(
+/// Create several topics in a stream, leaving those that exist (audited).
+///
+/// Meant for bootstrapping environments: each topic is validated and
+/// created on its own, and the response reports `created`,
+/// `already_exists` or `error` for each, in request order, so a script can
+/// be re-run safely. An existing topic is left as it is, whatever its
+/// partition count.
+///
+/// # Errors
+///
+/// Returns `AppError::BadRequest` for an empty list or one longer than
+/// [`MAX_BULK_TOPICS`], and `AppError::NotFound` if the stream does not
+/// exist.
+#[instrument(skip(state, timeout, audit, payload), fields(count = payload.len()))]
+pub async fn create_topics_bulk(
+    State(state): State<AppState>,
+    Path(path): Path<StreamPath>,
+    timeout: Option<RequestTimeout>,
+    audit: AuditContext,
+    Json(payload): Json<Vec<CreateTopicRequest>>,
+) -> AppResult<Json<BulkCreateTopicsResponse>> {
+    // Validate path parameter before use
+    validate_resource_name(&path.stream, "Stream")?;
+    if payload.is_empty() || payload.len() > MAX_BULK_TOPICS {
+        return Err(AppError::BadRequest(format!(
+            "Expected 1 to {MAX_BULK_TOPICS} topics, got {}",
+            payload.len()
+        )));
+    }
+
+    let client = state.iggy_scoped(timeout);
+    client.get_stream(&path.stream).await?;
+
+    // Invalid topics are reported without being sent to the server
+    let checks: Vec<AppResult<()>> = payload
+        .iter()
+        .map(|topic| validate_new_topic(&state, topic))
+        .collect();
+    let valid: Vec<(String, u32)> = payload
+        .iter()
+        .zip(&checks)
+        .filter(|(_, check)| check.is_ok())
+        .map(|(topic, _)| (topic.name.clone(), topic.partitions))
+        .collect();
+    let mut outcomes = client.ensure_topics(&path.stream, &valid).await.into_iter();
+
+    let mut results = Vec::with_capacity(payload.len());
+    for (topic, check) in payload.into_iter().zip(checks) {
+        let attempted = check.is_ok();
+        let outcome = check.and_then(|()| {
+            outcomes.next().unwrap_or_else(|| {
+                Err(AppError::Internal(format!(
+                    "No result for topic '{}'",
+                    topic.name
+                )))
+            })
+        });
+        if attempted && !matches!(outcome, Ok(false)) {
+            let resource = format!("{}/{}", path.stream, topic.name);
+            state
+                .audit
+                .record(&audit, AuditAction::CreateTopic, &resource, &outcome)
+                .await;
+        }
+        let (status, error) = match outcome {
+            Ok(true) => (BulkTopicStatus::Created, None),
+            Ok(false) => (BulkTopicStatus::AlreadyExists, None),
+            Err(e) => (BulkTopicStatus::Error, Some(e.to_string())),
+        };
+        results.push(BulkTopicResult {
+            name: topic.name,
+            status,
+            error,
+        });
+    }
+
+    Ok(Json(BulkCreateTopicsResponse {
+        stream: path.stream,
+        results,
+    }))
+}
+
+/// Check the name (including the naming policy) and partition count of a
+/// topic to create.
+fn validate_new_topic(state: &AppState, topic: &CreateTopicRequest) -> AppResult<()> {
+    validate_resource_name(&topic.name, "Topic")?;
+    state.config.naming_policy.check_topic(&topic.name)?;
+    validate_partition_count(topic.partitions, "Topic")
+}
+
+/// Rename a topic within its stream (audited).
+///
+/// The topic keeps its partitions, settings and messages. System topics
+/// are only renamed with the admin key, and a name in use is a
+/// `409 Conflict`.
+#[instrument(skip(state, timeout, admin, audit))]
+pub async fn rename_topic(
     State(state): State<AppState>,
     Path(path): Path<TopicPath>,
     timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    audit: AuditContext,
+    Json(payload): Json<RenameRequest>,
 ) -> AppResult<StatusCode> {
     // Validate path parameters before use
     validate_resource_name(&path.stream, "Stream")?;
     validate_resource_name(&path.topic, "Topic")?;
+    validate_resource_name(&payload.name, "Topic")?;
+    state.config.naming_policy.check_topic(&payload.name)?;
+    admin.guard_system_resource(&state, &path.stream, Some(&path.topic))?;
+    admin.guard_system_resource(&state, &path.stream, Some(&payload.name))?;
 
+    let result = state
+        .iggy_scoped(timeout)
+        .rename_topic(&path.stream, &path.topic, &payload.name)
+        .await;
+    let resource = format!("{}/{} -> {}", path.stream, path.topic, payload.name);
     state
+        .audit
+        .record(&audit, AuditAction::RenameTopic, &resource, &result)
+        .await;
+    result?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a topic from a stream (audited).
+///
+/// System topics (dead-letter, audit, ...) are only deleted with the admin
+/// key.
+#[instrument(skip(state, timeout, admin, audit))]
+pub async fn delete_topic(
+    State(state): State<AppState>,
+    Path(path): Path<TopicPath>,
+    timeout: Option<RequestTimeout>,
+    admin: AdminKey,
+    audit: AuditContext,
+) -> AppResult<StatusCode> {
+    // Validate path parameters before use
+    validate_resource_name(&path.stream, "Stream")?;
+    validate_resource_name(&path.topic, "Topic")?;
+    admin.guard_system_resource(&state, &path.stream, Some(&path.topic))?;
+
+    let result = state
         .iggy_scoped(timeout)
         .delete_topic(&path.stream, &path.topic)
-        .await?;
+        .await;
+    let resource = format!("{}/{}", path.stream, path.topic);
+    state
+        .audit
+        .record(&audit, AuditAction::DeleteTopic, &resource, &result)
+        .await;
+    result?;
 
     Ok(StatusCode::NO_CONTENT)
 }
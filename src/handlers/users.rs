@@ -0,0 +1,274 @@
+//! Iggy user management endpoints (admin scope).
+//!
+//! # Endpoints
+//!
+//! - `GET /admin/users` - List users
+//! - `POST /admin/users` - Create a user
+//! - `GET /admin/users/{username}` - Get a user with permissions
+//! - `DELETE /admin/users/{username}` - Delete a user
+//! - `PUT /admin/users/{username}/permissions` - Replace global permissions
+//! - `PUT /admin/users/{username}/password` - Change password
+//!
+//! These let deployments bootstrap Iggy credentials programmatically. Every
+//! route requires the `X-Admin-Key` header (see [`crate::middleware::admin`])
+//! on top of the regular API key, and the gateway's own Iggy user must hold
+//! `manage_users` on the server. Every change is recorded in the audit log
+//! (see [`crate::services::AuditService`]).
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use iggy::prelude::{GlobalPermissions, Permissions, UserStatus};
+use iggy_common::{UserInfo, UserInfoDetails};
+use tracing::instrument;
+
+use super::util::parse_timestamp_with_context;
+use crate::error::AppResult;
+use crate::middleware::RequestTimeout;
+use crate::models::{
+    AuditAction, ChangePasswordRequest, CreateUserRequest, UpdatePermissionsRequest,
+    UserPermissions, UserResponse,
+};
+use crate::services::AuditContext;
+use crate::state::AppState;
+use crate::validation::{validate_password, validate_resource_name};
+
+/// List all users.
+#[instrument(skip(state, timeout))]
+pub async fn list_users(
+    State(state): State<AppState>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<Vec<UserResponse>>> {
+    let users = state.iggy_scoped(timeout).list_users().await?;
+
+    Ok(Json(users.into_iter().map(user_response).collect()))
+}
+
+/// Get a user by name, including global permissions.
+#[instrument(skip(state, timeout))]
+pub async fn get_user(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    timeout: Option<RequestTimeout>,
+) -> AppResult<Json<UserResponse>> {
+    validate_resource_name(&username, "User")?;
+
+    let user = state.iggy_scoped(timeout).get_user(&username).await?;
+
+    Ok(Json(user_details_response(user)))
+}
+
+/// Create a user.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "username": "order-service",
+///   "password": "s3cret",
+///   "active": true,
+///   "permissions": { "send_messages": true, "read_streams": true }
+/// }
+/// ```
+#[instrument(skip(state, timeout, audit, payload))]
+pub async fn create_user(
+    State(state): State<AppState>,
+    timeout: Option<RequestTimeout>,
+    audit: AuditContext,
+    Json(payload): Json<CreateUserRequest>,
+) -> AppResult<(StatusCode, Json<UserResponse>)> {
+    validate_resource_name(&payload.username, "User")?;
+    validate_password(&payload.password)?;
+
+    let status = if payload.active {
+        UserStatus::Active
+    } else {
+        UserStatus::Inactive
+    };
+    let result = state
+        .iggy_scoped(timeout)
+        .create_user(
+            &payload.username,
+            &payload.password,
+            status,
+            payload.permissions.as_ref().map(to_iggy_permissions),
+        )
+        .await;
+    state
+        .audit
+        .record(&audit, AuditAction::CreateUser, &payload.username, &result)
+        .await;
+    let user = result?;
+
+    Ok((StatusCode::CREATED, Json(user_details_response(user))))
+}
+
+/// Replace a user's global permissions.
+#[instrument(skip(state, timeout, audit, payload))]
+pub async fn update_user_permissions(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    timeout: Option<RequestTimeout>,
+    audit: AuditContext,
+    Json(payload): Json<UpdatePermissionsRequest>,
+) -> AppResult<StatusCode> {
+    validate_resource_name(&username, "User")?;
+
+    let result = state
+        .iggy_scoped(timeout)
+        .update_permissions(
+            &username,
+            payload.permissions.as_ref().map(to_iggy_permissions),
+        )
+        .await;
+    state
+        .audit
+        .record(
+            &audit,
+            AuditAction::UpdateUserPermissions,
+            &username,
+            &result,
+        )
+        .await;
+    result?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Change a user's password.
+#[instrument(skip(state, timeout, audit, payload))]
+pub async fn change_user_password(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    timeout: Option<RequestTimeout>,
+    audit: AuditContext,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> AppResult<StatusCode> {
+    validate_resource_name(&username, "User")?;
+    validate_password(&payload.new_password)?;
+
+    let result = state
+        .iggy_scoped(timeout)
+        .change_password(&username, &payload.current_password, &payload.new_password)
+        .await;
+    state
+        .audit
+        .record(&audit, AuditAction::ChangeUserPassword, &username, &result)
+        .await;
+    result?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a user.
+#[instrument(skip(state, timeout, audit))]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    timeout: Option<RequestTimeout>,
+    audit: AuditContext,
+) -> AppResult<StatusCode> {
+    validate_resource_name(&username, "User")?;
+
+    let result = state.iggy_scoped(timeout).delete_user(&username).await;
+    state
+        .audit
+        .record(&audit, AuditAction::DeleteUser, &username, &result)
+        .await;
+    result?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// =============================================================================
+// SDK Conversions
+// =============================================================================
+
+fn user_response(user: UserInfo) -> UserResponse {
+    let created_at =
+        parse_timestamp_with_context(user.created_at.as_micros() as i64, "user", &user.username);
+    UserResponse {
+        id: user.id,
+        status: user_status_label(user.status),
+        username: user.username,
+        created_at,
+        permissions: None,
+    }
+}
+
+fn user_details_response(user: UserInfoDetails) -> UserResponse {
+    let created_at =
+        parse_timestamp_with_context(user.created_at.as_micros() as i64, "user", &user.username);
+    UserResponse {
+        id: user.id,
+        status: user_status_label(user.status),
+        username: user.username,
+        created_at,
+        // A user without permissions is reported as all-false rather than
+        // omitted, so single-user responses always carry the field.
+        permissions: Some(
+            user.permissions
+                .map(|p| from_iggy_permissions(&p.global))
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn user_status_label(status: UserStatus) -> String {
+    match status {
+        UserStatus::Active => "active",
+        UserStatus::Inactive => "inactive",
+    }
+    .to_string()
+}
+
+fn to_iggy_permissions(permissions: &UserPermissions) -> Permissions {
+    Permissions {
+        global: GlobalPermissions {
+            manage_servers: permissions.manage_servers,
+            read_servers: permissions.read_servers,
+            manage_users: permissions.manage_users,
+            read_users: permissions.read_users,
+            manage_streams: permissions.manage_streams,
+            read_streams: permissions.read_streams,
+            manage_topics: permissions.manage_topics,
+            read_topics: permissions.read_topics,
+            poll_messages: permissions.poll_messages,
+            send_messages: permissions.send_messages,
+        },
+        streams: None,
+    }
+}
+
+fn from_iggy_permissions(global: &GlobalPermissions) -> UserPermissions {
+    UserPermissions {
+        manage_servers: global.manage_servers,
+        read_servers: global.read_servers,
+        manage_users: global.manage_users,
+        read_users: global.read_users,
+        manage_streams: global.manage_streams,
+        read_streams: global.read_streams,
+        manage_topics: global.manage_topics,
+        read_topics: global.read_topics,
+        poll_messages: global.poll_messages,
+        send_messages: global.send_messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permissions_round_trip_through_sdk_type() {
+        let permissions = UserPermissions {
+            read_streams: true,
+            send_messages: true,
+            ..UserPermissions::default()
+        };
+
+        let iggy = to_iggy_permissions(&permissions);
+        assert!(iggy.streams.is_none());
+        assert_eq!(from_iggy_permissions(&iggy.global), permissions);
+    }
+}
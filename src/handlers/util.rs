@@ -1,8 +1,21 @@
 //! Shared utilities for handlers.
 
-use chrono::{DateTime, Utc};
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use chrono::{DateTime, TimeDelta, Utc};
 use tracing::warn;
 
+use crate::error::{AppError, AppResult};
+use crate::middleware::admin::ADMIN_KEY_HEADER;
+use crate::middleware::auth::constant_time_eq;
+use crate::middleware::ip::UNKNOWN_IP;
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+use crate::middleware::{ClientIp, SignedRequest};
+use crate::models::SendMessageRequest;
+use crate::services::AuditContext;
+use crate::state::AppState;
+use crate::validation::is_system_resource;
+
 /// Parse a timestamp from microseconds with proper logging for invalid values.
 ///
 /// If the timestamp cannot be converted (e.g., overflow, invalid value),
@@ -32,6 +45,125 @@ pub fn parse_timestamp_with_context(
     })
 }
 
+/// When a send asked to be delivered, relative to `now`: `None` for an
+/// immediate send, otherwise `deliver_at` or `now + delay_ms`.
+///
+/// # Errors
+///
+/// Returns `AppError::BadRequest` if both are set or `delay_ms` overflows.
+pub fn delivery_time(
+    request: &SendMessageRequest,
+    now: DateTime<Utc>,
+) -> AppResult<Option<DateTime<Utc>>> {
+    match (request.deliver_at, request.delay_ms) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(AppError::BadRequest(
+            "Set only one of deliver_at and delay_ms".to_string(),
+        )),
+        (Some(deliver_at), None) => Ok(Some(deliver_at)),
+        (None, Some(delay_ms)) => i64::try_from(delay_ms)
+            .ok()
+            .and_then(TimeDelta::try_milliseconds)
+            .and_then(|delay| now.checked_add_signed(delay))
+            .map(Some)
+            .ok_or_else(|| AppError::BadRequest(format!("delay_ms {delay_ms} is too large"))),
+    }
+}
+
+/// Whether the request carries a valid `X-Admin-Key`.
+///
+/// Unlike the admin-scoped routes, nothing is rejected at extraction: a
+/// handler asks [`AdminKey::guard_system_resource`] once it knows the
+/// target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdminKey(pub bool);
+
+impl AdminKey {
+    /// Reject writing to or deleting a system resource (see
+    /// [`is_system_resource`]) without the admin key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Forbidden` naming the resource.
+    pub fn guard_system_resource(
+        self,
+        state: &AppState,
+        stream: &str,
+        topic: Option<&str>,
+    ) -> AppResult<()> {
+        if self.0 || !is_system_resource(&state.config, stream, topic) {
+            return Ok(());
+        }
+        let resource = match topic {
+            Some(topic) => format!("Topic '{stream}/{topic}'"),
+            None => format!("Stream '{stream}'"),
+        };
+        Err(AppError::Forbidden(format!(
+            "{resource} is reserved for the service; X-Admin-Key required"
+        )))
+    }
+}
+
+impl FromRequestParts<AppState> for AdminKey {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(has_admin_key(parts, state)))
+    }
+}
+
+/// Check the request's `X-Admin-Key` against `ADMIN_API_KEY` (constant-time).
+fn has_admin_key(parts: &Parts, state: &AppState) -> bool {
+    let provided = parts
+        .headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    match (provided, &state.config.admin_api_key) {
+        (Some(provided), Some(expected)) => constant_time_eq(provided, expected),
+        _ => false,
+    }
+}
+
+/// Audit context of the request: its strongest credential, client IP and
+/// request ID.
+///
+/// The API key itself was already checked by the auth layer; the admin key
+/// is checked here because most audited routes are not admin-scoped.
+impl FromRequestParts<AppState> for AuditContext {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let actor = if has_admin_key(parts, state) {
+            "admin"
+        } else if parts.extensions.get::<SignedRequest>().is_some() {
+            "signed"
+        } else if state.config.api_key.is_some() {
+            "api_key"
+        } else {
+            "anonymous"
+        };
+
+        Ok(Self {
+            actor,
+            client_ip: parts
+                .extensions
+                .get::<ClientIp>()
+                .map_or_else(|| UNKNOWN_IP.to_string(), |ip| ip.0.clone()),
+            request_id: parts
+                .headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        })
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -63,4 +195,31 @@ mod tests {
         let diff = (now - result).num_seconds().abs();
         assert!(diff < 60, "Fallback should be close to current time");
     }
+
+    #[test]
+    fn test_delivery_time() {
+        let now = Utc::now();
+        let request = |deliver_at, delay_ms| SendMessageRequest {
+            event: crate::models::Event::new(
+                "test.event",
+                crate::models::EventPayload::Generic(serde_json::json!({})),
+            ),
+            partition_key: None,
+            partitioning: None,
+            deliver_at,
+            delay_ms,
+        };
+
+        assert_eq!(delivery_time(&request(None, None), now).unwrap(), None);
+        assert_eq!(
+            delivery_time(&request(None, Some(1500)), now).unwrap(),
+            Some(now + TimeDelta::milliseconds(1500))
+        );
+        assert_eq!(
+            delivery_time(&request(Some(now), None), now).unwrap(),
+            Some(now)
+        );
+        assert!(delivery_time(&request(Some(now), Some(1)), now).is_err());
+        assert!(delivery_time(&request(None, Some(u64::MAX)), now).is_err());
+    }
 }
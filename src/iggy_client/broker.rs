@@ -0,0 +1,632 @@
+//! The full message-broker surface used by the services, as a trait.
+//!
+//! [`MessageBroker`] extends the produce-path [`IggyOperations`] with
+//! polling, consumer offsets, and stream/topic administration.
+//! [`ConsumerService`](crate::services::ConsumerService) is generic over it
+//! (defaulting to [`IggyClientWrapper`]), as `ProducerService` is over
+//! `IggyOperations`, so both can run against another backend: an in-memory
+//! broker in tests and demos, or a bridge to a different system.
+//!
+//! Read operations return the Iggy SDK's models (`PolledMessages`,
+//! `TopicDetails`, ...), which every backend maps its data into; the API
+//! layer already serializes from them.
+//!
+//! # Backends of the Wrapper
+//!
+//! [`IggyClientWrapper`] serves its operations from the Iggy SDK, or from
+//! any other `MessageBroker` it was built with (see
+//! [`IggyClientWrapper::with_broker`]): the [`MemoryBroker`] with
+//! `BROKER_BACKEND=memory`, for one. Either way each operation goes through
+//! the wrapper's fair queue, deadline, circuit breaker and metrics. The
+//! wrapper holds the broker as a [`DynBroker`], the object-safe form every
+//! `MessageBroker` gets.
+//!
+//! # What Stays on the Wrapper
+//!
+//! [`AppState`] and the handlers hold the concrete [`IggyClientWrapper`],
+//! not a generic or boxed broker: the wrapper is the resilience layer, and
+//! a broker swapped in at that level would bypass it. The backend is
+//! chosen below the wrapper instead; [`AppState::with_broker`] runs the
+//! whole API against any `MessageBroker`. Connection management
+//! (`connect`, `health_check`, `reconnect_now`, connection observers) is a
+//! no-op for a broker backend, and user management and server stats,
+//! which only an Iggy server has, return `AppError::BadRequest`.
+//!
+//! [`AppState`]: crate::state::AppState
+//! [`AppState::with_broker`]: crate::state::AppState::with_broker
+//! [`MemoryBroker`]: super::MemoryBroker
+
+use std::future::Future;
+
+use futures_util::future::BoxFuture;
+use iggy::prelude::{
+    IggyExpiry, IggyMessage, MaxTopicSize, Partitioning, PolledMessages, Stream, StreamDetails,
+    Topic, TopicDetails,
+};
+
+use super::{IggyClientWrapper, IggyOperations, PollParams};
+use crate::error::AppResult;
+use crate::models::Event;
+
+/// Polling, offset and admin operations of a message broker.
+///
+/// Errors follow [`IggyClientWrapper`]'s classification: a missing stream
+/// or topic is `AppError::NotFound`, an unreachable broker one of the
+/// retryable connection variants.
+pub trait MessageBroker: IggyOperations {
+    /// Create `stream`/`topic` with `partitions`, `expiry` and `max_size`
+    /// unless it exists; whether it was created.
+    fn ensure_topic_with(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Poll messages from one partition of `stream`/`topic`.
+    fn poll_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+    ) -> impl Future<Output = AppResult<PolledMessages>> + Send;
+
+    /// Committed offset of standalone consumer `consumer_id` on one
+    /// partition (None = never committed).
+    fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> impl Future<Output = AppResult<Option<u64>>> + Send;
+
+    /// Commit `offset` for standalone consumer `consumer_id` on one
+    /// partition.
+    fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Forget the committed offset of standalone consumer `consumer_id` on
+    /// one partition.
+    fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Details of stream `name`.
+    fn get_stream(&self, name: &str) -> impl Future<Output = AppResult<StreamDetails>> + Send;
+
+    /// Details of `stream`/`topic`, including its partitions.
+    fn get_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+    ) -> impl Future<Output = AppResult<TopicDetails>> + Send;
+
+    /// All streams.
+    fn list_streams(&self) -> impl Future<Output = AppResult<Vec<Stream>>> + Send;
+
+    /// All topics of `stream`.
+    fn list_topics(&self, stream: &str) -> impl Future<Output = AppResult<Vec<Topic>>> + Send;
+
+    /// Create stream `name`; fails if it exists.
+    fn create_stream(&self, name: &str) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Create `stream`/`topic` with `partitions`; fails if it exists.
+    fn create_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Delete stream `name` and everything in it.
+    fn delete_stream(&self, name: &str) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Delete `stream`/`topic` and its messages.
+    fn delete_topic(&self, stream: &str, topic: &str)
+    -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Rename stream `name`, keeping its topics and messages.
+    ///
+    /// Returns `AppError::NotFound` if it does not exist and
+    /// `AppError::Conflict` if `new_name` is taken.
+    fn rename_stream(
+        &self,
+        name: &str,
+        new_name: &str,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Rename `stream`/`topic`, keeping its settings and messages.
+    ///
+    /// Returns `AppError::NotFound` if it does not exist and
+    /// `AppError::Conflict` if `new_name` is taken in the stream.
+    fn rename_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        new_name: &str,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Set the message expiry and size limit of `stream`/`topic` where they
+    /// differ (`None` leaves a setting as it is); whether it changed.
+    fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Delete every message of `stream`/`topic`, keeping the topic.
+    fn purge_topic(&self, stream: &str, topic: &str) -> impl Future<Output = AppResult<()>> + Send;
+}
+
+impl MessageBroker for IggyClientWrapper {
+    async fn ensure_topic_with(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> AppResult<bool> {
+        IggyClientWrapper::ensure_topic_with(self, stream, topic, partitions, expiry, max_size)
+            .await
+    }
+
+    async fn poll_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+    ) -> AppResult<PolledMessages> {
+        IggyClientWrapper::poll_messages(self, stream, topic, params).await
+    }
+
+    async fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<Option<u64>> {
+        IggyClientWrapper::get_consumer_offset(self, stream, topic, consumer_id, partition_id).await
+    }
+
+    async fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<()> {
+        IggyClientWrapper::store_consumer_offset(
+            self,
+            stream,
+            topic,
+            consumer_id,
+            partition_id,
+            offset,
+        )
+        .await
+    }
+
+    async fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<()> {
+        IggyClientWrapper::delete_consumer_offset(self, stream, topic, consumer_id, partition_id)
+            .await
+    }
+
+    async fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
+        IggyClientWrapper::get_stream(self, name).await
+    }
+
+    async fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
+        IggyClientWrapper::get_topic(self, stream, topic).await
+    }
+
+    async fn list_streams(&self) -> AppResult<Vec<Stream>> {
+        IggyClientWrapper::list_streams(self).await
+    }
+
+    async fn list_topics(&self, stream: &str) -> AppResult<Vec<Topic>> {
+        IggyClientWrapper::list_topics(self, stream).await
+    }
+
+    async fn create_stream(&self, name: &str) -> AppResult<()> {
+        IggyClientWrapper::create_stream(self, name).await
+    }
+
+    async fn create_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        IggyClientWrapper::create_topic(self, stream, topic, partitions).await
+    }
+
+    async fn delete_stream(&self, name: &str) -> AppResult<()> {
+        IggyClientWrapper::delete_stream(self, name).await
+    }
+
+    async fn delete_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        IggyClientWrapper::delete_topic(self, stream, topic).await
+    }
+
+    async fn rename_stream(&self, name: &str, new_name: &str) -> AppResult<()> {
+        IggyClientWrapper::rename_stream(self, name, new_name).await
+    }
+
+    async fn rename_topic(&self, stream: &str, topic: &str, new_name: &str) -> AppResult<()> {
+        IggyClientWrapper::rename_topic(self, stream, topic, new_name).await
+    }
+
+    async fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> AppResult<bool> {
+        IggyClientWrapper::enforce_topic_retention(
+            self,
+            stream,
+            topic,
+            max_age_secs,
+            max_size_bytes,
+        )
+        .await
+    }
+
+    async fn purge_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        IggyClientWrapper::purge_topic(self, stream, topic).await
+    }
+}
+
+/// Future returned by [`DynBroker`] operations.
+pub type BrokerFuture<'a, T> = BoxFuture<'a, AppResult<T>>;
+
+/// Object-safe form of [`MessageBroker`], implemented for every broker,
+/// which the wrapper holds its broker as.
+///
+/// Only the operations the wrapper serves from a broker are here; the
+/// configuration and `with_timeout` views are the wrapper's own.
+pub trait DynBroker: Send + Sync {
+    /// See [`IggyOperations::ensure_stream`].
+    fn ensure_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::ensure_topic_with`].
+    fn ensure_topic_with<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> BrokerFuture<'a, bool>;
+
+    /// See [`IggyOperations::send_event_partitioned`].
+    fn send_event_partitioned<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        event: &'a Event,
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`IggyOperations::send_events_batch_partitioned`].
+    fn send_events_batch_partitioned<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        events: &'a [Event],
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`IggyOperations::send_raw_messages`].
+    fn send_raw_messages<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        messages: &'a [IggyMessage],
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::poll_messages`].
+    fn poll_messages<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        params: PollParams,
+    ) -> BrokerFuture<'a, PolledMessages>;
+
+    /// See [`MessageBroker::get_consumer_offset`].
+    fn get_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> BrokerFuture<'a, Option<u64>>;
+
+    /// See [`MessageBroker::store_consumer_offset`].
+    fn store_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::delete_consumer_offset`].
+    fn delete_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::get_stream`].
+    fn get_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, StreamDetails>;
+
+    /// See [`MessageBroker::get_topic`].
+    fn get_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, TopicDetails>;
+
+    /// See [`MessageBroker::list_streams`].
+    fn list_streams(&self) -> BrokerFuture<'_, Vec<Stream>>;
+
+    /// See [`MessageBroker::list_topics`].
+    fn list_topics<'a>(&'a self, stream: &'a str) -> BrokerFuture<'a, Vec<Topic>>;
+
+    /// See [`MessageBroker::create_stream`].
+    fn create_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::create_topic`].
+    fn create_topic<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        partitions: u32,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::delete_stream`].
+    fn delete_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::delete_topic`].
+    fn delete_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::rename_stream`].
+    fn rename_stream<'a>(&'a self, name: &'a str, new_name: &'a str) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::rename_topic`].
+    fn rename_topic<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        new_name: &'a str,
+    ) -> BrokerFuture<'a, ()>;
+
+    /// See [`MessageBroker::enforce_topic_retention`].
+    fn enforce_topic_retention<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> BrokerFuture<'a, bool>;
+
+    /// See [`MessageBroker::purge_topic`].
+    fn purge_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, ()>;
+}
+
+impl<B: MessageBroker> DynBroker for B {
+    fn ensure_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(IggyOperations::ensure_stream(self, name))
+    }
+
+    fn ensure_topic_with<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> BrokerFuture<'a, bool> {
+        Box::pin(MessageBroker::ensure_topic_with(
+            self, stream, topic, partitions, expiry, max_size,
+        ))
+    }
+
+    fn send_event_partitioned<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        event: &'a Event,
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(IggyOperations::send_event_partitioned(
+            self,
+            stream,
+            topic,
+            event,
+            partitioning,
+        ))
+    }
+
+    fn send_events_batch_partitioned<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        events: &'a [Event],
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(IggyOperations::send_events_batch_partitioned(
+            self,
+            stream,
+            topic,
+            events,
+            partitioning,
+        ))
+    }
+
+    fn send_raw_messages<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        messages: &'a [IggyMessage],
+        partitioning: &'a Partitioning,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(IggyOperations::send_raw_messages(
+            self,
+            stream,
+            topic,
+            messages,
+            partitioning,
+        ))
+    }
+
+    fn poll_messages<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        params: PollParams,
+    ) -> BrokerFuture<'a, PolledMessages> {
+        Box::pin(MessageBroker::poll_messages(self, stream, topic, params))
+    }
+
+    fn get_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> BrokerFuture<'a, Option<u64>> {
+        Box::pin(MessageBroker::get_consumer_offset(
+            self,
+            stream,
+            topic,
+            consumer_id,
+            partition_id,
+        ))
+    }
+
+    fn store_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::store_consumer_offset(
+            self,
+            stream,
+            topic,
+            consumer_id,
+            partition_id,
+            offset,
+        ))
+    }
+
+    fn delete_consumer_offset<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::delete_consumer_offset(
+            self,
+            stream,
+            topic,
+            consumer_id,
+            partition_id,
+        ))
+    }
+
+    fn get_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, StreamDetails> {
+        Box::pin(MessageBroker::get_stream(self, name))
+    }
+
+    fn get_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, TopicDetails> {
+        Box::pin(MessageBroker::get_topic(self, stream, topic))
+    }
+
+    fn list_streams(&self) -> BrokerFuture<'_, Vec<Stream>> {
+        Box::pin(MessageBroker::list_streams(self))
+    }
+
+    fn list_topics<'a>(&'a self, stream: &'a str) -> BrokerFuture<'a, Vec<Topic>> {
+        Box::pin(MessageBroker::list_topics(self, stream))
+    }
+
+    fn create_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::create_stream(self, name))
+    }
+
+    fn create_topic<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        partitions: u32,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::create_topic(self, stream, topic, partitions))
+    }
+
+    fn delete_stream<'a>(&'a self, name: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::delete_stream(self, name))
+    }
+
+    fn delete_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::delete_topic(self, stream, topic))
+    }
+
+    fn rename_stream<'a>(&'a self, name: &'a str, new_name: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::rename_stream(self, name, new_name))
+    }
+
+    fn rename_topic<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        new_name: &'a str,
+    ) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::rename_topic(self, stream, topic, new_name))
+    }
+
+    fn enforce_topic_retention<'a>(
+        &'a self,
+        stream: &'a str,
+        topic: &'a str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> BrokerFuture<'a, bool> {
+        Box::pin(MessageBroker::enforce_topic_retention(
+            self,
+            stream,
+            topic,
+            max_age_secs,
+            max_size_bytes,
+        ))
+    }
+
+    fn purge_topic<'a>(&'a self, stream: &'a str, topic: &'a str) -> BrokerFuture<'a, ()> {
+        Box::pin(MessageBroker::purge_topic(self, stream, topic))
+    }
+}
@@ -52,6 +52,15 @@
 //! post-reconnect retry in `resilience::run_resilient` deliberately does
 //! not re-pass this gate (see that module's "Retry and the breaker gate").
 //!
+//! # Operation Classes
+//!
+//! The wrapper keeps one breaker per [`OperationClass`] in
+//! [`CircuitBreakers`], so a failing class fails fast on its own: polls
+//! timing out against an overloaded partition do not reject sends, and a
+//! stuck admin call does not take the data path down with it. Each class
+//! has its own failure threshold; the success threshold and open duration
+//! are shared. Metrics carry the class as the `class` label.
+//!
 //! # Usage
 //!
 //! ```rust,ignore
@@ -81,7 +90,8 @@
 //! }
 //! ```
 
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::time::Duration;
 
 use tokio::sync::RwLock;
@@ -90,6 +100,8 @@ use tokio::sync::RwLock;
 use tokio::time::Instant;
 use tracing::{debug, info, warn};
 
+use super::events::{ConnectionEvent, ConnectionObservers};
+
 /// Circuit breaker state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CircuitState {
@@ -185,6 +197,13 @@ pub struct CircuitBreaker {
     times_opened: AtomicU32,
     /// Total number of requests rejected due to open circuit (for metrics).
     requests_rejected: AtomicU64,
+    /// Mirror of `state == Closed`, readable without the lock (see
+    /// [`Self::is_closed`]).
+    closed: AtomicBool,
+    /// `class` label of the breaker's metrics (see [`Self::with_class`]).
+    class: &'static str,
+    /// Called on every transition from Closed to Open (see [`Self::on_open`]).
+    on_open: Option<Box<dyn Fn() + Send + Sync>>,
 }
 
 impl CircuitBreaker {
@@ -195,9 +214,28 @@ impl CircuitBreaker {
             state: RwLock::new(CircuitBreakerState::new()),
             times_opened: AtomicU32::new(0),
             requests_rejected: AtomicU64::new(0),
+            closed: AtomicBool::new(true),
+            class: "all",
+            on_open: None,
         }
     }
 
+    /// Label the breaker's metrics with `class`. A breaker without a class
+    /// guards every operation and is labeled `all`.
+    pub fn with_class(mut self, class: OperationClass) -> Self {
+        self.class = class.as_str();
+        self
+    }
+
+    /// Call `hook` every time the circuit opens from Closed. A reopen after
+    /// a failed half-open probe continues the same outage and is not
+    /// reported. The hook runs under the state lock, so it must return
+    /// quickly and must not touch the breaker.
+    pub fn on_open(mut self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_open = Some(Box::new(hook));
+        self
+    }
+
     /// Check if a request should be allowed through the circuit breaker.
     ///
     /// Returns `true` if the request can proceed, `false` if it should be rejected.
@@ -261,7 +299,7 @@ impl CircuitBreaker {
                     );
                     // The transitioning caller takes the first probe token.
                     state.half_open_probes_remaining -= 1;
-                    crate::metrics::set_circuit_breaker_state(1);
+                    crate::metrics::set_circuit_breaker_state(self.class, 1);
                     return true;
                 }
                 self.reject_request("open")
@@ -315,7 +353,7 @@ impl CircuitBreaker {
     /// situations.
     fn reject_request(&self, state_label: &'static str) -> bool {
         self.requests_rejected.fetch_add(1, Ordering::Relaxed);
-        crate::metrics::record_circuit_breaker_rejection(state_label);
+        crate::metrics::record_circuit_breaker_rejection(self.class, state_label);
         false
     }
 
@@ -360,8 +398,12 @@ impl CircuitBreaker {
                     state.state = CircuitState::Closed;
                     state.opened_at = None;
                     state.consecutive_failures = 0;
-                    crate::metrics::set_circuit_breaker_state(0);
-                    info!("Circuit breaker closed after successful recovery");
+                    self.closed.store(true, Ordering::Relaxed);
+                    crate::metrics::set_circuit_breaker_state(self.class, 0);
+                    info!(
+                        class = self.class,
+                        "Circuit breaker closed after successful recovery"
+                    );
                 }
             }
             CircuitState::Open => {
@@ -397,6 +439,7 @@ impl CircuitBreaker {
                 if state.consecutive_failures >= self.config.failure_threshold {
                     self.open_now(&mut state);
                     warn!(
+                        class = self.class,
                         failures = state.consecutive_failures,
                         open_duration = ?self.config.open_duration,
                         "Circuit breaker opened due to consecutive failures"
@@ -407,7 +450,10 @@ impl CircuitBreaker {
                 // Any failure in half-open state reopens the circuit
                 state.consecutive_successes = 0;
                 self.open_now(&mut state);
-                warn!("Circuit breaker reopened after failure in HalfOpen state");
+                warn!(
+                    class = self.class,
+                    "Circuit breaker reopened after failure in HalfOpen state"
+                );
             }
             CircuitState::Open => {
                 // Already open. Deliberately do NOT refresh opened_at:
@@ -422,6 +468,36 @@ impl CircuitBreaker {
         self.state.read().await.state
     }
 
+    /// Whether the circuit is Closed, without taking the state lock.
+    ///
+    /// A snapshot for hot paths that cannot await (e.g. per-request
+    /// middleware); it may briefly lag a transition in progress. Open and
+    /// HalfOpen both read as not closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// How long a rejected caller should wait before the breaker could admit
+    /// it again.
+    ///
+    /// `None` when Closed. When Open, the remainder of the open window; in
+    /// HalfOpen, the remainder of the probe re-grant window (zero while
+    /// tokens remain). Used as the `retry_after_ms` hint on 503 responses.
+    pub async fn retry_after(&self) -> Option<Duration> {
+        let state = self.state.read().await;
+        let since = match state.state {
+            CircuitState::Closed => return None,
+            CircuitState::Open => state.opened_at,
+            CircuitState::HalfOpen if state.half_open_probes_remaining > 0 => {
+                return Some(Duration::ZERO);
+            }
+            CircuitState::HalfOpen => state.half_open_granted_at,
+        };
+        Some(since.map_or(Duration::ZERO, |at| {
+            self.config.open_duration.saturating_sub(at.elapsed())
+        }))
+    }
+
     /// Get the number of times the circuit has been opened.
     pub fn times_opened(&self) -> u32 {
         self.times_opened.load(Ordering::Relaxed)
@@ -443,8 +519,9 @@ impl CircuitBreaker {
         // entry, but stale values should not outlive a manual reset.
         state.half_open_probes_remaining = 0;
         state.half_open_granted_at = None;
-        crate::metrics::set_circuit_breaker_state(0);
-        info!("Circuit breaker forcibly closed");
+        self.closed.store(true, Ordering::Relaxed);
+        crate::metrics::set_circuit_breaker_state(self.class, 0);
+        info!(class = self.class, "Circuit breaker forcibly closed");
     }
 
     /// Force the circuit to open (for testing or manual intervention).
@@ -455,7 +532,7 @@ impl CircuitBreaker {
         let mut state = self.state.write().await;
         if state.state != CircuitState::Open {
             self.open_now(&mut state);
-            warn!("Circuit breaker forcibly opened");
+            warn!(class = self.class, "Circuit breaker forcibly opened");
         }
     }
 
@@ -463,11 +540,16 @@ impl CircuitBreaker {
     /// in lockstep. Shared by the threshold, half-open-failure, and forced
     /// open transitions so the gauge cannot drift from the atomics.
     fn open_now(&self, state: &mut CircuitBreakerState) {
+        let was_closed = state.state == CircuitState::Closed;
         state.state = CircuitState::Open;
         state.opened_at = Some(Instant::now());
         self.times_opened.fetch_add(1, Ordering::Relaxed);
-        crate::metrics::record_circuit_breaker_open();
-        crate::metrics::set_circuit_breaker_state(2);
+        self.closed.store(false, Ordering::Relaxed);
+        crate::metrics::record_circuit_breaker_open(self.class);
+        crate::metrics::set_circuit_breaker_state(self.class, 2);
+        if was_closed && let Some(hook) = &self.on_open {
+            hook();
+        }
     }
 }
 
@@ -477,6 +559,121 @@ impl Default for CircuitBreaker {
     }
 }
 
+/// Class of an Iggy operation, each guarded by its own breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationClass {
+    /// Sending messages.
+    Send,
+    /// Polling messages and reading or changing consumer offsets.
+    Poll,
+    /// Everything else: streams, topics, users, stats and pings.
+    Admin,
+}
+
+impl OperationClass {
+    /// All classes, in the order they are reported.
+    pub const ALL: [OperationClass; 3] = [Self::Send, Self::Poll, Self::Admin];
+
+    /// Class of an operation by its `iggy_operation_duration_seconds`
+    /// `operation` label.
+    pub fn of(operation: &str) -> Self {
+        match operation {
+            "send" | "send_batch" | "send_raw" => Self::Send,
+            "poll" | "get_offset" | "store_offset" | "delete_offset" => Self::Poll,
+            _ => Self::Admin,
+        }
+    }
+
+    /// Lowercase name, used as the metrics label and in `/health`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Send => "send",
+            Self::Poll => "poll",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for OperationClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One circuit breaker per [`OperationClass`].
+pub struct CircuitBreakers {
+    send: CircuitBreaker,
+    poll: CircuitBreaker,
+    admin: CircuitBreaker,
+}
+
+impl CircuitBreakers {
+    /// Create the breakers from each class's configuration.
+    pub fn new(
+        send: CircuitBreakerConfig,
+        poll: CircuitBreakerConfig,
+        admin: CircuitBreakerConfig,
+    ) -> Self {
+        Self {
+            send: CircuitBreaker::new(send).with_class(OperationClass::Send),
+            poll: CircuitBreaker::new(poll).with_class(OperationClass::Poll),
+            admin: CircuitBreaker::new(admin).with_class(OperationClass::Admin),
+        }
+    }
+
+    /// Report every breaker opening to `observers`.
+    pub fn with_observers(self, observers: &Arc<ConnectionObservers>) -> Self {
+        let report = |class| {
+            let observers = Arc::clone(observers);
+            move || observers.notify(ConnectionEvent::CircuitOpened(class))
+        };
+        Self {
+            send: self.send.on_open(report(OperationClass::Send)),
+            poll: self.poll.on_open(report(OperationClass::Poll)),
+            admin: self.admin.on_open(report(OperationClass::Admin)),
+        }
+    }
+
+    /// The breaker guarding `class`.
+    pub fn get(&self, class: OperationClass) -> &CircuitBreaker {
+        match class {
+            OperationClass::Send => &self.send,
+            OperationClass::Poll => &self.poll,
+            OperationClass::Admin => &self.admin,
+        }
+    }
+
+    /// Each class with its breaker, in [`OperationClass::ALL`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (OperationClass, &CircuitBreaker)> {
+        OperationClass::ALL
+            .into_iter()
+            .map(move |class| (class, self.get(class)))
+    }
+
+    /// Whether every breaker is Closed, without taking their locks (see
+    /// [`CircuitBreaker::is_closed`]).
+    pub fn all_closed(&self) -> bool {
+        self.iter().all(|(_, breaker)| breaker.is_closed())
+    }
+
+    /// Force every breaker to close.
+    pub async fn force_close(&self) {
+        for (_, breaker) in self.iter() {
+            breaker.force_close().await;
+        }
+    }
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        Self::new(
+            CircuitBreakerConfig::default(),
+            CircuitBreakerConfig::default(),
+            CircuitBreakerConfig::default(),
+        )
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -667,6 +864,23 @@ mod tests {
         assert_eq!(cb.state().await, CircuitState::HalfOpen);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_tracks_open_and_half_open_windows() {
+        let config = CircuitBreakerConfig::new(1, 1, Duration::from_secs(30));
+        let cb = CircuitBreaker::new(config);
+        assert_eq!(cb.retry_after().await, None);
+
+        cb.record_failure().await;
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(cb.retry_after().await, Some(Duration::from_secs(20)));
+
+        // Half-open with the only probe token taken: wait out the re-grant.
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert!(cb.allow_request().await);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(cb.retry_after().await, Some(Duration::from_secs(25)));
+    }
+
     #[tokio::test(start_paused = true)]
     async fn test_half_open_reentry_grants_fresh_probe_tokens() {
         let config = CircuitBreakerConfig::new(1, 1, Duration::from_secs(30));
@@ -780,4 +994,80 @@ mod tests {
             "repeat force_open must not inflate the counter"
         );
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_is_closed_tracks_every_transition() {
+        let config = CircuitBreakerConfig::new(1, 1, Duration::from_secs(30));
+        let cb = CircuitBreaker::new(config);
+        assert!(cb.is_closed());
+
+        cb.record_failure().await;
+        assert!(!cb.is_closed(), "open");
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(cb.allow_request().await);
+        assert_eq!(cb.state().await, CircuitState::HalfOpen);
+        assert!(!cb.is_closed(), "half-open");
+
+        cb.record_success().await;
+        assert!(cb.is_closed(), "closed after recovery");
+
+        cb.force_open().await;
+        assert!(!cb.is_closed());
+        cb.force_close().await;
+        assert!(cb.is_closed());
+    }
+
+    #[test]
+    fn test_operation_class_of() {
+        assert_eq!(OperationClass::of("send_batch"), OperationClass::Send);
+        assert_eq!(OperationClass::of("store_offset"), OperationClass::Poll);
+        assert_eq!(OperationClass::of("create_topic"), OperationClass::Admin);
+        assert_eq!(OperationClass::of("ping"), OperationClass::Admin);
+    }
+
+    #[tokio::test]
+    async fn test_breakers_per_class_are_independent() {
+        let breakers = CircuitBreakers::new(
+            CircuitBreakerConfig::new(1, 1, Duration::from_secs(30)),
+            CircuitBreakerConfig::new(5, 1, Duration::from_secs(30)),
+            CircuitBreakerConfig::default(),
+        );
+
+        breakers.get(OperationClass::Poll).record_failure().await;
+        assert!(
+            breakers.get(OperationClass::Poll).is_closed(),
+            "threshold 5"
+        );
+        breakers.get(OperationClass::Send).record_failure().await;
+        assert!(
+            !breakers.get(OperationClass::Send).is_closed(),
+            "threshold 1"
+        );
+        assert!(breakers.get(OperationClass::Poll).allow_request().await);
+        assert!(!breakers.all_closed());
+
+        breakers.force_close().await;
+        assert!(breakers.all_closed());
+    }
+    #[tokio::test]
+    async fn test_open_hook_reports_openings_from_closed() {
+        let opened = Arc::new(AtomicU32::new(0));
+        let cb = CircuitBreaker::new(CircuitBreakerConfig::new(1, 1, Duration::from_secs(30)))
+            .on_open({
+                let opened = Arc::clone(&opened);
+                move || {
+                    opened.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+
+        cb.record_failure().await;
+        // Already open: no second transition
+        cb.record_failure().await;
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+
+        cb.force_close().await;
+        cb.force_open().await;
+        assert_eq!(opened.load(Ordering::Relaxed), 2);
+    }
 }
@@ -0,0 +1,205 @@
+//! Payload-level compression for batch sends.
+//!
+//! Batches of similar JSON events compress well, and Iggy stores payloads
+//! as-is. With `BATCH_COMPRESSION=gzip|zstd`, each batch message whose JSON
+//! payload is at least `COMPRESSION_THRESHOLD_BYTES` is compressed before
+//! sending and tagged with a `content-encoding` user header. Polls check
+//! that header and decompress transparently, so consumers of this API see
+//! the same events either way.
+//!
+//! A payload that does not shrink is sent uncompressed (and untagged), so
+//! small or incompressible events never pay the decode cost.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use bytes::Bytes;
+use iggy::prelude::{HeaderKey, HeaderValue, IggyMessage};
+
+use super::helpers::event_payload_message;
+use crate::error::{AppError, AppResult};
+use crate::models::Event;
+
+/// User header naming the payload encoding.
+pub const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+
+/// zstd level: the library default, a good ratio/speed balance for JSON.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression applied to batch payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadCompression {
+    /// Send payloads uncompressed (default)
+    #[default]
+    None,
+    /// gzip (widely supported, slower)
+    Gzip,
+    /// zstd (better ratio and speed)
+    Zstd,
+}
+
+impl PayloadCompression {
+    /// Value of the `content-encoding` header, or `None` when uncompressed.
+    pub fn encoding(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Zstd => Some("zstd"),
+        }
+    }
+}
+
+impl FromStr for PayloadCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "Unknown compression '{s}' (expected none, gzip, or zstd)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for PayloadCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.encoding().unwrap_or("none"))
+    }
+}
+
+/// Compress `payload` if worthwhile.
+///
+/// Returns `None` (send as-is) when compression is off, the payload is
+/// below `threshold` bytes, or the compressed form is not smaller.
+///
+/// # Errors
+///
+/// Returns `AppError::SendError` if the encoder fails.
+pub fn compress_payload(
+    compression: PayloadCompression,
+    threshold: usize,
+    payload: &[u8],
+) -> AppResult<Option<Vec<u8>>> {
+    if payload.len() < threshold {
+        return Ok(None);
+    }
+    let compressed = match compression {
+        PayloadCompression::None => return Ok(None),
+        PayloadCompression::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(payload)
+                .and_then(|()| encoder.finish())
+                .map_err(|e| AppError::SendError(format!("gzip compression failed: {e}")))?
+        }
+        PayloadCompression::Zstd => zstd::encode_all(payload, ZSTD_LEVEL)
+            .map_err(|e| AppError::SendError(format!("zstd compression failed: {e}")))?,
+    };
+    Ok((compressed.len() < payload.len()).then_some(compressed))
+}
+
+/// Build the Iggy message for one batch event, compressing its JSON payload
+/// per [`compress_payload`] and tagging compressed payloads with the
+/// `content-encoding` header (alongside the event's own headers, see
+/// [`event_payload_message`]).
+pub fn batch_message(
+    event: &Event,
+    compression: PayloadCompression,
+    threshold: usize,
+) -> AppResult<IggyMessage> {
+    let payload = serde_json::to_vec(event)?;
+    let (Some(compressed), Some(encoding)) = (
+        compress_payload(compression, threshold, &payload)?,
+        compression.encoding(),
+    ) else {
+        return event_payload_message(event, Bytes::from(payload), BTreeMap::new());
+    };
+
+    let header = |e: iggy::prelude::IggyError| AppError::SendError(e.to_string());
+    let headers = BTreeMap::from([(
+        HeaderKey::from_str(CONTENT_ENCODING_HEADER).map_err(header)?,
+        HeaderValue::from_str(encoding).map_err(header)?,
+    )]);
+    event_payload_message(event, Bytes::from(compressed), headers)
+}
+
+/// Decompress a payload tagged with `encoding`.
+///
+/// # Errors
+///
+/// Returns `AppError::PollError` for an unknown encoding or corrupt data.
+pub fn decompress_payload(encoding: &str, payload: &[u8]) -> AppResult<Vec<u8>> {
+    match encoding {
+        "gzip" => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(payload)
+                .read_to_end(&mut decoded)
+                .map_err(|e| AppError::PollError(format!("gzip decompression failed: {e}")))?;
+            Ok(decoded)
+        }
+        "zstd" => zstd::decode_all(payload)
+            .map_err(|e| AppError::PollError(format!("zstd decompression failed: {e}"))),
+        other => Err(AppError::PollError(format!(
+            "Unsupported payload encoding '{other}'"
+        ))),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn verbose_payload() -> Vec<u8> {
+        let event = r#"{"event_type":"order.created","payload":{"status":"pending"}}"#;
+        event.repeat(50).into_bytes()
+    }
+
+    #[test]
+    fn test_compression_round_trips() {
+        let payload = verbose_payload();
+        for compression in [PayloadCompression::Gzip, PayloadCompression::Zstd] {
+            let compressed = compress_payload(compression, 0, &payload)
+                .unwrap()
+                .expect("repetitive JSON should shrink");
+            assert!(compressed.len() < payload.len());
+
+            let encoding = compression.encoding().unwrap();
+            assert_eq!(decompress_payload(encoding, &compressed).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_small_or_disabled_payloads_are_sent_as_is() {
+        let payload = verbose_payload();
+        assert!(
+            compress_payload(PayloadCompression::None, 0, &payload)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            compress_payload(PayloadCompression::Zstd, payload.len() + 1, &payload)
+                .unwrap()
+                .is_none()
+        );
+        // Incompressible: a tiny payload grows under gzip framing.
+        assert!(
+            compress_payload(PayloadCompression::Gzip, 0, b"{}")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_unknown_encoding_is_rejected() {
+        assert!(matches!(
+            decompress_payload("br", b"data"),
+            Err(AppError::PollError(_))
+        ));
+    }
+}
@@ -37,11 +37,13 @@ impl ConnectionState {
         }
     }
 
-    pub fn set_connected(&self, connected: bool) {
-        self.connected.store(connected, Ordering::SeqCst);
+    /// Set the connected flag, returning its previous value.
+    pub fn set_connected(&self, connected: bool) -> bool {
+        let was_connected = self.connected.swap(connected, Ordering::SeqCst);
         if connected {
             self.reconnect_attempts.store(0, Ordering::SeqCst);
         }
+        was_connected
     }
 
     pub fn is_connected(&self) -> bool {
@@ -96,8 +98,10 @@ impl ConnectionState {
         }
     }
 
-    /// Get the current reconnect attempts count (for testing).
-    #[cfg(test)]
+    /// Get the current consecutive reconnect attempts count.
+    ///
+    /// Feeds the backoff estimate attached to connection errors as a
+    /// client retry hint.
     pub fn attempts(&self) -> u32 {
         self.reconnect_attempts.load(Ordering::SeqCst)
     }
@@ -128,10 +132,10 @@ mod tests {
     fn test_connection_state_set_connected() {
         let state = ConnectionState::new();
 
-        state.set_connected(true);
+        assert!(!state.set_connected(true), "returns the previous value");
         assert!(state.is_connected());
 
-        state.set_connected(false);
+        assert!(state.set_connected(false));
         assert!(!state.is_connected());
     }
 
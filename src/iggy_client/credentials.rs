@@ -0,0 +1,160 @@
+//! Credential sources for (re-)authenticating with the Iggy server.
+//!
+//! Connection-string clients sign in automatically with the credentials
+//! embedded in the string. That is the default here too, but it cannot
+//! follow a rotated password: the string is fixed at startup. A
+//! [`CredentialSource`] is consulted on every (re)connect instead, so a
+//! password mounted from a secret file is re-read each time the wrapper
+//! logs in.
+//!
+//! # Sources
+//!
+//! - [`ConnectionStringCredentials`] - rely on the SDK's auto sign-in (default)
+//! - [`StaticCredentials`] - fixed `IGGY_USERNAME` / `IGGY_PASSWORD`
+//! - [`PasswordFileCredentials`] - `IGGY_USERNAME` + `IGGY_PASSWORD_FILE`,
+//!   re-read on every login
+
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+
+/// Username and password for an explicit `login_user`.
+#[derive(Clone)]
+pub struct IggyCredentials {
+    /// Iggy username
+    pub username: String,
+    /// Iggy password
+    pub password: String,
+}
+
+// Manual Debug so the password never reaches logs or spans.
+impl std::fmt::Debug for IggyCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IggyCredentials")
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Supplies credentials for each (re)connect.
+pub trait CredentialSource: Send + Sync {
+    /// Credentials for the next login, or `None` to rely on the connection
+    /// string's auto sign-in.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` if the credentials cannot be loaded
+    /// (e.g. an unreadable password file).
+    fn credentials(&self) -> AppResult<Option<IggyCredentials>>;
+}
+
+/// Credentials embedded in the connection string (SDK auto sign-in).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectionStringCredentials;
+
+impl CredentialSource for ConnectionStringCredentials {
+    fn credentials(&self) -> AppResult<Option<IggyCredentials>> {
+        Ok(None)
+    }
+}
+
+/// Fixed credentials.
+#[derive(Debug, Clone)]
+pub struct StaticCredentials(pub IggyCredentials);
+
+impl CredentialSource for StaticCredentials {
+    fn credentials(&self) -> AppResult<Option<IggyCredentials>> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+/// Username plus a password file that is re-read on every login.
+///
+/// Trailing whitespace (the newline most secret mounts add) is trimmed.
+#[derive(Debug, Clone)]
+pub struct PasswordFileCredentials {
+    username: String,
+    path: String,
+}
+
+impl PasswordFileCredentials {
+    /// Create a source reading the password for `username` from `path`.
+    pub fn new(username: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            path: path.into(),
+        }
+    }
+}
+
+impl CredentialSource for PasswordFileCredentials {
+    fn credentials(&self) -> AppResult<Option<IggyCredentials>> {
+        let password = std::fs::read_to_string(&self.path).map_err(|e| {
+            AppError::ConfigError(format!(
+                "IGGY_PASSWORD_FILE '{}' could not be read: {e}",
+                self.path
+            ))
+        })?;
+        Ok(Some(IggyCredentials {
+            username: self.username.clone(),
+            password: password.trim_end().to_string(),
+        }))
+    }
+}
+
+/// Build the credential source selected by the `IGGY_*` credential settings.
+///
+/// `Config::validate` guarantees a username is paired with exactly one of
+/// `IGGY_PASSWORD` / `IGGY_PASSWORD_FILE`.
+pub fn credential_source_from_config(config: &Config) -> Arc<dyn CredentialSource> {
+    match (
+        &config.iggy_username,
+        &config.iggy_password,
+        &config.iggy_password_file,
+    ) {
+        (Some(username), _, Some(path)) => {
+            Arc::new(PasswordFileCredentials::new(username.clone(), path.clone()))
+        }
+        (Some(username), Some(password), None) => Arc::new(StaticCredentials(IggyCredentials {
+            username: username.clone(),
+            password: password.clone(),
+        })),
+        _ => Arc::new(ConnectionStringCredentials),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_uses_connection_string_credentials() {
+        let source = credential_source_from_config(&Config::default());
+        assert!(source.credentials().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_password_file_is_reread_on_every_call() {
+        let path =
+            std::env::temp_dir().join(format!("iggy-sample-password-{}.txt", std::process::id()));
+        let source = PasswordFileCredentials::new("svc", path.display().to_string());
+
+        std::fs::write(&path, "first\n").unwrap();
+        let first = source.credentials().unwrap().unwrap();
+        std::fs::write(&path, "rotated\n").unwrap();
+        let rotated = source.credentials().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(first.username, "svc");
+        assert_eq!(first.password, "first");
+        assert_eq!(rotated.password, "rotated");
+        assert!(!format!("{:?}", rotated).contains("rotated"));
+        assert!(matches!(
+            source.credentials(),
+            Err(AppError::ConfigError(_))
+        ));
+    }
+}
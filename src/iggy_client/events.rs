@@ -0,0 +1,127 @@
+//! Connection event hooks.
+//!
+//! A [`ConnectionObserver`] registered with
+//! [`IggyClientWrapper::observe`](super::IggyClientWrapper::observe) is told
+//! when the wrapper loses or regains its connection to Iggy, and when one of
+//! its circuit breakers opens. Transitions are reported once: a health probe
+//! confirming a connection that was already up is not an event.
+//!
+//! Observers are called synchronously from the code making the transition,
+//! sometimes while it holds a lock (a breaker opening holds the breaker's).
+//! They must return quickly and must not call back into the wrapper;
+//! anything slow (like the webhook notifier) belongs on a queue.
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::circuit_breaker::OperationClass;
+
+/// A change in the wrapper's connection to Iggy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The connection was established or re-established.
+    Connected,
+    /// The connection was lost (failed health probe or reconnect started).
+    Disconnected,
+    /// The circuit breaker of an operation class opened.
+    CircuitOpened(OperationClass),
+}
+
+impl ConnectionEvent {
+    /// Lowercase name, used in notifications.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::Disconnected => "disconnected",
+            Self::CircuitOpened(_) => "circuit_opened",
+        }
+    }
+}
+
+/// Callbacks for connection events; every method defaults to a no-op.
+pub trait ConnectionObserver: Send + Sync {
+    /// The connection was established or re-established.
+    fn on_connected(&self) {}
+
+    /// The connection was lost.
+    fn on_disconnected(&self) {}
+
+    /// The circuit breaker of `class` opened.
+    fn on_circuit_opened(&self, _class: OperationClass) {}
+}
+
+/// The observers registered with a wrapper, shared by its clones and its
+/// circuit breakers.
+#[derive(Default)]
+pub struct ConnectionObservers {
+    observers: RwLock<Vec<Arc<dyn ConnectionObserver>>>,
+}
+
+impl ConnectionObservers {
+    /// Register `observer` for every later event.
+    pub fn add(&self, observer: Arc<dyn ConnectionObserver>) {
+        self.observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(observer);
+    }
+
+    /// Report `event` to every observer, in registration order.
+    pub fn notify(&self, event: ConnectionEvent) {
+        let observers = self
+            .observers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        for observer in observers.iter() {
+            match event {
+                ConnectionEvent::Connected => observer.on_connected(),
+                ConnectionEvent::Disconnected => observer.on_disconnected(),
+                ConnectionEvent::CircuitOpened(class) => observer.on_circuit_opened(class),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<ConnectionEvent>>);
+
+    impl ConnectionObserver for Recorder {
+        fn on_connected(&self) {
+            self.0.lock().unwrap().push(ConnectionEvent::Connected);
+        }
+
+        fn on_circuit_opened(&self, class: OperationClass) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(ConnectionEvent::CircuitOpened(class));
+        }
+    }
+
+    #[test]
+    fn test_notify_dispatches_to_each_observer() {
+        let observers = ConnectionObservers::default();
+        let first = Arc::new(Recorder::default());
+        let second = Arc::new(Recorder::default());
+        observers.add(first.clone());
+        observers.add(second.clone());
+
+        observers.notify(ConnectionEvent::Connected);
+        // Not overridden by the recorder: the default no-op
+        observers.notify(ConnectionEvent::Disconnected);
+        observers.notify(ConnectionEvent::CircuitOpened(OperationClass::Poll));
+
+        let expected = vec![
+            ConnectionEvent::Connected,
+            ConnectionEvent::CircuitOpened(OperationClass::Poll),
+        ];
+        assert_eq!(*first.0.lock().unwrap(), expected);
+        assert_eq!(*second.0.lock().unwrap(), expected);
+    }
+}
@@ -0,0 +1,89 @@
+//! Failures injected into a live wrapper's operations, for tests
+//! (`test-util` feature).
+//!
+//! A [`FailurePolicy`] installed with
+//! [`IggyClientWrapper::with_failure_policy`](super::IggyClientWrapper::with_failure_policy)
+//! runs before every attempt `with_reconnect` makes, inside its timeout and
+//! circuit breaker. It can make the Nth attempt fail with a chosen error
+//! (a connection error goes through reconnect and retry like a real one)
+//! or hang past the operation timeout, so the retry, timeout and breaker
+//! paths can be tested against a real server without stopping it:
+//!
+//! ```rust,ignore
+//! let policy = Arc::new(FailurePolicy::new());
+//! let client = IggyClientWrapper::new(config).await?.with_failure_policy(policy.clone());
+//!
+//! policy.fail_nth(1, AppError::Disconnected("injected".into()));
+//! client.send_event_default(&event, None).await?; // reconnects, retry succeeds
+//! assert_eq!(policy.operations(), 2);
+//! ```
+//!
+//! Attempts are numbered from 1 across all operations of the wrapper and
+//! its `with_timeout` views; a retry is an attempt of its own.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::error::{AppError, AppResult};
+
+/// What happens to one attempt.
+#[derive(Debug)]
+enum Injection {
+    Fail(AppError),
+    Hang,
+}
+
+/// Failures to inject, by attempt number.
+#[derive(Debug, Default)]
+pub struct FailurePolicy {
+    /// Attempts seen so far
+    operations: AtomicU64,
+    /// Pending injections by 1-indexed attempt number
+    injections: Mutex<HashMap<u64, Injection>>,
+}
+
+impl FailurePolicy {
+    /// Create a policy that injects nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make attempt `n` (1-indexed) fail with `error`.
+    pub fn fail_nth(&self, n: u64, error: AppError) {
+        self.inject(n, Injection::Fail(error));
+    }
+
+    /// Make attempt `n` (1-indexed) never complete, so it fails with
+    /// `AppError::OperationTimeout` at the operation's deadline.
+    pub fn hang_nth(&self, n: u64) {
+        self.inject(n, Injection::Hang);
+    }
+
+    /// Attempts seen so far.
+    pub fn operations(&self) -> u64 {
+        self.operations.load(Ordering::SeqCst)
+    }
+
+    fn inject(&self, n: u64, injection: Injection) {
+        self.injections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(n, injection);
+    }
+
+    /// Count an attempt and apply its injection, if any.
+    pub(super) async fn before_attempt(&self) -> AppResult<()> {
+        let n = self.operations.fetch_add(1, Ordering::SeqCst) + 1;
+        let injection = self
+            .injections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&n);
+        match injection {
+            None => Ok(()),
+            Some(Injection::Fail(error)) => Err(error),
+            Some(Injection::Hang) => std::future::pending().await,
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Weighted fair queuing of Iggy operations across tenants.
+//!
+//! Every request shares the same Iggy connection, so one tenant issuing
+//! huge batches can keep it busy while everyone else waits behind it. With
+//! `FAIR_QUEUE_MAX_IN_FLIGHT` set, every operation going through
+//! `with_reconnect` first takes one of that many slots. Waiting operations
+//! queue per tenant, and each freed slot goes to the tenant that has been
+//! served least relative to its weight (`FAIR_QUEUE_WEIGHTS`, default 1):
+//! start-time fair queuing, where each grant advances the tenant's virtual
+//! time by `1 / weight`. A tenant becoming active again starts at the
+//! current virtual time, so idling does not bank credit for a later burst.
+//! `FAIR_QUEUE_TENANT_MAX_IN_FLIGHT` additionally bounds the slots a single
+//! tenant may hold, whatever its weight.
+//!
+//! An operation's deadline covers its wait for a slot: it waits at most
+//! its deadline and then fails with `timeout`; once granted, it gets what
+//! is left of the deadline. Reconnects and retries run within the slot.
+//!
+//! # Tenants
+//!
+//! The tenant of an operation is the one the request runs under (see
+//! [`with_tenant`] and `middleware::tenant`). Operations outside a request,
+//! such as background jobs or a coalesced batch flush, belong to
+//! [`INTERNAL_TENANT`].
+//!
+//! # Starvation Metrics
+//!
+//! Waits are recorded in `iggy_fair_queue_wait_seconds`, and those longer
+//! than `FAIR_QUEUE_STARVATION_MS` (or ending in a timeout) are counted in
+//! `iggy_fair_queue_starved_total`. Both are labelled by tenant; tenants
+//! without a configured weight share the `other` label, keeping the label
+//! count bounded.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+
+use crate::error::{AppError, AppResult};
+
+/// Tenant of operations issued outside a request.
+pub const INTERNAL_TENANT: &str = "internal";
+
+/// Metrics label of tenants without a configured weight.
+const OTHER_TENANT_LABEL: &str = "other";
+
+tokio::task_local! {
+    /// Tenant of the request handled by the current task.
+    static CURRENT_TENANT: Arc<str>;
+}
+
+/// Run `future` with its Iggy operations queued as `tenant`'s.
+pub async fn with_tenant<F: Future>(tenant: impl Into<Arc<str>>, future: F) -> F::Output {
+    CURRENT_TENANT.scope(tenant.into(), future).await
+}
+
+/// Tenant of the current task, [`INTERNAL_TENANT`] outside [`with_tenant`].
+pub(crate) fn current_tenant() -> Arc<str> {
+    CURRENT_TENANT
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| Arc::from(INTERNAL_TENANT))
+}
+
+/// Queue and slots of one tenant.
+#[derive(Default)]
+struct Tenant {
+    in_flight: usize,
+    /// Service received, in units of `1 / weight` per grant
+    virtual_time: f64,
+    waiters: VecDeque<oneshot::Sender<FairPermit>>,
+}
+
+impl Tenant {
+    fn is_idle(&self) -> bool {
+        self.in_flight == 0 && self.waiters.is_empty()
+    }
+}
+
+/// A tenant's place in an index: by virtual time, then by name.
+#[derive(Debug, Clone)]
+struct Position {
+    virtual_time: f64,
+    tenant: Arc<str>,
+}
+
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Position {}
+
+impl PartialOrd for Position {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Position {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.virtual_time
+            .total_cmp(&other.virtual_time)
+            .then_with(|| self.tenant.cmp(&other.tenant))
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    /// Virtual time of the latest grant; a tenant becoming active starts here
+    virtual_time: f64,
+    tenants: HashMap<Arc<str>, Tenant>,
+    /// Tenants with a waiter and a slot of their own to spare, furthest
+    /// behind first
+    ready: BTreeSet<Position>,
+    /// Idle tenants, forgotten once the system virtual time passes theirs
+    idle: BTreeSet<Position>,
+}
+
+impl QueueState {
+    /// Take `tenant` out of the indexes, before changing it.
+    fn unindex(&mut self, tenant: &Arc<str>) {
+        if let Some(entry) = self.tenants.get(tenant) {
+            let position = Position {
+                virtual_time: entry.virtual_time,
+                tenant: tenant.clone(),
+            };
+            self.ready.remove(&position);
+            self.idle.remove(&position);
+        }
+    }
+
+    /// Put `tenant` back into the index its state calls for, after a
+    /// change; `tenant_max_in_flight` as in [`FairQueue::new`].
+    fn index(&mut self, tenant: &Arc<str>, tenant_max_in_flight: usize) {
+        let Some(entry) = self.tenants.get(tenant) else {
+            return;
+        };
+        let position = Position {
+            virtual_time: entry.virtual_time,
+            tenant: tenant.clone(),
+        };
+        if entry.is_idle() {
+            self.idle.insert(position);
+        } else if !entry.waiters.is_empty()
+            && (tenant_max_in_flight == 0 || entry.in_flight < tenant_max_in_flight)
+        {
+            self.ready.insert(position);
+        }
+    }
+}
+
+/// Weighted fair scheduler of Iggy operations, keyed by tenant.
+pub struct FairQueue {
+    /// Slots across all tenants (0 = disabled)
+    max_in_flight: usize,
+    /// Slots of one tenant (0 = bounded by `max_in_flight` only)
+    tenant_max_in_flight: usize,
+    weights: HashMap<String, u32>,
+    starvation_threshold: Duration,
+    state: Mutex<QueueState>,
+}
+
+/// One operation slot, released on drop.
+pub struct FairPermit {
+    /// `None` once released, or for a grant its waiter never received
+    queue: Option<Arc<FairQueue>>,
+    tenant: Arc<str>,
+}
+
+impl Drop for FairPermit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release(&self.tenant);
+        }
+    }
+}
+
+impl FairQueue {
+    /// Create a queue of `max_in_flight` slots (0 disables it), at most
+    /// `tenant_max_in_flight` per tenant (0 = no per-tenant bound), shared
+    /// out by `weights` (tenants not listed weigh 1).
+    pub fn new(
+        max_in_flight: usize,
+        tenant_max_in_flight: usize,
+        weights: HashMap<String, u32>,
+        starvation_threshold: Duration,
+    ) -> Self {
+        Self {
+            max_in_flight,
+            tenant_max_in_flight,
+            weights,
+            starvation_threshold,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    /// Check if operations are queued at all.
+    pub fn is_enabled(&self) -> bool {
+        self.max_in_flight > 0
+    }
+
+    /// Wait up to `max_wait` for a slot for `tenant`; `None` when disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::OperationTimeout` if no slot was granted in time.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        tenant: Arc<str>,
+        max_wait: Duration,
+    ) -> AppResult<Option<FairPermit>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut state = self.lock();
+            let system_time = state.virtual_time;
+            state.unindex(&tenant);
+            let entry = state.tenants.entry(tenant.clone()).or_default();
+            if entry.is_idle() {
+                entry.virtual_time = entry.virtual_time.max(system_time);
+            }
+            entry.waiters.push_back(sender);
+            state.index(&tenant, self.tenant_max_in_flight);
+            self.dispatch(&mut state);
+        }
+
+        let label = self.label(&tenant);
+        let result = tokio::time::timeout(max_wait, receiver).await;
+        let waited = started.elapsed();
+        crate::metrics::record_fair_queue_wait(label, waited.as_secs_f64());
+        match result {
+            Ok(Ok(permit)) => {
+                if waited > self.starvation_threshold {
+                    crate::metrics::record_fair_queue_starved(label);
+                }
+                Ok(Some(permit))
+            }
+            // The queue outlives its waiters; a dropped sender means the
+            // grant failed, which is treated like running out of time
+            Ok(Err(_)) | Err(_) => {
+                crate::metrics::record_fair_queue_starved(label);
+                Err(AppError::OperationTimeout(format!(
+                    "No Iggy operation slot for tenant '{tenant}' within {max_wait:?}"
+                )))
+            }
+        }
+    }
+
+    /// Return `tenant`'s slot and hand it on.
+    fn release(self: &Arc<Self>, tenant: &Arc<str>) {
+        let mut state = self.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        state.unindex(tenant);
+        if let Some(entry) = state.tenants.get_mut(tenant) {
+            entry.in_flight = entry.in_flight.saturating_sub(1);
+        }
+        state.index(tenant, self.tenant_max_in_flight);
+        self.dispatch(&mut state);
+
+        // Forget idle tenants once they carry no credit or debt worth
+        // keeping, bounding the map under many short-lived tenants
+        let system_time = state.virtual_time;
+        while state
+            .idle
+            .first()
+            .is_some_and(|position| position.virtual_time <= system_time)
+        {
+            if let Some(position) = state.idle.pop_first() {
+                state.tenants.remove(&position.tenant);
+            }
+        }
+    }
+
+    /// Grant free slots to the waiting tenants furthest behind.
+    fn dispatch(self: &Arc<Self>, state: &mut QueueState) {
+        while state.in_flight < self.max_in_flight {
+            let Some(Position { tenant: name, .. }) = state.ready.pop_first() else {
+                break;
+            };
+            let weight = f64::from(self.weight(&name));
+            let Some(entry) = state.tenants.get_mut(&name) else {
+                continue;
+            };
+            let waiter = entry.waiters.pop_front();
+            let Some(waiter) = waiter.filter(|waiter| !waiter.is_closed()) else {
+                // Gave up waiting
+                state.index(&name, self.tenant_max_in_flight);
+                continue;
+            };
+            let start_time = entry.virtual_time;
+            entry.virtual_time += 1.0 / weight;
+            entry.in_flight += 1;
+            state.in_flight += 1;
+            state.virtual_time = state.virtual_time.max(start_time);
+
+            let permit = FairPermit {
+                queue: Some(self.clone()),
+                tenant: name.clone(),
+            };
+            if let Err(mut permit) = waiter.send(permit) {
+                // Gave up in the meantime: take the slot back here, as the
+                // permit's own release would need the lock held right now
+                permit.queue = None;
+                state.in_flight -= 1;
+                if let Some(entry) = state.tenants.get_mut(&permit.tenant) {
+                    entry.in_flight -= 1;
+                }
+            }
+            state.index(&name, self.tenant_max_in_flight);
+        }
+    }
+
+    fn weight(&self, tenant: &str) -> u32 {
+        self.weights.get(tenant).copied().unwrap_or(1).max(1)
+    }
+
+    /// Metrics label of `tenant`.
+    fn label<'a>(&self, tenant: &'a str) -> &'a str {
+        if tenant == INTERNAL_TENANT || self.weights.contains_key(tenant) {
+            tenant
+        } else {
+            OTHER_TENANT_LABEL
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    fn queue(max_in_flight: usize, tenant_max: usize, weights: &[(&str, u32)]) -> Arc<FairQueue> {
+        let weights = weights
+            .iter()
+            .map(|&(tenant, weight)| (tenant.to_string(), weight))
+            .collect();
+        Arc::new(FairQueue::new(
+            max_in_flight,
+            tenant_max,
+            weights,
+            Duration::from_secs(1),
+        ))
+    }
+
+    /// Queue `count` waiters for `tenant`, granted permits being sent to
+    /// `granted` tagged with the tenant.
+    fn spawn_waiters(
+        queue: &Arc<FairQueue>,
+        tenant: &'static str,
+        count: usize,
+        granted: &tokio::sync::mpsc::UnboundedSender<(&'static str, FairPermit)>,
+    ) {
+        for _ in 0..count {
+            let queue = queue.clone();
+            let granted = granted.clone();
+            tokio::spawn(async move {
+                let permit = queue.acquire(Arc::from(tenant), WAIT).await.unwrap();
+                let _ = granted.send((tenant, permit.unwrap()));
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_queue_grants_nothing_to_hold() {
+        let queue = queue(0, 0, &[]);
+        assert!(!queue.is_enabled());
+        assert!(queue.acquire(Arc::from("a"), WAIT).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slots_are_shared_by_weight() {
+        let queue = queue(1, 0, &[("heavy", 3)]);
+        let blocker = queue.acquire(Arc::from("x"), WAIT).await.unwrap();
+
+        let (granted, mut grants) = tokio::sync::mpsc::unbounded_channel();
+        spawn_waiters(&queue, "heavy", 6, &granted);
+        spawn_waiters(&queue, "light", 6, &granted);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Hand the slot on one grant at a time and record who got it
+        drop(blocker);
+        let mut order = Vec::new();
+        for _ in 0..8 {
+            let (tenant, permit) = grants.recv().await.unwrap();
+            order.push(tenant);
+            drop(permit);
+        }
+        let heavy = order.iter().filter(|&&tenant| tenant == "heavy").count();
+        assert_eq!(heavy, 6, "grant order: {order:?}");
+    }
+
+    #[tokio::test]
+    async fn test_tenant_bound_leaves_slots_to_others() {
+        let queue = queue(4, 1, &[]);
+        let first = queue.acquire(Arc::from("a"), WAIT).await.unwrap();
+        assert!(first.is_some());
+
+        // "a" holds its one slot, so its next operation waits
+        let blocked = queue
+            .acquire(Arc::from("a"), Duration::from_millis(50))
+            .await;
+        assert!(matches!(blocked, Err(AppError::OperationTimeout(_))));
+        // ...while other tenants still get the free ones
+        let other = queue.acquire(Arc::from("b"), WAIT).await.unwrap();
+        assert!(other.is_some());
+
+        drop(first);
+        assert!(queue.acquire(Arc::from("a"), WAIT).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waits_do_not_leak_slots() {
+        let queue = queue(1, 0, &[]);
+        let held = queue.acquire(Arc::from("a"), WAIT).await.unwrap();
+        let timed_out = queue
+            .acquire(Arc::from("b"), Duration::from_millis(20))
+            .await;
+        assert!(timed_out.is_err());
+
+        drop(held);
+        assert!(queue.acquire(Arc::from("b"), WAIT).await.unwrap().is_some());
+        assert_eq!(queue.lock().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_tenants_are_forgotten() {
+        let queue = queue(1, 0, &[]);
+        // "steady" keeps the virtual time moving past the one-off tenants
+        for n in 0..100 {
+            for tenant in [format!("once-{n}"), "steady".to_string()] {
+                let permit = queue.acquire(Arc::from(tenant), WAIT).await;
+                drop(permit.unwrap());
+            }
+        }
+        let state = queue.lock();
+        assert!(state.tenants.len() <= 3, "{}", state.tenants.len());
+        assert_eq!(state.idle.len(), state.tenants.len());
+        assert!(state.ready.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_is_task_local() {
+        assert_eq!(&*current_tenant(), INTERNAL_TENANT);
+        let tenant = with_tenant("acme", async { current_tenant() }).await;
+        assert_eq!(&*tenant, "acme");
+    }
+}
@@ -0,0 +1,56 @@
+//! Lock-free view of backend health for the request path.
+
+use std::sync::Arc;
+
+use super::{CircuitBreakers, ConnectionState};
+
+/// Shared, cheaply cloned signal of whether the Iggy backend is degraded.
+///
+/// Obtained from [`super::IggyClientWrapper::health_signal`]. Reads only
+/// atomics, so middleware can consult it on every request without awaiting
+/// a lock.
+#[derive(Clone)]
+pub struct HealthSignal {
+    state: Arc<ConnectionState>,
+    circuit_breakers: Arc<CircuitBreakers>,
+}
+
+impl HealthSignal {
+    pub(super) fn new(state: Arc<ConnectionState>, circuit_breakers: Arc<CircuitBreakers>) -> Self {
+        Self {
+            state,
+            circuit_breakers,
+        }
+    }
+
+    /// `true` while any circuit breaker is open or half-open, or an
+    /// app-level reconnection is in progress.
+    pub fn is_degraded(&self) -> bool {
+        !self.circuit_breakers.all_closed() || self.state.is_reconnecting()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iggy_client::OperationClass;
+
+    #[tokio::test]
+    async fn test_degraded_while_reconnecting_or_circuit_not_closed() {
+        let state = Arc::new(ConnectionState::new());
+        let circuit_breakers = Arc::new(CircuitBreakers::default());
+        let signal = HealthSignal::new(state.clone(), circuit_breakers.clone());
+        assert!(!signal.is_degraded());
+
+        assert!(state.start_reconnecting());
+        assert!(signal.is_degraded());
+        state.stop_reconnecting();
+        assert!(!signal.is_degraded());
+
+        let poll = circuit_breakers.get(OperationClass::Poll);
+        poll.force_open().await;
+        assert!(signal.is_degraded());
+        poll.force_close().await;
+        assert!(!signal.is_degraded());
+    }
+}
@@ -1,8 +1,16 @@
 //! Helper functions for the Iggy client.
 
-use iggy::prelude::{Identifier, IggyError};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::error::AppError;
+use bytes::Bytes;
+use iggy::prelude::{
+    HeaderKey, HeaderValue, Identifier, IggyError, IggyMessage, IggyMessageHeader, Partitioning,
+};
+
+use crate::error::{AppError, AppResult};
+use crate::models::Event;
 
 /// Classify an SDK error into a connection-aware `AppError`.
 ///
@@ -36,10 +44,95 @@ pub fn classify_iggy_error(error: IggyError, fallback: fn(String) -> AppError) -
         | IggyError::WebSocketReceiveError
         | IggyError::WebSocketSendError => AppError::ConnectionReset(error.to_string()),
         IggyError::CannotEstablishConnection => AppError::ConnectionFailed(error.to_string()),
+        // A session the server does not (or no longer) accepts: surfacing
+        // it as the operation's fallback (e.g. SendError) would hide that
+        // the fix is credentials, not the request.
+        IggyError::Unauthenticated | IggyError::InvalidCredentials => {
+            AppError::AuthenticationFailed(error.to_string())
+        }
         other => fallback(other.to_string()),
     }
 }
 
+/// User header carrying the event's `correlation_id`, so consumers and
+/// tooling can trace a message without decoding its payload.
+pub const CORRELATION_USER_HEADER: &str = "correlation_id";
+
+/// Build an Iggy message carrying `event` as its JSON payload.
+///
+/// Serializes straight into a byte buffer that becomes the message payload
+/// without another copy. The `String` + `IggyMessage::from_str` route this
+/// replaces copied the JSON a second time and re-validated it as UTF-8.
+pub fn event_message(event: &Event) -> AppResult<IggyMessage> {
+    event_payload_message(
+        event,
+        Bytes::from(serde_json::to_vec(event)?),
+        BTreeMap::new(),
+    )
+}
+
+/// Build the Iggy message for `event` from its encoded `payload`, adding
+/// the event's own user headers (its correlation ID) to `headers`.
+pub fn event_payload_message(
+    event: &Event,
+    payload: Bytes,
+    mut headers: BTreeMap<HeaderKey, HeaderValue>,
+) -> AppResult<IggyMessage> {
+    let header = |e: IggyError| AppError::SendError(e.to_string());
+    if let Some(correlation_id) = event.correlation_id {
+        headers.insert(
+            HeaderKey::from_str(CORRELATION_USER_HEADER).map_err(header)?,
+            HeaderValue::from_str(&correlation_id.to_string()).map_err(header)?,
+        );
+    }
+    if headers.is_empty() {
+        return payload_message(payload);
+    }
+    IggyMessage::builder()
+        .payload(payload)
+        .user_headers(headers)
+        .build()
+        .map_err(|e| AppError::SendError(e.to_string()))
+}
+
+/// Build an Iggy message around an already-encoded payload.
+pub fn payload_message(payload: Bytes) -> AppResult<IggyMessage> {
+    IggyMessage::builder()
+        .payload(payload)
+        .build()
+        .map_err(|e| AppError::SendError(e.to_string()))
+}
+
+/// Copy `message`, header included; the SDK's `IggyMessage` is not `Clone`.
+pub fn copy_message(message: &IggyMessage) -> IggyMessage {
+    let header = &message.header;
+    IggyMessage {
+        header: IggyMessageHeader {
+            checksum: header.checksum,
+            id: header.id,
+            offset: header.offset,
+            timestamp: header.timestamp,
+            origin_timestamp: header.origin_timestamp,
+            user_headers_length: header.user_headers_length,
+            payload_length: header.payload_length,
+            reserved: header.reserved,
+        },
+        payload: message.payload.clone(),
+        user_headers: message.user_headers.clone(),
+    }
+}
+
+/// SDK partitioning for an optional partition key: messages-key hashing when
+/// a key is given, server-side balanced otherwise.
+pub fn key_partitioning(partition_key: Option<&str>) -> Result<Partitioning, AppError> {
+    match partition_key {
+        Some(key) => {
+            Partitioning::messages_key_str(key).map_err(|e| AppError::SendError(e.to_string()))
+        }
+        None => Ok(Partitioning::balanced()),
+    }
+}
+
 /// Convert a resource name to a name-based (string) Identifier.
 ///
 /// Uses `Identifier::named` explicitly rather than `str::try_into`: the
@@ -76,11 +169,64 @@ pub fn rand_jitter() -> f64 {
     rand::random::<f64>()
 }
 
+/// Whether `address` (`host:port`) accepts a plain TCP connection within
+/// `timeout`.
+///
+/// Used to tell a TLS handshake failure (server reachable) apart from an
+/// unreachable server, which the SDK reports identically.
+pub async fn tcp_reachable(address: &str, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address)).await,
+        Ok(Ok(_))
+    )
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_event_message_payload_is_event_json() {
+        let event = Event::new(
+            "test",
+            crate::models::EventPayload::Generic(serde_json::json!({"k": "v"})),
+        );
+        let message = event_message(&event).unwrap();
+        let parsed: Event = serde_json::from_slice(&message.payload).unwrap();
+        assert_eq!(parsed.id, event.id);
+        assert!(message.user_headers_map().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_event_message_carries_correlation_header() {
+        let correlation_id = uuid::Uuid::new_v4();
+        let event = Event::new(
+            "test",
+            crate::models::EventPayload::Generic(serde_json::json!({})),
+        )
+        .with_correlation_id(correlation_id);
+        let headers = event_message(&event)
+            .unwrap()
+            .user_headers_map()
+            .unwrap()
+            .unwrap();
+        let value = headers
+            .get(&HeaderKey::from_str(CORRELATION_USER_HEADER).unwrap())
+            .unwrap();
+        assert_eq!(value.as_str().unwrap(), correlation_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_reachable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert!(tcp_reachable(&address, Duration::from_secs(1)).await);
+
+        drop(listener);
+        assert!(!tcp_reachable(&address, Duration::from_secs(1)).await);
+    }
+
     #[test]
     fn test_to_identifier_valid_names() {
         assert!(to_identifier("my-stream", "stream").is_ok());
@@ -173,6 +319,18 @@ mod tests {
         assert!(matches!(classified, AppError::SendError(_)));
     }
 
+    #[test]
+    fn test_classify_authentication_errors() {
+        for error in [IggyError::Unauthenticated, IggyError::InvalidCredentials] {
+            let classified = classify_iggy_error(error, AppError::SendError);
+            assert!(
+                matches!(classified, AppError::AuthenticationFailed(_)),
+                "expected AuthenticationFailed, got {:?}",
+                classified
+            );
+        }
+    }
+
     #[test]
     fn test_classify_cannot_establish_connection() {
         let classified =
@@ -0,0 +1,1260 @@
+//! In-memory broker backend (`BROKER_BACKEND=memory`).
+//!
+//! With the memory backend, [`IggyClientWrapper`](super::IggyClientWrapper)
+//! serves every stream, topic, send and poll from [`MemoryBroker`], a
+//! multi-partition log inside the process, instead of an Iggy server. It
+//! exists so the HTTP API can be tried, and CI can run, without a server;
+//! nothing is persisted and everything is lost on restart.
+//!
+//! `MemoryBroker` is a [`MessageBroker`] like any other: the wrapper is
+//! built around it (see `IggyClientWrapper::with_broker`), so operations
+//! still take a fair queue slot, run under the deadline and circuit
+//! breaker, and are recorded in the operation metrics. Services generic
+//! over `MessageBroker` can also use it directly.
+//!
+//! # Semantics
+//!
+//! Close enough to Iggy's for tests of this API:
+//!
+//! - Partitions are 0-indexed. Offsets start at 0 per partition and are
+//!   never reused, even after retention removes messages.
+//! - Balanced sends go round-robin over a topic's partitions; keyed sends
+//!   hash the key, so a key always lands on the same partition (but not
+//!   necessarily the one an Iggy server would pick).
+//! - Each standalone consumer has a committed offset per partition. A poll
+//!   without an offset or timestamp resumes after it (from 0 if never
+//!   committed), and `auto_commit` commits the last message returned. A
+//!   timestamp starts at the first message stamped at or after it.
+//! - Retention follows the topic's settings: messages older than the
+//!   message expiry, and the oldest messages beyond the max topic size,
+//!   are removed on every access. Topics created through the API never
+//!   expire; bootstrap specs set both limits.
+//!
+//! Errors use the variants the wrapper classifies Iggy's into: a missing
+//! stream or topic is `NotFound` on lookups and `StreamError` /
+//! `TopicError` / `SendError` / `PollError` elsewhere.
+//!
+//! Server statistics and user management have no in-memory equivalent and
+//! fail with `AppError::BadRequest` (see [`unsupported`]).
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use bytes::Bytes;
+use iggy::prelude::{
+    CompressionAlgorithm, IggyByteSize, IggyExpiry, IggyMessage, IggyTimestamp, MaxTopicSize,
+    Partition, Partitioning, PolledMessages, Stream, StreamDetails, Topic, TopicDetails,
+};
+use iggy_common::PartitioningKind;
+use uuid::Uuid;
+
+use super::helpers::{copy_message, event_message};
+use super::{IggyOperations, MessageBroker, PollParams, compression};
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::models::Event;
+
+/// Broker the wrapper talks to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BrokerBackend {
+    /// An Iggy server at `IGGY_CONNECTION_STRING` (default)
+    #[default]
+    Iggy,
+    /// The in-process [`MemoryBroker`]
+    Memory,
+}
+
+impl FromStr for BrokerBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iggy" => Ok(Self::Iggy),
+            "memory" => Ok(Self::Memory),
+            _ => Err(format!(
+                "Unknown broker backend '{s}' (expected iggy or memory)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for BrokerBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Iggy => "iggy",
+            Self::Memory => "memory",
+        })
+    }
+}
+
+/// Error of an operation only an Iggy server has, for a wrapper built
+/// around a [`MessageBroker`] such as the memory backend.
+pub fn unsupported(operation: &str) -> AppError {
+    AppError::BadRequest(format!(
+        "{operation} is not available without an Iggy server (BROKER_BACKEND=memory)"
+    ))
+}
+
+/// One partition's log.
+#[derive(Debug)]
+struct MemoryPartition {
+    created_at: IggyTimestamp,
+    /// Retained messages, oldest first
+    messages: VecDeque<IggyMessage>,
+    /// Offset the next message is written at
+    next_offset: u64,
+    /// Bytes of the retained messages
+    size_bytes: u64,
+    /// Committed offset per standalone consumer ID
+    consumer_offsets: HashMap<u32, u64>,
+}
+
+impl MemoryPartition {
+    fn new() -> Self {
+        Self {
+            created_at: IggyTimestamp::now(),
+            messages: VecDeque::new(),
+            next_offset: 0,
+            size_bytes: 0,
+            consumer_offsets: HashMap::new(),
+        }
+    }
+
+    /// Offset of the last message written (0 when empty), as Iggy reports.
+    fn current_offset(&self) -> u64 {
+        self.next_offset.saturating_sub(1)
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some(message) = self.messages.pop_front() {
+            self.size_bytes = self.size_bytes.saturating_sub(message_size(&message));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MemoryTopic {
+    id: u32,
+    created_at: IggyTimestamp,
+    message_expiry: IggyExpiry,
+    max_topic_size: MaxTopicSize,
+    partitions: Vec<MemoryPartition>,
+    /// Partition of the next balanced send
+    next_balanced: usize,
+}
+
+impl MemoryTopic {
+    fn size_bytes(&self) -> u64 {
+        self.partitions.iter().map(|p| p.size_bytes).sum()
+    }
+
+    fn messages_count(&self) -> u64 {
+        self.partitions
+            .iter()
+            .map(|p| p.messages.len() as u64)
+            .sum()
+    }
+
+    /// Remove what the topic's retention no longer keeps at `now`
+    /// (microseconds since the epoch).
+    fn retain(&mut self, now: u64) {
+        if let IggyExpiry::ExpireDuration(expiry) = self.message_expiry {
+            let cutoff = now.saturating_sub(expiry.get_duration().as_micros() as u64);
+            for partition in &mut self.partitions {
+                while partition
+                    .messages
+                    .front()
+                    .is_some_and(|message| message.header.timestamp < cutoff)
+                {
+                    partition.pop_oldest();
+                }
+            }
+        }
+        if let MaxTopicSize::Custom(max_size) = self.max_topic_size {
+            while self.size_bytes() > max_size.as_bytes_u64() {
+                // The oldest message across partitions goes first
+                let oldest = self
+                    .partitions
+                    .iter_mut()
+                    .filter(|p| !p.messages.is_empty())
+                    .min_by_key(|p| p.messages.front().map(|m| m.header.timestamp));
+                match oldest {
+                    Some(partition) => partition.pop_oldest(),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Index of the partition `partitioning` selects.
+    fn partition_index(&mut self, partitioning: &Partitioning) -> AppResult<usize> {
+        let count = self.partitions.len();
+        if count == 0 {
+            return Err(AppError::SendError("Topic has no partitions".to_string()));
+        }
+        match &partitioning.kind {
+            PartitioningKind::Balanced => {
+                let index = self.next_balanced % count;
+                self.next_balanced = self.next_balanced.wrapping_add(1);
+                Ok(index)
+            }
+            PartitioningKind::PartitionId => {
+                let id = partitioning
+                    .value
+                    .get(..4)
+                    .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or_else(|| AppError::SendError("Invalid partition ID".to_string()))?;
+                if id as usize >= count {
+                    return Err(AppError::SendError(format!(
+                        "Partition {id} not found ({count} partitions)"
+                    )));
+                }
+                Ok(id as usize)
+            }
+            PartitioningKind::MessagesKey => {
+                let mut hasher = DefaultHasher::new();
+                partitioning.value.hash(&mut hasher);
+                Ok((hasher.finish() % count as u64) as usize)
+            }
+        }
+    }
+
+    fn details(&self, name: &str) -> TopicDetails {
+        TopicDetails {
+            id: self.id,
+            created_at: self.created_at,
+            name: name.to_string(),
+            size: IggyByteSize::from(self.size_bytes()),
+            message_expiry: self.message_expiry,
+            compression_algorithm: CompressionAlgorithm::default(),
+            max_topic_size: self.max_topic_size,
+            replication_factor: 1,
+            messages_count: self.messages_count(),
+            partitions_count: self.partitions.len() as u32,
+            partitions: self
+                .partitions
+                .iter()
+                .zip(0u32..)
+                .map(|(partition, id)| Partition {
+                    id,
+                    created_at: partition.created_at,
+                    segments_count: 1,
+                    current_offset: partition.current_offset(),
+                    size: IggyByteSize::from(partition.size_bytes),
+                    messages_count: partition.messages.len() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    fn summary(&self, name: &str) -> Topic {
+        Topic {
+            id: self.id,
+            created_at: self.created_at,
+            name: name.to_string(),
+            size: IggyByteSize::from(self.size_bytes()),
+            message_expiry: self.message_expiry,
+            compression_algorithm: CompressionAlgorithm::default(),
+            max_topic_size: self.max_topic_size,
+            replication_factor: 1,
+            messages_count: self.messages_count(),
+            partitions_count: self.partitions.len() as u32,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MemoryStream {
+    id: u32,
+    created_at: IggyTimestamp,
+    topics: BTreeMap<String, MemoryTopic>,
+    next_topic_id: u32,
+}
+
+impl MemoryStream {
+    fn summary(&self, name: &str) -> Stream {
+        Stream {
+            id: self.id,
+            created_at: self.created_at,
+            name: name.to_string(),
+            size: IggyByteSize::from(
+                self.topics
+                    .values()
+                    .map(MemoryTopic::size_bytes)
+                    .sum::<u64>(),
+            ),
+            messages_count: self.topics.values().map(MemoryTopic::messages_count).sum(),
+            topics_count: self.topics.len() as u32,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    streams: BTreeMap<String, MemoryStream>,
+    next_stream_id: u32,
+}
+
+impl MemoryState {
+    fn stream_mut(
+        &mut self,
+        name: &str,
+        error: fn(String) -> AppError,
+    ) -> AppResult<&mut MemoryStream> {
+        self.streams
+            .get_mut(name)
+            .ok_or_else(|| error(format!("Stream '{name}' not found")))
+    }
+
+    /// `stream`/`topic`, with retention applied.
+    fn topic_mut(
+        &mut self,
+        stream: &str,
+        topic: &str,
+        error: fn(String) -> AppError,
+    ) -> AppResult<&mut MemoryTopic> {
+        let topic_state = self
+            .stream_mut(stream, error)?
+            .topics
+            .get_mut(topic)
+            .ok_or_else(|| error(format!("Topic '{topic}' in stream '{stream}' not found")))?;
+        topic_state.retain(IggyTimestamp::now().as_micros());
+        Ok(topic_state)
+    }
+
+    fn retain_all(&mut self) {
+        let now = IggyTimestamp::now().as_micros();
+        for stream in self.streams.values_mut() {
+            for topic in stream.topics.values_mut() {
+                topic.retain(now);
+            }
+        }
+    }
+}
+
+/// In-process multi-partition log standing in for an Iggy server.
+///
+/// Methods mirror the wrapper operations they back; see the module docs
+/// for the semantics. Clones share the log.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryBroker {
+    /// Application configuration (batch compression of sends)
+    config: Arc<Config>,
+    state: Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryBroker {
+    /// Create an empty broker.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: Arc::new(config),
+            state: Arc::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Create stream `name` unless it exists.
+    pub fn ensure_stream(&self, name: &str) -> AppResult<()> {
+        if !self.lock().streams.contains_key(name) {
+            self.create_stream(name)?;
+        }
+        Ok(())
+    }
+
+    /// Create `stream`/`topic` unless it exists; whether it was created.
+    pub fn ensure_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        message_expiry: IggyExpiry,
+        max_topic_size: MaxTopicSize,
+    ) -> AppResult<bool> {
+        let exists = self
+            .lock()
+            .stream_mut(stream, AppError::TopicError)?
+            .topics
+            .contains_key(topic);
+        if !exists {
+            self.create_topic(stream, topic, partitions, message_expiry, max_topic_size)?;
+        }
+        Ok(!exists)
+    }
+
+    /// Create stream `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::StreamError` if it exists.
+    pub fn create_stream(&self, name: &str) -> AppResult<()> {
+        let mut state = self.lock();
+        if state.streams.contains_key(name) {
+            return Err(AppError::StreamError(format!(
+                "Stream '{name}' already exists"
+            )));
+        }
+        let id = state.next_stream_id;
+        state.next_stream_id += 1;
+        state.streams.insert(
+            name.to_string(),
+            MemoryStream {
+                id,
+                created_at: IggyTimestamp::now(),
+                topics: BTreeMap::new(),
+                next_topic_id: 0,
+            },
+        );
+        Ok(())
+    }
+
+    /// Create `stream`/`topic` with `partitions` and the given retention.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::TopicError` if the stream is missing or the topic
+    /// exists.
+    pub fn create_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        message_expiry: IggyExpiry,
+        max_topic_size: MaxTopicSize,
+    ) -> AppResult<()> {
+        let mut state = self.lock();
+        let stream_state = state.stream_mut(stream, AppError::TopicError)?;
+        if stream_state.topics.contains_key(topic) {
+            return Err(AppError::TopicError(format!(
+                "Topic '{topic}' already exists in stream '{stream}'"
+            )));
+        }
+        let id = stream_state.next_topic_id;
+        stream_state.next_topic_id += 1;
+        stream_state.topics.insert(
+            topic.to_string(),
+            MemoryTopic {
+                id,
+                created_at: IggyTimestamp::now(),
+                message_expiry,
+                max_topic_size,
+                partitions: (0..partitions).map(|_| MemoryPartition::new()).collect(),
+                next_balanced: 0,
+            },
+        );
+        Ok(())
+    }
+
+    /// Delete stream `name` with its topics and messages.
+    pub fn delete_stream(&self, name: &str) -> AppResult<()> {
+        self.lock()
+            .streams
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| AppError::StreamError(format!("Stream '{name}' not found")))
+    }
+
+    /// Delete `stream`/`topic` with its messages.
+    pub fn delete_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        self.lock()
+            .stream_mut(stream, AppError::TopicError)?
+            .topics
+            .remove(topic)
+            .map(|_| ())
+            .ok_or_else(|| {
+                AppError::TopicError(format!("Topic '{topic}' in stream '{stream}' not found"))
+            })
+    }
+
+    /// Rename stream `name` to `new_name`, keeping its ID and topics.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the stream does not exist and
+    /// `AppError::Conflict` if `new_name` is taken.
+    pub fn rename_stream(&self, name: &str, new_name: &str) -> AppResult<()> {
+        let mut state = self.lock();
+        if !state.streams.contains_key(name) {
+            return Err(AppError::NotFound(format!("Stream '{name}' not found")));
+        }
+        if name == new_name {
+            return Ok(());
+        }
+        if state.streams.contains_key(new_name) {
+            return Err(AppError::Conflict(format!(
+                "Stream '{new_name}' already exists"
+            )));
+        }
+        if let Some(renamed) = state.streams.remove(name) {
+            state.streams.insert(new_name.to_string(), renamed);
+        }
+        Ok(())
+    }
+
+    /// Rename `stream`/`topic` to `new_name`, keeping its ID, settings and
+    /// messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the topic does not exist and
+    /// `AppError::Conflict` if `new_name` is taken in the stream.
+    pub fn rename_topic(&self, stream: &str, topic: &str, new_name: &str) -> AppResult<()> {
+        let mut state = self.lock();
+        let topics = &mut state.stream_mut(stream, AppError::NotFound)?.topics;
+        if !topics.contains_key(topic) {
+            return Err(AppError::NotFound(format!(
+                "Topic '{topic}' in stream '{stream}' not found"
+            )));
+        }
+        if topic == new_name {
+            return Ok(());
+        }
+        if topics.contains_key(new_name) {
+            return Err(AppError::Conflict(format!(
+                "Topic '{new_name}' already exists in stream '{stream}'"
+            )));
+        }
+        if let Some(renamed) = topics.remove(topic) {
+            topics.insert(new_name.to_string(), renamed);
+        }
+        Ok(())
+    }
+
+    /// Set the message expiry and size limit of `stream`/`topic` where they
+    /// differ from `max_age_secs` / `max_size_bytes`; whether it changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the topic does not exist.
+    pub fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> AppResult<bool> {
+        let mut state = self.lock();
+        let topic_state = state.topic_mut(stream, topic, AppError::NotFound)?;
+        let Some((expiry, max_size)) = super::retention_update(
+            topic_state.message_expiry,
+            topic_state.max_topic_size,
+            max_age_secs,
+            max_size_bytes,
+        ) else {
+            return Ok(false);
+        };
+        topic_state.message_expiry = expiry;
+        topic_state.max_topic_size = max_size;
+        topic_state.retain(IggyTimestamp::now().as_micros());
+        Ok(true)
+    }
+
+    /// Delete every message of `stream`/`topic`. Offsets carry on where
+    /// they were, and committed consumer offsets are kept.
+    pub fn purge_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        let mut state = self.lock();
+        let topic_state = state.topic_mut(stream, topic, AppError::TopicError)?;
+        for partition in &mut topic_state.partitions {
+            partition.messages.clear();
+            partition.size_bytes = 0;
+        }
+        Ok(())
+    }
+
+    /// Details of stream `name`.
+    pub fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
+        let mut state = self.lock();
+        state.retain_all();
+        let stream = state.stream_mut(name, AppError::NotFound)?;
+        let summary = stream.summary(name);
+        Ok(StreamDetails {
+            id: summary.id,
+            created_at: summary.created_at,
+            name: summary.name,
+            size: summary.size,
+            messages_count: summary.messages_count,
+            topics_count: summary.topics_count,
+            topics: stream
+                .topics
+                .iter()
+                .map(|(name, topic)| topic.summary(name))
+                .collect(),
+        })
+    }
+
+    /// Details of `stream`/`topic`, including its partitions.
+    pub fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
+        Ok(self
+            .lock()
+            .topic_mut(stream, topic, AppError::NotFound)?
+            .details(topic))
+    }
+
+    /// All streams.
+    pub fn list_streams(&self) -> Vec<Stream> {
+        let mut state = self.lock();
+        state.retain_all();
+        state
+            .streams
+            .iter()
+            .map(|(name, stream)| stream.summary(name))
+            .collect()
+    }
+
+    /// All topics of `stream`.
+    pub fn list_topics(&self, stream: &str) -> AppResult<Vec<Topic>> {
+        let mut state = self.lock();
+        state.retain_all();
+        Ok(state
+            .stream_mut(stream, AppError::TopicError)?
+            .topics
+            .iter()
+            .map(|(name, topic)| topic.summary(name))
+            .collect())
+    }
+
+    /// Append `messages` to the partition `partitioning` selects, assigning
+    /// offsets, timestamps and (when unset) IDs.
+    pub fn send_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitioning: &Partitioning,
+        messages: Vec<IggyMessage>,
+    ) -> AppResult<()> {
+        let mut state = self.lock();
+        let topic_state = state.topic_mut(stream, topic, AppError::SendError)?;
+        let index = topic_state.partition_index(partitioning)?;
+        let Some(partition) = topic_state.partitions.get_mut(index) else {
+            return Err(AppError::SendError(format!("Partition {index} not found")));
+        };
+
+        let timestamp = IggyTimestamp::now().as_micros();
+        for mut message in messages {
+            message.header.offset = partition.next_offset;
+            message.header.timestamp = timestamp;
+            if message.header.id == 0 {
+                message.header.id = Uuid::new_v4().as_u128();
+            }
+            partition.next_offset += 1;
+            partition.size_bytes += message_size(&message);
+            partition.messages.push_back(message);
+        }
+        topic_state.retain(timestamp);
+        Ok(())
+    }
+
+    /// Read up to `params.count` messages from one partition: from
+    /// `params.offset`, or after the consumer's committed offset.
+    pub fn poll_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: &PollParams,
+    ) -> AppResult<PolledMessages> {
+        let mut state = self.lock();
+        let partition = partition_mut(
+            state.topic_mut(stream, topic, AppError::PollError)?,
+            params.partition_id,
+        )?;
+
+        let start = match (params.offset, params.from_timestamp) {
+            (Some(offset), _) => offset,
+            (None, Some(timestamp)) => partition
+                .messages
+                .iter()
+                .find(|message| message.header.timestamp >= timestamp)
+                .map_or(u64::MAX, |message| message.header.offset),
+            (None, None) => partition
+                .consumer_offsets
+                .get(&params.consumer_id)
+                .map_or(0, |committed| committed + 1),
+        };
+        let messages: Vec<IggyMessage> = partition
+            .messages
+            .iter()
+            .skip_while(|message| message.header.offset < start)
+            .take(params.count as usize)
+            .map(copy_message)
+            .collect();
+        if params.auto_commit
+            && let Some(last) = messages.last()
+        {
+            partition
+                .consumer_offsets
+                .insert(params.consumer_id, last.header.offset);
+        }
+
+        Ok(PolledMessages {
+            partition_id: params.partition_id,
+            current_offset: partition.current_offset(),
+            count: messages.len() as u32,
+            messages,
+        })
+    }
+
+    /// Committed offset of `consumer_id` on one partition.
+    pub fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<Option<u64>> {
+        let mut state = self.lock();
+        let partition = partition_mut(
+            state.topic_mut(stream, topic, AppError::PollError)?,
+            partition_id,
+        )?;
+        Ok(partition.consumer_offsets.get(&consumer_id).copied())
+    }
+
+    /// Commit `offset` for `consumer_id` on one partition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::PollError` if nothing was written at `offset` yet.
+    pub fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<()> {
+        let mut state = self.lock();
+        let partition = partition_mut(
+            state.topic_mut(stream, topic, AppError::PollError)?,
+            partition_id,
+        )?;
+        if offset >= partition.next_offset {
+            return Err(AppError::PollError(format!(
+                "Offset {offset} is beyond the current offset of partition {partition_id}"
+            )));
+        }
+        partition.consumer_offsets.insert(consumer_id, offset);
+        Ok(())
+    }
+
+    /// Forget the committed offset of `consumer_id` on one partition.
+    pub fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<()> {
+        let mut state = self.lock();
+        let partition = partition_mut(
+            state.topic_mut(stream, topic, AppError::PollError)?,
+            partition_id,
+        )?;
+        partition.consumer_offsets.remove(&consumer_id);
+        Ok(())
+    }
+}
+
+impl IggyOperations for MemoryBroker {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Operations never wait, so there is nothing to bound.
+    fn with_timeout(&self, _timeout: Duration) -> Self {
+        self.clone()
+    }
+
+    async fn ensure_stream(&self, name: &str) -> AppResult<()> {
+        MemoryBroker::ensure_stream(self, name)
+    }
+
+    async fn ensure_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        MemoryBroker::ensure_topic(
+            self,
+            stream,
+            topic,
+            partitions,
+            IggyExpiry::NeverExpire,
+            MaxTopicSize::Unlimited,
+        )
+        .map(drop)
+    }
+
+    async fn partitions_count(&self, stream: &str, topic: &str) -> AppResult<u32> {
+        Ok(MemoryBroker::get_topic(self, stream, topic)?.partitions_count)
+    }
+
+    async fn send_event_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        self.send_messages(stream, topic, partitioning, vec![event_message(event)?])
+    }
+
+    async fn send_events_batch_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let messages = events
+            .iter()
+            .map(|event| {
+                compression::batch_message(
+                    event,
+                    self.config.batch_compression,
+                    self.config.compression_threshold_bytes,
+                )
+            })
+            .collect::<AppResult<_>>()?;
+        self.send_messages(stream, topic, partitioning, messages)
+    }
+
+    async fn send_raw_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: &[IggyMessage],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.send_messages(
+            stream,
+            topic,
+            partitioning,
+            messages.iter().map(copy_message).collect(),
+        )
+    }
+}
+
+impl MessageBroker for MemoryBroker {
+    async fn ensure_topic_with(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> AppResult<bool> {
+        MemoryBroker::ensure_topic(self, stream, topic, partitions, expiry, max_size)
+    }
+
+    async fn poll_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+    ) -> AppResult<PolledMessages> {
+        MemoryBroker::poll_messages(self, stream, topic, &params)
+    }
+
+    async fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<Option<u64>> {
+        MemoryBroker::get_consumer_offset(self, stream, topic, consumer_id, partition_id)
+    }
+
+    async fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<()> {
+        MemoryBroker::store_consumer_offset(self, stream, topic, consumer_id, partition_id, offset)
+    }
+
+    async fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<()> {
+        MemoryBroker::delete_consumer_offset(self, stream, topic, consumer_id, partition_id)
+    }
+
+    async fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
+        MemoryBroker::get_stream(self, name)
+    }
+
+    async fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
+        MemoryBroker::get_topic(self, stream, topic)
+    }
+
+    async fn list_streams(&self) -> AppResult<Vec<Stream>> {
+        Ok(MemoryBroker::list_streams(self))
+    }
+
+    async fn list_topics(&self, stream: &str) -> AppResult<Vec<Topic>> {
+        MemoryBroker::list_topics(self, stream)
+    }
+
+    async fn create_stream(&self, name: &str) -> AppResult<()> {
+        MemoryBroker::create_stream(self, name)
+    }
+
+    async fn create_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        MemoryBroker::create_topic(
+            self,
+            stream,
+            topic,
+            partitions,
+            IggyExpiry::NeverExpire,
+            MaxTopicSize::Unlimited,
+        )
+    }
+
+    async fn delete_stream(&self, name: &str) -> AppResult<()> {
+        MemoryBroker::delete_stream(self, name)
+    }
+
+    async fn delete_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        MemoryBroker::delete_topic(self, stream, topic)
+    }
+
+    async fn rename_stream(&self, name: &str, new_name: &str) -> AppResult<()> {
+        MemoryBroker::rename_stream(self, name, new_name)
+    }
+
+    async fn rename_topic(&self, stream: &str, topic: &str, new_name: &str) -> AppResult<()> {
+        MemoryBroker::rename_topic(self, stream, topic, new_name)
+    }
+
+    async fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> AppResult<bool> {
+        MemoryBroker::enforce_topic_retention(self, stream, topic, max_age_secs, max_size_bytes)
+    }
+
+    async fn purge_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        MemoryBroker::purge_topic(self, stream, topic)
+    }
+}
+
+fn partition_mut(topic: &mut MemoryTopic, partition_id: u32) -> AppResult<&mut MemoryPartition> {
+    topic
+        .partitions
+        .get_mut(partition_id as usize)
+        .ok_or_else(|| AppError::PollError(format!("Partition {partition_id} not found")))
+}
+
+/// Bytes a message counts against the max topic size: its payload and
+/// user headers.
+fn message_size(message: &IggyMessage) -> u64 {
+    let headers = message.user_headers.as_ref().map_or(0, Bytes::len);
+    (message.payload.len() + headers) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::indexing_slicing)]
+
+    use std::time::Duration;
+
+    use iggy::prelude::IggyDuration;
+
+    use super::*;
+
+    fn broker() -> MemoryBroker {
+        let broker = MemoryBroker::default();
+        broker.ensure_stream("orders").unwrap();
+        broker
+            .ensure_topic(
+                "orders",
+                "created",
+                2,
+                IggyExpiry::NeverExpire,
+                MaxTopicSize::Unlimited,
+            )
+            .unwrap();
+        broker
+    }
+
+    fn messages(payloads: &[&str]) -> Vec<IggyMessage> {
+        payloads
+            .iter()
+            .map(|payload| {
+                IggyMessage::builder()
+                    .payload(Bytes::from(payload.to_string()))
+                    .build()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn payloads(polled: &PolledMessages) -> Vec<String> {
+        polled
+            .messages
+            .iter()
+            .map(|m| String::from_utf8_lossy(&m.payload).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_next_polls_resume_after_the_committed_offset() {
+        let broker = broker();
+        let to_first = Partitioning::partition_id(0);
+        broker
+            .send_messages("orders", "created", &to_first, messages(&["a", "b", "c"]))
+            .unwrap();
+
+        let next = PollParams::new(0, 7).with_count(2).with_auto_commit(true);
+        let first = broker.poll_messages("orders", "created", &next).unwrap();
+        assert_eq!(payloads(&first), ["a", "b"]);
+        assert_eq!(first.current_offset, 2);
+        let second = broker.poll_messages("orders", "created", &next).unwrap();
+        assert_eq!(payloads(&second), ["c"]);
+        assert_eq!(
+            broker
+                .get_consumer_offset("orders", "created", 7, 0)
+                .unwrap(),
+            Some(2)
+        );
+
+        // Explicit offsets re-read without moving the committed offset
+        let replay = PollParams::new(0, 7).with_offset(1).with_count(10);
+        let replayed = broker.poll_messages("orders", "created", &replay).unwrap();
+        assert_eq!(payloads(&replayed), ["b", "c"]);
+        assert!(
+            broker
+                .store_consumer_offset("orders", "created", 7, 0, 3)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_timestamp_polls_start_at_the_first_later_message() {
+        let broker = broker();
+        let to_first = Partitioning::partition_id(0);
+        broker
+            .send_messages("orders", "created", &to_first, messages(&["a", "b"]))
+            .unwrap();
+
+        let all = PollParams::new(0, 7).with_timestamp(0).with_count(10);
+        let polled = broker.poll_messages("orders", "created", &all).unwrap();
+        assert_eq!(payloads(&polled), ["a", "b"]);
+        let future = PollParams::new(0, 7)
+            .with_timestamp(u64::MAX)
+            .with_count(10);
+        let polled = broker.poll_messages("orders", "created", &future).unwrap();
+        assert!(polled.messages.is_empty());
+        // An offset wins over a timestamp
+        let both = PollParams::new(0, 7)
+            .with_offset(1)
+            .with_timestamp(0)
+            .with_count(10);
+        let polled = broker.poll_messages("orders", "created", &both).unwrap();
+        assert_eq!(payloads(&polled), ["b"]);
+    }
+
+    #[test]
+    fn test_balanced_sends_round_robin_and_keys_stick() {
+        let broker = broker();
+        for _ in 0..4 {
+            broker
+                .send_messages(
+                    "orders",
+                    "created",
+                    &Partitioning::balanced(),
+                    messages(&["x"]),
+                )
+                .unwrap();
+        }
+        let topic = broker.get_topic("orders", "created").unwrap();
+        let counts: Vec<u64> = topic.partitions.iter().map(|p| p.messages_count).collect();
+        assert_eq!(counts, [2, 2]);
+
+        let key = Partitioning::messages_key_str("customer-1").unwrap();
+        for _ in 0..3 {
+            broker
+                .send_messages("orders", "created", &key, messages(&["k"]))
+                .unwrap();
+        }
+        let topic = broker.get_topic("orders", "created").unwrap();
+        assert!(topic.partitions.iter().any(|p| p.messages_count == 5));
+    }
+
+    #[test]
+    fn test_max_topic_size_drops_the_oldest_messages() {
+        let broker = broker();
+        broker
+            .ensure_topic(
+                "orders",
+                "capped",
+                1,
+                IggyExpiry::NeverExpire,
+                MaxTopicSize::Custom(IggyByteSize::from(4)),
+            )
+            .unwrap();
+        let to_first = Partitioning::partition_id(0);
+        broker
+            .send_messages("orders", "capped", &to_first, messages(&["aa", "bb", "cc"]))
+            .unwrap();
+
+        let all = PollParams::new(0, 1).with_offset(0).with_count(10);
+        let polled = broker.poll_messages("orders", "capped", &all).unwrap();
+        assert_eq!(payloads(&polled), ["bb", "cc"]);
+        // Offsets are not reused
+        assert_eq!(polled.messages[0].header.offset, 1);
+    }
+
+    #[test]
+    fn test_expired_messages_are_removed() {
+        let broker = broker();
+        broker
+            .ensure_topic(
+                "orders",
+                "expiring",
+                1,
+                IggyExpiry::ExpireDuration(IggyDuration::new(Duration::from_secs(60))),
+                MaxTopicSize::Unlimited,
+            )
+            .unwrap();
+        broker
+            .send_messages(
+                "orders",
+                "expiring",
+                &Partitioning::partition_id(0),
+                messages(&["old"]),
+            )
+            .unwrap();
+
+        let mut state = broker.lock();
+        let topic = state
+            .topic_mut("orders", "expiring", AppError::NotFound)
+            .unwrap();
+        assert_eq!(topic.messages_count(), 1);
+        topic.retain(IggyTimestamp::now().as_micros() + 61_000_000);
+        assert_eq!(topic.messages_count(), 0);
+    }
+
+    #[test]
+    fn test_renames_keep_messages_and_reject_taken_names() {
+        let broker = broker();
+        broker
+            .send_messages(
+                "orders",
+                "created",
+                &Partitioning::partition_id(0),
+                messages(&["a"]),
+            )
+            .unwrap();
+        broker.rename_topic("orders", "created", "placed").unwrap();
+        broker.rename_stream("orders", "sales").unwrap();
+
+        let topic = broker.get_topic("sales", "placed").unwrap();
+        assert_eq!(topic.messages_count, 1);
+        assert!(matches!(
+            broker.get_stream("orders"),
+            Err(AppError::NotFound(_))
+        ));
+
+        broker.create_stream("orders").unwrap();
+        assert!(matches!(
+            broker.rename_stream("sales", "orders"),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(matches!(
+            broker.rename_topic("sales", "missing", "other"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_resources_use_the_wrapper_error_variants() {
+        let broker = broker();
+        assert!(matches!(
+            broker.get_topic("orders", "missing"),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            broker.send_messages(
+                "orders",
+                "missing",
+                &Partitioning::balanced(),
+                messages(&["x"])
+            ),
+            Err(AppError::SendError(_))
+        ));
+        assert!(matches!(
+            broker.create_stream("orders"),
+            Err(AppError::StreamError(_))
+        ));
+        assert!(matches!(
+            broker.send_messages(
+                "orders",
+                "created",
+                &Partitioning::partition_id(2),
+                messages(&["x"])
+            ),
+            Err(AppError::SendError(_))
+        ));
+    }
+
+    #[test]
+    fn test_retention_is_enforced_and_purges_keep_offsets() {
+        let broker = broker();
+        let to_first = Partitioning::partition_id(0);
+        broker
+            .send_messages(
+                "orders",
+                "created",
+                &to_first,
+                messages(&["aa", "bb", "cc"]),
+            )
+            .unwrap();
+
+        assert!(
+            broker
+                .enforce_topic_retention("orders", "created", None, Some(4))
+                .unwrap()
+        );
+        assert!(
+            !broker
+                .enforce_topic_retention("orders", "created", None, Some(4))
+                .unwrap()
+        );
+        let all = PollParams::new(0, 1).with_offset(0).with_count(10);
+        let polled = broker.poll_messages("orders", "created", &all).unwrap();
+        assert_eq!(payloads(&polled), ["bb", "cc"]);
+
+        broker.purge_topic("orders", "created").unwrap();
+        assert!(
+            broker
+                .poll_messages("orders", "created", &all)
+                .unwrap()
+                .messages
+                .is_empty()
+        );
+        broker
+            .send_messages("orders", "created", &to_first, messages(&["dd"]))
+            .unwrap();
+        let polled = broker.poll_messages("orders", "created", &all).unwrap();
+        assert_eq!(polled.messages[0].header.offset, 3);
+    }
+}
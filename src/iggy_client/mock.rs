@@ -0,0 +1,606 @@
+//! In-process Iggy stand-in for tests (`test-util` feature).
+//!
+//! [`MockIggyClient`] implements [`IggyOperations`] and [`MessageBroker`]
+//! over in-memory streams and topics, so a
+//! [`ProducerService`](crate::services::ProducerService), a
+//! [`ConsumerService`](crate::services::ConsumerService) or the whole HTTP
+//! API (through [`AppState::with_broker`](crate::state::AppState::with_broker))
+//! can be tested without Docker:
+//!
+//! ```rust,ignore
+//! let client = MockIggyClient::new(Config::default()).with_topic("orders", "created", 3);
+//! let producer = ProducerService::new(client.clone());
+//!
+//! client.fail_next(MockOperation::Send, 1, AppError::Disconnected("injected".into()));
+//! assert!(producer.send_to("orders", "created", &event, None, None).await.is_err());
+//! producer.send_to("orders", "created", &event, None, None).await?;
+//! assert_eq!(client.sent_events("orders", "created").len(), 1);
+//! ```
+//!
+//! # Fault Injection
+//!
+//! [`fail_next`](MockIggyClient::fail_next) makes the next calls of an
+//! operation fail with a given error; [`delay_next`](MockIggyClient::delay_next)
+//! makes them take longer. Like the real client, every operation is bounded
+//! by `OPERATION_TIMEOUT_SECS` (or a `with_timeout` view's shorter timeout)
+//! and fails with `AppError::OperationTimeout` past it; under
+//! `tokio::time::pause()` timeouts are deterministic.
+//!
+//! Sends are recorded per topic in order, with the partition each went to:
+//! `balanced` takes turns over the partitions, a partition ID is taken as
+//! is and a key is hashed. They are also appended to a [`MemoryBroker`] log
+//! that polls, consumer offsets and stream/topic administration are served
+//! from, with its semantics.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use bytes::Bytes;
+use iggy::prelude::{
+    IggyExpiry, MaxTopicSize, PolledMessages, Stream, StreamDetails, Topic, TopicDetails,
+};
+use iggy::prelude::{IggyMessage, Partitioning};
+use iggy_common::PartitioningKind;
+
+use super::helpers::{copy_message, event_message};
+use super::{IggyOperations, MemoryBroker, MessageBroker, PollParams};
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::models::Event;
+
+/// Operations faults can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockOperation {
+    /// `ensure_stream` and `ensure_topic`
+    Ensure,
+    /// `partitions_count`
+    PartitionsCount,
+    /// Every send
+    Send,
+    /// `poll_messages` and the consumer offset operations
+    Poll,
+    /// Stream and topic lookups, listings and administration
+    Admin,
+}
+
+/// One injected fault.
+#[derive(Debug)]
+enum Fault {
+    Fail(AppError),
+    Delay(Duration),
+}
+
+/// A topic and what was sent to it.
+#[derive(Debug, Default)]
+struct MockTopic {
+    partitions: u32,
+    /// Message payloads, in send order
+    payloads: Vec<Bytes>,
+    /// Partition of each payload
+    sent_to: Vec<u32>,
+    /// Partition of the next `balanced` send
+    next_balanced: u32,
+}
+
+impl MockTopic {
+    /// Partition `partitioning` selects.
+    fn partition(&mut self, partitioning: &Partitioning) -> u32 {
+        let count = self.partitions.max(1);
+        match &partitioning.kind {
+            PartitioningKind::Balanced => {
+                let partition_id = self.next_balanced % count;
+                self.next_balanced = self.next_balanced.wrapping_add(1);
+                partition_id
+            }
+            PartitioningKind::PartitionId => partitioning
+                .value
+                .get(..4)
+                .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+                .map_or(0, u32::from_le_bytes),
+            PartitioningKind::MessagesKey => {
+                let mut hasher = DefaultHasher::new();
+                partitioning.value.hash(&mut hasher);
+                (hasher.finish() % u64::from(count)) as u32
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    streams: HashSet<String>,
+    topics: HashMap<(String, String), MockTopic>,
+    faults: HashMap<MockOperation, VecDeque<Fault>>,
+}
+
+/// In-memory [`MessageBroker`] implementation with fault injection.
+///
+/// Clones (and `with_timeout` views) share state, like clones of
+/// `IggyClientWrapper` share a connection.
+#[derive(Clone)]
+pub struct MockIggyClient {
+    config: Arc<Config>,
+    state: Arc<Mutex<MockState>>,
+    /// Log the sends are appended to and polls read from
+    log: MemoryBroker,
+    /// Bound on this view's operations
+    timeout: Duration,
+}
+
+impl MockIggyClient {
+    /// Create a mock with no streams.
+    pub fn new(config: Config) -> Self {
+        Self {
+            timeout: config.operation_timeout,
+            log: MemoryBroker::new(config.clone()),
+            config: Arc::new(config),
+            state: Arc::new(Mutex::new(MockState::default())),
+        }
+    }
+
+    /// Add `stream`/`topic` with `partitions` (and the stream).
+    #[must_use]
+    pub fn with_topic(self, stream: &str, topic: &str, partitions: u32) -> Self {
+        self.create_topic(stream, topic, partitions);
+        self
+    }
+
+    /// Make the next `times` calls of `operation` fail with `error`.
+    pub fn fail_next(&self, operation: MockOperation, times: usize, error: AppError) {
+        let mut state = self.lock();
+        let faults = state.faults.entry(operation).or_default();
+        faults.extend((0..times).map(|_| Fault::Fail(error.duplicate())));
+    }
+
+    /// Make the next `times` calls of `operation` take `delay` longer.
+    pub fn delay_next(&self, operation: MockOperation, times: usize, delay: Duration) {
+        let mut state = self.lock();
+        let faults = state.faults.entry(operation).or_default();
+        faults.extend((0..times).map(|_| Fault::Delay(delay)));
+    }
+
+    /// Check if `stream` exists.
+    pub fn has_stream(&self, stream: &str) -> bool {
+        self.lock().streams.contains(stream)
+    }
+
+    /// Partitions of `stream`/`topic`, if it exists.
+    pub fn topic_partitions(&self, stream: &str, topic: &str) -> Option<u32> {
+        self.lock()
+            .topics
+            .get(&(stream.to_string(), topic.to_string()))
+            .map(|topic| topic.partitions)
+    }
+
+    /// Payloads sent to `stream`/`topic`, in order.
+    pub fn sent_payloads(&self, stream: &str, topic: &str) -> Vec<Bytes> {
+        self.lock()
+            .topics
+            .get(&(stream.to_string(), topic.to_string()))
+            .map(|topic| topic.payloads.clone())
+            .unwrap_or_default()
+    }
+
+    /// Partition of each payload sent to `stream`/`topic`, in order.
+    pub fn sent_partitions(&self, stream: &str, topic: &str) -> Vec<u32> {
+        self.lock()
+            .topics
+            .get(&(stream.to_string(), topic.to_string()))
+            .map(|topic| topic.sent_to.clone())
+            .unwrap_or_default()
+    }
+
+    /// Events sent to `stream`/`topic`, in order (payloads that are not
+    /// plain event JSON are skipped).
+    pub fn sent_events(&self, stream: &str, topic: &str) -> Vec<Event> {
+        self.sent_payloads(stream, topic)
+            .iter()
+            .filter_map(|payload| serde_json::from_slice(payload).ok())
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add `stream`/`topic` unless it exists, in the log too.
+    fn create_topic(&self, stream: &str, topic: &str, partitions: u32) {
+        // Both exist or are created here, so neither call can fail
+        let _ = self.log.ensure_stream(stream);
+        let _ = self.log.ensure_topic(
+            stream,
+            topic,
+            partitions,
+            IggyExpiry::NeverExpire,
+            MaxTopicSize::Unlimited,
+        );
+        let mut state = self.lock();
+        state.streams.insert(stream.to_string());
+        if let Entry::Vacant(entry) = state.topics.entry((stream.to_string(), topic.to_string())) {
+            entry.insert(MockTopic {
+                partitions,
+                payloads: Vec::new(),
+                sent_to: Vec::new(),
+                next_balanced: 0,
+            });
+        }
+    }
+
+    /// Forget the recorded topics matching `remove`, after the log
+    /// dropped them.
+    fn forget_topics(&self, remove: impl Fn(&(String, String)) -> bool) {
+        self.lock().topics.retain(|key, _| !remove(key));
+    }
+
+    /// Move the recorded topics `rename` maps to a new key, after the log
+    /// renamed them.
+    fn rekey_topics(&self, rename: impl Fn(&(String, String)) -> Option<(String, String)>) {
+        let mut state = self.lock();
+        let renamed: Vec<_> = state
+            .topics
+            .keys()
+            .filter_map(|key| Some((key.clone(), rename(key)?)))
+            .collect();
+        for (old, new) in renamed {
+            if let Some(topic) = state.topics.remove(&old) {
+                state.topics.insert(new, topic);
+            }
+        }
+    }
+
+    /// Run `operation` after its next injected fault, within this view's
+    /// timeout.
+    async fn run<T: Send>(
+        &self,
+        operation: MockOperation,
+        apply: impl FnOnce(&Self) -> AppResult<T> + Send,
+    ) -> AppResult<T> {
+        let fault = self
+            .lock()
+            .faults
+            .get_mut(&operation)
+            .and_then(VecDeque::pop_front);
+        let faulted = async {
+            match fault {
+                Some(Fault::Fail(error)) => Err(error),
+                Some(Fault::Delay(delay)) => {
+                    tokio::time::sleep(delay).await;
+                    apply(self)
+                }
+                None => apply(self),
+            }
+        };
+        tokio::time::timeout(self.timeout, faulted)
+            .await
+            .map_err(|_| {
+                AppError::OperationTimeout(format!(
+                    "{operation:?} timed out after {:?}",
+                    self.timeout
+                ))
+            })?
+    }
+
+    /// Append `messages` to `stream`/`topic`, in one partition.
+    fn append(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: Vec<IggyMessage>,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        let mut state = self.lock();
+        let topic_state = state
+            .topics
+            .get_mut(&(stream.to_string(), topic.to_string()))
+            .ok_or_else(|| topic_not_found(stream, topic))?;
+        let partition_id = topic_state.partition(partitioning);
+        let payloads: Vec<_> = messages
+            .iter()
+            .map(|message| message.payload.clone())
+            .collect();
+        self.log.send_messages(
+            stream,
+            topic,
+            &Partitioning::partition_id(partition_id),
+            messages,
+        )?;
+        topic_state
+            .sent_to
+            .extend(std::iter::repeat_n(partition_id, payloads.len()));
+        topic_state.payloads.extend(payloads);
+        Ok(())
+    }
+}
+
+impl IggyOperations for MockIggyClient {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout: self.timeout.min(timeout),
+            ..self.clone()
+        }
+    }
+
+    async fn ensure_stream(&self, name: &str) -> AppResult<()> {
+        self.run(MockOperation::Ensure, |mock| {
+            mock.log.ensure_stream(name)?;
+            mock.lock().streams.insert(name.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    async fn ensure_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        self.run(MockOperation::Ensure, |mock| {
+            if !mock.has_stream(stream) {
+                return Err(AppError::NotFound(format!("Stream '{stream}' not found")));
+            }
+            mock.create_topic(stream, topic, partitions);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn partitions_count(&self, stream: &str, topic: &str) -> AppResult<u32> {
+        self.run(MockOperation::PartitionsCount, |mock| {
+            mock.topic_partitions(stream, topic)
+                .ok_or_else(|| topic_not_found(stream, topic))
+        })
+        .await
+    }
+
+    async fn send_event_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        let message = event_message(event)?;
+        self.run(MockOperation::Send, |mock| {
+            mock.append(stream, topic, vec![message], partitioning)
+        })
+        .await
+    }
+
+    async fn send_events_batch_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let messages = events
+            .iter()
+            .map(event_message)
+            .collect::<AppResult<Vec<_>>>()?;
+        self.run(MockOperation::Send, |mock| {
+            mock.append(stream, topic, messages, partitioning)
+        })
+        .await
+    }
+
+    async fn send_raw_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: &[IggyMessage],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        let messages = messages.iter().map(copy_message).collect();
+        self.run(MockOperation::Send, |mock| {
+            mock.append(stream, topic, messages, partitioning)
+        })
+        .await
+    }
+}
+
+impl MessageBroker for MockIggyClient {
+    async fn ensure_topic_with(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> AppResult<bool> {
+        self.run(MockOperation::Ensure, |mock| {
+            let created = mock
+                .log
+                .ensure_topic(stream, topic, partitions, expiry, max_size)?;
+            mock.create_topic(stream, topic, partitions);
+            Ok(created)
+        })
+        .await
+    }
+
+    async fn poll_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+    ) -> AppResult<PolledMessages> {
+        self.run(MockOperation::Poll, |mock| {
+            mock.log.poll_messages(stream, topic, &params)
+        })
+        .await
+    }
+
+    async fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<Option<u64>> {
+        self.run(MockOperation::Poll, |mock| {
+            mock.log
+                .get_consumer_offset(stream, topic, consumer_id, partition_id)
+        })
+        .await
+    }
+
+    async fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<()> {
+        self.run(MockOperation::Poll, |mock| {
+            mock.log
+                .store_consumer_offset(stream, topic, consumer_id, partition_id, offset)
+        })
+        .await
+    }
+
+    async fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<()> {
+        self.run(MockOperation::Poll, |mock| {
+            mock.log
+                .delete_consumer_offset(stream, topic, consumer_id, partition_id)
+        })
+        .await
+    }
+
+    async fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
+        self.run(MockOperation::Admin, |mock| mock.log.get_stream(name))
+            .await
+    }
+
+    async fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.get_topic(stream, topic)
+        })
+        .await
+    }
+
+    async fn list_streams(&self) -> AppResult<Vec<Stream>> {
+        self.run(MockOperation::Admin, |mock| Ok(mock.log.list_streams()))
+            .await
+    }
+
+    async fn list_topics(&self, stream: &str) -> AppResult<Vec<Topic>> {
+        self.run(MockOperation::Admin, |mock| mock.log.list_topics(stream))
+            .await
+    }
+
+    async fn create_stream(&self, name: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.create_stream(name)?;
+            mock.lock().streams.insert(name.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    async fn create_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.create_topic(
+                stream,
+                topic,
+                partitions,
+                IggyExpiry::NeverExpire,
+                MaxTopicSize::Unlimited,
+            )?;
+            mock.create_topic(stream, topic, partitions);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_stream(&self, name: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.delete_stream(name)?;
+            mock.lock().streams.remove(name);
+            mock.forget_topics(|(stream, _)| stream == name);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.delete_topic(stream, topic)?;
+            mock.forget_topics(|key| key.0 == stream && key.1 == topic);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rename_stream(&self, name: &str, new_name: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.rename_stream(name, new_name)?;
+            {
+                let mut state = mock.lock();
+                state.streams.remove(name);
+                state.streams.insert(new_name.to_string());
+            }
+            mock.rekey_topics(|(stream, topic)| {
+                (stream == name).then(|| (new_name.to_string(), topic.clone()))
+            });
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rename_topic(&self, stream: &str, topic: &str, new_name: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.rename_topic(stream, topic, new_name)?;
+            mock.rekey_topics(|key| {
+                (key.0 == stream && key.1 == topic)
+                    .then(|| (stream.to_string(), new_name.to_string()))
+            });
+            Ok(())
+        })
+        .await
+    }
+
+    async fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> AppResult<bool> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log
+                .enforce_topic_retention(stream, topic, max_age_secs, max_size_bytes)
+        })
+        .await
+    }
+
+    /// Empties the log; the recorded sends are kept.
+    async fn purge_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        self.run(MockOperation::Admin, |mock| {
+            mock.log.purge_topic(stream, topic)
+        })
+        .await
+    }
+}
+
+/// `AppError::NotFound` for a missing topic, worded like the wrapper's.
+fn topic_not_found(stream: &str, topic: &str) -> AppError {
+    AppError::NotFound(format!(
+        "Topic '{}' in stream '{}' not found",
+        topic, stream
+    ))
+}
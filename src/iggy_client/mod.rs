@@ -25,12 +25,28 @@
 //!
 //! # Module Structure
 //!
-//! - `circuit_breaker` - Fail-fast state machine with token-limited probing
+//! - `broker` - `MessageBroker`, the poll/offset/admin operations services use and
+//!   the backend interface the wrapper can be built around
+//! - `circuit_breaker` - Fail-fast state machine with token-limited probing,
+//!   one per operation class
 //! - `connection` - Connection state tracking for reconnection coordination
+//! - `credentials` - Credential sources for login after each (re)connect
+//! - `events` - Connection event hooks (`ConnectionObserver`)
+//! - `fair_queue` - Weighted fair queuing of operations across tenants
+//! - `failure` - `FailurePolicy`, failures injected into operations by tests
+//!   (`test-util` feature)
+//! - `health` - Lock-free degraded signal for request-path middleware
 //! - `params` - Parameter types like `PollParams`
+//! - `redelivery` - Redelivery copies of nacked messages (`redelivery_count` header)
+//! - `sequence` - Per-key sequence headers and gap/duplicate detection
 //! - `helpers` - Utility functions for identifier conversion and jitter
+//! - `memory` - In-process `MemoryBroker` behind `BROKER_BACKEND=memory`
+//! - `mock` - In-process `MockIggyClient` for tests (`test-util` feature)
+//! - `operations` - `IggyOperations`, the client operations the producer uses
 //! - `resilience` - Timeout/breaker/reconnect-retry composition (`run_resilient`)
+//! - `retry_budget` - Process-wide limit on reconnect-and-retry attempts
 //! - `scopeguard` - RAII guard for cleanup on drop
+//! - `timing` - Per-request time spent in Iggy operations (slow request logs)
 //!
 //! # Connection Resilience (two layers)
 //!
@@ -41,10 +57,14 @@
 //! - Per-operation timeouts (so requests never block unboundedly in the SDK)
 //! - Circuit breaking driven by classified connection errors AND timeouts
 //! - Live health probes (`health_check`) that keep the connection state, and
-//!   therefore `/health` and `/ready`, truthful
+//!   therefore `/health` and `/ready`, truthful; the background task
+//!   reconnects as soon as one fails (`reconnect_now`)
 //! - App-level reconnection with exponential backoff + jitter as a second
 //!   line of defense, for error classes that escape the SDK's internal retry
 //!   (see `helpers::classify_iggy_error`)
+//! - Failover across `IGGY_FALLBACK_SERVERS`: every `IGGY_FAILOVER_ATTEMPTS`
+//!   failed attempts, a reconnect session moves on to the next server
+//!   (round-robin). Startup tries each server once, in order.
 //!
 //! # Example
 //!
@@ -56,35 +76,87 @@
 //! client.send_event_default(&event, None).await?;
 //! ```
 
+mod broker;
 mod circuit_breaker;
+mod compression;
 mod connection;
+mod credentials;
+mod events;
+#[cfg(any(test, feature = "test-util"))]
+mod failure;
+mod fair_queue;
+mod health;
 mod helpers;
+mod memory;
+#[cfg(feature = "test-util")]
+mod mock;
+mod operations;
 mod params;
+mod redelivery;
 mod resilience;
+mod retry_budget;
 mod scopeguard;
+mod sequence;
+mod timing;
 
-use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use iggy::prelude::*;
+use iggy_common::{UserInfo, UserInfoDetails};
 use tokio::sync::RwLock;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::bootstrap::{ActualTopic, BootstrapSpec};
 use crate::config::Config;
 use crate::error::{AppError, AppResult};
-use crate::models::Event;
+use crate::models::{BootstrapResourceStatus, BootstrapState, Event};
+use broker::DynBroker;
+use retry_budget::RetryBudget;
 
 // Re-exports for public API
-pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+pub use broker::MessageBroker;
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitBreakers, CircuitState, OperationClass,
+};
+pub use compression::{
+    CONTENT_ENCODING_HEADER, PayloadCompression, batch_message, compress_payload,
+    decompress_payload,
+};
 pub use connection::ConnectionState;
-pub use helpers::{rand_jitter, to_identifier};
+pub use credentials::{
+    ConnectionStringCredentials, CredentialSource, IggyCredentials, PasswordFileCredentials,
+    StaticCredentials, credential_source_from_config,
+};
+pub use events::{ConnectionEvent, ConnectionObserver, ConnectionObservers};
+#[cfg(any(test, feature = "test-util"))]
+pub use failure::FailurePolicy;
+pub use fair_queue::{FairPermit, FairQueue, INTERNAL_TENANT, with_tenant};
+pub use health::HealthSignal;
+pub use helpers::{
+    CORRELATION_USER_HEADER, event_message, event_payload_message, key_partitioning,
+    payload_message, rand_jitter, to_identifier,
+};
+pub use memory::{BrokerBackend, MemoryBroker};
+#[cfg(feature = "test-util")]
+pub use mock::{MockIggyClient, MockOperation};
+pub use operations::IggyOperations;
 pub use params::PollParams;
+pub use redelivery::{
+    REDELIVERY_COUNT_HEADER, RedeliveryPolicy, redelivery_count, redelivery_message,
+};
+pub use sequence::{
+    PRODUCER_EPOCH_HEADER, SEQUENCE_HEADER, SEQUENCE_KEY_HEADER, SequenceChecker, Sequencer,
+    sequence_headers, sequenced_message,
+};
+pub use timing::{IggyTime, measure_iggy_time};
 
 // Internal-only: the error classifier's fallback contract (must be a
 // NON-connection variant) is too easy to violate to expose publicly.
-use helpers::classify_iggy_error;
+use helpers::{classify_iggy_error, copy_message, tcp_reachable};
 
 // =============================================================================
 // Constants
@@ -107,6 +179,9 @@ const MIN_RECONNECT_DELAY_MS: u64 = 100;
 /// overflow with an unbounded attempt counter.
 const MAX_BACKOFF_EXPONENT: u32 = 32;
 
+/// Timeout for the plain TCP probe that diagnoses TLS connect failures.
+const TLS_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Compute the reconnection delay for `attempt` (1-indexed): exponential
 /// backoff from `base_ms`, jittered by ±[`BACKOFF_JITTER_PERCENT`], clamped to
 /// `[MIN_RECONNECT_DELAY_MS, max_ms]`.
@@ -145,6 +220,9 @@ fn backoff_delay_ms(attempt: u32, base_ms: u64, max_ms: u64, jitter_unit: f64) -
 ///   per `open_duration` window; excess requests fail fast (see
 ///   `circuit_breaker` module docs for the token re-grant rules)
 ///
+/// Sends, polls and admin operations each have their own breaker (see
+/// [`OperationClass`]), so one failing class does not fail the others fast.
+///
 /// # Performance Considerations
 ///
 /// The client uses `RwLock<IggyClient>` for thread-safe reconnection support.
@@ -185,8 +263,27 @@ pub struct IggyClientWrapper {
     op_deadline: Duration,
     /// Connection state tracking
     state: Arc<ConnectionState>,
-    /// Circuit breaker for fail-fast during outages
-    circuit_breaker: Arc<CircuitBreaker>,
+    /// Circuit breakers for fail-fast during outages, one per operation class
+    circuit_breakers: Arc<CircuitBreakers>,
+    /// Limit on reconnect-and-retry attempts, shared by all operations
+    retry_budget: Arc<RetryBudget>,
+    /// Slots of operations shared out across tenants
+    /// (`FAIR_QUEUE_MAX_IN_FLIGHT`)
+    fair_queue: Arc<FairQueue>,
+    /// Cancelled on shutdown; aborts reconnection (see [`Self::with_shutdown`])
+    shutdown: CancellationToken,
+    /// Told about connection and circuit breaker transitions (see [`Self::observe`])
+    observers: Arc<ConnectionObservers>,
+    /// Index into `config.iggy_endpoints()` of the server in use
+    active_endpoint: Arc<AtomicUsize>,
+    /// Credentials for the explicit login after each (re)connect
+    credentials: Arc<dyn CredentialSource>,
+    /// Serves every operation instead of `client`, e.g. the
+    /// [`MemoryBroker`] of `BROKER_BACKEND=memory` (see [`Self::with_broker`])
+    broker: Option<Arc<dyn DynBroker>>,
+    /// Failures injected into operations by tests (see [`Self::with_failure_policy`])
+    #[cfg(any(test, feature = "test-util"))]
+    failure_policy: Option<Arc<FailurePolicy>>,
 }
 
 /// Clamp a requested per-request deadline to the configured global timeout:
@@ -195,6 +292,11 @@ fn clamp_deadline(requested: Duration, global: Duration) -> Duration {
     requested.min(global)
 }
 
+/// Error of a reconnection aborted by shutdown.
+fn shutting_down() -> AppError {
+    AppError::ShuttingDown("Reconnection aborted: the service is shutting down".to_string())
+}
+
 impl IggyClientWrapper {
     /// Create a new Iggy client wrapper from configuration.
     ///
@@ -209,39 +311,159 @@ impl IggyClientWrapper {
     /// - The connection string is invalid
     /// - The server is unreachable within the operation timeout
     /// - Authentication fails
-    #[instrument(skip(config), fields(connection_string = %config.iggy_connection_string))]
+    ///
+    /// Returns `AppError::TlsError` if TLS is enabled and the server is
+    /// reachable but the handshake fails (see [`Self::connect`]), and
+    /// `AppError::AuthenticationFailed` if the configured credentials are
+    /// rejected.
+    ///
+    /// With `BROKER_BACKEND=memory` nothing is connected: the wrapper is
+    /// built around a fresh [`MemoryBroker`] (see [`Self::with_broker`]).
     pub async fn new(config: Config) -> AppResult<Self> {
+        let credentials = credential_source_from_config(&config);
+        Self::with_credentials(config, credentials).await
+    }
+
+    /// Create a wrapper that logs in with `credentials` after every
+    /// (re)connect instead of the source selected by `IGGY_USERNAME` /
+    /// `IGGY_PASSWORD` / `IGGY_PASSWORD_FILE`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::new`].
+    #[instrument(
+        skip(config, credentials),
+        fields(connection_string = %config.iggy_connection_string)
+    )]
+    pub async fn with_credentials(
+        config: Config,
+        credentials: Arc<dyn CredentialSource>,
+    ) -> AppResult<Self> {
+        if config.broker_backend == BrokerBackend::Memory {
+            warn!("Using the in-memory broker backend; messages are lost on exit");
+            return Ok(Self::with_broker(config.clone(), MemoryBroker::new(config)));
+        }
         info!("Initializing Iggy client");
 
-        let client = IggyClient::from_connection_string(&config.iggy_connection_string)
+        let client = IggyClient::from_connection_string(&config.iggy_client_connection_string())
             .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
 
-        // Initialize circuit breaker from config
-        let circuit_breaker_config = CircuitBreakerConfig::new(
-            config.circuit_breaker_failure_threshold,
-            config.circuit_breaker_success_threshold,
-            config.circuit_breaker_open_duration,
+        let wrapper = Self::assemble(config, client, credentials, None);
+        crate::metrics::set_active_endpoint(wrapper.active_endpoint().as_deref(), None);
+
+        wrapper.connect_initial().await?;
+        Ok(wrapper)
+    }
+
+    /// Create a wrapper whose operations are served by `broker` instead of
+    /// an Iggy server.
+    ///
+    /// Nothing is connected: the SDK client is never used and the wrapper
+    /// starts out connected. Operations still go through the fair queue,
+    /// deadline, circuit breakers and metrics like SDK-backed ones.
+    pub fn with_broker<B: MessageBroker>(config: Config, broker: B) -> Self {
+        let credentials = credential_source_from_config(&config);
+        let wrapper = Self::assemble(
+            config,
+            IggyClient::default(),
+            credentials,
+            Some(Arc::new(broker)),
+        );
+        wrapper.mark_connected(true);
+        wrapper
+    }
+
+    /// Build the wrapper and its resilience state from `config`.
+    fn assemble(
+        config: Config,
+        client: IggyClient,
+        credentials: Arc<dyn CredentialSource>,
+        broker: Option<Arc<dyn DynBroker>>,
+    ) -> Self {
+        // Initialize the circuit breakers from config
+        let breaker_config = |class| {
+            CircuitBreakerConfig::new(
+                config.circuit_breaker_failure_threshold_for(class),
+                config.circuit_breaker_success_threshold,
+                config.circuit_breaker_open_duration,
+            )
+        };
+        let observers = Arc::new(ConnectionObservers::default());
+        let circuit_breakers = CircuitBreakers::new(
+            breaker_config(OperationClass::Send),
+            breaker_config(OperationClass::Poll),
+            breaker_config(OperationClass::Admin),
+        )
+        .with_observers(&observers);
+
+        let retry_budget = RetryBudget::new(config.retry_budget_per_sec);
+        if retry_budget.is_enabled() {
+            info!(
+                per_second = config.retry_budget_per_sec,
+                "Limiting reconnect-and-retry attempts"
+            );
+        }
+        let fair_queue = FairQueue::new(
+            config.fair_queue_max_in_flight,
+            config.fair_queue_tenant_max_in_flight,
+            config.fair_queue_weights.clone(),
+            config.fair_queue_starvation_threshold,
         );
 
-        let wrapper = Self {
+        Self {
             client: Arc::new(RwLock::new(client)),
             op_deadline: config.operation_timeout,
             config: Arc::new(config),
             state: Arc::new(ConnectionState::new()),
-            circuit_breaker: Arc::new(CircuitBreaker::new(circuit_breaker_config)),
-        };
+            circuit_breakers: Arc::new(circuit_breakers),
+            retry_budget: Arc::new(retry_budget),
+            fair_queue: Arc::new(fair_queue),
+            shutdown: CancellationToken::new(),
+            observers,
+            active_endpoint: Arc::new(AtomicUsize::new(0)),
+            credentials,
+            broker,
+            #[cfg(any(test, feature = "test-util"))]
+            failure_policy: None,
+        }
+    }
 
-        let timeout = wrapper.config.operation_timeout;
-        tokio::time::timeout(timeout, wrapper.connect())
-            .await
-            .map_err(|_| {
-                AppError::ConnectionFailed(format!(
+    /// First connection, trying each of `config.iggy_endpoints()` once in
+    /// order, each bounded by `OPERATION_TIMEOUT_SECS`. Only an unreachable
+    /// server moves on to the next; TLS and login failures are returned.
+    async fn connect_initial(&self) -> AppResult<()> {
+        let timeout = self.config.operation_timeout;
+        let endpoints = self.config.iggy_endpoints().len().max(1);
+
+        let mut index = 0;
+        loop {
+            let error = match tokio::time::timeout(timeout, self.connect()).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(e @ AppError::ConnectionFailed(_))) => e,
+                Ok(Err(e)) => return Err(e),
+                Err(_) => AppError::ConnectionFailed(format!(
                     "Initial connection timed out after {:?}",
                     timeout
-                ))
-            })??;
+                )),
+            };
+            index += 1;
+            if index == endpoints {
+                return Err(error);
+            }
+            warn!(
+                endpoint = ?self.active_endpoint(),
+                error = %error,
+                "Iggy server unreachable at startup; trying the next one"
+            );
 
-        Ok(wrapper)
+            let next = IggyClient::from_connection_string(
+                &self.config.iggy_client_connection_string_for(index),
+            )
+            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+            let old_client = std::mem::replace(&mut *self.client.write().await, next);
+            let _ = old_client.shutdown().await;
+            self.switch_endpoint(index);
+        }
     }
 
     // =========================================================================
@@ -252,21 +474,131 @@ impl IggyClientWrapper {
     ///
     /// This is called automatically during construction and reconnection.
     /// Direct calls are rarely needed.
+    ///
+    /// # TLS Failures
+    ///
+    /// The SDK reports a failed TLS handshake with the same
+    /// `CannotEstablishConnection` as an unreachable server. With TLS
+    /// enabled, a plain TCP probe tells the two apart: if the server accepts
+    /// a TCP connection, the failure happened at the TLS layer (untrusted
+    /// certificate or domain mismatch) and is reported as
+    /// `AppError::TlsError`, which is not retried.
     #[instrument(skip(self))]
     pub async fn connect(&self) -> AppResult<()> {
+        if self.broker.is_some() {
+            return Ok(());
+        }
         let client = self.client.read().await;
 
-        client
-            .connect()
-            .await
-            .map_err(|e| AppError::ConnectionFailed(e.to_string()))?;
+        if let Err(e) = client.connect().await {
+            return Err(self.classify_connect_error(e).await);
+        }
+        self.authenticate(&client).await?;
 
-        self.state.set_connected(true);
+        self.mark_connected(true);
         info!("Successfully connected to Iggy server");
 
         Ok(())
     }
 
+    /// Log in on `client` with the configured credential source.
+    ///
+    /// A no-op for [`ConnectionStringCredentials`], whose login the SDK
+    /// performs inside `connect()`. Credentials are fetched on every call,
+    /// so a rotated password file is picked up by the next reconnect.
+    async fn authenticate(&self, client: &IggyClient) -> AppResult<()> {
+        let Some(credentials) = self.credentials.credentials()? else {
+            return Ok(());
+        };
+
+        client
+            .login_user(&credentials.username, &credentials.password)
+            .await
+            .map_err(|e| classify_iggy_error(e, AppError::AuthenticationFailed))?;
+
+        debug!(username = %credentials.username, "Authenticated with Iggy server");
+        Ok(())
+    }
+
+    /// Map an SDK connect error, separating TLS handshake failures from
+    /// unreachable servers (see [`Self::connect`]).
+    async fn classify_connect_error(&self, error: IggyError) -> AppError {
+        if self.config.iggy_tls_enabled
+            && matches!(error, IggyError::CannotEstablishConnection)
+            && let Some(address) = self.active_endpoint()
+            && tcp_reachable(&address, TLS_PROBE_TIMEOUT).await
+        {
+            return AppError::TlsError(format!(
+                "{address} accepts TCP connections but the TLS handshake failed; check that \
+                 its certificate is trusted by IGGY_TLS_CA_PATH and matches IGGY_TLS_DOMAIN \
+                 ({error})"
+            ));
+        }
+        AppError::ConnectionFailed(error.to_string())
+    }
+
+    /// Update the connected flag, telling observers when it changes.
+    fn mark_connected(&self, connected: bool) {
+        if self.state.set_connected(connected) == connected {
+            return;
+        }
+        self.observers.notify(if connected {
+            ConnectionEvent::Connected
+        } else {
+            ConnectionEvent::Disconnected
+        });
+    }
+
+    /// `host:port` of the Iggy server in use: the one connected to, or
+    /// the one being tried while reconnecting. `None` if the connection
+    /// string has no `://` separator.
+    pub fn active_endpoint(&self) -> Option<String> {
+        let index = self.active_endpoint.load(Ordering::SeqCst);
+        self.config
+            .iggy_endpoints()
+            .get(index)
+            .map(|endpoint| endpoint.to_string())
+    }
+
+    /// Endpoint for reconnect `attempt` (1-indexed) of a session: after
+    /// every `IGGY_FAILOVER_ATTEMPTS` failed attempts, the next one in
+    /// `config.iggy_endpoints()`, wrapping around.
+    fn failover_if_due(&self, attempt: u32) -> usize {
+        let current = self.active_endpoint.load(Ordering::SeqCst);
+        let endpoints = self.config.iggy_endpoints().len();
+        let per_endpoint = self.config.iggy_failover_attempts.max(1);
+        if endpoints < 2 || attempt == 1 || !(attempt - 1).is_multiple_of(per_endpoint) {
+            return current;
+        }
+        let next = (current + 1) % endpoints;
+        self.switch_endpoint(next);
+        crate::metrics::record_failover();
+        warn!(
+            attempts = self.config.iggy_failover_attempts,
+            endpoint = ?self.active_endpoint(),
+            "Iggy server unreachable; failing over to the next one"
+        );
+        next
+    }
+
+    /// Make endpoint `index` the active one.
+    fn switch_endpoint(&self, index: usize) {
+        let previous = self.active_endpoint();
+        self.active_endpoint.store(index, Ordering::SeqCst);
+        crate::metrics::set_active_endpoint(self.active_endpoint().as_deref(), previous.as_deref());
+    }
+
+    /// Mark the connection lost without touching it, as chaos testing's
+    /// simulated disconnect (`chaos` feature).
+    ///
+    /// Operations that time out meanwhile reconnect, and the next live
+    /// health probe marks the connection up again if the server answers.
+    #[cfg(feature = "chaos")]
+    pub fn simulate_disconnect(&self) {
+        self.mark_connected(false);
+        crate::metrics::set_connection_status(false);
+    }
+
     /// Check if the client is currently connected.
     ///
     /// Note: This reflects the last known state. Use `health_check()` for
@@ -277,34 +609,38 @@ impl IggyClientWrapper {
 
     /// Perform a live connectivity check against the Iggy server.
     ///
-    /// Sends a `ping` bounded by the configured operation timeout and updates
-    /// the tracked connection state with the result. This is what keeps
+    /// Sends a `ping` bounded by `HEALTH_CHECK_TIMEOUT_MS` (short, so a dead
+    /// TCP connection is noticed in seconds) and updates the tracked
+    /// connection state with the result. This is what keeps
     /// `/health` and `/ready` truthful during an outage: the SDK's internal
     /// transport reconnection swallows most mid-operation failures, so without
     /// an active probe the connected flag would stay latched at its startup
     /// value.
     ///
-    /// Called periodically by the background health-check task; safe to call
-    /// from handlers as well.
+    /// Called periodically by the background health-check task and by
+    /// `/ready`; safe to call from other handlers as well.
     ///
     /// # Lock interaction
     ///
     /// The wrapper read guard is held for the probe's duration (the SDK
     /// client is not `Clone`, so the handle cannot be snapshotted out).
-    /// During an outage a probe can hold it for up to the operation timeout,
+    /// During an outage a probe can hold it for up to the health-check timeout,
     /// delaying — but not cancelling — a concurrent reconnect swap, which
     /// runs in a detached task (see `reconnect_bounded`) and simply acquires
     /// the write lock when the probe finishes. A probe that started against
     /// the OLD client and completes after a swap may briefly overwrite the
     /// fresh connected state; the next tick corrects it.
     pub async fn health_check(&self) -> bool {
+        if self.broker.is_some() {
+            return true;
+        }
         let result = {
             let client = self.client.read().await;
-            tokio::time::timeout(self.config.operation_timeout, client.ping()).await
+            tokio::time::timeout(self.config.health_check_timeout, client.ping()).await
         };
 
         let healthy = matches!(result, Ok(Ok(())));
-        self.state.set_connected(healthy);
+        self.mark_connected(healthy);
         crate::metrics::set_connection_status(healthy);
         if !healthy {
             debug!("Live health check failed: server did not answer ping in time");
@@ -312,6 +648,24 @@ impl IggyClientWrapper {
         healthy
     }
 
+    /// Reconnect now, without waiting for an operation to fail.
+    ///
+    /// Used by the background health-check task after a failed live probe,
+    /// so the connection is repaired before the next request pays for it.
+    /// Joins a reconnect session already in progress instead of starting a
+    /// second one; runs until it succeeds, `MAX_RECONNECT_ATTEMPTS` is
+    /// exhausted, or shutdown (see [`Self::with_shutdown`]).
+    ///
+    /// # Errors
+    ///
+    /// The reconnect session's error (see `reconnect`).
+    pub async fn reconnect_now(&self) -> AppResult<()> {
+        if self.broker.is_some() {
+            return Ok(());
+        }
+        self.reconnect().await
+    }
+
     /// Attempt to reconnect to the Iggy server with exponential backoff.
     ///
     /// This method is called automatically when operations fail due to connection issues.
@@ -326,8 +680,17 @@ impl IggyClientWrapper {
     ///
     /// - `Ok(())` if reconnection succeeds
     /// - `Err(AppError::ConnectionFailed)` if max attempts exceeded or reconnection fails
+    /// - `Err(AppError::AuthenticationFailed)` if max attempts exceeded and
+    ///   the last attempt connected but could not log in
+    /// - `Err(AppError::ShuttingDown)` if the shutdown token is cancelled
+    ///   while waiting between or on attempts
     #[instrument(skip(self))]
     async fn reconnect(&self) -> AppResult<()> {
+        if self.broker.is_some() {
+            // Nothing to reconnect; a broker failure is its own error
+            self.mark_connected(true);
+            return Ok(());
+        }
         // Prevent multiple concurrent reconnection attempts
         if !self.state.start_reconnecting() {
             debug!("Reconnection already in progress, waiting for completion...");
@@ -350,14 +713,20 @@ impl IggyClientWrapper {
             self.state.stop_reconnecting();
         });
 
-        self.state.set_connected(false);
+        self.mark_connected(false);
         // Start each reconnection session with a fresh attempt counter so a
         // previously exhausted session cannot poison this one into failing
         // immediately (and so the backoff exponent reflects THIS session).
         self.state.reset_attempts();
         let max_attempts = self.config.max_reconnect_attempts;
+        // Set when the most recent attempt connected but failed to log in,
+        // so exhausting the attempts reports the credentials, not the network.
+        let mut last_auth_error: Option<AppError> = None;
 
         loop {
+            if self.shutdown.is_cancelled() {
+                return Err(shutting_down());
+            }
             let attempt = self.state.increment_attempts();
             crate::metrics::record_reconnect_attempt();
 
@@ -367,11 +736,16 @@ impl IggyClientWrapper {
                     attempts = attempt - 1,
                     max_attempts, "Maximum reconnection attempts exceeded"
                 );
+                if let Some(auth_error) = last_auth_error {
+                    return Err(auth_error);
+                }
                 return Err(AppError::ConnectionFailed(format!(
                     "Failed to reconnect after {} attempts",
                     max_attempts
                 )));
             }
+            last_auth_error = None;
+            let endpoint = self.failover_if_due(attempt);
 
             let final_delay = backoff_delay_ms(
                 attempt,
@@ -386,10 +760,15 @@ impl IggyClientWrapper {
                 "Attempting to reconnect to Iggy server"
             );
 
-            sleep(Duration::from_millis(final_delay)).await;
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return Err(shutting_down()),
+                _ = sleep(Duration::from_millis(final_delay)) => {}
+            }
 
             // Create a new client instance for reconnection
-            match IggyClient::from_connection_string(&self.config.iggy_connection_string) {
+            match IggyClient::from_connection_string(
+                &self.config.iggy_client_connection_string_for(endpoint),
+            ) {
                 Ok(new_client) => {
                     // Bound the connect: the SDK's internal reconnection would
                     // otherwise retry inside connect() indefinitely. HALF the
@@ -401,7 +780,17 @@ impl IggyClientWrapper {
                     // still time out mid-attempt; they just report the
                     // timeout while the session continues.)
                     let attempt_timeout = self.config.operation_timeout / 2;
-                    match tokio::time::timeout(attempt_timeout, new_client.connect()).await {
+                    let connected = tokio::select! {
+                        _ = self.shutdown.cancelled() => None,
+                        result = tokio::time::timeout(attempt_timeout, new_client.connect()) => {
+                            Some(result)
+                        }
+                    };
+                    let Some(connected) = connected else {
+                        let _ = new_client.shutdown().await;
+                        return Err(shutting_down());
+                    };
+                    match connected {
                         Ok(Ok(())) => {}
                         Ok(Err(e)) => {
                             warn!(attempt, error = %e, "Reconnection attempt failed");
@@ -418,6 +807,20 @@ impl IggyClientWrapper {
                         }
                     }
 
+                    // Log in on the NEW client before it replaces the old
+                    // one, so operations never run on an unauthenticated
+                    // session. A rejected login is retried with backoff:
+                    // the credential source is re-read each attempt, so a
+                    // rotated secret can still succeed.
+                    if let Err(e) = self.authenticate(&new_client).await {
+                        warn!(attempt, error = %e, "Re-authentication after reconnect failed");
+                        let _ = new_client.shutdown().await;
+                        if matches!(e, AppError::AuthenticationFailed(_)) {
+                            last_auth_error = Some(e);
+                        }
+                        continue;
+                    }
+
                     // Successfully reconnected - swap the client and shut down
                     // the old one. Without shutdown() the old client's detached
                     // heartbeat task keeps running and can re-establish a
@@ -432,7 +835,7 @@ impl IggyClientWrapper {
                         warn!(error = %e, "Old client shutdown failed; its heartbeat task may persist");
                     }
 
-                    self.state.set_connected(true);
+                    self.mark_connected(true);
                     info!(attempt, "Successfully reconnected to Iggy server");
                     return Ok(());
                 }
@@ -484,33 +887,118 @@ impl IggyClientWrapper {
 
     /// Execute an operation with automatic reconnection on connection failure.
     ///
-    /// This is the core resilience mechanism: circuit-breaker gate, timeout
-    /// bound, and reconnect-plus-single-retry on classified connection
-    /// errors. The composition itself lives in [`resilience::run_resilient`]
-    /// (see its module docs for the full semantics, worst-case latency, and
-    /// breaker false-positive analysis); this method binds it to the
-    /// wrapper's breaker, this view's deadline, tracked connection state,
-    /// and bounded reconnect session.
+    /// This is the core resilience mechanism: circuit-breaker gate, a
+    /// deadline budget shared by every attempt, and
+    /// reconnect-plus-single-retry on classified connection errors within
+    /// what is left of it. The composition itself lives in
+    /// [`resilience::run_resilient`] (see its module docs for the full
+    /// semantics, worst-case latency, and breaker false-positive analysis);
+    /// this method binds it to the
+    /// breaker of `kind`'s [`OperationClass`], this view's deadline, tracked
+    /// connection state, and bounded reconnect session. The reconnect and
+    /// retry first take a token from the retry budget; with none left the
+    /// operation fails with `RetryBudgetExhausted` instead.
     ///
     /// A timeout counts as a circuit-breaker failure only when this view
     /// runs at the global deadline: a client-shortened deadline expiring
     /// says nothing about an outage, and letting it feed the shared breaker
     /// would let one client open the circuit for everyone.
-    async fn with_reconnect<F, Fut, T>(&self, operation: F) -> AppResult<T>
+    ///
+    /// Every call is timed into `iggy_operation_duration_seconds` under
+    /// `kind`, with its outcome and whether it went through reconnect and
+    /// retry (also counted in `iggy_operation_retries_total`).
+    ///
+    /// With `FAIR_QUEUE_MAX_IN_FLIGHT` set, the call first waits up to this
+    /// view's deadline for a slot of the current tenant (see
+    /// [`FairQueue`]), held until it returns.
+    async fn with_reconnect<F, Fut, T>(&self, kind: &'static str, operation: F) -> AppResult<T>
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = AppResult<T>>,
     {
+        // One deadline for the whole operation, queueing for a slot included
+        let deadline = tokio::time::Instant::now() + self.op_deadline;
+        let _slot = self
+            .fair_queue
+            .acquire(fair_queue::current_tenant(), self.op_deadline)
+            .await?;
+
+        // Injected failures run inside the timeout and breaker, like real ones
+        #[cfg(any(test, feature = "test-util"))]
+        let operation = {
+            let policy = self.failure_policy.as_deref();
+            move || {
+                let attempt = operation();
+                async move {
+                    if let Some(policy) = policy {
+                        policy.before_attempt().await?;
+                    }
+                    attempt.await
+                }
+            }
+        };
         let timeout_is_outage_signal = self.op_deadline >= self.config.operation_timeout;
-        resilience::run_resilient(
-            &self.circuit_breaker,
-            self.op_deadline,
+        let class = OperationClass::of(kind);
+        let started = Instant::now();
+        let retried = AtomicBool::new(false);
+        let result = resilience::run_resilient(
+            self.circuit_breakers.get(class),
+            deadline.saturating_duration_since(tokio::time::Instant::now()),
             timeout_is_outage_signal,
             || self.state.is_connected(),
-            || self.reconnect_bounded(),
+            || async {
+                // run_resilient reconnects only on its way to the retry
+                self.retry_budget.acquire()?;
+                retried.store(true, Ordering::Relaxed);
+                self.reconnect_bounded().await
+            },
             operation,
         )
-        .await
+        .await;
+        let elapsed = started.elapsed();
+        timing::record_iggy_op(elapsed);
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(e) => e.code(),
+        };
+        crate::metrics::record_operation(
+            kind,
+            outcome,
+            retried.load(Ordering::Relaxed),
+            elapsed.as_secs_f64(),
+        );
+
+        match result {
+            Err(e) if e.is_retryable() => Err(self.annotate_retry_hint(class, e).await),
+            other => other,
+        }
+    }
+
+    /// Attach a server-computed retry hint to a retryable error.
+    ///
+    /// Circuit-open rejections get the remaining open (or probe re-grant)
+    /// window of `class`'s breaker; connection errors get the un-jittered
+    /// reconnect backoff for the current attempt, and retry budget
+    /// rejections a jittered token interval. Timeouts carry no hint - the
+    /// broker may be fine and only this request was slow.
+    async fn annotate_retry_hint(&self, class: OperationClass, error: AppError) -> AppError {
+        let hint = match error.kind() {
+            AppError::CircuitOpen(_) => self.circuit_breakers.get(class).retry_after().await,
+            AppError::RetryBudgetExhausted(_) => Some(self.retry_budget.retry_after()),
+            AppError::ConnectionFailed(_)
+            | AppError::Disconnected(_)
+            | AppError::ConnectionReset(_) => Some(Duration::from_millis(backoff_delay_ms(
+                self.state.attempts().max(1),
+                self.config.reconnect_base_delay.as_millis() as u64,
+                self.config.reconnect_max_delay.as_millis() as u64,
+                0.5,
+            ))),
+            _ => None,
+        };
+        match hint {
+            Some(retry_after) => error.with_retry_after(retry_after),
+            None => error,
+        }
     }
 
     // =========================================================================
@@ -523,7 +1011,10 @@ impl IggyClientWrapper {
     /// will not create duplicate streams.
     #[instrument(skip(self))]
     pub async fn ensure_stream(&self, name: &str) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("ensure_stream", || async {
+            if let Some(broker) = &self.broker {
+                return broker.ensure_stream(name).await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(name, "stream")?;
 
@@ -561,7 +1052,61 @@ impl IggyClientWrapper {
     /// will not create duplicate topics.
     #[instrument(skip(self))]
     pub async fn ensure_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.ensure_topic_with(
+            stream,
+            topic,
+            partitions,
+            IggyExpiry::NeverExpire,
+            MaxTopicSize::Unlimited,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Ensure each of `topics` (name, partitions) exists within `stream`,
+    /// creating the missing ones.
+    ///
+    /// Returns one result per topic, in order: `true` if it was created,
+    /// `false` if it already existed (whatever its partition count). Topics
+    /// are handled one at a time and a failure does not stop the others.
+    #[instrument(skip(self, topics), fields(count = topics.len()))]
+    pub async fn ensure_topics(
+        &self,
+        stream: &str,
+        topics: &[(String, u32)],
+    ) -> Vec<AppResult<bool>> {
+        let mut results = Vec::with_capacity(topics.len());
+        for (topic, partitions) in topics {
+            results.push(
+                self.ensure_topic_with(
+                    stream,
+                    topic,
+                    *partitions,
+                    IggyExpiry::NeverExpire,
+                    MaxTopicSize::Unlimited,
+                )
+                .await,
+            );
+        }
+        results
+    }
+
+    /// [`ensure_topic`](Self::ensure_topic) with the expiry and size limit
+    /// a missing topic is created with; whether it was created.
+    async fn ensure_topic_with(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        expiry: IggyExpiry,
+        max_size: MaxTopicSize,
+    ) -> AppResult<bool> {
+        self.with_reconnect("ensure_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .ensure_topic_with(stream, topic, partitions, expiry, max_size)
+                    .await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(stream, "stream")?;
             let topic_id = to_identifier(topic, "topic")?;
@@ -569,7 +1114,7 @@ impl IggyClientWrapper {
             match client.get_topic(&stream_id, &topic_id).await {
                 Ok(Some(_)) => {
                     debug!(stream, topic, "Topic already exists");
-                    Ok(())
+                    Ok(false)
                 }
                 Ok(None) => {
                     info!(stream, topic, partitions, "Creating topic");
@@ -580,17 +1125,17 @@ impl IggyClientWrapper {
                             partitions,
                             Default::default(),
                             None,
-                            IggyExpiry::NeverExpire,
-                            MaxTopicSize::Unlimited,
+                            expiry,
+                            max_size,
                         )
                         .await
                     {
-                        Ok(_) => Ok(()),
+                        Ok(_) => Ok(true),
                         // Lost a creation race - the topic exists, which is
                         // all this method guarantees.
                         Err(IggyError::TopicNameAlreadyExists(_, _)) => {
                             debug!(stream, topic, "Topic was created concurrently");
-                            Ok(())
+                            Ok(false)
                         }
                         Err(e) => Err(classify_iggy_error(e, AppError::TopicError)),
                     }
@@ -602,7 +1147,8 @@ impl IggyClientWrapper {
         .await
     }
 
-    /// Initialize default stream and topic from configuration.
+    /// Initialize default stream and topic from configuration, then the
+    /// streams and topics of the bootstrap spec, if one is configured.
     ///
     /// Call this after creating the wrapper to ensure the default
     /// stream and topic exist before sending messages.
@@ -615,9 +1161,106 @@ impl IggyClientWrapper {
             self.config.topic_partitions,
         )
         .await?;
+        if let Some(spec) = &self.config.bootstrap {
+            self.apply_bootstrap(spec).await?;
+        }
         Ok(())
     }
 
+    /// Create the streams and topics of `spec` that do not exist yet.
+    ///
+    /// Existing ones are left untouched, so this is safe to run on every
+    /// start; see [`bootstrap_status`](Self::bootstrap_status) for drift.
+    #[instrument(skip(self, spec))]
+    pub async fn apply_bootstrap(&self, spec: &BootstrapSpec) -> AppResult<()> {
+        for stream in &spec.streams {
+            self.ensure_stream(&stream.name).await?;
+        }
+        let topics = spec.planned_topics();
+        for topic in &topics {
+            let expiry = topic
+                .message_expiry_secs
+                .map_or(IggyExpiry::ServerDefault, |secs| {
+                    IggyExpiry::ExpireDuration(IggyDuration::new(Duration::from_secs(secs)))
+                });
+            let max_size = topic
+                .max_size_bytes
+                .map_or(MaxTopicSize::ServerDefault, |bytes| {
+                    MaxTopicSize::Custom(IggyByteSize::from(bytes))
+                });
+            self.ensure_topic_with(
+                &topic.stream,
+                &topic.topic,
+                topic.partitions,
+                expiry,
+                max_size,
+            )
+            .await?;
+        }
+        info!(
+            streams = spec.streams.len(),
+            topics = topics.len(),
+            "Bootstrap spec applied"
+        );
+        Ok(())
+    }
+
+    /// Compare the server with `spec`: one entry per stream and per topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first lookup error other than a missing stream or topic.
+    #[instrument(skip(self, spec))]
+    pub async fn bootstrap_status(
+        &self,
+        spec: &BootstrapSpec,
+    ) -> AppResult<Vec<BootstrapResourceStatus>> {
+        let mut statuses = Vec::new();
+        let mut missing_streams = Vec::new();
+        for stream in &spec.streams {
+            let state = match self.get_stream(&stream.name).await {
+                Ok(_) => BootstrapState::InSync,
+                Err(AppError::NotFound(_)) => {
+                    missing_streams.push(stream.name.as_str());
+                    BootstrapState::Missing
+                }
+                Err(e) => return Err(e),
+            };
+            statuses.push(BootstrapResourceStatus {
+                stream: stream.name.clone(),
+                topic: None,
+                state,
+                drift: Vec::new(),
+            });
+        }
+
+        for topic in spec.planned_topics() {
+            let (state, drift) = if missing_streams.contains(&topic.stream.as_str()) {
+                (BootstrapState::Missing, Vec::new())
+            } else {
+                match self.get_topic(&topic.stream, &topic.topic).await {
+                    Ok(details) => {
+                        let drift = topic.drift(&actual_topic(&details));
+                        if drift.is_empty() {
+                            (BootstrapState::InSync, drift)
+                        } else {
+                            (BootstrapState::Drifted, drift)
+                        }
+                    }
+                    Err(AppError::NotFound(_)) => (BootstrapState::Missing, Vec::new()),
+                    Err(e) => return Err(e),
+                }
+            };
+            statuses.push(BootstrapResourceStatus {
+                stream: topic.stream,
+                topic: Some(topic.topic),
+                state,
+                drift,
+            });
+        }
+        Ok(statuses)
+    }
+
     // =========================================================================
     // Message Sending
     // =========================================================================
@@ -644,25 +1287,39 @@ impl IggyClientWrapper {
         event: &Event,
         partition_key: Option<&str>,
     ) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        let partitioning = key_partitioning(partition_key)?;
+        self.send_event_partitioned(stream, topic, event, &partitioning)
+            .await
+    }
+
+    /// Send an event with an explicit SDK [`Partitioning`].
+    ///
+    /// Used when the caller has already resolved the target partition, e.g.
+    /// `Partitioning::partition_id` for explicit or sticky partitioning.
+    #[instrument(skip(self, event, partitioning), fields(event_id = %event.id))]
+    pub async fn send_event_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        self.with_reconnect("send", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .send_event_partitioned(stream, topic, event, partitioning)
+                    .await;
+            }
             let client = self.client.read().await;
 
-            let payload = serde_json::to_string(event)?;
-            let message =
-                IggyMessage::from_str(&payload).map_err(|e| AppError::SendError(e.to_string()))?;
+            let message = event_message(event)?;
 
             let stream_id = to_identifier(stream, "stream")?;
             let topic_id = to_identifier(topic, "topic")?;
 
-            let partitioning = match partition_key {
-                Some(key) => Partitioning::messages_key_str(key)
-                    .map_err(|e| AppError::SendError(e.to_string()))?,
-                None => Partitioning::balanced(),
-            };
-
             let mut messages = vec![message];
             client
-                .send_messages(&stream_id, &topic_id, &partitioning, &mut messages)
+                .send_messages(&stream_id, &topic_id, partitioning, &mut messages)
                 .await
                 .map_err(|e| classify_iggy_error(e, AppError::SendError))?;
 
@@ -713,35 +1370,41 @@ impl IggyClientWrapper {
         topic: &str,
         events: &[Event],
         partition_key: Option<&str>,
+    ) -> AppResult<()> {
+        let partitioning = key_partitioning(partition_key)?;
+        self.send_events_batch_partitioned(stream, topic, events, &partitioning)
+            .await
+    }
+
+    /// Send a batch of events with an explicit SDK [`Partitioning`].
+    #[instrument(skip(self, events, partitioning), fields(batch_size = events.len()))]
+    pub async fn send_events_batch_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partitioning: &Partitioning,
     ) -> AppResult<()> {
         if events.is_empty() {
             return Ok(());
         }
 
-        self.with_reconnect(|| async {
+        self.with_reconnect("send_batch", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .send_events_batch_partitioned(stream, topic, events, partitioning)
+                    .await;
+            }
             let client = self.client.read().await;
 
             let stream_id = to_identifier(stream, "stream")?;
             let topic_id = to_identifier(topic, "topic")?;
 
-            let partitioning = match partition_key {
-                Some(key) => Partitioning::messages_key_str(key)
-                    .map_err(|e| AppError::SendError(e.to_string()))?,
-                None => Partitioning::balanced(),
-            };
-
-            // Convert all events to messages in one pass
-            let mut messages: Vec<IggyMessage> = events
-                .iter()
-                .map(|event| {
-                    let payload = serde_json::to_string(event)?;
-                    IggyMessage::from_str(&payload).map_err(|e| AppError::SendError(e.to_string()))
-                })
-                .collect::<AppResult<Vec<_>>>()?;
+            let mut messages = self.batch_messages(events)?;
 
             // Send all messages in a single network call
             client
-                .send_messages(&stream_id, &topic_id, &partitioning, &mut messages)
+                .send_messages(&stream_id, &topic_id, partitioning, &mut messages)
                 .await
                 .map_err(|e| classify_iggy_error(e, AppError::SendError))?;
 
@@ -751,6 +1414,57 @@ impl IggyClientWrapper {
         .await
     }
 
+    /// Convert all events to messages in one pass, compressing payloads
+    /// when BATCH_COMPRESSION is enabled.
+    fn batch_messages(&self, events: &[Event]) -> AppResult<Vec<IggyMessage>> {
+        let compression = self.config.batch_compression;
+        let threshold = self.config.compression_threshold_bytes;
+        events
+            .iter()
+            .map(|event| compression::batch_message(event, compression, threshold))
+            .collect()
+    }
+
+    /// Send already-built messages (e.g. redelivery copies) as-is.
+    #[instrument(skip(self, messages, partitioning), fields(batch_size = messages.len()))]
+    pub async fn send_raw_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: &[IggyMessage],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        self.with_reconnect("send_raw", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .send_raw_messages(stream, topic, messages, partitioning)
+                    .await;
+            }
+            let client = self.client.read().await;
+
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            // The SDK takes the batch mutably; retries need the original.
+            let mut messages: Vec<IggyMessage> = messages.iter().map(copy_message).collect();
+            client
+                .send_messages(&stream_id, &topic_id, partitioning, &mut messages)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::SendError))?;
+
+            debug!(
+                batch_size = messages.len(),
+                "Raw messages sent successfully"
+            );
+            Ok(())
+        })
+        .await
+    }
+
     /// Send multiple events in a batch to the default stream and topic.
     pub async fn send_events_batch_default(
         &self,
@@ -797,7 +1511,10 @@ impl IggyClientWrapper {
         topic: &str,
         params: PollParams,
     ) -> AppResult<PolledMessages> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("poll", || async {
+            if let Some(broker) = &self.broker {
+                return broker.poll_messages(stream, topic, params.clone()).await;
+            }
             let client = self.client.read().await;
 
             let stream_id = to_identifier(stream, "stream")?;
@@ -808,9 +1525,12 @@ impl IggyClientWrapper {
                     AppError::BadRequest(format!("Invalid consumer ID: {}", params.consumer_id))
                 })?);
 
-            let strategy = match params.offset {
-                Some(off) => PollingStrategy::offset(off),
-                None => PollingStrategy::next(),
+            let strategy = match (params.offset, params.from_timestamp) {
+                (Some(off), _) => PollingStrategy::offset(off),
+                (None, Some(timestamp)) => {
+                    PollingStrategy::timestamp(IggyTimestamp::from(timestamp))
+                }
+                (None, None) => PollingStrategy::next(),
             };
 
             let messages = client
@@ -836,49 +1556,172 @@ impl IggyClientWrapper {
         .await
     }
 
-    /// Poll messages from the default stream and topic.
-    pub async fn poll_messages_default(&self, params: PollParams) -> AppResult<PolledMessages> {
-        self.poll_messages(
-            &self.config.default_stream,
-            &self.config.default_topic,
-            params,
-        )
-        .await
-    }
-
-    // =========================================================================
-    // Stream & Topic Management
-    // =========================================================================
-
-    /// Get stream information.
+    /// Get the committed (stored) offset of a standalone consumer on one
+    /// partition.
+    ///
+    /// Returns `None` when the consumer has never committed on the partition.
     #[instrument(skip(self))]
-    pub async fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
-        self.with_reconnect(|| async {
+    pub async fn get_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<Option<u64>> {
+        self.with_reconnect("get_offset", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .get_consumer_offset(stream, topic, consumer_id, partition_id)
+                    .await;
+            }
             let client = self.client.read().await;
-            let stream_id = to_identifier(name, "stream")?;
 
-            client
-                .get_stream(&stream_id)
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            let consumer = Consumer::new(Identifier::numeric(consumer_id).map_err(|_| {
+                AppError::BadRequest(format!("Invalid consumer ID: {}", consumer_id))
+            })?);
+
+            let offset = client
+                .get_consumer_offset(&consumer, &stream_id, &topic_id, Some(partition_id))
                 .await
-                .map_err(|e| classify_iggy_error(e, AppError::StreamError))?
-                .ok_or_else(|| AppError::NotFound(format!("Stream '{}' not found", name)))
+                .map_err(|e| classify_iggy_error(e, AppError::PollError))?;
+
+            Ok(offset.map(|info| info.stored_offset))
         })
         .await
     }
 
-    /// Get topic information.
+    /// Commit (store) the offset of a standalone consumer on one partition.
+    ///
+    /// The next poll without an explicit offset resumes after `offset`.
     #[instrument(skip(self))]
-    pub async fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
-        self.with_reconnect(|| async {
+    pub async fn store_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<()> {
+        self.with_reconnect("store_offset", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .store_consumer_offset(stream, topic, consumer_id, partition_id, offset)
+                    .await;
+            }
             let client = self.client.read().await;
+
             let stream_id = to_identifier(stream, "stream")?;
             let topic_id = to_identifier(topic, "topic")?;
 
+            let consumer = Consumer::new(Identifier::numeric(consumer_id).map_err(|_| {
+                AppError::BadRequest(format!("Invalid consumer ID: {}", consumer_id))
+            })?);
+
             client
-                .get_topic(&stream_id, &topic_id)
+                .store_consumer_offset(&consumer, &stream_id, &topic_id, Some(partition_id), offset)
                 .await
-                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?
-                .ok_or_else(|| {
+                .map_err(|e| classify_iggy_error(e, AppError::PollError))?;
+
+            debug!(
+                stream,
+                topic, consumer_id, partition_id, offset, "Consumer offset stored"
+            );
+            Ok(())
+        })
+        .await
+    }
+
+    /// Delete the committed offset of a standalone consumer on one
+    /// partition, so Iggy no longer retains it.
+    #[instrument(skip(self))]
+    pub async fn delete_consumer_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+    ) -> AppResult<()> {
+        self.with_reconnect("delete_offset", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .delete_consumer_offset(stream, topic, consumer_id, partition_id)
+                    .await;
+            }
+            let client = self.client.read().await;
+
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            let consumer = Consumer::new(Identifier::numeric(consumer_id).map_err(|_| {
+                AppError::BadRequest(format!("Invalid consumer ID: {}", consumer_id))
+            })?);
+
+            client
+                .delete_consumer_offset(&consumer, &stream_id, &topic_id, Some(partition_id))
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::PollError))?;
+
+            debug!(
+                stream,
+                topic, consumer_id, partition_id, "Consumer offset deleted"
+            );
+            Ok(())
+        })
+        .await
+    }
+
+    /// Poll messages from the default stream and topic.
+    pub async fn poll_messages_default(&self, params: PollParams) -> AppResult<PolledMessages> {
+        self.poll_messages(
+            &self.config.default_stream,
+            &self.config.default_topic,
+            params,
+        )
+        .await
+    }
+
+    // =========================================================================
+    // Stream & Topic Management
+    // =========================================================================
+
+    /// Get stream information.
+    #[instrument(skip(self))]
+    pub async fn get_stream(&self, name: &str) -> AppResult<StreamDetails> {
+        self.with_reconnect("get_stream", || async {
+            if let Some(broker) = &self.broker {
+                return broker.get_stream(name).await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(name, "stream")?;
+
+            client
+                .get_stream(&stream_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::StreamError))?
+                .ok_or_else(|| AppError::NotFound(format!("Stream '{}' not found", name)))
+        })
+        .await
+    }
+
+    /// Get topic information.
+    #[instrument(skip(self))]
+    pub async fn get_topic(&self, stream: &str, topic: &str) -> AppResult<TopicDetails> {
+        self.with_reconnect("get_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker.get_topic(stream, topic).await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            client
+                .get_topic(&stream_id, &topic_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?
+                .ok_or_else(|| {
                     AppError::NotFound(format!(
                         "Topic '{}' in stream '{}' not found",
                         topic, stream
@@ -891,7 +1734,10 @@ impl IggyClientWrapper {
     /// List all streams.
     #[instrument(skip(self))]
     pub async fn list_streams(&self) -> AppResult<Vec<Stream>> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("list_streams", || async {
+            if let Some(broker) = &self.broker {
+                return broker.list_streams().await;
+            }
             let client = self.client.read().await;
 
             client
@@ -905,7 +1751,10 @@ impl IggyClientWrapper {
     /// List all topics in a stream.
     #[instrument(skip(self))]
     pub async fn list_topics(&self, stream: &str) -> AppResult<Vec<Topic>> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("list_topics", || async {
+            if let Some(broker) = &self.broker {
+                return broker.list_topics(stream).await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(stream, "stream")?;
 
@@ -920,7 +1769,10 @@ impl IggyClientWrapper {
     /// Create a new stream.
     #[instrument(skip(self))]
     pub async fn create_stream(&self, name: &str) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("create_stream", || async {
+            if let Some(broker) = &self.broker {
+                return broker.create_stream(name).await;
+            }
             let client = self.client.read().await;
 
             client
@@ -937,7 +1789,10 @@ impl IggyClientWrapper {
     /// Create a new topic.
     #[instrument(skip(self))]
     pub async fn create_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("create_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker.create_topic(stream, topic, partitions).await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(stream, "stream")?;
 
@@ -965,7 +1820,10 @@ impl IggyClientWrapper {
     /// **Warning**: This permanently deletes the stream and all its topics/messages.
     #[instrument(skip(self))]
     pub async fn delete_stream(&self, name: &str) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("delete_stream", || async {
+            if let Some(broker) = &self.broker {
+                return broker.delete_stream(name).await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(name, "stream")?;
 
@@ -985,7 +1843,10 @@ impl IggyClientWrapper {
     /// **Warning**: This permanently deletes the topic and all its messages.
     #[instrument(skip(self))]
     pub async fn delete_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
-        self.with_reconnect(|| async {
+        self.with_reconnect("delete_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker.delete_topic(stream, topic).await;
+            }
             let client = self.client.read().await;
             let stream_id = to_identifier(stream, "stream")?;
             let topic_id = to_identifier(topic, "topic")?;
@@ -1001,6 +1862,380 @@ impl IggyClientWrapper {
         .await
     }
 
+    /// Rename a stream, keeping its ID, topics and messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the stream does not exist and
+    /// `AppError::Conflict` if `new_name` is taken.
+    #[instrument(skip(self))]
+    pub async fn rename_stream(&self, name: &str, new_name: &str) -> AppResult<()> {
+        self.with_reconnect("rename_stream", || async {
+            if let Some(broker) = &self.broker {
+                return broker.rename_stream(name, new_name).await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(name, "stream")?;
+
+            // Look the stream up first so a missing one is a 404 rather than
+            // an opaque update failure.
+            client
+                .get_stream(&stream_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::StreamError))?
+                .ok_or_else(|| AppError::NotFound(format!("Stream '{name}' not found")))?;
+            match client.update_stream(&stream_id, new_name).await {
+                Ok(()) => {
+                    info!(stream = name, new_name, "Stream renamed");
+                    Ok(())
+                }
+                Err(IggyError::StreamNameAlreadyExists(_)) => Err(AppError::Conflict(format!(
+                    "Stream '{new_name}' already exists"
+                ))),
+                Err(e) => Err(classify_iggy_error(e, AppError::StreamError)),
+            }
+        })
+        .await
+    }
+
+    /// Rename a topic, keeping its ID, settings and messages.
+    ///
+    /// Iggy updates a topic's name together with its settings, so the
+    /// current settings are read and sent back unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the topic does not exist and
+    /// `AppError::Conflict` if `new_name` is taken in the stream.
+    #[instrument(skip(self))]
+    pub async fn rename_topic(&self, stream: &str, topic: &str, new_name: &str) -> AppResult<()> {
+        self.with_reconnect("rename_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker.rename_topic(stream, topic, new_name).await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            let details = client
+                .get_topic(&stream_id, &topic_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?
+                .ok_or_else(|| {
+                    AppError::NotFound(format!("Topic '{topic}' in stream '{stream}' not found"))
+                })?;
+            let result = client
+                .update_topic(
+                    &stream_id,
+                    &topic_id,
+                    new_name,
+                    details.compression_algorithm,
+                    Some(details.replication_factor),
+                    details.message_expiry,
+                    details.max_topic_size,
+                )
+                .await;
+            match result {
+                Ok(()) => {
+                    info!(stream, topic, new_name, "Topic renamed");
+                    Ok(())
+                }
+                Err(IggyError::TopicNameAlreadyExists(_, _)) => Err(AppError::Conflict(format!(
+                    "Topic '{new_name}' already exists in stream '{stream}'"
+                ))),
+                Err(e) => Err(classify_iggy_error(e, AppError::TopicError)),
+            }
+        })
+        .await
+    }
+
+    /// Set a topic's message expiry to `max_age_secs` and its size limit
+    /// to `max_size_bytes` where they differ (`None` leaves a setting as
+    /// it is), keeping its name and other settings. Returns whether the
+    /// topic was updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the topic does not exist.
+    #[instrument(skip(self))]
+    pub async fn enforce_topic_retention(
+        &self,
+        stream: &str,
+        topic: &str,
+        max_age_secs: Option<u64>,
+        max_size_bytes: Option<u64>,
+    ) -> AppResult<bool> {
+        self.with_reconnect("enforce_topic_retention", || async {
+            if let Some(broker) = &self.broker {
+                return broker
+                    .enforce_topic_retention(stream, topic, max_age_secs, max_size_bytes)
+                    .await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            let details = client
+                .get_topic(&stream_id, &topic_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?
+                .ok_or_else(|| {
+                    AppError::NotFound(format!("Topic '{topic}' in stream '{stream}' not found"))
+                })?;
+            let Some((expiry, max_size)) = retention_update(
+                details.message_expiry,
+                details.max_topic_size,
+                max_age_secs,
+                max_size_bytes,
+            ) else {
+                return Ok(false);
+            };
+            client
+                .update_topic(
+                    &stream_id,
+                    &topic_id,
+                    topic,
+                    details.compression_algorithm,
+                    Some(details.replication_factor),
+                    expiry,
+                    max_size,
+                )
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?;
+
+            info!(
+                stream,
+                topic,
+                ?max_age_secs,
+                ?max_size_bytes,
+                "Topic retention updated"
+            );
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Delete every message of a topic, keeping the topic and its
+    /// partitions.
+    ///
+    /// **Warning**: The messages cannot be recovered.
+    #[instrument(skip(self))]
+    pub async fn purge_topic(&self, stream: &str, topic: &str) -> AppResult<()> {
+        self.with_reconnect("purge_topic", || async {
+            if let Some(broker) = &self.broker {
+                return broker.purge_topic(stream, topic).await;
+            }
+            let client = self.client.read().await;
+            let stream_id = to_identifier(stream, "stream")?;
+            let topic_id = to_identifier(topic, "topic")?;
+
+            client
+                .purge_topic(&stream_id, &topic_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::TopicError))?;
+
+            warn!(stream, topic, "Topic purged");
+            Ok(())
+        })
+        .await
+    }
+
+    // =========================================================================
+    // Server Information
+    // =========================================================================
+
+    /// Get server-wide statistics (version, uptime, resource usage, counts).
+    #[instrument(skip(self))]
+    pub async fn server_stats(&self) -> AppResult<Stats> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("Server stats"));
+        }
+        self.with_reconnect("server_stats", || async {
+            let client = self.client.read().await;
+
+            client
+                .get_stats()
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))
+        })
+        .await
+    }
+
+    /// Ping the server through the resilient path and return the round-trip
+    /// latency.
+    ///
+    /// Unlike [`Self::health_check`] this is a regular operation: it honors
+    /// this view's deadline and the circuit breaker, and does not touch the
+    /// tracked connection state.
+    #[instrument(skip(self))]
+    pub async fn ping(&self) -> AppResult<Duration> {
+        self.with_reconnect("ping", || async {
+            if self.broker.is_some() {
+                return Ok(Duration::ZERO);
+            }
+            let client = self.client.read().await;
+
+            let start = std::time::Instant::now();
+            client
+                .ping()
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))?;
+            Ok(start.elapsed())
+        })
+        .await
+    }
+
+    // =========================================================================
+    // User Management
+    // =========================================================================
+    //
+    // Passthrough to Iggy's user APIs. The connected user must itself hold
+    // `manage_users` / `read_users` permissions on the server; the HTTP layer
+    // additionally gates these behind the admin scope. Passwords are never
+    // recorded in spans.
+
+    /// List all users.
+    #[instrument(skip(self))]
+    pub async fn list_users(&self) -> AppResult<Vec<UserInfo>> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("list_users", || async {
+            let client = self.client.read().await;
+
+            client
+                .get_users()
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))
+        })
+        .await
+    }
+
+    /// Get a user, including permissions.
+    #[instrument(skip(self))]
+    pub async fn get_user(&self, username: &str) -> AppResult<UserInfoDetails> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("get_user", || async {
+            let client = self.client.read().await;
+            let user_id = to_identifier(username, "user")?;
+
+            client
+                .get_user(&user_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))?
+                .ok_or_else(|| AppError::NotFound(format!("User '{}' not found", username)))
+        })
+        .await
+    }
+
+    /// Create a user.
+    ///
+    /// An existing username is reported as `BadRequest` rather than an
+    /// internal error, since retrying cannot succeed.
+    #[instrument(skip(self, password, permissions))]
+    pub async fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+        status: UserStatus,
+        permissions: Option<Permissions>,
+    ) -> AppResult<UserInfoDetails> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("create_user", || async {
+            let client = self.client.read().await;
+
+            let user = client
+                .create_user(username, password, status, permissions.clone())
+                .await
+                .map_err(|e| match e {
+                    IggyError::UserAlreadyExists => {
+                        AppError::BadRequest(format!("User '{}' already exists", username))
+                    }
+                    other => classify_iggy_error(other, AppError::Internal),
+                })?;
+
+            info!(username, "User created");
+            Ok(user)
+        })
+        .await
+    }
+
+    /// Replace a user's permissions (`None` clears them).
+    #[instrument(skip(self, permissions))]
+    pub async fn update_permissions(
+        &self,
+        username: &str,
+        permissions: Option<Permissions>,
+    ) -> AppResult<()> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("update_permissions", || async {
+            let client = self.client.read().await;
+            let user_id = to_identifier(username, "user")?;
+
+            client
+                .update_permissions(&user_id, permissions.clone())
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))?;
+
+            info!(username, "User permissions updated");
+            Ok(())
+        })
+        .await
+    }
+
+    /// Change a user's password. The current password must match.
+    #[instrument(skip(self, current_password, new_password))]
+    pub async fn change_password(
+        &self,
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> AppResult<()> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("change_password", || async {
+            let client = self.client.read().await;
+            let user_id = to_identifier(username, "user")?;
+
+            client
+                .change_password(&user_id, current_password, new_password)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))?;
+
+            info!(username, "User password changed");
+            Ok(())
+        })
+        .await
+    }
+
+    /// Delete a user.
+    #[instrument(skip(self))]
+    pub async fn delete_user(&self, username: &str) -> AppResult<()> {
+        if self.broker.is_some() {
+            return Err(memory::unsupported("User management"));
+        }
+        self.with_reconnect("delete_user", || async {
+            let client = self.client.read().await;
+            let user_id = to_identifier(username, "user")?;
+
+            client
+                .delete_user(&user_id)
+                .await
+                .map_err(|e| classify_iggy_error(e, AppError::Internal))?;
+
+            warn!(username, "User deleted");
+            Ok(())
+        })
+        .await
+    }
+
     // =========================================================================
     // Accessors
     // =========================================================================
@@ -1020,6 +2255,40 @@ impl IggyClientWrapper {
         &self.config
     }
 
+    /// Tie reconnection to `shutdown`: once it is cancelled, a reconnect
+    /// session stops between (or during) attempts with
+    /// `AppError::ShuttingDown` instead of retrying forever under
+    /// `MAX_RECONNECT_ATTEMPTS=0`.
+    ///
+    /// Set once, before the wrapper is cloned into services: clones made
+    /// earlier keep the token they were made with.
+    #[must_use]
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Inject `policy`'s failures into every operation attempt, for tests
+    /// of the retry, timeout and circuit breaker paths (`test-util`
+    /// feature; see [`FailurePolicy`]). Applies to a broker backend too.
+    ///
+    /// Like [`Self::with_shutdown`], set it before the wrapper is cloned.
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub fn with_failure_policy(mut self, policy: Arc<FailurePolicy>) -> Self {
+        self.failure_policy = Some(policy);
+        self
+    }
+
+    /// Register `observer` for connection events: connection lost or
+    /// regained, and circuit breakers opening (see [`ConnectionObserver`]).
+    ///
+    /// Observers are shared by every clone of the wrapper, including
+    /// clones made before the call.
+    pub fn observe(&self, observer: Arc<dyn ConnectionObserver>) {
+        self.observers.add(observer);
+    }
+
     /// Return a view of this wrapper whose operations are bounded by
     /// `timeout` instead of the configured `OPERATION_TIMEOUT_SECS`.
     ///
@@ -1048,24 +2317,63 @@ impl IggyClientWrapper {
         scoped
     }
 
-    /// Get the current circuit breaker state.
-    pub async fn circuit_breaker_state(&self) -> CircuitState {
-        self.circuit_breaker.state().await
+    /// Get the current state of the circuit breaker of `class`.
+    pub async fn circuit_breaker_state(&self, class: OperationClass) -> CircuitState {
+        self.circuit_breakers.get(class).state().await
     }
 
-    /// Get circuit breaker metrics.
+    /// Get the metrics of the circuit breaker of `class`.
     ///
     /// Returns a tuple of (times_opened, requests_rejected).
-    pub fn circuit_breaker_metrics(&self) -> (u32, u64) {
-        (
-            self.circuit_breaker.times_opened(),
-            self.circuit_breaker.requests_rejected(),
-        )
+    pub fn circuit_breaker_metrics(&self, class: OperationClass) -> (u32, u64) {
+        let breaker = self.circuit_breakers.get(class);
+        (breaker.times_opened(), breaker.requests_rejected())
+    }
+
+    /// Shared signal of whether the backend is degraded (a circuit not
+    /// closed or reconnecting), for consumers that cannot await, such as
+    /// adaptive rate limiting.
+    pub fn health_signal(&self) -> HealthSignal {
+        HealthSignal::new(self.state.clone(), self.circuit_breakers.clone())
     }
 
-    /// Force close the circuit breaker (for manual recovery).
+    /// Force close every circuit breaker (for manual recovery).
     pub async fn force_close_circuit(&self) {
-        self.circuit_breaker.force_close().await;
+        self.circuit_breakers.force_close().await;
+    }
+}
+
+/// The message expiry and size limit a topic with `expiry` and `max_size`
+/// needs to match a retention policy's `max_age_secs` and
+/// `max_size_bytes` (`None` keeps the current setting), or `None` if it
+/// already does.
+fn retention_update(
+    expiry: IggyExpiry,
+    max_size: MaxTopicSize,
+    max_age_secs: Option<u64>,
+    max_size_bytes: Option<u64>,
+) -> Option<(IggyExpiry, MaxTopicSize)> {
+    let wanted_expiry = max_age_secs.map_or(expiry, |secs| {
+        IggyExpiry::ExpireDuration(IggyDuration::new(Duration::from_secs(secs)))
+    });
+    let wanted_size = max_size_bytes.map_or(max_size, |bytes| {
+        MaxTopicSize::Custom(IggyByteSize::from(bytes))
+    });
+    (wanted_expiry != expiry || wanted_size != max_size).then_some((wanted_expiry, wanted_size))
+}
+
+/// Settings of an existing topic, for comparison with a bootstrap spec.
+fn actual_topic(details: &TopicDetails) -> ActualTopic {
+    ActualTopic {
+        partitions: details.partitions_count,
+        message_expiry_secs: match details.message_expiry {
+            IggyExpiry::ExpireDuration(duration) => Some(duration.get_duration().as_secs()),
+            _ => None,
+        },
+        max_size_bytes: match details.max_topic_size {
+            MaxTopicSize::Custom(size) => Some(size.as_bytes_u64()),
+            _ => None,
+        },
     }
 }
 
@@ -1080,14 +2388,22 @@ mod tests {
     #[allow(clippy::expect_used)]
     fn unconnected_wrapper() -> IggyClientWrapper {
         let config = Config::default();
-        let client = IggyClient::from_connection_string(&config.iggy_connection_string)
+        let client = IggyClient::from_connection_string(&config.iggy_client_connection_string())
             .expect("default connection string parses");
         IggyClientWrapper {
             client: Arc::new(RwLock::new(client)),
             op_deadline: config.operation_timeout,
             config: Arc::new(config),
             state: Arc::new(ConnectionState::new()),
-            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            circuit_breakers: Arc::new(CircuitBreakers::default()),
+            retry_budget: Arc::new(RetryBudget::new(1)),
+            fair_queue: Arc::new(FairQueue::new(0, 0, Default::default(), Duration::ZERO)),
+            shutdown: CancellationToken::new(),
+            observers: Arc::new(ConnectionObservers::default()),
+            active_endpoint: Arc::new(AtomicUsize::new(0)),
+            credentials: Arc::new(ConnectionStringCredentials),
+            broker: None,
+            failure_policy: None,
         }
     }
 
@@ -1116,6 +2432,208 @@ mod tests {
         assert!(scoped.op_deadline < scoped.config.operation_timeout);
     }
 
+    #[tokio::test(start_paused = true)]
+    #[allow(clippy::expect_used)]
+    async fn test_retry_hint_wiring() {
+        let wrapper = unconnected_wrapper();
+        wrapper
+            .circuit_breakers
+            .get(OperationClass::Send)
+            .force_open()
+            .await;
+
+        let open = wrapper
+            .annotate_retry_hint(OperationClass::Send, AppError::CircuitOpen("open".into()))
+            .await;
+        assert_eq!(open.retry_after(), Some(Duration::from_secs(30)));
+
+        // Only the send breaker is open: the poll breaker gives no hint.
+        let other_class = wrapper
+            .annotate_retry_hint(OperationClass::Poll, AppError::CircuitOpen("open".into()))
+            .await;
+        assert_eq!(other_class.retry_after(), None);
+
+        // No reconnect attempts yet: the hint is the base backoff.
+        let disconnected = wrapper
+            .annotate_retry_hint(OperationClass::Send, AppError::Disconnected("gone".into()))
+            .await;
+        assert_eq!(
+            disconnected.retry_after(),
+            Some(wrapper.config.reconnect_base_delay)
+        );
+
+        let timeout = wrapper
+            .annotate_retry_hint(
+                OperationClass::Send,
+                AppError::OperationTimeout("slow".into()),
+            )
+            .await;
+        assert!(timeout.is_retryable());
+        assert_eq!(timeout.retry_after(), None);
+
+        // One retry per second: one to two seconds, jittered.
+        let exhausted = wrapper
+            .annotate_retry_hint(
+                OperationClass::Send,
+                AppError::RetryBudgetExhausted("empty".into()),
+            )
+            .await
+            .retry_after()
+            .expect("budget rejections carry a hint");
+        assert!(exhausted >= Duration::from_secs(1) && exhausted <= Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_failure_policy_fails_only_the_nth_attempt() {
+        let policy = Arc::new(FailurePolicy::new());
+        let wrapper = unconnected_wrapper().with_failure_policy(policy.clone());
+        wrapper.mark_connected(true);
+        policy.fail_nth(2, AppError::BadRequest("injected".into()));
+
+        let attempt = || wrapper.with_reconnect("send", || async { Ok(()) });
+        assert!(attempt().await.is_ok());
+        assert!(matches!(attempt().await, Err(AppError::BadRequest(_))));
+        assert!(attempt().await.is_ok());
+        assert_eq!(policy.operations(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failure_policy_hangs_trip_the_timeout_and_breaker() {
+        let policy = Arc::new(FailurePolicy::new());
+        let wrapper = unconnected_wrapper().with_failure_policy(policy.clone());
+        wrapper.mark_connected(true);
+        // Default breaker threshold: 5 failures
+        for n in 1..=5 {
+            policy.hang_nth(n);
+        }
+
+        for _ in 0..5 {
+            let result = wrapper.with_reconnect("send", || async { Ok(()) }).await;
+            assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+        }
+        assert_eq!(
+            wrapper.circuit_breaker_state(OperationClass::Send).await,
+            CircuitState::Open
+        );
+        let rejected = wrapper.with_reconnect("send", || async { Ok(()) }).await;
+        assert!(matches!(
+            rejected.as_ref().map_err(AppError::kind),
+            Err(AppError::CircuitOpen(_))
+        ));
+        assert_eq!(policy.operations(), 5, "an open circuit makes no attempt");
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[allow(clippy::unwrap_used)]
+    async fn test_broker_backend_goes_through_the_resilient_path() {
+        let policy = Arc::new(FailurePolicy::new());
+        let wrapper = IggyClientWrapper::with_broker(Config::default(), MemoryBroker::default())
+            .with_failure_policy(policy.clone());
+        assert!(wrapper.is_connected());
+        wrapper.ensure_stream("orders").await.unwrap();
+        policy.fail_nth(2, AppError::BadRequest("injected".into()));
+        for n in 3..=7 {
+            policy.hang_nth(n);
+        }
+
+        assert!(matches!(
+            wrapper.get_stream("orders").await,
+            Err(AppError::BadRequest(_))
+        ));
+        for _ in 0..5 {
+            let result = wrapper.create_stream("payments").await;
+            assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+        }
+        assert_eq!(
+            wrapper.circuit_breaker_state(OperationClass::Admin).await,
+            CircuitState::Open
+        );
+        assert!(wrapper.get_stream("payments").await.is_err());
+        assert_eq!(policy.operations(), 7, "an open circuit makes no attempt");
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[allow(clippy::unwrap_used)]
+    async fn test_queueing_for_a_slot_counts_against_the_deadline() {
+        let policy = Arc::new(FailurePolicy::new());
+        let mut wrapper = unconnected_wrapper().with_failure_policy(policy.clone());
+        wrapper.fair_queue = Arc::new(FairQueue::new(1, 0, Default::default(), Duration::ZERO));
+        wrapper.mark_connected(true);
+        policy.hang_nth(1);
+
+        // The only slot is held for most of the deadline
+        let deadline = wrapper.op_deadline;
+        let held = wrapper
+            .fair_queue
+            .acquire(INTERNAL_TENANT.into(), deadline)
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(deadline * 3 / 4).await;
+            drop(held);
+        });
+
+        let started = tokio::time::Instant::now();
+        let result = wrapper.with_reconnect("send", || async { Ok(()) }).await;
+        assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+        // Not the wait plus a whole deadline of its own
+        assert!(started.elapsed() <= deadline, "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_aborts_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        let wrapper = unconnected_wrapper().with_shutdown(shutdown.clone());
+        shutdown.cancel();
+
+        let result = wrapper.reconnect().await;
+        assert!(matches!(result, Err(AppError::ShuttingDown(_))));
+        assert!(
+            !wrapper.state.is_reconnecting(),
+            "the session is released for shutdown"
+        );
+    }
+
+    #[test]
+    fn test_failover_rotates_every_failover_attempts() {
+        let mut wrapper = unconnected_wrapper();
+        wrapper.config = Arc::new(Config {
+            iggy_fallback_servers: vec!["iggy-2:8090".to_string()],
+            iggy_failover_attempts: 2,
+            ..Config::default()
+        });
+        assert_eq!(wrapper.active_endpoint().as_deref(), Some("localhost:8090"));
+
+        let endpoints: Vec<usize> = (1..=6)
+            .map(|attempt| wrapper.failover_if_due(attempt))
+            .collect();
+        assert_eq!(endpoints, [0, 0, 1, 1, 0, 0]);
+        assert_eq!(wrapper.active_endpoint().as_deref(), Some("localhost:8090"));
+    }
+
+    #[test]
+    fn test_observers_see_each_connection_transition_once() {
+        struct Transitions(std::sync::atomic::AtomicU32);
+        impl ConnectionObserver for Transitions {
+            fn on_connected(&self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+            fn on_disconnected(&self) {
+                self.0.fetch_add(100, Ordering::SeqCst);
+            }
+        }
+
+        let wrapper = unconnected_wrapper();
+        let transitions = Arc::new(Transitions(std::sync::atomic::AtomicU32::new(0)));
+        wrapper.clone().observe(transitions.clone());
+
+        wrapper.mark_connected(true);
+        wrapper.mark_connected(true);
+        wrapper.mark_connected(false);
+        wrapper.mark_connected(false);
+        assert_eq!(transitions.0.load(Ordering::SeqCst), 101);
+    }
+
     #[test]
     fn test_clamp_deadline_shortens() {
         // A client may shorten the deadline below the global bound.
@@ -1202,4 +2720,21 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_retention_update_changes_only_differing_settings() {
+        let day = IggyExpiry::ExpireDuration(IggyDuration::new(Duration::from_secs(86_400)));
+        let unlimited = MaxTopicSize::Unlimited;
+
+        assert_eq!(retention_update(day, unlimited, Some(86_400), None), None);
+        assert_eq!(retention_update(day, unlimited, None, None), None);
+        assert_eq!(
+            retention_update(IggyExpiry::NeverExpire, unlimited, Some(86_400), None),
+            Some((day, unlimited))
+        );
+        assert_eq!(
+            retention_update(day, unlimited, None, Some(1024)),
+            Some((day, MaxTopicSize::Custom(IggyByteSize::from(1024))))
+        );
+    }
 }
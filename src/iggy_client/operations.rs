@@ -0,0 +1,138 @@
+//! The Iggy operations the produce path depends on, as a trait.
+//!
+//! [`ProducerService`](crate::services::ProducerService) is generic over
+//! [`IggyOperations`] (defaulting to [`IggyClientWrapper`]), so it can run
+//! against the in-process `MockIggyClient` of the `test-util` feature
+//! instead of a live server.
+//!
+//! The trait covers what the producer needs: topology (ensuring streams
+//! and topics, partition counts) and the three send shapes. Polls, offsets
+//! and stream/topic administration are in
+//! [`MessageBroker`](super::MessageBroker), which extends it; users and
+//! server stats stay on [`IggyClientWrapper`].
+
+use std::future::Future;
+use std::time::Duration;
+
+use iggy::prelude::{IggyMessage, Partitioning};
+
+use super::IggyClientWrapper;
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::models::Event;
+
+/// Operations of an Iggy client used by the produce path.
+///
+/// Errors follow [`IggyClientWrapper`]'s classification, so callers (and
+/// tests) see the same `AppError` variants from every implementation.
+pub trait IggyOperations: Clone + Send + Sync + 'static {
+    /// Application configuration.
+    fn config(&self) -> &Config;
+
+    /// A view whose operations are bounded by `timeout` (clamped to the
+    /// configured operation timeout).
+    #[must_use]
+    fn with_timeout(&self, timeout: Duration) -> Self;
+
+    /// Create stream `name` unless it exists.
+    fn ensure_stream(&self, name: &str) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Create `stream`/`topic` with `partitions` unless it exists.
+    fn ensure_topic(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Number of partitions of `stream`/`topic`.
+    ///
+    /// Returns `AppError::NotFound` if the topic does not exist.
+    fn partitions_count(
+        &self,
+        stream: &str,
+        topic: &str,
+    ) -> impl Future<Output = AppResult<u32>> + Send;
+
+    /// Send one event.
+    fn send_event_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partitioning: &Partitioning,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Send a batch of events in one request (compressed per
+    /// `BATCH_COMPRESSION`).
+    fn send_events_batch_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partitioning: &Partitioning,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Send already-built messages as-is.
+    fn send_raw_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: &[IggyMessage],
+        partitioning: &Partitioning,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+}
+
+impl IggyOperations for IggyClientWrapper {
+    fn config(&self) -> &Config {
+        IggyClientWrapper::config(self)
+    }
+
+    fn with_timeout(&self, timeout: Duration) -> Self {
+        IggyClientWrapper::with_timeout(self, timeout)
+    }
+
+    async fn ensure_stream(&self, name: &str) -> AppResult<()> {
+        IggyClientWrapper::ensure_stream(self, name).await
+    }
+
+    async fn ensure_topic(&self, stream: &str, topic: &str, partitions: u32) -> AppResult<()> {
+        IggyClientWrapper::ensure_topic(self, stream, topic, partitions).await
+    }
+
+    async fn partitions_count(&self, stream: &str, topic: &str) -> AppResult<u32> {
+        let details = self.get_topic(stream, topic).await?;
+        Ok(details.partitions_count)
+    }
+
+    async fn send_event_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        event: &Event,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        IggyClientWrapper::send_event_partitioned(self, stream, topic, event, partitioning).await
+    }
+
+    async fn send_events_batch_partitioned(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        IggyClientWrapper::send_events_batch_partitioned(self, stream, topic, events, partitioning)
+            .await
+    }
+
+    async fn send_raw_messages(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: &[IggyMessage],
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        IggyClientWrapper::send_raw_messages(self, stream, topic, messages, partitioning).await
+    }
+}
@@ -26,10 +26,16 @@ pub struct PollParams {
     pub consumer_id: u32,
     /// Starting offset (None = from last committed)
     pub offset: Option<u64>,
+    /// Without an `offset`, start at the first message stamped at or after
+    /// this time, in Unix microseconds (None = from last committed)
+    pub from_timestamp: Option<u64>,
     /// Maximum messages to return
     pub count: u32,
     /// Whether to auto-commit offset after polling
     pub auto_commit: bool,
+    /// Payload schema version to upcast events to (None = as stored).
+    /// Applied by `ConsumerService`, not sent to Iggy.
+    pub target_version: Option<u32>,
 }
 
 impl PollParams {
@@ -37,15 +43,19 @@ impl PollParams {
     ///
     /// Defaults:
     /// - offset: None (use last committed)
+    /// - from_timestamp: None
     /// - count: DEFAULT_POLL_COUNT (10)
     /// - auto_commit: false
+    /// - target_version: None (events as stored)
     pub fn new(partition_id: u32, consumer_id: u32) -> Self {
         Self {
             partition_id,
             consumer_id,
             offset: None,
+            from_timestamp: None,
             count: DEFAULT_POLL_COUNT,
             auto_commit: false,
+            target_version: None,
         }
     }
 
@@ -55,6 +65,13 @@ impl PollParams {
         self
     }
 
+    /// Start at the first message stamped at or after `timestamp_micros`
+    /// (ignored when an offset is set).
+    pub fn with_timestamp(mut self, timestamp_micros: u64) -> Self {
+        self.from_timestamp = Some(timestamp_micros);
+        self
+    }
+
     /// Set the maximum message count.
     pub fn with_count(mut self, count: u32) -> Self {
         self.count = count;
@@ -66,6 +83,12 @@ impl PollParams {
         self.auto_commit = auto_commit;
         self
     }
+
+    /// Set the schema version events are upcast to.
+    pub fn with_target_version(mut self, target_version: Option<u32>) -> Self {
+        self.target_version = target_version;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -79,6 +102,7 @@ mod tests {
         assert_eq!(params.partition_id, 1);
         assert_eq!(params.consumer_id, 2);
         assert_eq!(params.offset, None);
+        assert_eq!(params.from_timestamp, None);
         assert_eq!(params.count, 10);
         assert!(!params.auto_commit);
     }
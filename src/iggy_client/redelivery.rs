@@ -0,0 +1,123 @@
+//! Copies of negatively acknowledged (nacked) messages for redelivery.
+//!
+//! Iggy has no per-message requeue: a partition is an append-only log and
+//! a consumer only has an offset. A nack therefore appends a COPY of the
+//! message (same payload and user headers, so compressed payloads stay
+//! decodable) to the tail of the topic, or to a retry topic, with a
+//! `redelivery_count` user header counting how many times it has been
+//! requeued. Once that count exceeds `MAX_REDELIVERIES`, the copy goes to
+//! the dead-letter topic instead.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use iggy::prelude::{HeaderKey, HeaderValue, IggyMessage};
+
+use crate::error::{AppError, AppResult};
+
+/// User header counting how many times a message has been redelivered.
+pub const REDELIVERY_COUNT_HEADER: &str = "redelivery_count";
+
+/// Where nacked messages go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedeliveryPolicy {
+    /// Redeliveries allowed before a message is dead-lettered
+    pub max_redeliveries: u32,
+    /// Topic (in the source stream) for requeued copies; `None` requeues to
+    /// the source topic itself
+    pub retry_topic: Option<String>,
+    /// Topic (in the source stream) for messages out of redeliveries
+    pub dlq_topic: String,
+}
+
+impl RedeliveryPolicy {
+    /// Destination topic for a copy with `redelivery_count`, and whether
+    /// that is the dead-letter topic.
+    pub fn destination<'a>(
+        &'a self,
+        source_topic: &'a str,
+        redelivery_count: u32,
+    ) -> (&'a str, bool) {
+        if redelivery_count > self.max_redeliveries {
+            (&self.dlq_topic, true)
+        } else {
+            (self.retry_topic.as_deref().unwrap_or(source_topic), false)
+        }
+    }
+}
+
+/// Times `message` has already been redelivered (0 when untagged or the
+/// header is not a number).
+pub fn redelivery_count(message: &IggyMessage) -> u32 {
+    let Ok(Some(headers)) = message.user_headers_map() else {
+        return 0;
+    };
+    headers
+        .iter()
+        .find(|(key, _)| key.as_str() == Ok(REDELIVERY_COUNT_HEADER))
+        .and_then(|(_, value)| value.as_str().ok()?.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Build a copy of `message` for redelivery, keeping its payload and user
+/// headers and setting `redelivery_count` to `count`.
+///
+/// # Errors
+///
+/// Returns `AppError::SendError` if the headers cannot be decoded or the
+/// copy cannot be built.
+pub fn redelivery_message(message: &IggyMessage, count: u32) -> AppResult<IggyMessage> {
+    let header = |e: iggy::prelude::IggyError| AppError::SendError(e.to_string());
+    let mut headers: BTreeMap<HeaderKey, HeaderValue> = message
+        .user_headers_map()
+        .map_err(header)?
+        .unwrap_or_default();
+    headers.retain(|key, _| key.as_str() != Ok(REDELIVERY_COUNT_HEADER));
+    headers.insert(
+        HeaderKey::from_str(REDELIVERY_COUNT_HEADER).map_err(header)?,
+        HeaderValue::from_str(&count.to_string()).map_err(header)?,
+    );
+    IggyMessage::builder()
+        .payload(message.payload.clone())
+        .user_headers(headers)
+        .build()
+        .map_err(|e| AppError::SendError(e.to_string()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redelivery_copy_increments_count_and_keeps_payload() {
+        let original = IggyMessage::from_str(r#"{"event_type":"order.created"}"#).unwrap();
+        assert_eq!(redelivery_count(&original), 0);
+
+        let first = redelivery_message(&original, 1).unwrap();
+        assert_eq!(redelivery_count(&first), 1);
+        assert_eq!(first.payload, original.payload);
+
+        // The header is replaced, not duplicated.
+        let second = redelivery_message(&first, 2).unwrap();
+        assert_eq!(redelivery_count(&second), 2);
+        assert_eq!(second.user_headers_map().unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_destination_switches_to_dlq_after_max_redeliveries() {
+        let policy = RedeliveryPolicy {
+            max_redeliveries: 2,
+            retry_topic: None,
+            dlq_topic: "dlq".to_string(),
+        };
+        assert_eq!(policy.destination("orders", 2), ("orders", false));
+        assert_eq!(policy.destination("orders", 3), ("dlq", true));
+
+        let policy = RedeliveryPolicy {
+            retry_topic: Some("orders-retry".to_string()),
+            ..policy
+        };
+        assert_eq!(policy.destination("orders", 1), ("orders-retry", false));
+    }
+}
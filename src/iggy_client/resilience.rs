@@ -10,9 +10,10 @@
 //!
 //! 1. **Circuit breaker gate**: if the breaker rejects the request, fail
 //!    fast with `CircuitOpen` without running the operation.
-//! 2. **First attempt**, bounded by `timeout`.
+//! 2. **First attempt**, bounded by the deadline budget (see below).
 //! 3. **Classified connection error** → record breaker failure, run the
-//!    `reconnect` step, then retry the operation exactly once.
+//!    `reconnect` step, then retry the operation exactly once, both within
+//!    what is left of the budget.
 //! 4. **Non-connection error** → returned as-is; the breaker records
 //!    neither success nor failure (bad requests must not open the circuit),
 //!    but any half-open probe token the request consumed is RELEASED so an
@@ -25,8 +26,20 @@
 //!    only outage signal). A client-shortened `X-Request-Timeout` deadline
 //!    expiring says nothing about an outage and must not feed the shared
 //!    breaker — otherwise one client could open the circuit for everyone.
-//!    Reconnect + single retry happen only if `is_connected` reports the
-//!    connection as lost — a timeout alone could just be a slow operation.
+//!    A timed-out attempt has used the whole budget, so it is never
+//!    retried. If `is_connected` reports the connection as lost, the
+//!    background health check is already reconnecting (it reconnects as
+//!    soon as a ping fails); the timeout is returned either way.
+//!
+//! # Deadline budget
+//!
+//! `timeout` (the request's `X-Request-Timeout`, or `OPERATION_TIMEOUT_SECS`)
+//! is a budget for the whole operation rather than for each attempt: its
+//! deadline is fixed when the operation starts, the reconnect is waited
+//! for only until then, and the retry gets whatever remains. A reconnect
+//! that outlasts the budget is left to finish in the background and the
+//! operation fails with `timeout` without retrying; so does one whose
+//! budget ran out just as the reconnect completed.
 //!
 //! # Retry and the breaker gate
 //!
@@ -41,9 +54,8 @@
 //!
 //! # Worst-case latency
 //!
-//! A request on the reconnect path can take up to 3x `timeout` (first
-//! attempt + bounded reconnect + retry) — bounded, but well above the
-//! typical single-operation expectation.
+//! A request takes at most `timeout`, reconnect and retry included (plus
+//! the breaker bookkeeping after the deadline).
 //!
 //! # Breaker false positives — and who feeds the breaker
 //!
@@ -72,6 +84,7 @@
 
 use std::time::Duration;
 
+use tokio::time::Instant;
 use tracing::{debug, warn};
 
 use super::circuit_breaker::CircuitBreaker;
@@ -94,11 +107,12 @@ pub(super) fn is_connection_error(error: &AppError) -> bool {
 /// live server:
 ///
 /// - `breaker` — gates the request and records the outcome
-/// - `timeout` — per-attempt bound (both the first attempt and the retry)
+/// - `timeout` — budget of the whole operation (first attempt, reconnect
+///   and retry)
 /// - `timeout_is_outage_signal` — whether an expired `timeout` may be
 ///   recorded as a breaker failure (false for client-shortened deadlines)
 /// - `is_connected` — consulted only on the timeout branch, to distinguish
-///   "slow operation" from "lost connection"
+///   "slow operation" from "lost connection" in the logs
 /// - `reconnect` — the bounded reconnect step; invoked at most once
 /// - `operation` — the Iggy call; invoked once, plus at most one retry
 pub(super) async fn run_resilient<T, F, Fut, C, R, RFut>(
@@ -128,8 +142,11 @@ where
         )));
     }
 
-    // First attempt with timeout
-    match tokio::time::timeout(timeout, operation()).await {
+    // The budget of the whole operation, reconnect and retry included
+    let deadline = Instant::now() + timeout;
+
+    // First attempt
+    match tokio::time::timeout_at(deadline, operation()).await {
         Ok(Ok(value)) => {
             breaker.record_success().await;
             Ok(value)
@@ -137,8 +154,23 @@ where
         Ok(Err(e)) if is_connection_error(&e) => {
             breaker.record_failure().await;
             warn!(error = %e, "Operation failed due to connection error, attempting reconnect");
-            reconnect().await?;
-            retry_once(breaker, timeout, timeout_is_outage_signal, &operation).await
+            match tokio::time::timeout_at(deadline, reconnect()).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    // The reconnect session itself carries on in the background
+                    return Err(AppError::OperationTimeout(format!(
+                        "Operation used up its {timeout:?} budget while reconnecting"
+                    )));
+                }
+            }
+            retry_once(
+                breaker,
+                deadline,
+                timeout,
+                timeout_is_outage_signal,
+                &operation,
+            )
+            .await
         }
         Ok(Err(e)) => {
             // Non-connection error - record neither success nor failure,
@@ -168,36 +200,35 @@ where
                 );
             }
 
-            // Only reconnect if we have evidence the connection is actually
-            // lost (the background health check drives this flag via live
-            // pings). A timeout alone could just be a slow operation.
+            // The budget is spent, so there is no retry. A connection
+            // found lost is already being repaired by the background health
+            // check, which drives this flag via live pings.
             if !is_connected() {
                 warn!(
                     timeout = ?timeout,
-                    "Operation timed out and connection state is disconnected, attempting reconnect"
+                    "Operation timed out and connection state is disconnected"
                 );
-                reconnect().await?;
-                retry_once(breaker, timeout, timeout_is_outage_signal, &operation).await
             } else {
                 debug!(
                     timeout = ?timeout,
-                    "Operation timed out but connection state is healthy, not reconnecting"
+                    "Operation timed out but connection state is healthy"
                 );
-                Err(AppError::OperationTimeout(format!(
-                    "Operation timed out after {:?}",
-                    timeout
-                )))
             }
+            Err(AppError::OperationTimeout(format!(
+                "Operation timed out after {:?}",
+                timeout
+            )))
         }
     }
 }
 
-/// Single post-reconnect retry with timeout and circuit-breaker bookkeeping.
-/// Shared by both reconnect paths of [`run_resilient`]. Deliberately does
-/// not re-pass the breaker gate (see module docs, "Retry and the breaker
-/// gate").
+/// Single post-reconnect retry, bounded by what is left of the operation's
+/// `timeout` budget at `deadline`, with circuit-breaker bookkeeping.
+/// Deliberately does not re-pass the breaker gate (see module docs, "Retry
+/// and the breaker gate").
 async fn retry_once<T, F, Fut>(
     breaker: &CircuitBreaker,
+    deadline: Instant,
     timeout: Duration,
     timeout_is_outage_signal: bool,
     operation: &F,
@@ -206,7 +237,14 @@ where
     F: Fn() -> Fut,
     Fut: Future<Output = AppResult<T>>,
 {
-    match tokio::time::timeout(timeout, operation()).await {
+    if Instant::now() >= deadline {
+        // Nothing left to retry with; the first attempt's outcome is
+        // already recorded
+        return Err(AppError::OperationTimeout(format!(
+            "Operation used up its {timeout:?} budget before its retry"
+        )));
+    }
+    match tokio::time::timeout_at(deadline, operation()).await {
         Ok(Ok(value)) => {
             breaker.record_success().await;
             Ok(value)
@@ -232,7 +270,7 @@ where
                 breaker.release_probe().await;
             }
             Err(AppError::OperationTimeout(format!(
-                "Operation timed out after {:?} on retry",
+                "Operation used up its {:?} budget on retry",
                 timeout
             )))
         }
@@ -350,10 +388,14 @@ mod tests {
     }
 
     #[tokio::test(start_paused = true)]
-    async fn timeout_while_disconnected_reconnects_and_retry_succeeds() {
+    async fn timeout_while_disconnected_is_not_retried() {
+        // A timed-out first attempt has used the whole budget: no reconnect
+        // wait and no retry, even with the connection reported lost (the
+        // health check is already reconnecting).
         let breaker = breaker_with(5);
         let calls = Arc::new(AtomicU32::new(0));
         let reconnects = Arc::new(AtomicU32::new(0));
+        let started = Instant::now();
 
         let op_calls = Arc::clone(&calls);
         let result: AppResult<u32> = run_resilient(
@@ -365,19 +407,17 @@ mod tests {
             move || {
                 let calls = Arc::clone(&op_calls);
                 async move {
-                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
-                        std::future::pending().await
-                    } else {
-                        Ok(7)
-                    }
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    std::future::pending().await
                 }
             },
         )
         .await;
 
-        assert_eq!(result.unwrap(), 7);
-        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
-        assert_eq!(calls.load(Ordering::SeqCst), 2, "exactly one retry");
+        assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+        assert_eq!(started.elapsed(), TIMEOUT);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "no retry");
     }
 
     #[tokio::test(start_paused = true)]
@@ -603,42 +643,75 @@ mod tests {
     }
 
     #[tokio::test(start_paused = true)]
-    async fn timeout_branch_reconnect_failure_propagates() {
-        // The timeout-while-disconnected branch has its own `reconnect().await?`;
-        // its failure must propagate without a retry.
+    async fn retry_gets_only_the_remaining_budget() {
+        // First attempt fails after 3s, the reconnect takes 1s: the retry
+        // is cut off at the 5s budget, not 5s after it started.
         let breaker = breaker_with(5);
         let calls = Arc::new(AtomicU32::new(0));
-        let reconnects = Arc::new(AtomicU32::new(0));
+        let started = Instant::now();
 
         let op_calls = Arc::clone(&calls);
         let result: AppResult<u32> = run_resilient(
             &breaker,
             TIMEOUT,
             true,
-            || false, // disconnected
-            fake_reconnect(
-                &reconnects,
-                Err(AppError::ConnectionFailed("reconnect exhausted".into())),
-            ),
+            || true,
+            || async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            },
             move || {
                 let calls = Arc::clone(&op_calls);
                 async move {
-                    calls.fetch_add(1, Ordering::SeqCst);
-                    std::future::pending().await
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        tokio::time::sleep(Duration::from_secs(3)).await;
+                        Err(AppError::ConnectionFailed("first attempt".into()))
+                    } else {
+                        std::future::pending().await
+                    }
                 }
             },
         )
         .await;
 
         assert!(
-            matches!(&result, Err(AppError::ConnectionFailed(msg)) if msg.contains("reconnect exhausted"))
+            matches!(&result, Err(AppError::OperationTimeout(msg)) if msg.contains("on retry"))
         );
-        assert_eq!(
-            calls.load(Ordering::SeqCst),
-            1,
-            "no retry after failed reconnect"
+        assert_eq!(started.elapsed(), TIMEOUT);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconnect_outlasting_the_budget_skips_the_retry() {
+        let breaker = breaker_with(5);
+        let calls = Arc::new(AtomicU32::new(0));
+        let started = Instant::now();
+
+        let op_calls = Arc::clone(&calls);
+        let result: AppResult<u32> = run_resilient(
+            &breaker,
+            TIMEOUT,
+            true,
+            || true,
+            || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            },
+            move || {
+                let calls = Arc::clone(&op_calls);
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(AppError::ConnectionFailed("first attempt".into()))
+                }
+            },
+        )
+        .await;
+
+        assert!(
+            matches!(&result, Err(AppError::OperationTimeout(msg)) if msg.contains("reconnecting"))
         );
-        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
+        assert_eq!(started.elapsed(), TIMEOUT);
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "no retry");
     }
 
     #[tokio::test(start_paused = true)]
@@ -673,11 +746,10 @@ mod tests {
     }
 
     #[tokio::test(start_paused = true)]
-    async fn scoped_timeout_while_disconnected_still_reconnects_without_breaker_failures() {
-        // The H2 exemption suppresses breaker RECORDING for scoped
-        // deadlines, not RECOVERY: a scoped request finding the connection
-        // down must still trigger the reconnect, and neither its
-        // first-attempt timeout nor its retry timeout may feed the breaker.
+    async fn scoped_timeout_while_disconnected_is_not_a_breaker_failure() {
+        // The H2 exemption holds with the connection lost too: the scoped
+        // timeout must not feed the breaker, and with the budget spent
+        // there is no retry (recovery is the health check's).
         let breaker = breaker_with(1); // any recorded failure would open it
         let calls = Arc::new(AtomicU32::new(0));
         let reconnects = Arc::new(AtomicU32::new(0));
@@ -699,15 +771,13 @@ mod tests {
         )
         .await;
 
-        assert!(
-            matches!(&result, Err(AppError::OperationTimeout(msg)) if msg.contains("on retry"))
-        );
-        assert_eq!(reconnects.load(Ordering::SeqCst), 1, "recovery still fires");
-        assert_eq!(calls.load(Ordering::SeqCst), 2, "single retry");
+        assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+        assert_eq!(reconnects.load(Ordering::SeqCst), 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "no retry");
         assert_eq!(
             breaker.state().await,
             CircuitState::Closed,
-            "scoped timeouts on both attempts must not feed the breaker"
+            "a scoped timeout must not feed the breaker"
         );
     }
 
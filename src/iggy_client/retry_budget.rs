@@ -0,0 +1,101 @@
+//! Process-wide budget for reconnect-and-retry attempts.
+//!
+//! Every operation failing with a connection error (or timing out while
+//! disconnected) gets one reconnect and retry (see `resilience`). During a
+//! partial outage that doubles the load on a struggling server exactly when
+//! it can least take it. With `RETRY_BUDGET_PER_SEC` set, the whole process
+//! may start at most that many retries per second (token bucket, burst of
+//! one second's worth); an operation finding the budget empty fails at once
+//! with `retry_budget_exhausted` instead of retrying.
+//!
+//! The rejection carries a `retry_after_ms` of one token interval plus up
+//! to one more of random jitter, so rejected clients come back spread out
+//! instead of as a new synchronized wave.
+
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+
+use super::helpers::rand_jitter;
+use crate::error::{AppError, AppResult};
+
+/// Token bucket limiting retries across all operations of the process.
+pub struct RetryBudget {
+    /// `None` when unlimited
+    limiter: Option<DefaultDirectRateLimiter>,
+    /// Time to refill one token
+    interval: Duration,
+}
+
+impl RetryBudget {
+    /// Create a budget of `per_second` retries per second; 0 is unlimited.
+    pub fn new(per_second: u32) -> Self {
+        match NonZeroU32::new(per_second) {
+            Some(rate) => Self {
+                limiter: Some(RateLimiter::direct(Quota::per_second(rate))),
+                interval: Duration::from_secs(1) / rate.get(),
+            },
+            None => Self {
+                limiter: None,
+                interval: Duration::ZERO,
+            },
+        }
+    }
+
+    /// Check if retries are limited.
+    pub fn is_enabled(&self) -> bool {
+        self.limiter.is_some()
+    }
+
+    /// Take one retry from the budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::RetryBudgetExhausted` when the budget is empty.
+    pub fn acquire(&self) -> AppResult<()> {
+        let Some(limiter) = &self.limiter else {
+            return Ok(());
+        };
+        if limiter.check().is_ok() {
+            crate::metrics::record_retry_budget("granted");
+            Ok(())
+        } else {
+            crate::metrics::record_retry_budget("exhausted");
+            Err(AppError::RetryBudgetExhausted(
+                "Retry budget exhausted; not retrying the operation".to_string(),
+            ))
+        }
+    }
+
+    /// Jittered wait before a rejected caller should try again: one token
+    /// interval plus up to one more.
+    pub fn retry_after(&self) -> Duration {
+        self.interval.mul_f64(1.0 + rand_jitter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_rejects_once_empty() {
+        let budget = RetryBudget::new(2);
+        assert!(budget.acquire().is_ok());
+        assert!(budget.acquire().is_ok());
+
+        let exhausted = budget.acquire();
+        assert!(matches!(exhausted, Err(AppError::RetryBudgetExhausted(_))));
+
+        let wait = budget.retry_after();
+        assert!(wait >= Duration::from_millis(500) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_zero_budget_is_unlimited() {
+        let budget = RetryBudget::new(0);
+        assert!(!budget.is_enabled());
+        assert!((0..1_000).all(|_| budget.acquire().is_ok()));
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-request accounting of time spent in Iggy operations.
+//!
+//! [`measure_iggy_time`] runs a future (a whole HTTP request, for slow
+//! request logging) with a task-local tally; every operation going through
+//! `with_reconnect` while it runs adds its duration, retries and reconnects
+//! included. Operations outside a measured future, or on another task (a
+//! coalesced batch flush, background jobs), are not counted.
+//!
+//! Concurrent operations within one request are summed, so the total can
+//! exceed the request's wall-clock time.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::time::Duration;
+
+/// Time spent in Iggy operations by one measured future.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IggyTime {
+    /// Summed duration of the operations
+    pub total: Duration,
+    /// Number of operations
+    pub operations: u32,
+}
+
+tokio::task_local! {
+    static IGGY_TIME: Cell<IggyTime>;
+}
+
+/// Run `future`, returning its output and the Iggy time it accumulated.
+pub async fn measure_iggy_time<F: Future>(future: F) -> (F::Output, IggyTime) {
+    IGGY_TIME
+        .scope(Cell::new(IggyTime::default()), async move {
+            let output = future.await;
+            (output, IGGY_TIME.with(Cell::get))
+        })
+        .await
+}
+
+/// Add one operation to the enclosing measurement, if any.
+pub(crate) fn record_iggy_op(elapsed: Duration) {
+    // Outside `measure_iggy_time` there is nothing to add to
+    let _ = IGGY_TIME.try_with(|time| {
+        let mut tally = time.get();
+        tally.total += elapsed;
+        tally.operations += 1;
+        time.set(tally);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_measure_sums_operations_within_scope() {
+        record_iggy_op(Duration::from_millis(500));
+
+        let (output, time) = measure_iggy_time(async {
+            record_iggy_op(Duration::from_millis(30));
+            record_iggy_op(Duration::from_millis(12));
+            "done"
+        })
+        .await;
+
+        assert_eq!(output, "done");
+        assert_eq!(
+            time,
+            IggyTime {
+                total: Duration::from_millis(42),
+                operations: 2,
+            }
+        );
+    }
+}
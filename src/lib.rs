@@ -45,6 +45,11 @@
 //! }
 //! ```
 //!
+//! ## Typed Client
+//!
+//! Enable the `client` feature for [`client::ApiClient`], a `reqwest`-based
+//! client that reuses the [`models`] types for every endpoint.
+//!
 //! ## Security Configuration
 //!
 //! Enable API key authentication:
@@ -57,6 +62,9 @@
 //! RATE_LIMIT_RPS=100 RATE_LIMIT_BURST=50 cargo run
 //! ```
 
+pub mod bootstrap;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod config;
 pub mod error;
 pub mod handlers;
@@ -74,5 +82,5 @@ pub mod validation;
 pub use config::Config;
 pub use error::{AppError, AppResult};
 pub use iggy_client::{IggyClientWrapper, PollParams};
-pub use routes::build_router;
+pub use routes::{build_admin_router, build_router};
 pub use state::AppState;
@@ -2,10 +2,12 @@ use std::net::SocketAddr;
 use std::process::ExitCode;
 
 use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
-use iggy_sample::{AppState, Config, IggyClientWrapper, build_router, utils};
+use iggy_sample::iggy_client::{BrokerBackend, OperationClass};
+use iggy_sample::{AppState, Config, IggyClientWrapper, build_admin_router, build_router, utils};
 
 #[tokio::main]
 async fn main() -> ExitCode {
@@ -60,18 +62,22 @@ async fn run() -> Result<(), exitcode::ExitCode> {
         // Seed the gauges so every series exists from the first scrape -
         // absent-series is otherwise indistinguishable from healthy.
         iggy_sample::metrics::set_connection_status(false);
-        iggy_sample::metrics::set_circuit_breaker_state(0);
+        for class in OperationClass::ALL {
+            iggy_sample::metrics::set_circuit_breaker_state(class.as_str(), 0);
+        }
     } else {
         info!("Metrics exporter disabled (METRICS_PORT=0)");
     }
 
     // Initialize Iggy client
-    info!("Connecting to Iggy server...");
+    if config.broker_backend == BrokerBackend::Iggy {
+        info!("Connecting to Iggy server...");
+    }
     let iggy_client = IggyClientWrapper::new(config.clone()).await.map_err(|e| {
         error!("Failed to connect to Iggy server: {e}");
         exitcode::UNAVAILABLE
     })?;
-    info!("Successfully connected to Iggy server");
+    info!(backend = %config.broker_backend, "Broker ready");
     iggy_sample::metrics::set_connection_status(true);
 
     // Initialize default stream and topic
@@ -85,12 +91,30 @@ async fn run() -> Result<(), exitcode::ExitCode> {
         config.default_stream, config.default_topic
     );
 
+    // Separate client for the consume path, if configured
+    let read_client = match config.read_config() {
+        Some(read_config) => {
+            info!("Connecting to the Iggy read endpoint...");
+            let read_client = IggyClientWrapper::new(read_config).await.map_err(|e| {
+                error!("Failed to connect to the Iggy read endpoint: {e}");
+                exitcode::UNAVAILABLE
+            })?;
+            Some(read_client)
+        }
+        None => None,
+    };
+
     // Build application state and router
-    let state = AppState::new(iggy_client, config.clone());
+    let state = AppState::with_read_client(iggy_client, read_client, config.clone());
+    state.restore_state().await;
     let app = build_router(state.clone()).map_err(|e| {
         error!("Failed to build router: {e}");
         exitcode::CONFIG
     })?;
+    let admin_app = build_admin_router(state.clone()).map_err(|e| {
+        error!("Failed to build admin router: {e}");
+        exitcode::CONFIG
+    })?;
 
     // Start server
     let addr: SocketAddr = config.server_addr().parse().map_err(|e| {
@@ -110,20 +134,75 @@ async fn run() -> Result<(), exitcode::ExitCode> {
     info!("  POST /messages         - Send a message");
     info!("  GET  /messages         - Poll messages");
     info!("  POST /messages/batch   - Send batch of messages");
+    info!("  POST /messages/fanout  - Send one message to several topics");
     info!("  GET  /streams          - List streams");
     info!("  POST /streams          - Create stream");
     info!("  GET  /streams/{{name}}   - Get stream info");
-    info!("  DELETE /streams/{{name}} - Delete stream");
+    if admin_app.is_none() {
+        info!("  DELETE /streams/{{name}} - Delete stream");
+    }
+
+    // Admin listener, if ADMIN_PORT is set
+    let admin = match (admin_app, config.admin_addr()) {
+        (Some(admin_app), Some(admin_addr)) => {
+            let admin_addr: SocketAddr = admin_addr.parse().map_err(|e| {
+                error!("Invalid admin address: {e}");
+                exitcode::CONFIG
+            })?;
+            let admin_listener = TcpListener::bind(admin_addr).await.map_err(|e| {
+                error!("Failed to bind admin listener to {admin_addr}: {e}");
+                exitcode::UNAVAILABLE
+            })?;
+            info!("Admin routes (/admin/*, stream and topic DELETE) on http://{admin_addr}");
+            Some((admin_listener, admin_app))
+        }
+        _ => None,
+    };
 
     // Start server with graceful shutdown. ConnectInfo exposes the peer
     // address to the middleware stack, which TRUSTED_PROXIES enforcement
-    // needs to decide whether forwarded headers can be honored.
-    let serve_result = axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(utils::shutdown_signal())
-    .await;
+    // needs to decide whether forwarded headers can be honored. Open
+    // message taps never finish on their own and a benchmark can run for
+    // minutes, so both are ended as soon as the signal arrives for the
+    // drain to complete.
+    // A listener failing stops the other one too.
+    let shutdown = CancellationToken::new();
+    let tap = state.tap.clone();
+    let benchmark = state.benchmark.clone();
+    let signal = async {
+        tokio::select! {
+            () = utils::shutdown_signal() => {}
+            () = shutdown.cancelled() => {}
+        }
+        tap.close();
+        benchmark.stop();
+        shutdown.cancel();
+    };
+    let public = async {
+        let result = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await;
+        shutdown.cancel();
+        result
+    };
+    let admin = async {
+        let Some((admin_listener, admin_app)) = admin else {
+            return Ok(());
+        };
+        let result = axum::serve(
+            admin_listener,
+            admin_app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await;
+        shutdown.cancel();
+        result
+    };
+    let ((), public_result, admin_result) = tokio::join!(signal, public, admin);
+    let serve_result = public_result.and(admin_result);
 
     // Gracefully shutdown background tasks on BOTH exit paths - a serve
     // error must not leave the stats/health tasks running un-awaited.
@@ -8,17 +8,41 @@
 //! ## Counters
 //! - `iggy_messages_sent_total` - Total messages sent (with labels: stream, topic, status)
 //! - `iggy_messages_polled_total` - Total messages polled (with labels: stream, topic)
+//! - `iggy_poll_duplicates_filtered_total` - Events left out of polls as duplicates (labels: stream, topic)
 //! - `iggy_connection_reconnects_total` - Total reconnection attempts
-//! - `iggy_circuit_breaker_opens_total` - Times the circuit breaker opened
-//! - `iggy_circuit_breaker_rejections_total` - Requests rejected by circuit breaker (label: state = open | half_open)
+//! - `iggy_circuit_breaker_opens_total` - Times the circuit breaker opened (label: class = send | poll | admin)
+//! - `iggy_circuit_breaker_rejections_total` - Requests rejected by circuit breaker (labels: class, state = open | half_open)
+//! - `iggy_canary_failures_total` - Canary probes that failed or timed out
+//! - `iggy_operation_retries_total` - Iggy operations that reconnected and retried (labels: operation, outcome)
+//! - `iggy_retry_budget_total` - Retries asked of `RETRY_BUDGET_PER_SEC` (label: outcome = granted | exhausted)
+//! - `iggy_failovers_total` - Reconnect sessions moving on to the next of `IGGY_FALLBACK_SERVERS`
+//! - `iggy_spool_events_total` - Events through the outage spool (label: outcome = spooled | drained | dropped | rejected)
+//! - `iggy_outbox_dropped_total` - Sends lost by the in-memory outbox (label: reason = overflow | full | rejected)
+//! - `iggy_shadow_sends_total` - Events copied to shadow topics by `SHADOW_RULES` (labels: topic, outcome = success | failure)
+//! - `iggy_ip_filter_rejections_total` - Requests rejected by `IP_ALLOWLIST`/`IP_DENYLIST` (label: reason = denylisted | not_allowlisted)
+//! - `iggy_fair_queue_starved_total` - Iggy operations that waited longer than `FAIR_QUEUE_STARVATION_MS` for a slot, or timed out (label: tenant)
+//! - `iggy_pipeline_events_total` - Source events handled by pipelines (labels: pipeline, outcome = produced | filtered | failed | transform_error)
+//! - `iggy_replicated_messages_total` - Local messages handled by the replicator (labels: stream, topic, outcome = replicated | skipped | failed)
 //!
 //! ## Histograms
 //! - `iggy_send_duration_seconds` - Message send duration
 //! - `iggy_poll_duration_seconds` - Message poll duration
+//! - `iggy_event_time_lag_seconds` - Time from an event's timestamp to its poll (labels: stream, topic)
+//! - `iggy_canary_rtt_seconds` - Canary send-to-read-back round-trip time
+//! - `iggy_operation_duration_seconds` - Iggy operation duration, retries included (labels: operation, outcome, retried)
+//! - `iggy_http_request_size_bytes` - Request body size (labels: method, route)
+//! - `iggy_http_response_size_bytes` - Response body size (labels: method, route)
+//! - `iggy_fair_queue_wait_seconds` - Wait of Iggy operations for a fair queue slot (label: tenant)
 //!
 //! ## Gauges
 //! - `iggy_connection_status` - Current connection status (1 = connected, 0 = disconnected)
-//! - `iggy_circuit_breaker_state` - Circuit breaker state (0 = closed, 1 = half-open, 2 = open)
+//! - `iggy_circuit_breaker_state` - Circuit breaker state per class (0 = closed, 1 = half-open, 2 = open)
+//! - `iggy_active_endpoint` - 1 for the Iggy server in use, 0 for the others tried (label: endpoint)
+//! - `iggy_consumer_lag` - Unconsumed messages per partition for monitored consumers (labels: stream, topic, consumer_id, partition)
+//! - `iggy_http_requests_in_flight` - HTTP requests currently being handled (with `MAX_IN_FLIGHT_REQUESTS` set)
+//! - `iggy_spool_pending_bytes` - Bytes in the outage spool waiting to be delivered (with `SPOOL_DIR` set)
+//! - `iggy_outbox_depth` - Sends in the in-memory outbox waiting to be delivered (with `OUTBOX_CAPACITY` set)
+//! - `iggy_replication_lag` - Local messages not yet replicated to the remote cluster (labels: stream, topic)
 //!
 //! # Usage
 //!
@@ -42,13 +66,38 @@ use tracing::info;
 pub mod names {
     pub const MESSAGES_SENT_TOTAL: &str = "iggy_messages_sent_total";
     pub const MESSAGES_POLLED_TOTAL: &str = "iggy_messages_polled_total";
+    pub const POLL_DUPLICATES_FILTERED_TOTAL: &str = "iggy_poll_duplicates_filtered_total";
     pub const CONNECTION_RECONNECTS_TOTAL: &str = "iggy_connection_reconnects_total";
     pub const CIRCUIT_BREAKER_OPENS_TOTAL: &str = "iggy_circuit_breaker_opens_total";
     pub const CIRCUIT_BREAKER_REJECTIONS_TOTAL: &str = "iggy_circuit_breaker_rejections_total";
     pub const SEND_DURATION_SECONDS: &str = "iggy_send_duration_seconds";
     pub const POLL_DURATION_SECONDS: &str = "iggy_poll_duration_seconds";
+    pub const EVENT_TIME_LAG_SECONDS: &str = "iggy_event_time_lag_seconds";
     pub const CONNECTION_STATUS: &str = "iggy_connection_status";
     pub const CIRCUIT_BREAKER_STATE: &str = "iggy_circuit_breaker_state";
+    pub const CONSUMER_LAG: &str = "iggy_consumer_lag";
+    pub const CANARY_RTT_SECONDS: &str = "iggy_canary_rtt_seconds";
+    pub const CANARY_FAILURES_TOTAL: &str = "iggy_canary_failures_total";
+    pub const HTTP_REQUESTS_IN_FLIGHT: &str = "iggy_http_requests_in_flight";
+    pub const HTTP_REQUEST_SIZE_BYTES: &str = "iggy_http_request_size_bytes";
+    pub const HTTP_RESPONSE_SIZE_BYTES: &str = "iggy_http_response_size_bytes";
+    pub const OPERATION_DURATION_SECONDS: &str = "iggy_operation_duration_seconds";
+    pub const OPERATION_RETRIES_TOTAL: &str = "iggy_operation_retries_total";
+    pub const RETRY_BUDGET_TOTAL: &str = "iggy_retry_budget_total";
+    pub const FAILOVERS_TOTAL: &str = "iggy_failovers_total";
+    pub const ACTIVE_ENDPOINT: &str = "iggy_active_endpoint";
+    pub const SPOOL_EVENTS_TOTAL: &str = "iggy_spool_events_total";
+    pub const SPOOL_PENDING_BYTES: &str = "iggy_spool_pending_bytes";
+    pub const OUTBOX_DROPPED_TOTAL: &str = "iggy_outbox_dropped_total";
+    pub const OUTBOX_DEPTH: &str = "iggy_outbox_depth";
+    pub const SHADOW_SENDS_TOTAL: &str = "iggy_shadow_sends_total";
+    pub const LEAK_SUSPECTED_TOTAL: &str = "iggy_leak_suspected_total";
+    pub const IP_FILTER_REJECTIONS_TOTAL: &str = "iggy_ip_filter_rejections_total";
+    pub const FAIR_QUEUE_WAIT_SECONDS: &str = "iggy_fair_queue_wait_seconds";
+    pub const FAIR_QUEUE_STARVED_TOTAL: &str = "iggy_fair_queue_starved_total";
+    pub const PIPELINE_EVENTS_TOTAL: &str = "iggy_pipeline_events_total";
+    pub const REPLICATED_MESSAGES_TOTAL: &str = "iggy_replicated_messages_total";
+    pub const REPLICATION_LAG: &str = "iggy_replication_lag";
 }
 
 /// Initialize the Prometheus metrics exporter.
@@ -91,6 +140,58 @@ pub fn init_metrics(metrics_addr: SocketAddr) -> Result<(), String> {
         names::CIRCUIT_BREAKER_REJECTIONS_TOTAL,
         "Total number of requests rejected by circuit breaker"
     );
+    describe_counter!(
+        names::CANARY_FAILURES_TOTAL,
+        "Total number of canary probes that failed or timed out"
+    );
+    describe_counter!(
+        names::OPERATION_RETRIES_TOTAL,
+        "Total number of Iggy operations that reconnected and retried"
+    );
+    describe_counter!(
+        names::RETRY_BUDGET_TOTAL,
+        "Total number of retries asked of the retry budget, granted or exhausted"
+    );
+    describe_counter!(
+        names::FAILOVERS_TOTAL,
+        "Total number of failovers to the next Iggy server"
+    );
+    describe_counter!(
+        names::SPOOL_EVENTS_TOTAL,
+        "Total number of events spooled, drained, dropped or rejected by the outage spool"
+    );
+    describe_counter!(
+        names::OUTBOX_DROPPED_TOTAL,
+        "Total number of sends evicted, refused or dropped by the in-memory outbox"
+    );
+    describe_counter!(
+        names::SHADOW_SENDS_TOTAL,
+        "Total number of events copied to shadow topics"
+    );
+    describe_counter!(
+        names::LEAK_SUSPECTED_TOTAL,
+        "Total number of leak checks that found an internal counter still growing"
+    );
+    describe_counter!(
+        names::IP_FILTER_REJECTIONS_TOTAL,
+        "Total number of requests rejected by the IP allow or deny list"
+    );
+    describe_counter!(
+        names::FAIR_QUEUE_STARVED_TOTAL,
+        "Total number of Iggy operations that waited too long for a fair queue slot"
+    );
+    describe_counter!(
+        names::POLL_DUPLICATES_FILTERED_TOTAL,
+        "Total number of events left out of polls as duplicates"
+    );
+    describe_counter!(
+        names::PIPELINE_EVENTS_TOTAL,
+        "Total number of source events produced, filtered out, failed or skipped by pipelines"
+    );
+    describe_counter!(
+        names::REPLICATED_MESSAGES_TOTAL,
+        "Total number of local messages replicated, skipped as replicas or failed by the replicator"
+    );
 
     describe_histogram!(
         names::SEND_DURATION_SECONDS,
@@ -100,15 +201,63 @@ pub fn init_metrics(metrics_addr: SocketAddr) -> Result<(), String> {
         names::POLL_DURATION_SECONDS,
         "Message poll operation duration in seconds"
     );
+    describe_histogram!(
+        names::EVENT_TIME_LAG_SECONDS,
+        "Time from an event's timestamp to its poll in seconds"
+    );
+    describe_histogram!(
+        names::CANARY_RTT_SECONDS,
+        "Canary heartbeat send-to-read-back round-trip time in seconds"
+    );
+    describe_histogram!(
+        names::HTTP_REQUEST_SIZE_BYTES,
+        "HTTP request body size in bytes, per route"
+    );
+    describe_histogram!(
+        names::HTTP_RESPONSE_SIZE_BYTES,
+        "HTTP response body size in bytes, per route"
+    );
+    describe_histogram!(
+        names::OPERATION_DURATION_SECONDS,
+        "Iggy operation duration in seconds, including reconnect and retry"
+    );
+    describe_histogram!(
+        names::FAIR_QUEUE_WAIT_SECONDS,
+        "Wait of Iggy operations for a fair queue slot in seconds, per tenant"
+    );
 
     describe_gauge!(
         names::CONNECTION_STATUS,
         "Iggy connection status (1 = connected, 0 = disconnected)"
     );
+    describe_gauge!(
+        names::ACTIVE_ENDPOINT,
+        "Iggy server in use (1) or previously in use (0), by endpoint"
+    );
+    describe_gauge!(
+        names::SPOOL_PENDING_BYTES,
+        "Bytes in the outage spool waiting to be delivered to Iggy"
+    );
+    describe_gauge!(
+        names::OUTBOX_DEPTH,
+        "Sends in the in-memory outbox waiting to be delivered to Iggy"
+    );
     describe_gauge!(
         names::CIRCUIT_BREAKER_STATE,
         "Circuit breaker state (0 = closed, 1 = half-open, 2 = open)"
     );
+    describe_gauge!(
+        names::CONSUMER_LAG,
+        "Messages not yet consumed per partition, for monitored consumer IDs"
+    );
+    describe_gauge!(
+        names::HTTP_REQUESTS_IN_FLIGHT,
+        "HTTP requests currently being handled (load shedding enabled)"
+    );
+    describe_gauge!(
+        names::REPLICATION_LAG,
+        "Local messages not yet replicated to the remote cluster, per topic"
+    );
 
     info!(addr = %metrics_addr, "Prometheus metrics endpoint started");
     Ok(())
@@ -136,23 +285,118 @@ pub fn record_messages_polled(stream: &str, topic: &str, count: u64) {
         .increment(count);
 }
 
+/// Record `count` events left out of a poll of `stream`/`topic` as
+/// duplicates.
+pub fn record_poll_duplicates(stream: &str, topic: &str, count: u64) {
+    if count > 0 {
+        counter!(names::POLL_DUPLICATES_FILTERED_TOTAL, "stream" => stream.to_string(), "topic" => topic.to_string())
+            .increment(count);
+    }
+}
+
 /// Record a reconnection attempt.
 pub fn record_reconnect_attempt() {
     counter!(names::CONNECTION_RECONNECTS_TOTAL).increment(1);
 }
 
-/// Record circuit breaker opening.
-pub fn record_circuit_breaker_open() {
-    counter!(names::CIRCUIT_BREAKER_OPENS_TOTAL).increment(1);
+/// Record a failover to the next Iggy server.
+pub fn record_failover() {
+    counter!(names::FAILOVERS_TOTAL).increment(1);
 }
 
-/// Record circuit breaker rejection.
+/// Record `count` events through the outage spool.
+///
+/// `outcome` is `"spooled"` (written while Iggy was unreachable),
+/// `"drained"` (delivered later), `"dropped"` (rejected by Iggy on
+/// delivery) or `"rejected"` (the spool could not take them).
+pub fn record_spool_events(outcome: &'static str, count: u64) {
+    counter!(names::SPOOL_EVENTS_TOTAL, "outcome" => outcome).increment(count);
+}
+
+/// Record `count` sends lost by the in-memory outbox.
+///
+/// `reason` is `"overflow"` (evicted by `drop_oldest`), `"full"` (refused
+/// by a full outbox) or `"rejected"` (rejected by Iggy on delivery).
+pub fn record_outbox_dropped(reason: &'static str, count: u64) {
+    counter!(names::OUTBOX_DROPPED_TOTAL, "reason" => reason).increment(count);
+}
+
+/// Record `count` events copied to the shadow topic `topic`.
+pub fn record_shadow_sends(topic: &str, outcome: &'static str, count: u64) {
+    counter!(names::SHADOW_SENDS_TOTAL, "topic" => topic.to_string(), "outcome" => outcome)
+        .increment(count);
+}
+
+/// Record a leak check finding `counter` (a field of `GET /admin/internals`)
+/// still growing.
+pub fn record_leak_suspected(counter: &'static str) {
+    counter!(names::LEAK_SUSPECTED_TOTAL, "counter" => counter).increment(1);
+}
+
+/// Record a request rejected by the IP filter.
+///
+/// `reason` is `"denylisted"` (on `IP_DENYLIST`) or `"not_allowlisted"`
+/// (missing from `IP_ALLOWLIST`, or not resolved).
+pub fn record_ip_filter_rejection(reason: &'static str) {
+    counter!(names::IP_FILTER_REJECTIONS_TOTAL, "reason" => reason).increment(1);
+}
+
+/// Record the wait of an Iggy operation for a fair queue slot.
+pub fn record_fair_queue_wait(tenant: &str, seconds: f64) {
+    histogram!(names::FAIR_QUEUE_WAIT_SECONDS, "tenant" => tenant.to_string()).record(seconds);
+}
+
+/// Record an Iggy operation starved of a fair queue slot.
+pub fn record_fair_queue_starved(tenant: &str) {
+    counter!(names::FAIR_QUEUE_STARVED_TOTAL, "tenant" => tenant.to_string()).increment(1);
+}
+
+/// Record `count` source events of the pipeline `pipeline`.
+///
+/// `outcome` is `"produced"` (sent to the sink), `"filtered"` (dropped by
+/// the filter or the custom transform), `"failed"` (their sink send
+/// failed; they are retried) or `"transform_error"` (the custom transform
+/// failed on them; they are skipped).
+pub fn record_pipeline_events(pipeline: &str, outcome: &'static str, count: u64) {
+    counter!(names::PIPELINE_EVENTS_TOTAL, "pipeline" => pipeline.to_string(), "outcome" => outcome)
+        .increment(count);
+}
+
+/// Record `count` local messages of `stream`/`topic` handled by the
+/// replicator.
+///
+/// `outcome` is `"replicated"` (sent to the remote cluster), `"skipped"`
+/// (themselves replicas, see `REPLICATED_FROM_HEADER`) or `"failed"`
+/// (their remote send failed; they are retried).
+pub fn record_replicated_messages(stream: &str, topic: &str, outcome: &'static str, count: u64) {
+    counter!(names::REPLICATED_MESSAGES_TOTAL, "stream" => stream.to_string(), "topic" => topic.to_string(), "outcome" => outcome)
+        .increment(count);
+}
+
+/// Update the replication lag gauge of `stream`/`topic`.
+pub fn set_replication_lag(stream: &str, topic: &str, lag: u64) {
+    gauge!(names::REPLICATION_LAG, "stream" => stream.to_string(), "topic" => topic.to_string())
+        .set(lag as f64);
+}
+
+/// Record the opening of the `class` circuit breaker.
+pub fn record_circuit_breaker_open(class: &'static str) {
+    counter!(names::CIRCUIT_BREAKER_OPENS_TOTAL, "class" => class).increment(1);
+}
+
+/// Record a rejection by the `class` circuit breaker.
 ///
 /// `state` labels which breaker state rejected the request (`"open"` or
 /// `"half_open"`), so operators can distinguish a hard-open circuit from an
 /// exhausted half-open probe budget during recovery.
-pub fn record_circuit_breaker_rejection(state: &'static str) {
-    counter!(names::CIRCUIT_BREAKER_REJECTIONS_TOTAL, "state" => state).increment(1);
+pub fn record_circuit_breaker_rejection(class: &'static str, state: &'static str) {
+    counter!(names::CIRCUIT_BREAKER_REJECTIONS_TOTAL, "class" => class, "state" => state)
+        .increment(1);
+}
+
+/// Record a failed or timed-out canary probe.
+pub fn record_canary_failure() {
+    counter!(names::CANARY_FAILURES_TOTAL).increment(1);
 }
 
 // =============================================================================
@@ -171,6 +415,50 @@ pub fn record_poll_duration(stream: &str, topic: &str, duration_secs: f64) {
         .record(duration_secs);
 }
 
+/// Record the event-time lag of an event polled from `stream`/`topic`.
+pub fn record_event_time_lag(stream: &str, topic: &str, lag_secs: f64) {
+    histogram!(names::EVENT_TIME_LAG_SECONDS, "stream" => stream.to_string(), "topic" => topic.to_string())
+        .record(lag_secs);
+}
+
+/// Record a successful canary round trip.
+pub fn record_canary_rtt(rtt_secs: f64) {
+    histogram!(names::CANARY_RTT_SECONDS).record(rtt_secs);
+}
+
+/// Record one Iggy operation run through the resilience layer.
+///
+/// `operation` is the operation kind (`send`, `poll`, `create_topic`, ...),
+/// `outcome` is `success` or the error code, and `retried` tells whether it
+/// reconnected and retried; retried operations are also counted in
+/// `iggy_operation_retries_total`.
+pub fn record_operation(operation: &'static str, outcome: &'static str, retried: bool, secs: f64) {
+    let retried_label = if retried { "true" } else { "false" };
+    histogram!(names::OPERATION_DURATION_SECONDS, "operation" => operation, "outcome" => outcome, "retried" => retried_label)
+        .record(secs);
+    if retried {
+        counter!(names::OPERATION_RETRIES_TOTAL, "operation" => operation, "outcome" => outcome)
+            .increment(1);
+    }
+}
+
+/// Record a retry asked of the retry budget, `granted` or `exhausted`.
+pub fn record_retry_budget(outcome: &'static str) {
+    counter!(names::RETRY_BUDGET_TOTAL, "outcome" => outcome).increment(1);
+}
+
+/// Record an HTTP request body size.
+pub fn record_request_size(method: &str, route: &str, bytes: u64) {
+    histogram!(names::HTTP_REQUEST_SIZE_BYTES, "method" => method.to_string(), "route" => route.to_string())
+        .record(bytes as f64);
+}
+
+/// Record an HTTP response body size.
+pub fn record_response_size(method: &str, route: &str, bytes: u64) {
+    histogram!(names::HTTP_RESPONSE_SIZE_BYTES, "method" => method.to_string(), "route" => route.to_string())
+        .record(bytes as f64);
+}
+
 // =============================================================================
 // Gauge Recording Functions
 // =============================================================================
@@ -180,11 +468,48 @@ pub fn set_connection_status(connected: bool) {
     gauge!(names::CONNECTION_STATUS).set(if connected { 1.0 } else { 0.0 });
 }
 
-/// Update circuit breaker state gauge.
+/// Mark `active` as the Iggy server in use and `previous` as no longer in
+/// use.
+pub fn set_active_endpoint(active: Option<&str>, previous: Option<&str>) {
+    if let Some(previous) = previous {
+        gauge!(names::ACTIVE_ENDPOINT, "endpoint" => previous.to_string()).set(0.0);
+    }
+    if let Some(active) = active {
+        gauge!(names::ACTIVE_ENDPOINT, "endpoint" => active.to_string()).set(1.0);
+    }
+}
+
+/// Update the outage spool backlog gauge.
+pub fn set_spool_pending_bytes(bytes: u64) {
+    gauge!(names::SPOOL_PENDING_BYTES).set(bytes as f64);
+}
+
+/// Update the outbox depth gauge.
+pub fn set_outbox_depth(sends: usize) {
+    gauge!(names::OUTBOX_DEPTH).set(sends as f64);
+}
+
+/// Update the state gauge of the `class` circuit breaker.
 ///
 /// States: 0 = closed, 1 = half-open, 2 = open
-pub fn set_circuit_breaker_state(state: u8) {
-    gauge!(names::CIRCUIT_BREAKER_STATE).set(f64::from(state));
+pub fn set_circuit_breaker_state(class: &'static str, state: u8) {
+    gauge!(names::CIRCUIT_BREAKER_STATE, "class" => class).set(f64::from(state));
+}
+
+/// Update the consumer lag gauge for one partition.
+pub fn set_consumer_lag(stream: &str, topic: &str, consumer_id: u32, partition_id: u32, lag: u64) {
+    gauge!(names::CONSUMER_LAG, "stream" => stream.to_string(), "topic" => topic.to_string(), "consumer_id" => consumer_id.to_string(), "partition" => partition_id.to_string())
+        .set(lag as f64);
+}
+
+/// Count a request admitted by load shedding.
+pub fn increment_requests_in_flight() {
+    gauge!(names::HTTP_REQUESTS_IN_FLIGHT).increment(1.0);
+}
+
+/// Count an admitted request as finished.
+pub fn decrement_requests_in_flight() {
+    gauge!(names::HTTP_REQUESTS_IN_FLIGHT).decrement(1.0);
 }
 
 #[cfg(test)]
@@ -213,8 +538,25 @@ mod tests {
 
     #[test]
     fn test_set_circuit_breaker_state() {
-        set_circuit_breaker_state(0); // closed
-        set_circuit_breaker_state(1); // half-open
-        set_circuit_breaker_state(2); // open
+        set_circuit_breaker_state("send", 0); // closed
+        set_circuit_breaker_state("poll", 1); // half-open
+        set_circuit_breaker_state("admin", 2); // open
+    }
+
+    #[test]
+    fn test_record_canary_metrics() {
+        record_canary_rtt(0.012);
+        record_canary_failure();
+    }
+
+    #[test]
+    fn test_requests_in_flight() {
+        increment_requests_in_flight();
+        decrement_requests_in_flight();
+    }
+
+    #[test]
+    fn test_set_consumer_lag() {
+        set_consumer_lag("test-stream", "test-topic", 1, 0, 42);
     }
 }
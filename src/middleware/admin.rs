@@ -0,0 +1,124 @@
+//! Admin scope enforcement for privileged routes.
+//!
+//! Routes that manage Iggy credentials (`/admin/users`) require a second,
+//! separate key in the `X-Admin-Key` header, on top of the regular
+//! `X-API-Key` authentication. Holding the service API key alone is not
+//! enough to create users or change permissions.
+//!
+//! # Usage
+//!
+//! ```bash
+//! ADMIN_API_KEY=admin-secret cargo run
+//!
+//! curl -H "X-API-Key: $API_KEY" -H "X-Admin-Key: admin-secret" \
+//!   http://localhost:8000/admin/users
+//! ```
+//!
+//! # Fail-Closed Behavior
+//!
+//! When `ADMIN_API_KEY` is not set, admin-scoped routes reject every request
+//! with 403: an unset key disables the routes rather than opening them.
+//! The comparison is constant-time, like the API key check.
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::warn;
+
+use super::auth::constant_time_eq;
+use crate::error::AppError;
+
+/// Header name for the admin key.
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// Admin scope configuration shared by the admin middleware.
+#[derive(Clone, Default)]
+pub struct AdminScope {
+    /// Expected admin key (None = admin routes disabled)
+    key: Option<Arc<String>>,
+}
+
+impl AdminScope {
+    /// Create an admin scope from the configured `ADMIN_API_KEY`.
+    pub fn new(key: Option<String>) -> Self {
+        Self {
+            key: key.map(Arc::new),
+        }
+    }
+
+    /// Check if admin routes are enabled (a key is configured).
+    pub fn is_enabled(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Check a provided admin key against the configured one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Forbidden` if admin routes are disabled or the
+    /// key is missing or wrong.
+    pub fn check(&self, provided: Option<&str>) -> Result<(), AppError> {
+        let Some(expected) = &self.key else {
+            return Err(AppError::Forbidden(
+                "Admin endpoints are disabled (ADMIN_API_KEY not set)".to_string(),
+            ));
+        };
+        match provided {
+            Some(key) if constant_time_eq(key, expected) => Ok(()),
+            Some(_) => Err(AppError::Forbidden("Invalid admin key".to_string())),
+            None => Err(AppError::Forbidden("Admin key required".to_string())),
+        }
+    }
+}
+
+/// Middleware that requires a valid `X-Admin-Key` header.
+///
+/// Apply with `axum::middleware::from_fn_with_state(scope, require_admin_scope)`
+/// as a `route_layer` on the admin routes only.
+pub async fn require_admin_scope(
+    State(scope): State<AdminScope>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    if let Err(e) = scope.check(provided) {
+        warn!(path = %request.uri().path(), "Admin scope check failed");
+        return e.into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_scope_rejects_everything() {
+        let scope = AdminScope::new(None);
+        assert!(!scope.is_enabled());
+        assert!(matches!(scope.check(None), Err(AppError::Forbidden(_))));
+        assert!(matches!(
+            scope.check(Some("anything")),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_enabled_scope_requires_matching_key() {
+        let scope = AdminScope::new(Some("admin-secret".to_string()));
+        assert!(scope.is_enabled());
+        assert!(scope.check(Some("admin-secret")).is_ok());
+        assert!(matches!(
+            scope.check(Some("wrong")),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(matches!(scope.check(None), Err(AppError::Forbidden(_))));
+    }
+}
@@ -31,6 +31,12 @@
 //! - `/ready` - Readiness probe
 //!
 //! This allows Kubernetes/load balancer health checks to function.
+//!
+//! # Request Signing
+//!
+//! With `REQUEST_SIGNING_SECRET` set, requests carrying `X-Signature` are
+//! authenticated by their HMAC signature instead (see `middleware::signing`).
+//! Setting only the signing secret requires every request to be signed.
 
 use std::num::NonZeroU32;
 use std::sync::Arc;
@@ -48,6 +54,8 @@ use tracing::{debug, error, warn};
 
 use super::ip::extract_client_ip_with_validation;
 use super::rate_limit::TrustedProxyConfig;
+use super::signing::{RequestSigning, SIGNATURE_HEADER};
+use crate::error::error_body;
 
 /// Header name for API key.
 pub const API_KEY_HEADER: &str = "x-api-key";
@@ -92,7 +100,8 @@ type AuthFailureLimiter = RateLimiter<String, DefaultKeyedStateStore<String>, De
 
 /// API key authentication layer.
 ///
-/// When the expected key is `None`, all requests are allowed (auth disabled).
+/// When neither an expected key nor request signing is configured, all
+/// requests are allowed (auth disabled).
 /// Bypass paths can be configured via the `AUTH_BYPASS_PATHS` environment variable.
 ///
 /// # Brute Force Protection
@@ -111,6 +120,8 @@ pub struct ApiKeyAuth {
     failure_limiter: Option<Arc<AuthFailureLimiter>>,
     /// Trusted proxy configuration for spoofing-resistant IP extraction
     trusted_proxies: Arc<TrustedProxyConfig>,
+    /// Verifier of `X-Signature` (None = request signing disabled)
+    signing: Option<RequestSigning>,
 }
 
 impl ApiKeyAuth {
@@ -141,23 +152,29 @@ impl ApiKeyAuth {
         bypass_paths: Vec<String>,
         trusted_proxies: Arc<TrustedProxyConfig>,
     ) -> Self {
-        let failure_limiter = if api_key.is_some() {
-            // Only create rate limiter when auth is enabled
-            let quota = Quota::per_minute(DEFAULT_AUTH_FAILURE_LIMIT)
-                .allow_burst(DEFAULT_AUTH_FAILURE_BURST);
-            Some(Arc::new(RateLimiter::keyed(quota)))
-        } else {
-            None
-        };
+        // Only create rate limiter when auth is enabled
+        let failure_limiter = api_key.is_some().then(failure_limiter);
 
         Self {
             expected_key: api_key.map(Arc::new),
             bypass_paths: Arc::new(bypass_paths),
             failure_limiter,
             trusted_proxies,
+            signing: None,
         }
     }
 
+    /// Also accept requests signed with the shared secret of `signing`.
+    ///
+    /// Signed requests are verified instead of checking the API key, and
+    /// invalid signatures count against the same per-IP failure budget.
+    /// Without an API key, every request must be signed.
+    pub fn with_request_signing(mut self, signing: RequestSigning) -> Self {
+        self.failure_limiter.get_or_insert_with(failure_limiter);
+        self.signing = Some(signing);
+        self
+    }
+
     /// Create with default bypass paths ("/health", "/ready").
     pub fn with_defaults(api_key: Option<String>) -> Self {
         Self::new(
@@ -171,10 +188,17 @@ impl ApiKeyAuth {
 
     /// Check if authentication is enabled.
     pub fn is_enabled(&self) -> bool {
-        self.expected_key.is_some()
+        self.expected_key.is_some() || self.signing.is_some()
     }
 }
 
+/// Create the per-IP auth failure limiter.
+fn failure_limiter() -> Arc<AuthFailureLimiter> {
+    let quota =
+        Quota::per_minute(DEFAULT_AUTH_FAILURE_LIMIT).allow_burst(DEFAULT_AUTH_FAILURE_BURST);
+    Arc::new(RateLimiter::keyed(quota))
+}
+
 impl<S> Layer<S> for ApiKeyAuth {
     type Service = ApiKeyAuthService<S>;
 
@@ -185,6 +209,7 @@ impl<S> Layer<S> for ApiKeyAuth {
             bypass_paths: self.bypass_paths.clone(),
             failure_limiter: self.failure_limiter.clone(),
             trusted_proxies: self.trusted_proxies.clone(),
+            signing: self.signing.clone(),
         }
     }
 }
@@ -197,6 +222,7 @@ pub struct ApiKeyAuthService<S> {
     bypass_paths: Arc<Vec<String>>,
     failure_limiter: Option<Arc<AuthFailureLimiter>>,
     trusted_proxies: Arc<TrustedProxyConfig>,
+    signing: Option<RequestSigning>,
 }
 
 impl<S> Service<Request<Body>> for ApiKeyAuthService<S>
@@ -219,14 +245,14 @@ where
         let bypass_paths = self.bypass_paths.clone();
         let failure_limiter = self.failure_limiter.clone();
         let trusted_proxies = self.trusted_proxies.clone();
+        let signing = self.signing.clone();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
-            // If no API key is configured, allow all requests
-            let expected = match expected_key {
-                Some(key) => key,
-                None => return inner.call(req).await,
-            };
+            // If neither an API key nor signing is configured, allow all requests
+            if expected_key.is_none() && signing.is_none() {
+                return inner.call(req).await;
+            }
 
             // Check if path should bypass authentication
             let path = req.uri().path();
@@ -235,14 +261,44 @@ where
                 return inner.call(req).await;
             }
 
+            // A signed request stands or falls by its signature, whatever
+            // API key it also carries.
+            if let Some(signing) = signing
+                && req.headers().contains_key(SIGNATURE_HEADER)
+            {
+                let client_ip =
+                    extract_client_ip_with_validation(&req, &trusted_proxies).into_owned();
+                let path = req.uri().path().to_string();
+                return match signing.verify_request(req).await {
+                    Ok(req) => {
+                        debug!("Request signature authentication successful");
+                        inner.call(req).await
+                    }
+                    Err(e) => {
+                        if let Some(response) = throttle(failure_limiter.as_deref(), &client_ip) {
+                            return Ok(response);
+                        }
+                        warn!(
+                            path = %path,
+                            client_ip = %client_ip,
+                            reason = %e,
+                            "Invalid request signature"
+                        );
+                        Ok(unauthorized_response(&e.to_string()))
+                    }
+                };
+            }
+
             // Validate the key FIRST. The failure limiter only meters
             // FAILURES: consuming a token on every request would throttle
             // legitimate clients down to the failure budget (~10 req/min/IP),
             // and all direct clients share the "unknown" bucket.
             let provided_key = extract_api_key(&req);
 
-            match provided_key {
-                Some(extracted) if constant_time_eq(&extracted.key, &expected) => {
+            match (provided_key, expected_key) {
+                (Some(extracted), Some(expected))
+                    if constant_time_eq(&extracted.key, &expected) =>
+                {
                     // Valid API key - proceed without touching the limiter
                     debug!(
                         from_query = extracted.from_query,
@@ -250,30 +306,26 @@ where
                     );
                     inner.call(req).await
                 }
-                provided => {
+                (provided, expected) => {
                     // Auth failure: consume one failure token for this IP.
                     // Once the failure budget is exhausted, respond 429 so
                     // brute-force attempts are throttled.
                     let client_ip =
                         extract_client_ip_with_validation(&req, &trusted_proxies).into_owned();
 
-                    if let Some(ref limiter) = failure_limiter
-                        && let Err(not_until) = limiter.check_key(&client_ip)
-                    {
-                        let wait_time = not_until
-                            .wait_time_from(governor::clock::DefaultClock::default().now());
-                        let retry_after = wait_time.as_secs().max(1);
+                    if let Some(response) = throttle(failure_limiter.as_deref(), &client_ip) {
+                        return Ok(response);
+                    }
 
-                        error!(
+                    if expected.is_none() {
+                        // Signing only: an API key is no substitute
+                        warn!(
+                            path = %req.uri().path(),
                             client_ip = %client_ip,
-                            retry_after_secs = retry_after,
-                            "IP blocked due to excessive auth failures"
+                            "Unsigned request"
                         );
-
-                        return Ok(rate_limited_response(retry_after));
-                    }
-
-                    if provided.is_some() {
+                        Ok(unauthorized_response("Request signature required"))
+                    } else if provided.is_some() {
                         warn!(
                             path = %req.uri().path(),
                             client_ip = %client_ip,
@@ -294,6 +346,22 @@ where
     }
 }
 
+/// Consume one failure token for `client_ip`: the 429 response once its
+/// failure budget is exhausted.
+fn throttle(limiter: Option<&AuthFailureLimiter>, client_ip: &str) -> Option<Response<Body>> {
+    let not_until = limiter?.check_key(&client_ip.to_string()).err()?;
+    let wait_time = not_until.wait_time_from(governor::clock::DefaultClock::default().now());
+    let retry_after = wait_time.as_secs().max(1);
+
+    error!(
+        client_ip = %client_ip,
+        retry_after_secs = retry_after,
+        "IP blocked due to excessive auth failures"
+    );
+
+    Some(rate_limited_response(retry_after))
+}
+
 /// Result of extracting an API key with metadata about the source.
 struct ExtractedApiKey {
     key: String,
@@ -353,7 +421,7 @@ fn extract_api_key<B>(req: &Request<B>) -> Option<ExtractedApiKey> {
 ///
 /// This prevents timing attacks where an attacker could determine
 /// the correct API key by measuring response times.
-fn constant_time_eq(a: &str, b: &str) -> bool {
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
     let a_bytes = a.as_bytes();
     let b_bytes = b.as_bytes();
 
@@ -370,7 +438,7 @@ fn unauthorized_response(message: &str) -> Response<Body> {
             ("WWW-Authenticate", "API-Key"),
             ("Content-Type", "application/json"),
         ],
-        format!(r#"{{"error":"unauthorized","message":"{}"}}"#, message),
+        error_body("unauthorized", message, false, None),
     )
         .into_response()
 }
@@ -383,7 +451,12 @@ fn rate_limited_response(retry_after: u64) -> Response<Body> {
             ("Retry-After", retry_after.to_string()),
             ("Content-Type", "application/json".to_string()),
         ],
-        r#"{"error":"too_many_requests","message":"Too many failed authentication attempts. Please wait before retrying."}"#.to_string(),
+        error_body(
+            "too_many_requests",
+            "Too many failed authentication attempts. Please wait before retrying.",
+            true,
+            Some(retry_after * 1000),
+        ),
     )
         .into_response()
 }
@@ -392,6 +465,7 @@ fn rate_limited_response(retry_after: u64) -> Response<Body> {
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
+    use crate::middleware::signing::{SIGNATURE_TIMESTAMP_HEADER, sign};
 
     /// Minimal inner service returning 200 OK, for driving the auth layer.
     #[derive(Clone)]
@@ -463,6 +537,26 @@ mod tests {
         }
         assert!(saw_429, "failure budget was never exhausted");
 
+        // The 429 carries the shared retry fields so clients can back off
+        // without special-casing auth throttling.
+        let resp = svc.call(request_with_key(Some("wrong"))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            body.get("error"),
+            Some(&serde_json::json!("too_many_requests"))
+        );
+        assert_eq!(body.get("retryable"), Some(&serde_json::json!(true)));
+        assert!(
+            body.get("retry_after_ms")
+                .and_then(serde_json::Value::as_u64)
+                .unwrap()
+                >= 1000
+        );
+
         // A VALID key from the same (blocked) bucket still succeeds: only
         // failures are blocked, legitimate clients are unaffected.
         let resp = svc.call(request_with_key(Some("secret"))).await.unwrap();
@@ -494,6 +588,70 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 
+    fn signed_request(secret: &str, body: &'static str) -> Request<Body> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let signature = sign(secret, "POST", "/messages", timestamp, body.as_bytes());
+        Request::builder()
+            .method("POST")
+            .uri("/messages")
+            .header(SIGNATURE_HEADER, signature)
+            .header(SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn signing_only() -> ApiKeyAuth {
+        ApiKeyAuth::with_defaults(None).with_request_signing(RequestSigning::new(
+            "shared".to_string(),
+            std::time::Duration::from_secs(300),
+            1024,
+            100,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests_are_verified_instead_of_the_key() {
+        let auth = signing_only();
+        assert!(auth.is_enabled());
+        let mut svc = auth.layer(OkService);
+
+        let resp = svc.call(signed_request("shared", "{}")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = svc.call(signed_request("guessed", "{}")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Signing only: an unsigned request is rejected, key or not
+        let resp = svc.call(request_with_key(Some("shared"))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_bad_signature_is_rejected_despite_valid_key() {
+        let auth = ApiKeyAuth::with_defaults(Some("secret".to_string())).with_request_signing(
+            RequestSigning::new(
+                "shared".to_string(),
+                std::time::Duration::from_secs(300),
+                1024,
+                100,
+            ),
+        );
+        let mut svc = auth.layer(OkService);
+
+        let mut req = signed_request("guessed", "{}");
+        req.headers_mut()
+            .insert(API_KEY_HEADER, "secret".parse().unwrap());
+        let resp = svc.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Unsigned requests still authenticate with the key
+        let resp = svc.call(request_with_key(Some("secret"))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[test]
     fn test_api_key_auth_enabled() {
         let auth = ApiKeyAuth::with_defaults(Some("secret".to_string()));
@@ -0,0 +1,227 @@
+//! Fault injection for resilience testing (`chaos` feature).
+//!
+//! Rules set at runtime through `POST /admin/chaos` inject faults into
+//! requests to matching routes, each with its own probability:
+//!
+//! - `latency` - delay the request before the handler runs
+//! - `error` - answer with the given status instead of running the handler
+//! - `disconnect` - force the Iggy connection state to disconnected, then
+//!   run the request; operations that time out reconnect, and the next
+//!   background health probe restores the state if the server is fine
+//!
+//! ```bash
+//! curl -X POST -H "X-Admin-Key: $ADMIN_API_KEY" -H 'Content-Type: application/json' \
+//!   http://localhost:8000/admin/chaos \
+//!   -d '{"rules": [{"route": "/messages", "probability": 0.2,
+//!                  "fault": {"type": "latency", "ms": 750}}]}'
+//! ```
+//!
+//! `/admin/chaos` itself is never affected, so injection can always be
+//! turned off again (`{"rules": []}`). The feature is off by default and
+//! must never be enabled in production builds.
+
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::warn;
+
+use crate::error::{AppError, AppResult, error_body};
+use crate::models::{ChaosConfig, ChaosFault, ChaosRule};
+use crate::state::AppState;
+
+/// Route of the chaos endpoint, exempt from injection.
+const CHAOS_ROUTE: &str = "/admin/chaos";
+
+/// Upper bound on injected latency, so a typo cannot park requests for hours.
+const MAX_LATENCY: Duration = Duration::from_secs(60);
+
+/// Fault injection rules, replaced as a whole by `POST /admin/chaos`.
+#[derive(Debug, Default)]
+pub struct Chaos {
+    rules: RwLock<Vec<ChaosRule>>,
+}
+
+impl Chaos {
+    /// Create with no rules (injection off).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current rules.
+    pub fn config(&self) -> ChaosConfig {
+        ChaosConfig {
+            rules: self
+                .rules
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        }
+    }
+
+    /// Replace the rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` for an empty route, a probability
+    /// outside 0.0 - 1.0, latency above 60s, or an error status outside
+    /// 400 - 599. Nothing is replaced then.
+    pub fn set(&self, config: ChaosConfig) -> AppResult<()> {
+        for rule in &config.rules {
+            validate_rule(rule)?;
+        }
+        if !config.rules.is_empty() {
+            warn!(rules = config.rules.len(), "Chaos injection enabled");
+        }
+        *self.rules.write().unwrap_or_else(PoisonError::into_inner) = config.rules;
+        Ok(())
+    }
+
+    /// Roll the rules matching `route` in order; the first hit's fault.
+    pub fn pick(&self, route: &str) -> Option<ChaosFault> {
+        if route == CHAOS_ROUTE {
+            return None;
+        }
+        let rules = self.rules.read().unwrap_or_else(PoisonError::into_inner);
+        rules
+            .iter()
+            .filter(|rule| route_matches(&rule.route, route))
+            .find(|rule| rule.probability >= 1.0 || rand::random::<f64>() < rule.probability)
+            .map(|rule| rule.fault)
+    }
+}
+
+fn validate_rule(rule: &ChaosRule) -> AppResult<()> {
+    if rule.route.is_empty() {
+        return Err(AppError::BadRequest(
+            "Chaos rule route must not be empty".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&rule.probability) {
+        return Err(AppError::BadRequest(format!(
+            "Chaos rule probability must be between 0.0 and 1.0, got {}",
+            rule.probability
+        )));
+    }
+    match rule.fault {
+        ChaosFault::Latency { ms } if Duration::from_millis(ms) > MAX_LATENCY => Err(
+            AppError::BadRequest(format!("Chaos latency must be at most 60000ms, got {ms}")),
+        ),
+        ChaosFault::Error { status } if !(400..=599).contains(&status) => {
+            Err(AppError::BadRequest(format!(
+                "Chaos error status must be 400 - 599, got {status}"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether rule `pattern` covers `route`: equal, `*`, or a `*`-terminated
+/// prefix of it.
+fn route_matches(pattern: &str, route: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => route.starts_with(prefix),
+        None => pattern == route,
+    }
+}
+
+/// Middleware injecting the faults of `state.chaos`.
+///
+/// Apply with `axum::middleware::from_fn_with_state(state, inject_chaos)`.
+pub async fn inject_chaos(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || request.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+    let Some(fault) = state.chaos.pick(&route) else {
+        return next.run(request).await;
+    };
+
+    warn!(route = %route, ?fault, "Injecting chaos fault");
+    match fault {
+        ChaosFault::Latency { ms } => {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            next.run(request).await
+        }
+        ChaosFault::Error { status } => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let retryable = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+            (
+                status,
+                [(CONTENT_TYPE, "application/json")],
+                error_body(
+                    "chaos_injected",
+                    "Fault injected by chaos testing",
+                    retryable,
+                    None,
+                ),
+            )
+                .into_response()
+        }
+        ChaosFault::Disconnect => {
+            state.iggy_client.simulate_disconnect();
+            next.run(request).await
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn rule(route: &str, probability: f64, fault: ChaosFault) -> ChaosRule {
+        ChaosRule {
+            route: route.to_string(),
+            probability,
+            fault,
+        }
+    }
+
+    #[test]
+    fn test_rules_match_exact_routes_and_prefixes() {
+        assert!(route_matches("*", "/messages"));
+        assert!(route_matches("/streams/*", "/streams/{stream}/topics"));
+        assert!(route_matches("/messages", "/messages"));
+        assert!(!route_matches("/messages", "/messages/batch"));
+    }
+
+    #[test]
+    fn test_first_hit_wins_and_the_chaos_route_is_exempt() {
+        let chaos = Chaos::new();
+        let error = ChaosFault::Error { status: 503 };
+        chaos
+            .set(ChaosConfig {
+                rules: vec![
+                    rule("/messages", 0.0, ChaosFault::Disconnect),
+                    rule("*", 1.0, error),
+                ],
+            })
+            .unwrap();
+
+        assert_eq!(chaos.pick("/messages"), Some(error));
+        assert_eq!(chaos.pick(CHAOS_ROUTE), None);
+
+        chaos.set(ChaosConfig::default()).unwrap();
+        assert_eq!(chaos.pick("/messages"), None);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected_without_replacing() {
+        let chaos = Chaos::new();
+        for bad in [
+            rule("*", 1.5, ChaosFault::Disconnect),
+            rule("", 0.5, ChaosFault::Disconnect),
+            rule("*", 0.5, ChaosFault::Error { status: 200 }),
+            rule("*", 0.5, ChaosFault::Latency { ms: 600_000 }),
+        ] {
+            let result = chaos.set(ChaosConfig { rules: vec![bad] });
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+        assert!(chaos.config().rules.is_empty());
+    }
+}
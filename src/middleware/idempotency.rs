@@ -0,0 +1,606 @@
+//! `Idempotency-Key` support for the mutating endpoints.
+//!
+//! A client retrying `POST /messages`, `/messages/batch`,
+//! `/messages/fanout`, `/streams` or a topic creation after a lost response
+//! sends the same `Idempotency-Key` header. The first response is stored
+//! for `IDEMPOTENCY_TTL_SECS` and replayed to the retries, marked with
+//! `Idempotent-Replayed: true`, instead of sending or creating twice.
+//!
+//! Responses are stored by key, method, path and query, with a SHA-256 of
+//! the request body: reusing a key with a different body, or while the
+//! first request is still running, is a `409 Conflict`. Server errors (5xx)
+//! are not stored, so a retry after one runs again.
+//!
+//! At `IDEMPOTENCY_MAX_KEYS`, the oldest stored response is dropped for a
+//! new key. Requests still in flight are never dropped, so the store can
+//! briefly hold more keys than that while that many requests are running.
+//!
+//! # Persistence
+//!
+//! Responses are kept in this instance's memory and, with
+//! `STATE_BACKEND=sqlite`, also written to the [`StateStore`] (namespace
+//! `idempotency`) once the request completes, and removed from it when they
+//! expire or are dropped here. A key this instance has not seen is looked
+//! up there before the request runs, so retries are still replayed after a
+//! restart and, with a shared SQLite file, on another replica. Requests in
+//! flight are only known to the instance running them: the same key sent
+//! to two replicas at once can run twice. Bodies that are not UTF-8 are
+//! kept in memory only. `AppState::restore_state` removes responses that
+//! expired while no instance was running. The `iggy` journal is not used:
+//! it is never truncated, so keyed traffic would slow every startup down.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use axum::body::{Body, Bytes, to_bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+use crate::services::{IDEMPOTENCY_NAMESPACE, JournalState, StateStore};
+use crate::state::AppState;
+use crate::utils::{hex, unhex};
+
+/// Header carrying the client's idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header marking a replayed response.
+pub const REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest accepted idempotency key.
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// Key, then `METHOD /path?query`.
+type EntryId = (String, String);
+
+/// SHA-256 of a request body.
+type BodyHash = [u8; 32];
+
+/// A response kept for replay.
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// A stored response as written to the [`StateStore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedResponse {
+    /// Hex SHA-256 of the request body
+    body_hash: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    stored_at: DateTime<Utc>,
+}
+
+impl PersistedResponse {
+    /// `None` when the body is not UTF-8.
+    fn new(body_hash: BodyHash, response: &StoredResponse) -> Option<Self> {
+        Some(Self {
+            body_hash: hex(&body_hash),
+            status: response.status.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: String::from_utf8(response.body.to_vec()).ok()?,
+            stored_at: Utc::now(),
+        })
+    }
+
+    fn is_expired(&self, ttl: Duration) -> bool {
+        (Utc::now() - self.stored_at)
+            .to_std()
+            .is_ok_and(|age| age >= ttl)
+    }
+
+    fn body_hash(&self) -> Option<BodyHash> {
+        unhex(&self.body_hash)?.try_into().ok()
+    }
+
+    fn response(self) -> Option<StoredResponse> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            headers.append(
+                HeaderName::try_from(name).ok()?,
+                HeaderValue::try_from(value).ok()?,
+            );
+        }
+        Some(StoredResponse {
+            status: StatusCode::from_u16(self.status).ok()?,
+            headers,
+            body: Bytes::from(self.body),
+        })
+    }
+}
+
+/// Key of an entry in the [`StateStore`]: the idempotency key (which has no
+/// spaces), a space, then the route.
+fn persisted_key(id: &EntryId) -> String {
+    format!("{} {}", id.0, id.1)
+}
+
+impl IntoResponse for StoredResponse {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, self.headers, self.body).into_response();
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+#[derive(Debug)]
+enum Slot {
+    /// The first request is still running
+    InFlight,
+    Done(StoredResponse),
+}
+
+#[derive(Debug)]
+struct Entry {
+    body_hash: BodyHash,
+    slot: Slot,
+    stored_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_id: HashMap<EntryId, Entry>,
+    /// Stored responses in the order they were stored, which is also the
+    /// order they expire in; records of entries since replaced are skipped
+    stored: VecDeque<(Instant, EntryId)>,
+    /// Stored responses dropped since last taken, to remove from the
+    /// [`StateStore`]
+    dropped: Vec<EntryId>,
+}
+
+impl Entries {
+    /// Drop the oldest stored response while `drop` accepts its time.
+    fn drop_oldest_while(&mut self, mut drop: impl FnMut(&Self, Instant) -> bool) {
+        while let Some((stored_at, id)) = self.stored.front() {
+            if !drop(self, *stored_at) {
+                break;
+            }
+            let current = self.by_id.get(id).is_some_and(|entry| {
+                entry.stored_at == *stored_at && matches!(entry.slot, Slot::Done(_))
+            });
+            if current {
+                self.by_id.remove(id);
+                self.dropped.push(id.clone());
+            }
+            self.stored.pop_front();
+        }
+    }
+}
+
+/// What to do with a request carrying a key.
+#[derive(Debug)]
+enum Claim {
+    /// First use of the key: run the request
+    Run,
+    Replay(StoredResponse),
+    Reject(AppError),
+}
+
+/// Responses to requests carrying an `Idempotency-Key`, kept for replay.
+pub struct IdempotencyStore {
+    ttl: Duration,
+    max_keys: usize,
+    entries: Mutex<Entries>,
+    /// Where completed responses are also kept (see the module docs)
+    state_store: Arc<dyn StateStore>,
+}
+
+impl IdempotencyStore {
+    /// Create a store replaying responses for `ttl`, keeping at most
+    /// `max_keys` of them (at least 1) in memory and persisting them to
+    /// `state_store`.
+    pub fn new(ttl: Duration, max_keys: usize, state_store: Arc<dyn StateStore>) -> Self {
+        Self {
+            ttl,
+            max_keys: max_keys.max(1),
+            entries: Mutex::new(Entries::default()),
+            state_store,
+        }
+    }
+
+    /// Number of stored responses and requests in flight.
+    pub fn key_count(&self) -> usize {
+        self.lock().by_id.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Look `id` up, marking it in flight if it is new (or expired).
+    fn claim(&self, id: &EntryId, body_hash: BodyHash) -> Claim {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        entries.drop_oldest_while(|_, stored_at| now.duration_since(stored_at) >= self.ttl);
+        if let Some(entry) = entries.by_id.get(id)
+            && now.duration_since(entry.stored_at) < self.ttl
+        {
+            if entry.body_hash != body_hash {
+                return Claim::Reject(AppError::Conflict(
+                    "Idempotency-Key was already used with a different request body".to_string(),
+                ));
+            }
+            return match &entry.slot {
+                Slot::InFlight => Claim::Reject(AppError::Conflict(
+                    "A request with this Idempotency-Key is still in progress".to_string(),
+                )),
+                Slot::Done(response) => Claim::Replay(response.clone()),
+            };
+        }
+
+        // In-flight entries are not in `stored`, so they are never dropped
+        let max_keys = self.max_keys;
+        entries.drop_oldest_while(|entries, _| entries.by_id.len() >= max_keys);
+        entries.by_id.insert(
+            id.clone(),
+            Entry {
+                body_hash,
+                slot: Slot::InFlight,
+                stored_at: now,
+            },
+        );
+        Claim::Run
+    }
+
+    /// Store the response to the in-flight request `id`, returning the
+    /// hash of its body.
+    fn complete(&self, id: &EntryId, response: StoredResponse) -> Option<BodyHash> {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        let entry = entries.by_id.get_mut(id)?;
+        let body_hash = entry.body_hash;
+        entry.slot = Slot::Done(response);
+        entry.stored_at = now;
+        entries.stored.push_back((now, id.clone()));
+        Some(body_hash)
+    }
+
+    /// Look a key this instance does not know up in the [`StateStore`]: a
+    /// response persisted by an earlier run or another replica.
+    async fn claim_persisted(&self, id: &EntryId, body_hash: BodyHash) -> Claim {
+        if !self.state_store.is_enabled() {
+            return Claim::Run;
+        }
+        let key = persisted_key(id);
+        let persisted = match self.state_store.get(IDEMPOTENCY_NAMESPACE, &key).await {
+            Ok(Some(value)) => serde_json::from_value::<PersistedResponse>(value),
+            Ok(None) => return Claim::Run,
+            Err(e) => {
+                warn!(key = %id.0, error = %e, "Failed to look up persisted idempotent response");
+                return Claim::Run;
+            }
+        };
+        let persisted = match persisted {
+            Ok(persisted) if !persisted.is_expired(self.ttl) => persisted,
+            _ => {
+                self.state_store.delete(IDEMPOTENCY_NAMESPACE, &key).await;
+                return Claim::Run;
+            }
+        };
+        if persisted.body_hash() != Some(body_hash) {
+            return Claim::Reject(AppError::Conflict(
+                "Idempotency-Key was already used with a different request body".to_string(),
+            ));
+        }
+        persisted.response().map_or(Claim::Run, Claim::Replay)
+    }
+
+    /// Persist the completed response to `id`.
+    async fn persist(&self, id: &EntryId, body_hash: BodyHash, response: &StoredResponse) {
+        if !self.state_store.is_enabled() {
+            return;
+        }
+        if let Some(persisted) = PersistedResponse::new(body_hash, response) {
+            self.state_store
+                .put(IDEMPOTENCY_NAMESPACE, &persisted_key(id), &persisted)
+                .await;
+        }
+    }
+
+    /// Remove the responses dropped from memory from the [`StateStore`] too,
+    /// in the background.
+    fn forget_dropped(self: &Arc<Self>) {
+        let dropped = std::mem::take(&mut self.lock().dropped);
+        if dropped.is_empty() || !self.state_store.is_enabled() {
+            return;
+        }
+        let store = Arc::clone(self);
+        tokio::spawn(async move {
+            for id in dropped {
+                store
+                    .state_store
+                    .delete(IDEMPOTENCY_NAMESPACE, &persisted_key(&id))
+                    .await;
+            }
+        });
+    }
+
+    /// Remove the responses in `state` (as loaded at startup) that expired
+    /// or cannot be read from the [`StateStore`], returning how many.
+    pub async fn remove_expired(&self, state: &mut JournalState) -> usize {
+        let persisted = std::mem::take(state.namespace_mut(IDEMPOTENCY_NAMESPACE));
+        let mut removed = 0;
+        for (key, value) in persisted {
+            let live = serde_json::from_value::<PersistedResponse>(value)
+                .is_ok_and(|persisted| !persisted.is_expired(self.ttl));
+            if !live {
+                self.state_store.delete(IDEMPOTENCY_NAMESPACE, &key).await;
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Forget the in-flight request `id`, so a retry runs again.
+    fn release(&self, id: &EntryId) {
+        let mut entries = self.lock();
+        if entries
+            .by_id
+            .get(id)
+            .is_some_and(|entry| matches!(entry.slot, Slot::InFlight))
+        {
+            entries.by_id.remove(id);
+        }
+    }
+}
+
+/// Releases an in-flight key unless its response was stored, including
+/// when the request is cancelled.
+struct InFlight<'a> {
+    store: &'a IdempotencyStore,
+    id: EntryId,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.store.release(&self.id);
+    }
+}
+
+/// Middleware storing and replaying responses to requests that carry an
+/// `Idempotency-Key` header; requests without one pass through.
+///
+/// Apply with `axum::middleware::from_fn_with_state(state, replay_idempotent)`
+/// as a `route_layer` on the mutating routes.
+pub async fn replay_idempotent(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match parse_key(key) {
+        Ok(key) => key,
+        Err(e) => return e.into_response(),
+    };
+    let route = request
+        .uri()
+        .path_and_query()
+        .map_or_else(|| request.uri().path(), |path| path.as_str());
+    let id = (key, format!("{} {route}", request.method()));
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, state.config.max_request_body_size).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let store = &state.idempotency;
+    let body_hash = digest(&body);
+    let claim = store.claim(&id, body_hash);
+    store.forget_dropped();
+    match claim {
+        Claim::Run => {}
+        Claim::Replay(response) => {
+            debug!(key = %id.0, route = %id.1, "Replaying idempotent response");
+            return response.into_response();
+        }
+        Claim::Reject(e) => return e.into_response(),
+    }
+    let in_flight = InFlight { store, id };
+    match store.claim_persisted(&in_flight.id, body_hash).await {
+        Claim::Run => {}
+        Claim::Replay(response) => {
+            debug!(key = %in_flight.id.0, "Replaying persisted idempotent response");
+            return response.into_response();
+        }
+        Claim::Reject(e) => return e.into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => return AppError::Internal(format!("Reading response: {e}")).into_response(),
+    };
+    let stored = StoredResponse {
+        status: parts.status,
+        headers: parts.headers.clone(),
+        body: body.clone(),
+    };
+    if let Some(body_hash) = store.complete(&in_flight.id, stored.clone()) {
+        store.persist(&in_flight.id, body_hash, &stored).await;
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Validate an `Idempotency-Key` value: 1 to 255 visible ASCII characters.
+fn parse_key(value: &HeaderValue) -> Result<String, AppError> {
+    match value.to_str() {
+        Ok(key)
+            if !key.is_empty()
+                && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH
+                && key.bytes().all(|b| b.is_ascii_graphic()) =>
+        {
+            Ok(key.to_string())
+        }
+        _ => Err(AppError::BadRequest(format!(
+            "Idempotency-Key must be 1 to {MAX_IDEMPOTENCY_KEY_LENGTH} visible ASCII characters"
+        ))),
+    }
+}
+
+fn digest(body: &[u8]) -> BodyHash {
+    Sha256::digest(body).into()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::iggy_client::{BrokerBackend, IggyClientWrapper};
+    use crate::services::{MemoryStateStore, StateBackend, open_state_store};
+
+    fn memory() -> Arc<dyn StateStore> {
+        Arc::new(MemoryStateStore)
+    }
+
+    fn id(key: &str) -> EntryId {
+        (key.to_string(), "POST /messages".to_string())
+    }
+
+    fn response(body: &'static str) -> StoredResponse {
+        StoredResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_completed_request_is_replayed_for_the_same_body_only() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10, memory());
+        assert!(matches!(store.claim(&id("a"), digest(b"1")), Claim::Run));
+        assert!(matches!(
+            store.claim(&id("a"), digest(b"1")),
+            Claim::Reject(AppError::Conflict(_))
+        ));
+
+        store.complete(&id("a"), response("sent"));
+        match store.claim(&id("a"), digest(b"1")) {
+            Claim::Replay(replayed) => assert_eq!(replayed.body, "sent"),
+            other => panic!("expected a replay, got {other:?}"),
+        }
+        assert!(matches!(
+            store.claim(&id("a"), digest(b"2")),
+            Claim::Reject(AppError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn test_released_and_expired_keys_run_again() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10, memory());
+        assert!(matches!(store.claim(&id("a"), digest(b"1")), Claim::Run));
+        store.release(&id("a"));
+        assert!(matches!(store.claim(&id("a"), digest(b"1")), Claim::Run));
+
+        let expired = IdempotencyStore::new(Duration::ZERO, 10, memory());
+        assert!(matches!(expired.claim(&id("a"), digest(b"1")), Claim::Run));
+        expired.complete(&id("a"), response("sent"));
+        assert!(matches!(expired.claim(&id("a"), digest(b"1")), Claim::Run));
+    }
+
+    #[test]
+    fn test_oldest_key_is_dropped_when_full() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 2, memory());
+        for key in ["a", "b", "c"] {
+            assert!(matches!(store.claim(&id(key), digest(b"1")), Claim::Run));
+            store.complete(&id(key), response(key));
+        }
+        assert_eq!(store.key_count(), 2);
+        assert!(matches!(
+            store.claim(&id("c"), digest(b"1")),
+            Claim::Replay(_)
+        ));
+    }
+
+    #[test]
+    fn test_in_flight_keys_are_never_dropped() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 1, memory());
+        assert!(matches!(store.claim(&id("a"), digest(b"1")), Claim::Run));
+        assert!(matches!(store.claim(&id("b"), digest(b"1")), Claim::Run));
+        assert_eq!(store.key_count(), 2);
+        // "a" is still running, so its retry is not run a second time
+        assert!(matches!(
+            store.claim(&id("a"), digest(b"1")),
+            Claim::Reject(AppError::Conflict(_))
+        ));
+
+        store.complete(&id("a"), response("a"));
+        store.complete(&id("b"), response("b"));
+        assert!(matches!(store.claim(&id("c"), digest(b"1")), Claim::Run));
+        // Both stored responses made way for "c"
+        assert_eq!(store.key_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_responses_are_replayed_after_a_restart() {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            state_backend: StateBackend::Iggy,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config.clone()).await.unwrap();
+        let state_store = open_state_store(client, &config);
+        let ttl = Duration::from_secs(60);
+
+        let store = IdempotencyStore::new(ttl, 10, state_store.clone());
+        assert!(matches!(store.claim(&id("a"), digest(b"1")), Claim::Run));
+        let hash = store.complete(&id("a"), response("sent")).unwrap();
+        store.persist(&id("a"), hash, &response("sent")).await;
+
+        let restarted = IdempotencyStore::new(ttl, 10, state_store.clone());
+        assert!(matches!(
+            restarted.claim(&id("a"), digest(b"1")),
+            Claim::Run
+        ));
+        match restarted.claim_persisted(&id("a"), digest(b"1")).await {
+            Claim::Replay(replayed) => assert_eq!(replayed.body, "sent"),
+            other => panic!("expected a replay, got {other:?}"),
+        }
+        assert!(matches!(
+            restarted.claim_persisted(&id("a"), digest(b"2")).await,
+            Claim::Reject(AppError::Conflict(_))
+        ));
+
+        let mut state = state_store.replay().await.unwrap();
+        assert_eq!(restarted.remove_expired(&mut state).await, 0);
+        let expired = IdempotencyStore::new(Duration::ZERO, 10, state_store.clone());
+        let mut state = state_store.replay().await.unwrap();
+        assert_eq!(expired.remove_expired(&mut state).await, 1);
+        assert!(matches!(
+            restarted.claim_persisted(&id("a"), digest(b"1")).await,
+            Claim::Run
+        ));
+    }
+
+    #[test]
+    fn test_key_format() {
+        assert!(parse_key(&HeaderValue::from_static("order-42")).is_ok());
+        assert!(parse_key(&HeaderValue::from_static("has space")).is_err());
+        let long = "k".repeat(MAX_IDEMPOTENCY_KEY_LENGTH + 1);
+        assert!(parse_key(&HeaderValue::from_str(&long).unwrap()).is_err());
+    }
+}
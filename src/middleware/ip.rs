@@ -103,9 +103,12 @@
 
 use std::borrow::Cow;
 use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
-use axum::extract::ConnectInfo;
+use axum::extract::{ConnectInfo, State};
 use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
 use tracing::{debug, warn};
 
 use super::rate_limit::TrustedProxyConfig;
@@ -340,6 +343,25 @@ pub fn extract_client_ip<B>(req: &Request<B>) -> Cow<'static, str> {
     }
 }
 
+/// Client IP of a request, as resolved by [`record_client_ip`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIp(pub String);
+
+/// Middleware that resolves the client IP with
+/// [`extract_client_ip_with_validation`] and stores it in request
+/// extensions as [`ClientIp`], for handlers that record it (audit log).
+///
+/// Apply with `axum::middleware::from_fn_with_state(trusted_proxies, record_client_ip)`.
+pub async fn record_client_ip(
+    State(trusted_proxies): State<Arc<TrustedProxyConfig>>,
+    mut request: axum::extract::Request,
+    next: Next,
+) -> Response {
+    let ip = extract_client_ip_with_validation(&request, &trusted_proxies).into_owned();
+    request.extensions_mut().insert(ClientIp(ip));
+    next.run(request).await
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
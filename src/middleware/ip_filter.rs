@@ -0,0 +1,236 @@
+//! Per-IP allow and deny lists.
+//!
+//! `IP_ALLOWLIST` and `IP_DENYLIST` take IP addresses and CIDR ranges,
+//! matched against the client IP as resolved with `TRUSTED_PROXIES` (see
+//! `middleware::ip`), so forwarded headers only count when a trusted proxy
+//! sent them. Without `TRUSTED_PROXIES`, where rate limiting would trust
+//! any forwarded header, the lists are matched against the connection's
+//! peer address instead: a spoofed header must not open them.
+//!
+//! A request is rejected with `403 Forbidden` when its IP is on the deny
+//! list, or when an allow list is set and does not contain it. A client IP
+//! that cannot be resolved only passes a deny list.
+//!
+//! `IP_FILTER_PATHS` limits the lists to path prefixes, e.g. `/admin` to
+//! keep the admin endpoints on an internal network while producers connect
+//! from anywhere. Rejections are counted in `iggy_ip_filter_rejections_total`.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::{debug, warn};
+
+use super::ip::extract_client_ip_with_validation;
+use super::rate_limit::{CidrRange, RateLimitError, TrustedProxyConfig};
+use crate::error::AppError;
+use crate::metrics;
+
+/// Why a request was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    Denylisted,
+    NotAllowlisted,
+}
+
+impl Rejection {
+    fn label(self) -> &'static str {
+        match self {
+            Rejection::Denylisted => "denylisted",
+            Rejection::NotAllowlisted => "not_allowlisted",
+        }
+    }
+}
+
+/// Allow and deny lists of client IPs.
+#[derive(Debug, Clone)]
+pub struct IpFilter {
+    allow: Vec<CidrRange>,
+    deny: Vec<CidrRange>,
+    /// Path prefixes the lists apply to (empty = every path)
+    paths: Vec<String>,
+    trusted_proxies: Arc<TrustedProxyConfig>,
+}
+
+impl IpFilter {
+    /// Create a filter from allow and deny list entries (IPs or CIDR
+    /// ranges), applied to `paths` (every path if empty).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RateLimitError::InvalidIpFilterCidr`] on the first entry
+    /// that fails to parse: skipping a typo'd entry would open or close
+    /// the service to the wrong clients.
+    pub fn try_new(
+        allow: &[String],
+        deny: &[String],
+        paths: Vec<String>,
+        trusted_proxies: Arc<TrustedProxyConfig>,
+    ) -> Result<Self, RateLimitError> {
+        let parse = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| {
+                    CidrRange::parse(entry)
+                        .ok_or_else(|| RateLimitError::InvalidIpFilterCidr(entry.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allow: parse(allow)?,
+            deny: parse(deny)?,
+            paths,
+            trusted_proxies,
+        })
+    }
+
+    /// Check if either list has entries.
+    pub fn is_enabled(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// Whether the lists apply to `path`: it is, or is under, one of the
+    /// configured prefixes.
+    fn applies_to(&self, path: &str) -> bool {
+        self.paths.is_empty()
+            || self.paths.iter().any(|prefix| {
+                let prefix = prefix.trim_end_matches('/');
+                path == prefix
+                    || path
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+
+    /// Client IP of `request`: resolved through the trusted proxies, or the
+    /// peer address if none are configured.
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        if self.trusted_proxies.is_enabled() {
+            extract_client_ip_with_validation(request, &self.trusted_proxies)
+                .parse()
+                .ok()
+        } else {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(peer)| peer.ip())
+        }
+    }
+
+    /// Check a client IP (`None` if it could not be resolved) against the
+    /// lists; the deny list wins.
+    fn check(&self, ip: Option<IpAddr>) -> Result<(), Rejection> {
+        if let Some(ip) = ip
+            && self.deny.iter().any(|range| range.contains(&ip))
+        {
+            return Err(Rejection::Denylisted);
+        }
+        let allowed = ip.is_some_and(|ip| self.allow.iter().any(|range| range.contains(&ip)));
+        if self.allow.is_empty() || allowed {
+            Ok(())
+        } else {
+            Err(Rejection::NotAllowlisted)
+        }
+    }
+}
+
+/// Middleware rejecting requests from IPs the filter does not admit with
+/// `403 Forbidden`.
+///
+/// Apply with `axum::middleware::from_fn_with_state(Arc<IpFilter>, filter_ips)`.
+pub async fn filter_ips(
+    State(filter): State<Arc<IpFilter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !filter.applies_to(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let client_ip = filter.client_ip(&request);
+    match filter.check(client_ip) {
+        Ok(()) => {
+            debug!(client_ip = ?client_ip, "IP filter passed");
+            next.run(request).await
+        }
+        Err(rejection) => {
+            warn!(
+                client_ip = ?client_ip,
+                path = %request.uri().path(),
+                reason = rejection.label(),
+                "Request rejected by IP filter"
+            );
+            metrics::record_ip_filter_rejection(rejection.label());
+            AppError::Forbidden("Client IP is not allowed".to_string()).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn filter(allow: &[&str], deny: &[&str], paths: &[&str]) -> IpFilter {
+        let strings = |entries: &[&str]| -> Vec<String> {
+            entries.iter().map(|e| (*e).to_string()).collect()
+        };
+        IpFilter::try_new(
+            &strings(allow),
+            &strings(deny),
+            strings(paths),
+            Arc::new(TrustedProxyConfig::default()),
+        )
+        .unwrap()
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn test_deny_list_wins_over_allow_list() {
+        let filter = filter(&["10.0.0.0/8"], &["10.6.6.6"], &[]);
+        assert_eq!(filter.check(ip("10.1.2.3")), Ok(()));
+        assert_eq!(filter.check(ip("10.6.6.6")), Err(Rejection::Denylisted));
+        assert_eq!(
+            filter.check(ip("203.0.113.9")),
+            Err(Rejection::NotAllowlisted)
+        );
+    }
+
+    #[test]
+    fn test_unresolved_ip_passes_only_a_deny_list() {
+        assert_eq!(filter(&[], &["10.6.6.6"], &[]).check(None), Ok(()));
+        assert_eq!(
+            filter(&["10.0.0.0/8"], &[], &[]).check(None),
+            Err(Rejection::NotAllowlisted)
+        );
+    }
+
+    #[test]
+    fn test_paths_match_whole_segments() {
+        let filter = filter(&["10.0.0.0/8"], &[], &["/admin/"]);
+        assert!(filter.applies_to("/admin"));
+        assert!(filter.applies_to("/admin/audit"));
+        assert!(!filter.applies_to("/administrator"));
+        assert!(!filter.applies_to("/messages"));
+        assert!(self::filter(&[], &["::1"], &[]).applies_to("/messages"));
+    }
+
+    #[test]
+    fn test_invalid_entry_is_rejected() {
+        let result = IpFilter::try_new(
+            &["10.0.0.0/33".to_string()],
+            &[],
+            Vec::new(),
+            Arc::new(TrustedProxyConfig::default()),
+        );
+        assert!(matches!(
+            result,
+            Err(RateLimitError::InvalidIpFilterCidr(_))
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+//! Load shedding: a global cap on in-flight requests.
+//!
+//! Every request admitted to the handlers may hold an Iggy operation open.
+//! Under a traffic spike, queueing more of them only grows latency for
+//! everyone and piles work onto the single Iggy connection. With
+//! `MAX_IN_FLIGHT_REQUESTS` set, a request arriving while that many are
+//! already being handled is rejected immediately instead.
+//!
+//! Unlike `tower::limit::GlobalConcurrencyLimitLayer`, which makes callers
+//! wait for a slot, this layer never waits: saturation is answered with
+//! `503 Service Unavailable`, `Retry-After: 1` and the standard JSON error
+//! body (`"error": "overloaded"`, `"retryable": true`), so clients back off
+//! and other replicas can take the load.
+//!
+//! `/health` and `/ready` are never shed, so orchestrator probes keep
+//! working on a busy (but healthy) instance.
+//!
+//! # Metrics
+//!
+//! - `iggy_http_requests_in_flight` - requests currently being handled
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::Body;
+use axum::http::{Request, Response, StatusCode};
+use axum::response::IntoResponse;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::error::error_body;
+
+/// Paths that bypass load shedding (liveness and readiness probes).
+const PROBE_PATHS: [&str; 2] = ["/health", "/ready"];
+
+/// Seconds clients are told to wait after being shed.
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// Load-shedding layer for Tower middleware stack.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/api", get(handler))
+///     .layer(LoadShedLayer::new(512)); // at most 512 requests in flight
+/// ```
+#[derive(Clone)]
+pub struct LoadShedLayer {
+    slots: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+impl LoadShedLayer {
+    /// Create a layer admitting at most `max_in_flight` concurrent requests.
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+        }
+    }
+}
+
+impl<S> Layer<S> for LoadShedLayer {
+    type Service = LoadShedService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadShedService {
+            inner,
+            slots: self.slots.clone(),
+            max_in_flight: self.max_in_flight,
+        }
+    }
+}
+
+/// Load-shedding service wrapper.
+#[derive(Clone)]
+pub struct LoadShedService<S> {
+    inner: S,
+    slots: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+impl<S> Service<Request<Body>> for LoadShedService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+
+        if PROBE_PATHS.contains(&req.uri().path()) {
+            return Box::pin(async move { inner.call(req).await });
+        }
+
+        let Ok(permit) = self.slots.clone().try_acquire_owned() else {
+            warn!(
+                path = %req.uri().path(),
+                max_in_flight = self.max_in_flight,
+                "Server saturated, shedding request"
+            );
+            return Box::pin(async move { Ok(overloaded_response()) });
+        };
+
+        Box::pin(async move {
+            // Held until the handler has produced its response.
+            let _in_flight = InFlight::new(permit);
+            inner.call(req).await
+        })
+    }
+}
+
+/// An admitted request; keeps the in-flight gauge in step with its slot.
+struct InFlight {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl InFlight {
+    fn new(permit: OwnedSemaphorePermit) -> Self {
+        crate::metrics::increment_requests_in_flight();
+        Self { _permit: permit }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        crate::metrics::decrement_requests_in_flight();
+    }
+}
+
+/// 503 response for a shed request.
+fn overloaded_response() -> Response<Body> {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [
+            ("Retry-After", RETRY_AFTER_SECS.to_string()),
+            ("Content-Type", "application/json".to_string()),
+        ],
+        error_body(
+            "overloaded",
+            "Server is at capacity. Please retry later.",
+            true,
+            Some(RETRY_AFTER_SECS * 1000),
+        ),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    /// Minimal inner service returning 200 OK, for driving the layer.
+    #[derive(Clone)]
+    struct OkService;
+
+    impl Service<Request<Body>> for OkService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            std::future::ready(Ok(StatusCode::OK.into_response()))
+        }
+    }
+
+    fn request(path: &str) -> Request<Body> {
+        Request::builder().uri(path).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_limit_are_shed() {
+        let layer = LoadShedLayer::new(1);
+        let mut svc = layer.layer(OkService);
+
+        // Occupy the only slot, as a long-running request would.
+        let held = layer.slots.clone().try_acquire_owned().unwrap();
+
+        let resp = svc.call(request("/messages")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()["Retry-After"], "1");
+
+        // Probes are never shed.
+        let resp = svc.call(request("/health")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        drop(held);
+        let resp = svc.call(request("/messages")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        // Completed requests hand their slot back.
+        assert_eq!(layer.slots.available_permits(), 1);
+    }
+}
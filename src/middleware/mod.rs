@@ -3,17 +3,27 @@
 //! This module provides production-ready middleware components:
 //!
 //! - **Rate Limiting**: Token bucket algorithm with configurable RPS and burst
+//! - **Load Shedding**: Global in-flight cap answering 503 when saturated
 //! - **API Key Authentication**: Constant-time comparison for security
+//! - **Request Signing**: HMAC-SHA256 signatures with timestamps, instead of the API key
+//! - **Admin Scope**: Separate `X-Admin-Key` for credential-management routes
 //! - **Request ID**: Automatic generation and propagation for distributed tracing
 //! - **Request Timeout**: Client-specified timeout propagation
 //! - **Trusted Proxy Validation**: CIDR-based proxy source validation
+//! - **Client IP**: Resolved client IP in request extensions, for the audit log
+//! - **Tenant**: `X-Tenant-Id` (or client IP) scoping a request's Iggy operations for fair queuing
+//! - **IP Filter**: `IP_ALLOWLIST`/`IP_DENYLIST` CIDR lists answering 403
+//! - **Payload Sizes**: Body size histograms per route and the top-talkers report
+//! - **Slow Requests**: WARN logs with an auth/handler/Iggy time breakdown
+//! - **Chaos**: Runtime-configured fault injection (`chaos` feature)
+//! - **Idempotency**: Replay of responses to retries carrying an `Idempotency-Key`
 //!
 //! # Architecture
 //!
 //! ```text
-//! Request → Rate Limiter → Auth → Timeout → Request ID → Handler → Response
-//!              ↓              ↓       ↓           ↓
-//!          429 Too Many   401 Unauth  ext    X-Request-Id header
+//! Request → Rate Limiter → Auth → Load Shed → Timeout → Request ID → Handler → Response
+//!              ↓              ↓         ↓          ↓           ↓
+//!          429 Too Many   401 Unauth  503 Full    ext    X-Request-Id header
 //! ```
 //!
 //! # Security Considerations
@@ -24,16 +34,40 @@
 //! - Request IDs enable audit trails and debugging
 //! - Request timeout bounds prevent abuse via extreme values
 
+pub mod admin;
 pub mod auth;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod idempotency;
 pub mod ip;
+pub mod ip_filter;
+pub mod load_shed;
+pub mod payload_size;
 pub mod rate_limit;
 pub mod request_id;
+pub mod signing;
+pub mod slow_request;
+pub mod tenant;
 pub mod timeout;
 
+pub use admin::{ADMIN_KEY_HEADER, AdminScope, require_admin_scope};
 pub use auth::ApiKeyAuth;
-pub use ip::extract_client_ip_with_validation;
-pub use rate_limit::{RateLimitError, RateLimitLayer, TrustedProxyConfig};
-pub use request_id::RequestIdLayer;
+#[cfg(feature = "chaos")]
+pub use chaos::{Chaos, inject_chaos};
+pub use idempotency::{IDEMPOTENCY_KEY_HEADER, IdempotencyStore, replay_idempotent};
+pub use ip::{ClientIp, extract_client_ip_with_validation, record_client_ip};
+pub use ip_filter::{IpFilter, filter_ips};
+pub use load_shed::LoadShedLayer;
+pub use payload_size::record_payload_sizes;
+pub use rate_limit::{
+    RateLimitError, RateLimitLayer, RateLimitMode, RouteClass, TrustedProxyConfig,
+};
+pub use request_id::{CorrelationId, RequestId, RequestIdLayer};
+pub use signing::{
+    RequestSigning, SIGNATURE_HEADER, SIGNATURE_TIMESTAMP_HEADER, SignedRequest, sign,
+};
+pub use slow_request::{log_slow_requests, mark_authenticated};
+pub use tenant::{TENANT_HEADER, TenantScope, scope_tenant};
 pub use timeout::{
     MAX_REQUEST_TIMEOUT_MS, MIN_REQUEST_TIMEOUT_MS, REQUEST_TIMEOUT_HEADER, RequestTimeout,
     extract_request_timeout,
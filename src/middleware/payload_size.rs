@@ -0,0 +1,56 @@
+//! Request and response body size recording.
+//!
+//! Records every request and response body size per route as histograms
+//! (`iggy_http_request_size_bytes`, `iggy_http_response_size_bytes`), and
+//! feeds request sizes to the [`TopTalkers`] report by client IP.
+//!
+//! Sizes are taken from the body's exact size hint (`Content-Length` for
+//! requests) without buffering anything, so chunked request bodies and
+//! streamed poll responses are not measured. Routes are labelled by their
+//! pattern (`/streams/{stream}/topics`), not the concrete path, to keep
+//! label cardinality bounded.
+
+use std::sync::Arc;
+
+use axum::body::HttpBody as _;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use super::ip::{ClientIp, UNKNOWN_IP};
+use crate::metrics::{record_request_size, record_response_size};
+use crate::services::TopTalkers;
+
+/// Route label for requests that matched no route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Middleware that records body sizes and feeds the top-talkers report.
+///
+/// Apply with `axum::middleware::from_fn_with_state(talkers, record_payload_sizes)`
+/// inside the client IP layer, so [`ClientIp`] is available.
+pub async fn record_payload_sizes(
+    State(talkers): State<Arc<TopTalkers>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_string();
+    if let Some(bytes) = request.body().size_hint().exact() {
+        record_request_size(&method, &route, bytes);
+        let client_ip = request
+            .extensions()
+            .get::<ClientIp>()
+            .map_or(UNKNOWN_IP, |ip| ip.0.as_str());
+        talkers.record(client_ip, bytes);
+    }
+
+    let response = next.run(request).await;
+    if let Some(bytes) = response.body().size_hint().exact() {
+        record_response_size(&method, &route, bytes);
+    }
+    response
+}
@@ -16,6 +16,36 @@
 //! - `rate_limit_burst`: Instantaneous bucket capacity (governor's `allow_burst` REPLACES the default capacity; it is not added on top of RPS)
 //! - `trusted_proxies`: CIDR ranges of trusted reverse proxies
 //!
+//! # Route Classes
+//!
+//! One bucket per IP normally covers every endpoint, so a client polling
+//! heavily can use up the budget its producers need. Each [`RouteClass`]
+//! (read, write, admin) can be given its own quota with
+//! [`RateLimitLayer::with_class_limit`] (`RATE_LIMIT_READ_RPS`,
+//! `RATE_LIMIT_WRITE_RPS`, `RATE_LIMIT_ADMIN_RPS`); classes without one
+//! share the default bucket. The class is picked from the matched route, so
+//! this layer must be applied with `Router::layer` (after routing).
+//!
+//! # Queue Mode
+//!
+//! By default a request over the limit is rejected at once. With
+//! `RATE_LIMIT_MODE=queue` ([`RateLimitLayer::with_queueing`]), a request
+//! whose next token is due within `QUEUE_MAX_WAIT_MS` is held until then
+//! (governor's `until_key_ready`) and processed, so short bursts from
+//! well-behaved clients are smoothed instead of bounced. Requests that would
+//! wait longer, or are still not admitted when the wait expires, get the
+//! usual 429.
+//!
+//! # Adaptive Mode
+//!
+//! A recovering Iggy backend (circuit breaker open or half-open, or an
+//! app-level reconnect in progress) cannot absorb full request volume. With
+//! [`RateLimitLayer::with_adaptive_limit`] (`ADAPTIVE_RATE_LIMIT_PERCENT`),
+//! requests are checked against a second, reduced per-IP limit while the
+//! [`HealthSignal`] reports the backend degraded, and against the normal
+//! limit again once the circuit closes. `X-RateLimit-Limit` reports the limit
+//! in effect.
+//!
 //! # Response Headers
 //!
 //! On rate limit exceeded (429):
@@ -23,6 +53,10 @@
 //! - `X-RateLimit-Limit`: Configured RPS limit
 //! - `X-RateLimit-Remaining`: Remaining requests in current window
 //!
+//! The body is the standard JSON error shape with `"error":
+//! "too_many_requests"`, `"retryable": true` and `retry_after_ms` (the
+//! un-rounded wait, so clients need not parse the header).
+//!
 //! # IP Spoofing Mitigation
 //!
 //! Per-IP rate limiting relies on headers like `X-Forwarded-For` when behind
@@ -38,14 +72,18 @@
 //! the ignored headers are noted at debug level. See `middleware::ip` for
 //! the full resolution rules (rightmost-untrusted X-Forwarded-For).
 
+use std::collections::HashMap;
 use std::fmt;
 use std::net::IpAddr;
 use std::num::NonZeroU32;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use axum::body::Body;
-use axum::http::{Request, Response, StatusCode};
+use axum::extract::MatchedPath;
+use axum::http::{Method, Request, Response, StatusCode};
 use axum::response::IntoResponse;
 use governor::clock::{Clock, DefaultClock};
 use governor::state::keyed::DefaultKeyedStateStore;
@@ -64,6 +102,9 @@ pub enum RateLimitError {
     /// trusted-proxy list would otherwise degrade to trusting spoofable
     /// forwarded headers from everyone.
     InvalidTrustedProxyCidr(String),
+    /// An IP_ALLOWLIST or IP_DENYLIST entry could not be parsed as an IP or
+    /// CIDR range.
+    InvalidIpFilterCidr(String),
 }
 
 impl fmt::Display for RateLimitError {
@@ -82,15 +123,56 @@ impl fmt::Display for RateLimitError {
                     entry
                 )
             }
+            RateLimitError::InvalidIpFilterCidr(entry) => {
+                write!(
+                    f,
+                    "Invalid IP_ALLOWLIST/IP_DENYLIST entry '{entry}': expected an IP address or CIDR range"
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for RateLimitError {}
 
+/// What happens to a request over the rate limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Reject immediately with 429 (default)
+    #[default]
+    Reject,
+    /// Wait up to `QUEUE_MAX_WAIT_MS` for a token, then process or reject
+    Queue,
+}
+
+impl FromStr for RateLimitMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "queue" => Ok(Self::Queue),
+            _ => Err(format!(
+                "Unknown rate limit mode '{s}' (expected reject or queue)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reject => f.write_str("reject"),
+            Self::Queue => f.write_str("queue"),
+        }
+    }
+}
+
 use tracing::{debug, warn};
 
 use super::ip::extract_client_ip_with_validation;
+use crate::error::error_body;
+use crate::iggy_client::HealthSignal;
 
 /// Type alias for per-IP rate limiter.
 ///
@@ -240,6 +322,35 @@ impl TrustedProxyConfig {
     }
 }
 
+/// Endpoint class with its own (optional) rate limit quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// Safe methods (polls, listings, stats)
+    Read,
+    /// Sends and resource changes
+    Write,
+    /// Everything under `/admin`
+    Admin,
+}
+
+impl RouteClass {
+    /// Class of a request: admin by matched route (falling back to the URI
+    /// path), otherwise read or write by method.
+    pub fn of<B>(req: &Request<B>) -> Self {
+        let path = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(req.uri().path(), MatchedPath::as_str);
+        if path == "/admin" || path.starts_with("/admin/") {
+            Self::Admin
+        } else if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+}
+
 /// Rate limiting layer for Tower middleware stack.
 ///
 /// Uses per-IP rate limiting to prevent abuse from individual clients
@@ -258,10 +369,35 @@ pub struct RateLimitLayer {
     limiter: Arc<KeyedLimiter>,
     /// Configured RPS limit (for headers)
     limit: u32,
+    /// Configured burst capacity (for deriving the adaptive and class limits)
+    burst: u32,
+    /// Per-class quotas replacing the default bucket for their class
+    classes: HashMap<RouteClass, ClassLimit>,
+    /// Reduced limit applied while the backend is degraded
+    adaptive: Option<AdaptiveLimit>,
+    /// Longest a request over the limit is held for a token (queue mode)
+    queue_max_wait: Option<Duration>,
     /// Trusted proxy configuration for IP spoofing mitigation
     trusted_proxies: Arc<TrustedProxyConfig>,
 }
 
+/// Per-IP limit for one route class.
+#[derive(Clone)]
+struct ClassLimit {
+    limiter: Arc<KeyedLimiter>,
+    /// Class RPS limit (for headers)
+    limit: u32,
+}
+
+/// Reduced per-IP limit used while `health` reports the backend degraded.
+#[derive(Clone)]
+struct AdaptiveLimit {
+    health: HealthSignal,
+    limiter: Arc<KeyedLimiter>,
+    /// Reduced RPS limit (for headers)
+    limit: u32,
+}
+
 impl RateLimitLayer {
     /// Create a new per-IP rate limit layer.
     ///
@@ -303,24 +439,75 @@ impl RateLimitLayer {
         // Validate rps is non-zero
         let rps_nonzero = NonZeroU32::new(rps).ok_or(RateLimitError::ZeroRps)?;
 
-        // burst.max(1) is always >= 1, so this is infallible
-        // We use a const NonZeroU32 for the minimum burst value
-        const MIN_BURST: NonZeroU32 = NonZeroU32::new(1).unwrap();
-        let burst_nonzero = NonZeroU32::new(burst).unwrap_or(MIN_BURST);
-
-        // Create quota: burst capacity refilled at `rps` per second
-        let quota = Quota::per_second(rps_nonzero).allow_burst(burst_nonzero);
-
-        // Create keyed rate limiter with custom hasher for efficiency
-        let limiter = RateLimiter::keyed(quota);
-
         Ok(Self {
-            limiter: Arc::new(limiter),
+            limiter: Arc::new(keyed_limiter(rps_nonzero, burst)),
             limit: rps,
+            burst,
+            classes: HashMap::new(),
+            adaptive: None,
+            queue_max_wait: None,
             trusted_proxies,
         })
     }
 
+    /// Give requests of `class` their own per-IP quota of `rps` (with the
+    /// configured burst) instead of the default bucket.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RateLimitError::ZeroRps` if `rps` is 0.
+    pub fn with_class_limit(mut self, class: RouteClass, rps: u32) -> Result<Self, RateLimitError> {
+        let rps_nonzero = NonZeroU32::new(rps).ok_or(RateLimitError::ZeroRps)?;
+        self.classes.insert(
+            class,
+            ClassLimit {
+                limiter: Arc::new(keyed_limiter(rps_nonzero, self.burst)),
+                limit: rps,
+            },
+        );
+        Ok(self)
+    }
+
+    /// Enable queue mode: hold a request over the limit for up to
+    /// `max_wait` until its IP has a token, rejecting only if none becomes
+    /// available in time.
+    #[must_use]
+    pub fn with_queueing(mut self, max_wait: Duration) -> Self {
+        self.queue_max_wait = Some(max_wait);
+        self
+    }
+
+    /// Enable adaptive mode: while `health` reports the backend degraded,
+    /// limit each IP to `percent` of the configured RPS and burst (at least
+    /// 1 each).
+    #[must_use]
+    pub fn with_adaptive_limit(mut self, health: HealthSignal, percent: u32) -> Self {
+        let rps = NonZeroU32::new(scaled_limit(self.limit, percent)).unwrap_or(NonZeroU32::MIN);
+        self.adaptive = Some(AdaptiveLimit {
+            health,
+            limiter: Arc::new(keyed_limiter(rps, scaled_limit(self.burst, percent))),
+            limit: rps.get(),
+        });
+        self
+    }
+
+    /// Client IPs tracked across the default, class and adaptive limiters.
+    ///
+    /// Keys are never evicted, so this grows with the number of distinct
+    /// clients seen.
+    pub fn key_count(&self) -> usize {
+        self.limiter.len()
+            + self
+                .classes
+                .values()
+                .map(|class| class.limiter.len())
+                .sum::<usize>()
+            + self
+                .adaptive
+                .as_ref()
+                .map_or(0, |adaptive| adaptive.limiter.len())
+    }
+
     /// Create a disabled rate limiter (allows all requests).
     ///
     /// Use this when rate limiting is configured to be disabled.
@@ -329,6 +516,19 @@ impl RateLimitLayer {
     }
 }
 
+/// Keyed limiter with `burst` capacity (at least 1) refilled at `rps` per
+/// second.
+fn keyed_limiter(rps: NonZeroU32, burst: u32) -> KeyedLimiter {
+    let burst = NonZeroU32::new(burst).unwrap_or(NonZeroU32::MIN);
+    RateLimiter::keyed(Quota::per_second(rps).allow_burst(burst))
+}
+
+/// `percent` of `value`, rounded down but never below 1.
+fn scaled_limit(value: u32, percent: u32) -> u32 {
+    let scaled = u64::from(value) * u64::from(percent) / 100;
+    u32::try_from(scaled).unwrap_or(u32::MAX).max(1)
+}
+
 impl<S> Layer<S> for RateLimitLayer {
     type Service = RateLimitService<S>;
 
@@ -337,6 +537,9 @@ impl<S> Layer<S> for RateLimitLayer {
             inner,
             limiter: self.limiter.clone(),
             limit: self.limit,
+            classes: Arc::new(self.classes.clone()),
+            adaptive: self.adaptive.clone(),
+            queue_max_wait: self.queue_max_wait,
             trusted_proxies: self.trusted_proxies.clone(),
         }
     }
@@ -348,6 +551,9 @@ pub struct RateLimitService<S> {
     inner: S,
     limiter: Arc<KeyedLimiter>,
     limit: u32,
+    classes: Arc<HashMap<RouteClass, ClassLimit>>,
+    adaptive: Option<AdaptiveLimit>,
+    queue_max_wait: Option<Duration>,
     trusted_proxies: Arc<TrustedProxyConfig>,
 }
 
@@ -367,8 +573,18 @@ where
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        let limiter = self.limiter.clone();
-        let limit = self.limit;
+        // Degraded backend: check against the reduced limit instead;
+        // otherwise the route class's own quota, if it has one.
+        let (limiter, limit, degraded) = match &self.adaptive {
+            Some(adaptive) if adaptive.health.is_degraded() => {
+                (adaptive.limiter.clone(), adaptive.limit, true)
+            }
+            _ => match self.classes.get(&RouteClass::of(&req)) {
+                Some(class) => (class.limiter.clone(), class.limit, false),
+                None => (self.limiter.clone(), self.limit, false),
+            },
+        };
+        let queue_max_wait = self.queue_max_wait;
         let trusted_proxies = self.trusted_proxies.clone();
         let mut inner = self.inner.clone();
 
@@ -386,16 +602,35 @@ where
                 }
                 Err(not_until) => {
                     // Rate limit exceeded for this IP
-                    // Only extract path for logging (lazy evaluation)
-                    let path = req.uri().path();
                     let wait_time =
                         not_until.wait_time_from(governor::clock::DefaultClock::default().now());
-                    let retry_after = wait_time.as_secs().max(1);
+
+                    // Queue mode: hold the request if a token is due in time.
+                    // Other requests from the same IP may take it first, so
+                    // the wait itself is bounded too.
+                    if let Some(max_wait) = queue_max_wait
+                        && wait_time <= max_wait
+                        && tokio::time::timeout(max_wait, limiter.until_key_ready(&client_ip))
+                            .await
+                            .is_ok()
+                    {
+                        debug!(
+                            client_ip = %client_ip,
+                            wait_ms = wait_time.as_millis() as u64,
+                            "Rate-limited request queued until a token was available"
+                        );
+                        return inner.call(req).await;
+                    }
+
+                    // Only extract path for logging (lazy evaluation)
+                    let path = req.uri().path();
+                    let retry_after = wait_time.as_millis().div_ceil(1000).max(1);
 
                     warn!(
                         client_ip = %client_ip,
                         path = %path,
                         retry_after_secs = retry_after,
+                        degraded,
                         "Rate limit exceeded for IP"
                     );
 
@@ -406,8 +641,14 @@ where
                             ("Retry-After", retry_after.to_string()),
                             ("X-RateLimit-Limit", limit.to_string()),
                             ("X-RateLimit-Remaining", "0".to_string()),
+                            ("Content-Type", "application/json".to_string()),
                         ],
-                        "Rate limit exceeded. Please retry later.",
+                        error_body(
+                            "too_many_requests",
+                            "Rate limit exceeded. Please retry later.",
+                            true,
+                            Some(wait_time.as_millis() as u64),
+                        ),
                     )
                         .into_response();
 
@@ -429,6 +670,167 @@ mod tests {
         assert_eq!(layer.limit, 100);
     }
 
+    /// Minimal inner service returning 200 OK, for driving the layer.
+    #[derive(Clone)]
+    struct OkService;
+
+    impl Service<Request<Body>> for OkService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            std::future::ready(Ok(StatusCode::OK.into_response()))
+        }
+    }
+
+    fn request() -> Request<Body> {
+        Request::builder()
+            .uri("/messages")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn request_to(method: Method, path: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_key_count_covers_every_limiter() {
+        let layer = RateLimitLayer::new(100, 10)
+            .unwrap()
+            .with_class_limit(RouteClass::Read, 100)
+            .unwrap();
+        let mut svc = layer.clone().layer(OkService);
+        assert_eq!(layer.key_count(), 0);
+
+        svc.call(request_to(Method::GET, "/messages"))
+            .await
+            .unwrap();
+        svc.call(request_to(Method::POST, "/messages"))
+            .await
+            .unwrap();
+        // One client, seen by the read class and the default limiter
+        assert_eq!(layer.key_count(), 2);
+    }
+
+    #[test]
+    fn test_route_class_of_request() {
+        assert_eq!(
+            RouteClass::of(&request_to(Method::GET, "/messages")),
+            RouteClass::Read
+        );
+        assert_eq!(
+            RouteClass::of(&request_to(Method::POST, "/messages")),
+            RouteClass::Write
+        );
+        assert_eq!(
+            RouteClass::of(&request_to(Method::DELETE, "/streams/s")),
+            RouteClass::Write
+        );
+        assert_eq!(
+            RouteClass::of(&request_to(Method::GET, "/admin/users")),
+            RouteClass::Admin
+        );
+        assert_eq!(
+            RouteClass::of(&request_to(Method::GET, "/administrator")),
+            RouteClass::Read
+        );
+    }
+
+    #[tokio::test]
+    async fn test_class_limits_are_independent_buckets() {
+        let mut svc = RateLimitLayer::new(100, 1)
+            .unwrap()
+            .with_class_limit(RouteClass::Read, 1)
+            .unwrap()
+            .layer(OkService);
+
+        // The read class has its own single-token bucket...
+        let read = || request_to(Method::GET, "/messages");
+        assert_eq!(svc.call(read()).await.unwrap().status(), StatusCode::OK);
+        let limited = svc.call(read()).await.unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers()["X-RateLimit-Limit"], "1");
+
+        // ...so exhausting it leaves writes untouched.
+        let write = request_to(Method::POST, "/messages");
+        assert_eq!(svc.call(write).await.unwrap().status(), StatusCode::OK);
+
+        assert!(matches!(
+            RateLimitLayer::new(100, 1)
+                .unwrap()
+                .with_class_limit(RouteClass::Admin, 0),
+            Err(RateLimitError::ZeroRps)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_queue_mode_delays_instead_of_rejecting() {
+        // 20 RPS with a single-token bucket: the next token is ~50ms away.
+        let mut reject = RateLimitLayer::new(20, 1).unwrap().layer(OkService);
+        assert_eq!(
+            reject.call(request()).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            reject.call(request()).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        let mut queue = RateLimitLayer::new(20, 1)
+            .unwrap()
+            .with_queueing(Duration::from_millis(500))
+            .layer(OkService);
+        assert_eq!(
+            queue.call(request()).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            queue.call(request()).await.unwrap().status(),
+            StatusCode::OK
+        );
+
+        // A token further away than the bound is rejected without waiting.
+        let mut short = RateLimitLayer::new(1, 1)
+            .unwrap()
+            .with_queueing(Duration::from_millis(10))
+            .layer(OkService);
+        assert_eq!(
+            short.call(request()).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            short.call(request()).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_mode_parsing() {
+        assert_eq!("reject".parse(), Ok(RateLimitMode::Reject));
+        assert_eq!("queue".parse(), Ok(RateLimitMode::Queue));
+        assert!("drop".parse::<RateLimitMode>().is_err());
+        assert_eq!(RateLimitMode::default().to_string(), "reject");
+    }
+
+    #[test]
+    fn test_scaled_limit_never_drops_below_one() {
+        assert_eq!(scaled_limit(100, 10), 10);
+        assert_eq!(scaled_limit(50, 10), 5);
+        assert_eq!(scaled_limit(5, 10), 1);
+        assert_eq!(scaled_limit(100, 100), 100);
+        assert_eq!(scaled_limit(u32::MAX, 100), u32::MAX);
+    }
+
     #[test]
     fn test_rate_limit_zero_rps_returns_error() {
         let result = RateLimitLayer::new(0, 50);
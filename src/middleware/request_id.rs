@@ -5,7 +5,12 @@
 //! - Generates UUIDv4 request IDs for incoming requests without one
 //! - Propagates existing `X-Request-Id` headers
 //! - Adds `X-Request-Id` to all responses
-//! - Integrates with tracing spans for observability
+//! - Echoes a client's `X-Correlation-Id` on the response
+//! - Runs the request in a `request` tracing span carrying `request_id`, so
+//!   every log line emitted while handling it (including those of
+//!   `IggyClientWrapper`) can be correlated
+//! - Exposes the ID to error responses via [`current_request_id`], so every
+//!   error JSON body includes `request_id`
 //!
 //! # Usage
 //!
@@ -13,7 +18,7 @@
 //! 1. Checks for an existing `X-Request-Id` header
 //! 2. Generates a new UUID if none exists
 //! 3. Adds the ID to the response headers
-//! 4. Includes the ID in tracing spans
+//! 4. Includes the ID in tracing spans and error bodies
 //!
 //! # Client Usage
 //!
@@ -24,6 +29,13 @@
 //! ```
 //!
 //! The same ID will be returned in the response for correlation.
+//!
+//! # Correlation IDs
+//!
+//! Events sent by a request get a `correlation_id` (see [`CorrelationId`]):
+//! the client's `X-Correlation-Id` when given, so a message can be traced
+//! back across services to the request that started the flow, or else the
+//! request ID itself.
 
 use std::task::{Context, Poll};
 
@@ -31,16 +43,34 @@ use axum::body::Body;
 use axum::http::header::HeaderValue;
 use axum::http::{Request, Response};
 use tower::{Layer, Service};
-use tracing::{Span, debug};
+use tracing::{Instrument, debug, info_span};
 use uuid::Uuid;
 
+use crate::error::AppError;
+
 /// Header name for request ID.
 pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
+/// Header name for a client-supplied correlation ID.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
 /// Fallback header value when request ID parsing fails.
 /// Using `from_static` avoids runtime parsing and is infallible.
 static UNKNOWN_REQUEST_ID: HeaderValue = HeaderValue::from_static("unknown");
 
+tokio::task_local! {
+    /// Request ID of the request handled by the current task.
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Request ID of the request being handled by the current task.
+///
+/// `None` outside the request ID layer and in tasks spawned by a handler,
+/// such as a shared reconnect session.
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
 /// Request ID layer for Tower middleware stack.
 #[derive(Clone, Default)]
 pub struct RequestIdLayer;
@@ -84,6 +114,7 @@ where
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         // Extract or generate request ID
         let request_id = extract_or_generate_request_id(&req);
+        let correlation_id = req.headers().get(CORRELATION_ID_HEADER).cloned();
 
         // Add request ID to request headers (so handlers can access it)
         req.headers_mut().insert(
@@ -93,14 +124,17 @@ where
                 .unwrap_or_else(|_| UNKNOWN_REQUEST_ID.clone()),
         );
 
-        // Record in current span
-        Span::current().record("request_id", &request_id);
-        debug!(request_id = %request_id, "Processing request");
+        // Every log line of the request carries its ID via this span
+        let span = info_span!("request", request_id = %request_id);
+        debug!(parent: &span, "Processing request");
 
         let mut inner = self.inner.clone();
+        let scoped_id = request_id.clone();
 
+        let handled = async move { inner.call(req).await }.instrument(span);
+        let handled = CURRENT_REQUEST_ID.scope(scoped_id, handled);
         Box::pin(async move {
-            let mut response = inner.call(req).await?;
+            let mut response = handled.await?;
 
             // Add request ID to response headers
             response.headers_mut().insert(
@@ -109,6 +143,11 @@ where
                     .parse()
                     .unwrap_or_else(|_| UNKNOWN_REQUEST_ID.clone()),
             );
+            if let Some(correlation_id) = correlation_id {
+                response
+                    .headers_mut()
+                    .insert(CORRELATION_ID_HEADER, correlation_id);
+            }
 
             Ok(response)
         })
@@ -129,6 +168,91 @@ fn extract_or_generate_request_id<B>(req: &Request<B>) -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Request ID of the current request, for handlers.
+///
+/// Handlers declare `request_id: Option<RequestId>`; it is `None` only on
+/// routes outside the request ID layer.
+#[derive(Debug, Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// The request ID as received or generated.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The request ID as a UUID, when it is one (generated IDs always are).
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.0).ok()
+    }
+}
+
+impl<S> axum::extract::OptionalFromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| Self(s.to_string())))
+    }
+}
+
+/// Correlation ID for the events a request sends.
+///
+/// The `X-Correlation-Id` header when present, which must then be a UUID
+/// (events carry `correlation_id` as one); otherwise the request ID when
+/// it is a UUID, as generated IDs always are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CorrelationId(Option<Uuid>);
+
+impl CorrelationId {
+    /// The correlation ID, if the request has one.
+    pub fn get(self) -> Option<Uuid> {
+        self.0
+    }
+
+    fn from_headers(headers: &axum::http::HeaderMap) -> Result<Self, AppError> {
+        if let Some(value) = headers.get(CORRELATION_ID_HEADER) {
+            return value
+                .to_str()
+                .ok()
+                .and_then(|value| Uuid::parse_str(value.trim()).ok())
+                .map(|id| Self(Some(id)))
+                .ok_or_else(|| {
+                    AppError::BadRequest("X-Correlation-Id must be a UUID".to_string())
+                });
+        }
+        Ok(Self(
+            headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| Uuid::parse_str(v).ok()),
+        ))
+    }
+}
+
+impl<S> axum::extract::FromRequestParts<S> for CorrelationId
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_headers(&parts.headers)
+    }
+}
+
 /// Extension trait to extract request ID from requests.
 pub trait RequestIdExt {
     /// Get the request ID from the request headers.
@@ -145,7 +269,7 @@ impl<B> RequestIdExt for Request<B> {
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 mod tests {
     use super::*;
 
@@ -185,4 +309,79 @@ mod tests {
 
         assert_eq!(req.request_id(), None);
     }
+
+    #[test]
+    fn test_correlation_id_prefers_header_over_request_id() {
+        let request_id = Uuid::new_v4();
+        let correlation_id = Uuid::new_v4();
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, request_id.to_string().parse().unwrap());
+        assert_eq!(
+            CorrelationId::from_headers(&headers).unwrap().get(),
+            Some(request_id)
+        );
+
+        headers.insert(
+            CORRELATION_ID_HEADER,
+            correlation_id.to_string().parse().unwrap(),
+        );
+        assert_eq!(
+            CorrelationId::from_headers(&headers).unwrap().get(),
+            Some(correlation_id)
+        );
+
+        headers.insert(CORRELATION_ID_HEADER, "trace-42".parse().unwrap());
+        assert!(matches!(
+            CorrelationId::from_headers(&headers),
+            Err(AppError::BadRequest(_))
+        ));
+
+        headers.clear();
+        headers.insert(REQUEST_ID_HEADER, "my-request".parse().unwrap());
+        assert_eq!(CorrelationId::from_headers(&headers).unwrap().get(), None);
+    }
+
+    /// Inner service failing every request, as a handler would.
+    #[derive(Clone)]
+    struct NotFoundService;
+
+    impl Service<Request<Body>> for NotFoundService {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            let error = AppError::NotFound("Stream 'orders' not found".to_string());
+            std::future::ready(Ok(axum::response::IntoResponse::into_response(error)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_bodies_carry_the_request_id() {
+        let mut service = RequestIdLayer::new().layer(NotFoundService);
+        let req = Request::builder()
+            .header(REQUEST_ID_HEADER, "scoped-id")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = service.call(req).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "scoped-id");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "scoped-id");
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[test]
+    fn test_request_id_as_uuid() {
+        let id = Uuid::new_v4();
+        assert_eq!(RequestId(id.to_string()).as_uuid(), Some(id));
+        assert_eq!(RequestId("my-correlation-id".to_string()).as_uuid(), None);
+    }
 }
@@ -0,0 +1,86 @@
+//! Slow request logging with a tail-latency breakdown.
+//!
+//! With `SLOW_REQUEST_THRESHOLD_MS` set, every request taking at least that
+//! long is logged at WARN with its route, status and where the time went:
+//!
+//! - `auth_ms` - API key authentication
+//! - `handler_ms` - everything after authentication until the response
+//!   headers (the handler and the middleware inside auth)
+//! - `iggy_ms` / `iggy_ops` - the part of `handler_ms` spent in Iggy
+//!   operations, summed over the request's operations (see
+//!   [`measure_iggy_time`])
+//!
+//! ```text
+//! WARN Slow request method=POST route=/messages status=201 duration_ms=812
+//!      auth_ms=0 handler_ms=812 iggy_ms=797 iggy_ops=1
+//! ```
+//!
+//! Rate limiting (including queue-mode waits) runs before this layer and is
+//! not included. Streamed responses count until their headers are sent.
+
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::warn;
+
+use crate::iggy_client::measure_iggy_time;
+
+/// When the request got past authentication, set by [`mark_authenticated`].
+#[derive(Debug, Clone, Default)]
+struct AuthenticatedAt(Arc<OnceLock<Instant>>);
+
+/// Middleware that logs requests slower than the threshold.
+///
+/// Apply with `axum::middleware::from_fn_with_state(threshold, log_slow_requests)`
+/// just outside the auth layer, and [`mark_authenticated`] just inside it.
+pub async fn log_slow_requests(
+    State(threshold): State<Duration>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let authenticated = AuthenticatedAt::default();
+    request.extensions_mut().insert(authenticated.clone());
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || request.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+
+    let (response, iggy) = measure_iggy_time(next.run(request)).await;
+
+    let elapsed = started.elapsed();
+    if elapsed >= threshold {
+        // Never marked: rejected by auth, which took the whole time
+        let auth = authenticated
+            .0
+            .get()
+            .map_or(elapsed, |at| at.duration_since(started));
+        warn!(
+            method = %method,
+            route = %route,
+            status = response.status().as_u16(),
+            duration_ms = elapsed.as_millis() as u64,
+            auth_ms = auth.as_millis() as u64,
+            handler_ms = elapsed.saturating_sub(auth).as_millis() as u64,
+            iggy_ms = iggy.total.as_millis() as u64,
+            iggy_ops = iggy.operations,
+            "Slow request"
+        );
+    }
+    response
+}
+
+/// Middleware that marks the end of authentication for [`log_slow_requests`].
+///
+/// Apply with `axum::middleware::from_fn(mark_authenticated)` just inside
+/// the auth layer.
+pub async fn mark_authenticated(request: Request, next: Next) -> Response {
+    if let Some(authenticated) = request.extensions().get::<AuthenticatedAt>() {
+        let _ = authenticated.0.set(Instant::now());
+    }
+    next.run(request).await
+}
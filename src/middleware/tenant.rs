@@ -0,0 +1,223 @@
+//! Tenant of a request, for fair queuing of Iggy operations.
+//!
+//! With `FAIR_QUEUE_MAX_IN_FLIGHT` set, each request runs under a tenant
+//! (see [`with_tenant`]) whose Iggy operations are queued together: the
+//! `X-Tenant-Id` header set by a trusted proxy, or else the request's
+//! credential and client IP, e.g. `api_key:203.0.113.7`.
+//!
+//! The service has a single API key (and signing secret), so credentials
+//! alone cannot tell tenants apart. The header can, but a client choosing
+//! its own could claim a weighted tenant's share, or rotate names to get
+//! around `FAIR_QUEUE_TENANT_MAX_IN_FLIGHT`. It is therefore only honored
+//! on requests whose peer is in `TRUSTED_PROXIES`, where the proxy in front
+//! of the clients is expected to set it; from anyone else it is ignored. A
+//! header that is not a valid tenant name is ignored too.
+//!
+//! For the same reason the client IP is resolved like IP filtering does:
+//! through `X-Forwarded-For` only from trusted proxies, and from the peer
+//! address when none are configured, never from a header the client sets.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use super::admin::ADMIN_KEY_HEADER;
+use super::auth::constant_time_eq;
+use super::ip::extract_client_ip_with_validation;
+use super::rate_limit::TrustedProxyConfig;
+use super::signing::SignedRequest;
+use crate::config::Config;
+use crate::iggy_client::with_tenant;
+
+/// Header naming the tenant of a request.
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Longest accepted tenant name.
+const MAX_TENANT_LEN: usize = 64;
+
+/// What the tenant middleware needs to resolve a request's tenant.
+#[derive(Clone)]
+pub struct TenantScope {
+    trusted_proxies: Arc<TrustedProxyConfig>,
+    admin_api_key: Option<Arc<String>>,
+    api_key_enabled: bool,
+}
+
+impl TenantScope {
+    /// Create a tenant scope from the configuration and the parsed
+    /// `TRUSTED_PROXIES`.
+    pub fn new(config: &Config, trusted_proxies: Arc<TrustedProxyConfig>) -> Self {
+        Self {
+            trusted_proxies,
+            admin_api_key: config.admin_api_key.clone().map(Arc::new),
+            api_key_enabled: config.api_key.is_some(),
+        }
+    }
+
+    /// Strongest credential of `request`, named like the audit log does.
+    ///
+    /// Runs inside authentication, so a request that got here with the API
+    /// key enabled and no signature sent the key.
+    fn credential<B>(&self, request: &axum::http::Request<B>) -> &'static str {
+        let admin = request
+            .headers()
+            .get(ADMIN_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .zip(self.admin_api_key.as_deref())
+            .is_some_and(|(provided, expected)| constant_time_eq(provided, expected));
+        if admin {
+            "admin"
+        } else if request.extensions().get::<SignedRequest>().is_some() {
+            "signed"
+        } else if self.api_key_enabled {
+            "api_key"
+        } else {
+            "anonymous"
+        }
+    }
+
+    /// Client IP of `request`: resolved through the trusted proxies, or the
+    /// peer address if none are configured.
+    fn client_ip<B>(&self, request: &axum::http::Request<B>) -> String {
+        if self.trusted_proxies.is_enabled() {
+            extract_client_ip_with_validation(request, &self.trusted_proxies).into_owned()
+        } else {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map_or_else(
+                    || "unknown".to_string(),
+                    |ConnectInfo(peer)| peer.ip().to_string(),
+                )
+        }
+    }
+
+    /// Tenant of `request`: its `X-Tenant-Id` when a trusted proxy sent it,
+    /// or else its credential and client IP.
+    fn tenant_of<B>(&self, request: &axum::http::Request<B>) -> String {
+        let from_trusted_proxy = self.trusted_proxies.is_enabled()
+            && request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .is_some_and(|ConnectInfo(peer)| self.trusted_proxies.is_trusted_ip(&peer.ip()));
+        request
+            .headers()
+            .get(TENANT_HEADER)
+            .filter(|_| from_trusted_proxy)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|tenant| is_valid_tenant(tenant))
+            .map_or_else(
+                || format!("{}:{}", self.credential(request), self.client_ip(request)),
+                str::to_string,
+            )
+    }
+}
+
+/// Middleware that runs the request under its tenant.
+///
+/// Apply with `axum::middleware::from_fn_with_state(tenant_scope, scope_tenant)`.
+pub async fn scope_tenant(
+    State(scope): State<Arc<TenantScope>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let tenant = scope.tenant_of(&request);
+    with_tenant(tenant, next.run(request)).await
+}
+
+/// Whether `tenant` is 1 to [`MAX_TENANT_LEN`] ASCII letters, digits, `-`,
+/// `_` or `.`.
+fn is_valid_tenant(tenant: &str) -> bool {
+    !tenant.is_empty()
+        && tenant.len() <= MAX_TENANT_LEN
+        && tenant
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    /// A request from `peer`, forwarded for 203.0.113.7.
+    fn request(peer: &str, tenant: Option<&str>) -> axum::http::Request<Body> {
+        let mut builder = axum::http::Request::builder().header("x-forwarded-for", "203.0.113.7");
+        if let Some(tenant) = tenant {
+            builder = builder.header(TENANT_HEADER, tenant);
+        }
+        let mut request = builder.body(Body::empty()).unwrap();
+        let peer: SocketAddr = format!("{peer}:40000").parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+        request
+    }
+
+    fn scope(trusted_proxies: &[&str]) -> TenantScope {
+        let trusted_proxies: Vec<String> =
+            trusted_proxies.iter().map(ToString::to_string).collect();
+        let config = Config {
+            api_key: Some("api-key".to_string()),
+            admin_api_key: Some("admin-key".to_string()),
+            ..Config::default()
+        };
+        TenantScope::new(
+            &config,
+            Arc::new(TrustedProxyConfig::try_new(&trusted_proxies).unwrap()),
+        )
+    }
+
+    #[test]
+    fn test_tenant_header_from_trusted_proxy_takes_precedence() {
+        let scope = scope(&["10.0.0.0/8"]);
+        let tenant = scope.tenant_of(&request("10.0.0.5", Some("acme")));
+        assert_eq!(tenant, "acme");
+        let tenant = scope.tenant_of(&request("10.0.0.5", None));
+        assert_eq!(tenant, "api_key:203.0.113.7");
+    }
+
+    #[test]
+    fn test_tenant_header_from_anyone_else_is_ignored() {
+        // A direct client keys on its own address
+        let tenant = scope(&["10.0.0.0/8"]).tenant_of(&request("198.51.100.9", Some("acme")));
+        assert_eq!(tenant, "api_key:198.51.100.9");
+        // Without TRUSTED_PROXIES, no peer is trusted with the header, nor
+        // with X-Forwarded-For
+        let tenant = scope(&[]).tenant_of(&request("10.0.0.5", Some("acme")));
+        assert_eq!(tenant, "api_key:10.0.0.5");
+    }
+
+    #[test]
+    fn test_invalid_tenant_header_falls_back_to_client_ip() {
+        let scope = scope(&["10.0.0.0/8"]);
+        let too_long = "a".repeat(MAX_TENANT_LEN + 1);
+        for tenant in ["", "a b", "acme/other", too_long.as_str()] {
+            let tenant = scope.tenant_of(&request("10.0.0.5", Some(tenant)));
+            assert_eq!(tenant, "api_key:203.0.113.7");
+        }
+    }
+
+    #[test]
+    fn test_tenant_is_keyed_by_credential() {
+        let scope = scope(&[]);
+        let mut admin = request("10.0.0.5", None);
+        admin
+            .headers_mut()
+            .insert(ADMIN_KEY_HEADER, "admin-key".parse().unwrap());
+        assert_eq!(scope.tenant_of(&admin), "admin:10.0.0.5");
+
+        let mut wrong_key = request("10.0.0.5", None);
+        wrong_key
+            .headers_mut()
+            .insert(ADMIN_KEY_HEADER, "guess".parse().unwrap());
+        assert_eq!(scope.tenant_of(&wrong_key), "api_key:10.0.0.5");
+
+        let mut signed = request("10.0.0.5", None);
+        signed.extensions_mut().insert(SignedRequest);
+        assert_eq!(scope.tenant_of(&signed), "signed:10.0.0.5");
+    }
+}
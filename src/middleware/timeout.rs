@@ -29,8 +29,9 @@
 //!
 //! All Iggy-touching handlers pass the extracted timeout through
 //! `AppState::{producer_scoped, consumer_scoped, iggy_scoped}` into
-//! `IggyClientWrapper::with_timeout`, which bounds every operation attempt
-//! (and the caller's wait on any reconnect) by the request deadline.
+//! `IggyClientWrapper::with_timeout`, which makes the request deadline the
+//! budget of every operation: its attempt, the wait on any reconnect and
+//! the retry all have to fit in it.
 //! Requests without the header use the global `OPERATION_TIMEOUT_SECS`.
 //!
 //! # Benefits
@@ -1,18 +1,21 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::Event;
+use super::{Event, EventPayload};
+use crate::bootstrap::RetentionPolicy;
 
 /// Request to create a new stream.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateStreamRequest {
     /// Stream name (must be unique)
     pub name: String,
 }
 
 /// Request to create a new topic within a stream.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTopicRequest {
     /// Topic name (must be unique within the stream)
     pub name: String,
@@ -25,18 +28,426 @@ fn default_partitions() -> u32 {
     1
 }
 
+/// Request to rename a stream or topic (`PATCH`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenameRequest {
+    /// New name (must be unused within its scope)
+    pub name: String,
+}
+
+/// What happened to one topic of a bulk creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkTopicStatus {
+    /// The topic was created
+    Created,
+    /// A topic of that name existed; its settings were left as they are
+    AlreadyExists,
+    /// The topic was invalid or could not be created
+    Error,
+}
+
+/// Outcome for one topic of a bulk creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkTopicResult {
+    /// Topic name
+    pub name: String,
+    /// What happened to the topic
+    pub status: BulkTopicStatus,
+    /// Why it failed (present only for `error`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of `POST /streams/{stream}/topics/bulk`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkCreateTopicsResponse {
+    /// Stream name
+    pub stream: String,
+    /// One entry per requested topic, in request order
+    pub results: Vec<BulkTopicResult>,
+}
+
+/// How a send picks its target partition.
+///
+/// Serialized as a string:
+///
+/// - `"balanced"` - server-side round robin
+/// - `"key"` - hash of `partition_key` (required with this strategy) using
+///   the configured `PARTITION_KEY_HASHING`; `"key:server"` or
+///   `"key:murmur2"` picks the [`KeyHashing`] per request
+/// - `"partition_id:<n>"` - explicit partition, validated against the
+///   topic's partition count
+/// - `"sticky"` - stay on one partition for `STICKY_PARTITION_SECS`, then
+///   move to the next one
+///
+/// When omitted, a send uses `key` if a `partition_key` is present and
+/// `balanced` otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PartitioningStrategy {
+    /// Server-side round robin
+    Balanced,
+    /// Hash of the partition key (`None` = configured default hashing)
+    Key(Option<KeyHashing>),
+    /// Explicit partition ID
+    PartitionId(u32),
+    /// One partition per time window, per producer
+    Sticky,
+}
+
+impl std::str::FromStr for PartitioningStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "balanced" => Ok(Self::Balanced),
+            "key" => Ok(Self::Key(None)),
+            "sticky" => Ok(Self::Sticky),
+            _ => {
+                if let Some(id) = s.strip_prefix("partition_id:") {
+                    id.parse()
+                        .map(Self::PartitionId)
+                        .map_err(|_| format!("Invalid partition ID in '{s}'"))
+                } else if let Some(hashing) = s.strip_prefix("key:") {
+                    hashing.parse().map(|h| Self::Key(Some(h)))
+                } else {
+                    Err(format!(
+                        "Unknown partitioning '{s}' \
+                         (expected balanced, key, partition_id:<n>, or sticky)"
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for PartitioningStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Balanced => f.write_str("balanced"),
+            Self::Key(None) => f.write_str("key"),
+            Self::Key(Some(hashing)) => write!(f, "key:{hashing}"),
+            Self::PartitionId(id) => write!(f, "partition_id:{id}"),
+            Self::Sticky => f.write_str("sticky"),
+        }
+    }
+}
+
+impl TryFrom<String> for PartitioningStrategy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PartitioningStrategy> for String {
+    fn from(value: PartitioningStrategy) -> Self {
+        value.to_string()
+    }
+}
+
+/// How a partition key is mapped to a partition.
+///
+/// - `server` - Iggy's server-side key hashing (default)
+/// - `murmur2` - computed in the gateway exactly like Kafka's default
+///   partitioner (`murmur2(key) & 0x7fffffff % partitions`), so a key lands
+///   on the same partition number it had in a Kafka topic with the same
+///   partition count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyHashing {
+    /// Iggy server-side key hashing
+    #[default]
+    Server,
+    /// Kafka-compatible murmur2, computed client-side
+    Murmur2,
+}
+
+impl std::str::FromStr for KeyHashing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "server" => Ok(Self::Server),
+            "murmur2" => Ok(Self::Murmur2),
+            _ => Err(format!(
+                "Unknown key hashing '{s}' (expected server or murmur2)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyHashing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Server => f.write_str("server"),
+            Self::Murmur2 => f.write_str("murmur2"),
+        }
+    }
+}
+
+/// What `POST /messages/batch?atomic=true` does with a batch that Iggy
+/// confirmed only part of.
+///
+/// - `tombstone` - send a `batch.compensated` event for every confirmed
+///   event, so consumers can undo it (default)
+/// - `dlq` - write the unconfirmed events to the DLQ topic (`DLQ_TOPIC`),
+///   keeping the confirmed ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchCompensation {
+    /// Tombstones for the confirmed events
+    #[default]
+    Tombstone,
+    /// The unconfirmed events to the DLQ topic
+    Dlq,
+}
+
+impl std::str::FromStr for BatchCompensation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tombstone" => Ok(Self::Tombstone),
+            "dlq" => Ok(Self::Dlq),
+            _ => Err(format!(
+                "Unknown batch compensation '{s}' (expected tombstone or dlq)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for BatchCompensation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tombstone => f.write_str("tombstone"),
+            Self::Dlq => f.write_str("dlq"),
+        }
+    }
+}
+
 /// Request to send a message to a topic.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SendMessageRequest {
     /// The event to publish
     pub event: Event,
     /// Optional partition key for consistent routing
     #[serde(default)]
     pub partition_key: Option<String>,
+    /// Optional partitioning strategy (see [`PartitioningStrategy`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PartitioningStrategy>,
+    /// Deliver at this time instead of now (exclusive with `delay_ms`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deliver_at: Option<DateTime<Utc>>,
+    /// Deliver this many milliseconds from now (exclusive with `deliver_at`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+}
+
+/// Request body for sending a batch of messages.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendBatchRequest {
+    /// List of events to send
+    pub events: Vec<Event>,
+    /// Optional partition key for all messages in the batch
+    #[serde(default)]
+    pub partition_key: Option<String>,
+    /// Optional partitioning strategy for the whole batch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PartitioningStrategy>,
+}
+
+/// Request to send one event to several topics (`POST /messages/fanout`).
+///
+/// Exactly one of `destinations` and `group` is given.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FanoutRequest {
+    /// The event to publish to every destination
+    pub event: Event,
+    /// Topics to send the event to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub destinations: Vec<FanoutDestination>,
+    /// Name of a `FANOUT_GROUPS` group to send the event to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Optional partition key, used in every destination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+}
+
+/// One topic of a fan-out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanoutDestination {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Rhai predicate routing the event here only when it holds (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+/// Query parameters for sending a batch.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SendBatchQuery {
+    /// Send in chunks, each confirmed by Iggy, and compensate on partial
+    /// failure instead of spooling (see [`AtomicBatchReport`])
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+/// Query parameters for polling messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollQuery {
+    /// Partition ID to poll from (default: 0, Iggy uses 0-indexed partitions)
+    #[serde(default)]
+    pub partition_id: u32,
+    /// Consumer ID for offset tracking (default: 1)
+    #[serde(default = "default_consumer")]
+    pub consumer_id: u32,
+    /// Starting offset (optional, defaults to next uncommitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Number of messages to poll (default: 10, capped by POLL_MAX_COUNT)
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Whether to auto-commit offset after polling
+    #[serde(default)]
+    pub auto_commit: bool,
+    /// Upcast older events to this payload schema version (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_version: Option<u32>,
+    /// Resume where the poll that returned this token left off; replaces
+    /// `partition_id`, `consumer_id` and `offset` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<String>,
+    /// Rhai predicate the returned events must match (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// `desc` returns the newest messages first, without consuming them
+    #[serde(default, skip_serializing_if = "MessageOrder::is_asc")]
+    pub order: MessageOrder,
+}
+
+impl Default for PollQuery {
+    fn default() -> Self {
+        Self {
+            partition_id: 0,
+            consumer_id: default_consumer(),
+            offset: None,
+            count: default_count(),
+            auto_commit: false,
+            target_version: None,
+            continuation: None,
+            filter: None,
+            order: MessageOrder::Asc,
+        }
+    }
+}
+
+/// Query parameters for peeking at messages without consuming them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeekQuery {
+    /// Partition ID to read from (default: 0)
+    #[serde(default)]
+    pub partition_id: u32,
+    /// Offset of the first message to return (default: 0, or the last
+    /// message with `order=desc`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Number of messages to return (default: 10, capped by POLL_MAX_COUNT)
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// `desc` returns messages newest first, from `offset` backwards
+    #[serde(default, skip_serializing_if = "MessageOrder::is_asc")]
+    pub order: MessageOrder,
+}
+
+impl Default for PeekQuery {
+    fn default() -> Self {
+        Self {
+            partition_id: 0,
+            offset: None,
+            count: default_count(),
+            order: MessageOrder::Asc,
+        }
+    }
+}
+
+/// Order of the messages returned by a poll or peek.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageOrder {
+    /// Oldest first, from the requested offset (the default)
+    #[default]
+    Asc,
+    /// Newest first, from the requested offset (default: the partition's
+    /// last message) backwards
+    Desc,
+}
+
+impl MessageOrder {
+    fn is_asc(&self) -> bool {
+        *self == Self::Asc
+    }
+}
+
+/// Query parameters for searching one partition's messages
+/// (`GET /streams/{stream}/topics/{topic}/search`).
+///
+/// Exactly one of `q` and `path` (with `equals`) is given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Partition ID to search (default: 0)
+    #[serde(default)]
+    pub partition_id: u32,
+    /// Substring of the event's JSON the matches contain (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// JSONPath into the event, like `$.payload.data.items[0].sku` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Value at `path` of the matches: JSON, or else a plain string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+    /// Offset the scan starts at (default: 0)
+    #[serde(default)]
+    pub from_offset: u64,
+    /// Most messages to scan (default and cap: SEARCH_MAX_SCAN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_scan: Option<u32>,
+    /// Most matches to return (default: 10, capped by POLL_MAX_COUNT)
+    #[serde(default = "default_count")]
+    pub limit: u32,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            partition_id: 0,
+            q: None,
+            path: None,
+            equals: None,
+            from_offset: 0,
+            max_scan: None,
+            limit: default_count(),
+        }
+    }
+}
+
+fn default_consumer() -> u32 {
+    1
+}
+
+fn default_count() -> u32 {
+    10
 }
 
 /// Response after successfully sending a message.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SendMessageResponse {
     /// Whether the message was sent successfully
     pub success: bool,
@@ -48,38 +459,1135 @@ pub struct SendMessageResponse {
     pub topic: String,
     /// Timestamp of acknowledgment
     pub timestamp: DateTime<Utc>,
+    /// Correlation ID the event was sent with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    /// Whether the event was held for later delivery instead of sent, in
+    /// the local spool or the outbox (`202 Accepted`; see `SPOOL_DIR` and
+    /// `OUTBOX_CAPACITY`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub spooled: bool,
+}
+
+/// Rolling event counts by type (`GET /analytics/event-counts`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventCountsResponse {
+    /// Stream counted
+    pub stream: String,
+    /// Topic counted (`EVENT_COUNTS_TOPIC`)
+    pub topic: String,
+    /// End of every window
+    pub as_of: DateTime<Utc>,
+    /// Counts per window, shortest first
+    pub windows: Vec<EventCountWindow>,
+}
+
+/// Event counts of one rolling window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventCountWindow {
+    /// Window name (`1m`, `5m` or `1h`)
+    pub window: String,
+    /// Window length in seconds
+    pub window_secs: u64,
+    /// Events in the window, of any type
+    pub total: u64,
+    /// Events in the window by `event_type`
+    pub counts: BTreeMap<String, u64>,
+}
+
+/// Outcome of `POST /messages/fanout`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanoutResponse {
+    /// Whether every destination not skipped confirmed the event
+    pub success: bool,
+    /// The event ID, the same in every destination
+    pub event_id: Uuid,
+    /// Correlation ID the event was sent with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    /// One result per destination, in request order
+    pub results: Vec<FanoutResult>,
+}
+
+/// Outcome of a fan-out in one destination.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanoutResult {
+    /// Stream sent to
+    pub stream: String,
+    /// Topic sent to
+    pub topic: String,
+    /// Whether the send was confirmed
+    pub success: bool,
+    /// Whether the destination's `when` did not hold, so nothing was sent
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+    /// Timestamp of acknowledgment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Why the send failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of `POST /messages/batch?atomic=true`.
+///
+/// `success` is true only when Iggy confirmed every chunk. Otherwise the
+/// chunks after the first failure were not sent, and `compensation` says
+/// how the batch was compensated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtomicBatchReport {
+    /// ID carried by the batch's compensation events
+    pub batch_id: Uuid,
+    /// Whether every event was confirmed
+    pub success: bool,
+    /// Stream the batch was sent to
+    pub stream: String,
+    /// Topic the batch was sent to
+    pub topic: String,
+    /// Events confirmed by Iggy
+    pub confirmed: usize,
+    /// Events that failed or were not sent
+    pub failed: usize,
+    /// Every chunk, in batch order
+    pub chunks: Vec<AtomicChunkReport>,
+    /// How the partial batch was compensated (absent on success)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compensation: Option<CompensationReport>,
+}
+
+/// One chunk of an atomic batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtomicChunkReport {
+    /// Index in the batch of the chunk's first event
+    pub first_event: usize,
+    /// Events in the chunk
+    pub events: usize,
+    /// Partition the chunk was sent to (absent when Iggy hashes the
+    /// partition key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_id: Option<u32>,
+    /// What became of the chunk
+    pub status: AtomicChunkStatus,
+    /// Why the send failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What became of one chunk of an atomic batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AtomicChunkStatus {
+    /// Confirmed by Iggy
+    Confirmed,
+    /// Refused or not confirmed by Iggy
+    Failed,
+    /// Not sent, because an earlier chunk failed
+    NotSent,
+}
+
+/// Compensation of a partially confirmed atomic batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompensationReport {
+    /// Compensation applied (`ATOMIC_BATCH_COMPENSATION`)
+    pub strategy: BatchCompensation,
+    /// Topic the compensation events were written to
+    pub topic: String,
+    /// Compensation events written
+    pub events: usize,
+    /// Whether Iggy confirmed them
+    pub success: bool,
+    /// Why writing them failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A send held for delayed delivery (`GET /scheduled`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledMessage {
+    /// Schedule ID (used to cancel)
+    pub id: Uuid,
+    /// Target stream
+    pub stream: String,
+    /// Target topic
+    pub topic: String,
+    /// When the message is produced
+    pub deliver_at: DateTime<Utc>,
+    /// The event to publish
+    pub event: Event,
+    /// Partition key of the eventual send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    /// Partitioning strategy of the eventual send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PartitioningStrategy>,
+}
+
+/// Request to register a recurring schedule (`POST /schedules`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateScheduleRequest {
+    /// Schedule name (unique; registering an existing name replaces it)
+    pub name: String,
+    /// Cron expression: `min hour day month weekday`, or with a leading
+    /// seconds field (and optional trailing year), evaluated in UTC
+    pub cron: String,
+    /// Target stream (default: `IGGY_STREAM`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<String>,
+    /// Target topic (default: `IGGY_TOPIC`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Event type of every produced event
+    pub event_type: String,
+    /// Payload of every produced event
+    pub payload: EventPayload,
+    /// Partition key of every produced event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    /// Random delay of up to this many milliseconds added to each run
+    #[serde(default)]
+    pub jitter_ms: u64,
+    /// Whether the schedule runs (default: true)
+    #[serde(default = "default_schedule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_schedule_enabled() -> bool {
+    true
+}
+
+/// Request to enable or disable a schedule
+/// (`PUT /schedules/{name}/enabled`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct UpdateScheduleRequest {
+    /// Whether the schedule runs
+    pub enabled: bool,
+}
+
+/// Outcome of a schedule's most recent run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    /// When the run happened
+    pub at: DateTime<Utc>,
+    /// Whether the event was produced
+    pub success: bool,
+    /// ID of the produced event
+    pub event_id: Uuid,
+    /// Send error of a failed run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A recurring schedule and its run status (`GET /schedules`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleInfo {
+    /// Schedule name
+    pub name: String,
+    /// Cron expression as registered
+    pub cron: String,
+    /// Target stream
+    pub stream: String,
+    /// Target topic
+    pub topic: String,
+    /// Event type of produced events
+    pub event_type: String,
+    /// Payload of produced events
+    pub payload: EventPayload,
+    /// Partition key of produced events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    /// Maximum random delay added to each run, in milliseconds
+    pub jitter_ms: u64,
+    /// Whether the schedule runs
+    pub enabled: bool,
+    /// When the schedule was registered
+    pub created_at: DateTime<Utc>,
+    /// Next run, jitter included (`None` while disabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// Most recent run (`None` until the first one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduleRun>,
+    /// Runs since registration
+    pub run_count: u64,
+    /// Runs whose send failed
+    pub failure_count: u64,
+}
+
+/// The definition of a schedule, to register it again (`POST /admin/restore`).
+impl From<ScheduleInfo> for CreateScheduleRequest {
+    fn from(info: ScheduleInfo) -> Self {
+        Self {
+            name: info.name,
+            cron: info.cron,
+            stream: Some(info.stream),
+            topic: Some(info.topic),
+            event_type: info.event_type,
+            payload: info.payload,
+            partition_key: info.partition_key,
+            jitter_ms: info.jitter_ms,
+            enabled: info.enabled,
+        }
+    }
+}
+
+/// Request to define a pipeline (`POST /pipelines`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatePipelineRequest {
+    /// Pipeline name (unique; defining an existing name replaces it)
+    pub name: String,
+    /// Stream read from (default: `IGGY_STREAM`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_stream: Option<String>,
+    /// Topic read from
+    pub source_topic: String,
+    /// Filter events must match to be produced, e.g.
+    /// `event_type == order.created && payload.total >= 100`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Rhai predicate events must also match, e.g.
+    /// `payload.items.len() > 1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Changes made to each produced event's payload
+    #[serde(default)]
+    pub transform: PipelineTransform,
+    /// WASM module run on each event after `transform` (`wasm` feature)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<WasmTransformRef>,
+    /// Stream produced to (default: `IGGY_STREAM`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sink_stream: Option<String>,
+    /// Topic produced to
+    pub sink_topic: String,
+    /// Consumer ID the source offsets are committed under (default: derived
+    /// from the name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consumer_id: Option<u32>,
+    /// Create the pipeline paused (default: false)
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Changes a pipeline makes to the top-level fields of each payload's
+/// `data`: `drop` first, then `rename`, then `set`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineTransform {
+    /// Fields renamed, old name to new name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rename: BTreeMap<String, String>,
+    /// Fields set to a constant, added or overwritten
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, serde_json::Value>,
+    /// Fields removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drop: Vec<String>,
+}
+
+/// Counters of a pipeline since it was defined.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineMetrics {
+    /// Source events read
+    pub processed: u64,
+    /// Source events dropped by the filter, the expression or the WASM
+    /// transform
+    pub filtered: u64,
+    /// Events produced to the sink
+    pub produced: u64,
+    /// Batches whose sink send or checkpoint failed (and were retried)
+    pub failures: u64,
+    /// Source events skipped because the expression or the WASM transform
+    /// failed on them
+    #[serde(default)]
+    pub transform_errors: u64,
+    /// End of the most recent run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Error of the most recent failed batch, cleared by the next success
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// A pipeline and its progress (`GET /pipelines`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineInfo {
+    /// Pipeline name
+    pub name: String,
+    /// Stream read from
+    pub source_stream: String,
+    /// Topic read from
+    pub source_topic: String,
+    /// Filter as defined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Rhai predicate as defined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Changes made to each produced event's payload
+    pub transform: PipelineTransform,
+    /// WASM module run on each event after `transform`, version pinned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<WasmTransformRef>,
+    /// Stream produced to
+    pub sink_stream: String,
+    /// Topic produced to
+    pub sink_topic: String,
+    /// Consumer ID the source offsets are committed under
+    pub consumer_id: u32,
+    /// Whether the pipeline is paused
+    pub paused: bool,
+    /// When the pipeline was defined
+    pub created_at: DateTime<Utc>,
+    /// Next source offset to read, by partition (known partitions only)
+    pub offsets: BTreeMap<u32, u64>,
+    /// Counters since the pipeline was defined
+    pub metrics: PipelineMetrics,
+}
+
+/// The definition of a pipeline, to define it again (`POST /admin/restore`).
+/// The consumer ID is kept, so the pipeline resumes from its checkpoint.
+impl From<PipelineInfo> for CreatePipelineRequest {
+    fn from(info: PipelineInfo) -> Self {
+        Self {
+            name: info.name,
+            source_stream: Some(info.source_stream),
+            source_topic: info.source_topic,
+            filter: info.filter,
+            expression: info.expression,
+            transform: info.transform,
+            wasm: info.wasm,
+            sink_stream: Some(info.sink_stream),
+            sink_topic: info.sink_topic,
+            consumer_id: Some(info.consumer_id),
+            paused: info.paused,
+        }
+    }
+}
+
+/// A WASM module version used as a pipeline transform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmTransformRef {
+    /// Module name
+    pub module: String,
+    /// Module version (default: the latest when the pipeline is defined)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+}
+
+/// One uploaded version of a WASM module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmModuleVersion {
+    /// Version number, from 1 per module name
+    pub version: u32,
+    /// SHA-256 of the uploaded bytes, hex-encoded
+    pub sha256: String,
+    /// Size of the uploaded module
+    pub size_bytes: u64,
+    /// When the version was uploaded
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// A WASM module and its kept versions (`GET /admin/wasm-modules`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmModuleInfo {
+    /// Module name
+    pub name: String,
+    /// Kept versions, oldest first
+    pub versions: Vec<WasmModuleVersion>,
+}
+
+/// Request to run a WASM module against sample events
+/// (`POST /admin/wasm-modules/{name}/dry-run`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmDryRunRequest {
+    /// Module version (default: the latest)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Events to transform
+    pub events: Vec<Event>,
+}
+
+/// Outcome of a dry run, one result per input event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmDryRunResponse {
+    /// Module name
+    pub module: String,
+    /// Version that ran
+    pub version: u32,
+    /// Results in input order
+    pub results: Vec<WasmDryRunResult>,
+}
+
+/// What a WASM module made of one event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmDryRunResult {
+    /// ID of the input event
+    pub event_id: Uuid,
+    /// Event a pipeline would produce (`None` when dropped or failed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Event>,
+    /// Whether the module dropped the event
+    pub dropped: bool,
+    /// Fuel the invocation consumed
+    pub fuel_consumed: u64,
+    /// Why the invocation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A payload variant and the shape of its `data` (`GET /event-types`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTypeInfo {
+    /// Variant name, sent as the payload's `type`
+    pub name: String,
+    /// What the variant carries
+    pub description: String,
+    /// JSON Schema of the payload's `data`
+    pub schema: serde_json::Value,
+}
+
+/// Administrative operation recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    CreateStream,
+    DeleteStream,
+    CreateTopic,
+    DeleteTopic,
+    RenameStream,
+    RenameTopic,
+    CreateUser,
+    DeleteUser,
+    UpdateUserPermissions,
+    ChangeUserPassword,
+}
+
+/// Result of an audited operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// One entry of the audit log (`GET /admin/audit`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Entry ID
+    pub id: Uuid,
+    /// When the operation finished
+    pub timestamp: DateTime<Utc>,
+    /// What was done
+    pub action: AuditAction,
+    /// Affected resource: `stream`, `stream/topic`, or a username; renames
+    /// record `old -> new`
+    pub resource: String,
+    /// Credential the caller presented: `admin`, `signed`, `api_key`, or
+    /// `anonymous`
+    pub actor: String,
+    /// Client IP, resolved like rate limiting does
+    pub client_ip: String,
+    /// Request ID of the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Whether the operation succeeded
+    pub outcome: AuditOutcome,
+    /// Error of a failed operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Position in the audit topic (set when read back)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+/// Query parameters for reading the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// First audit topic offset to read (default: 0)
+    #[serde(default)]
+    pub offset: u64,
+    /// Entries to scan (default: 100)
+    #[serde(default = "default_audit_count")]
+    pub count: u32,
+    /// Only entries with this action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<AuditAction>,
+    /// Only entries with this outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<AuditOutcome>,
+}
+
+fn default_audit_count() -> u32 {
+    100
+}
+
+/// A page of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogResponse {
+    /// Matching entries, oldest first
+    pub entries: Vec<AuditEntry>,
+    /// Offset to pass to read the next page
+    pub next_offset: u64,
+}
+
+/// A client ranked by the request body bytes it sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopTalker {
+    /// Client IP, resolved like rate limiting does
+    pub client_ip: String,
+    /// Requests with a body sent in the window
+    pub requests: u64,
+    /// Request body bytes sent in the window
+    pub total_bytes: u64,
+    /// Largest single request body in the window
+    pub largest_bytes: u64,
+    /// When the client last sent a request body
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Clients sending the most request body bytes (`GET /admin/top-talkers`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopTalkersResponse {
+    /// Length of one window; the report covers the current and previous one
+    pub window_secs: u64,
+    /// Clients by `total_bytes`, largest first
+    pub talkers: Vec<TopTalker>,
+}
+
+/// Sizes of internal collections, for spotting leaks in soak tests
+/// (`GET /admin/internals`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InternalsResponse {
+    /// Background tasks spawned and not yet finished
+    pub open_tasks: usize,
+    /// Sends waiting in the in-memory outbox
+    pub outbox_depth: usize,
+    /// Client IPs tracked by the rate limiter
+    pub rate_limiter_keys: usize,
+    /// Consumers tracked by the consumer registry
+    pub consumer_registry_size: usize,
+    /// Event IDs remembered for poll deduplication
+    #[serde(default)]
+    pub poll_dedup_ids: usize,
+    /// Partition keys tracked by the ordering check
+    #[serde(default)]
+    pub ordering_check_keys: usize,
+    /// Counters that rose at each of the last `LEAK_CHECK_WINDOW` samples
+    /// (empty while the self-check is off)
+    pub suspected_leaks: Vec<String>,
+}
+
+/// Per-key ordering violations of a topic (`GET /admin/ordering-report`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderingReportResponse {
+    /// Stream checked
+    pub stream: String,
+    /// Topic checked (`ORDERING_CHECK_TOPIC`)
+    pub topic: String,
+    /// When the report was taken
+    pub as_of: DateTime<Utc>,
+    /// How long keys and violations are kept (`ORDERING_CHECK_WINDOW_SECS`)
+    pub window_secs: u64,
+    /// Messages checked since the checker started
+    pub messages_checked: u64,
+    /// Messages without a `sequence_key` header since the checker started,
+    /// which are not checked
+    pub unkeyed_messages: u64,
+    /// Partition keys seen within the window
+    pub keys: usize,
+    /// Violations within the window by `type`
+    pub violation_counts: BTreeMap<String, u64>,
+    /// Violations within the window, most recent first
+    pub violations: Vec<OrderingViolation>,
+}
+
+/// A message breaking its partition key's ordering, tagged by `type`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderingViolation {
+    /// The key was first seen in another partition, so its messages are
+    /// not ordered relative to each other
+    KeySplit {
+        /// Partition key
+        key: String,
+        /// Partition of the message
+        partition_id: u32,
+        /// Offset of the message
+        offset: u64,
+        /// Partitions the key was seen in before
+        other_partitions: Vec<u32>,
+    },
+    /// The event's timestamp is earlier than that of the key's previous
+    /// event in the partition
+    TimestampRegression {
+        /// Partition key
+        key: String,
+        /// Partition of the message
+        partition_id: u32,
+        /// Offset of the message
+        offset: u64,
+        /// Timestamp of the key's previous event
+        previous: DateTime<Utc>,
+        /// Timestamp of this event
+        received: DateTime<Utc>,
+    },
+    /// The key's sequence skipped numbers (a lost or failed send)
+    SequenceGap {
+        /// Partition key
+        key: String,
+        /// Partition of the message
+        partition_id: u32,
+        /// Offset of the message after the gap
+        offset: u64,
+        /// Sequence number that should have come next
+        expected: u64,
+        /// Sequence number read
+        received: u64,
+    },
+    /// The key's sequence repeated or went back (a retried send)
+    SequenceDuplicate {
+        /// Partition key
+        key: String,
+        /// Partition of the message
+        partition_id: u32,
+        /// Offset of the repeated message
+        offset: u64,
+        /// Sequence number that should have come next
+        expected: u64,
+        /// Sequence number read
+        received: u64,
+    },
+}
+
+impl OrderingViolation {
+    /// The `type` tag of the violation.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::KeySplit { .. } => "key_split",
+            Self::TimestampRegression { .. } => "timestamp_regression",
+            Self::SequenceGap { .. } => "sequence_gap",
+            Self::SequenceDuplicate { .. } => "sequence_duplicate",
+        }
+    }
+}
+
+/// Query parameters of the message tap (`GET /admin/tap`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TapQuery {
+    /// Only messages sent to this stream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<String>,
+    /// Only messages sent to this topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Share of the matching messages streamed, in (0, 1] (default: 1)
+    #[serde(default = "default_tap_sample")]
+    pub sample: f64,
+}
+
+fn default_tap_sample() -> f64 {
+    1.0
+}
+
+/// A sent message, as streamed by the message tap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TappedMessage {
+    /// Stream the message was sent to
+    pub stream: String,
+    /// Topic the message was sent to
+    pub topic: String,
+    /// Partition key of the send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    /// The event as sent (after enrichment)
+    pub event: Event,
+    /// When the send succeeded
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Load test run by `POST /admin/benchmark`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRequest {
+    /// Payload bytes per event (default: 256)
+    #[serde(default = "default_benchmark_event_size")]
+    pub event_size: usize,
+    /// Events per second across all workers (default: 0 = as fast as possible)
+    #[serde(default)]
+    pub rate: u32,
+    /// Length of the run (default: 10)
+    #[serde(default = "default_benchmark_duration_secs")]
+    pub duration_secs: u64,
+    /// Events per send; above 1 every send is a batch (default: 1)
+    #[serde(default = "default_benchmark_batch_size")]
+    pub batch_size: usize,
+    /// Workers sending in parallel (default: 1)
+    #[serde(default = "default_benchmark_concurrency")]
+    pub concurrency: u32,
+}
+
+fn default_benchmark_event_size() -> usize {
+    256
+}
+
+fn default_benchmark_duration_secs() -> u64 {
+    10
+}
+
+fn default_benchmark_batch_size() -> usize {
+    1
+}
+
+fn default_benchmark_concurrency() -> u32 {
+    1
+}
+
+/// Send latency distribution of a benchmark, per send (batch) in
+/// milliseconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencySummary {
+    /// Fastest send
+    pub min_ms: f64,
+    /// Average send
+    pub mean_ms: f64,
+    /// Median
+    pub p50_ms: f64,
+    /// 90th percentile
+    pub p90_ms: f64,
+    /// 99th percentile
+    pub p99_ms: f64,
+    /// 99.9th percentile
+    pub p999_ms: f64,
+    /// Slowest send
+    pub max_ms: f64,
+}
+
+/// Result of a benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResponse {
+    /// Stream the load was sent to
+    pub stream: String,
+    /// Topic the load was sent to
+    pub topic: String,
+    /// The run's parameters
+    pub request: BenchmarkRequest,
+    /// Serialized size of one event
+    pub event_bytes: usize,
+    /// Actual length of the run
+    pub elapsed_secs: f64,
+    /// Events sent successfully
+    pub events_sent: u64,
+    /// Sends (batches) that succeeded
+    pub sends: u64,
+    /// Sends (batches) that failed
+    pub failed_sends: u64,
+    /// First send error, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+    /// Events sent successfully per second
+    pub events_per_second: f64,
+    /// Event bytes sent successfully per second
+    pub bytes_per_second: f64,
+    /// Latency of successful sends
+    pub latency: LatencySummary,
+    /// The run was cut short by server shutdown
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stopped_early: bool,
+}
+
+/// Fault injection rules of `POST /admin/chaos` (`chaos` feature).
+///
+/// An empty rule list turns injection off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    /// Rules tried in order; the first one whose roll hits applies
+    #[serde(default)]
+    pub rules: Vec<ChaosRule>,
+}
+
+/// One fault, injected into requests to matching routes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChaosRule {
+    /// Route pattern as registered (`/streams/{stream}/topics`), a prefix
+    /// ending in `*` (`/streams/*`), or `*` for every route
+    pub route: String,
+    /// Chance of injecting the fault into a matching request (0.0 - 1.0)
+    pub probability: f64,
+    /// What to inject
+    pub fault: ChaosFault,
+}
+
+/// Fault injected by a [`ChaosRule`], tagged by `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChaosFault {
+    /// Delay the request before it reaches the handler
+    Latency {
+        /// Added delay
+        ms: u64,
+    },
+    /// Answer with an error instead of running the handler
+    Error {
+        /// HTTP status of the error (400 - 599)
+        status: u16,
+    },
+    /// Mark the Iggy connection disconnected, then run the request
+    Disconnect,
 }
 
 /// Response containing polled messages.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PollMessagesResponse {
     /// List of received messages
     pub messages: Vec<ReceivedMessage>,
+    /// Fields following the messages
+    #[serde(flatten)]
+    pub tail: PollResponseTail,
+}
+
+/// Fields of a [`PollMessagesResponse`] after its `messages`, which a
+/// streamed poll writes once the array has been walked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PollResponseTail {
     /// Number of messages returned
     pub count: usize,
     /// Partition ID the messages came from
     pub partition_id: u32,
     /// Current offset after polling
     pub current_offset: u64,
+    /// Whether the poll reached the partition's last message, so a tailing
+    /// client can back off before polling again
+    #[serde(default)]
+    pub end_of_partition: bool,
+    /// Time the server spent polling the broker, in milliseconds
+    #[serde(default)]
+    pub server_poll_duration_ms: f64,
+    /// Events left out as duplicates of ones already returned to the
+    /// consumer (`POLL_DEDUP_WINDOW_SECS`)
+    #[serde(default)]
+    pub duplicates_filtered: usize,
+    /// Anomalies noticed in the returned messages (omitted when none)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<PollWarning>,
+    /// Token resuming after these messages on any replica, with
+    /// `POLL_CONTINUATION_SECRET` set (`?continuation=`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<String>,
+}
+
+/// Anomaly in a poll's messages, tagged by `type`.
+///
+/// Sequence warnings compare the `sequence` headers stamped by
+/// `KEY_SEQUENCING` between messages of the same key in one response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PollWarning {
+    /// A key's sequence skipped numbers (a lost or failed send)
+    SequenceGap {
+        /// Partition key
+        key: String,
+        /// Offset of the message after the gap
+        offset: u64,
+        /// Sequence number that should have come next
+        expected: u64,
+        /// Sequence number read
+        received: u64,
+    },
+    /// A key's sequence repeated or went back (a retried send or a
+    /// redelivered copy)
+    SequenceDuplicate {
+        /// Partition key
+        key: String,
+        /// Offset of the repeated message
+        offset: u64,
+        /// Sequence number that should have come next
+        expected: u64,
+        /// Sequence number read
+        received: u64,
+    },
+    /// An event could not be upcast to `target_version`; it is returned
+    /// at `schema_version`, the last version reached
+    UpcastFailed {
+        /// Offset of the event
+        offset: u64,
+        /// Event type
+        event_type: String,
+        /// Version the event is returned at
+        schema_version: u32,
+        /// Why the next step failed
+        error: String,
+    },
+    /// The poll's `filter` failed on an event; it is left out
+    FilterFailed {
+        /// Offset of the event
+        offset: u64,
+        /// Why the evaluation failed
+        error: String,
+    },
 }
 
 /// A message received from polling.
-#[derive(Debug, Serialize)]
+///
+/// `partition_id` + `offset` identify the message's position for
+/// checkpointing; `id` and `checksum` let consumers de-duplicate
+/// redeliveries.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ReceivedMessage {
+    /// Partition the message was read from
+    pub partition_id: u32,
     /// Message offset within the partition
     pub offset: u64,
-    /// Message timestamp
+    /// Message timestamp (server append time)
     pub timestamp: DateTime<Utc>,
     /// Message ID
     pub id: u128,
+    /// Server-computed payload checksum
+    pub checksum: u64,
+    /// User headers attached to the message (omitted when empty)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
     /// The deserialized event
     pub event: Event,
     /// Raw message size in bytes
     pub size: usize,
 }
 
+/// Result of a message search.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// Matching messages, by offset
+    pub matches: Vec<ReceivedMessage>,
+    /// Number of matches returned
+    pub count: usize,
+    /// Partition searched
+    pub partition_id: u32,
+    /// Messages read
+    pub scanned: u64,
+    /// Offset to pass as `from_offset` to continue the search
+    pub next_offset: u64,
+    /// Why the scan stopped
+    pub stopped: SearchStop,
+}
+
+/// Why a message search stopped scanning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStop {
+    /// Reached the partition's last message
+    EndOfPartition,
+    /// Found `limit` matches
+    Limit,
+    /// Read `max_scan` messages
+    MaxScan,
+    /// Ran for `SEARCH_TIMEOUT_MS`
+    Timeout,
+}
+
+/// Query parameters of the stream and topic listings (`GET /streams`,
+/// `GET /streams/{stream}/topics`).
+///
+/// Without `page` or `per_page` the whole (filtered, sorted) listing is
+/// returned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// 1-indexed page (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// Items per page (default: 100 when `page` is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<u32>,
+    /// Only items whose name contains this, ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_contains: Option<String>,
+    /// Order of the items (default: `name`)
+    #[serde(default)]
+    pub sort: ListSort,
+}
+
+/// Field a listing is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// `name`
+    #[default]
+    Name,
+    /// `id`
+    Id,
+    /// `created_at`
+    CreatedAt,
+    /// `messages_count`
+    MessagesCount,
+    /// `size_bytes`
+    SizeBytes,
+}
+
+/// Order of a listing, serialized as the field name, prefixed with `-` for
+/// descending order: `name`, `-size_bytes`, ...
+///
+/// Sortable fields: `name`, `id`, `created_at`, `messages_count` and
+/// `size_bytes`. Ties are broken by name, so pages are stable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ListSort {
+    /// Field sorted by
+    pub key: SortKey,
+    /// Largest (or last) first
+    pub descending: bool,
+}
+
+impl std::str::FromStr for ListSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (descending, field) = match s.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, s),
+        };
+        let key = match field {
+            "name" => SortKey::Name,
+            "id" => SortKey::Id,
+            "created_at" => SortKey::CreatedAt,
+            "messages_count" => SortKey::MessagesCount,
+            "size_bytes" => SortKey::SizeBytes,
+            _ => {
+                return Err(format!(
+                    "Unknown sort '{s}' (expected name, id, created_at, messages_count \
+                     or size_bytes, optionally prefixed with -)"
+                ));
+            }
+        };
+        Ok(Self { key, descending })
+    }
+}
+
+impl std::fmt::Display for ListSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.descending {
+            f.write_str("-")?;
+        }
+        f.write_str(match self.key {
+            SortKey::Name => "name",
+            SortKey::Id => "id",
+            SortKey::CreatedAt => "created_at",
+            SortKey::MessagesCount => "messages_count",
+            SortKey::SizeBytes => "size_bytes",
+        })
+    }
+}
+
+impl TryFrom<String> for ListSort {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ListSort> for String {
+    fn from(value: ListSort) -> Self {
+        value.to_string()
+    }
+}
+
 /// Stream information response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StreamInfo {
     /// Stream ID
     pub id: u32,
@@ -96,7 +1604,7 @@ pub struct StreamInfo {
 }
 
 /// Topic information response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TopicInfo {
     /// Topic ID
     pub id: u32,
@@ -114,24 +1622,737 @@ pub struct TopicInfo {
     pub messages_count: u64,
 }
 
+/// Per-partition detail within [`TopicStatsResponse`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionStats {
+    /// Partition ID
+    pub id: u32,
+    /// Messages stored in the partition
+    pub messages_count: u64,
+    /// Partition size in bytes
+    pub size_bytes: u64,
+    /// Offset of the latest message in the partition
+    pub current_offset: u64,
+    /// Number of on-disk segments
+    pub segments_count: u32,
+}
+
+/// Topic statistics response with per-partition detail.
+///
+/// Served from a per-topic cache refreshed at most once per
+/// `STATS_CACHE_TTL_SECS`, like the global `/stats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicStatsResponse {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Number of partitions
+    pub partitions_count: u32,
+    /// Total messages across all partitions
+    pub messages_count: u64,
+    /// Total size in bytes across all partitions
+    pub size_bytes: u64,
+    /// Per-partition breakdown, ordered by partition ID
+    pub partitions: Vec<PartitionStats>,
+    /// Age of the cached statistics in seconds (0 = fresh)
+    pub cache_age_seconds: u64,
+}
+
+/// Query parameters of a topic profile
+/// (`GET /streams/{stream}/topics/{topic}/profile`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileQuery {
+    /// Recent messages to sample, spread across partitions (default: 200,
+    /// at most 1000)
+    #[serde(default = "default_profile_sample")]
+    pub sample: u32,
+}
+
+impl Default for ProfileQuery {
+    fn default() -> Self {
+        Self {
+            sample: default_profile_sample(),
+        }
+    }
+}
+
+fn default_profile_sample() -> u32 {
+    200
+}
+
+/// What flows through a topic, estimated from its recent messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicProfile {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Messages sampled
+    pub sampled: usize,
+    /// Timestamp of the oldest sampled message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oldest: Option<DateTime<Utc>>,
+    /// Timestamp of the newest sampled message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newest: Option<DateTime<Utc>>,
+    /// Stored size of the sampled messages
+    pub payload_bytes: SizeDistribution,
+    /// Most frequent event types, most frequent first
+    pub event_types: Vec<FrequencyCount>,
+    /// Most frequent payload fields as JSONPaths (`[]` for array
+    /// elements), most frequent first
+    pub fields: Vec<FrequencyCount>,
+    /// Uncompressed JSON size of the sampled events, in bytes
+    pub json_bytes: u64,
+    /// Size the sample would have with each `BATCH_COMPRESSION`
+    pub compression: Vec<CompressionEstimate>,
+}
+
+/// Distribution of message sizes, in bytes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SizeDistribution {
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
+/// How often a value occurs in a topic profile's sample.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyCount {
+    /// Event type or field path
+    pub value: String,
+    /// Sampled messages with it
+    pub count: usize,
+    /// `count` over the messages sampled (0.0 - 1.0)
+    pub share: f64,
+}
+
+/// Estimated effect of one batch compression on a topic profile's sample.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompressionEstimate {
+    /// `gzip` or `zstd`
+    pub algorithm: String,
+    /// Total size once compressed, with `COMPRESSION_THRESHOLD_BYTES`
+    /// applied per message as batch sends do
+    pub bytes: u64,
+    /// `json_bytes` over `bytes` (higher is better)
+    pub ratio: f64,
+    /// Messages that would be compressed (large enough, and shrinking)
+    pub compressed_messages: usize,
+}
+
+/// Summary of one topic within [`StreamStatsResponse`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamTopicStats {
+    /// Topic name
+    pub name: String,
+    /// Number of partitions
+    pub partitions_count: u32,
+    /// Total messages across all partitions
+    pub messages_count: u64,
+    /// Total size in bytes across all partitions
+    pub size_bytes: u64,
+}
+
+/// Statistics for a stream with per-topic detail (`GET /streams/{name}/stats`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamStatsResponse {
+    /// Stream name
+    pub stream: String,
+    /// Number of topics
+    pub topics_count: u32,
+    /// Total messages across all topics
+    pub messages_count: u64,
+    /// Total size in bytes across all topics
+    pub size_bytes: u64,
+    /// Per-topic breakdown, ordered by topic name
+    pub topics: Vec<StreamTopicStats>,
+    /// Age of the cached statistics in seconds (0 = fresh)
+    pub cache_age_seconds: u64,
+}
+
+/// Consumer lag on a single partition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionLag {
+    /// Partition ID
+    pub partition_id: u32,
+    /// Offset of the latest message in the partition
+    pub current_offset: u64,
+    /// Offset last committed by the consumer (`None` = never committed)
+    pub committed_offset: Option<u64>,
+    /// Messages not yet consumed
+    pub lag: u64,
+}
+
+/// Consumer lag response for one consumer on one topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumerLagResponse {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Consumer ID
+    pub consumer_id: u32,
+    /// Sum of the per-partition lag
+    pub total_lag: u64,
+    /// Per-partition breakdown, ordered by partition ID
+    pub partitions: Vec<PartitionLag>,
+}
+
+/// One acknowledged position: the offset of the last processed message on a
+/// partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AckOffset {
+    /// Partition ID
+    pub partition_id: u32,
+    /// Offset of the last message processed on the partition
+    pub offset: u64,
+}
+
+/// Request body for acknowledging processed messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckRequest {
+    /// Positions to commit; several entries for one partition commit the
+    /// highest offset
+    pub offsets: Vec<AckOffset>,
+}
+
+/// Offsets committed by an acknowledgment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckResponse {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Consumer ID
+    pub consumer_id: u32,
+    /// Offset committed per partition, ordered by partition ID
+    pub committed: Vec<AckOffset>,
+}
+
+/// Request body for negatively acknowledging (nacking) messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NackRequest {
+    /// Positions of the messages to redeliver
+    pub offsets: Vec<AckOffset>,
+}
+
+/// What happened to one nacked message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NackedMessage {
+    /// Partition the message was polled from
+    pub partition_id: u32,
+    /// Offset the message was polled from
+    pub offset: u64,
+    /// `redelivery_count` header on the copy
+    pub redelivery_count: u32,
+    /// Topic the copy was appended to
+    pub topic: String,
+    /// Whether the copy went to the dead-letter topic
+    pub dead_lettered: bool,
+}
+
+/// Outcome of a nack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NackResponse {
+    /// Stream name
+    pub stream: String,
+    /// Topic the messages were polled from
+    pub topic: String,
+    /// Consumer ID
+    pub consumer_id: u32,
+    /// One entry per nacked message, ordered by partition and offset
+    pub messages: Vec<NackedMessage>,
+}
+
+/// Offset of a registered consumer on one partition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumerOffset {
+    /// Partition ID
+    pub partition_id: u32,
+    /// Offset last committed through this service (`None` = not committed)
+    pub committed_offset: Option<u64>,
+}
+
+/// A consumer seen polling through this service (`GET /consumers`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumerInfo {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Consumer ID
+    pub consumer_id: u32,
+    /// When the consumer last polled
+    pub last_poll_at: DateTime<Utc>,
+    /// Seconds since the last poll
+    pub idle_seconds: u64,
+    /// Partitions polled, ordered by partition ID
+    pub partitions: Vec<ConsumerOffset>,
+}
+
+/// Backing Iggy server information (`GET /admin/server-info`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfoResponse {
+    /// Iggy server version
+    pub version: String,
+    /// Server host name
+    pub hostname: String,
+    /// Server operating system name and version
+    pub os: String,
+    /// Server process ID
+    pub process_id: u32,
+    /// Server uptime in seconds
+    pub uptime_seconds: u64,
+    /// Ping round-trip latency from this gateway, in milliseconds
+    pub ping_latency_ms: f64,
+    /// Connected clients
+    pub clients_count: u32,
+    /// Consumer groups across all topics
+    pub consumer_groups_count: u32,
+    /// Streams on the server
+    pub streams_count: u32,
+    /// Topics on the server
+    pub topics_count: u32,
+    /// Partitions on the server
+    pub partitions_count: u32,
+    /// Segments on the server
+    pub segments_count: u32,
+    /// Messages stored on the server
+    pub messages_count: u64,
+    /// Server process CPU usage (percent)
+    pub cpu_usage: f32,
+    /// Server process memory usage in bytes
+    pub memory_usage_bytes: u64,
+    /// Total host memory in bytes
+    pub total_memory_bytes: u64,
+    /// Available host memory in bytes
+    pub available_memory_bytes: u64,
+}
+
+/// How a resource of the bootstrap spec compares with the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapState {
+    /// Exists with the settings of the spec
+    InSync,
+    /// Exists with different settings
+    Drifted,
+    /// Does not exist
+    Missing,
+}
+
+/// One stream or topic of the bootstrap spec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapResourceStatus {
+    /// Stream name
+    pub stream: String,
+    /// Topic name (absent for the stream itself)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Comparison with the server
+    pub state: BootstrapState,
+    /// Differing settings of a drifted topic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drift: Vec<String>,
+}
+
+/// Bootstrap spec compared with the server (`GET /admin/bootstrap/status`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapStatusResponse {
+    /// Whether a bootstrap spec is configured
+    pub configured: bool,
+    /// Whether every resource is in sync
+    pub in_sync: bool,
+    /// Every stream and topic of the spec, in spec order
+    pub resources: Vec<BootstrapResourceStatus>,
+}
+
+/// A topic under a retention policy, with the manager's last actions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionTopicStatus {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Policy from the bootstrap spec
+    pub policy: RetentionPolicy,
+    /// Last pass that checked the topic without error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_enforced_at: Option<DateTime<Utc>>,
+    /// Last time the topic's settings had to be changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated_at: Option<DateTime<Utc>>,
+    /// Last time the topic was purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_purged_at: Option<DateTime<Utc>>,
+    /// When the topic is purged next (with `purge_interval_secs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_purge_at: Option<DateTime<Utc>>,
+    /// Error of the last pass, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Retention policies and their enforcement (`GET /admin/retention`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionStatusResponse {
+    /// Whether the retention manager runs
+    pub enabled: bool,
+    /// Seconds between enforcement passes
+    pub interval_secs: u64,
+    /// Topics with a retention policy, in spec order
+    pub topics: Vec<RetentionTopicStatus>,
+}
+
+/// A local topic replicated to the remote cluster, with its progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationTopicStatus {
+    /// Local stream read
+    pub source_stream: String,
+    /// Local topic read
+    pub source_topic: String,
+    /// Remote stream written
+    pub target_stream: String,
+    /// Remote topic written
+    pub target_topic: String,
+    /// Next local offset to replicate, by partition
+    pub offsets: BTreeMap<u32, u64>,
+    /// Local messages not yet replicated, as of the last run
+    pub lag: u64,
+    /// Messages copied to the remote topic since startup
+    pub replicated: u64,
+    /// Messages left out since startup because they were themselves
+    /// replicated from another cluster
+    pub skipped: u64,
+    /// Runs that failed since startup (and were retried)
+    pub failures: u64,
+    /// End of the most recent run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Error of the most recent run, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Replication to a remote cluster (`GET /admin/replication`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationStatusResponse {
+    /// Whether the replicator runs (`REPLICATION_CONNECTION_STRING` set)
+    pub enabled: bool,
+    /// Whether the remote cluster is connected
+    pub connected: bool,
+    /// Value of the `replicated_from` header stamped on copies
+    pub origin: String,
+    /// Consumer ID whose local offsets checkpoint the replication
+    pub consumer_id: u32,
+    /// Milliseconds between runs
+    pub interval_ms: u64,
+    /// Error of the last failed connection attempt, cleared once connected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_error: Option<String>,
+    /// Replicated topics, in `REPLICATION_TOPICS` order
+    pub topics: Vec<ReplicationTopicStatus>,
+}
+
+/// Gateway-side state of one instance (`POST /admin/snapshot`), loaded
+/// into another with `POST /admin/restore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewaySnapshot {
+    /// Bundle format version (see `SNAPSHOT_VERSION`)
+    pub version: u32,
+    /// Service that took the snapshot (`SERVICE_NAME`)
+    pub service: String,
+    /// When the snapshot was taken
+    pub created_at: DateTime<Utc>,
+    /// Recurring schedules, ordered by name
+    #[serde(default)]
+    pub schedules: Vec<CreateScheduleRequest>,
+    /// Pipelines, ordered by name
+    #[serde(default)]
+    pub pipelines: Vec<CreatePipelineRequest>,
+    /// Consumer registry, ordered by stream, topic and ID
+    #[serde(default)]
+    pub consumers: Vec<ConsumerInfo>,
+}
+
+/// Outcome of `POST /admin/restore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreResponse {
+    /// Schedules registered
+    pub schedules: usize,
+    /// Pipelines defined
+    pub pipelines: usize,
+    /// Consumers added to the registry
+    pub consumers: usize,
+    /// Items that could not be restored
+    pub failures: Vec<RestoreFailure>,
+}
+
+/// An item of a snapshot that could not be restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreFailure {
+    /// `schedule` or `pipeline`
+    pub kind: String,
+    /// Item name
+    pub name: String,
+    /// Why it was rejected
+    pub error: String,
+}
+
+/// State store migrations (`GET /admin/migrations`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationsResponse {
+    /// Active state backend (`STATE_BACKEND`); `memory` has nothing to
+    /// migrate
+    pub backend: String,
+    /// Whether an incompatible migration is pending, keeping `/ready` at 503
+    pub blocking: bool,
+    /// Migrations known to this build, by version
+    pub migrations: Vec<MigrationStatus>,
+}
+
+/// One state store migration and whether it was applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// Version; migrations are applied in increasing order
+    pub version: u32,
+    /// What the migration changes
+    pub name: String,
+    /// Whether this build can serve while the migration is pending
+    pub compatible: bool,
+    /// `applied`, `pending` or `failed`
+    pub state: MigrationState,
+    /// When the migration was applied (by this or an earlier instance)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<DateTime<Utc>>,
+    /// Why the migration failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether a migration was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    Failed,
+}
+
+/// Global permissions of an Iggy user.
+///
+/// Mirrors Iggy's `GlobalPermissions`; omitted flags default to `false`.
+/// Per-stream permissions are not exposed by this API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPermissions {
+    /// Manage server settings
+    pub manage_servers: bool,
+    /// Read server information and stats
+    pub read_servers: bool,
+    /// Create, update and delete users
+    pub manage_users: bool,
+    /// Read users
+    pub read_users: bool,
+    /// Create, update and delete streams
+    pub manage_streams: bool,
+    /// Read streams
+    pub read_streams: bool,
+    /// Create, update and delete topics
+    pub manage_topics: bool,
+    /// Read topics
+    pub read_topics: bool,
+    /// Poll messages from any topic
+    pub poll_messages: bool,
+    /// Send messages to any topic
+    pub send_messages: bool,
+}
+
+/// Iggy user response (`/admin/users`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserResponse {
+    /// Server-assigned user ID
+    pub id: u32,
+    /// Username
+    pub username: String,
+    /// `active` or `inactive`
+    pub status: String,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Global permissions (only included when fetching a single user)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<UserPermissions>,
+}
+
+/// Request to create an Iggy user.
+#[derive(Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    /// Username (alphanumeric, `-`, `_`, `.`)
+    pub username: String,
+    /// Initial password
+    pub password: String,
+    /// Whether the user can log in immediately
+    #[serde(default = "default_user_active")]
+    pub active: bool,
+    /// Global permissions (none when omitted)
+    #[serde(default)]
+    pub permissions: Option<UserPermissions>,
+}
+
+fn default_user_active() -> bool {
+    true
+}
+
+// Manual Debug so passwords never reach logs or spans.
+impl std::fmt::Debug for CreateUserRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreateUserRequest")
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .field("active", &self.active)
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+/// Request to replace a user's permissions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdatePermissionsRequest {
+    /// New global permissions (`null` clears them)
+    pub permissions: Option<UserPermissions>,
+}
+
+/// Request to change a user's password.
+#[derive(Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    /// Current password (verified by the server)
+    pub current_password: String,
+    /// New password
+    pub new_password: String,
+}
+
+impl std::fmt::Debug for ChangePasswordRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangePasswordRequest")
+            .field("current_password", &"[REDACTED]")
+            .field("new_password", &"[REDACTED]")
+            .finish()
+    }
+}
+
 /// Health check response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     /// Service health status
     pub status: String,
     /// Whether Iggy connection is healthy
     pub iggy_connected: bool,
+    /// `host:port` of the Iggy server in use (see `IGGY_FALLBACK_SERVERS`)
+    pub iggy_endpoint: Option<String>,
+    /// State of each Iggy circuit breaker
+    pub circuit_breakers: CircuitBreakerStates,
+    /// The read connection, with `IGGY_READ_CONNECTION_STRING` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iggy_read: Option<ReadConnectionHealth>,
+    /// Storage use against `MAX_TOTAL_SIZE_BYTES` / `MAX_TOPIC_SIZE_BYTES`,
+    /// when either is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_pressure: Option<StoragePressure>,
+    /// This replica's view of the leader election, with
+    /// `LEADER_ELECTION_LEASE_SECS` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leadership: Option<LeadershipStatus>,
+    /// Endpoint groups this instance serves (`ENABLE_*_API`)
+    pub features: EnabledFeatures,
     /// Service version
     pub version: String,
     /// Current timestamp
     pub timestamp: DateTime<Utc>,
 }
 
+/// Endpoint groups served by this instance; a disabled group answers 404.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnabledFeatures {
+    /// Sends, `/scheduled` and `/schedules` (`ENABLE_PRODUCE_API`)
+    pub produce_api: bool,
+    /// Polls, peeks, search, profiles and consumers (`ENABLE_CONSUME_API`)
+    pub consume_api: bool,
+    /// `/admin/*` and stream/topic DELETE (`ENABLE_ADMIN_API`), wherever
+    /// `ADMIN_PORT` serves them
+    pub admin_api: bool,
+}
+
+/// Health of the separate Iggy connection used for polls, acks and consumer
+/// lag.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadConnectionHealth {
+    /// Whether the read connection is healthy
+    pub connected: bool,
+    /// `host:port` of the read server
+    pub endpoint: Option<String>,
+    /// State of each circuit breaker of the read connection
+    pub circuit_breakers: CircuitBreakerStates,
+}
+
+/// Storage use against the configured thresholds, as of the last stats
+/// refresh.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoragePressure {
+    /// Whether any threshold is exceeded
+    pub exceeded: bool,
+    /// Whether produces are refused while exceeded
+    /// (`STORAGE_REJECT_PRODUCES`)
+    pub rejecting_produces: bool,
+    /// Bytes stored across all streams
+    pub total_size_bytes: u64,
+    /// `MAX_TOTAL_SIZE_BYTES`, if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size_bytes: Option<u64>,
+    /// `MAX_TOPIC_SIZE_BYTES`, if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_topic_size_bytes: Option<u64>,
+    /// Topics above `MAX_TOPIC_SIZE_BYTES`, as `stream/topic`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics_over_limit: Vec<String>,
+}
+
+/// This replica's view of the leader election for singleton background
+/// tasks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeadershipStatus {
+    /// Whether this replica runs the singleton tasks
+    pub leader: bool,
+    /// This replica's candidate ID (`LEADER_ELECTION_ID`)
+    pub candidate: String,
+    /// Candidate holding the lease, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+    /// `LEADER_ELECTION_LEASE_SECS`
+    pub lease_secs: u64,
+}
+
+/// State of the circuit breaker of each Iggy operation class: `closed`,
+/// `half-open` or `open`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CircuitBreakerStates {
+    /// Sending messages
+    pub send: String,
+    /// Polling messages and consumer offsets
+    pub poll: String,
+    /// Stream, topic, user and server operations
+    pub admin: String,
+}
+
 /// Statistics response.
 ///
 /// These statistics are retrieved from a background-refreshed cache.
 /// The `cache_age_seconds` field indicates how old the data is.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StatsResponse {
     /// Number of active streams
     pub streams_count: u32,
@@ -147,12 +2368,74 @@ pub struct StatsResponse {
     pub cache_age_seconds: u64,
     /// Whether the cache is considered stale (exceeded TTL)
     pub cache_stale: bool,
+    /// Event-time lag of recently polled events, per topic polled
+    #[serde(default)]
+    pub event_time_lag: Vec<TopicEventTimeLag>,
+    /// Topics whose throughput currently deviates from its moving average
+    /// (`THROUGHPUT_ANOMALY_THRESHOLD`)
+    #[serde(default)]
+    pub anomalies: Vec<ThroughputAnomaly>,
+}
+
+/// A topic's message rate deviating from its moving average.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputAnomaly {
+    /// Stream of the topic
+    pub stream: String,
+    /// Topic
+    pub topic: String,
+    /// Whether the rate dropped or spiked
+    pub kind: AnomalyKind,
+    /// Messages per second over the last stats refresh
+    pub rate_per_sec: f64,
+    /// Moving average of the rate before it
+    pub expected_per_sec: f64,
+    /// Standard deviations between the two (negative for a drop)
+    pub deviations: f64,
+    /// When the anomaly started
+    pub since: DateTime<Utc>,
+}
+
+/// Direction of a throughput anomaly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Fewer messages than usual
+    Drop,
+    /// More messages than usual
+    Spike,
+}
+
+impl AnomalyKind {
+    /// Lowercase name, as serialized.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Drop => "drop",
+            Self::Spike => "spike",
+        }
+    }
+}
+
+/// How long after their timestamp a topic's recent events were polled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicEventTimeLag {
+    /// Stream polled
+    pub stream: String,
+    /// Topic polled
+    pub topic: String,
+    /// Recent events the percentiles cover
+    pub samples: usize,
+    /// Median lag in seconds
+    pub p50_seconds: f64,
+    /// 99th percentile lag in seconds
+    pub p99_seconds: f64,
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 mod tests {
     use super::*;
+    use crate::models::EventPayload;
 
     #[test]
     fn test_create_stream_request_deserialization() {
@@ -173,6 +2456,30 @@ mod tests {
         assert_eq!(request.partitions, 1);
     }
 
+    #[test]
+    fn test_partitioning_strategy_round_trips_as_string() {
+        for (text, strategy) in [
+            ("balanced", PartitioningStrategy::Balanced),
+            ("key", PartitioningStrategy::Key(None)),
+            (
+                "key:murmur2",
+                PartitioningStrategy::Key(Some(KeyHashing::Murmur2)),
+            ),
+            ("partition_id:2", PartitioningStrategy::PartitionId(2)),
+            ("sticky", PartitioningStrategy::Sticky),
+        ] {
+            let json = format!(r#"{{"events": [], "partitioning": "{text}"}}"#);
+            let request: SendBatchRequest =
+                serde_json::from_str(&json).expect("Deserialization should succeed");
+            assert_eq!(request.partitioning, Some(strategy));
+            assert_eq!(strategy.to_string(), text);
+        }
+
+        assert!("partition_id:x".parse::<PartitioningStrategy>().is_err());
+        assert!("random".parse::<PartitioningStrategy>().is_err());
+        assert!("key:md5".parse::<PartitioningStrategy>().is_err());
+    }
+
     #[test]
     fn test_send_message_response_serialization() {
         let response = SendMessageResponse {
@@ -181,10 +2488,50 @@ mod tests {
             stream: "test-stream".to_string(),
             topic: "test-topic".to_string(),
             timestamp: Utc::now(),
+            correlation_id: None,
+            spooled: false,
         };
 
         let json = serde_json::to_string(&response).expect("Serialization should succeed");
         assert!(json.contains("\"success\":true"));
+        assert!(!json.contains("spooled"));
+    }
+
+    #[test]
+    fn test_received_message_metadata_serialization() {
+        let message = ReceivedMessage {
+            partition_id: 2,
+            offset: 41,
+            timestamp: Utc::now(),
+            id: 7,
+            checksum: 123_456,
+            headers: BTreeMap::new(),
+            event: Event::new("test", EventPayload::Generic(serde_json::json!({}))),
+            size: 64,
+        };
+
+        let json = serde_json::to_value(&message).expect("Serialization should succeed");
+        assert_eq!(json["partition_id"], 2);
+        assert_eq!(json["offset"], 41);
+        assert_eq!(json["checksum"], 123_456);
+        // Empty header maps are omitted, and default back on deserialize.
+        assert!(json.get("headers").is_none());
+        let parsed: ReceivedMessage =
+            serde_json::from_value(json).expect("Deserialization should succeed");
+        assert!(parsed.headers.is_empty());
+    }
+
+    #[test]
+    fn test_create_user_request_defaults_and_redaction() {
+        let json = r#"{"username":"svc","password":"s3cret","permissions":{"read_streams":true}}"#;
+        let request: CreateUserRequest =
+            serde_json::from_str(json).expect("Deserialization should succeed");
+
+        assert!(request.active);
+        let permissions = request.permissions.as_ref().expect("permissions present");
+        assert!(permissions.read_streams);
+        assert!(!permissions.manage_users);
+        assert!(!format!("{:?}", request).contains("s3cret"));
     }
 
     #[test]
@@ -192,11 +2539,26 @@ mod tests {
         let response = HealthResponse {
             status: "healthy".to_string(),
             iggy_connected: true,
+            iggy_endpoint: Some("localhost:8090".to_string()),
+            iggy_read: None,
+            storage_pressure: None,
+            leadership: None,
+            circuit_breakers: CircuitBreakerStates {
+                send: "closed".to_string(),
+                poll: "open".to_string(),
+                admin: "closed".to_string(),
+            },
+            features: EnabledFeatures {
+                produce_api: true,
+                consume_api: true,
+                admin_api: false,
+            },
             version: "0.1.0".to_string(),
             timestamp: Utc::now(),
         };
 
         let json = serde_json::to_string(&response).expect("Serialization should succeed");
         assert!(json.contains("\"status\":\"healthy\""));
+        assert!(json.contains("\"poll\":\"open\""));
     }
 }
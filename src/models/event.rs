@@ -1,8 +1,12 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use schemars::{JsonSchema, schema_for};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::EventTypeInfo;
+use super::upcast::INITIAL_SCHEMA_VERSION;
+
 /// Base event structure with common metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
@@ -10,6 +14,10 @@ pub struct Event {
     pub id: Uuid,
     /// Event type discriminator
     pub event_type: String,
+    /// Version of the payload shape for this event type (1 when absent;
+    /// see [`super::UpcasterRegistry`])
+    #[serde(default = "initial_schema_version")]
+    pub schema_version: u32,
     /// ISO 8601 timestamp of event creation
     pub timestamp: DateTime<Utc>,
     /// Event payload (type-specific data)
@@ -20,6 +28,10 @@ pub struct Event {
     /// Optional source system identifier
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+    /// When the gateway produced the event to Iggy (set by event
+    /// enrichment; see `EVENT_ENRICHMENT`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub produced_at: Option<DateTime<Utc>>,
 }
 
 impl Event {
@@ -28,10 +40,12 @@ impl Event {
         Self {
             id: Uuid::new_v4(),
             event_type: event_type.into(),
+            schema_version: INITIAL_SCHEMA_VERSION,
             timestamp: Utc::now(),
             payload,
             correlation_id: None,
             source: None,
+            produced_at: None,
         }
     }
 
@@ -46,6 +60,16 @@ impl Event {
         self.source = Some(source.into());
         self
     }
+
+    /// Set the payload schema version.
+    pub fn with_schema_version(mut self, schema_version: u32) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+}
+
+fn initial_schema_version() -> u32 {
+    INITIAL_SCHEMA_VERSION
 }
 
 /// Type-safe event payloads using enum variants.
@@ -56,12 +80,49 @@ pub enum EventPayload {
     User(UserEvent),
     /// Order-related events
     Order(OrderEvent),
+    /// Inventory-related events
+    Inventory(InventoryEvent),
+    /// Payment-related events
+    Payment(PaymentEvent),
     /// Generic JSON payload for flexibility
     Generic(serde_json::Value),
 }
 
+impl EventPayload {
+    /// Every payload variant with the JSON Schema of its `data`, derived
+    /// from the payload types (backs `GET /event-types`).
+    ///
+    /// A new variant needs an entry here to be listed.
+    pub fn catalog() -> Vec<EventTypeInfo> {
+        let entry = |name: &str, description: &str, schema: schemars::Schema| EventTypeInfo {
+            name: name.to_string(),
+            description: description.to_string(),
+            schema: schema.to_value(),
+        };
+        vec![
+            entry("User", "User-related events", schema_for!(UserEvent)),
+            entry("Order", "Order-related events", schema_for!(OrderEvent)),
+            entry(
+                "Inventory",
+                "Inventory-related events",
+                schema_for!(InventoryEvent),
+            ),
+            entry(
+                "Payment",
+                "Payment-related events",
+                schema_for!(PaymentEvent),
+            ),
+            entry(
+                "Generic",
+                "Generic JSON payload for flexibility",
+                schema_for!(serde_json::Value),
+            ),
+        ]
+    }
+}
+
 /// User domain events.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "action")]
 pub enum UserEvent {
     Created {
@@ -92,7 +153,7 @@ pub enum UserEvent {
 /// This module uses `rust_decimal::Decimal` for monetary amounts (`total_amount`,
 /// `unit_price`) to ensure exact decimal arithmetic without floating-point
 /// precision issues. This is the recommended approach for financial calculations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "action")]
 pub enum OrderEvent {
     Created {
@@ -120,7 +181,7 @@ pub enum OrderEvent {
 /// Order line item.
 ///
 /// Uses `Decimal` for exact monetary representation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OrderItem {
     pub product_id: Uuid,
     pub quantity: u32,
@@ -128,8 +189,82 @@ pub struct OrderItem {
     pub unit_price: Decimal,
 }
 
+/// Inventory domain events.
+///
+/// Quantities are per SKU in one warehouse. A reservation holds stock for
+/// an order until it is released (cancelled, expired) or the order ships.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action")]
+pub enum InventoryEvent {
+    StockAdjusted {
+        sku: String,
+        warehouse_id: String,
+        /// Change in on-hand quantity (negative for shrinkage, write-offs)
+        delta: i64,
+        /// On-hand quantity after the adjustment
+        quantity_on_hand: u64,
+        reason: String,
+    },
+    Reserved {
+        reservation_id: Uuid,
+        order_id: Uuid,
+        sku: String,
+        warehouse_id: String,
+        quantity: u32,
+        /// When an unconfirmed reservation lapses
+        expires_at: DateTime<Utc>,
+    },
+    Released {
+        reservation_id: Uuid,
+        sku: String,
+        quantity: u32,
+        reason: String,
+    },
+}
+
+/// Payment domain events.
+///
+/// Amounts use `Decimal` like [`OrderEvent`], with an ISO 4217 `currency`
+/// code. A payment is authorized, then captured (possibly for less), and
+/// may later be refunded in one or more parts.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action")]
+pub enum PaymentEvent {
+    Authorized {
+        payment_id: Uuid,
+        order_id: Uuid,
+        amount: Decimal,
+        currency: String,
+        /// e.g. `card`, `bank_transfer`, `wallet`
+        method: String,
+        authorization_code: String,
+    },
+    Captured {
+        payment_id: Uuid,
+        amount: Decimal,
+        currency: String,
+    },
+    Refunded {
+        payment_id: Uuid,
+        refund_id: Uuid,
+        amount: Decimal,
+        currency: String,
+        reason: String,
+    },
+    Failed {
+        payment_id: Uuid,
+        order_id: Uuid,
+        amount: Decimal,
+        currency: String,
+        /// Processor decline or error code, e.g. `insufficient_funds`
+        failure_code: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+}
+
 /// Order status enumeration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum OrderStatus {
     Pending,
@@ -141,7 +276,7 @@ pub enum OrderStatus {
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 mod tests {
     use super::*;
     use std::str::FromStr;
@@ -190,6 +325,21 @@ mod tests {
         assert_eq!(parsed.event_type, event.event_type);
     }
 
+    #[test]
+    fn test_catalog_covers_every_variant() {
+        let catalog = EventPayload::catalog();
+        let names: Vec<&str> = catalog.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["User", "Order", "Inventory", "Payment", "Generic"]);
+
+        // Schemas are derived from the types, so they carry every action
+        let user = serde_json::to_string(&catalog[0].schema).unwrap();
+        for action in ["Created", "Updated", "Deleted", "LoggedIn"] {
+            assert!(user.contains(action), "{action} missing from {user}");
+        }
+        let order = serde_json::to_string(&catalog[1].schema).unwrap();
+        assert!(order.contains("OrderItem") && order.contains("delivered"));
+    }
+
     #[test]
     fn test_order_status_serialization() {
         let status = OrderStatus::Processing;
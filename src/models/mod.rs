@@ -1,8 +1,35 @@
 mod api;
 mod event;
+mod upcast;
 
 pub use api::{
-    CreateStreamRequest, CreateTopicRequest, HealthResponse, PollMessagesResponse, ReceivedMessage,
-    SendMessageRequest, SendMessageResponse, StatsResponse, StreamInfo, TopicInfo,
+    AckOffset, AckRequest, AckResponse, AnomalyKind, AtomicBatchReport, AtomicChunkReport,
+    AtomicChunkStatus, AuditAction, AuditEntry, AuditLogResponse, AuditOutcome, AuditQuery,
+    BatchCompensation, BenchmarkRequest, BenchmarkResponse, BootstrapResourceStatus,
+    BootstrapState, BootstrapStatusResponse, BulkCreateTopicsResponse, BulkTopicResult,
+    BulkTopicStatus, ChangePasswordRequest, ChaosConfig, ChaosFault, ChaosRule,
+    CircuitBreakerStates, CompensationReport, CompressionEstimate, ConsumerInfo,
+    ConsumerLagResponse, ConsumerOffset, CreatePipelineRequest, CreateScheduleRequest,
+    CreateStreamRequest, CreateTopicRequest, CreateUserRequest, EnabledFeatures, EventCountWindow,
+    EventCountsResponse, EventTypeInfo, FanoutDestination, FanoutRequest, FanoutResponse,
+    FanoutResult, FrequencyCount, GatewaySnapshot, HealthResponse, InternalsResponse, KeyHashing,
+    LatencySummary, LeadershipStatus, ListQuery, ListSort, MessageOrder, MigrationState,
+    MigrationStatus, MigrationsResponse, NackRequest, NackResponse, NackedMessage,
+    OrderingReportResponse, OrderingViolation, PartitionLag, PartitionStats, PartitioningStrategy,
+    PeekQuery, PipelineInfo, PipelineMetrics, PipelineTransform, PollMessagesResponse, PollQuery,
+    PollResponseTail, PollWarning, ProfileQuery, ReadConnectionHealth, ReceivedMessage,
+    RenameRequest, ReplicationStatusResponse, ReplicationTopicStatus, RestoreFailure,
+    RestoreResponse, RetentionStatusResponse, RetentionTopicStatus, ScheduleInfo, ScheduleRun,
+    ScheduledMessage, SearchQuery, SearchResponse, SearchStop, SendBatchQuery, SendBatchRequest,
+    SendMessageRequest, SendMessageResponse, ServerInfoResponse, SizeDistribution, SortKey,
+    StatsResponse, StoragePressure, StreamInfo, StreamStatsResponse, StreamTopicStats, TapQuery,
+    TappedMessage, ThroughputAnomaly, TopTalker, TopTalkersResponse, TopicEventTimeLag, TopicInfo,
+    TopicProfile, TopicStatsResponse, UpdatePermissionsRequest, UpdateScheduleRequest,
+    UserPermissions, UserResponse, WasmDryRunRequest, WasmDryRunResponse, WasmDryRunResult,
+    WasmModuleInfo, WasmModuleVersion, WasmTransformRef,
 };
-pub use event::{Event, EventPayload, OrderEvent, OrderItem, OrderStatus, UserEvent};
+pub use event::{
+    Event, EventPayload, InventoryEvent, OrderEvent, OrderItem, OrderStatus, PaymentEvent,
+    UserEvent,
+};
+pub use upcast::{INITIAL_SCHEMA_VERSION, UpcastError, Upcaster, UpcasterRegistry};
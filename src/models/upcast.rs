@@ -0,0 +1,207 @@
+//! Event schema versioning and upcasting.
+//!
+//! Every [`Event`](super::Event) carries a `schema_version` (1 when absent,
+//! so events stored before versioning read as v1). When a payload shape
+//! changes, bump the version for new events and register an upcaster that
+//! turns a vN payload of that event type into its vN+1 shape. A poll with
+//! `target_version` runs the stored payload through each step up to the
+//! target, so events written under old shapes stay consumable.
+//!
+//! Upcasters work on the raw JSON payload (`{"type": ..., "data": ...}`)
+//! before it is deserialized, so an old shape that no longer matches the
+//! typed payload can still be rewritten into one that does.
+//!
+//! Events already at or above the target are left as stored; there is no
+//! downcasting.
+//!
+//! # Example
+//!
+//! ```rust
+//! use iggy_sample::models::UpcasterRegistry;
+//!
+//! let mut upcasters = UpcasterRegistry::new();
+//! // v2 of `user.created` renamed `name` to `full_name`
+//! upcasters.register("user.created", 1, |mut payload| {
+//!     let data = payload
+//!         .get_mut("data")
+//!         .and_then(|data| data.as_object_mut())
+//!         .ok_or("payload has no data object")?;
+//!     if let Some(name) = data.remove("name") {
+//!         data.insert("full_name".to_string(), name);
+//!     }
+//!     Ok(payload)
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// Schema version of events that do not carry one.
+pub const INITIAL_SCHEMA_VERSION: u32 = 1;
+
+/// Transformation of one event type's payload from vN to vN+1.
+pub type Upcaster = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// An event could not be brought up to the requested version.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("cannot upcast '{event_type}' from v{schema_version}: {reason}")]
+pub struct UpcastError {
+    /// Event type
+    pub event_type: String,
+    /// Version the event was left at (the steps before the failure applied)
+    pub schema_version: u32,
+    /// Why the next step failed
+    pub reason: String,
+}
+
+/// Upcasters keyed by event type and the version they upgrade from.
+#[derive(Default)]
+pub struct UpcasterRegistry {
+    steps: HashMap<(String, u32), Upcaster>,
+}
+
+impl UpcasterRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the `from_version` → `from_version + 1` step for
+    /// `event_type` payloads, replacing any previous one.
+    pub fn register<F>(&mut self, event_type: &str, from_version: u32, upcaster: F) -> &mut Self
+    where
+        F: Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.steps
+            .insert((event_type.to_string(), from_version), Box::new(upcaster));
+        self
+    }
+
+    /// Number of registered steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// `true` when no step is registered.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Upcast a serialized event (the whole JSON object) in place to
+    /// `target_version`, updating its `schema_version` after every step.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`UpcastError`] when a step is missing or fails; the
+    /// event is left at the last version reached.
+    pub fn upcast(&self, event: &mut Value, target_version: u32) -> Result<(), UpcastError> {
+        let mut version = schema_version_of(event);
+        let event_type = event
+            .get("event_type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let fail = |version: u32, reason: String| UpcastError {
+            event_type: event_type.clone(),
+            schema_version: version,
+            reason,
+        };
+        let Some(fields) = event.as_object_mut() else {
+            return Err(fail(version, "event is not a JSON object".to_string()));
+        };
+
+        while version < target_version {
+            let step = self
+                .steps
+                .get(&(event_type.clone(), version))
+                .ok_or_else(|| fail(version, format!("no upcaster to v{}", version + 1)))?;
+            let payload = fields.get("payload").cloned().unwrap_or_default();
+            let upcast = step(payload).map_err(|reason| fail(version, reason))?;
+            version += 1;
+            fields.insert("payload".to_string(), upcast);
+            fields.insert("schema_version".to_string(), Value::from(version));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for UpcasterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut steps: Vec<_> = self.steps.keys().collect();
+        steps.sort();
+        f.debug_struct("UpcasterRegistry")
+            .field("steps", &steps)
+            .finish()
+    }
+}
+
+/// `schema_version` of a serialized event ([`INITIAL_SCHEMA_VERSION`] when
+/// absent or not a number).
+fn schema_version_of(event: &Value) -> u32 {
+    event
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(INITIAL_SCHEMA_VERSION)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registry() -> UpcasterRegistry {
+        let mut registry = UpcasterRegistry::new();
+        registry
+            .register("thing.made", 1, |mut payload| {
+                payload["data"]["size"] = json!("large");
+                Ok(payload)
+            })
+            .register("thing.made", 2, |mut payload| {
+                let size = payload["data"]["size"].take();
+                payload["data"]["dimensions"] = json!({ "size": size });
+                Ok(payload)
+            })
+            .register("thing.made", 3, |_| Err("v4 is not out yet".to_string()));
+        registry
+    }
+
+    #[test]
+    fn test_upcast_applies_each_step_to_target() {
+        let mut event = json!({
+            "event_type": "thing.made",
+            "payload": { "type": "Generic", "data": {} }
+        });
+        registry().upcast(&mut event, 3).unwrap();
+        assert_eq!(event["schema_version"], 3);
+        assert_eq!(event["payload"]["data"]["dimensions"]["size"], "large");
+
+        // Already at (or past) the target: untouched
+        let before = event.clone();
+        registry().upcast(&mut event, 2).unwrap();
+        assert_eq!(event, before);
+    }
+
+    #[test]
+    fn test_upcast_failure_leaves_last_version_reached() {
+        let mut event = json!({
+            "event_type": "thing.made",
+            "schema_version": 2,
+            "payload": { "type": "Generic", "data": { "size": "small" } }
+        });
+        let err = registry().upcast(&mut event, 5).unwrap_err();
+        assert_eq!(err.schema_version, 3);
+        assert_eq!(err.reason, "v4 is not out yet");
+        assert_eq!(event["schema_version"], 3);
+        assert_eq!(event["payload"]["data"]["dimensions"]["size"], "small");
+
+        let mut other = json!({ "event_type": "other", "payload": {} });
+        let err = registry().upcast(&mut other, 2).unwrap_err();
+        assert_eq!(err.schema_version, 1);
+        assert!(err.reason.contains("no upcaster"));
+    }
+}
@@ -7,17 +7,47 @@
 //!    │
 //!    ▼
 //! ┌──────────────────┐
+//! │   Request ID     │ ← Adds X-Request-Id header, request span, error body `request_id`
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
+//! │    IP Filter     │ ← 403 if denylisted or not allowlisted (if IP_ALLOWLIST/IP_DENYLIST)
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
 //! │  Rate Limiting   │ ← 429 if exceeded
 //! └────────┬─────────┘
 //!          │
 //!          ▼
 //! ┌──────────────────┐
+//! │  Slow Requests   │ ← WARN with time breakdown (if SLOW_REQUEST_THRESHOLD_MS)
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
 //! │  Authentication  │ ← 401 if invalid (bypassed for /health, /ready)
 //! └────────┬─────────┘
 //!          │
 //!          ▼
 //! ┌──────────────────┐
-//! │   Request ID     │ ← Adds X-Request-Id header
+//! │  Load Shedding   │ ← 503 if MAX_IN_FLIGHT_REQUESTS are in flight
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
+//! │    Client IP     │ ← Resolves the client IP (audit log, top talkers), tenant
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
+//! │  Payload Sizes   │ ← Body size histograms, top-talkers tally
+//! └────────┬─────────┘
+//!          │
+//!          ▼
+//! ┌──────────────────┐
+//! │      Chaos       │ ← Injected latency/errors/disconnects (chaos feature)
 //! └────────┬─────────┘
 //!          │
 //!          ▼
@@ -50,21 +80,49 @@
 //! - `/messages` - Message operations on default stream/topic
 //! - `/streams` - Stream management
 //! - `/streams/{stream}/topics` - Topic management
+//! - `/consumers` - Consumers seen polling through this instance
+//! - `/scheduled` - Messages held for delayed delivery
+//! - `/schedules` - Recurring (cron) producers
+//! - `/pipelines` - Filtered, transformed copies of one topic into another
+//! - `/admin` - Backing Iggy server administration (`/admin/users`,
+//!   `/admin/audit`, `/admin/top-talkers`, `/admin/tap`,
+//!   `/admin/benchmark`, `/admin/internals`, `/admin/snapshot`,
+//!   `/admin/restore`, `/admin/chaos` and `/admin/wasm-modules` also
+//!   require the admin scope)
+//!
+//! With `ADMIN_PORT` set, `/admin` and stream/topic `DELETE` are only
+//! served by the admin listener's router, [`build_admin_router`].
+//!
+//! # Endpoint Groups
+//!
+//! `ENABLE_PRODUCE_API` (sends, `/scheduled`, `/schedules`),
+//! `ENABLE_CONSUME_API` (polls, peeks, search, profiles, `/consumers`, acks)
+//! and `ENABLE_ADMIN_API` (the admin routes above) turn a group off, for a
+//! produce-only or consume-only deployment. A disabled group's routes stay
+//! in the router but answer 404, naming the flag, before their handler
+//! runs. Health, listings, pipelines and stream/topic management are always
+//! served; `GET /health` reports the enabled groups under `features`.
 
 use std::sync::Arc;
 
 use axum::Router;
-use axum::extract::DefaultBodyLimit;
-use axum::middleware;
-use axum::routing::{delete, get, post};
-use tower_http::cors::{Any, CorsLayer};
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::{HeaderName, HeaderValue, Method};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, patch, post, put};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
+use crate::config::Config;
+use crate::error::AppError;
 use crate::handlers;
 use crate::middleware::{
-    ApiKeyAuth, RateLimitError, RateLimitLayer, RequestIdLayer, TrustedProxyConfig,
-    extract_request_timeout,
+    AdminScope, ApiKeyAuth, IpFilter, LoadShedLayer, RateLimitError, RateLimitLayer, RateLimitMode,
+    RequestIdLayer, RequestSigning, TenantScope, TrustedProxyConfig, extract_request_timeout,
+    filter_ips, log_slow_requests, mark_authenticated, record_client_ip, record_payload_sizes,
+    replay_idempotent, require_admin_scope, scope_tenant,
 };
 use crate::state::AppState;
 
@@ -74,8 +132,11 @@ use crate::state::AppState;
 ///
 /// Middleware is configured based on the application config:
 ///
-/// - **Rate Limiting**: Enabled if `rate_limit_rps > 0`
-/// - **Authentication**: Enabled if `api_key` is set
+/// - **Rate Limiting**: Enabled if `rate_limit_rps > 0`, reduced to
+///   `adaptive_rate_limit_percent` while Iggy is degraded (if set)
+/// - **Load Shedding**: Enabled if `max_in_flight_requests > 0`
+/// - **IP Filter**: Enabled if `ip_allowlist` or `ip_denylist` is set
+/// - **Authentication**: Enabled if `api_key` or `request_signing_secret` is set
 /// - **CORS**: Configured from `cors_allowed_origins`
 ///
 /// # Arguments
@@ -88,15 +149,11 @@ use crate::state::AppState;
 ///
 /// # Errors
 ///
-/// Returns `RateLimitError` if rate limiting configuration is invalid.
+/// Returns `RateLimitError` if the rate limiting, trusted proxy or IP
+/// filter configuration is invalid.
 pub fn build_router(state: AppState) -> Result<Router, RateLimitError> {
     let config = &state.config;
 
-    // =========================================================================
-    // CORS Configuration
-    // =========================================================================
-    let cors = build_cors_layer(&config.cors_allowed_origins);
-
     // =========================================================================
     // Build Router with Routes
     // =========================================================================
@@ -105,32 +162,330 @@ pub fn build_router(state: AppState) -> Result<Router, RateLimitError> {
         .route("/health", get(handlers::health_check))
         .route("/ready", get(handlers::readiness_check))
         .route("/stats", get(handlers::stats))
-        // Message endpoints (default stream/topic)
-        .route("/messages", post(handlers::send_message))
-        .route("/messages", get(handlers::poll_messages))
-        .route("/messages/batch", post(handlers::send_batch))
-        // Message endpoints (specific stream/topic)
+        // Event catalog
+        .route("/event-types", get(handlers::list_event_types))
+        // Rolling event counts
+        .route("/analytics/event-counts", get(handlers::event_counts))
+        // Pipeline endpoints
+        .route("/pipelines", post(handlers::create_pipeline))
+        .route("/pipelines", get(handlers::list_pipelines))
+        .route("/pipelines/{name}", get(handlers::get_pipeline))
+        .route("/pipelines/{name}", delete(handlers::delete_pipeline))
+        .route("/pipelines/{name}/pause", post(handlers::pause_pipeline))
+        .route("/pipelines/{name}/resume", post(handlers::resume_pipeline))
+        // Stream management endpoints
+        .route("/streams", get(handlers::list_streams))
+        .route("/streams/{name}", get(handlers::get_stream))
+        .route("/streams/{name}", patch(handlers::rename_stream))
+        .route("/streams/{name}/stats", get(handlers::stream_stats))
+        // Topic management endpoints
+        .route("/streams/{stream}/topics", get(handlers::list_topics))
+        .route("/streams/{stream}/topics/{topic}", get(handlers::get_topic))
+        .route(
+            "/streams/{stream}/topics/{topic}",
+            patch(handlers::rename_topic),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/stats",
+            get(handlers::topic_stats),
+        );
+
+    // =========================================================================
+    // Idempotent Routes
+    // =========================================================================
+    // Sends and stream/topic creation replay their first response to retries
+    // carrying the same Idempotency-Key (see middleware::idempotency).
+    if config.idempotency_enabled() {
+        info!(
+            ttl_secs = config.idempotency_ttl.as_secs(),
+            max_keys = config.idempotency_max_keys,
+            "Idempotency-Key replay enabled"
+        );
+    } else {
+        info!("Idempotency-Key replay disabled (IDEMPOTENCY_TTL_SECS=0)");
+    }
+    let idempotent = |routes: Router<AppState>| {
+        if config.idempotency_enabled() {
+            routes.route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                replay_idempotent,
+            ))
+        } else {
+            routes
+        }
+    };
+    router = router.merge(idempotent(
+        Router::new()
+            .route("/streams", post(handlers::create_stream))
+            .route("/streams/{stream}/topics", post(handlers::create_topic))
+            .route(
+                "/streams/{stream}/topics/bulk",
+                post(handlers::create_topics_bulk),
+            ),
+    ));
+
+    // =========================================================================
+    // Produce API (ENABLE_PRODUCE_API)
+    // =========================================================================
+    let produce = Router::new()
         .route(
             "/streams/{stream}/topics/{topic}/messages",
             post(handlers::messages::send_message_to),
         )
+        // Delayed delivery endpoints
+        .route("/scheduled", get(handlers::list_scheduled))
+        .route("/scheduled/{id}", delete(handlers::cancel_scheduled))
+        // Recurring schedule endpoints
+        .route("/schedules", post(handlers::create_schedule))
+        .route("/schedules", get(handlers::list_schedules))
+        .route("/schedules/{name}", get(handlers::get_schedule))
+        .route("/schedules/{name}", delete(handlers::delete_schedule))
+        .route(
+            "/schedules/{name}/enabled",
+            put(handlers::set_schedule_enabled),
+        )
+        // Message endpoints (default stream/topic)
+        .merge(idempotent(
+            Router::new()
+                .route("/messages", post(handlers::send_message))
+                .route("/messages/batch", post(handlers::send_batch))
+                .route("/messages/fanout", post(handlers::send_fanout)),
+        ));
+    router = router.merge(api_group(
+        produce,
+        "ENABLE_PRODUCE_API",
+        config.enable_produce_api,
+    ));
+
+    // =========================================================================
+    // Consume API (ENABLE_CONSUME_API)
+    // =========================================================================
+    let consume = Router::new()
+        // Message endpoints (default stream/topic)
+        .route("/messages", get(handlers::poll_messages))
+        .route(
+            "/messages/by-id/{id}",
+            get(handlers::messages::message_by_id),
+        )
+        // Message endpoints (specific stream/topic)
         .route(
             "/streams/{stream}/topics/{topic}/messages",
             get(handlers::messages::poll_messages_from),
         )
-        // Stream management endpoints
-        .route("/streams", get(handlers::list_streams))
-        .route("/streams", post(handlers::create_stream))
-        .route("/streams/{name}", get(handlers::get_stream))
+        .route(
+            "/streams/{stream}/topics/{topic}/messages/peek",
+            get(handlers::messages::peek_messages),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/search",
+            get(handlers::messages::search_messages),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/partitions/{partition}/messages/{offset}",
+            get(handlers::messages::message_at_offset),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/profile",
+            get(handlers::topic_profile),
+        )
+        // Consumer monitoring endpoints
+        .route("/consumers", get(handlers::list_consumers))
+        .route(
+            "/streams/{stream}/topics/{topic}/consumers/{id}/lag",
+            get(handlers::consumer_lag),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/consumers/{id}/ack",
+            post(handlers::ack_messages),
+        )
+        .route(
+            "/streams/{stream}/topics/{topic}/consumers/{id}/nack",
+            post(handlers::nack_messages),
+        );
+    router = router.merge(api_group(
+        consume,
+        "ENABLE_CONSUME_API",
+        config.enable_consume_api,
+    ));
+
+    // =========================================================================
+    // Admin Routes (ENABLE_ADMIN_API)
+    // =========================================================================
+    // With ADMIN_PORT set, these are only served by the admin listener (see
+    // build_admin_router).
+    if !config.admin_listener_enabled() {
+        router = router.merge(api_group(
+            admin_routes(config),
+            "ENABLE_ADMIN_API",
+            config.enable_admin_api,
+        ));
+    }
+
+    let (router, rate_limit) = with_middleware(router, &state)?;
+    if let Some(rate_limit) = &rate_limit {
+        state.track_rate_limiter(rate_limit);
+    }
+    Ok(router.with_state(state))
+}
+
+/// Build the router of the admin listener: `/admin/*` and stream and topic
+/// deletion, behind the same middleware stack as the public router.
+///
+/// Returns `None` unless `ADMIN_PORT` is set; [`build_router`] then serves
+/// these routes itself. The admin listener has its own rate limit buckets.
+///
+/// # Errors
+///
+/// Returns `RateLimitError` if the rate limiting, trusted proxy or IP
+/// filter configuration is invalid.
+pub fn build_admin_router(state: AppState) -> Result<Option<Router>, RateLimitError> {
+    if !state.config.admin_listener_enabled() {
+        return Ok(None);
+    }
+    let (router, _) = with_middleware(admin_routes(&state.config), &state)?;
+    Ok(Some(router.with_state(state)))
+}
+
+/// Admin passthrough, admin-scoped and destructive routes.
+fn admin_routes(config: &Config) -> Router<AppState> {
+    // Credential management, the audit log, the top-talkers report (client
+    // IPs), the ordering report (partition keys), the message tap (payloads),
+    // load tests and the internal counters require X-Admin-Key in addition
+    // to the API key; with no ADMIN_API_KEY configured they fail closed with
+    // 403. route_layer scopes the check to the admin routes only.
+    let admin_scope = AdminScope::new(config.admin_api_key.clone());
+    if admin_scope.is_enabled() {
+        info!("Admin user management and audit endpoints enabled");
+    } else {
+        info!("Admin user management and audit endpoints disabled (no ADMIN_API_KEY set)");
+    }
+    let admin_users = Router::new()
+        .route("/admin/users", get(handlers::list_users))
+        .route("/admin/users", post(handlers::create_user))
+        .route("/admin/users/{username}", get(handlers::get_user))
+        .route("/admin/users/{username}", delete(handlers::delete_user))
+        .route(
+            "/admin/users/{username}/permissions",
+            put(handlers::update_user_permissions),
+        )
+        .route(
+            "/admin/users/{username}/password",
+            put(handlers::change_user_password),
+        )
+        .route("/admin/audit", get(handlers::admin::audit_log))
+        .route("/admin/top-talkers", get(handlers::admin::top_talkers))
+        .route(
+            "/admin/ordering-report",
+            get(handlers::admin::ordering_report),
+        )
+        .route("/admin/tap", get(handlers::admin::tap))
+        .route("/admin/benchmark", post(handlers::admin::benchmark))
+        .route("/admin/internals", get(handlers::admin::internals))
+        .route("/admin/snapshot", post(handlers::admin::snapshot))
+        .route("/admin/restore", post(handlers::admin::restore));
+    #[cfg(feature = "chaos")]
+    let admin_users = admin_users
+        .route("/admin/chaos", get(handlers::admin::get_chaos))
+        .route("/admin/chaos", post(handlers::admin::set_chaos));
+    #[cfg(feature = "wasm")]
+    let admin_users = admin_users
+        .route(
+            "/admin/wasm-modules",
+            get(handlers::admin::list_wasm_modules),
+        )
+        .route(
+            "/admin/wasm-modules/{name}",
+            get(handlers::admin::get_wasm_module),
+        )
+        // Modules may be larger than MAX_REQUEST_BODY_SIZE
+        .route(
+            "/admin/wasm-modules/{name}",
+            put(handlers::admin::upload_wasm_module)
+                .layer(DefaultBodyLimit::max(config.wasm_max_module_bytes)),
+        )
+        .route(
+            "/admin/wasm-modules/{name}",
+            delete(handlers::admin::delete_wasm_module),
+        )
+        .route(
+            "/admin/wasm-modules/{name}/dry-run",
+            post(handlers::admin::dry_run_wasm_module),
+        );
+    let admin_users = admin_users.route_layer(middleware::from_fn_with_state(
+        admin_scope.clone(),
+        require_admin_scope,
+    ));
+
+    let passthrough = Router::new()
+        // Admin passthrough endpoints (backing Iggy server)
+        .route("/admin/server-info", get(handlers::admin::server_info))
+        .route(
+            "/admin/bootstrap/status",
+            get(handlers::admin::bootstrap_status),
+        )
+        .route("/admin/retention", get(handlers::admin::retention_status))
+        .route(
+            "/admin/replication",
+            get(handlers::admin::replication_status),
+        )
+        .route("/admin/migrations", get(handlers::admin::migrations))
+        // Destructive stream and topic operations
         .route("/streams/{name}", delete(handlers::delete_stream))
-        // Topic management endpoints
-        .route("/streams/{stream}/topics", get(handlers::list_topics))
-        .route("/streams/{stream}/topics", post(handlers::create_topic))
-        .route("/streams/{stream}/topics/{topic}", get(handlers::get_topic))
         .route(
             "/streams/{stream}/topics/{topic}",
             delete(handlers::delete_topic),
         );
+    // With ADMIN_API_KEY set, every admin route needs X-Admin-Key. Without
+    // it the routes above stay behind the API key alone, which on the public
+    // port of an authenticated deployment every producer holds.
+    let passthrough = if admin_scope.is_enabled() {
+        passthrough.route_layer(middleware::from_fn_with_state(
+            admin_scope,
+            require_admin_scope,
+        ))
+    } else {
+        if config.auth_enabled() && !config.admin_listener_enabled() && config.enable_admin_api {
+            warn!(
+                "Stream and topic DELETE and the admin passthrough routes are served on the \
+                 public port behind the API key alone: set ADMIN_PORT or ADMIN_API_KEY to \
+                 restrict them"
+            );
+        }
+        passthrough
+    };
+
+    passthrough.merge(admin_users)
+}
+
+/// Serve the routes of an endpoint group as they are when `enabled`, or
+/// answer each of them with 404 when its `flag` turned it off.
+fn api_group(routes: Router<AppState>, flag: &'static str, enabled: bool) -> Router<AppState> {
+    if enabled {
+        return routes;
+    }
+    info!("{flag}=false: these endpoints answer 404");
+    routes.route_layer(middleware::from_fn_with_state(flag, disabled_route))
+}
+
+/// Answer a route of a disabled endpoint group, before its handler runs.
+async fn disabled_route(State(flag): State<&'static str>, request: Request, _: Next) -> Response {
+    AppError::NotFound(format!(
+        "{} {} is disabled ({flag}=false)",
+        request.method(),
+        request.uri().path()
+    ))
+    .into_response()
+}
+
+/// Apply the middleware stack to `router`, returning it with its rate
+/// limiter (if enabled) for the internal counters.
+fn with_middleware(
+    mut router: Router<AppState>,
+    state: &AppState,
+) -> Result<(Router<AppState>, Option<RateLimitLayer>), RateLimitError> {
+    let config = &state.config;
+    let cors = build_cors_layer(config);
+    let mut tracked_rate_limit = None;
 
     // =========================================================================
     // Apply Middleware Stack (order matters - applied bottom to top)
@@ -153,20 +508,102 @@ pub fn build_router(state: AppState) -> Result<Router, RateLimitError> {
     // Extracts X-Request-Timeout header and stores in request extensions
     router = router.layer(middleware::from_fn(extract_request_timeout));
 
-    // 5. Request ID
-    router = router.layer(RequestIdLayer::new());
+    // 5. Chaos injection (chaos feature) - inside the request ID, so
+    //    injected errors carry one
+    #[cfg(feature = "chaos")]
+    {
+        tracing::warn!("Chaos injection compiled in; rules are set via POST /admin/chaos");
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::inject_chaos,
+        ));
+    }
 
-    // Trusted proxy configuration is shared by auth (brute-force tracking)
-    // and rate limiting; invalid entries fail startup rather than silently
-    // degrading to trust-all.
+    // Trusted proxy configuration is shared by auth (brute-force tracking),
+    // rate limiting and the client IP of the audit log and top talkers;
+    // invalid entries fail startup rather than silently degrading to
+    // trust-all.
     let trusted_proxies = Arc::new(TrustedProxyConfig::try_new(&config.trusted_proxies)?);
 
-    // 6. Authentication (if enabled)
-    let auth_layer = ApiKeyAuth::with_trusted_proxies(
+    // 6. Payload sizes (if metrics or top talkers are enabled) - inside the
+    //    client IP layer, which it reads
+    if config.metrics_enabled() || config.top_talkers_enabled() {
+        router = router.layer(middleware::from_fn_with_state(
+            state.top_talkers.clone(),
+            record_payload_sizes,
+        ));
+    }
+    if config.top_talkers_enabled() {
+        info!(
+            limit = config.top_talkers_limit,
+            window_secs = config.top_talkers_window.as_secs(),
+            "Top talkers report enabled"
+        );
+    } else {
+        info!("Top talkers report disabled (TOP_TALKERS_LIMIT=0)");
+    }
+
+    // 7. Client IP (if the audit log or top talkers are enabled) - resolved
+    //    once for the handlers and middleware that record it
+    if config.audit_enabled {
+        info!(topic = %config.audit_topic, "Audit log enabled");
+    } else {
+        info!("Audit log disabled (AUDIT_ENABLED=false)");
+    }
+    if config.audit_enabled || config.top_talkers_enabled() {
+        router = router.layer(middleware::from_fn_with_state(
+            trusted_proxies.clone(),
+            record_client_ip,
+        ));
+    }
+    //    The tenant (if fair queuing is enabled) is resolved alongside it,
+    //    scoping the request's Iggy operations
+    if config.fair_queue_enabled() {
+        info!(
+            max_in_flight = config.fair_queue_max_in_flight,
+            tenant_max_in_flight = config.fair_queue_tenant_max_in_flight,
+            weighted_tenants = config.fair_queue_weights.len(),
+            "Fair queuing of Iggy operations enabled"
+        );
+        router = router.layer(middleware::from_fn_with_state(
+            Arc::new(TenantScope::new(config, trusted_proxies.clone())),
+            scope_tenant,
+        ));
+    }
+
+    // 8. Load shedding (if enabled) - inside auth and rate limiting, so
+    //    rejected requests never take an in-flight slot
+    if config.load_shedding_enabled() {
+        info!(
+            max_in_flight = config.max_in_flight_requests,
+            "Load shedding enabled"
+        );
+        router = router.layer(LoadShedLayer::new(config.max_in_flight_requests));
+    }
+
+    // 9. End of authentication, for the slow request breakdown
+    if config.slow_request_logging_enabled() {
+        router = router.layer(middleware::from_fn(mark_authenticated));
+    }
+
+    // 10. Authentication (if enabled)
+    let mut auth_layer = ApiKeyAuth::with_trusted_proxies(
         config.api_key.clone(),
         config.auth_bypass_paths.clone(),
         trusted_proxies.clone(),
     );
+    if let Some(secret) = &config.request_signing_secret {
+        info!(
+            max_age_secs = config.signature_max_age.as_secs(),
+            "Request signing enabled"
+        );
+        auth_layer = auth_layer.with_request_signing(RequestSigning::new(
+            secret.clone(),
+            config.signature_max_age,
+            config.max_request_body_size,
+            config.signature_max_seen,
+        ));
+    }
     if auth_layer.is_enabled() {
         info!("API key authentication enabled");
         router = router.layer(auth_layer);
@@ -174,8 +611,21 @@ pub fn build_router(state: AppState) -> Result<Router, RateLimitError> {
         info!("API key authentication disabled (no API_KEY set)");
     }
 
-    // 7. Rate Limiting (if enabled) - applied last, so it runs FIRST on
-    //    incoming requests (outermost layer), before auth ever sees them
+    // 11. Slow request logging (if enabled) - just outside auth, so the
+    //     breakdown can tell auth time apart from handler time
+    if config.slow_request_logging_enabled() {
+        info!(
+            threshold_ms = config.slow_request_threshold.as_millis() as u64,
+            "Slow request logging enabled"
+        );
+        router = router.layer(middleware::from_fn_with_state(
+            config.slow_request_threshold,
+            log_slow_requests,
+        ));
+    }
+
+    // 12. Rate Limiting (if enabled) - applied after auth, so it runs
+    //     before auth ever sees incoming requests
     if config.rate_limiting_enabled() {
         info!(
             rps = config.rate_limit_rps,
@@ -183,84 +633,204 @@ pub fn build_router(state: AppState) -> Result<Router, RateLimitError> {
             trusted_proxies = config.trusted_proxies.len(),
             "Rate limiting enabled"
         );
-        router = router.layer(RateLimitLayer::with_trusted_proxies(
+        let mut rate_limit = RateLimitLayer::with_trusted_proxies(
             config.rate_limit_rps,
             config.rate_limit_burst,
-            trusted_proxies,
-        )?);
+            trusted_proxies.clone(),
+        )?;
+        for (class, rps) in config.rate_limit_classes() {
+            info!(?class, rps, "Route class rate limit configured");
+            rate_limit = rate_limit.with_class_limit(class, rps)?;
+        }
+        if config.rate_limit_mode == RateLimitMode::Queue {
+            info!(
+                max_wait_ms = config.queue_max_wait.as_millis() as u64,
+                "Rate limit queue mode enabled"
+            );
+            rate_limit = rate_limit.with_queueing(config.queue_max_wait);
+        }
+        if config.adaptive_rate_limiting_enabled() {
+            info!(
+                percent = config.adaptive_rate_limit_percent,
+                "Adaptive rate limiting enabled while Iggy is degraded"
+            );
+            rate_limit = rate_limit.with_adaptive_limit(
+                state.iggy_client.health_signal(),
+                config.adaptive_rate_limit_percent,
+            );
+        }
+        router = router.layer(rate_limit.clone());
+        tracked_rate_limit = Some(rate_limit);
     } else {
         info!("Rate limiting disabled (RATE_LIMIT_RPS=0)");
     }
 
-    // Add state
-    Ok(router.with_state(state))
+    // 13. IP allow/deny lists (if set) - outside the rate limiter and auth,
+    //     so rejected clients never reach them
+    let ip_filter = IpFilter::try_new(
+        &config.ip_allowlist,
+        &config.ip_denylist,
+        config.ip_filter_paths.clone(),
+        trusted_proxies,
+    )?;
+    if ip_filter.is_enabled() {
+        info!(
+            allowlist = config.ip_allowlist.len(),
+            denylist = config.ip_denylist.len(),
+            paths = ?config.ip_filter_paths,
+            "IP filter enabled"
+        );
+        router = router.layer(middleware::from_fn_with_state(
+            Arc::new(ip_filter),
+            filter_ips,
+        ));
+    }
+
+    // 14. Request ID - outermost, so every response and error body carries
+    //     one, including rejections by the IP filter, rate limiter and auth
+    router = router.layer(RequestIdLayer::new());
+
+    Ok((router, tracked_rate_limit))
 }
 
 /// Build CORS layer from configuration.
 ///
-/// # Arguments
-///
-/// * `allowed_origins` - List of allowed origins, or `["*"]` for any origin
+/// Origins, methods and headers come from the `CORS_*` settings, which
+/// `Config::validate` has already checked. With `CORS_ADMIN_ALLOWED_ORIGINS`
+/// set, `/admin` routes answer only those origins and every other route the
+/// public list.
 ///
 /// # Security Note
 ///
 /// Using `*` (any origin) is convenient for development but should be
 /// avoided in production. Specify explicit origins instead.
-fn build_cors_layer(allowed_origins: &[String]) -> CorsLayer {
-    // Check if we should allow any origin
-    let allow_any = allowed_origins.iter().any(|o| o == "*");
-
-    if allow_any {
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any)
-    } else {
-        // Parse specific origins; dropped entries are surfaced instead of
-        // silently vanishing (an all-invalid list fails closed - no origins
-        // allowed - which would otherwise look like a mystery CORS failure).
-        let origins: Vec<_> = allowed_origins
-            .iter()
-            .filter_map(|o| match o.parse() {
-                Ok(origin) => Some(origin),
-                Err(_) => {
-                    warn!(origin = %o, "Ignoring invalid CORS_ALLOWED_ORIGINS entry");
-                    None
+fn build_cors_layer(config: &Config) -> CorsLayer {
+    let public = CorsOrigins::new(&config.cors_allowed_origins);
+    let allow_origin = match &config.cors_admin_allowed_origins {
+        None => public.into_allow_origin(),
+        Some(admin) => {
+            let admin = CorsOrigins::new(admin);
+            AllowOrigin::predicate(move |origin, parts| {
+                if is_admin_path(parts.uri.path()) {
+                    admin.allows(origin)
+                } else {
+                    public.allows(origin)
                 }
             })
-            .collect();
+        }
+    };
 
-        if origins.is_empty() {
-            warn!(
-                "CORS_ALLOWED_ORIGINS contained no valid origins; all cross-origin requests will be rejected"
-            );
+    // tower-http refuses `Any` together with credentials; mirroring the
+    // preflight request allows the same without the wildcard.
+    let credentials = config.cors_allow_credentials;
+    let allow_methods = if config.cors_allowed_methods.is_empty() {
+        if credentials {
+            AllowMethods::mirror_request()
+        } else {
+            AllowMethods::any()
+        }
+    } else {
+        AllowMethods::list(
+            config
+                .cors_allowed_methods
+                .iter()
+                .filter_map(|m| Method::from_bytes(m.as_bytes()).ok()),
+        )
+    };
+    let allow_headers = if config.cors_allowed_headers.is_empty() {
+        if credentials {
+            AllowHeaders::mirror_request()
+        } else {
+            AllowHeaders::any()
+        }
+    } else {
+        AllowHeaders::list(
+            config
+                .cors_allowed_headers
+                .iter()
+                .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok()),
+        )
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .allow_credentials(credentials)
+}
+
+/// An origin policy: any origin, or an explicit list.
+#[derive(Clone)]
+enum CorsOrigins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+impl CorsOrigins {
+    fn new(origins: &[String]) -> Self {
+        if origins.iter().any(|o| o == "*") {
+            Self::Any
+        } else {
+            Self::List(origins.iter().filter_map(|o| o.parse().ok()).collect())
         }
+    }
 
-        CorsLayer::new()
-            .allow_origin(origins)
-            .allow_methods(Any)
-            .allow_headers(Any)
+    fn allows(&self, origin: &HeaderValue) -> bool {
+        match self {
+            Self::Any => true,
+            Self::List(origins) => origins.contains(origin),
+        }
+    }
+
+    fn into_allow_origin(self) -> AllowOrigin {
+        match self {
+            Self::Any => AllowOrigin::any(),
+            Self::List(origins) => AllowOrigin::list(origins),
+        }
     }
 }
 
+/// Whether `path` is `/admin` or under it.
+fn is_admin_path(path: &str) -> bool {
+    path.strip_prefix("/admin")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_build_cors_layer_any() {
-        let origins = vec!["*".to_string()];
-        let _layer = build_cors_layer(&origins);
+        let _layer = build_cors_layer(&Config::default());
         // Just verify it doesn't panic
     }
 
     #[test]
     fn test_build_cors_layer_specific() {
-        let origins = vec![
-            "https://example.com".to_string(),
-            "https://app.example.com".to_string(),
-        ];
-        let _layer = build_cors_layer(&origins);
-        // Just verify it doesn't panic
+        let config = Config {
+            cors_allowed_origins: vec![
+                "https://example.com".to_string(),
+                "https://app.example.com".to_string(),
+            ],
+            cors_admin_allowed_origins: Some(vec![]),
+            cors_allow_credentials: true,
+            ..Config::default()
+        };
+        let _layer = build_cors_layer(&config);
+        // Just verify it doesn't panic (credentials with `Any` would)
+    }
+
+    #[test]
+    fn test_cors_origin_policies() {
+        let origin = HeaderValue::from_static("https://app.example.com");
+        assert!(CorsOrigins::new(&["*".to_string()]).allows(&origin));
+        assert!(CorsOrigins::new(&["https://app.example.com".to_string()]).allows(&origin));
+        assert!(!CorsOrigins::new(&[]).allows(&origin));
+
+        assert!(is_admin_path("/admin"));
+        assert!(is_admin_path("/admin/audit"));
+        assert!(!is_admin_path("/administrator"));
+        assert!(!is_admin_path("/messages"));
     }
 }
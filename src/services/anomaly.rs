@@ -0,0 +1,338 @@
+//! Throughput anomaly detection on the stats cache.
+//!
+//! With `THROUGHPUT_ANOMALY_THRESHOLD` set, each stats refresh derives the
+//! message rate of every topic from the change in its message count, and
+//! compares it with an exponentially weighted moving average (EWMA) of the
+//! topic's past rates (weight `THROUGHPUT_ANOMALY_ALPHA` on the newest):
+//!
+//! - `drop` - the rate is more than the threshold's number of standard
+//!   deviations below the average (a producer stopped or slowed down)
+//! - `spike` - the rate is as far above it (a retry storm, a backfill)
+//!
+//! An anomaly is logged at WARN and handed to the registered
+//! [`AnomalyObserver`]s (the webhook notifier, see `NOTIFY_WEBHOOK_URL`)
+//! when it starts, listed in `anomalies` by `GET /stats` while it lasts,
+//! and logged at INFO when it clears. The average keeps adapting
+//! meanwhile, so a lasting change in throughput becomes the new normal.
+//!
+//! # Limits
+//!
+//! Rates are only as fine as `STATS_CACHE_TTL_SECS`. A topic is judged
+//! after [`WARMUP_SAMPLES`] refreshes, and deviations are measured against
+//! at least [`MIN_STD_DEV`] messages per second, so a quiet topic does not
+//! alarm on every message. Purges make the count go down; that refresh is
+//! skipped. State is in-memory and per-instance.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::Instant;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::models::{AnomalyKind, ThroughputAnomaly};
+
+/// Refreshes observed before a topic's rate is judged.
+pub const WARMUP_SAMPLES: u32 = 10;
+
+/// Smallest standard deviation deviations are measured against, in
+/// messages per second.
+pub const MIN_STD_DEV: f64 = 1.0;
+
+/// Callbacks for throughput anomalies.
+pub trait AnomalyObserver: Send + Sync {
+    /// A topic's throughput started deviating (or flipped direction).
+    fn on_anomaly(&self, anomaly: &ThroughputAnomaly);
+}
+
+/// Rate history of one topic.
+#[derive(Debug)]
+struct TopicRate {
+    messages_count: u64,
+    observed_at: Instant,
+    samples: u32,
+    mean: f64,
+    variance: f64,
+    active: Option<ThroughputAnomaly>,
+}
+
+impl TopicRate {
+    fn new(messages_count: u64, now: Instant) -> Self {
+        Self {
+            messages_count,
+            observed_at: now,
+            samples: 0,
+            mean: 0.0,
+            variance: 0.0,
+            active: None,
+        }
+    }
+
+    /// Fold `rate` into the moving average and variance.
+    fn update(&mut self, rate: f64, alpha: f64) {
+        if self.samples == 0 {
+            self.mean = rate;
+        } else {
+            let diff = rate - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    /// Direction and size (in standard deviations) of `rate`'s deviation,
+    /// if beyond `threshold` once warmed up.
+    fn judge(&self, rate: f64, threshold: f64) -> Option<(AnomalyKind, f64)> {
+        if self.samples < WARMUP_SAMPLES {
+            return None;
+        }
+        let deviations = (rate - self.mean) / self.variance.sqrt().max(MIN_STD_DEV);
+        if deviations <= -threshold {
+            Some((AnomalyKind::Drop, deviations))
+        } else if deviations >= threshold {
+            Some((AnomalyKind::Spike, deviations))
+        } else {
+            None
+        }
+    }
+}
+
+/// Per-topic throughput baselines and the anomalies they currently show.
+pub struct ThroughputAnomalies {
+    /// Deviations that make an anomaly (0 = detection disabled)
+    threshold: f64,
+    alpha: f64,
+    topics: Mutex<HashMap<(String, String), TopicRate>>,
+    observers: RwLock<Vec<Arc<dyn AnomalyObserver>>>,
+}
+
+impl ThroughputAnomalies {
+    /// Create a detector flagging rates `threshold` standard deviations
+    /// from their EWMA of weight `alpha`; a zero threshold disables it.
+    pub fn new(threshold: f64, alpha: f64) -> Self {
+        Self {
+            threshold,
+            alpha,
+            topics: Mutex::new(HashMap::new()),
+            observers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Check if detection is enabled (`THROUGHPUT_ANOMALY_THRESHOLD` set).
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0.0
+    }
+
+    /// Register `observer` for every later anomaly.
+    pub fn observe(&self, observer: Arc<dyn AnomalyObserver>) {
+        self.observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(observer);
+    }
+
+    /// Anomalies in progress, ordered by stream and topic.
+    pub fn active(&self) -> Vec<ThroughputAnomaly> {
+        let mut active: Vec<_> = self
+            .lock()
+            .values()
+            .filter_map(|topic| topic.active.clone())
+            .collect();
+        active.sort_by(|a, b| (&a.stream, &a.topic).cmp(&(&b.stream, &b.topic)));
+        active
+    }
+
+    /// Judge the message count of each `(stream, topic, messages_count)`
+    /// of a refresh; a `None` count (its stream failed to refresh) keeps
+    /// the topic unjudged. Topics missing from `topics` are forgotten.
+    pub fn evaluate<'a>(&self, topics: impl IntoIterator<Item = (&'a str, &'a str, Option<u64>)>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let started = self.evaluate_at(topics, Instant::now());
+        if started.is_empty() {
+            return;
+        }
+        let observers = self
+            .observers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for anomaly in &started {
+            for observer in &observers {
+                observer.on_anomaly(anomaly);
+            }
+        }
+    }
+
+    /// Judge `topics` as of `now`, returning the anomalies that started.
+    fn evaluate_at<'a>(
+        &self,
+        topics: impl IntoIterator<Item = (&'a str, &'a str, Option<u64>)>,
+        now: Instant,
+    ) -> Vec<ThroughputAnomaly> {
+        let mut state = self.lock();
+        let mut seen = HashSet::with_capacity(state.len());
+        let mut started = Vec::new();
+
+        for (stream, topic, messages_count) in topics {
+            let key = (stream.to_string(), topic.to_string());
+            seen.insert(key.clone());
+            let Some(messages_count) = messages_count else {
+                continue;
+            };
+            let Some(rate) = state.get_mut(&key) else {
+                state.insert(key, TopicRate::new(messages_count, now));
+                continue;
+            };
+            let elapsed = now.duration_since(rate.observed_at).as_secs_f64();
+            if elapsed <= 0.0 {
+                continue;
+            }
+            let Some(appended) = messages_count.checked_sub(rate.messages_count) else {
+                // Purged: restart the count without judging the refresh
+                rate.messages_count = messages_count;
+                rate.observed_at = now;
+                continue;
+            };
+            let per_sec = appended as f64 / elapsed;
+            rate.messages_count = messages_count;
+            rate.observed_at = now;
+
+            match rate.judge(per_sec, self.threshold) {
+                Some((kind, deviations)) => {
+                    let expected_per_sec = rate.mean;
+                    match &mut rate.active {
+                        Some(active) if active.kind == kind => {
+                            active.rate_per_sec = per_sec;
+                            active.expected_per_sec = expected_per_sec;
+                            active.deviations = deviations;
+                        }
+                        active => {
+                            let anomaly = ThroughputAnomaly {
+                                stream: stream.to_string(),
+                                topic: topic.to_string(),
+                                kind,
+                                rate_per_sec: per_sec,
+                                expected_per_sec,
+                                deviations,
+                                since: Utc::now(),
+                            };
+                            warn!(
+                                stream,
+                                topic,
+                                kind = kind.as_str(),
+                                rate_per_sec = per_sec,
+                                expected_per_sec,
+                                deviations,
+                                "Throughput anomaly"
+                            );
+                            *active = Some(anomaly.clone());
+                            started.push(anomaly);
+                        }
+                    }
+                }
+                None => {
+                    if let Some(cleared) = rate.active.take() {
+                        info!(
+                            stream,
+                            topic,
+                            kind = cleared.kind.as_str(),
+                            rate_per_sec = per_sec,
+                            "Throughput anomaly cleared"
+                        );
+                    }
+                }
+            }
+            rate.update(per_sec, self.alpha);
+        }
+
+        state.retain(|key, _| seen.contains(key));
+        started
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), TopicRate>> {
+        self.topics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Feed `rates` (messages per second) one second apart, starting at a
+    /// count of 0, and return the anomalies started by each refresh.
+    fn feed(
+        detector: &ThroughputAnomalies,
+        start: Instant,
+        rates: &[u64],
+    ) -> Vec<Vec<ThroughputAnomaly>> {
+        let mut count = 0;
+        let mut results = vec![detector.evaluate_at([("s", "t", Some(count))], start)];
+        for (i, rate) in rates.iter().enumerate() {
+            count += rate;
+            let now = start + Duration::from_secs(i as u64 + 1);
+            results.push(detector.evaluate_at([("s", "t", Some(count))], now));
+        }
+        results
+    }
+
+    #[test]
+    fn test_drops_and_spikes_are_flagged_after_warmup() {
+        let detector = ThroughputAnomalies::new(3.0, 0.05);
+        let steady = [100, 102, 98, 101, 99, 100, 103, 97, 100, 101, 99, 100];
+        let start = Instant::now();
+        let results = feed(&detector, start, &steady);
+        assert!(results.iter().all(Vec::is_empty));
+        assert!(detector.active().is_empty());
+
+        let count: u64 = steady.iter().sum();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let started = detector.evaluate_at([("s", "t", Some(count + 5))], at(13));
+        assert_eq!(started.len(), 1);
+        let drop = started.first().unwrap();
+        assert_eq!(drop.kind, AnomalyKind::Drop);
+        assert!(drop.deviations <= -3.0);
+        assert!((drop.expected_per_sec - 100.0).abs() < 2.0);
+        assert_eq!(detector.active().len(), 1);
+
+        // Still dropping: reported, not started again
+        assert!(
+            detector
+                .evaluate_at([("s", "t", Some(count + 10))], at(14))
+                .is_empty()
+        );
+        assert_eq!(detector.active().len(), 1);
+
+        let started = detector.evaluate_at([("s", "t", Some(count + 10 + 1000))], at(15));
+        assert_eq!(started.first().unwrap().kind, AnomalyKind::Spike);
+    }
+
+    #[test]
+    fn test_purges_and_deleted_topics_reset_the_baseline() {
+        let detector = ThroughputAnomalies::new(3.0, 0.05);
+        let start = Instant::now();
+        feed(&detector, start, &[100; 12]);
+
+        // An unknown count (failed refresh) is not judged a drop
+        let later = start + Duration::from_secs(13);
+        assert!(detector.evaluate_at([("s", "t", None)], later).is_empty());
+        assert!(detector.lock().contains_key(&("s".into(), "t".into())));
+
+        // A purge lowers the count: skipped rather than judged a drop
+        let later = start + Duration::from_secs(14);
+        assert!(
+            detector
+                .evaluate_at([("s", "t", Some(10))], later)
+                .is_empty()
+        );
+        assert!(detector.active().is_empty());
+
+        detector.evaluate_at([("s", "other", Some(0))], later);
+        assert!(!detector.lock().contains_key(&("s".into(), "t".into())));
+    }
+}
@@ -0,0 +1,273 @@
+//! Audit log of administrative and destructive operations.
+//!
+//! Creating or deleting a stream, topic or Iggy user, and changing a user's
+//! permissions or password, appends an [`AuditEntry`] to the `AUDIT_TOPIC`
+//! (single partition, default stream): what was done to which resource, by
+//! which credential, from which client IP, under which request ID, and
+//! whether it succeeded. `GET /admin/audit` reads it back.
+//!
+//! Operations rejected by request validation are not recorded: they never
+//! reached Iggy.
+//!
+//! # Actor
+//!
+//! The service has no named API keys, so the actor is the strongest
+//! credential the caller presented: `admin` (a valid `X-Admin-Key`),
+//! `signed` (a valid `X-Signature`), `api_key` (authenticated with
+//! `API_KEY`), or `anonymous`.
+//!
+//! # Failure Handling
+//!
+//! Writing the entry happens after the operation and never changes its
+//! response: a failed write is logged and the entry is lost. The topic is
+//! created on the first write.
+
+use bytes::Bytes;
+use chrono::Utc;
+use iggy::prelude::Partitioning;
+use tokio::sync::OnceCell;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{IggyClientWrapper, PollParams, payload_message};
+use crate::models::{AuditAction, AuditEntry, AuditLogResponse, AuditOutcome, AuditQuery};
+
+/// The audit topic has a single partition; entries are read in write order.
+const AUDIT_PARTITION_ID: u32 = 0;
+
+/// Consumer ID used for audit reads (offset-based; nothing is committed).
+const AUDIT_CONSUMER_ID: u32 = 1;
+
+/// Who performed an audited operation, and from where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditContext {
+    /// `admin`, `signed`, `api_key` or `anonymous`
+    pub actor: &'static str,
+    /// Client IP, resolved like rate limiting does
+    pub client_ip: String,
+    /// Request ID of the operation
+    pub request_id: Option<String>,
+}
+
+/// Writes and reads the audit topic.
+pub struct AuditService {
+    client: IggyClientWrapper,
+    stream: String,
+    topic: String,
+    enabled: bool,
+    /// Set once the audit stream and topic are known to exist
+    ready: OnceCell<()>,
+}
+
+impl AuditService {
+    /// Create an audit log in `topic` of `stream`; a disabled one records
+    /// nothing.
+    pub fn new(client: IggyClientWrapper, stream: &str, topic: &str, enabled: bool) -> Self {
+        Self {
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            enabled,
+            ready: OnceCell::new(),
+        }
+    }
+
+    /// Check if operations are recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record the outcome of `action` on `resource`. Never fails: a write
+    /// error is logged.
+    pub async fn record<T>(
+        &self,
+        context: &AuditContext,
+        action: AuditAction,
+        resource: &str,
+        result: &AppResult<T>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let entry = entry(context, action, resource, result);
+        if let Err(e) = self.append(&entry).await {
+            warn!(
+                action = ?entry.action,
+                resource = %entry.resource,
+                outcome = ?entry.outcome,
+                error = %e,
+                "Failed to write audit entry"
+            );
+        }
+    }
+
+    /// Read up to `query.count` entries from `query.offset`, keeping those
+    /// matching the query's filters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` when the audit log is disabled, or
+    /// the error reading the topic.
+    pub async fn query(&self, query: &AuditQuery) -> AppResult<AuditLogResponse> {
+        if !self.enabled {
+            return Err(AppError::BadRequest(
+                "Audit log is disabled (AUDIT_ENABLED=false)".to_string(),
+            ));
+        }
+        self.ensure_topic().await?;
+
+        let params = PollParams::new(AUDIT_PARTITION_ID, AUDIT_CONSUMER_ID)
+            .with_offset(query.offset)
+            .with_count(query.count);
+        let polled = self
+            .client
+            .poll_messages(&self.stream, &self.topic, params)
+            .await?;
+        let next_offset = polled
+            .messages
+            .last()
+            .map_or(query.offset, |last| last.header.offset + 1);
+
+        let entries = polled
+            .messages
+            .iter()
+            .filter_map(
+                |message| match serde_json::from_slice::<AuditEntry>(&message.payload) {
+                    Ok(entry) => Some(AuditEntry {
+                        offset: Some(message.header.offset),
+                        ..entry
+                    }),
+                    Err(e) => {
+                        warn!(
+                            offset = message.header.offset,
+                            error = %e,
+                            "Skipping unreadable audit entry"
+                        );
+                        None
+                    }
+                },
+            )
+            .filter(|entry| matches(entry, query))
+            .collect();
+
+        Ok(AuditLogResponse {
+            entries,
+            next_offset,
+        })
+    }
+
+    /// Append an entry to the audit topic.
+    async fn append(&self, entry: &AuditEntry) -> AppResult<()> {
+        self.ensure_topic().await?;
+        let message = payload_message(Bytes::from(serde_json::to_vec(entry)?))?;
+        self.client
+            .send_raw_messages(
+                &self.stream,
+                &self.topic,
+                &[message],
+                &Partitioning::partition_id(AUDIT_PARTITION_ID),
+            )
+            .await
+    }
+
+    /// Create the audit stream and topic if needed, once per instance.
+    async fn ensure_topic(&self) -> AppResult<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                self.client.ensure_stream(&self.stream).await?;
+                self.client.ensure_topic(&self.stream, &self.topic, 1).await
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Build the entry recording `result` of `action` on `resource`.
+fn entry<T>(
+    context: &AuditContext,
+    action: AuditAction,
+    resource: &str,
+    result: &AppResult<T>,
+) -> AuditEntry {
+    let (outcome, error) = match result {
+        Ok(_) => (AuditOutcome::Success, None),
+        Err(e) => (AuditOutcome::Failure, Some(e.to_string())),
+    };
+    AuditEntry {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        action,
+        resource: resource.to_string(),
+        actor: context.actor.to_string(),
+        client_ip: context.client_ip.clone(),
+        request_id: context.request_id.clone(),
+        outcome,
+        error,
+        offset: None,
+    }
+}
+
+/// Check `entry` against the action and outcome filters of `query`.
+fn matches(entry: &AuditEntry, query: &AuditQuery) -> bool {
+    query.action.is_none_or(|action| action == entry.action)
+        && query.outcome.is_none_or(|outcome| outcome == entry.outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> AuditContext {
+        AuditContext {
+            actor: "admin",
+            client_ip: "10.0.0.7".to_string(),
+            request_id: Some("req-1".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_entry_records_outcome_and_error() {
+        let ok: AppResult<()> = Ok(());
+        let recorded = entry(&context(), AuditAction::CreateStream, "orders", &ok);
+        assert_eq!(recorded.outcome, AuditOutcome::Success);
+        assert_eq!(recorded.error, None);
+        assert_eq!(recorded.actor, "admin");
+        assert_eq!(recorded.request_id.as_deref(), Some("req-1"));
+
+        let failed: AppResult<()> = Err(AppError::NotFound("Stream 'orders'".to_string()));
+        let recorded = entry(&context(), AuditAction::DeleteStream, "orders", &failed);
+        assert_eq!(recorded.outcome, AuditOutcome::Failure);
+        assert!(recorded.error.is_some_and(|e| e.contains("orders")));
+    }
+
+    #[test]
+    fn test_matches_applies_each_filter() {
+        let ok: AppResult<()> = Ok(());
+        let recorded = entry(&context(), AuditAction::DeleteTopic, "orders/events", &ok);
+        let query = |action, outcome| AuditQuery {
+            offset: 0,
+            count: 100,
+            action,
+            outcome,
+        };
+
+        assert!(matches(&recorded, &query(None, None)));
+        assert!(matches(
+            &recorded,
+            &query(Some(AuditAction::DeleteTopic), None)
+        ));
+        assert!(!matches(
+            &recorded,
+            &query(Some(AuditAction::CreateTopic), None)
+        ));
+        assert!(matches(
+            &recorded,
+            &query(None, Some(AuditOutcome::Success))
+        ));
+        assert!(!matches(
+            &recorded,
+            &query(Some(AuditAction::DeleteTopic), Some(AuditOutcome::Failure))
+        ));
+    }
+}
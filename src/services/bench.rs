@@ -0,0 +1,365 @@
+//! Load generation for sizing a deployment.
+//!
+//! `POST /admin/benchmark` sends synthetic events to a dedicated topic
+//! (`BENCHMARK_TOPIC` in the default stream, created on demand) for a
+//! given duration and reports the achieved throughput and the latency
+//! distribution of the sends:
+//!
+//! - `event_size` - payload bytes per event
+//! - `rate` - events per second across all workers (0 = as fast as possible)
+//! - `duration_secs` - length of the run, at most `BENCHMARK_MAX_DURATION_SECS`
+//! - `batch_size` - events per send (1 = single sends)
+//! - `concurrency` - workers sending in parallel
+//!
+//! Sends go through a [`ProducerService`] of their own, so they take the
+//! same path as client traffic (partitioning, retries, circuit breakers)
+//! without counting toward the `/stats` message totals. One run at a time;
+//! shutdown cuts a run short and reports what was measured so far.
+
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tokio::time::{Instant, MissedTickBehavior, interval, sleep_until};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, instrument, warn};
+
+use super::ProducerService;
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::IggyClientWrapper;
+use crate::models::{BenchmarkRequest, BenchmarkResponse, Event, EventPayload, LatencySummary};
+use crate::utils::percentile;
+
+/// Event type of benchmark events.
+pub const BENCHMARK_EVENT_TYPE: &str = "benchmark.load";
+
+/// Largest `event_size` accepted.
+pub const MAX_BENCHMARK_EVENT_SIZE: usize = 1024 * 1024;
+
+/// Largest `concurrency` accepted.
+pub const MAX_BENCHMARK_CONCURRENCY: u32 = 64;
+
+/// Runs load tests against a dedicated topic.
+pub struct Benchmark {
+    client: IggyClientWrapper,
+    producer: ProducerService,
+    stream: String,
+    topic: String,
+    partitions: u32,
+    max_duration: Duration,
+    max_batch_size: usize,
+    /// Held for the length of a run
+    running: Mutex<()>,
+    /// Cuts the current run short (server shutdown)
+    stop: CancellationToken,
+}
+
+impl Benchmark {
+    /// Create a benchmark sending to `stream`/`topic` (created with
+    /// `partitions` if missing); runs are limited to `max_duration`
+    /// (zero = disabled) and batches to `max_batch_size` events.
+    pub fn new(
+        client: IggyClientWrapper,
+        stream: &str,
+        topic: &str,
+        partitions: u32,
+        max_duration: Duration,
+        max_batch_size: usize,
+    ) -> Self {
+        Self {
+            producer: ProducerService::new(client.clone()),
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            partitions,
+            max_duration,
+            max_batch_size,
+            running: Mutex::new(()),
+            stop: CancellationToken::new(),
+        }
+    }
+
+    /// Check if benchmarks may run (`BENCHMARK_MAX_DURATION_SECS` > 0).
+    pub fn is_enabled(&self) -> bool {
+        !self.max_duration.is_zero()
+    }
+
+    /// Check that `request` is within the benchmark's limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` naming the first parameter out of range.
+    pub fn validate(&self, request: &BenchmarkRequest) -> AppResult<()> {
+        if !(1..=MAX_BENCHMARK_EVENT_SIZE).contains(&request.event_size) {
+            return Err(AppError::BadRequest(format!(
+                "event_size must be between 1 and {MAX_BENCHMARK_EVENT_SIZE}"
+            )));
+        }
+        if !(1..=self.max_duration.as_secs()).contains(&request.duration_secs) {
+            return Err(AppError::BadRequest(format!(
+                "duration_secs must be between 1 and {} (BENCHMARK_MAX_DURATION_SECS)",
+                self.max_duration.as_secs()
+            )));
+        }
+        if !(1..=self.max_batch_size).contains(&request.batch_size) {
+            return Err(AppError::BadRequest(format!(
+                "batch_size must be between 1 and {} (BATCH_MAX_SIZE)",
+                self.max_batch_size
+            )));
+        }
+        if !(1..=MAX_BENCHMARK_CONCURRENCY).contains(&request.concurrency) {
+            return Err(AppError::BadRequest(format!(
+                "concurrency must be between 1 and {MAX_BENCHMARK_CONCURRENCY}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run the load test described by `request` and report the results.
+    ///
+    /// Failed sends are counted, not fatal: the run always lasts its full
+    /// duration unless the benchmark is stopped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` if benchmarks are disabled, another
+    /// run is in progress or `request` is out of range, and the Iggy error
+    /// if the benchmark topic cannot be created.
+    #[instrument(skip(self), fields(topic = %self.topic))]
+    pub async fn run(&self, request: BenchmarkRequest) -> AppResult<BenchmarkResponse> {
+        if !self.is_enabled() {
+            return Err(AppError::BadRequest(
+                "Benchmarks are disabled (BENCHMARK_MAX_DURATION_SECS=0)".to_string(),
+            ));
+        }
+        let Ok(_running) = self.running.try_lock() else {
+            return Err(AppError::BadRequest(
+                "A benchmark is already running".to_string(),
+            ));
+        };
+        self.validate(&request)?;
+
+        self.client.ensure_stream(&self.stream).await?;
+        self.client
+            .ensure_topic(&self.stream, &self.topic, self.partitions)
+            .await?;
+
+        let payload = EventPayload::Generic(serde_json::json!({
+            "data": "x".repeat(request.event_size),
+        }));
+        let event_bytes =
+            serde_json::to_vec(&Event::new(BENCHMARK_EVENT_TYPE, payload.clone()))?.len();
+        let period = send_interval(&request);
+        let started = Instant::now();
+        let deadline = started + Duration::from_secs(request.duration_secs);
+        info!(?request, event_bytes, "Benchmark started");
+
+        let mut workers = JoinSet::new();
+        for _ in 0..request.concurrency {
+            workers.spawn(run_worker(Worker {
+                producer: self.producer.clone(),
+                stream: self.stream.clone(),
+                topic: self.topic.clone(),
+                payload: payload.clone(),
+                batch_size: request.batch_size,
+                period,
+                deadline,
+                stop: self.stop.clone(),
+            }));
+        }
+        let mut stats = WorkerStats::default();
+        while let Some(worker) = workers.join_next().await {
+            match worker {
+                Ok(worker) => stats.merge(worker),
+                Err(e) => warn!(error = %e, "Benchmark worker failed"),
+            }
+        }
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let events_per_second = stats.events_sent as f64 / elapsed;
+        info!(
+            events_sent = stats.events_sent,
+            failed_sends = stats.failed_sends,
+            events_per_second,
+            "Benchmark finished"
+        );
+        Ok(BenchmarkResponse {
+            stream: self.stream.clone(),
+            topic: self.topic.clone(),
+            event_bytes,
+            elapsed_secs: elapsed,
+            events_sent: stats.events_sent,
+            sends: stats.latencies.len() as u64,
+            failed_sends: stats.failed_sends,
+            first_error: stats.first_error,
+            events_per_second,
+            bytes_per_second: events_per_second * event_bytes as f64,
+            latency: summarize(&mut stats.latencies),
+            stopped_early: self.stop.is_cancelled(),
+            request,
+        })
+    }
+
+    /// Cut the current run (and any later one) short.
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+}
+
+/// Pause between two sends of one worker to reach the requested rate
+/// (None = no pause).
+fn send_interval(request: &BenchmarkRequest) -> Option<Duration> {
+    (request.rate > 0).then(|| {
+        let events_per_tick = request.batch_size as f64 * f64::from(request.concurrency);
+        Duration::from_secs_f64(events_per_tick / f64::from(request.rate))
+    })
+}
+
+/// What one worker sends.
+struct Worker {
+    producer: ProducerService,
+    stream: String,
+    topic: String,
+    payload: EventPayload,
+    batch_size: usize,
+    period: Option<Duration>,
+    deadline: Instant,
+    stop: CancellationToken,
+}
+
+/// What one or more workers measured.
+#[derive(Debug, Default)]
+struct WorkerStats {
+    /// Latency of every successful send
+    latencies: Vec<Duration>,
+    events_sent: u64,
+    failed_sends: u64,
+    first_error: Option<String>,
+}
+
+impl WorkerStats {
+    fn merge(&mut self, other: WorkerStats) {
+        self.latencies.extend(other.latencies);
+        self.events_sent += other.events_sent;
+        self.failed_sends += other.failed_sends;
+        self.first_error = self.first_error.take().or(other.first_error);
+    }
+}
+
+/// Send batches until the deadline passes or the benchmark stops.
+async fn run_worker(worker: Worker) -> WorkerStats {
+    let mut stats = WorkerStats::default();
+    let mut ticker = worker.period.map(|period| {
+        let mut ticker = interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker
+    });
+
+    loop {
+        if let Some(ticker) = &mut ticker {
+            tokio::select! {
+                biased;
+
+                _ = worker.stop.cancelled() => break,
+                _ = sleep_until(worker.deadline) => break,
+                _ = ticker.tick() => {}
+            }
+        }
+        if worker.stop.is_cancelled() || Instant::now() >= worker.deadline {
+            break;
+        }
+
+        let events: Vec<Event> = (0..worker.batch_size)
+            .map(|_| Event::new(BENCHMARK_EVENT_TYPE, worker.payload.clone()))
+            .collect();
+        let start = Instant::now();
+        let result = match events.as_slice() {
+            [event] => worker
+                .producer
+                .send_to(&worker.stream, &worker.topic, event, None, None)
+                .await
+                .map(|_| ()),
+            events => worker
+                .producer
+                .send_batch_to(&worker.stream, &worker.topic, events, None, None)
+                .await
+                .map(|_| ()),
+        };
+        match result {
+            Ok(()) => {
+                stats.latencies.push(start.elapsed());
+                stats.events_sent += events.len() as u64;
+            }
+            Err(e) => {
+                stats.failed_sends += 1;
+                stats.first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+    }
+    stats
+}
+
+/// Latency distribution of `latencies` (sorted in place).
+fn summarize(latencies: &mut [Duration]) -> LatencySummary {
+    if latencies.is_empty() {
+        return LatencySummary::default();
+    }
+    latencies.sort_unstable();
+    let total: Duration = latencies.iter().sum();
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    LatencySummary {
+        min_ms: ms(percentile(latencies, 0.0)),
+        mean_ms: ms(total) / latencies.len() as f64,
+        p50_ms: ms(percentile(latencies, 0.5)),
+        p90_ms: ms(percentile(latencies, 0.9)),
+        p99_ms: ms(percentile(latencies, 0.99)),
+        p999_ms: ms(percentile(latencies, 0.999)),
+        max_ms: ms(percentile(latencies, 1.0)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn request(rate: u32, batch_size: usize, concurrency: u32) -> BenchmarkRequest {
+        BenchmarkRequest {
+            event_size: 256,
+            rate,
+            duration_secs: 10,
+            batch_size,
+            concurrency,
+        }
+    }
+
+    #[test]
+    fn test_send_interval_spreads_the_rate_over_workers() {
+        assert_eq!(send_interval(&request(0, 1, 1)), None);
+        assert_eq!(
+            send_interval(&request(1000, 1, 1)),
+            Some(Duration::from_millis(1))
+        );
+        // 4 workers sending batches of 10 at 1000 events/s in total
+        assert_eq!(
+            send_interval(&request(1000, 10, 4)),
+            Some(Duration::from_millis(40))
+        );
+    }
+
+    #[test]
+    fn test_summary_uses_nearest_rank_percentiles() {
+        let mut latencies: Vec<Duration> = (1..=1000).rev().map(Duration::from_millis).collect();
+        let summary = summarize(&mut latencies);
+
+        assert_eq!(summary.min_ms, 1.0);
+        assert_eq!(summary.p50_ms, 500.0);
+        assert_eq!(summary.p90_ms, 900.0);
+        assert_eq!(summary.p99_ms, 990.0);
+        assert_eq!(summary.p999_ms, 999.0);
+        assert_eq!(summary.max_ms, 1000.0);
+        assert!((summary.mean_ms - 500.5).abs() < 1e-9);
+
+        assert_eq!(summarize(&mut []).max_ms, 0.0);
+    }
+}
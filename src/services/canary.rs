@@ -0,0 +1,177 @@
+//! Synthetic canary: end-to-end send → poll round trips.
+//!
+//! Connection state only says the TCP session is up. The canary proves the
+//! full path works by periodically sending a heartbeat event to a dedicated
+//! topic and reading it back, recording:
+//!
+//! - `iggy_canary_rtt_seconds` - send-to-read-back latency
+//! - `iggy_canary_failures_total` - probes that failed or timed out
+//!
+//! # Read-Back Without Consumer State
+//!
+//! Each probe records the partition's next offset BEFORE sending, then polls
+//! from that offset until its own event ID shows up. No consumer offset is
+//! stored, so probes are independent of each other and of restarts, and
+//! several replicas can share the canary topic (foreign heartbeats are
+//! skipped).
+
+use std::time::{Duration, Instant};
+
+use tokio::time::sleep;
+use tracing::{debug, instrument};
+
+use super::{ConsumerService, ProducerService};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{IggyClientWrapper, PollParams};
+use crate::models::{Event, EventPayload};
+
+/// Event type of canary heartbeats.
+pub const CANARY_EVENT_TYPE: &str = "canary.heartbeat";
+
+/// The canary topic has a single partition; probes write and read it.
+const CANARY_PARTITION_ID: u32 = 0;
+
+/// Consumer ID used for canary polls (offset-based; nothing is committed).
+const CANARY_CONSUMER_ID: u32 = 1;
+
+/// Messages fetched per read-back poll.
+const CANARY_POLL_COUNT: u32 = 100;
+
+/// Pause between read-back polls while the heartbeat is not yet visible.
+const CANARY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs canary probes against a dedicated topic.
+///
+/// Uses its own producer/consumer services, so heartbeats never count
+/// toward the `/stats` message totals.
+#[derive(Clone)]
+pub struct CanaryService {
+    client: IggyClientWrapper,
+    producer: ProducerService,
+    consumer: ConsumerService,
+    stream: String,
+    topic: String,
+    timeout: Duration,
+}
+
+impl CanaryService {
+    /// Create a canary for `stream`/`topic`; each probe is bounded by `timeout`.
+    pub fn new(client: IggyClientWrapper, stream: &str, topic: &str, timeout: Duration) -> Self {
+        Self {
+            producer: ProducerService::new(client.clone()),
+            consumer: ConsumerService::new(client.clone()),
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            timeout,
+        }
+    }
+
+    /// Create the single-partition canary topic if it does not exist.
+    pub async fn ensure_topic(&self) -> AppResult<()> {
+        self.client.ensure_stream(&self.stream).await?;
+        self.client.ensure_topic(&self.stream, &self.topic, 1).await
+    }
+
+    /// Run one probe and return the round-trip time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the send/poll error, or `AppError::OperationTimeout` if the
+    /// heartbeat is not read back within the probe timeout.
+    #[instrument(skip(self), fields(stream = %self.stream, topic = %self.topic))]
+    pub async fn probe(&self) -> AppResult<Duration> {
+        tokio::time::timeout(self.timeout, self.round_trip())
+            .await
+            .map_err(|_| {
+                AppError::OperationTimeout(format!(
+                    "Canary heartbeat not read back within {:?}",
+                    self.timeout
+                ))
+            })?
+    }
+
+    async fn round_trip(&self) -> AppResult<Duration> {
+        let mut offset = self.partition_write_offset().await?;
+
+        let event = Event::new(
+            CANARY_EVENT_TYPE,
+            EventPayload::Generic(serde_json::json!({})),
+        );
+        let start = Instant::now();
+        self.producer
+            .send_to(&self.stream, &self.topic, &event, None, None)
+            .await?;
+
+        loop {
+            let params = PollParams::new(CANARY_PARTITION_ID, CANARY_CONSUMER_ID)
+                .with_offset(offset)
+                .with_count(CANARY_POLL_COUNT);
+            let polled = self
+                .consumer
+                .poll_from(&self.stream, &self.topic, params)
+                .await?;
+
+            if polled.messages.iter().any(|m| m.event.id == event.id) {
+                let rtt = start.elapsed();
+                debug!(
+                    rtt_ms = rtt.as_millis() as u64,
+                    "Canary round trip completed"
+                );
+                return Ok(rtt);
+            }
+            // Skip past other replicas' heartbeats seen so far.
+            if let Some(last) = polled.messages.last() {
+                offset = last.offset + 1;
+            }
+            sleep(CANARY_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Offset the next message on the canary partition will be written at.
+    async fn partition_write_offset(&self) -> AppResult<u64> {
+        let details = self.client.get_topic(&self.stream, &self.topic).await?;
+        let partition = details
+            .partitions
+            .iter()
+            .find(|p| p.id == CANARY_PARTITION_ID)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Canary topic '{}' has no partition {}",
+                    self.topic, CANARY_PARTITION_ID
+                ))
+            })?;
+        Ok(next_offset(
+            partition.current_offset,
+            partition.messages_count,
+        ))
+    }
+}
+
+/// Offset of the next message written to a partition.
+///
+/// `current_offset` is the LAST written offset, and is also 0 for an empty
+/// partition, so the message count disambiguates the two.
+fn next_offset(current_offset: u64, messages_count: u64) -> u64 {
+    if messages_count == 0 {
+        0
+    } else {
+        current_offset + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_offset_on_empty_partition_is_zero() {
+        assert_eq!(next_offset(0, 0), 0);
+    }
+
+    #[test]
+    fn test_next_offset_follows_last_written() {
+        assert_eq!(next_offset(0, 1), 1);
+        assert_eq!(next_offset(41, 42), 42);
+    }
+}
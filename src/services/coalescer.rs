@@ -0,0 +1,173 @@
+//! Coalescing of single sends into batches.
+//!
+//! High-rate callers of `POST /messages` pay one Iggy round trip per event.
+//! With `COALESCE_WINDOW_MS` set, single sends to the same destination
+//! (stream, topic, and resolved partition target) that arrive within the
+//! window are sent as ONE batch. Each caller still gets its own response,
+//! once the batch it joined has been flushed.
+//!
+//! # Flushing
+//!
+//! A batch is flushed when its window elapses (timed from its first event)
+//! or as soon as it reaches `COALESCE_MAX_BATCH` events. Flushes run in
+//! their own task, so a caller that disconnects mid-wait cannot cancel the
+//! send for everyone else in the batch. A failed flush fails every request
+//! in it with the same error.
+//!
+//! Flushes use the root client, so they are bounded by the global
+//! `OPERATION_TIMEOUT_SECS` rather than any one caller's request timeout.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time::sleep;
+use tracing::debug;
+
+use super::partitioner::PartitionTarget;
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::IggyOperations;
+use crate::models::Event;
+
+/// Destination shared by every event in a batch.
+type BatchKey = (String, String, PartitionTarget);
+
+/// Outcome delivered to each waiter; the error is shared across the batch.
+type FlushResult = Result<(), Arc<AppError>>;
+
+/// Events waiting for the same destination.
+struct PendingBatch {
+    /// Distinguishes this batch from a later one for the same key, so a
+    /// stale window timer never flushes a newer batch early.
+    id: u64,
+    events: Vec<Event>,
+    waiters: Vec<oneshot::Sender<FlushResult>>,
+}
+
+/// Groups single sends into batches per destination.
+pub(super) struct Coalescer<C> {
+    client: C,
+    window: Duration,
+    max_batch: usize,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<BatchKey, PendingBatch>>,
+}
+
+impl<C: IggyOperations> Coalescer<C> {
+    /// Create a coalescer flushing after `window` or at `max_batch` events.
+    pub(super) fn new(client: C, window: Duration, max_batch: usize) -> Self {
+        Self {
+            client,
+            window,
+            max_batch,
+            next_id: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add `event` to the batch for its destination and wait until that
+    /// batch has been sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns the batch send's error, or `AppError::SendError` if the
+    /// batch was dropped without being sent.
+    pub(super) async fn submit(
+        self: &Arc<Self>,
+        stream: &str,
+        topic: &str,
+        target: PartitionTarget,
+        event: Event,
+    ) -> AppResult<()> {
+        let key = (stream.to_string(), topic.to_string(), target);
+        let (tx, rx) = oneshot::channel();
+
+        let full = {
+            // Never held across an await; the map stays consistent.
+            let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+            let batch = match pending.entry(key.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                    self.schedule_window_flush(key.clone(), id);
+                    entry.insert(PendingBatch {
+                        id,
+                        events: Vec::new(),
+                        waiters: Vec::new(),
+                    })
+                }
+            };
+            batch.events.push(event);
+            batch.waiters.push(tx);
+            if batch.events.len() >= self.max_batch {
+                pending.remove(&key)
+            } else {
+                None
+            }
+        };
+        if let Some(batch) = full {
+            self.spawn_flush(key, batch);
+        }
+
+        match rx.await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(shared)) => Err(Arc::try_unwrap(shared).unwrap_or_else(|e| e.duplicate())),
+            Err(_) => Err(AppError::SendError(
+                "Coalesced batch was dropped before it was sent".to_string(),
+            )),
+        }
+    }
+
+    /// Flush batch `id` of `key` once the window elapses, unless it has
+    /// already been flushed for reaching `max_batch`.
+    fn schedule_window_flush(self: &Arc<Self>, key: BatchKey, id: u64) {
+        let this = Arc::clone(self);
+        tokio::spawn(async move {
+            sleep(this.window).await;
+            let batch = {
+                let mut pending = this.pending.lock().unwrap_or_else(PoisonError::into_inner);
+                match pending.get(&key) {
+                    Some(batch) if batch.id == id => pending.remove(&key),
+                    _ => None,
+                }
+            };
+            if let Some(batch) = batch {
+                this.flush(&key, batch).await;
+            }
+        });
+    }
+
+    fn spawn_flush(self: &Arc<Self>, key: BatchKey, batch: PendingBatch) {
+        let this = Arc::clone(self);
+        tokio::spawn(async move { this.flush(&key, batch).await });
+    }
+
+    /// Send `batch` and report the outcome to every waiter.
+    async fn flush(&self, key: &BatchKey, batch: PendingBatch) {
+        let (stream, topic, target) = key;
+        let result = match target.partitioning() {
+            Ok(partitioning) => {
+                self.client
+                    .send_events_batch_partitioned(stream, topic, &batch.events, &partitioning)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        debug!(
+            stream,
+            topic,
+            batch_size = batch.events.len(),
+            success = result.is_ok(),
+            "Coalesced batch flushed"
+        );
+
+        let result: FlushResult = result.map_err(Arc::new);
+        for waiter in batch.waiters {
+            // A waiter that gave up (client disconnected) is fine to skip.
+            let _ = waiter.send(result.clone());
+        }
+    }
+}
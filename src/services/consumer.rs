@@ -2,7 +2,24 @@
 //!
 //! This service handles message consumption with:
 //! - Automatic message parsing and deserialization
+//! - Transparent decompression of `content-encoding`-tagged payloads
 //! - Offset tracking per consumer
+//! - Peeks that read at an offset without touching any consumer's state
+//! - Explicit acknowledgment (offset commit) after processing
+//! - Nacks: redelivery copies with a `redelivery_count`, then dead-lettering
+//! - Registry of consumers seen polling (see [`ConsumerRegistry`])
+//! - Streamed JSON responses for large polls (bounded response memory)
+//! - Per-message position metadata (partition, offset, checksum, headers)
+//! - Poll diagnostics (`end_of_partition`, `server_poll_duration_ms`) for
+//!   tailing loops
+//! - `sequence_gap` / `sequence_duplicate` warnings for per-key sequences
+//! - Upcasting of older event schema versions on request (`target_version`)
+//! - Signed `continuation` tokens resuming a poll on any replica (see
+//!   [`ContinuationTokens`])
+//! - Duplicate events filtered out of polls within a window (see
+//!   [`PollDedup`])
+//! - Event-time lag of polled events (see [`EventTimeLag`])
+//! - Consumer lag computation (latest offset − committed offset)
 //! - Message statistics
 //!
 //! # Consumer IDs
@@ -10,16 +27,50 @@
 //! Each consumer ID maintains its own offset position. Use consistent IDs
 //! across application restarts to resume from the last committed position.
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use axum::body::Body;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use iggy::prelude::IggyMessage;
+use iggy::prelude::{IggyMessage, Partitioning, PolledMessages};
 use tracing::{debug, instrument, warn};
 
-use crate::error::AppResult;
-use crate::iggy_client::{IggyClientWrapper, PollParams};
-use crate::models::{Event, PollMessagesResponse, ReceivedMessage};
+use super::{ConsumerRegistry, Continuation, ContinuationTokens, EventTimeLag, PollDedup};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{
+    CONTENT_ENCODING_HEADER, IggyClientWrapper, MessageBroker, PollParams, RedeliveryPolicy,
+    SequenceChecker, decompress_payload, redelivery_count, redelivery_message,
+};
+use crate::models::{
+    AckOffset, ConsumerLagResponse, Event, NackedMessage, PartitionLag, PollMessagesResponse,
+    PollResponseTail, PollWarning, ReceivedMessage, UpcasterRegistry,
+};
+
+/// Consumer ID peeks poll as: above
+/// [`MAX_CONSUMER_ID`](crate::validation::MAX_CONSUMER_ID), so no client's
+/// consumer can share it, and never committed.
+pub const PEEK_CONSUMER_ID: u32 = u32::MAX;
+
+/// Where [`ConsumerService::read_tail`] starts reading a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailStart {
+    /// The offset after the last message already read
+    Offset(u64),
+    /// The first message stamped at or after this time, for a partition
+    /// not read yet
+    Since(DateTime<Utc>),
+}
+
+impl TailStart {
+    /// Resume from `next`, or start at `since` when nothing was read yet.
+    pub fn resume(next: Option<u64>, since: DateTime<Utc>) -> Self {
+        next.map_or(Self::Since(since), Self::Offset)
+    }
+}
 
 /// Service for consuming messages from Iggy streams.
 ///
@@ -32,34 +83,95 @@ use crate::models::{Event, PollMessagesResponse, ReceivedMessage};
 /// - Eventual consistency is acceptable (exact real-time count not required)
 /// - No other operations depend on this counter's value for correctness
 /// - `Relaxed` has minimal overhead on all architectures
+///
+/// # Broker
+///
+/// The service is generic over [`MessageBroker`] and defaults to
+/// [`IggyClientWrapper`].
 #[derive(Clone)]
-pub struct ConsumerService {
-    client: IggyClientWrapper,
+pub struct ConsumerService<B = IggyClientWrapper> {
+    client: B,
     /// Total messages consumed (monotonic counter, eventually consistent).
     messages_consumed: Arc<AtomicU64>,
+    /// Consumers seen polling, shared across request-scoped views.
+    registry: Arc<ConsumerRegistry>,
+    /// Schema upcasters applied to polls with a `target_version`.
+    upcasters: Arc<UpcasterRegistry>,
+    /// Issuer of the `continuation` tokens of poll responses.
+    continuations: Arc<ContinuationTokens>,
+    /// Filter of duplicate events out of polls (None = disabled).
+    dedup: Option<Arc<PollDedup>>,
+    /// Event-time lag of consumed events, shared like the registry.
+    event_lag: Arc<EventTimeLag>,
 }
 
-impl ConsumerService {
+impl<B: MessageBroker> ConsumerService<B> {
     /// Create a new consumer service.
-    pub fn new(client: IggyClientWrapper) -> Self {
+    pub fn new(client: B) -> Self {
         Self {
             client,
             messages_consumed: Arc::new(AtomicU64::new(0)),
+            registry: Arc::new(ConsumerRegistry::new()),
+            upcasters: Arc::new(UpcasterRegistry::new()),
+            continuations: Arc::new(ContinuationTokens::default()),
+            dedup: None,
+            event_lag: Arc::new(EventTimeLag::new()),
         }
     }
 
+    /// Use `upcasters` for polls that request a `target_version`. With
+    /// none registered, such polls return older events as stored, each with
+    /// an `upcast_failed` warning.
+    #[must_use]
+    pub fn with_upcasters(mut self, upcasters: Arc<UpcasterRegistry>) -> Self {
+        self.upcasters = upcasters;
+        self
+    }
+
+    /// Add a `continuation` token from `continuations` to poll responses.
+    /// Peeks get none.
+    #[must_use]
+    pub fn with_continuations(mut self, continuations: Arc<ContinuationTokens>) -> Self {
+        self.continuations = continuations;
+        self
+    }
+
+    /// Leave out of polls the events `dedup` has already returned to the
+    /// polling consumer. Peeks are not filtered.
+    #[must_use]
+    pub fn with_dedup(mut self, dedup: Arc<PollDedup>) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
     /// Return a view of this service whose Iggy operations are bounded by
     /// `timeout` (clamped to the configured global — see
     /// [`IggyClientWrapper::with_timeout`]). The consumed-messages counter
-    /// is shared with the parent, so stats stay global.
+    /// and consumer registry are shared with the parent, so stats stay
+    /// global.
     #[must_use]
     pub fn with_timeout(&self, timeout: std::time::Duration) -> Self {
         Self {
             client: self.client.with_timeout(timeout),
             messages_consumed: Arc::clone(&self.messages_consumed),
+            registry: Arc::clone(&self.registry),
+            upcasters: Arc::clone(&self.upcasters),
+            continuations: Arc::clone(&self.continuations),
+            dedup: self.dedup.clone(),
+            event_lag: Arc::clone(&self.event_lag),
         }
     }
 
+    /// Registry of the consumers that have polled through this service.
+    pub fn registry(&self) -> &Arc<ConsumerRegistry> {
+        &self.registry
+    }
+
+    /// Event-time lag of the events polled through this service.
+    pub fn event_lag(&self) -> &Arc<EventTimeLag> {
+        &self.event_lag
+    }
+
     /// Poll messages from the default stream and topic.
     ///
     /// # Arguments
@@ -67,8 +179,8 @@ impl ConsumerService {
     /// * `params` - Polling parameters (partition, consumer, offset, count, auto_commit)
     #[instrument(skip(self, params), fields(partition_id = params.partition_id, consumer_id = params.consumer_id))]
     pub async fn poll(&self, params: PollParams) -> AppResult<PollMessagesResponse> {
-        let stream = self.client.default_stream().to_string();
-        let topic = self.client.default_topic().to_string();
+        let config = self.client.config();
+        let (stream, topic) = (config.default_stream.clone(), config.default_topic.clone());
         self.poll_from(&stream, &topic, params).await
     }
 
@@ -79,25 +191,554 @@ impl ConsumerService {
         stream: &str,
         topic: &str,
         params: PollParams,
+    ) -> AppResult<PollMessagesResponse> {
+        self.poll_response(stream, topic, params, true).await
+    }
+
+    /// Read up to `count` messages of one partition from `offset`, without
+    /// committing or moving any consumer's offset.
+    ///
+    /// Polls as [`PEEK_CONSUMER_ID`] with an explicit offset and no
+    /// auto-commit, so Iggy stores nothing; the peek is kept out of the
+    /// consumer registry and the consumed-messages counters.
+    #[instrument(skip(self))]
+    pub async fn peek_from(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_id: u32,
+        offset: u64,
+        count: u32,
+    ) -> AppResult<PollMessagesResponse> {
+        let params = PollParams::new(partition_id, PEEK_CONSUMER_ID)
+            .with_offset(offset)
+            .with_count(count);
+        self.poll_response(stream, topic, params, false).await
+    }
+
+    /// Read up to `count` messages of one partition ending at `until`
+    /// (default: its last message), newest first, without committing.
+    ///
+    /// The start offset is computed from the partition's last offset in
+    /// the topic details, then read like [`Self::peek_from`]; messages
+    /// removed by retention shorten the page rather than shift it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the topic has no such partition, or
+    /// the poll error.
+    #[instrument(skip(self))]
+    pub async fn peek_newest(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_id: u32,
+        until: Option<u64>,
+        count: u32,
+    ) -> AppResult<PollMessagesResponse> {
+        let details = self.client.get_topic(stream, topic).await?;
+        let partition = details
+            .partitions
+            .iter()
+            .find(|partition| partition.id == partition_id)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Topic '{topic}' has no partition {partition_id}"))
+            })?;
+        let end = newest_range_end(partition.current_offset, partition.messages_count, until);
+        let start = end.saturating_sub(u64::from(count));
+
+        let mut response = self
+            .peek_from(stream, topic, partition_id, start, count)
+            .await?;
+        response.messages.retain(|message| message.offset < end);
+        response.messages.reverse();
+        response.tail.count = response.messages.len();
+        Ok(response)
+    }
+
+    /// Read one partition from `start` up to its end, without committing,
+    /// in peeks of `count` messages.
+    ///
+    /// Each peek's events go to `read` with the offset to resume from
+    /// after them, which steps over messages that are not events. Nothing
+    /// is passed for a peek that returns no message. Used by the background
+    /// readers (message index, event counts, ordering check) that follow a
+    /// topic with their own offsets.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed poll; what was passed to `read` before it
+    /// stays read.
+    pub async fn read_tail(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_id: u32,
+        mut start: TailStart,
+        count: u32,
+        mut read: impl FnMut(&[ReceivedMessage], u64),
+    ) -> AppResult<()> {
+        loop {
+            let params = PollParams::new(partition_id, PEEK_CONSUMER_ID).with_count(count);
+            let params = match start {
+                TailStart::Offset(offset) => params.with_offset(offset),
+                TailStart::Since(since) => {
+                    params.with_timestamp(u64::try_from(since.timestamp_micros()).unwrap_or(0))
+                }
+            };
+            let polled = self.client.poll_messages(stream, topic, params).await?;
+            let Some(last) = polled.messages.last() else {
+                return Ok(());
+            };
+            let next = last.header.offset + 1;
+            let messages =
+                self.parse_messages(partition_id, &polled.messages, None, &mut Vec::new());
+            read(&messages, next);
+            if reached_end(&polled) {
+                return Ok(());
+            }
+            start = TailStart::Offset(next);
+        }
+    }
+
+    /// Read up to `count` of the newest messages of a topic, spread evenly
+    /// across its partitions, in pages of at most `page` messages and
+    /// without committing. Partitions holding fewer than their share leave
+    /// the rest to the others.
+    ///
+    /// # Errors
+    ///
+    /// Returns the topic lookup or poll error.
+    #[instrument(skip(self))]
+    pub async fn sample_newest(
+        &self,
+        stream: &str,
+        topic: &str,
+        count: u32,
+        page: u32,
+    ) -> AppResult<Vec<ReceivedMessage>> {
+        let mut partitions = self.client.get_topic(stream, topic).await?.partitions;
+        // Smallest first, so what they lack goes to the larger ones
+        partitions.sort_by_key(|partition| partition.messages_count);
+
+        let mut sample = Vec::new();
+        for (index, partition) in partitions.iter().enumerate() {
+            let remaining = count.saturating_sub(u32::try_from(sample.len()).unwrap_or(u32::MAX));
+            let left = u32::try_from(partitions.len() - index).unwrap_or(u32::MAX);
+            let per_partition = remaining.div_ceil(left);
+            let (mut read, mut until) = (0, None);
+            while read < per_partition {
+                let response = self
+                    .peek_newest(
+                        stream,
+                        topic,
+                        partition.id,
+                        until,
+                        page.min(per_partition - read),
+                    )
+                    .await?;
+                // Newest first: the last message is the oldest read
+                let Some(oldest) = response.messages.last().map(|m| m.offset) else {
+                    break;
+                };
+                read += u32::try_from(response.messages.len()).unwrap_or(u32::MAX);
+                sample.extend(response.messages);
+                match oldest.checked_sub(1) {
+                    Some(before) => until = Some(before),
+                    None => break,
+                }
+            }
+        }
+        Ok(sample)
+    }
+
+    /// Read the message at `offset` of one partition, without committing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if the partition holds no readable
+    /// message at `offset`, or the poll error.
+    #[instrument(skip(self))]
+    pub async fn message_at_offset(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<ReceivedMessage> {
+        self.peek_from(stream, topic, partition_id, offset, 1)
+            .await?
+            .messages
+            .into_iter()
+            .find(|message| message.offset == offset)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "No message at offset {offset} on partition {partition_id}"
+                ))
+            })
+    }
+
+    /// Poll and parse one batch; `consume` records it as consumed by the
+    /// polling consumer (registry and counters).
+    async fn poll_response(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+        consume: bool,
     ) -> AppResult<PollMessagesResponse> {
         let partition_id = params.partition_id;
+        let (consumer_id, auto_commit) = (params.consumer_id, params.auto_commit);
+        let target_version = params.target_version;
+        let requested_offset = params.offset;
         let start = std::time::Instant::now();
         let result = self.client.poll_messages(stream, topic, params).await;
-        crate::metrics::record_poll_duration(stream, topic, start.elapsed().as_secs_f64());
+        let poll_duration = start.elapsed();
+        crate::metrics::record_poll_duration(stream, topic, poll_duration.as_secs_f64());
         let polled = result?;
+        if consume {
+            self.record_poll(
+                stream,
+                topic,
+                consumer_id,
+                partition_id,
+                auto_commit,
+                &polled,
+            );
+        }
+        let end_of_partition = reached_end(&polled);
+        let continuation = if consume {
+            self.continuation(stream, topic, consumer_id, requested_offset, &polled)
+        } else {
+            None
+        };
 
-        let messages = self.parse_messages(&polled.messages);
+        let mut warnings = Vec::new();
+        let mut messages = self.parse_messages(
+            partition_id,
+            &polled.messages,
+            target_version,
+            &mut warnings,
+        );
+        let duplicates_filtered = match &self.dedup {
+            Some(dedup) if consume => dedup.filter(stream, topic, consumer_id, &mut messages),
+            _ => 0,
+        };
         let message_count = messages.len();
+        let mut sequences = SequenceChecker::new();
+        warnings.extend(
+            messages
+                .iter()
+                .filter_map(|message| sequences.check(message)),
+        );
 
-        self.messages_consumed
-            .fetch_add(message_count as u64, Ordering::Relaxed);
-        crate::metrics::record_messages_polled(stream, topic, message_count as u64);
+        if consume {
+            self.messages_consumed
+                .fetch_add(message_count as u64, Ordering::Relaxed);
+            crate::metrics::record_messages_polled(stream, topic, message_count as u64);
+            crate::metrics::record_poll_duplicates(stream, topic, duplicates_filtered as u64);
+            self.event_lag.record(stream, topic, &messages, Utc::now());
+        }
 
         Ok(PollMessagesResponse {
             messages,
-            count: message_count,
+            tail: PollResponseTail {
+                count: message_count,
+                partition_id,
+                current_offset: polled.current_offset,
+                end_of_partition,
+                server_poll_duration_ms: poll_duration.as_secs_f64() * 1000.0,
+                duplicates_filtered,
+                warnings,
+                continuation,
+            },
+        })
+    }
+
+    /// Token resuming after `polled`: past its last message, or where it
+    /// started when it returned nothing. An empty poll from the committed
+    /// offset gets none, since the next one starts there anyway.
+    fn continuation(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        requested_offset: Option<u64>,
+        polled: &PolledMessages,
+    ) -> Option<String> {
+        let offset = polled
+            .messages
+            .last()
+            .map(|message| message.header.offset + 1)
+            .or(requested_offset)?;
+        self.continuations.issue(
+            stream,
+            topic,
+            Continuation {
+                partition_id: polled.partition_id,
+                consumer_id,
+                offset,
+            },
+        )
+    }
+
+    /// Poll messages from the default stream and topic as a streamed JSON
+    /// body (see [`Self::poll_streamed_from`]).
+    #[instrument(skip(self, params), fields(partition_id = params.partition_id, consumer_id = params.consumer_id))]
+    pub async fn poll_streamed(&self, params: PollParams) -> AppResult<Body> {
+        let config = self.client.config();
+        let (stream, topic) = (config.default_stream.clone(), config.default_topic.clone());
+        self.poll_streamed_from(&stream, &topic, params).await
+    }
+
+    /// Poll messages from a specific stream and topic as a streamed JSON
+    /// body.
+    ///
+    /// The body has the same shape as a serialized [`PollMessagesResponse`],
+    /// but messages are parsed and serialized one at a time as the client
+    /// reads, so neither the parsed events nor the full JSON document are
+    /// ever held in memory at once. Only the raw polled batch is. `count`
+    /// is written after the array, since skipped messages are only known
+    /// once it has been walked.
+    ///
+    /// # Errors
+    ///
+    /// Returns the poll error; once the body has started, nothing can fail.
+    #[instrument(skip(self, params), fields(partition_id = params.partition_id, consumer_id = params.consumer_id))]
+    pub async fn poll_streamed_from(
+        &self,
+        stream: &str,
+        topic: &str,
+        params: PollParams,
+    ) -> AppResult<Body> {
+        let partition_id = params.partition_id;
+        let (consumer_id, auto_commit) = (params.consumer_id, params.auto_commit);
+        let target_version = params.target_version;
+        let requested_offset = params.offset;
+        let start = std::time::Instant::now();
+        let result = self.client.poll_messages(stream, topic, params).await;
+        let poll_duration = start.elapsed();
+        crate::metrics::record_poll_duration(stream, topic, poll_duration.as_secs_f64());
+        let polled = result?;
+        self.record_poll(
+            stream,
+            topic,
+            consumer_id,
+            partition_id,
+            auto_commit,
+            &polled,
+        );
+
+        let continuation = self.continuation(stream, topic, consumer_id, requested_offset, &polled);
+        let chunks = PollResponseChunks {
+            consumer: self.clone(),
+            stream: stream.to_string(),
+            topic: topic.to_string(),
             partition_id,
+            consumer_id,
+            target_version,
             current_offset: polled.current_offset,
+            end_of_partition: reached_end(&polled),
+            server_poll_duration_ms: poll_duration.as_secs_f64() * 1000.0,
+            continuation,
+            messages: polled.messages.into_iter(),
+            written: 0,
+            duplicates_filtered: 0,
+            sequences: SequenceChecker::new(),
+            warnings: Vec::new(),
+            state: ChunkState::Start,
+        };
+        Ok(Body::from_stream(futures_util::stream::iter(
+            chunks.map(Ok::<_, Infallible>),
+        )))
+    }
+
+    /// Commit the offsets of messages a consumer has processed.
+    ///
+    /// Polling without `auto_commit` and acknowledging afterwards gives
+    /// at-least-once consumption: a consumer that crashes before acking
+    /// receives the same messages again on its next poll. Entries are
+    /// collapsed to the highest offset per partition, and partitions are
+    /// committed in ID order. Returns what was committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed commit; partitions before it stay
+    /// committed.
+    #[instrument(skip(self, offsets), fields(count = offsets.len()))]
+    pub async fn ack(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        offsets: &[AckOffset],
+    ) -> AppResult<Vec<AckOffset>> {
+        let committed = latest_per_partition(offsets);
+        for ack in &committed {
+            self.client
+                .store_consumer_offset(stream, topic, consumer_id, ack.partition_id, ack.offset)
+                .await?;
+            self.registry
+                .record_commit(stream, topic, consumer_id, ack.partition_id, ack.offset);
+        }
+        Ok(committed)
+    }
+
+    /// Negatively acknowledge messages so they are delivered again.
+    ///
+    /// Each message is read back at its position and a copy appended per
+    /// `policy` (see [`crate::iggy_client::RedeliveryPolicy`]): to the same
+    /// partition of the source topic, or to the retry topic, with its
+    /// `redelivery_count` incremented; past `max_redeliveries`, to the
+    /// dead-letter topic. Retry and dead-letter topics are created on first
+    /// use. The consumer's offset is NOT moved: ack past the nacked messages
+    /// once done with the rest of the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` for a position holding no message, or
+    /// the first failed read/send; messages before it stay requeued.
+    #[instrument(skip(self, offsets, policy), fields(count = offsets.len()))]
+    pub async fn nack(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        offsets: &[AckOffset],
+        policy: &RedeliveryPolicy,
+    ) -> AppResult<Vec<NackedMessage>> {
+        let mut positions = offsets.to_vec();
+        positions.sort_by_key(|ack| (ack.partition_id, ack.offset));
+        positions.dedup();
+
+        let mut nacked = Vec::with_capacity(positions.len());
+        for ack in positions {
+            let message = self.message_at(stream, topic, consumer_id, ack).await?;
+            let count = redelivery_count(&message).saturating_add(1);
+            let (destination, dead_lettered) = policy.destination(topic, count);
+
+            let partitioning = if destination == topic {
+                Partitioning::partition_id(ack.partition_id)
+            } else {
+                self.client.ensure_topic(stream, destination, 1).await?;
+                Partitioning::balanced()
+            };
+            self.client
+                .send_raw_messages(
+                    stream,
+                    destination,
+                    &[redelivery_message(&message, count)?],
+                    &partitioning,
+                )
+                .await?;
+            if dead_lettered {
+                warn!(
+                    stream,
+                    topic,
+                    partition_id = ack.partition_id,
+                    offset = ack.offset,
+                    redelivery_count = count,
+                    dlq_topic = destination,
+                    "Message dead-lettered after too many redeliveries"
+                );
+            }
+
+            nacked.push(NackedMessage {
+                partition_id: ack.partition_id,
+                offset: ack.offset,
+                redelivery_count: count,
+                topic: destination.to_string(),
+                dead_lettered,
+            });
+        }
+        Ok(nacked)
+    }
+
+    /// Read the raw message at one position, without committing.
+    async fn message_at(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        ack: AckOffset,
+    ) -> AppResult<IggyMessage> {
+        let params = PollParams::new(ack.partition_id, consumer_id)
+            .with_offset(ack.offset)
+            .with_count(1);
+        self.client
+            .poll_messages(stream, topic, params)
+            .await?
+            .messages
+            .into_iter()
+            .find(|msg| msg.header.offset == ack.offset)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "No message at offset {} on partition {}",
+                    ack.offset, ack.partition_id
+                ))
+            })
+    }
+
+    /// Record a successful poll in the registry. With auto-commit, Iggy
+    /// commits the offset of the last message returned.
+    fn record_poll(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        auto_commit: bool,
+        polled: &PolledMessages,
+    ) {
+        let committed = polled
+            .messages
+            .last()
+            .filter(|_| auto_commit)
+            .map(|msg| msg.header.offset);
+        self.registry
+            .record_poll(stream, topic, consumer_id, partition_id, committed);
+    }
+
+    /// Compute a standalone consumer's lag on every partition of a topic.
+    ///
+    /// Reads the topic's partition offsets, then the consumer's committed
+    /// offset per partition. Partitions are queried sequentially: lag is a
+    /// monitoring read, and fanning out would multiply load on the broker
+    /// for topics with many partitions.
+    #[instrument(skip(self))]
+    pub async fn consumer_lag(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+    ) -> AppResult<ConsumerLagResponse> {
+        let details = self.client.get_topic(stream, topic).await?;
+
+        let mut partitions = Vec::with_capacity(details.partitions.len());
+        for partition in &details.partitions {
+            let committed = self
+                .client
+                .get_consumer_offset(stream, topic, consumer_id, partition.id)
+                .await?;
+            partitions.push(PartitionLag {
+                partition_id: partition.id,
+                current_offset: partition.current_offset,
+                committed_offset: committed,
+                lag: partition_lag(
+                    partition.current_offset,
+                    partition.messages_count,
+                    committed,
+                ),
+            });
+        }
+        partitions.sort_by_key(|p| p.partition_id);
+
+        Ok(ConsumerLagResponse {
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            consumer_id,
+            total_lag: partitions.iter().map(|p| p.lag).sum(),
+            partitions,
         })
     }
 
@@ -106,44 +747,139 @@ impl ConsumerService {
     /// # Message Parsing
     ///
     /// - Successfully parsed messages are returned in the result
-    /// - Failed parsing is logged and the message is skipped
+    /// - Payloads tagged with a `content-encoding` header (compressed batch
+    ///   sends) are decompressed first; `size` stays the stored size
+    /// - Failed parsing or decompression is logged and the message is skipped
     /// - Invalid timestamps are logged and fall back to current time
-    fn parse_messages(&self, messages: &[IggyMessage]) -> Vec<ReceivedMessage> {
-        let mut parsed = Vec::with_capacity(messages.len());
-
-        for msg in messages {
-            match serde_json::from_slice::<Event>(&msg.payload) {
-                Ok(event) => {
-                    // Convert timestamp with proper error handling
-                    let timestamp =
-                        self.parse_timestamp(msg.header.timestamp as i64, msg.header.offset);
-
-                    parsed.push(ReceivedMessage {
-                        offset: msg.header.offset,
-                        timestamp,
-                        id: msg.header.id,
-                        event,
-                        size: msg.payload.len(),
-                    });
-                }
+    /// - Undecodable user headers are logged and dropped (the event is kept)
+    /// - With `target_version`, older events are upcast first; one that
+    ///   cannot be is kept at the version reached, with a warning pushed
+    ///   to `warnings`
+    fn parse_messages(
+        &self,
+        partition_id: u32,
+        messages: &[IggyMessage],
+        target_version: Option<u32>,
+        warnings: &mut Vec<PollWarning>,
+    ) -> Vec<ReceivedMessage> {
+        let parsed: Vec<ReceivedMessage> = messages
+            .iter()
+            .filter_map(|msg| self.parse_message(partition_id, msg, target_version, warnings))
+            .collect();
+
+        debug!(
+            total = messages.len(),
+            parsed = parsed.len(),
+            "Message parsing complete"
+        );
+        parsed
+    }
+
+    /// Parse one raw Iggy message, or `None` if it is skipped (see
+    /// [`Self::parse_messages`]).
+    fn parse_message(
+        &self,
+        partition_id: u32,
+        msg: &IggyMessage,
+        target_version: Option<u32>,
+        warnings: &mut Vec<PollWarning>,
+    ) -> Option<ReceivedMessage> {
+        let headers = self.parse_headers(msg);
+        let payload = match headers.get(CONTENT_ENCODING_HEADER) {
+            Some(encoding) => match decompress_payload(encoding, &msg.payload) {
+                Ok(decoded) => Cow::Owned(decoded),
                 Err(e) => {
                     warn!(
                         offset = msg.header.offset,
                         message_id = msg.header.id,
-                        payload_size = msg.payload.len(),
                         error = %e,
-                        "Failed to parse message as Event, skipping"
+                        "Failed to decompress message payload, skipping"
                     );
+                    return None;
                 }
+            },
+            None => Cow::Borrowed(msg.payload.as_ref()),
+        };
+
+        let parsed = match target_version {
+            Some(target_version) => self.upcast_event(msg, &payload, target_version, warnings),
+            None => serde_json::from_slice::<Event>(&payload),
+        };
+        match parsed {
+            Ok(event) => {
+                // Convert timestamp with proper error handling
+                let timestamp =
+                    self.parse_timestamp(msg.header.timestamp as i64, msg.header.offset);
+
+                Some(ReceivedMessage {
+                    partition_id,
+                    offset: msg.header.offset,
+                    timestamp,
+                    id: msg.header.id,
+                    checksum: msg.header.checksum,
+                    headers,
+                    event,
+                    size: msg.payload.len(),
+                })
+            }
+            Err(e) => {
+                warn!(
+                    offset = msg.header.offset,
+                    message_id = msg.header.id,
+                    payload_size = msg.payload.len(),
+                    error = %e,
+                    "Failed to parse message as Event, skipping"
+                );
+                None
             }
         }
+    }
 
-        debug!(
-            total = messages.len(),
-            parsed = parsed.len(),
-            "Message parsing complete"
-        );
-        parsed
+    /// Deserialize an event after upcasting it to `target_version`.
+    ///
+    /// A failed upcast is not fatal: the event is deserialized at the
+    /// version it reached, and an `upcast_failed` warning records why.
+    fn upcast_event(
+        &self,
+        msg: &IggyMessage,
+        payload: &[u8],
+        target_version: u32,
+        warnings: &mut Vec<PollWarning>,
+    ) -> serde_json::Result<Event> {
+        let mut event: serde_json::Value = serde_json::from_slice(payload)?;
+        if let Err(e) = self.upcasters.upcast(&mut event, target_version) {
+            debug!(offset = msg.header.offset, error = %e, "Event not upcast");
+            warnings.push(PollWarning::UpcastFailed {
+                offset: msg.header.offset,
+                event_type: e.event_type,
+                schema_version: e.schema_version,
+                error: e.reason,
+            });
+        }
+        serde_json::from_value(event)
+    }
+
+    /// Decode a message's user headers into a string map.
+    ///
+    /// Keys and values are rendered as their bare string form regardless of
+    /// the header kind, so the JSON response stays flat. A header block that fails to
+    /// decode is logged and treated as empty rather than dropping the event.
+    fn parse_headers(&self, msg: &IggyMessage) -> BTreeMap<String, String> {
+        match msg.user_headers_map() {
+            Ok(Some(headers)) => headers
+                .into_iter()
+                .map(|(key, value)| (key.to_string_value(), value.to_string_value()))
+                .collect(),
+            Ok(None) => BTreeMap::new(),
+            Err(e) => {
+                warn!(
+                    offset = msg.header.offset,
+                    error = %e,
+                    "Failed to decode message headers, omitting them"
+                );
+                BTreeMap::new()
+            }
+        }
     }
 
     /// Parse a microsecond timestamp to DateTime, logging invalid values.
@@ -173,10 +909,334 @@ impl ConsumerService {
     }
 }
 
+/// Opening of a streamed poll response, up to the first message.
+const RESPONSE_HEAD: &str = r#"{"messages":["#;
+
+/// Closing of a streamed poll response: ends the `messages` array and adds
+/// the fields of `tail`, serialized as [`PollMessagesResponse`] flattens
+/// them.
+fn response_tail(tail: &PollResponseTail) -> Vec<u8> {
+    let mut closing = b"],".to_vec();
+    match serde_json::to_vec(tail) {
+        // Splice the object's fields in after the array
+        Ok(json) => closing.extend(json.iter().skip(1)),
+        Err(e) => {
+            warn!(error = %e, "Failed to serialize poll response fields");
+            closing.push(b'}');
+        }
+    }
+    closing
+}
+
+/// Progress of a streamed poll response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    /// Nothing written yet
+    Start,
+    /// Inside the `messages` array
+    Messages,
+    /// Closing fields written
+    Done,
+}
+
+/// Iterator over the JSON chunks of a streamed poll response: the opening
+/// `{"messages":[`, one chunk per parsed message, then the closing fields.
+///
+/// Consumption metrics are recorded when the array is finished, so a client
+/// that disconnects mid-body is only counted for what it was sent.
+struct PollResponseChunks<B> {
+    consumer: ConsumerService<B>,
+    stream: String,
+    topic: String,
+    partition_id: u32,
+    consumer_id: u32,
+    target_version: Option<u32>,
+    current_offset: u64,
+    end_of_partition: bool,
+    server_poll_duration_ms: f64,
+    continuation: Option<String>,
+    messages: std::vec::IntoIter<IggyMessage>,
+    written: usize,
+    /// Parsed messages left out as duplicates
+    duplicates_filtered: usize,
+    /// Sequence warnings collected as messages are written
+    sequences: SequenceChecker,
+    warnings: Vec<PollWarning>,
+    state: ChunkState,
+}
+
+impl<B: MessageBroker> PollResponseChunks<B> {
+    /// Serialize the next parseable message, prefixed with a separator
+    /// after the first.
+    fn next_message(&mut self) -> Option<Bytes> {
+        for msg in self.messages.by_ref() {
+            let Some(parsed) = self.consumer.parse_message(
+                self.partition_id,
+                &msg,
+                self.target_version,
+                &mut self.warnings,
+            ) else {
+                continue;
+            };
+            if let Some(dedup) = &self.consumer.dedup
+                && !dedup.admits(&self.stream, &self.topic, self.consumer_id, &parsed)
+            {
+                self.duplicates_filtered += 1;
+                continue;
+            }
+            let mut chunk = if self.written == 0 {
+                Vec::new()
+            } else {
+                vec![b',']
+            };
+            if let Err(e) = serde_json::to_writer(&mut chunk, &parsed) {
+                warn!(offset = parsed.offset, error = %e, "Failed to serialize message, skipping");
+                continue;
+            }
+            self.written += 1;
+            self.warnings.extend(self.sequences.check(&parsed));
+            self.consumer.event_lag.record(
+                &self.stream,
+                &self.topic,
+                std::iter::once(&parsed),
+                Utc::now(),
+            );
+            return Some(Bytes::from(chunk));
+        }
+        None
+    }
+
+    /// Close the array with the trailing response fields and record the
+    /// consumption metrics.
+    fn finish(&mut self) -> Bytes {
+        let count = self.written;
+        self.consumer
+            .messages_consumed
+            .fetch_add(count as u64, Ordering::Relaxed);
+        crate::metrics::record_messages_polled(&self.stream, &self.topic, count as u64);
+        crate::metrics::record_poll_duplicates(
+            &self.stream,
+            &self.topic,
+            self.duplicates_filtered as u64,
+        );
+        debug!(parsed = count, "Streamed poll response complete");
+
+        Bytes::from(response_tail(&PollResponseTail {
+            count,
+            partition_id: self.partition_id,
+            current_offset: self.current_offset,
+            end_of_partition: self.end_of_partition,
+            server_poll_duration_ms: self.server_poll_duration_ms,
+            duplicates_filtered: self.duplicates_filtered,
+            warnings: std::mem::take(&mut self.warnings),
+            continuation: self.continuation.take(),
+        }))
+    }
+}
+
+impl<B: MessageBroker> Iterator for PollResponseChunks<B> {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Bytes> {
+        match self.state {
+            ChunkState::Start => {
+                self.state = ChunkState::Messages;
+                Some(Bytes::from_static(RESPONSE_HEAD.as_bytes()))
+            }
+            ChunkState::Messages => self.next_message().or_else(|| {
+                self.state = ChunkState::Done;
+                Some(self.finish())
+            }),
+            ChunkState::Done => None,
+        }
+    }
+}
+
+/// Whether `polled` reached the end of its partition: it returned nothing,
+/// or its last message is at the partition's current (last written)
+/// offset. Checked on the raw batch, so skipped messages still count.
+fn reached_end(polled: &PolledMessages) -> bool {
+    polled
+        .messages
+        .last()
+        .is_none_or(|message| message.header.offset >= polled.current_offset)
+}
+
+/// Lag of one partition: messages after the committed offset.
+///
+/// An empty partition has no lag. A consumer that never committed lags by
+/// everything up to and including `current_offset`. A committed offset
+/// ahead of the partition (e.g. after topic re-creation) reports zero
+/// rather than underflowing.
+fn partition_lag(current_offset: u64, messages_count: u64, committed: Option<u64>) -> u64 {
+    if messages_count == 0 {
+        return 0;
+    }
+    match committed {
+        Some(committed) => current_offset.saturating_sub(committed),
+        None => current_offset.saturating_add(1),
+    }
+}
+
+/// Offset just past the newest message to read from a partition whose last
+/// written offset is `current_offset`: past `until` if given and written,
+/// past the last message otherwise, and 0 for an empty partition.
+fn newest_range_end(current_offset: u64, messages_count: u64, until: Option<u64>) -> u64 {
+    if messages_count == 0 {
+        return 0;
+    }
+    until.unwrap_or(current_offset).min(current_offset) + 1
+}
+
+/// Collapse acknowledgments to the highest offset per partition, ordered by
+/// partition ID.
+fn latest_per_partition(offsets: &[AckOffset]) -> Vec<AckOffset> {
+    let mut latest = BTreeMap::new();
+    for ack in offsets {
+        let offset = latest.entry(ack.partition_id).or_insert(ack.offset);
+        *offset = (*offset).max(ack.offset);
+    }
+    latest
+        .into_iter()
+        .map(|(partition_id, offset)| AckOffset {
+            partition_id,
+            offset,
+        })
+        .collect()
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_partition_lag() {
+        // Empty partition: nothing to consume
+        assert_eq!(partition_lag(0, 0, None), 0);
+        // Never committed: everything through current_offset is pending
+        assert_eq!(partition_lag(9, 10, None), 10);
+        // Committed at 4 of 0..=9: offsets 5..=9 remain
+        assert_eq!(partition_lag(9, 10, Some(4)), 5);
+        // Fully caught up
+        assert_eq!(partition_lag(9, 10, Some(9)), 0);
+        // Committed offset ahead of the partition never underflows
+        assert_eq!(partition_lag(3, 4, Some(10)), 0);
+    }
+
+    #[test]
+    fn test_newest_range_end() {
+        // Empty partition: nothing to read
+        assert_eq!(newest_range_end(0, 0, None), 0);
+        // Offsets 0..=9: read up to the last message
+        assert_eq!(newest_range_end(9, 10, None), 10);
+        assert_eq!(newest_range_end(9, 10, Some(4)), 5);
+        // An offset past the last message reads from the last one
+        assert_eq!(newest_range_end(9, 10, Some(50)), 10);
+    }
+
+    #[test]
+    fn test_latest_per_partition_keeps_highest_offset() {
+        let ack = |partition_id, offset| AckOffset {
+            partition_id,
+            offset,
+        };
+        let offsets = [ack(2, 7), ack(0, 3), ack(2, 9), ack(2, 8)];
+        assert_eq!(latest_per_partition(&offsets), vec![ack(0, 3), ack(2, 9)]);
+        assert!(latest_per_partition(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_streamed_response_has_poll_response_shape() {
+        let tail = PollResponseTail {
+            count: 0,
+            partition_id: 2,
+            current_offset: 41,
+            end_of_partition: true,
+            server_poll_duration_ms: 0.25,
+            ..PollResponseTail::default()
+        };
+        let mut empty = RESPONSE_HEAD.as_bytes().to_vec();
+        empty.extend(response_tail(&tail));
+        let parsed: PollMessagesResponse = serde_json::from_slice(&empty).unwrap();
+        assert!(parsed.messages.is_empty());
+        let parsed = parsed.tail;
+        assert_eq!(parsed.count, 0);
+        assert_eq!(parsed.partition_id, 2);
+        assert_eq!(parsed.current_offset, 41);
+        assert!(parsed.end_of_partition);
+        assert_eq!(parsed.server_poll_duration_ms, 0.25);
+        assert_eq!(parsed.duplicates_filtered, 0);
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(parsed.continuation, None);
+
+        let message = ReceivedMessage {
+            partition_id: 2,
+            offset: 40,
+            timestamp: Utc::now(),
+            id: 7,
+            checksum: 1,
+            headers: BTreeMap::new(),
+            event: Event::new(
+                "test",
+                crate::models::EventPayload::Generic(serde_json::json!({})),
+            ),
+            size: 64,
+        };
+        let item = serde_json::to_string(&message).unwrap();
+        let warnings = [PollWarning::SequenceDuplicate {
+            key: "k".to_string(),
+            offset: 40,
+            expected: 2,
+            received: 1,
+        }];
+        let tail = PollResponseTail {
+            count: 2,
+            partition_id: 2,
+            current_offset: 41,
+            end_of_partition: false,
+            server_poll_duration_ms: 1.0,
+            duplicates_filtered: 3,
+            warnings: warnings.to_vec(),
+            continuation: Some("2.1.41.9.ab".to_string()),
+        };
+        let mut body = format!("{RESPONSE_HEAD}{item},{item}").into_bytes();
+        body.extend(response_tail(&tail));
+        let parsed: PollMessagesResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed.messages.len(), 2);
+        assert!(parsed.messages.iter().all(|m| m.offset == 40));
+        let parsed = parsed.tail;
+        assert_eq!(parsed.count, 2);
+        assert_eq!(parsed.duplicates_filtered, 3);
+        assert_eq!(parsed.warnings, warnings);
+        assert_eq!(parsed.continuation.as_deref(), Some("2.1.41.9.ab"));
+        assert!(!parsed.end_of_partition);
+    }
+
+    #[test]
+    fn test_reached_end_of_partition() {
+        let polled = |offsets: &[u64], current_offset| PolledMessages {
+            partition_id: 0,
+            current_offset,
+            count: offsets.len() as u32,
+            messages: offsets
+                .iter()
+                .map(|&offset| {
+                    let mut message = IggyMessage::builder()
+                        .payload(Bytes::from_static(b"{}"))
+                        .build()
+                        .unwrap();
+                    message.header.offset = offset;
+                    message
+                })
+                .collect(),
+        };
+        assert!(reached_end(&polled(&[], 0)));
+        assert!(reached_end(&polled(&[], 9)));
+        assert!(reached_end(&polled(&[8, 9], 9)));
+        assert!(!reached_end(&polled(&[7, 8], 9)));
+    }
+
     #[test]
     fn test_consumer_messages_counter() {
         let counter = AtomicU64::new(0);
@@ -0,0 +1,184 @@
+//! Signed continuation tokens for polls served by several replicas.
+//!
+//! A client polling without committing (no `auto_commit`, acks later)
+//! keeps its position between polls itself. Behind a load balancer its
+//! polls land on different replicas, none of which saw the previous one.
+//! With `POLL_CONTINUATION_SECRET` set, every poll response carries a
+//! `continuation` token naming the partition, consumer ID and next offset.
+//! Passing it back as `?continuation=` resumes there on any replica that
+//! shares the secret:
+//!
+//! ```text
+//! token = PARTITION "." CONSUMER "." OFFSET "." EXPIRES "." hex(HMAC-SHA256(secret, ...))
+//! ```
+//!
+//! The MAC also covers the stream and topic, so a token only resumes the
+//! topic it was issued for, and `EXPIRES` (Unix seconds) ends it after
+//! `POLL_CONTINUATION_TTL_SECS`. The position itself is readable: the
+//! token is tamper-proof, not secret.
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::{AppError, AppResult};
+use crate::utils::{hex, unhex, unix_now};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Where a continued poll resumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Continuation {
+    /// Partition polled
+    pub partition_id: u32,
+    /// Consumer ID polling
+    pub consumer_id: u32,
+    /// Offset of the next message to read
+    pub offset: u64,
+}
+
+/// Issuer and verifier of continuation tokens.
+#[derive(Debug, Default)]
+pub struct ContinuationTokens {
+    /// `None` when tokens are disabled
+    secret: Option<String>,
+    ttl: Duration,
+}
+
+impl ContinuationTokens {
+    /// Create tokens signed with `secret`, valid for `ttl` after issue.
+    /// Without a secret none are issued or accepted.
+    pub fn new(secret: Option<String>, ttl: Duration) -> Self {
+        Self { secret, ttl }
+    }
+
+    /// Check if poll responses carry tokens.
+    pub fn is_enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Token resuming `stream`/`topic` at `position`, if enabled.
+    pub fn issue(&self, stream: &str, topic: &str, position: Continuation) -> Option<String> {
+        let expires = unix_now().saturating_add(self.ttl.as_secs());
+        let fields = format!(
+            "{}.{}.{}.{expires}",
+            position.partition_id, position.consumer_id, position.offset
+        );
+        let mac = self.mac(stream, topic, &fields)?.finalize().into_bytes();
+        Some(format!("{fields}.{}", hex(&mac)))
+    }
+
+    /// Verify `token` for `stream`/`topic` and return where it resumes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` when tokens are disabled, or the
+    /// token is malformed, was issued for another topic or with another
+    /// secret, or has expired.
+    pub fn resume(&self, token: &str, stream: &str, topic: &str) -> AppResult<Continuation> {
+        let invalid = || AppError::BadRequest("Invalid continuation token".to_string());
+        let Some((fields, signature)) = token.rsplit_once('.') else {
+            return Err(invalid());
+        };
+        let Some(mac) = self.mac(stream, topic, fields) else {
+            return Err(AppError::BadRequest(
+                "Continuation tokens are disabled (POLL_CONTINUATION_SECRET unset)".to_string(),
+            ));
+        };
+        let signature = unhex(signature).ok_or_else(invalid)?;
+        mac.verify_slice(&signature).map_err(|_| invalid())?;
+
+        let parts: Vec<&str> = fields.split('.').collect();
+        let [partition_id, consumer_id, offset, expires] = parts[..] else {
+            return Err(invalid());
+        };
+        let expires: u64 = expires.parse().map_err(|_| invalid())?;
+        if expires < unix_now() {
+            return Err(AppError::BadRequest(
+                "Continuation token expired".to_string(),
+            ));
+        }
+        Ok(Continuation {
+            partition_id: partition_id.parse().map_err(|_| invalid())?,
+            consumer_id: consumer_id.parse().map_err(|_| invalid())?,
+            offset: offset.parse().map_err(|_| invalid())?,
+        })
+    }
+
+    /// MAC over `stream`, `topic` and the token's `fields`, once fed.
+    fn mac(&self, stream: &str, topic: &str, fields: &str) -> Option<HmacSha256> {
+        let secret = self.secret.as_ref()?;
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+        mac.update(format!("{stream}\n{topic}\n{fields}").as_bytes());
+        Some(mac)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> ContinuationTokens {
+        ContinuationTokens::new(Some("shared".to_string()), Duration::from_secs(60))
+    }
+
+    const POSITION: Continuation = Continuation {
+        partition_id: 2,
+        consumer_id: 7,
+        offset: 42,
+    };
+
+    #[test]
+    fn test_token_resumes_its_topic_only() {
+        let token = tokens().issue("orders", "events", POSITION).unwrap();
+        assert!(token.starts_with("2.7.42."));
+
+        // Another replica with the same secret
+        assert_eq!(
+            tokens().resume(&token, "orders", "events").unwrap(),
+            POSITION
+        );
+        assert!(tokens().resume(&token, "orders", "audit").is_err());
+
+        let other = ContinuationTokens::new(Some("other".to_string()), Duration::from_secs(60));
+        assert!(other.resume(&token, "orders", "events").is_err());
+        assert!(
+            ContinuationTokens::default()
+                .resume(&token, "orders", "events")
+                .unwrap_err()
+                .to_string()
+                .contains("disabled")
+        );
+        assert!(
+            ContinuationTokens::default()
+                .issue("orders", "events", POSITION)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tampered_or_expired_tokens_are_rejected() {
+        let token = tokens().issue("orders", "events", POSITION).unwrap();
+        let tampered = token.replacen("2.7.42.", "2.7.0.", 1);
+        assert!(tokens().resume(&tampered, "orders", "events").is_err());
+        assert!(tokens().resume("garbage", "orders", "events").is_err());
+        assert!(tokens().resume("1.2.3.4.zz", "orders", "events").is_err());
+
+        let expired = ContinuationTokens::new(Some("shared".to_string()), Duration::ZERO);
+        let token = expired.issue("orders", "events", POSITION).unwrap();
+        let (fields, _) = token.rsplit_once('.').unwrap();
+        let (position, _) = fields.rsplit_once('.').unwrap();
+        let stale = format!("{position}.1");
+        let mac = expired.mac("orders", "events", &stale).unwrap();
+        let stale = format!("{stale}.{}", hex(&mac.finalize().into_bytes()));
+        assert!(
+            expired
+                .resume(&stale, "orders", "events")
+                .unwrap_err()
+                .to_string()
+                .contains("expired")
+        );
+    }
+}
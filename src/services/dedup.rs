@@ -0,0 +1,226 @@
+//! Consumer-side deduplication of polled events.
+//!
+//! A producer retrying a send whose acknowledgment was lost writes the same
+//! event twice, at two offsets. With `POLL_DEDUP_WINDOW_SECS` set, polls
+//! remember the event IDs returned to each consumer (per stream, topic and
+//! consumer ID) and leave out an event whose ID was already returned from
+//! another position within the window. The response counts what was left
+//! out in `duplicates_filtered`.
+//!
+//! # What Is Not a Duplicate
+//!
+//! - The same message read again at the same partition and offset (a poll
+//!   retried without committing, or a rewind), so at-least-once redelivery
+//!   still reaches the consumer
+//! - Nack redeliveries (messages with a `redelivery_count` header), which
+//!   copy the event on purpose
+//!
+//! # Scope
+//!
+//! IDs are in-memory and per-instance, at most `POLL_DEDUP_MAX_IDS` of them
+//! (oldest forgotten first): a duplicate polled through another replica, or
+//! after a restart, is returned. Peeks are never deduplicated.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::iggy_client::REDELIVERY_COUNT_HEADER;
+use crate::models::ReceivedMessage;
+
+/// An event ID as returned to one consumer of one topic.
+type Key = (String, String, u32, Uuid);
+
+/// Where an event was returned from: partition ID and offset.
+type Position = (u32, u64);
+
+#[derive(Debug, Default)]
+struct DedupState {
+    positions: HashMap<Key, Position>,
+    /// Keys in the order they were first returned
+    order: VecDeque<(Instant, Key)>,
+}
+
+impl DedupState {
+    /// Forget the IDs first returned `window` or longer before `now`.
+    fn evict(&mut self, now: Instant, window: Duration) {
+        while let Some((seen_at, _)) = self.order.front()
+            && now.duration_since(*seen_at) >= window
+        {
+            self.pop_oldest();
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((_, key)) = self.order.pop_front() {
+            self.positions.remove(&key);
+        }
+    }
+}
+
+/// Recently returned event IDs per consumer, for filtering duplicates out
+/// of polls.
+#[derive(Debug)]
+pub struct PollDedup {
+    window: Duration,
+    max_ids: usize,
+    state: Mutex<DedupState>,
+}
+
+impl PollDedup {
+    /// Create a filter remembering IDs for `window`, at most `max_ids` at
+    /// once.
+    pub fn new(window: Duration, max_ids: usize) -> Self {
+        Self {
+            window,
+            max_ids,
+            state: Mutex::new(DedupState::default()),
+        }
+    }
+
+    /// Number of event IDs remembered.
+    pub fn len(&self) -> usize {
+        self.lock().positions.len()
+    }
+
+    /// Check if no event ID is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove from `messages` the duplicates of events already returned to
+    /// `consumer_id`, remembering the rest. Returns the number removed.
+    pub fn filter(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        messages: &mut Vec<ReceivedMessage>,
+    ) -> usize {
+        let now = Instant::now();
+        let before = messages.len();
+        messages.retain(|message| self.admits_at(stream, topic, consumer_id, message, now));
+        before - messages.len()
+    }
+
+    /// Check if `message` may be returned to `consumer_id`, remembering it
+    /// if so.
+    pub fn admits(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        message: &ReceivedMessage,
+    ) -> bool {
+        self.admits_at(stream, topic, consumer_id, message, Instant::now())
+    }
+
+    fn admits_at(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        message: &ReceivedMessage,
+        now: Instant,
+    ) -> bool {
+        if message.headers.contains_key(REDELIVERY_COUNT_HEADER) {
+            return true;
+        }
+        let key = (
+            stream.to_string(),
+            topic.to_string(),
+            consumer_id,
+            message.event.id,
+        );
+        let position = (message.partition_id, message.offset);
+
+        let mut state = self.lock();
+        state.evict(now, self.window);
+        if let Some(seen) = state.positions.get(&key) {
+            return *seen == position;
+        }
+        state.positions.insert(key.clone(), position);
+        state.order.push_back((now, key));
+        while state.order.len() > self.max_ids {
+            state.pop_oldest();
+        }
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DedupState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::models::{Event, EventPayload};
+
+    fn message(event: &Event, offset: u64) -> ReceivedMessage {
+        ReceivedMessage {
+            partition_id: 0,
+            offset,
+            timestamp: Utc::now(),
+            id: 0,
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event: event.clone(),
+            size: 64,
+        }
+    }
+
+    fn event() -> Event {
+        Event::new(
+            "order.created",
+            EventPayload::Generic(serde_json::json!({})),
+        )
+    }
+
+    #[test]
+    fn test_duplicates_at_other_offsets_are_filtered() {
+        let dedup = PollDedup::new(Duration::from_secs(60), 100);
+        let (first, second) = (event(), event());
+        let mut messages = vec![message(&first, 0), message(&second, 1), message(&first, 2)];
+        assert_eq!(dedup.filter("s", "t", 1, &mut messages), 1);
+        assert_eq!(messages.len(), 2);
+
+        // Re-reading the same offsets is not a duplicate
+        let mut again = vec![message(&first, 0), message(&second, 1)];
+        assert_eq!(dedup.filter("s", "t", 1, &mut again), 0);
+        // Other consumers keep their own IDs
+        let mut other = vec![message(&first, 2)];
+        assert_eq!(dedup.filter("s", "t", 2, &mut other), 0);
+
+        // Nack redeliveries copy the event on purpose
+        let mut redelivered = message(&first, 3);
+        redelivered
+            .headers
+            .insert(REDELIVERY_COUNT_HEADER.to_string(), "1".to_string());
+        assert!(dedup.admits("s", "t", 1, &redelivered));
+    }
+
+    #[test]
+    fn test_ids_are_forgotten_after_the_window_or_beyond_the_cap() {
+        let dedup = PollDedup::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+        let (first, second, third) = (event(), event(), event());
+        assert!(dedup.admits_at("s", "t", 1, &message(&first, 0), now));
+        assert!(!dedup.admits_at("s", "t", 1, &message(&first, 5), now));
+
+        let later = now + Duration::from_secs(60);
+        assert!(dedup.admits_at("s", "t", 1, &message(&first, 5), later));
+
+        assert!(dedup.admits_at("s", "t", 1, &message(&second, 6), later));
+        assert!(dedup.admits_at("s", "t", 1, &message(&third, 7), later));
+        assert_eq!(dedup.len(), 2);
+        assert!(dedup.admits_at("s", "t", 1, &message(&first, 8), later));
+    }
+}
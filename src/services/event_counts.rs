@@ -0,0 +1,264 @@
+//! Rolling counts of events by type, for `GET /analytics/event-counts`.
+//!
+//! With `EVENT_COUNTS_TOPIC` set, a background task reads that topic of the
+//! default stream every `EVENT_COUNTS_INTERVAL_SECS` and counts its events
+//! by `event_type` in 10-second buckets of the message timestamp. The
+//! endpoint sums the buckets into rolling windows of the last minute, five
+//! minutes and hour, each accurate to one bucket.
+//!
+//! # Reading Without Consumer State
+//!
+//! Like the message index (see [`super::MessageIndex`]), the counter reads
+//! with [`ConsumerService::read_tail`] from its own next offset per
+//! partition and never commits, so it does not disturb the topic's
+//! consumers. Startup reads each partition from the first message of the
+//! last hour, found by timestamp; older messages are never read.
+//!
+//! Counts are in-memory and per-instance: they are rebuilt on restart, and
+//! each replica counts the whole topic.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, instrument};
+
+use super::{ConsumerService, TailStart};
+use crate::error::AppResult;
+use crate::iggy_client::IggyClientWrapper;
+use crate::models::{EventCountWindow, EventCountsResponse, ReceivedMessage};
+
+/// Width of one bucket, in seconds.
+const BUCKET_SECS: i64 = 10;
+
+/// Messages read per partition per peek while catching up.
+const COUNT_POLL_COUNT: u32 = 1000;
+
+/// Length of the longest window, in seconds.
+const LONGEST_WINDOW_SECS: i64 = 3600;
+
+/// Reported windows: name and length in seconds, shortest first. The last
+/// is the longest kept.
+const WINDOWS: [(&str, i64); 3] = [("1m", 60), ("5m", 300), ("1h", LONGEST_WINDOW_SECS)];
+
+#[derive(Debug, Default)]
+struct CountState {
+    /// Events per type, by bucket start (Unix seconds)
+    buckets: BTreeMap<i64, HashMap<String, u64>>,
+    /// Next offset to read per partition
+    next_offsets: HashMap<u32, u64>,
+}
+
+impl CountState {
+    /// Count `messages` as of `now`, skipping those older than the longest
+    /// window. Returns the number counted.
+    fn record(&mut self, messages: &[ReceivedMessage], now: DateTime<Utc>) -> usize {
+        let oldest = oldest_bucket(now);
+        let mut counted = 0;
+        for message in messages {
+            let bucket = bucket_of(message.timestamp.timestamp());
+            if bucket < oldest {
+                continue;
+            }
+            *self
+                .buckets
+                .entry(bucket)
+                .or_default()
+                .entry(message.event.event_type.clone())
+                .or_default() += 1;
+            counted += 1;
+        }
+        counted
+    }
+
+    /// Drop buckets older than the longest window at `now`.
+    fn evict(&mut self, now: DateTime<Utc>) {
+        self.buckets = self.buckets.split_off(&oldest_bucket(now));
+    }
+
+    /// Counts per type over the last `secs` seconds at `now`.
+    fn window(&self, now: DateTime<Utc>, secs: i64) -> BTreeMap<String, u64> {
+        let first = bucket_of(now.timestamp() - secs) + BUCKET_SECS;
+        let mut counts = BTreeMap::new();
+        for bucket in self.buckets.range(first..).map(|(_, bucket)| bucket) {
+            for (event_type, count) in bucket {
+                *counts.entry(event_type.clone()).or_default() += count;
+            }
+        }
+        counts
+    }
+}
+
+/// Start of the bucket holding Unix second `secs`.
+fn bucket_of(secs: i64) -> i64 {
+    secs - secs.rem_euclid(BUCKET_SECS)
+}
+
+/// Start of the oldest bucket still in the longest window at `now`.
+fn oldest_bucket(now: DateTime<Utc>) -> i64 {
+    bucket_of(now.timestamp() - LONGEST_WINDOW_SECS) + BUCKET_SECS
+}
+
+/// Rolling event counts of one topic, by event type.
+pub struct EventCounter {
+    client: IggyClientWrapper,
+    consumer: ConsumerService,
+    stream: String,
+    /// `None` when counting is disabled
+    topic: Option<String>,
+    state: Mutex<CountState>,
+}
+
+impl EventCounter {
+    /// Create a counter of `stream`/`topic`; without a topic nothing is
+    /// counted.
+    pub fn new(client: IggyClientWrapper, stream: &str, topic: Option<&str>) -> Self {
+        Self {
+            consumer: ConsumerService::new(client.clone()),
+            client,
+            stream: stream.to_string(),
+            topic: topic.map(str::to_string),
+            state: Mutex::new(CountState::default()),
+        }
+    }
+
+    /// Check if a topic is counted (`EVENT_COUNTS_TOPIC` set).
+    pub fn is_enabled(&self) -> bool {
+        self.topic.is_some()
+    }
+
+    /// Counts of each window as of now, or `None` while disabled.
+    pub fn counts(&self) -> Option<EventCountsResponse> {
+        let topic = self.topic.as_ref()?;
+        let now = Utc::now();
+        let state = self.lock();
+        let windows = WINDOWS
+            .iter()
+            .map(|&(window, secs)| {
+                let counts = state.window(now, secs);
+                EventCountWindow {
+                    window: window.to_string(),
+                    window_secs: secs.unsigned_abs(),
+                    total: counts.values().sum(),
+                    counts,
+                }
+            })
+            .collect();
+        Some(EventCountsResponse {
+            stream: self.stream.clone(),
+            topic: topic.clone(),
+            as_of: now,
+            windows,
+        })
+    }
+
+    /// Count what was appended to each partition since the last call, then
+    /// drop buckets past the longest window. Returns the number counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed topic lookup or peek; what was read before
+    /// it stays counted and the next call resumes from there.
+    #[instrument(skip(self), fields(stream = %self.stream, topic = ?self.topic))]
+    pub async fn catch_up(&self) -> AppResult<usize> {
+        let Some(topic) = &self.topic else {
+            return Ok(0);
+        };
+        let partitions = self.client.get_topic(&self.stream, topic).await?.partitions;
+        let mut counted = 0;
+        for partition in &partitions {
+            counted += self.catch_up_partition(topic, partition.id).await?;
+        }
+        self.lock().evict(Utc::now());
+        debug!(counted, "Event counts caught up");
+        Ok(counted)
+    }
+
+    /// Read one partition from its next offset (or the start of the
+    /// longest window) up to its end.
+    async fn catch_up_partition(&self, topic: &str, partition_id: u32) -> AppResult<usize> {
+        let next = self.lock().next_offsets.get(&partition_id).copied();
+        let start = TailStart::resume(
+            next,
+            Utc::now() - chrono::Duration::seconds(LONGEST_WINDOW_SECS),
+        );
+        let mut counted = 0;
+        self.consumer
+            .read_tail(
+                &self.stream,
+                topic,
+                partition_id,
+                start,
+                COUNT_POLL_COUNT,
+                |messages, next| {
+                    let mut state = self.lock();
+                    counted += state.record(messages, Utc::now());
+                    state.next_offsets.insert(partition_id, next);
+                },
+            )
+            .await?;
+        Ok(counted)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CountState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+    use crate::models::{Event, EventPayload};
+
+    fn message(event_type: &str, timestamp: DateTime<Utc>) -> ReceivedMessage {
+        ReceivedMessage {
+            partition_id: 0,
+            offset: 0,
+            timestamp,
+            id: 0,
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event: Event::new(event_type, EventPayload::Generic(serde_json::json!({}))),
+            size: 64,
+        }
+    }
+
+    #[test]
+    fn test_windows_count_recent_events_by_type() {
+        let now = Utc::now();
+        let ago = |secs| now - chrono::Duration::seconds(secs);
+        let mut state = CountState::default();
+        let counted = state.record(
+            &[
+                message("order.created", now),
+                message("order.created", ago(30)),
+                message("order.paid", ago(120)),
+                message("order.created", ago(1200)),
+                message("order.created", ago(4000)),
+            ],
+            now,
+        );
+        assert_eq!(counted, 4);
+
+        let minute = state.window(now, 60);
+        assert_eq!(minute, BTreeMap::from([("order.created".to_string(), 2)]));
+        let five = state.window(now, 300);
+        assert_eq!(five["order.created"], 2);
+        assert_eq!(five["order.paid"], 1);
+        let hour = state.window(now, 3600);
+        assert_eq!(hour["order.created"], 3);
+    }
+
+    #[test]
+    fn test_buckets_past_the_longest_window_are_dropped() {
+        let now = Utc::now();
+        let mut state = CountState::default();
+        state.record(&[message("order.created", now)], now);
+
+        let later = now + chrono::Duration::seconds(3600 + BUCKET_SECS);
+        assert!(state.window(later, 3600).is_empty());
+        state.evict(later);
+        assert!(state.buckets.is_empty());
+    }
+}
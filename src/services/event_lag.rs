@@ -0,0 +1,157 @@
+//! Event-time lag of polled events, for pipeline freshness.
+//!
+//! The lag of an event is how long after its `timestamp` (event time) a
+//! consumer polled it (processing time). Every consuming poll records the
+//! lag of each returned event in the `iggy_event_time_lag_seconds`
+//! histogram, labeled by stream and topic, and keeps the last
+//! [`MAX_LAG_SAMPLES`] per topic in memory for the p50/p99 of `/stats`.
+//!
+//! Peeks are not recorded. Events stamped ahead of this instance's clock
+//! count as no lag. Samples are per-instance and lost on restart.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{ReceivedMessage, TopicEventTimeLag};
+use crate::utils::percentile;
+
+/// Recent lag samples kept per topic.
+pub const MAX_LAG_SAMPLES: usize = 1024;
+
+/// Recent event-time lags per (stream, topic).
+#[derive(Debug, Default)]
+pub struct EventTimeLag {
+    samples: Mutex<BTreeMap<(String, String), VecDeque<f64>>>,
+}
+
+impl EventTimeLag {
+    /// Create a tracker with no samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the lag of each of `messages` polled from `stream`/`topic`
+    /// at `now`.
+    pub fn record<'a>(
+        &self,
+        stream: &str,
+        topic: &str,
+        messages: impl IntoIterator<Item = &'a ReceivedMessage>,
+        now: DateTime<Utc>,
+    ) {
+        let lags: Vec<f64> = messages
+            .into_iter()
+            .map(|message| lag_seconds(message.event.timestamp, now))
+            .collect();
+        if lags.is_empty() {
+            return;
+        }
+        for &lag in &lags {
+            crate::metrics::record_event_time_lag(stream, topic, lag);
+        }
+
+        let mut samples = self.lock();
+        let topic_samples = samples
+            .entry((stream.to_string(), topic.to_string()))
+            .or_default();
+        topic_samples.extend(lags);
+        while topic_samples.len() > MAX_LAG_SAMPLES {
+            topic_samples.pop_front();
+        }
+    }
+
+    /// p50 and p99 of the recent samples of each topic polled, ordered by
+    /// stream and topic.
+    pub fn summary(&self) -> Vec<TopicEventTimeLag> {
+        self.lock()
+            .iter()
+            .map(|((stream, topic), samples)| {
+                let mut sorted: Vec<f64> = samples.iter().copied().collect();
+                sorted.sort_unstable_by(f64::total_cmp);
+                TopicEventTimeLag {
+                    stream: stream.clone(),
+                    topic: topic.clone(),
+                    samples: sorted.len(),
+                    p50_seconds: percentile(&sorted, 0.5),
+                    p99_seconds: percentile(&sorted, 0.99),
+                }
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<(String, String), VecDeque<f64>>> {
+        self.samples.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Seconds from `event_time` to `now`, or 0 for an event from the future.
+fn lag_seconds(event_time: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    (now - event_time)
+        .to_std()
+        .map_or(0.0, |lag| lag.as_secs_f64())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::models::{Event, EventPayload};
+
+    fn message(event_time: DateTime<Utc>) -> ReceivedMessage {
+        let mut event = Event::new("x", EventPayload::Generic(serde_json::json!({})));
+        event.timestamp = event_time;
+        ReceivedMessage {
+            partition_id: 0,
+            offset: 0,
+            timestamp: Utc::now(),
+            id: 0,
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event,
+            size: 64,
+        }
+    }
+
+    #[test]
+    fn test_summary_reports_percentiles_per_topic() {
+        let lag = EventTimeLag::new();
+        let now = Utc::now();
+        let messages: Vec<_> = (1..=100)
+            .map(|secs| message(now - chrono::Duration::seconds(secs)))
+            .collect();
+        lag.record("s", "t", &messages, now);
+        // Clock skew: an event from the future has no lag
+        lag.record(
+            "s",
+            "u",
+            &[message(now + chrono::Duration::seconds(5))],
+            now,
+        );
+
+        let summary = lag.summary();
+        assert_eq!(summary.len(), 2);
+        let first = summary.first().unwrap();
+        assert_eq!((first.topic.as_str(), first.samples), ("t", 100));
+        assert_eq!(first.p50_seconds, 50.0);
+        assert_eq!(first.p99_seconds, 99.0);
+        let second = summary.get(1).unwrap();
+        assert_eq!((second.samples, second.p99_seconds), (1, 0.0));
+    }
+
+    #[test]
+    fn test_only_the_most_recent_samples_are_kept() {
+        let lag = EventTimeLag::new();
+        let now = Utc::now();
+        let old = [message(now - chrono::Duration::seconds(600))];
+        lag.record("s", "t", &old, now);
+        let recent: Vec<_> = (0..MAX_LAG_SAMPLES).map(|_| message(now)).collect();
+        lag.record("s", "t", &recent, now);
+
+        let summary = lag.summary();
+        let topic = summary.first().unwrap();
+        assert_eq!(topic.samples, MAX_LAG_SAMPLES);
+        assert_eq!(topic.p99_seconds, 0.0);
+    }
+}
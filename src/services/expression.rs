@@ -0,0 +1,276 @@
+//! Predicate expressions over events, in the Rhai language.
+//!
+//! Poll filters (`?filter=`), fan-out routing (a destination's `when`) and
+//! pipelines (`expression`) take an expression that evaluates to a boolean:
+//!
+//! ```text
+//! event_type == "order.created" && payload.total >= 100 && payload.items.len() > 1
+//! ```
+//!
+//! # Variables
+//!
+//! - `event_type` - the event's type
+//! - `source` - its source system, or `()` when unset
+//! - `payload` - the payload's `data`
+//! - `event` - the whole event as a map (`event.id`, `event.timestamp`, ...)
+//!
+//! # Validation and Limits
+//!
+//! Expressions are compiled when they are registered: when a pipeline is
+//! defined, or a poll or fan-out request arrives. Syntax errors and unknown
+//! variables are rejected with 400 before any event is evaluated. Only a
+//! single expression is accepted (no statements or loops), and `print`,
+//! `debug` and `eval` are unavailable.
+//!
+//! Every evaluation is bounded by `EXPRESSION_MAX_OPERATIONS` and
+//! `EXPRESSION_TIMEOUT_MS`. An evaluation that exceeds either, or that
+//! does not return a boolean, fails; each caller documents what a failure
+//! means for the event.
+
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Scope};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+use crate::models::Event;
+
+/// Longest accepted expression, in bytes.
+pub const MAX_EXPRESSION_LEN: usize = 4096;
+
+/// Operations between two checks of the evaluation deadline.
+const DEADLINE_CHECK_INTERVAL: u64 = 256;
+
+/// Variables an expression can read.
+const VARIABLES: [&str; 4] = ["event", "event_type", "source", "payload"];
+
+thread_local! {
+    /// Deadline of the evaluation running on this thread
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Compiles predicates and bounds their evaluations.
+pub struct Expressions {
+    engine: Engine,
+    timeout: Duration,
+}
+
+impl Expressions {
+    /// Create an engine whose evaluations perform at most `max_operations`
+    /// operations and run for at most `timeout`.
+    pub fn new(max_operations: u64, timeout: Duration) -> Self {
+        let mut engine = Engine::new();
+        engine
+            .set_strict_variables(true)
+            .set_max_operations(max_operations)
+            .set_max_expr_depths(64, 32)
+            .set_max_call_levels(16)
+            .set_max_string_size(64 * 1024)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000)
+            .disable_symbol("eval")
+            .on_print(|_| {})
+            .on_debug(|_, _, _| {})
+            .on_progress(|operations| {
+                let expired = operations % DEADLINE_CHECK_INTERVAL == 0
+                    && DEADLINE
+                        .get()
+                        .is_some_and(|deadline| Instant::now() >= deadline);
+                expired.then_some(Dynamic::UNIT)
+            });
+        Self { engine, timeout }
+    }
+
+    /// Compile `source` into a predicate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` for an expression that is too long,
+    /// does not parse, or reads an unknown variable.
+    pub fn compile(self: &Arc<Self>, source: &str) -> AppResult<Predicate> {
+        if source.len() > MAX_EXPRESSION_LEN {
+            return Err(AppError::BadRequest(format!(
+                "Expression exceeds maximum length of {MAX_EXPRESSION_LEN} bytes"
+            )));
+        }
+        let mut scope = Scope::new();
+        for name in VARIABLES {
+            scope.push_dynamic(name, Dynamic::UNIT);
+        }
+        let ast = self
+            .engine
+            .compile_expression_with_scope(&scope, source)
+            .map_err(|e| AppError::BadRequest(format!("Invalid expression '{source}': {e}")))?;
+        Ok(Predicate {
+            source: source.to_string(),
+            ast,
+            expressions: Arc::clone(self),
+        })
+    }
+}
+
+/// A compiled expression, evaluated against one event at a time.
+#[derive(Clone)]
+pub struct Predicate {
+    source: String,
+    ast: AST,
+    expressions: Arc<Expressions>,
+}
+
+impl Predicate {
+    /// The expression as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the predicate against `event`.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the evaluation failed: a runtime error, a result that is
+    /// not a boolean, or a limit exceeded.
+    pub fn matches(&self, event: &Event) -> Result<bool, String> {
+        self.evaluate(event, None)
+    }
+
+    /// Evaluate the predicate against `event`, stopping at `deadline` if
+    /// that comes before the evaluation's own timeout.
+    ///
+    /// # Errors
+    ///
+    /// As [`Predicate::matches`], or the deadline passed.
+    pub fn matches_before(&self, event: &Event, deadline: Instant) -> Result<bool, String> {
+        self.evaluate(event, Some(deadline))
+    }
+
+    fn evaluate(&self, event: &Event, deadline: Option<Instant>) -> Result<bool, String> {
+        let mut scope = scope_of(event)?;
+        let timeout = self.expressions.timeout;
+        let own_deadline = Instant::now() + timeout;
+        let cut_short = deadline.is_some_and(|deadline| deadline < own_deadline);
+        DEADLINE.set(Some(deadline.map_or(own_deadline, |d| d.min(own_deadline))));
+        let result = self
+            .expressions
+            .engine
+            .eval_ast_with_scope::<bool>(&mut scope, &self.ast);
+        DEADLINE.set(None);
+        result.map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) if cut_short => {
+                "Expression stopped at the request's evaluation budget".to_string()
+            }
+            EvalAltResult::ErrorTerminated(..) => {
+                format!("Expression timed out after {} ms", timeout.as_millis())
+            }
+            e => e.to_string(),
+        })
+    }
+}
+
+impl fmt::Debug for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Predicate").field(&self.source).finish()
+    }
+}
+
+/// The variables of an evaluation against `event`.
+fn scope_of(event: &Event) -> Result<Scope<'static>, String> {
+    let to_dynamic = |value: &Value| rhai::serde::to_dynamic(value).map_err(|e| e.to_string());
+    let value = serde_json::to_value(event).map_err(|e| e.to_string())?;
+    let payload = value.pointer("/payload/data").unwrap_or(&Value::Null);
+
+    let mut scope = Scope::new();
+    scope.push_dynamic("event_type", Dynamic::from(event.event_type.clone()));
+    scope.push_dynamic(
+        "source",
+        event.source.clone().map_or(Dynamic::UNIT, Dynamic::from),
+    );
+    scope.push_dynamic("payload", to_dynamic(payload)?);
+    scope.push_dynamic("event", to_dynamic(&value)?);
+    Ok(scope)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::models::EventPayload;
+    use serde_json::json;
+
+    fn event(event_type: &str, data: Value) -> Event {
+        Event::new(event_type, EventPayload::Generic(data))
+    }
+
+    fn expressions() -> Arc<Expressions> {
+        Arc::new(Expressions::new(100_000, Duration::from_millis(100)))
+    }
+
+    #[test]
+    fn test_predicates_read_the_event() {
+        let expressions = expressions();
+        let predicate = expressions
+            .compile(r#"event_type == "order.created" && payload.total >= 100"#)
+            .unwrap();
+        assert!(
+            predicate
+                .matches(&event("order.created", json!({"total": 150})))
+                .unwrap()
+        );
+        assert!(
+            !predicate
+                .matches(&event("order.created", json!({"total": 99})))
+                .unwrap()
+        );
+        assert!(
+            !predicate
+                .matches(&event("order.paid", json!({"total": 150})))
+                .unwrap()
+        );
+
+        let predicate = expressions
+            .compile(r#"source == () && payload.items.len() > 1"#)
+            .unwrap();
+        assert!(
+            predicate
+                .matches(&event("x", json!({"items": [1, 2]})))
+                .unwrap()
+        );
+        let sourced = event("x", json!({"items": [1, 2]})).with_source("billing");
+        assert!(!predicate.matches(&sourced).unwrap());
+
+        let predicate = expressions
+            .compile("event.event_type.starts_with(\"x.\")")
+            .unwrap();
+        assert!(predicate.matches(&event("x.y", json!({}))).unwrap());
+        assert_eq!(predicate.source(), "event.event_type.starts_with(\"x.\")");
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected_at_compile_time() {
+        let expressions = expressions();
+        for invalid in [
+            "event_type ==",
+            "unknown_variable > 1",
+            "let x = 1; x > 0",
+            "eval(\"true\")",
+        ] {
+            assert!(expressions.compile(invalid).is_err(), "{invalid}");
+        }
+        let long = format!("payload.x == \"{}\"", "a".repeat(MAX_EXPRESSION_LEN));
+        assert!(expressions.compile(&long).is_err());
+    }
+
+    #[test]
+    fn test_evaluations_must_return_booleans_within_limits() {
+        let predicate = expressions().compile("payload.total + 1").unwrap();
+        assert!(predicate.matches(&event("x", json!({"total": 1}))).is_err());
+
+        let limited = Arc::new(Expressions::new(3, Duration::from_millis(100)));
+        let predicate = limited
+            .compile("payload.n + payload.n + payload.n + payload.n + payload.n > 0")
+            .unwrap();
+        assert!(predicate.matches(&event("x", json!({"n": 1}))).is_err());
+    }
+}
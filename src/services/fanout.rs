@@ -0,0 +1,168 @@
+//! Fan-out: one event produced to several topics in one call.
+//!
+//! `POST /messages/fanout` sends an event to a list of `stream/topic`
+//! destinations, or to a named group from `FANOUT_GROUPS`:
+//!
+//! ```text
+//! FANOUT_GROUPS=orders:orders/created+analytics/orders,payments:payments/settled+ops/audit
+//! ```
+//!
+//! # Delivery
+//!
+//! Destinations are sent to concurrently, each as a normal send (so
+//! shadowing, the tap and enrichment apply per destination), and each gets
+//! its own result. There is no transaction across topics: a destination
+//! that fails does not undo the others, and fan-outs are never spooled.
+//! The same event ID is written to every destination, so consumers of
+//! several of them can de-duplicate.
+//!
+//! # Routing
+//!
+//! A destination with a `when` predicate (see [`super::Expressions`])
+//! only gets the event when the predicate holds; otherwise its result is
+//! `skipped`. A predicate that fails to evaluate fails its destination
+//! like a failed send.
+
+use std::fmt;
+use std::str::FromStr;
+
+use futures_util::future::join_all;
+
+use super::{Predicate, ProducerService};
+use crate::iggy_client::IggyOperations;
+use crate::models::{Event, FanoutDestination, FanoutResult};
+
+/// A named list of destinations (`FANOUT_GROUPS`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanoutGroup {
+    /// Name requests refer to the group by
+    pub name: String,
+    /// Topics the group's fan-outs are sent to
+    pub destinations: Vec<FanoutDestination>,
+}
+
+impl FromStr for FanoutGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid fanout group '{s}' (expected name:stream/topic+...)");
+        let (name, destinations) = s.split_once(':').ok_or_else(invalid)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        let destinations = destinations
+            .split('+')
+            .map(|target| {
+                target
+                    .trim()
+                    .split_once('/')
+                    .filter(|(stream, topic)| {
+                        !stream.is_empty() && !topic.is_empty() && !topic.contains('/')
+                    })
+                    .map(|(stream, topic)| FanoutDestination {
+                        stream: stream.to_string(),
+                        topic: topic.to_string(),
+                        when: None,
+                    })
+                    .ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            name: name.to_string(),
+            destinations,
+        })
+    }
+}
+
+impl fmt::Display for FanoutGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+        for (index, destination) in self.destinations.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "+" };
+            write!(f, "{separator}{}/{}", destination.stream, destination.topic)?;
+        }
+        Ok(())
+    }
+}
+
+/// A fan-out destination with its compiled `when`.
+#[derive(Debug, Clone)]
+pub struct FanoutRoute {
+    /// Topic the event may be sent to
+    pub destination: FanoutDestination,
+    /// Predicate the event must match to be sent (None = always)
+    pub when: Option<Predicate>,
+}
+
+/// Send `event` to every route it matches at once, returning one result
+/// per route in the same order.
+pub async fn fan_out<C: IggyOperations>(
+    producer: &ProducerService<C>,
+    event: &Event,
+    routes: &[FanoutRoute],
+    partition_key: Option<&str>,
+) -> Vec<FanoutResult> {
+    let sends = routes.iter().map(|route| async move {
+        let destination = &route.destination;
+        let routed = route
+            .when
+            .as_ref()
+            .map_or(Ok(true), |when| when.matches(event));
+        let sent = match routed {
+            Ok(true) => producer
+                .send_to(
+                    &destination.stream,
+                    &destination.topic,
+                    event,
+                    partition_key,
+                    None,
+                )
+                .await
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            Ok(false) => Ok(None),
+            Err(e) => Err(format!("Routing expression failed: {e}")),
+        };
+        FanoutResult {
+            stream: destination.stream.clone(),
+            topic: destination.topic.clone(),
+            success: matches!(sent, Ok(Some(_))),
+            skipped: matches!(sent, Ok(None)),
+            timestamp: sent
+                .as_ref()
+                .ok()
+                .and_then(Option::as_ref)
+                .map(|response| response.timestamp),
+            error: sent.err(),
+        }
+    });
+    join_all(sends).await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_parsing() {
+        let group: FanoutGroup = "orders:orders/created+analytics/orders".parse().unwrap();
+        assert_eq!(group.name, "orders");
+        assert_eq!(group.destinations.len(), 2);
+        assert_eq!(group.destinations[1].stream, "analytics");
+        assert_eq!(group.destinations[1].topic, "orders");
+        assert_eq!(group.to_string(), "orders:orders/created+analytics/orders");
+
+        for invalid in [
+            "orders",
+            ":orders/created",
+            "orders:orders",
+            "orders:orders/created+",
+            "orders:orders/created/x",
+        ] {
+            assert!(invalid.parse::<FanoutGroup>().is_err(), "{invalid}");
+        }
+    }
+}
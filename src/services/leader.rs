@@ -0,0 +1,504 @@
+//! Leader election for singleton background tasks.
+//!
+//! Some background tasks act on state shared by every replica, and running
+//! them once per replica multiplies their effect. With
+//! `LEADER_ELECTION_LEASE_SECS` set, the replicas elect a leader through a
+//! lease kept in the `LEADER_ELECTION_TOPIC` (single partition, default
+//! stream), and only the leader runs:
+//!
+//! - retention enforcement of the bootstrap spec's policies
+//! - recurring (cron) schedules
+//! - delayed delivery, from a `SCHEDULED_TOPIC` every replica follows
+//!
+//! Without leader election every replica leads, as before.
+//!
+//! # Lease
+//!
+//! The topic is a log of `claim`, `renew` and `release` records naming a
+//! candidate (`LEADER_ELECTION_ID`, default: `HOSTNAME`). Each replica
+//! replays the log in offset order by the same rules, so all agree on the
+//! holder:
+//!
+//! - a claim takes the lease when it is free: released, or not renewed for
+//!   a whole lease before the claim (by Iggy's timestamps). Of two claims
+//!   racing for a free lease, the first written wins. A won claim starts a
+//!   term, numbered by the claim's offset
+//! - a renew or release counts only for the current term; a renew of a
+//!   later term is adopted, so a replica that started reading mid-term
+//!   learns the holder from its next renew
+//!
+//! Every third of the lease, the holder renews and any other replica that
+//! sees the lease free claims it; each then reads the log back for the
+//! outcome. On shutdown the leader releases the lease, so another replica
+//! takes over on its next tick rather than a lease later.
+//!
+//! A replica starting up reads only the last record; the log is never
+//! replayed from the start, and a retention policy in the bootstrap spec
+//! keeps it bounded.
+//!
+//! # Clocks
+//!
+//! A holder stops acting as leader one lease after it sent its last
+//! accepted record, by its own clock, even while Iggy is unreachable. The
+//! lease only becomes claimable later, by Iggy's clock, so two replicas
+//! never lead at once as long as the clocks agree to well within a lease.
+//! Candidate IDs must be unique: replicas sharing one lead together.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use chrono::Utc;
+use iggy::prelude::Partitioning;
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::iggy_client::{IggyClientWrapper, PollParams, payload_message};
+use crate::models::LeadershipStatus;
+
+/// The lease topic has a single partition; records are replayed in order.
+const LEASE_PARTITION_ID: u32 = 0;
+
+/// Consumer ID used for lease reads (offset-based; nothing is committed).
+const LEASE_CONSUMER_ID: u32 = 1;
+
+/// Records read per poll while catching up.
+const LEASE_POLL_COUNT: u32 = 100;
+
+/// One record of the lease log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum LeaseRecord {
+    Claim { candidate: String },
+    Renew { candidate: String, term: u64 },
+    Release { candidate: String, term: u64 },
+}
+
+/// The lease as replayed so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Lease {
+    /// `None` until a replica that started reading mid-term sees a renew
+    holder: Option<String>,
+    /// Offset of the claim that started the term (0 while unknown)
+    term: u64,
+    /// Iggy timestamp of the term's last accepted record, in microseconds
+    renewed_at: u64,
+}
+
+#[derive(Debug, Default)]
+struct ElectionState {
+    /// `None` while the lease is free
+    lease: Option<Lease>,
+    /// Next offset to read; `None` before the first read
+    next_offset: Option<u64>,
+    /// When this replica stops leading, by its own clock
+    leading_until: Option<Instant>,
+}
+
+impl ElectionState {
+    /// Check if the lease is free at `timestamp` (microseconds).
+    fn is_free_at(&self, timestamp: u64, lease_micros: u64) -> bool {
+        self.lease
+            .as_ref()
+            .is_none_or(|lease| timestamp > lease.renewed_at.saturating_add(lease_micros))
+    }
+
+    /// Replay one record written at `offset` and `timestamp`.
+    fn apply(&mut self, record: LeaseRecord, offset: u64, timestamp: u64, lease_micros: u64) {
+        match record {
+            LeaseRecord::Claim { candidate } => {
+                if self.is_free_at(timestamp, lease_micros) {
+                    self.lease = Some(Lease {
+                        holder: Some(candidate),
+                        term: offset,
+                        renewed_at: timestamp,
+                    });
+                }
+            }
+            LeaseRecord::Renew { candidate, term } => match &mut self.lease {
+                Some(lease) if term < lease.term => {}
+                Some(lease) if term == lease.term => {
+                    lease.holder = Some(candidate);
+                    lease.renewed_at = timestamp;
+                }
+                _ => {
+                    self.lease = Some(Lease {
+                        holder: Some(candidate),
+                        term,
+                        renewed_at: timestamp,
+                    });
+                }
+            },
+            LeaseRecord::Release { term, .. } => {
+                if self.lease.as_ref().is_some_and(|lease| term >= lease.term) {
+                    self.lease = None;
+                }
+            }
+        }
+    }
+
+    /// Replay the record a replica starts reading from. A claim there may
+    /// have lost to an earlier one, so it only tells that the lease was
+    /// held at `timestamp`, by a holder still unknown.
+    fn apply_first(&mut self, record: LeaseRecord, offset: u64, timestamp: u64, lease_micros: u64) {
+        if matches!(record, LeaseRecord::Claim { .. }) {
+            self.lease = Some(Lease {
+                holder: None,
+                term: 0,
+                renewed_at: timestamp,
+            });
+        } else {
+            self.apply(record, offset, timestamp, lease_micros);
+        }
+    }
+
+    /// The record this replica writes on a tick at `now`: a renew while it
+    /// holds the lease, a claim while the lease is free, otherwise none.
+    fn next_record(&self, candidate: &str, now: u64, lease_micros: u64) -> Option<LeaseRecord> {
+        match &self.lease {
+            Some(lease) if lease.holder.as_deref() == Some(candidate) => Some(LeaseRecord::Renew {
+                candidate: candidate.to_string(),
+                term: lease.term,
+            }),
+            _ if self.is_free_at(now, lease_micros) => Some(LeaseRecord::Claim {
+                candidate: candidate.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Elects one leader among the replicas sharing a lease topic.
+pub struct LeaderElection {
+    client: IggyClientWrapper,
+    stream: String,
+    topic: String,
+    candidate: String,
+    /// Zero when disabled
+    lease: Duration,
+    state: Mutex<ElectionState>,
+    /// Set once the lease stream and topic are known to exist
+    ready: OnceCell<()>,
+}
+
+impl LeaderElection {
+    /// Create an election through `topic` of `stream`, standing as
+    /// `candidate` (a random ID when `None`). A zero `lease` disables it,
+    /// and this replica always leads.
+    pub fn new(
+        client: IggyClientWrapper,
+        stream: &str,
+        topic: &str,
+        candidate: Option<&str>,
+        lease: Duration,
+    ) -> Self {
+        Self {
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            candidate: candidate.map_or_else(|| Uuid::new_v4().to_string(), str::to_string),
+            lease,
+            state: Mutex::new(ElectionState::default()),
+            ready: OnceCell::new(),
+        }
+    }
+
+    /// Check if leadership is elected rather than assumed.
+    pub fn is_enabled(&self) -> bool {
+        !self.lease.is_zero()
+    }
+
+    /// How often the lease is renewed or claimed: a third of the lease.
+    pub fn renew_interval(&self) -> Duration {
+        self.lease / 3
+    }
+
+    /// Check if this replica should run singleton tasks now.
+    pub fn is_leader(&self) -> bool {
+        !self.is_enabled()
+            || self
+                .lock()
+                .leading_until
+                .is_some_and(|until| until > Instant::now())
+    }
+
+    /// This replica's view of the election.
+    pub fn status(&self) -> LeadershipStatus {
+        let leader = self.is_leader();
+        let state = self.lock();
+        LeadershipStatus {
+            leader,
+            candidate: self.candidate.clone(),
+            holder: state.lease.as_ref().and_then(|lease| lease.holder.clone()),
+            lease_secs: self.lease.as_secs(),
+        }
+    }
+
+    /// Read the log, renew or claim the lease as due, and read the outcome.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed read or write. This replica keeps leading
+    /// until its lease runs out by its own clock, so a tick may fail now
+    /// and then without a change of leader.
+    pub async fn tick(&self) -> AppResult<()> {
+        self.ensure_topic().await?;
+        self.catch_up().await?;
+
+        let lease_micros = self.lease_micros();
+        let now = micros_now();
+        let record = self.lock().next_record(&self.candidate, now, lease_micros);
+        let Some(record) = record else {
+            self.step_down();
+            return Ok(());
+        };
+        let sent_at = Instant::now();
+        self.append(&record).await?;
+        self.catch_up().await?;
+
+        let mut state = self.lock();
+        let was_leader = state.leading_until.is_some_and(|until| until > sent_at);
+        let holds = state
+            .lease
+            .as_ref()
+            .is_some_and(|lease| lease.holder.as_deref() == Some(self.candidate.as_str()));
+        if holds {
+            state.leading_until = Some(sent_at + self.lease);
+            if !was_leader {
+                info!(candidate = %self.candidate, "Acquired leadership");
+            }
+        } else {
+            state.leading_until = None;
+            if was_leader {
+                warn!(candidate = %self.candidate, "Lost leadership");
+            }
+        }
+        Ok(())
+    }
+
+    /// Give up the lease if held, so another replica takes over on its
+    /// next tick. Never fails: a write error is logged, and the lease then
+    /// runs out instead.
+    pub async fn release(&self) {
+        let term = {
+            let mut state = self.lock();
+            let held = state.leading_until.take().is_some();
+            match &state.lease {
+                Some(lease) if held && lease.holder.as_deref() == Some(self.candidate.as_str()) => {
+                    lease.term
+                }
+                _ => return,
+            }
+        };
+        let record = LeaseRecord::Release {
+            candidate: self.candidate.clone(),
+            term,
+        };
+        match self.append(&record).await {
+            Ok(()) => info!(candidate = %self.candidate, "Released leadership"),
+            Err(e) => warn!(error = %e, "Failed to release leadership"),
+        }
+    }
+
+    /// Stop leading, logging if this replica was the leader.
+    fn step_down(&self) {
+        let mut state = self.lock();
+        if state
+            .leading_until
+            .take()
+            .is_some_and(|until| until > Instant::now())
+        {
+            warn!(candidate = %self.candidate, "Lost leadership");
+        }
+    }
+
+    /// Replay what was appended to the log since the last read.
+    async fn catch_up(&self) -> AppResult<()> {
+        let lease_micros = self.lease_micros();
+        let mut first = false;
+        let next_offset = self.lock().next_offset;
+        let mut offset = match next_offset {
+            Some(offset) => offset,
+            None => {
+                first = true;
+                self.last_offset().await?
+            }
+        };
+        loop {
+            let params = PollParams::new(LEASE_PARTITION_ID, LEASE_CONSUMER_ID)
+                .with_offset(offset)
+                .with_count(LEASE_POLL_COUNT);
+            let polled = self
+                .client
+                .poll_messages(&self.stream, &self.topic, params)
+                .await?;
+            let mut state = self.lock();
+            for message in &polled.messages {
+                let header = &message.header;
+                match serde_json::from_slice::<LeaseRecord>(&message.payload) {
+                    Ok(record) if first => {
+                        state.apply_first(record, header.offset, header.timestamp, lease_micros);
+                    }
+                    Ok(record) => {
+                        state.apply(record, header.offset, header.timestamp, lease_micros);
+                    }
+                    Err(e) => {
+                        warn!(
+                            offset = header.offset,
+                            error = %e,
+                            "Skipping unreadable lease record"
+                        );
+                    }
+                }
+                first = false;
+                offset = header.offset + 1;
+            }
+            state.next_offset = Some(offset);
+            if polled.messages.len() < LEASE_POLL_COUNT as usize {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Offset of the log's last record, or 0 while it is empty.
+    async fn last_offset(&self) -> AppResult<u64> {
+        let params = PollParams::new(LEASE_PARTITION_ID, LEASE_CONSUMER_ID)
+            .with_offset(0)
+            .with_count(1);
+        let polled = self
+            .client
+            .poll_messages(&self.stream, &self.topic, params)
+            .await?;
+        Ok(if polled.messages.is_empty() {
+            0
+        } else {
+            polled.current_offset
+        })
+    }
+
+    /// Append a record to the lease topic.
+    async fn append(&self, record: &LeaseRecord) -> AppResult<()> {
+        let message = payload_message(Bytes::from(serde_json::to_vec(record)?))?;
+        self.client
+            .send_raw_messages(
+                &self.stream,
+                &self.topic,
+                &[message],
+                &Partitioning::partition_id(LEASE_PARTITION_ID),
+            )
+            .await
+    }
+
+    /// Create the lease stream and topic if needed, once per instance.
+    async fn ensure_topic(&self) -> AppResult<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                self.client.ensure_stream(&self.stream).await?;
+                self.client.ensure_topic(&self.stream, &self.topic, 1).await
+            })
+            .await
+            .map(|_| ())
+    }
+
+    fn lease_micros(&self) -> u64 {
+        u64::try_from(self.lease.as_micros()).unwrap_or(u64::MAX)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ElectionState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The current time in microseconds, the unit of Iggy's timestamps.
+fn micros_now() -> u64 {
+    u64::try_from(Utc::now().timestamp_micros()).unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// A 10-second lease.
+    const LEASE: u64 = 10_000_000;
+
+    fn claim(candidate: &str) -> LeaseRecord {
+        LeaseRecord::Claim {
+            candidate: candidate.to_string(),
+        }
+    }
+
+    fn renew(candidate: &str, term: u64) -> LeaseRecord {
+        LeaseRecord::Renew {
+            candidate: candidate.to_string(),
+            term,
+        }
+    }
+
+    fn holder(state: &ElectionState) -> Option<&str> {
+        state.lease.as_ref()?.holder.as_deref()
+    }
+
+    #[test]
+    fn test_first_claim_wins_until_the_lease_runs_out() {
+        let mut state = ElectionState::default();
+        state.apply(claim("a"), 0, 1_000, LEASE);
+        state.apply(claim("b"), 1, 2_000, LEASE);
+        assert_eq!(holder(&state), Some("a"));
+
+        // Renewals keep it; a claim after a silent lease takes it
+        state.apply(renew("a", 0), 2, 5_000_000, LEASE);
+        state.apply(claim("b"), 3, 14_000_000, LEASE);
+        assert_eq!(holder(&state), Some("a"));
+        state.apply(claim("b"), 4, 15_000_001, LEASE);
+        assert_eq!(holder(&state), Some("b"));
+        assert_eq!(state.lease.as_ref().unwrap().term, 4);
+
+        // The old holder's late renew and release belong to a past term
+        state.apply(renew("a", 0), 5, 15_000_002, LEASE);
+        state.apply(
+            LeaseRecord::Release {
+                candidate: "a".to_string(),
+                term: 0,
+            },
+            6,
+            15_000_003,
+            LEASE,
+        );
+        assert_eq!(holder(&state), Some("b"));
+
+        state.apply(
+            LeaseRecord::Release {
+                candidate: "b".to_string(),
+                term: 4,
+            },
+            7,
+            15_000_004,
+            LEASE,
+        );
+        assert!(state.lease.is_none());
+        state.apply(claim("a"), 8, 15_000_005, LEASE);
+        assert_eq!(holder(&state), Some("a"));
+    }
+
+    #[test]
+    fn test_joining_mid_term_learns_the_holder_from_a_renew() {
+        // Started on a claim that lost: the lease is held, holder unknown
+        let mut state = ElectionState::default();
+        state.apply_first(claim("b"), 3, 1_000, LEASE);
+        assert!(state.lease.is_some());
+        assert_eq!(holder(&state), None);
+        assert_eq!(state.next_record("c", 2_000, LEASE), None);
+
+        state.apply(renew("a", 2), 4, 3_000_000, LEASE);
+        assert_eq!(holder(&state), Some("a"));
+        assert_eq!(
+            state.next_record("a", 3_000_001, LEASE),
+            Some(renew("a", 2))
+        );
+        assert_eq!(state.next_record("c", 13_000_001, LEASE), Some(claim("c")));
+    }
+}
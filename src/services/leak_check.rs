@@ -0,0 +1,133 @@
+//! Leak detection for soak tests of this long-running gateway.
+//!
+//! Internal collections that should stay bounded under steady load (open
+//! background tasks, outbox depth, rate limiter keys, consumer registry
+//! size, poll deduplication IDs, ordering check keys) are sampled every
+//! `LEAK_CHECK_INTERVAL_SECS`. A counter that rose at each of the last
+//! `LEAK_CHECK_WINDOW` samples is reported as a suspected leak: logged,
+//! counted in `iggy_leak_suspected_total` and listed by
+//! `GET /admin/internals` until it stops growing.
+//!
+//! Growth under rising load looks the same as a leak, so a suspect is a
+//! prompt to look, not proof.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, PoisonError};
+
+use crate::models::InternalsResponse;
+
+/// Consecutive growth of one counter.
+#[derive(Debug, Clone, Copy)]
+struct Growth {
+    /// Value at the last sample
+    last: usize,
+    /// Samples in a row that were above the one before
+    streak: usize,
+}
+
+/// Detector of counters growing at every sample.
+#[derive(Debug)]
+pub struct LeakCheck {
+    /// Rises in a row that make a counter a suspect
+    window: usize,
+    growth: Mutex<BTreeMap<&'static str, Growth>>,
+}
+
+impl LeakCheck {
+    /// Create a detector flagging counters that rose `window` samples in a
+    /// row (at least 1).
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            growth: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record a sample; the counters now suspected of leaking, by name.
+    pub fn record(&self, internals: &InternalsResponse) -> Vec<&'static str> {
+        let mut growth = self.growth.lock().unwrap_or_else(PoisonError::into_inner);
+        for (name, value) in counters(internals) {
+            growth
+                .entry(name)
+                .and_modify(|growth| {
+                    growth.streak = if value > growth.last {
+                        growth.streak + 1
+                    } else {
+                        0
+                    };
+                    growth.last = value;
+                })
+                .or_insert(Growth {
+                    last: value,
+                    streak: 0,
+                });
+        }
+        growth
+            .iter()
+            .filter(|(_, growth)| growth.streak >= self.window)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// The counters suspected of leaking at the last sample, by name.
+    pub fn suspects(&self) -> Vec<String> {
+        self.growth
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, growth)| growth.streak >= self.window)
+            .map(|(name, _)| (*name).to_string())
+            .collect()
+    }
+}
+
+/// The sampled counters of `internals`, by name.
+fn counters(internals: &InternalsResponse) -> [(&'static str, usize); 6] {
+    [
+        ("open_tasks", internals.open_tasks),
+        ("outbox_depth", internals.outbox_depth),
+        ("rate_limiter_keys", internals.rate_limiter_keys),
+        ("consumer_registry_size", internals.consumer_registry_size),
+        ("poll_dedup_ids", internals.poll_dedup_ids),
+        ("ordering_check_keys", internals.ordering_check_keys),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(open_tasks: usize, rate_limiter_keys: usize) -> InternalsResponse {
+        InternalsResponse {
+            open_tasks,
+            outbox_depth: 0,
+            rate_limiter_keys,
+            consumer_registry_size: 0,
+            poll_dedup_ids: 0,
+            ordering_check_keys: 0,
+            suspected_leaks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_counter_growing_for_the_window_is_suspected() {
+        let check = LeakCheck::new(3);
+        assert!(check.record(&sample(5, 10)).is_empty());
+        assert!(check.record(&sample(5, 11)).is_empty());
+        assert!(check.record(&sample(5, 12)).is_empty());
+        assert_eq!(check.record(&sample(5, 13)), ["rate_limiter_keys"]);
+        assert_eq!(check.suspects(), ["rate_limiter_keys"]);
+    }
+
+    #[test]
+    fn test_flat_or_falling_sample_clears_the_suspect() {
+        let check = LeakCheck::new(2);
+        check.record(&sample(1, 0));
+        check.record(&sample(2, 0));
+        assert_eq!(check.record(&sample(3, 0)), ["open_tasks"]);
+
+        assert!(check.record(&sample(3, 0)).is_empty());
+        assert!(check.suspects().is_empty());
+        assert!(check.record(&sample(4, 0)).is_empty());
+    }
+}
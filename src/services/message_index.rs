@@ -0,0 +1,369 @@
+//! Index of recent messages by event ID, for `GET /messages/by-id/{id}`.
+//!
+//! Lost-message reports usually come with an event ID, not a position. With
+//! `MESSAGE_INDEX_TTL_SECS` set, a background task reads every partition of
+//! the default topic as it grows and remembers where each event landed, so
+//! the report can be answered without scanning the topic.
+//!
+//! # Reading Without Consumer State
+//!
+//! The index reads with peeks (see [`ConsumerService::read_tail`]): it
+//! keeps its own next offset per partition and never commits, so it is
+//! invisible to the consumers of the topic and to `/consumers`.
+//!
+//! # Bounds
+//!
+//! An entry lives for the TTL after its message's timestamp; messages that
+//! are already older when read are skipped. Startup reads each partition
+//! from the first message within the TTL, found by timestamp. At most `MESSAGE_INDEX_MAX_ENTRIES`
+//! are kept, the earliest indexed dropped first. An event sent more than
+//! once (retries, nacked copies) resolves to the copy read last.
+//!
+//! The index is in-memory and per-instance: it is rebuilt on restart.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use super::{ConsumerService, TailStart};
+use crate::error::AppResult;
+use crate::iggy_client::IggyClientWrapper;
+use crate::models::ReceivedMessage;
+
+/// Messages read per partition per peek while catching up.
+const INDEX_POLL_COUNT: u32 = 1000;
+
+/// Where an indexed event is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLocation {
+    /// Partition holding the message
+    pub partition_id: u32,
+    /// Offset of the message within the partition
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    location: MessageLocation,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    entries: HashMap<Uuid, Entry>,
+    /// Event IDs in the order indexed, with their expiry, for eviction
+    order: VecDeque<(Uuid, DateTime<Utc>)>,
+    /// Next offset to read per partition
+    next_offsets: HashMap<u32, u64>,
+}
+
+impl IndexState {
+    /// Where the event `id` is stored, unless its entry expired by `now`.
+    fn lookup(&self, id: Uuid, now: DateTime<Utc>) -> Option<MessageLocation> {
+        self.entries
+            .get(&id)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.location)
+    }
+
+    /// Index `messages` as of `now`, each for `ttl` after its timestamp,
+    /// skipping those already expired and keeping at most `max_entries`.
+    /// Returns the number indexed.
+    fn record(
+        &mut self,
+        messages: &[ReceivedMessage],
+        now: DateTime<Utc>,
+        ttl: chrono::Duration,
+        max_entries: usize,
+    ) -> usize {
+        let mut indexed = 0;
+        for message in messages {
+            let expires_at = message
+                .timestamp
+                .checked_add_signed(ttl)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            if expires_at <= now {
+                continue;
+            }
+            let location = MessageLocation {
+                partition_id: message.partition_id,
+                offset: message.offset,
+            };
+            self.entries.insert(
+                message.event.id,
+                Entry {
+                    location,
+                    expires_at,
+                },
+            );
+            self.order.push_back((message.event.id, expires_at));
+            while self.entries.len() > max_entries {
+                self.pop_oldest();
+            }
+            indexed += 1;
+        }
+        indexed
+    }
+
+    /// Drop entries expired at `now`. Entries are mostly indexed in
+    /// timestamp order; one that expires behind a later one waits for it,
+    /// but is never returned by [`Self::lookup`].
+    fn evict(&mut self, now: DateTime<Utc>) {
+        while self
+            .order
+            .front()
+            .is_some_and(|(_, expires_at)| *expires_at <= now)
+        {
+            self.pop_oldest();
+        }
+    }
+
+    /// Drop the front of `order`, and its entry unless it was re-indexed
+    /// since.
+    fn pop_oldest(&mut self) {
+        if let Some((id, expires_at)) = self.order.pop_front()
+            && self
+                .entries
+                .get(&id)
+                .is_some_and(|entry| entry.expires_at == expires_at)
+        {
+            self.entries.remove(&id);
+        }
+    }
+}
+
+/// Event ID → position index of the default topic's recent messages.
+pub struct MessageIndex {
+    client: IggyClientWrapper,
+    consumer: ConsumerService,
+    stream: String,
+    topic: String,
+    ttl: Duration,
+    max_entries: usize,
+    state: Mutex<IndexState>,
+}
+
+impl MessageIndex {
+    /// Create an index of `stream`/`topic` keeping entries for `ttl` (zero
+    /// disables it), at most `max_entries` of them (at least 1).
+    pub fn new(
+        client: IggyClientWrapper,
+        stream: &str,
+        topic: &str,
+        ttl: Duration,
+        max_entries: usize,
+    ) -> Self {
+        Self {
+            consumer: ConsumerService::new(client.clone()),
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            ttl,
+            max_entries: max_entries.max(1),
+            state: Mutex::new(IndexState::default()),
+        }
+    }
+
+    /// Check if the index is maintained (`MESSAGE_INDEX_TTL_SECS` > 0).
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Stream and topic the index covers.
+    pub fn source(&self) -> (&str, &str) {
+        (&self.stream, &self.topic)
+    }
+
+    /// Number of indexed events.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Check if no event is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Where the event `id` is stored, if it was indexed and has not
+    /// expired.
+    pub fn lookup(&self, id: Uuid) -> Option<MessageLocation> {
+        self.lock().lookup(id, Utc::now())
+    }
+
+    /// Read what was appended to each partition since the last call and
+    /// index it, then drop expired entries. Returns the number of messages
+    /// indexed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed topic lookup or peek; what was read before
+    /// it stays indexed and the next call resumes from there.
+    #[instrument(skip(self), fields(stream = %self.stream, topic = %self.topic))]
+    pub async fn catch_up(&self) -> AppResult<usize> {
+        let topic = self.client.get_topic(&self.stream, &self.topic).await?;
+        let mut indexed = 0;
+        for partition in &topic.partitions {
+            indexed += self.catch_up_partition(partition.id).await?;
+        }
+        self.lock().evict(Utc::now());
+        debug!(indexed, entries = self.len(), "Message index caught up");
+        Ok(indexed)
+    }
+
+    /// Read one partition from its next offset (or the start of the TTL)
+    /// up to its end.
+    async fn catch_up_partition(&self, partition_id: u32) -> AppResult<usize> {
+        let ttl = chrono::Duration::from_std(self.ttl).unwrap_or(chrono::Duration::MAX);
+        let next = self.lock().next_offsets.get(&partition_id).copied();
+        let since = Utc::now()
+            .checked_sub_signed(ttl)
+            .unwrap_or(DateTime::UNIX_EPOCH);
+        let mut indexed = 0;
+        self.consumer
+            .read_tail(
+                &self.stream,
+                &self.topic,
+                partition_id,
+                TailStart::resume(next, since),
+                INDEX_POLL_COUNT,
+                |messages, next| {
+                    let mut state = self.lock();
+                    indexed += state.record(messages, Utc::now(), ttl, self.max_entries);
+                    state.next_offsets.insert(partition_id, next);
+                },
+            )
+            .await?;
+        Ok(indexed)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IndexState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::iggy_client::BrokerBackend;
+    use crate::models::{Event, EventPayload};
+
+    fn ttl() -> chrono::Duration {
+        chrono::Duration::seconds(60)
+    }
+
+    fn message(offset: u64, timestamp: DateTime<Utc>) -> ReceivedMessage {
+        ReceivedMessage {
+            partition_id: 1,
+            offset,
+            timestamp,
+            id: u128::from(offset),
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event: Event::new("test", EventPayload::Generic(serde_json::json!({}))),
+            size: 64,
+        }
+    }
+
+    #[test]
+    fn test_entries_expire_after_the_message_timestamp() {
+        let mut state = IndexState::default();
+        let now = Utc::now();
+        let messages = [
+            message(4, now - chrono::Duration::seconds(61)),
+            message(5, now),
+        ];
+        let (stale, fresh) = (&messages[0], &messages[1]);
+
+        let recorded = state.record(&messages, now, ttl(), 10);
+        assert_eq!(recorded, 1);
+        assert_eq!(state.lookup(stale.event.id, now), None);
+        assert_eq!(
+            state.lookup(fresh.event.id, now),
+            Some(MessageLocation {
+                partition_id: 1,
+                offset: 5
+            })
+        );
+
+        let later = now + chrono::Duration::seconds(61);
+        assert_eq!(state.lookup(fresh.event.id, later), None);
+        state.evict(later);
+        assert!(state.entries.is_empty());
+        assert!(state.order.is_empty());
+    }
+
+    #[test]
+    fn test_oldest_entries_are_dropped_beyond_the_limit() {
+        let mut state = IndexState::default();
+        let now = Utc::now();
+        let messages: Vec<_> = (0..3).map(|offset| message(offset, now)).collect();
+
+        state.record(&messages, now, ttl(), 2);
+        assert_eq!(state.entries.len(), 2);
+        assert_eq!(state.lookup(messages[0].event.id, now), None);
+        assert!(state.lookup(messages[2].event.id, now).is_some());
+
+        // A copy read later moves the event, and dropping the first copy's
+        // order record keeps it
+        let mut copy = message(9, now + chrono::Duration::seconds(1));
+        copy.event.id = messages[1].event.id;
+        state.record(&[copy], now, ttl(), 2);
+        let next = message(10, now + chrono::Duration::seconds(2));
+        state.record(&[next], now, ttl(), 2);
+        let moved = state.lookup(messages[1].event.id, now).unwrap();
+        assert_eq!(moved.offset, 9);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_resumes_after_what_it_read() {
+        use iggy::prelude::Partitioning;
+
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config).await.unwrap();
+        client.ensure_stream("s").await.unwrap();
+        client.ensure_topic("s", "t", 1).await.unwrap();
+        let send = |count: usize| {
+            let client = client.clone();
+            async move {
+                let events: Vec<Event> = (0..count)
+                    .map(|_| Event::new("test", EventPayload::Generic(serde_json::json!({}))))
+                    .collect();
+                client
+                    .send_events_batch_partitioned(
+                        "s",
+                        "t",
+                        &events,
+                        &Partitioning::partition_id(0),
+                    )
+                    .await
+                    .unwrap();
+                events
+            }
+        };
+
+        let first = send(3).await;
+        let index = MessageIndex::new(client.clone(), "s", "t", Duration::from_secs(60), 100);
+        assert_eq!(index.catch_up().await.unwrap(), 3);
+        assert_eq!(index.catch_up().await.unwrap(), 0);
+        send(1).await;
+        assert_eq!(index.catch_up().await.unwrap(), 1);
+        assert_eq!(
+            index.lookup(first.first().unwrap().id),
+            Some(MessageLocation {
+                partition_id: 0,
+                offset: 0
+            })
+        );
+    }
+}
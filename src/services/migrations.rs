@@ -0,0 +1,344 @@
+//! Startup migrations of persisted gateway state.
+//!
+//! Persisted definitions (see [`super::StateStore`]) outlive the build that
+//! wrote them. When the shape of a stored value changes, a [`Migration`]
+//! added to [`MIGRATIONS`] rewrites the old values. On startup, before the
+//! state is loaded, `AppState::restore_state` applies every migration not
+//! yet recorded as applied, in version order, writes back what it changed,
+//! and records it (with the time) under the `_migrations` namespace of the
+//! same backend, so each migration runs once per backend, not per replica.
+//!
+//! A migration whose writes fail half-way runs again on the next start, so
+//! migrations must be idempotent.
+//!
+//! # Failures
+//!
+//! A failed migration is not recorded and is retried on the next start;
+//! later migrations are not attempted. A *compatible* migration only
+//! improves stored data, so the gateway loads the state and serves anyway.
+//! An incompatible one means this build cannot read what is stored: the
+//! state is not loaded and `GET /ready` answers 503 until a start applies
+//! it, so a rolling deploy stalls instead of serving without definitions.
+//! `GET /admin/migrations` reports each migration's status.
+//!
+//! With `STATE_BACKEND=memory` nothing is stored, so nothing is migrated.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use super::{JournalState, StateBackend, StateStore};
+use crate::models::{MigrationState, MigrationStatus, MigrationsResponse};
+
+/// Namespace recording the applied migrations, keyed by zero-padded
+/// version.
+pub const MIGRATIONS_NAMESPACE: &str = "_migrations";
+
+/// Migrations of this build, in version order.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "Record the initial layout of schedules and pipelines",
+    compatible: true,
+    up: baseline,
+}];
+
+/// One change to the layout of persisted state.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Version; migrations are applied in increasing order
+    pub version: u32,
+    /// What the migration changes
+    pub name: &'static str,
+    /// Whether this build can serve while the migration is pending
+    pub compatible: bool,
+    /// Rewrite the loaded state in place; an error leaves it unchanged
+    pub up: fn(&mut JournalState) -> Result<(), String>,
+}
+
+/// Version 1: the layout written since persistence was added.
+fn baseline(_: &mut JournalState) -> Result<(), String> {
+    Ok(())
+}
+
+/// Record of an applied migration, stored in [`MIGRATIONS_NAMESPACE`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AppliedMigration {
+    version: u32,
+    name: String,
+    applied_at: DateTime<Utc>,
+}
+
+/// Applies the migrations of this build and reports their status.
+pub struct Migrations {
+    migrations: Vec<Migration>,
+    backend: StateBackend,
+    status: Mutex<Vec<MigrationStatus>>,
+}
+
+impl Migrations {
+    /// Create a runner for `migrations` on `backend`; all start pending.
+    pub fn new(mut migrations: Vec<Migration>, backend: StateBackend) -> Self {
+        migrations.sort_by_key(|migration| migration.version);
+        let status = migrations
+            .iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_string(),
+                compatible: migration.compatible,
+                state: MigrationState::Pending,
+                applied_at: None,
+                error: None,
+            })
+            .collect();
+        Self {
+            migrations,
+            backend,
+            status: Mutex::new(status),
+        }
+    }
+
+    /// Check if migrations run (`STATE_BACKEND` other than `memory`).
+    pub fn is_enabled(&self) -> bool {
+        self.backend != StateBackend::Memory
+    }
+
+    /// Apply the pending migrations to `state`, as loaded from `store`,
+    /// persisting their changes. Returns `false` when an incompatible
+    /// migration is still pending, in which case `state` must not be used.
+    pub async fn run(&self, store: &dyn StateStore, state: &mut JournalState) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let applied: BTreeMap<u32, AppliedMigration> = state
+            .take::<AppliedMigration>(MIGRATIONS_NAMESPACE)
+            .into_iter()
+            .map(|applied| (applied.version, applied))
+            .collect();
+
+        let mut halted = false;
+        for (index, migration) in self.migrations.iter().enumerate() {
+            let outcome = match applied.get(&migration.version) {
+                Some(applied) => Ok(applied.applied_at),
+                None if halted => continue,
+                None => apply(migration, store, state).await,
+            };
+            if let Err(e) = &outcome {
+                warn!(
+                    version = migration.version,
+                    name = migration.name,
+                    compatible = migration.compatible,
+                    error = %e,
+                    "State migration failed"
+                );
+                halted = true;
+            }
+            if let Some(status) = self.lock().get_mut(index) {
+                match outcome {
+                    Ok(applied_at) => {
+                        status.state = MigrationState::Applied;
+                        status.applied_at = Some(applied_at);
+                        status.error = None;
+                    }
+                    Err(e) => {
+                        status.state = MigrationState::Failed;
+                        status.error = Some(e);
+                    }
+                }
+            }
+        }
+
+        if self.is_blocking() {
+            error!("An incompatible state migration is pending; see GET /admin/migrations");
+            return false;
+        }
+        true
+    }
+
+    /// Whether an incompatible migration is pending (or failed).
+    pub fn is_blocking(&self) -> bool {
+        self.is_enabled()
+            && self
+                .lock()
+                .iter()
+                .any(|status| !status.compatible && status.state != MigrationState::Applied)
+    }
+
+    /// Status of every migration (`GET /admin/migrations`).
+    pub fn status(&self) -> MigrationsResponse {
+        MigrationsResponse {
+            backend: self.backend.to_string(),
+            blocking: self.is_blocking(),
+            migrations: self.lock().clone(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<MigrationStatus>> {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Run `migration` on `state`, persist what it changed and record it.
+async fn apply(
+    migration: &Migration,
+    store: &dyn StateStore,
+    state: &mut JournalState,
+) -> Result<DateTime<Utc>, String> {
+    let before = state.clone();
+    if let Err(e) = (migration.up)(state) {
+        *state = before;
+        return Err(e);
+    }
+    for (namespace, key, value) in state.changes_since(&before) {
+        store
+            .write(&namespace, &key, value)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let applied = AppliedMigration {
+        version: migration.version,
+        name: migration.name.to_string(),
+        applied_at: Utc::now(),
+    };
+    let record = serde_json::to_value(&applied).map_err(|e| e.to_string())?;
+    store
+        .write(
+            MIGRATIONS_NAMESPACE,
+            &format!("{:04}", migration.version),
+            Some(record),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    info!(
+        version = migration.version,
+        name = migration.name,
+        "Applied state migration"
+    );
+    Ok(applied.applied_at)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::config::Config;
+    use crate::iggy_client::{BrokerBackend, IggyClientWrapper};
+    use crate::services::{SCHEDULES_NAMESPACE, open_state_store};
+
+    /// Rename `cron` to `schedule` in every stored schedule.
+    fn rename_cron(state: &mut JournalState) -> Result<(), String> {
+        for value in state.namespace_mut(SCHEDULES_NAMESPACE).values_mut() {
+            let object = value.as_object_mut().ok_or("not an object")?;
+            if let Some(cron) = object.remove("cron") {
+                object.insert("schedule".to_string(), cron);
+            }
+        }
+        Ok(())
+    }
+
+    fn failing(_: &mut JournalState) -> Result<(), String> {
+        Err("cannot convert".to_string())
+    }
+
+    fn migration(
+        version: u32,
+        compatible: bool,
+        up: fn(&mut JournalState) -> Result<(), String>,
+    ) -> Migration {
+        Migration {
+            version,
+            name: "test",
+            compatible,
+            up,
+        }
+    }
+
+    async fn store() -> Arc<dyn StateStore> {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            state_backend: StateBackend::Iggy,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config.clone()).await.unwrap();
+        open_state_store(client, &config)
+    }
+
+    #[tokio::test]
+    async fn test_pending_migrations_are_applied_once() {
+        let store = store().await;
+        store
+            .put(
+                SCHEDULES_NAMESPACE,
+                "heartbeat",
+                &json!({ "cron": "* * * * *" }),
+            )
+            .await;
+
+        let migrations =
+            Migrations::new(vec![migration(2, false, rename_cron)], StateBackend::Iggy);
+        assert!(migrations.is_blocking());
+        let mut state = store.replay().await.unwrap();
+        assert!(migrations.run(store.as_ref(), &mut state).await);
+        assert!(!migrations.is_blocking());
+
+        // Persisted: a restart loads the migrated value and skips the migration
+        let mut state = store.replay().await.unwrap();
+        let restarted = Migrations::new(vec![migration(2, false, failing)], StateBackend::Iggy);
+        assert!(restarted.run(store.as_ref(), &mut state).await);
+        let status = restarted.status();
+        assert_eq!(status.migrations[0].state, MigrationState::Applied);
+        assert!(status.migrations[0].applied_at.is_some());
+        let schedules: Vec<Value> = state.take(SCHEDULES_NAMESPACE);
+        assert_eq!(schedules, vec![json!({ "schedule": "* * * * *" })]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_incompatible_migration_blocks_and_halts() {
+        let store = store().await;
+        let migrations = Migrations::new(
+            vec![
+                migration(3, true, rename_cron),
+                migration(1, true, baseline),
+                migration(2, false, failing),
+            ],
+            StateBackend::Iggy,
+        );
+        let mut state = store.replay().await.unwrap();
+        assert!(!migrations.run(store.as_ref(), &mut state).await);
+
+        let status = migrations.status();
+        assert!(status.blocking);
+        let states: Vec<_> = status.migrations.iter().map(|m| m.state).collect();
+        assert_eq!(
+            states,
+            vec![
+                MigrationState::Applied,
+                MigrationState::Failed,
+                MigrationState::Pending
+            ]
+        );
+        assert_eq!(
+            status.migrations[1].error.as_deref(),
+            Some("cannot convert")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_has_nothing_to_migrate() {
+        let migrations = Migrations::new(vec![migration(1, false, failing)], StateBackend::Memory);
+        assert!(!migrations.is_blocking());
+        let mut state = JournalState::default();
+        assert!(migrations.run(store().await.as_ref(), &mut state).await);
+        assert_eq!(
+            migrations.status().migrations[0].state,
+            MigrationState::Pending
+        );
+    }
+}
@@ -1,5 +1,80 @@
+mod anomaly;
+mod audit;
+mod bench;
+mod canary;
+mod coalescer;
 mod consumer;
+mod continuation;
+mod dedup;
+mod event_counts;
+mod event_lag;
+mod expression;
+mod fanout;
+mod leader;
+mod leak_check;
+mod message_index;
+mod migrations;
+mod notifier;
+mod ordering;
+mod outbox;
+mod partitioner;
+mod pipeline;
 mod producer;
+mod profile;
+mod recurring;
+mod registry;
+mod replicator;
+mod retention;
+mod scheduler;
+mod search;
+mod shadow;
+mod spool;
+mod state_store;
+mod storage;
+mod talkers;
+mod tap;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-pub use consumer::ConsumerService;
-pub use producer::ProducerService;
+pub use anomaly::{AnomalyObserver, MIN_STD_DEV, ThroughputAnomalies, WARMUP_SAMPLES};
+pub use audit::{AuditContext, AuditService};
+pub use bench::{
+    BENCHMARK_EVENT_TYPE, Benchmark, MAX_BENCHMARK_CONCURRENCY, MAX_BENCHMARK_EVENT_SIZE,
+};
+pub use canary::{CANARY_EVENT_TYPE, CanaryService};
+pub use consumer::{ConsumerService, PEEK_CONSUMER_ID, TailStart};
+pub use continuation::{Continuation, ContinuationTokens};
+pub use dedup::PollDedup;
+pub use event_counts::EventCounter;
+pub use event_lag::{EventTimeLag, MAX_LAG_SAMPLES};
+pub use expression::{Expressions, MAX_EXPRESSION_LEN, Predicate};
+pub use fanout::{FanoutGroup, FanoutRoute, fan_out};
+pub use leader::LeaderElection;
+pub use leak_check::LeakCheck;
+pub use message_index::{MessageIndex, MessageLocation};
+pub use migrations::{MIGRATIONS, MIGRATIONS_NAMESPACE, Migration, Migrations};
+pub use notifier::{NOTIFY_QUEUE_CAPACITY, NOTIFY_TIMEOUT, WebhookNotifier};
+pub use ordering::OrderingChecker;
+pub use outbox::{Outbox, OutboxOverflow};
+pub use pipeline::{CustomTransform, Pipelines, default_consumer_id};
+pub use producer::{COMPENSATION_EVENT_TYPE, ProducerService};
+pub use profile::{MAX_PROFILE_SAMPLE, PROFILE_EVENT_TYPES, PROFILE_FIELDS, profile};
+pub use recurring::RecurringSchedules;
+pub use registry::{ConsumerRegistry, IdleConsumer};
+pub use replicator::{
+    REPLICATED_FROM_HEADER, ReplicationMapping, Replicator, replica_message, replicated_from,
+};
+pub use retention::RetentionManager;
+pub use scheduler::Scheduler;
+pub use search::{SearchLimits, SearchMatcher, search};
+pub use shadow::ShadowRule;
+pub use spool::{SEGMENT_BYTES, Spool, SpooledSend};
+pub use state_store::{
+    IDEMPOTENCY_NAMESPACE, JournalState, MemoryStateStore, PIPELINES_NAMESPACE, REPLAY_BATCH_SIZE,
+    SCHEDULES_NAMESPACE, StateBackend, StateFuture, StateRecord, StateStore, open_state_store,
+};
+pub use storage::StorageAlarm;
+pub use talkers::{MAX_TRACKED_CLIENTS, TopTalkers};
+pub use tap::{MessageTap, TAP_CHANNEL_CAPACITY, TapFilter, TapItem};
+#[cfg(feature = "wasm")]
+pub use wasm::{MAX_MODULE_VERSIONS, WasmLimits, WasmModule, WasmModules};
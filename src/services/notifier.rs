@@ -0,0 +1,300 @@
+//! Webhook notifications of connection events and throughput anomalies.
+//!
+//! With `NOTIFY_WEBHOOK_URL` set, the gateway POSTs a JSON message to it
+//! when it loses or regains its connection to Iggy and when a circuit
+//! breaker opens (see `ConnectionObserver`), so an outage pages someone
+//! instead of waiting to be noticed on a dashboard:
+//!
+//! ```json
+//! {
+//!   "text": "iggy-sample lost its connection to Iggy",
+//!   "event": "disconnected",
+//!   "class": null,
+//!   "service": "iggy-sample",
+//!   "timestamp": "2026-10-16T09:12:44Z"
+//! }
+//! ```
+//!
+//! `text` makes the body a valid Slack incoming-webhook message; other
+//! receivers can use the structured fields. `class` is the operation class
+//! of a `circuit_opened` event.
+//!
+//! A throughput anomaly (see [`super::ThroughputAnomalies`]) is sent as a
+//! `throughput_drop` or `throughput_spike` event, with the anomaly's
+//! details in an extra `anomaly` field.
+//!
+//! # Delivery
+//!
+//! Events are queued (at most [`NOTIFY_QUEUE_CAPACITY`]) and POSTed one at
+//! a time by a background task, each bounded by [`NOTIFY_TIMEOUT`]. A
+//! failed POST is logged and not retried; events arriving while the queue
+//! is full are dropped. Notifying never slows down or fails the gateway.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::AnomalyObserver;
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{ConnectionEvent, ConnectionObserver, OperationClass};
+use crate::models::{AnomalyKind, ThroughputAnomaly};
+
+/// Events waiting to be sent; more are dropped.
+pub const NOTIFY_QUEUE_CAPACITY: usize = 64;
+
+/// Bound on one webhook POST.
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something to notify about.
+#[derive(Debug, Clone)]
+enum Notice {
+    Connection(ConnectionEvent),
+    Anomaly(ThroughputAnomaly),
+}
+
+impl Notice {
+    /// Event name, used in notifications.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Connection(event) => event.as_str(),
+            Self::Anomaly(anomaly) => match anomaly.kind {
+                AnomalyKind::Drop => "throughput_drop",
+                AnomalyKind::Spike => "throughput_spike",
+            },
+        }
+    }
+}
+
+/// Body POSTed to the webhook.
+#[derive(Debug, Serialize)]
+struct Notification<'a> {
+    text: String,
+    event: &'static str,
+    class: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anomaly: Option<&'a ThroughputAnomaly>,
+    service: &'a str,
+    timestamp: DateTime<Utc>,
+}
+
+/// Sends connection events and throughput anomalies to a webhook.
+pub struct WebhookNotifier {
+    http: reqwest::Client,
+    url: String,
+    service: String,
+    sender: mpsc::Sender<Notice>,
+    events: mpsc::Receiver<Notice>,
+}
+
+impl WebhookNotifier {
+    /// Create a notifier POSTing to `url`, naming the gateway `service`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` if the HTTP client cannot be built.
+    pub fn new(url: &str, service: &str) -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(NOTIFY_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::ConfigError(format!("Webhook HTTP client: {e}")))?;
+        let (sender, events) = mpsc::channel(NOTIFY_QUEUE_CAPACITY);
+        Ok(Self {
+            http,
+            url: url.to_string(),
+            service: service.to_string(),
+            sender,
+            events,
+        })
+    }
+
+    /// Observer queueing events for this notifier, to register with
+    /// `IggyClientWrapper::observe`.
+    pub fn observer(&self) -> Arc<dyn ConnectionObserver> {
+        Arc::new(EventQueue(self.sender.clone()))
+    }
+
+    /// Observer queueing throughput anomalies for this notifier, to
+    /// register with `ThroughputAnomalies::observe`.
+    pub fn anomaly_observer(&self) -> Arc<dyn AnomalyObserver> {
+        Arc::new(EventQueue(self.sender.clone()))
+    }
+
+    /// Send queued events until `cancel` is cancelled.
+    pub async fn run(mut self, cancel: CancellationToken) {
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+                Some(event) = self.events.recv() => self.send(event).await,
+            }
+        }
+    }
+
+    /// POST one event; a failure is logged.
+    async fn send(&self, event: Notice) {
+        let notification = notification(&event, &self.service, Utc::now());
+        let result = self
+            .http
+            .post(&self.url)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        match result {
+            Ok(_) => debug!(event = event.as_str(), "Sent webhook notification"),
+            Err(e) => warn!(
+                event = event.as_str(),
+                error = %e,
+                "Failed to send webhook notification"
+            ),
+        }
+    }
+}
+
+/// Observer side of a [`WebhookNotifier`]: queues events without waiting.
+struct EventQueue(mpsc::Sender<Notice>);
+
+impl EventQueue {
+    fn push(&self, event: Notice) {
+        let name = event.as_str();
+        if self.0.try_send(event).is_err() {
+            warn!(
+                event = name,
+                "Webhook notification queue full; dropping event"
+            );
+        }
+    }
+}
+
+impl ConnectionObserver for EventQueue {
+    fn on_connected(&self) {
+        self.push(Notice::Connection(ConnectionEvent::Connected));
+    }
+
+    fn on_disconnected(&self) {
+        self.push(Notice::Connection(ConnectionEvent::Disconnected));
+    }
+
+    fn on_circuit_opened(&self, class: OperationClass) {
+        self.push(Notice::Connection(ConnectionEvent::CircuitOpened(class)));
+    }
+}
+
+impl AnomalyObserver for EventQueue {
+    fn on_anomaly(&self, anomaly: &ThroughputAnomaly) {
+        self.push(Notice::Anomaly(anomaly.clone()));
+    }
+}
+
+/// Build the webhook body for `event`.
+fn notification<'a>(event: &'a Notice, service: &'a str, at: DateTime<Utc>) -> Notification<'a> {
+    let (text, class, anomaly) = match event {
+        Notice::Connection(ConnectionEvent::Connected) => {
+            (format!("{service} is connected to Iggy again"), None, None)
+        }
+        Notice::Connection(ConnectionEvent::Disconnected) => {
+            (format!("{service} lost its connection to Iggy"), None, None)
+        }
+        Notice::Connection(ConnectionEvent::CircuitOpened(class)) => (
+            format!("{service} opened its {class} circuit breaker; {class} operations fail fast"),
+            Some(class.as_str()),
+            None,
+        ),
+        Notice::Anomaly(anomaly) => (
+            format!(
+                "{service} sees a throughput {} on {}/{}: {:.1} messages/s, {:.1} expected",
+                anomaly.kind.as_str(),
+                anomaly.stream,
+                anomaly.topic,
+                anomaly.rate_per_sec,
+                anomaly.expected_per_sec
+            ),
+            None,
+            Some(anomaly),
+        ),
+    };
+    Notification {
+        text,
+        event: event.as_str(),
+        class,
+        anomaly,
+        service,
+        timestamp: at,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_is_a_slack_message_with_structured_fields() {
+        let at = Utc::now();
+        let body = serde_json::to_value(notification(
+            &Notice::Connection(ConnectionEvent::CircuitOpened(OperationClass::Send)),
+            "gateway",
+            at,
+        ))
+        .unwrap();
+
+        assert_eq!(body["event"], "circuit_opened");
+        assert_eq!(body["class"], "send");
+        assert_eq!(body["service"], "gateway");
+        assert!(
+            body["text"]
+                .as_str()
+                .unwrap()
+                .contains("send circuit breaker")
+        );
+
+        let disconnected = Notice::Connection(ConnectionEvent::Disconnected);
+        let body = serde_json::to_value(notification(&disconnected, "gateway", at)).unwrap();
+        assert_eq!(body["event"], "disconnected");
+        assert!(body["class"].is_null());
+        assert!(body.get("anomaly").is_none());
+    }
+
+    #[test]
+    fn test_anomaly_notification_carries_the_anomaly() {
+        let anomaly = Notice::Anomaly(ThroughputAnomaly {
+            stream: "orders".to_string(),
+            topic: "created".to_string(),
+            kind: AnomalyKind::Drop,
+            rate_per_sec: 2.0,
+            expected_per_sec: 120.0,
+            deviations: -9.5,
+            since: Utc::now(),
+        });
+        let body = serde_json::to_value(notification(&anomaly, "gateway", Utc::now())).unwrap();
+
+        assert_eq!(body["event"], "throughput_drop");
+        assert_eq!(body["anomaly"]["topic"], "created");
+        assert_eq!(
+            body["text"],
+            "gateway sees a throughput drop on orders/created: 2.0 messages/s, 120.0 expected"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_events() {
+        let notifier = WebhookNotifier::new("http://127.0.0.1:9/hook", "gateway").unwrap();
+        let observer = notifier.observer();
+        for _ in 0..NOTIFY_QUEUE_CAPACITY + 10 {
+            observer.on_disconnected();
+        }
+
+        let mut notifier = notifier;
+        let mut queued = 0;
+        while notifier.events.try_recv().is_ok() {
+            queued += 1;
+        }
+        assert_eq!(queued, NOTIFY_QUEUE_CAPACITY);
+    }
+}
@@ -0,0 +1,428 @@
+//! Per-key ordering check of a topic, for `GET /admin/ordering-report`.
+//!
+//! Iggy keeps messages in order within a partition only, so consumers can
+//! rely on a key's order only if all its messages land in one partition in
+//! the order they were produced. With `ORDERING_CHECK_TOPIC` set, a
+//! background task reads that topic of the default stream every
+//! `ORDERING_CHECK_INTERVAL_SECS` and checks each key's messages in offset
+//! order for:
+//!
+//! - `key_split` - the key was already seen in another partition (e.g. the
+//!   partition count changed, or producers partition differently)
+//! - `timestamp_regression` - the event's timestamp is earlier than that of
+//!   the key's previous event in the partition
+//! - `sequence_gap`, `sequence_duplicate` - the `sequence` header skipped
+//!   or repeated numbers within one producer epoch
+//!
+//! Keys come from the `sequence_key` header stamped by `KEY_SEQUENCING`;
+//! messages without one are counted as unkeyed and not checked. Nack
+//! redeliveries (a `redelivery_count` header) are appended out of order on
+//! purpose and are skipped.
+//!
+//! # Window
+//!
+//! Keys and violations are kept for `ORDERING_CHECK_WINDOW_SECS` after the
+//! timestamp of the message they were last seen in. Startup reads each
+//! partition from the first message of the window, found by timestamp.
+//! Like the event counter (see [`super::EventCounter`]), the checker reads
+//! with [`ConsumerService::read_tail`] without committing, and its state
+//! is in-memory and per-instance.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, instrument, warn};
+
+use super::{ConsumerService, TailStart};
+use crate::error::AppResult;
+use crate::iggy_client::{
+    IggyClientWrapper, REDELIVERY_COUNT_HEADER, SEQUENCE_KEY_HEADER, sequence_headers,
+};
+use crate::models::{OrderingReportResponse, OrderingViolation, ReceivedMessage};
+
+/// Messages read per partition per peek while catching up.
+const ORDERING_POLL_COUNT: u32 = 1000;
+
+/// Most violations kept; the oldest are dropped first.
+const MAX_VIOLATIONS: usize = 1000;
+
+/// Last message of a key in one partition.
+#[derive(Debug)]
+struct KeyPosition {
+    /// Producer epoch and sequence number, when stamped
+    sequence: Option<(String, u64)>,
+    event_time: DateTime<Utc>,
+    /// Message timestamp, for eviction
+    seen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct OrderingState {
+    /// Last message per key, by partition
+    keys: HashMap<String, BTreeMap<u32, KeyPosition>>,
+    /// Violations with their message timestamps, oldest first
+    violations: VecDeque<(DateTime<Utc>, OrderingViolation)>,
+    /// Next offset to read per partition
+    next_offsets: HashMap<u32, u64>,
+    checked: u64,
+    unkeyed: u64,
+}
+
+impl OrderingState {
+    /// Check `messages` of one partition in offset order, skipping those
+    /// older than `window` at `now`. Returns the number of violations found.
+    fn record(
+        &mut self,
+        messages: &[ReceivedMessage],
+        now: DateTime<Utc>,
+        window: Duration,
+    ) -> usize {
+        let oldest = oldest_kept(now, window);
+        let mut found = 0;
+        for message in messages {
+            if message.timestamp < oldest || message.headers.contains_key(REDELIVERY_COUNT_HEADER) {
+                continue;
+            }
+            self.checked += 1;
+            let Some(key) = message.headers.get(SEQUENCE_KEY_HEADER) else {
+                self.unkeyed += 1;
+                continue;
+            };
+            for violation in self.check(key, message) {
+                self.violations.push_back((message.timestamp, violation));
+                found += 1;
+            }
+        }
+        while self.violations.len() > MAX_VIOLATIONS {
+            self.violations.pop_front();
+        }
+        found
+    }
+
+    /// Check the next message of `key`, remembering it as the key's last.
+    fn check(&mut self, key: &str, message: &ReceivedMessage) -> Vec<OrderingViolation> {
+        let partition_id = message.partition_id;
+        let offset = message.offset;
+        let sequence = sequence_headers(&message.headers)
+            .map(|(_, epoch, sequence)| (epoch.to_string(), sequence));
+        let partitions = self.keys.entry(key.to_string()).or_default();
+        let mut violations = Vec::new();
+
+        match partitions.get(&partition_id) {
+            Some(previous) => {
+                if message.event.timestamp < previous.event_time {
+                    violations.push(OrderingViolation::TimestampRegression {
+                        key: key.to_string(),
+                        partition_id,
+                        offset,
+                        previous: previous.event_time,
+                        received: message.event.timestamp,
+                    });
+                }
+                if let (Some((last_epoch, last)), Some((epoch, received))) =
+                    (&previous.sequence, &sequence)
+                    && last_epoch == epoch
+                {
+                    let (expected, received) = (last + 1, *received);
+                    let key = key.to_string();
+                    if received > expected {
+                        violations.push(OrderingViolation::SequenceGap {
+                            key,
+                            partition_id,
+                            offset,
+                            expected,
+                            received,
+                        });
+                    } else if received < expected {
+                        violations.push(OrderingViolation::SequenceDuplicate {
+                            key,
+                            partition_id,
+                            offset,
+                            expected,
+                            received,
+                        });
+                    }
+                }
+            }
+            None if !partitions.is_empty() => {
+                violations.push(OrderingViolation::KeySplit {
+                    key: key.to_string(),
+                    partition_id,
+                    offset,
+                    other_partitions: partitions.keys().copied().collect(),
+                });
+            }
+            None => {}
+        }
+
+        partitions.insert(
+            partition_id,
+            KeyPosition {
+                sequence,
+                event_time: message.event.timestamp,
+                seen_at: message.timestamp,
+            },
+        );
+        violations
+    }
+
+    /// Forget keys and violations last seen longer than `window` before
+    /// `now`.
+    fn evict(&mut self, now: DateTime<Utc>, window: Duration) {
+        let oldest = oldest_kept(now, window);
+        self.keys.retain(|_, partitions| {
+            partitions.retain(|_, position| position.seen_at >= oldest);
+            !partitions.is_empty()
+        });
+        while let Some((seen_at, _)) = self.violations.front()
+            && *seen_at < oldest
+        {
+            self.violations.pop_front();
+        }
+    }
+}
+
+/// Oldest message timestamp still in `window` at `now`.
+fn oldest_kept(now: DateTime<Utc>, window: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(window)
+        .ok()
+        .and_then(|window| now.checked_sub_signed(window))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Ordering check of one topic's partition keys.
+pub struct OrderingChecker {
+    client: IggyClientWrapper,
+    consumer: ConsumerService,
+    stream: String,
+    /// `None` when the check is disabled
+    topic: Option<String>,
+    window: Duration,
+    state: Mutex<OrderingState>,
+}
+
+impl OrderingChecker {
+    /// Create a checker of `stream`/`topic` keeping keys and violations for
+    /// `window`; without a topic nothing is checked.
+    pub fn new(
+        client: IggyClientWrapper,
+        stream: &str,
+        topic: Option<&str>,
+        window: Duration,
+    ) -> Self {
+        Self {
+            consumer: ConsumerService::new(client.clone()),
+            client,
+            stream: stream.to_string(),
+            topic: topic.map(str::to_string),
+            window,
+            state: Mutex::new(OrderingState::default()),
+        }
+    }
+
+    /// Check if a topic is checked (`ORDERING_CHECK_TOPIC` set).
+    pub fn is_enabled(&self) -> bool {
+        self.topic.is_some()
+    }
+
+    /// Number of partition keys tracked.
+    pub fn key_count(&self) -> usize {
+        self.lock().keys.len()
+    }
+
+    /// The violations within the window as of now, or `None` while
+    /// disabled.
+    pub fn report(&self) -> Option<OrderingReportResponse> {
+        let topic = self.topic.as_ref()?;
+        let now = Utc::now();
+        let mut state = self.lock();
+        state.evict(now, self.window);
+
+        let mut violation_counts = BTreeMap::new();
+        for (_, violation) in &state.violations {
+            *violation_counts
+                .entry(violation.kind().to_string())
+                .or_default() += 1;
+        }
+        Some(OrderingReportResponse {
+            stream: self.stream.clone(),
+            topic: topic.clone(),
+            as_of: now,
+            window_secs: self.window.as_secs(),
+            messages_checked: state.checked,
+            unkeyed_messages: state.unkeyed,
+            keys: state.keys.len(),
+            violation_counts,
+            violations: state
+                .violations
+                .iter()
+                .rev()
+                .map(|(_, violation)| violation.clone())
+                .collect(),
+        })
+    }
+
+    /// Check what was appended to each partition since the last call, then
+    /// forget keys and violations past the window. Returns the number of
+    /// violations found.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failed topic lookup or peek; what was read before
+    /// it stays checked and the next call resumes from there.
+    #[instrument(skip(self), fields(stream = %self.stream, topic = ?self.topic))]
+    pub async fn catch_up(&self) -> AppResult<usize> {
+        let Some(topic) = &self.topic else {
+            return Ok(0);
+        };
+        let partitions = self.client.get_topic(&self.stream, topic).await?.partitions;
+        let mut found = 0;
+        for partition in &partitions {
+            found += self.catch_up_partition(topic, partition.id).await?;
+        }
+        self.lock().evict(Utc::now(), self.window);
+        if found > 0 {
+            warn!(violations = found, "Ordering violations found");
+        } else {
+            debug!("Ordering check caught up");
+        }
+        Ok(found)
+    }
+
+    /// Read one partition from its next offset (or the start of the
+    /// window) up to its end.
+    async fn catch_up_partition(&self, topic: &str, partition_id: u32) -> AppResult<usize> {
+        let next = self.lock().next_offsets.get(&partition_id).copied();
+        let since = oldest_kept(Utc::now(), self.window);
+        let mut found = 0;
+        self.consumer
+            .read_tail(
+                &self.stream,
+                topic,
+                partition_id,
+                TailStart::resume(next, since),
+                ORDERING_POLL_COUNT,
+                |messages, next| {
+                    let mut state = self.lock();
+                    found += state.record(messages, Utc::now(), self.window);
+                    state.next_offsets.insert(partition_id, next);
+                },
+            )
+            .await?;
+        Ok(found)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OrderingState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::iggy_client::{PRODUCER_EPOCH_HEADER, SEQUENCE_HEADER};
+    use crate::models::{Event, EventPayload};
+
+    const WINDOW: Duration = Duration::from_secs(3600);
+
+    fn message(
+        partition_id: u32,
+        offset: u64,
+        key: Option<&str>,
+        sequence: u64,
+        event_time: DateTime<Utc>,
+    ) -> ReceivedMessage {
+        let mut headers = BTreeMap::new();
+        if let Some(key) = key {
+            headers.insert(SEQUENCE_KEY_HEADER.to_string(), key.to_string());
+            headers.insert(SEQUENCE_HEADER.to_string(), sequence.to_string());
+            headers.insert(PRODUCER_EPOCH_HEADER.to_string(), "e1".to_string());
+        }
+        let mut event = Event::new("x", EventPayload::Generic(serde_json::json!({})));
+        event.timestamp = event_time;
+        ReceivedMessage {
+            partition_id,
+            offset,
+            timestamp: Utc::now(),
+            id: 0,
+            checksum: 0,
+            headers,
+            event,
+            size: 64,
+        }
+    }
+
+    #[test]
+    fn test_violations_of_each_kind_are_found() {
+        let now = Utc::now();
+        let ago = |secs| now - chrono::Duration::seconds(secs);
+        let mut state = OrderingState::default();
+        let found = state.record(
+            &[
+                message(0, 0, Some("a"), 1, ago(30)),
+                message(0, 1, Some("a"), 2, ago(20)),
+                message(0, 2, None, 0, ago(10)),
+                message(0, 3, Some("a"), 4, ago(25)),
+                message(0, 4, Some("a"), 4, ago(5)),
+            ],
+            now,
+            WINDOW,
+        );
+        assert_eq!(found, 3);
+        assert_eq!(
+            state.record(&[message(1, 0, Some("a"), 5, now)], now, WINDOW),
+            1
+        );
+        assert_eq!((state.checked, state.unkeyed), (6, 1));
+
+        let kinds: Vec<_> = state.violations.iter().map(|(_, v)| v.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "timestamp_regression",
+                "sequence_gap",
+                "sequence_duplicate",
+                "key_split"
+            ]
+        );
+        let (_, split) = state.violations.back().unwrap();
+        assert_eq!(
+            *split,
+            OrderingViolation::KeySplit {
+                key: "a".to_string(),
+                partition_id: 1,
+                offset: 0,
+                other_partitions: vec![0],
+            }
+        );
+    }
+
+    #[test]
+    fn test_keys_and_violations_past_the_window_are_forgotten() {
+        let now = Utc::now();
+        let mut state = OrderingState::default();
+        state.record(
+            &[
+                message(0, 0, Some("a"), 1, now),
+                message(0, 1, Some("a"), 3, now),
+            ],
+            now,
+            WINDOW,
+        );
+        assert_eq!((state.keys.len(), state.violations.len()), (1, 1));
+
+        let later = now + chrono::Duration::seconds(3601);
+        state.evict(later, WINDOW);
+        assert!(state.keys.is_empty());
+        assert!(state.violations.is_empty());
+        // Messages older than the window are not checked at all
+        assert_eq!(
+            state.record(&[message(0, 2, Some("a"), 9, now)], later, WINDOW),
+            0
+        );
+        assert_eq!(state.checked, 2);
+    }
+}
@@ -0,0 +1,451 @@
+//! In-memory outbox for sends made while the send circuit is open.
+//!
+//! The lighter alternative to the disk spool (see `Spool`): with
+//! `OUTBOX_CAPACITY` set, a send (single or batch) rejected because the
+//! send circuit breaker is open is queued in memory and answered with
+//! `202 Accepted` and `"spooled": true`. A background task (see `AppState`)
+//! delivers the queue in order once Iggy takes sends again. While the
+//! queue holds anything, new sends are queued behind it, so events sharing
+//! a partition key keep their order.
+//!
+//! # Overflow
+//!
+//! A send arriving at a full outbox is handled by `OUTBOX_OVERFLOW`:
+//!
+//! - `reject` (default) - the send fails as it would without an outbox
+//! - `drop_oldest` - the oldest queued events are discarded to make room
+//! - `block` - the request waits for room (bounded by its timeout)
+//!
+//! A batch larger than the whole outbox is always rejected.
+//!
+//! # Delivery
+//!
+//! The queue lives in memory only: events still queued at shutdown or a
+//! crash are lost, and evicted or rejected ones are counted in
+//! `iggy_outbox_dropped_total`. An event Iggy rejects with a non-retryable
+//! error on delivery is logged and dropped so it cannot block the queue.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::{ProducerService, SpooledSend};
+use crate::error::{AppError, AppResult};
+use crate::models::{Event, PartitioningStrategy, SendMessageResponse};
+
+/// Wait before delivering again after Iggy failed a delivery.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// What happens to a send arriving at a full outbox.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutboxOverflow {
+    /// Wait for room
+    Block,
+    /// Discard the oldest queued events to make room
+    DropOldest,
+    /// Fail the send (default)
+    #[default]
+    Reject,
+}
+
+impl FromStr for OutboxOverflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Self::Block),
+            "drop_oldest" => Ok(Self::DropOldest),
+            "reject" => Ok(Self::Reject),
+            _ => Err(format!(
+                "Unknown outbox overflow policy '{s}' (expected block, drop_oldest or reject)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutboxOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block => f.write_str("block"),
+            Self::DropOldest => f.write_str("drop_oldest"),
+            Self::Reject => f.write_str("reject"),
+        }
+    }
+}
+
+/// Queued sends, each with a sequence number so delivery removes the send
+/// it delivered even if `drop_oldest` evicted it meanwhile.
+#[derive(Default)]
+struct Queue {
+    sends: VecDeque<(u64, SpooledSend)>,
+    next_seq: u64,
+}
+
+/// Bounded in-memory buffer for sends while the send circuit is open,
+/// shared by the send handlers and the delivery task.
+pub struct Outbox {
+    capacity: usize,
+    overflow: OutboxOverflow,
+    queue: Mutex<Queue>,
+    /// Wakes the delivery loop when a send is queued.
+    queued: Notify,
+    /// Wakes blocked senders when sends leave the queue.
+    freed: Notify,
+}
+
+impl Outbox {
+    /// Create an outbox of `capacity` events; 0 disables it.
+    pub fn new(capacity: usize, overflow: OutboxOverflow) -> Self {
+        Self {
+            capacity,
+            overflow,
+            queue: Mutex::new(Queue::default()),
+            queued: Notify::new(),
+            freed: Notify::new(),
+        }
+    }
+
+    /// Check if sends are queued while the circuit is open.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Events waiting for delivery.
+    pub fn depth(&self) -> usize {
+        self.lock().sends.len()
+    }
+
+    /// Send one event through `producer`, queueing it if the send circuit
+    /// is open or the outbox is still delivering.
+    ///
+    /// # Errors
+    ///
+    /// Returns the send's error if it is not a `CircuitOpen`, or if the
+    /// outbox could not take the event (see "Overflow").
+    pub async fn send(
+        &self,
+        producer: &ProducerService,
+        send: SpooledSend,
+    ) -> AppResult<SendMessageResponse> {
+        let failure = if self.is_draining() {
+            None
+        } else {
+            match producer
+                .send_to(
+                    &send.stream,
+                    &send.topic,
+                    &send.event,
+                    send.partition_key.as_deref(),
+                    send.partitioning,
+                )
+                .await
+            {
+                Err(e) if self.is_enabled() && is_circuit_open(&e) => Some(e),
+                result => return result,
+            }
+        };
+        self.enqueue(producer, vec![send], failure)
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::Internal("Queued send has no response".to_string()))
+    }
+
+    /// Send a batch through `producer` like [`Self::send`]; a queued batch
+    /// is kept whole and in order.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::send`].
+    pub async fn send_batch(
+        &self,
+        producer: &ProducerService,
+        stream: &str,
+        topic: &str,
+        events: Vec<Event>,
+        partition_key: Option<String>,
+        partitioning: Option<PartitioningStrategy>,
+    ) -> AppResult<Vec<SendMessageResponse>> {
+        let failure = if self.is_draining() {
+            None
+        } else {
+            match producer
+                .send_batch_to(
+                    stream,
+                    topic,
+                    &events,
+                    partition_key.as_deref(),
+                    partitioning,
+                )
+                .await
+            {
+                Err(e) if self.is_enabled() && is_circuit_open(&e) => Some(e),
+                result => return result,
+            }
+        };
+        let sends = events
+            .into_iter()
+            .map(|event| SpooledSend {
+                stream: stream.to_string(),
+                topic: topic.to_string(),
+                event,
+                partition_key: partition_key.clone(),
+                partitioning,
+            })
+            .collect();
+        self.enqueue(producer, sends, failure).await
+    }
+
+    /// Deliver queued sends through `producer` until `cancel` is cancelled,
+    /// pausing for [`RETRY_DELAY`] while Iggy does not take them.
+    pub async fn run(&self, producer: &ProducerService, cancel: CancellationToken) {
+        loop {
+            let Some((seq, send)) = self.front() else {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => break,
+                    _ = self.queued.notified() => continue,
+                }
+            };
+
+            let result = tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+                result = producer.send_to(
+                    &send.stream,
+                    &send.topic,
+                    &send.event,
+                    send.partition_key.as_deref(),
+                    send.partitioning,
+                ) => result,
+            };
+            match result {
+                Ok(_) => self.remove(seq),
+                Err(e) if e.is_retryable() => {
+                    debug!(error = %e, "Iggy still unavailable; outbox delivery paused");
+                    tokio::select! {
+                        biased;
+
+                        _ = cancel.cancelled() => break,
+                        _ = tokio::time::sleep(RETRY_DELAY) => {}
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        event_id = %send.event.id,
+                        stream = %send.stream,
+                        topic = %send.topic,
+                        error = %e,
+                        "Dropping queued send rejected by Iggy"
+                    );
+                    crate::metrics::record_outbox_dropped("rejected", 1);
+                    self.remove(seq);
+                }
+            }
+        }
+
+        let lost = self.depth();
+        if lost > 0 {
+            warn!(
+                events = lost,
+                "Shutting down with undelivered sends in the outbox"
+            );
+        }
+    }
+
+    /// Check if earlier sends are still waiting in the outbox.
+    fn is_draining(&self) -> bool {
+        self.is_enabled() && self.depth() > 0
+    }
+
+    /// Queue `sends` (enriched as `producer` would send them now) and
+    /// answer them as queued. `failure` is the send error that led here,
+    /// returned instead if the outbox cannot take them.
+    async fn enqueue(
+        &self,
+        producer: &ProducerService,
+        sends: Vec<SpooledSend>,
+        failure: Option<AppError>,
+    ) -> AppResult<Vec<SendMessageResponse>> {
+        let count = sends.len();
+        if count > self.capacity {
+            crate::metrics::record_outbox_dropped("full", count as u64);
+            return Err(failure.unwrap_or_else(|| self.full_error()));
+        }
+
+        let timestamp = Utc::now();
+        let mut responses = Vec::with_capacity(count);
+        let sends: Vec<SpooledSend> = sends
+            .into_iter()
+            .map(|mut send| {
+                // Correlation and enrichment come from the request, gone by
+                // the time the send is delivered
+                send.event = producer.enrich(&send.event).into_owned();
+                responses.push(SendMessageResponse {
+                    success: true,
+                    event_id: send.event.id,
+                    stream: send.stream.clone(),
+                    topic: send.topic.clone(),
+                    timestamp,
+                    correlation_id: send.event.correlation_id,
+                    spooled: true,
+                });
+                send
+            })
+            .collect();
+
+        loop {
+            // Registered before checking, so room freed in between is seen
+            let freed = self.freed.notified();
+            tokio::pin!(freed);
+            freed.as_mut().enable();
+
+            {
+                let mut queue = self.lock();
+                let room = self.capacity - queue.sends.len();
+                if room >= count || self.overflow == OutboxOverflow::DropOldest {
+                    let evicted = count.saturating_sub(room);
+                    queue.sends.drain(..evicted);
+                    for send in sends {
+                        let seq = queue.next_seq;
+                        queue.next_seq += 1;
+                        queue.sends.push_back((seq, send));
+                    }
+                    crate::metrics::set_outbox_depth(queue.sends.len());
+                    drop(queue);
+
+                    if evicted > 0 {
+                        warn!(evicted, "Outbox full; dropped the oldest queued sends");
+                        crate::metrics::record_outbox_dropped("overflow", evicted as u64);
+                    }
+                    match &failure {
+                        Some(e) => warn!(error = %e, count, "Send circuit open; queued send"),
+                        None => debug!(count, "Outbox delivering; queued send"),
+                    }
+                    self.queued.notify_one();
+                    return Ok(responses);
+                }
+                if self.overflow == OutboxOverflow::Reject {
+                    crate::metrics::record_outbox_dropped("full", count as u64);
+                    return Err(failure.unwrap_or_else(|| self.full_error()));
+                }
+            }
+
+            info!(count, "Outbox full; waiting for room");
+            freed.await;
+        }
+    }
+
+    /// The oldest queued send.
+    fn front(&self) -> Option<(u64, SpooledSend)> {
+        self.lock().sends.front().cloned()
+    }
+
+    /// Remove the send numbered `seq` after delivery, unless it was already
+    /// evicted.
+    fn remove(&self, seq: u64) {
+        let mut queue = self.lock();
+        if queue.sends.front().is_some_and(|(front, _)| *front == seq) {
+            queue.sends.pop_front();
+        }
+        crate::metrics::set_outbox_depth(queue.sends.len());
+        drop(queue);
+        self.freed.notify_waiters();
+    }
+
+    fn full_error(&self) -> AppError {
+        AppError::CircuitOpen(format!(
+            "Outbox is full ({} events, OUTBOX_CAPACITY)",
+            self.capacity
+        ))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The send circuit breaker rejected the send without trying Iggy.
+fn is_circuit_open(error: &AppError) -> bool {
+    matches!(error.kind(), AppError::CircuitOpen(_))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::models::EventPayload;
+
+    fn send(key: &str) -> SpooledSend {
+        SpooledSend {
+            stream: "orders".to_string(),
+            topic: "created".to_string(),
+            event: Event::new(
+                "order.created",
+                EventPayload::Generic(serde_json::json!({})),
+            ),
+            partition_key: Some(key.to_string()),
+            partitioning: None,
+        }
+    }
+
+    fn queue(outbox: &Outbox, keys: &[&str]) {
+        let mut queue = outbox.lock();
+        for key in keys {
+            let seq = queue.next_seq;
+            queue.next_seq += 1;
+            queue.sends.push_back((seq, send(key)));
+        }
+    }
+
+    fn keys(outbox: &Outbox) -> Vec<String> {
+        outbox
+            .lock()
+            .sends
+            .iter()
+            .filter_map(|(_, send)| send.partition_key.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_overflow_policy_parsing() {
+        for text in ["block", "drop_oldest", "reject"] {
+            let policy: OutboxOverflow = text.parse().unwrap();
+            assert_eq!(policy.to_string(), text);
+        }
+        assert!("drop_newest".parse::<OutboxOverflow>().is_err());
+        assert_eq!(OutboxOverflow::default(), OutboxOverflow::Reject);
+    }
+
+    #[test]
+    fn test_remove_skips_evicted_sends() {
+        let outbox = Outbox::new(2, OutboxOverflow::DropOldest);
+        queue(&outbox, &["a", "b"]);
+        let (first, _) = outbox.front().unwrap();
+
+        // "a" was evicted while being delivered
+        outbox.lock().sends.pop_front();
+        queue(&outbox, &["c"]);
+        outbox.remove(first);
+        assert_eq!(keys(&outbox), ["b", "c"]);
+
+        let (second, _) = outbox.front().unwrap();
+        outbox.remove(second);
+        assert_eq!(keys(&outbox), ["c"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_is_disabled() {
+        let outbox = Outbox::new(0, OutboxOverflow::Block);
+        assert!(!outbox.is_enabled());
+        assert!(!outbox.is_draining());
+    }
+}
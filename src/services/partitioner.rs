@@ -0,0 +1,235 @@
+//! Client-side partition selection.
+//!
+//! # Sticky Assignment
+//!
+//! `sticky` partitioning sends everything for a topic to ONE partition for
+//! a time window, then moves on to the next partition (round robin across
+//! windows). Compared to `balanced`, consecutive sends land together, which
+//! keeps per-partition batches large; over time every partition still
+//! receives traffic.
+//!
+//! Assignments are per producer (per gateway process) and per stream/topic.
+//! A window of zero moves to the next partition on every send, i.e. plain
+//! client-side round robin.
+//!
+//! # Kafka-Compatible Key Hashing
+//!
+//! [`murmur2_partition`] reproduces Kafka's default partitioner for keyed
+//! records, so a key maps to the same partition NUMBER it had in a Kafka
+//! topic with the same partition count. This only holds while the counts
+//! match; adding partitions remaps keys, as it does in Kafka.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use iggy::prelude::Partitioning;
+
+use crate::error::AppResult;
+use crate::iggy_client::key_partitioning;
+
+/// Resolved destination of a send within a topic.
+///
+/// Hashable so that coalesced single sends are grouped by destination:
+/// only sends that would land the same way share a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum PartitionTarget {
+    /// Server-side round robin
+    Balanced,
+    /// Explicit (or client-computed) partition
+    Partition(u32),
+    /// Server-side hashing of this key
+    ServerKey(String),
+}
+
+impl PartitionTarget {
+    /// SDK partitioning for this target.
+    pub(super) fn partitioning(&self) -> AppResult<Partitioning> {
+        match self {
+            Self::Balanced => Ok(Partitioning::balanced()),
+            Self::Partition(partition_id) => Ok(Partitioning::partition_id(*partition_id)),
+            Self::ServerKey(key) => key_partitioning(Some(key)),
+        }
+    }
+}
+
+/// Current sticky assignment for one stream/topic.
+#[derive(Debug, Clone, Copy)]
+struct StickyAssignment {
+    partition_id: u32,
+    expires_at: Instant,
+}
+
+/// Tracks the sticky partition of each stream/topic.
+#[derive(Debug)]
+pub(super) struct StickyPartitioner {
+    window: Duration,
+    assignments: Mutex<HashMap<(String, String), StickyAssignment>>,
+}
+
+impl StickyPartitioner {
+    /// Create a partitioner that holds each partition for `window`.
+    pub(super) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            assignments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Partition for the next send to `stream`/`topic`.
+    ///
+    /// Keeps the current partition while its window is open (and it still
+    /// exists - the topic may have shrunk); otherwise advances to the next
+    /// partition, starting from 0.
+    pub(super) fn partition(
+        &self,
+        stream: &str,
+        topic: &str,
+        partitions_count: u32,
+        now: Instant,
+    ) -> u32 {
+        // The lock is never held across an await, and the map is always
+        // left consistent, so a poisoned lock is safe to reuse.
+        let mut assignments = self
+            .assignments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let key = (stream.to_string(), topic.to_string());
+
+        if let Some(current) = assignments.get(&key)
+            && now < current.expires_at
+            && current.partition_id < partitions_count
+        {
+            return current.partition_id;
+        }
+
+        let partition_id = assignments
+            .get(&key)
+            .map_or(0, |a| (a.partition_id + 1) % partitions_count.max(1));
+        assignments.insert(
+            key,
+            StickyAssignment {
+                partition_id,
+                expires_at: now + self.window,
+            },
+        );
+        partition_id
+    }
+}
+
+/// Seed of Kafka's murmur2 (`org.apache.kafka.common.utils.Utils.murmur2`).
+const MURMUR2_SEED: u32 = 0x9747_b28c;
+
+/// Multiplication constant of murmur2.
+const MURMUR2_M: u32 = 0x5bd1_e995;
+
+/// Partition for `key` under Kafka's default partitioner:
+/// `(murmur2(key) & 0x7fffffff) % partitions_count`.
+pub(super) fn murmur2_partition(key: &[u8], partitions_count: u32) -> u32 {
+    (murmur2(key) & 0x7fff_ffff) % partitions_count.max(1)
+}
+
+/// 32-bit murmur2 exactly as implemented by Kafka (seed `0x9747b28c`,
+/// little-endian 4-byte blocks).
+fn murmur2(data: &[u8]) -> u32 {
+    let mut h = MURMUR2_SEED ^ data.len() as u32;
+
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let mut k = little_endian(block);
+        k = k.wrapping_mul(MURMUR2_M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(MURMUR2_M);
+        h = h.wrapping_mul(MURMUR2_M) ^ k;
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        h ^= little_endian(tail);
+        h = h.wrapping_mul(MURMUR2_M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(MURMUR2_M);
+    h ^ (h >> 15)
+}
+
+/// Little-endian value of up to 4 bytes.
+fn little_endian(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |acc, &byte| (acc << 8) | u32::from(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_murmur2_matches_kafka_reference_values() {
+        // Expected values from Kafka's own `UtilsTest.testMurmur2`.
+        let cases: [(&str, i32); 6] = [
+            ("21", -973_932_308),
+            ("foobar", -790_332_482),
+            ("a-little-bit-long-string", -985_981_536),
+            ("a-little-bit-longer-string", -1_486_304_829),
+            (
+                "lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58_897_971,
+            ),
+            ("abc", 479_470_107),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(murmur2(key.as_bytes()) as i32, expected, "key {key}");
+        }
+    }
+
+    #[test]
+    fn test_murmur2_partition_is_stable() {
+        // Same key, same partition count: always the same partition.
+        assert_eq!(murmur2_partition(b"order-123", 3), 1);
+        assert_eq!(murmur2_partition(b"order-123", 6), 1);
+        assert_eq!(murmur2_partition(b"user-1", 3), 2);
+        for _ in 0..100 {
+            assert_eq!(murmur2_partition(b"user-1", 3), 2);
+        }
+    }
+
+    #[test]
+    fn test_sticky_partition_holds_for_window_then_advances() {
+        let partitioner = StickyPartitioner::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(partitioner.partition("s", "t", 3, start), 0);
+        assert_eq!(
+            partitioner.partition("s", "t", 3, start + Duration::from_secs(9)),
+            0
+        );
+        assert_eq!(
+            partitioner.partition("s", "t", 3, start + Duration::from_secs(10)),
+            1
+        );
+        assert_eq!(
+            partitioner.partition("s", "t", 3, start + Duration::from_secs(20)),
+            2
+        );
+        assert_eq!(
+            partitioner.partition("s", "t", 3, start + Duration::from_secs(30)),
+            0
+        );
+        // Topics are tracked independently.
+        assert_eq!(partitioner.partition("s", "other", 3, start), 0);
+    }
+
+    #[test]
+    fn test_zero_window_round_robins_every_send() {
+        let partitioner = StickyPartitioner::new(Duration::ZERO);
+        let now = Instant::now();
+
+        let picks: Vec<u32> = (0..4)
+            .map(|_| partitioner.partition("s", "t", 2, now))
+            .collect();
+        assert_eq!(picks, vec![0, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,897 @@
+//! Stream processing pipelines: filtered, reshaped copies of one topic
+//! produced to another.
+//!
+//! `POST /pipelines` defines a source topic, an optional filter, a payload
+//! transform and a sink topic. A background task (see `AppState`) runs every
+//! pipeline each `PIPELINE_INTERVAL_MS`: it reads what was appended to each
+//! source partition since its checkpoint, drops events the filter rejects,
+//! transforms the rest and produces them to the sink in one batch per read.
+//!
+//! # Filters
+//!
+//! Clauses joined by `&&`, each `field op value`:
+//!
+//! ```text
+//! event_type == order.created && payload.total >= 100 && payload.customer.tier != "free"
+//! ```
+//!
+//! Fields are `event_type`, `source`, or `payload.` followed by a dotted
+//! path into the payload's `data` (array elements by index). Operators are
+//! `==`, `!=`, `>`, `>=`, `<` and `<=`; the value is JSON when it parses as
+//! JSON and a bare string otherwise. Orderings compare numbers with numbers
+//! and strings with strings; any other pair never matches. A missing field
+//! is `null`.
+//!
+//! For anything the clauses cannot say, `expression` takes a Rhai predicate
+//! (see [`super::Expressions`]), checked after the filter. An event whose
+//! evaluation fails (runtime error, limit exceeded) is skipped and counted
+//! in `transform_errors`.
+//!
+//! # Transforms
+//!
+//! Transforms reshape the top-level fields of the payload's `data`: `drop`
+//! first, then `rename`, then `set`. Produced events keep the source
+//! event's ID, type, timestamp and correlation ID, so consumers of both
+//! topics can relate them; their payload becomes `Generic` (the shape may
+//! no longer match the original variant) and their `source` is
+//! `pipeline:<name>`. A payload whose `data` is not an object is passed
+//! through unchanged.
+//!
+//! A [`CustomTransform`] (a WASM module with the `wasm` feature) then runs
+//! on each transformed event and may replace or drop it. Events it fails on
+//! are skipped and counted in `transform_errors`, so one bad event cannot
+//! stall the pipeline.
+//!
+//! # Checkpoints
+//!
+//! Each pipeline commits its source offsets under its own consumer ID (by
+//! default derived from its name), after the sink send of every batch. A
+//! pipeline redefined under the same name, or on another replica, resumes
+//! from the checkpoint; a new one starts at each partition's first
+//! message. Delivery is at-least-once: a failed send is retried from the
+//! checkpoint at the next run, and a crash between send and commit
+//! produces the batch again.
+//!
+//! # Scope
+//!
+//! Definitions are in-memory and per-instance, like recurring schedules
+//! (see [`super::RecurringSchedules`]): they are lost on restart unless
+//! persisted (`STATE_BACKEND`, see [`super::StateStore`]) or carried
+//! over with `POST /admin/snapshot` and `POST /admin/restore`, and with
+//! leader election on (see [`super::LeaderElection`]) only the leader runs them.
+//! Define them from deployment tooling, with every replica.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::Utc;
+use serde_json::Value;
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use super::partitioner::murmur2_partition;
+use super::{ConsumerService, Expressions, Predicate, ProducerService};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::IggyClientWrapper;
+use crate::metrics;
+use crate::models::{
+    CreatePipelineRequest, Event, EventPayload, PipelineInfo, PipelineMetrics, PipelineTransform,
+    ReceivedMessage,
+};
+use crate::validation::MAX_CONSUMER_ID;
+
+/// Consumer ID of a pipeline defined without one: a hash of its name.
+pub fn default_consumer_id(name: &str) -> u32 {
+    murmur2_partition(name.as_bytes(), MAX_CONSUMER_ID) + 1
+}
+
+/// A comparison operator of a filter clause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// Operator tokens, two-character ones first so `>=` is not read as `>`.
+const OPS: [(&str, Op); 6] = [
+    ("==", Op::Eq),
+    ("!=", Op::Ne),
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+];
+
+/// The event field a filter clause tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    EventType,
+    Source,
+    /// JSON pointer into the serialized payload, under `/data`
+    Payload(String),
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "event_type" => Ok(Self::EventType),
+            "source" => Ok(Self::Source),
+            _ => {
+                let path = s
+                    .strip_prefix("payload.")
+                    .filter(|path| path.split('.').all(|segment| !segment.is_empty()))
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown field '{s}' (expected event_type, source or payload.<path>)"
+                        )
+                    })?;
+                let pointer = path
+                    .split('.')
+                    .map(|segment| segment.replace('~', "~0").replace('/', "~1"))
+                    .fold(String::from("/data"), |pointer, segment| {
+                        pointer + "/" + segment.as_str()
+                    });
+                Ok(Self::Payload(pointer))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+impl Clause {
+    fn matches(&self, event: &Event, payload: &Value) -> bool {
+        let actual = match &self.field {
+            Field::EventType => Value::String(event.event_type.clone()),
+            Field::Source => event.source.clone().map_or(Value::Null, Value::String),
+            Field::Payload(pointer) => payload.pointer(pointer).cloned().unwrap_or(Value::Null),
+        };
+        let ordering = match (&actual, &self.value) {
+            (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => None,
+            },
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        // Numbers compare by value, so `1` equals `1.0`
+        let equal = ordering.map_or(actual == self.value, |ordering| ordering.is_eq());
+        match self.op {
+            Op::Eq => equal,
+            Op::Ne => !equal,
+            Op::Gt => ordering.is_some_and(|ordering| ordering.is_gt()),
+            Op::Ge => ordering.is_some_and(|ordering| ordering.is_ge()),
+            Op::Lt => ordering.is_some_and(|ordering| ordering.is_lt()),
+            Op::Le => ordering.is_some_and(|ordering| ordering.is_le()),
+        }
+    }
+}
+
+/// A parsed pipeline filter: every clause must match.
+#[derive(Debug, Clone)]
+struct Filter {
+    clauses: Vec<Clause>,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clauses = s
+            .split("&&")
+            .map(|clause| {
+                let clause = clause.trim();
+                parse_clause(clause).map_err(|e| format!("Invalid filter clause '{clause}': {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { clauses })
+    }
+}
+
+fn parse_clause(clause: &str) -> Result<Clause, String> {
+    let start = clause
+        .find(['=', '!', '<', '>'])
+        .ok_or("expected field, operator and value")?;
+    let (field, rest) = clause.split_at(start);
+    let (op, value) = OPS
+        .iter()
+        .find_map(|&(token, op)| rest.strip_prefix(token).map(|value| (op, value.trim())))
+        .ok_or("unknown operator")?;
+    if value.is_empty() {
+        return Err("missing value".to_string());
+    }
+    Ok(Clause {
+        field: field.trim().parse()?,
+        op,
+        value: serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+    })
+}
+
+impl Filter {
+    /// Check if `event` passes every clause.
+    fn matches(&self, event: &Event) -> bool {
+        let payload = serde_json::to_value(&event.payload).unwrap_or(Value::Null);
+        self.clauses
+            .iter()
+            .all(|clause| clause.matches(event, &payload))
+    }
+}
+
+/// The event pipeline `name` produces for `event` under `transform`.
+fn transform_event(name: &str, transform: &PipelineTransform, event: &Event) -> Event {
+    let mut data = match serde_json::to_value(&event.payload) {
+        Ok(Value::Object(mut payload)) => payload.remove("data").unwrap_or(Value::Null),
+        _ => Value::Null,
+    };
+    if let Value::Object(fields) = &mut data {
+        for field in &transform.drop {
+            fields.remove(field);
+        }
+        for (from, to) in &transform.rename {
+            if let Some(value) = fields.remove(from) {
+                fields.insert(to.clone(), value);
+            }
+        }
+        for (field, value) in &transform.set {
+            fields.insert(field.clone(), value.clone());
+        }
+    }
+
+    let mut output = event.clone();
+    output.payload = EventPayload::Generic(data);
+    output.source = Some(format!("pipeline:{name}"));
+    output.produced_at = None;
+    output
+}
+
+/// A user-supplied transform run after a pipeline's built-in one.
+pub trait CustomTransform: Send + Sync {
+    /// The event to produce for `event`, or `None` to drop it.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the transform failed; the event is skipped.
+    fn apply(&self, event: &Event) -> Result<Option<Event>, String>;
+}
+
+/// A defined pipeline.
+#[derive(Clone)]
+struct Entry {
+    /// Distinguishes this definition from a later one under the same name
+    id: Uuid,
+    filter: Option<Filter>,
+    expression: Option<Predicate>,
+    custom: Option<Arc<dyn CustomTransform>>,
+    info: PipelineInfo,
+}
+
+/// Sink events made from a batch of source messages.
+#[derive(Default)]
+struct Processed {
+    events: Vec<Event>,
+    /// Dropped by the filter, the expression or the custom transform
+    filtered: u64,
+    /// Skipped because the expression or the custom transform failed
+    transform_errors: u64,
+}
+
+impl Entry {
+    /// Filter and transform `messages` into sink events.
+    fn process(&self, messages: &[ReceivedMessage]) -> Processed {
+        let mut processed = Processed::default();
+        for message in messages {
+            if let Some(filter) = &self.filter
+                && !filter.matches(&message.event)
+            {
+                processed.filtered += 1;
+                continue;
+            }
+            if let Some(expression) = &self.expression {
+                match expression.matches(&message.event) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        processed.filtered += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(
+                            pipeline = %self.info.name,
+                            event_id = %message.event.id,
+                            error = %e,
+                            "Pipeline expression failed; event skipped"
+                        );
+                        processed.transform_errors += 1;
+                        continue;
+                    }
+                }
+            }
+            let event = transform_event(&self.info.name, &self.info.transform, &message.event);
+            let Some(custom) = &self.custom else {
+                processed.events.push(event);
+                continue;
+            };
+            match custom.apply(&event) {
+                Ok(Some(event)) => processed.events.push(event),
+                Ok(None) => processed.filtered += 1,
+                Err(e) => {
+                    warn!(
+                        pipeline = %self.info.name,
+                        event_id = %message.event.id,
+                        error = %e,
+                        "Pipeline transform failed; event skipped"
+                    );
+                    processed.transform_errors += 1;
+                }
+            }
+        }
+        processed
+    }
+}
+
+/// Outcome of one batch read from a source partition.
+struct Batch {
+    partition_id: u32,
+    /// Next offset to read after the batch
+    next_offset: u64,
+    processed: u64,
+    filtered: u64,
+    transform_errors: u64,
+    produced: u64,
+}
+
+/// Defined pipelines and their progress.
+pub struct Pipelines {
+    client: IggyClientWrapper,
+    consumer: ConsumerService,
+    expressions: Arc<Expressions>,
+    max_pipelines: usize,
+    /// Source messages read per partition per peek
+    batch_size: u32,
+    pipelines: Mutex<BTreeMap<String, Entry>>,
+}
+
+impl Pipelines {
+    /// Create an empty set holding at most `max_pipelines` pipelines, each
+    /// reading `batch_size` messages at a time and compiling its
+    /// `expression` with `expressions`.
+    pub fn new(
+        client: IggyClientWrapper,
+        expressions: Arc<Expressions>,
+        max_pipelines: usize,
+        batch_size: u32,
+    ) -> Self {
+        Self {
+            consumer: ConsumerService::new(client.clone()),
+            client,
+            expressions,
+            max_pipelines,
+            batch_size,
+            pipelines: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Define `request` reading `source_stream` and producing to
+    /// `sink_stream`, replacing any pipeline with the same name. `custom`
+    /// is the resolved `request.wasm`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` for an invalid filter or expression, a
+    /// sink equal to
+    /// the source, or when `max_pipelines` other pipelines are defined.
+    pub fn register(
+        &self,
+        request: CreatePipelineRequest,
+        source_stream: String,
+        sink_stream: String,
+        custom: Option<Arc<dyn CustomTransform>>,
+    ) -> AppResult<PipelineInfo> {
+        let filter = request
+            .filter
+            .as_deref()
+            .map(Filter::from_str)
+            .transpose()
+            .map_err(AppError::BadRequest)?;
+        let expression = request
+            .expression
+            .as_deref()
+            .map(|source| self.expressions.compile(source))
+            .transpose()?;
+        if source_stream == sink_stream && request.source_topic == request.sink_topic {
+            return Err(AppError::BadRequest(
+                "A pipeline's sink must differ from its source".to_string(),
+            ));
+        }
+
+        let entry = Entry {
+            id: Uuid::new_v4(),
+            filter,
+            expression,
+            custom,
+            info: PipelineInfo {
+                consumer_id: request
+                    .consumer_id
+                    .unwrap_or_else(|| default_consumer_id(&request.name)),
+                name: request.name,
+                source_stream,
+                source_topic: request.source_topic,
+                filter: request.filter,
+                expression: request.expression,
+                transform: request.transform,
+                wasm: request.wasm,
+                sink_stream,
+                sink_topic: request.sink_topic,
+                paused: request.paused,
+                created_at: Utc::now(),
+                offsets: BTreeMap::new(),
+                metrics: PipelineMetrics::default(),
+            },
+        };
+        let info = entry.info.clone();
+
+        let mut pipelines = self.lock();
+        if !pipelines.contains_key(&info.name) && pipelines.len() >= self.max_pipelines {
+            return Err(AppError::BadRequest(format!(
+                "Too many pipelines (limit {})",
+                self.max_pipelines
+            )));
+        }
+        pipelines.insert(info.name.clone(), entry);
+        Ok(info)
+    }
+
+    /// Pause or resume a pipeline. A resumed pipeline continues from its
+    /// checkpoint at the next run.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no pipeline is named `name`.
+    pub fn set_paused(&self, name: &str, paused: bool) -> AppResult<PipelineInfo> {
+        let mut pipelines = self.lock();
+        let entry = pipelines.get_mut(name).ok_or_else(|| not_found(name))?;
+        entry.info.paused = paused;
+        Ok(entry.info.clone())
+    }
+
+    /// Remove a pipeline and return it. Its committed offsets are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no pipeline is named `name`.
+    pub fn remove(&self, name: &str) -> AppResult<PipelineInfo> {
+        self.lock()
+            .remove(name)
+            .map(|entry| entry.info)
+            .ok_or_else(|| not_found(name))
+    }
+
+    /// One pipeline by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no pipeline is named `name`.
+    pub fn get(&self, name: &str) -> AppResult<PipelineInfo> {
+        self.lock()
+            .get(name)
+            .map(|entry| entry.info.clone())
+            .ok_or_else(|| not_found(name))
+    }
+
+    /// Every pipeline, ordered by name.
+    pub fn list(&self) -> Vec<PipelineInfo> {
+        self.lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    /// Number of defined pipelines.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// `true` when no pipeline is defined.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run every pipeline that is not paused up to the end of its source.
+    /// Failures are logged and recorded on the pipeline, which retries from
+    /// its checkpoint at the next run. Returns the number of events
+    /// produced.
+    pub async fn run_all(&self, producer: &ProducerService) -> u64 {
+        let entries: Vec<Entry> = self
+            .lock()
+            .values()
+            .filter(|entry| !entry.info.paused)
+            .cloned()
+            .collect();
+
+        let mut produced = 0;
+        for entry in &entries {
+            let result = self.run_pipeline(producer, entry, &mut produced).await;
+            self.record_run(entry, result.err().map(|e| e.to_string()));
+        }
+        produced
+    }
+
+    /// Run one pipeline over each source partition, adding the events it
+    /// produces to `produced`.
+    #[instrument(skip_all, fields(pipeline = %entry.info.name))]
+    async fn run_pipeline(
+        &self,
+        producer: &ProducerService,
+        entry: &Entry,
+        produced: &mut u64,
+    ) -> AppResult<()> {
+        let info = &entry.info;
+        let partitions = self
+            .client
+            .get_topic(&info.source_stream, &info.source_topic)
+            .await?
+            .partitions;
+        for partition in &partitions {
+            let mut offset = match info.offsets.get(&partition.id) {
+                Some(&offset) => offset,
+                None => self
+                    .client
+                    .get_consumer_offset(
+                        &info.source_stream,
+                        &info.source_topic,
+                        info.consumer_id,
+                        partition.id,
+                    )
+                    .await?
+                    .map_or(0, |committed| committed + 1),
+            };
+            loop {
+                let (batch, end_of_partition) = self
+                    .run_batch(producer, entry, partition.id, offset)
+                    .await?;
+                offset = batch.next_offset;
+                *produced += batch.produced;
+                self.record_batch(entry, &batch);
+                if end_of_partition {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read, filter, transform and produce one batch from `offset`, then
+    /// commit it. Returns the batch and whether the partition's end was
+    /// reached.
+    async fn run_batch(
+        &self,
+        producer: &ProducerService,
+        entry: &Entry,
+        partition_id: u32,
+        offset: u64,
+    ) -> AppResult<(Batch, bool)> {
+        let info = &entry.info;
+        let response = self
+            .consumer
+            .peek_from(
+                &info.source_stream,
+                &info.source_topic,
+                partition_id,
+                offset,
+                self.batch_size,
+            )
+            .await?;
+        let next_offset = match response.messages.last() {
+            Some(last) => last.offset + 1,
+            // A batch of messages that are not events: step over it
+            None if !response.tail.end_of_partition => offset + u64::from(self.batch_size),
+            None => offset,
+        };
+        let processed = response.messages.len() as u64;
+        let end_of_partition = response.tail.end_of_partition;
+        // Expressions and WASM transforms run guest code for up to their
+        // limits per event, so the batch is processed off the runtime
+        let worker = entry.clone();
+        let messages = response.messages;
+        let Processed {
+            events,
+            filtered,
+            transform_errors,
+        } = tokio::task::spawn_blocking(move || worker.process(&messages))
+            .await
+            .map_err(|e| AppError::Internal(format!("Pipeline batch failed: {e}")))?;
+
+        if !events.is_empty()
+            && let Err(e) = producer
+                .send_batch_to(&info.sink_stream, &info.sink_topic, &events, None, None)
+                .await
+        {
+            metrics::record_pipeline_events(&info.name, "failed", events.len() as u64);
+            return Err(e);
+        }
+        if next_offset > offset {
+            self.client
+                .store_consumer_offset(
+                    &info.source_stream,
+                    &info.source_topic,
+                    info.consumer_id,
+                    partition_id,
+                    next_offset - 1,
+                )
+                .await?;
+        }
+
+        metrics::record_pipeline_events(&info.name, "produced", events.len() as u64);
+        metrics::record_pipeline_events(&info.name, "filtered", filtered);
+        metrics::record_pipeline_events(&info.name, "transform_error", transform_errors);
+        let batch = Batch {
+            partition_id,
+            next_offset,
+            processed,
+            filtered,
+            transform_errors,
+            produced: events.len() as u64,
+        };
+        Ok((batch, end_of_partition))
+    }
+
+    /// Record a committed batch, unless its pipeline has since been removed
+    /// or replaced.
+    fn record_batch(&self, entry: &Entry, batch: &Batch) {
+        let mut pipelines = self.lock();
+        let Some(current) = pipelines
+            .get_mut(&entry.info.name)
+            .filter(|current| current.id == entry.id)
+        else {
+            return;
+        };
+        current
+            .info
+            .offsets
+            .insert(batch.partition_id, batch.next_offset);
+        let metrics = &mut current.info.metrics;
+        metrics.processed += batch.processed;
+        metrics.filtered += batch.filtered;
+        metrics.transform_errors += batch.transform_errors;
+        metrics.produced += batch.produced;
+    }
+
+    /// Record the end of a run, failed with `error` or not.
+    fn record_run(&self, entry: &Entry, error: Option<String>) {
+        if let Some(error) = &error {
+            warn!(pipeline = %entry.info.name, error = %error, "Pipeline run failed");
+        } else {
+            debug!(pipeline = %entry.info.name, "Pipeline caught up");
+        }
+        let mut pipelines = self.lock();
+        let Some(current) = pipelines
+            .get_mut(&entry.info.name)
+            .filter(|current| current.id == entry.id)
+        else {
+            return;
+        };
+        let metrics = &mut current.info.metrics;
+        metrics.last_run_at = Some(Utc::now());
+        if error.is_some() {
+            metrics.failures += 1;
+        }
+        metrics.last_error = error;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Entry>> {
+        self.pipelines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::NotFound(format!("Pipeline '{name}' not found"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::iggy_client::BrokerBackend;
+    use serde_json::json;
+
+    fn event(event_type: &str, data: Value) -> Event {
+        Event::new(event_type, EventPayload::Generic(data))
+    }
+
+    #[test]
+    fn test_filter_clauses() {
+        let filter: Filter = "event_type == order.created && payload.total >= 100"
+            .parse()
+            .unwrap();
+        assert!(filter.matches(&event("order.created", json!({"total": 100.0}))));
+        assert!(!filter.matches(&event("order.created", json!({"total": 99}))));
+        assert!(!filter.matches(&event("order.paid", json!({"total": 500}))));
+        // A missing field is null, which no ordering matches
+        assert!(!filter.matches(&event("order.created", json!({}))));
+
+        let filter: Filter = r#"payload.customer.tier != "free" && payload.items.0 == "book""#
+            .parse()
+            .unwrap();
+        let paid = json!({"customer": {"tier": "gold"}, "items": ["book"]});
+        assert!(filter.matches(&event("x", paid)));
+        let free = json!({"customer": {"tier": "free"}, "items": ["book"]});
+        assert!(!filter.matches(&event("x", free)));
+
+        let filter: Filter = "source == null".parse().unwrap();
+        assert!(filter.matches(&event("x", json!({}))));
+
+        for invalid in [
+            "",
+            "event_type",
+            "event_type = x",
+            "event_type ==",
+            "payload == 1",
+            "payload..total > 1",
+            "total > 1 && event_type == x",
+        ] {
+            assert!(invalid.parse::<Filter>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_transform_drops_renames_then_sets() {
+        let transform = PipelineTransform {
+            rename: BTreeMap::from([("total".to_string(), "amount".to_string())]),
+            set: BTreeMap::from([("currency".to_string(), json!("EUR"))]),
+            drop: vec!["card".to_string()],
+        };
+        let source = event(
+            "order.created",
+            json!({"total": 5, "card": "4111", "id": 1}),
+        )
+        .with_correlation_id(Uuid::new_v4());
+        let output = transform_event("orders", &transform, &source);
+
+        assert_eq!(output.id, source.id);
+        assert_eq!(output.correlation_id, source.correlation_id);
+        assert_eq!(output.source.as_deref(), Some("pipeline:orders"));
+        let EventPayload::Generic(data) = output.payload else {
+            panic!("expected a Generic payload");
+        };
+        assert_eq!(data, json!({"amount": 5, "currency": "EUR", "id": 1}));
+
+        // Non-object data passes through
+        let output = transform_event("orders", &transform, &event("x", json!([1, 2])));
+        assert!(matches!(output.payload, EventPayload::Generic(data) if data == json!([1, 2])));
+    }
+
+    /// Drops `x.drop` events and fails on `x.fail` ones.
+    struct Picky;
+
+    impl CustomTransform for Picky {
+        fn apply(&self, event: &Event) -> Result<Option<Event>, String> {
+            match event.event_type.as_str() {
+                "x.drop" => Ok(None),
+                "x.fail" => Err("boom".to_string()),
+                _ => Ok(Some(event.clone())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_transform_runs_after_the_filter() {
+        let message = |event_type: &str, n: u64| ReceivedMessage {
+            partition_id: 0,
+            offset: n,
+            timestamp: Utc::now(),
+            id: 0,
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event: event(event_type, json!({ "n": n })),
+            size: 64,
+        };
+        let entry = Entry {
+            id: Uuid::new_v4(),
+            filter: Some("payload.n > 0".parse().unwrap()),
+            expression: None,
+            custom: Some(Arc::new(Picky)),
+            info: PipelineInfo {
+                name: "picky".to_string(),
+                source_stream: "s".to_string(),
+                source_topic: "a".to_string(),
+                filter: None,
+                expression: None,
+                transform: PipelineTransform::default(),
+                wasm: None,
+                sink_stream: "s".to_string(),
+                sink_topic: "b".to_string(),
+                consumer_id: 1,
+                paused: false,
+                created_at: Utc::now(),
+                offsets: BTreeMap::new(),
+                metrics: PipelineMetrics::default(),
+            },
+        };
+
+        let processed = entry.process(&[
+            message("x.keep", 0),
+            message("x.keep", 1),
+            message("x.drop", 2),
+            message("x.fail", 3),
+        ]);
+        assert_eq!(processed.events.len(), 1);
+        assert_eq!(
+            processed.events.first().unwrap().source.as_deref(),
+            Some("pipeline:picky")
+        );
+        assert_eq!(processed.filtered, 2);
+        assert_eq!(processed.transform_errors, 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_validates_and_replaces_by_name() {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config).await.unwrap();
+        let expressions = Arc::new(Expressions::new(1000, std::time::Duration::from_millis(10)));
+        let pipelines = Pipelines::new(client, expressions, 1, 100);
+        let request = |name: &str| CreatePipelineRequest {
+            name: name.to_string(),
+            source_stream: None,
+            source_topic: "orders".to_string(),
+            filter: Some("event_type == order.created".to_string()),
+            expression: None,
+            transform: PipelineTransform::default(),
+            wasm: None,
+            sink_stream: None,
+            sink_topic: "big-orders".to_string(),
+            consumer_id: None,
+            paused: false,
+        };
+
+        let info = pipelines
+            .register(request("big"), "s".into(), "s".into(), None)
+            .unwrap();
+        assert_eq!(info.consumer_id, default_consumer_id("big"));
+        assert!((1..=MAX_CONSUMER_ID).contains(&info.consumer_id));
+        assert!(
+            pipelines
+                .register(request("big"), "s".into(), "s".into(), None)
+                .is_ok()
+        );
+        let result = pipelines.register(request("other"), "s".into(), "s".into(), None);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let mut looped = request("big");
+        looped.sink_topic = "orders".to_string();
+        let result = pipelines.register(looped, "s".into(), "s".into(), None);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let mut invalid = request("big");
+        invalid.filter = Some("total > 1".to_string());
+        let result = pipelines.register(invalid, "s".into(), "s".into(), None);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let mut invalid = request("big");
+        invalid.expression = Some("payload.total >".to_string());
+        let result = pipelines.register(invalid, "s".into(), "s".into(), None);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        assert!(pipelines.set_paused("big", true).unwrap().paused);
+        assert!(matches!(
+            pipelines.set_paused("missing", true),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
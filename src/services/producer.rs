@@ -1,12 +1,28 @@
+use std::borrow::Cow;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use chrono::Utc;
-use tracing::{info, instrument};
+use iggy::prelude::{IggyMessage, Partitioning};
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
 
-use crate::error::AppResult;
-use crate::iggy_client::IggyClientWrapper;
-use crate::models::{Event, EventPayload, SendMessageResponse};
+use super::coalescer::Coalescer;
+use super::partitioner::{PartitionTarget, StickyPartitioner, murmur2_partition};
+use super::shadow::ShadowRule;
+use super::tap::MessageTap;
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{
+    IggyClientWrapper, IggyOperations, Sequencer, batch_message, event_message, sequenced_message,
+};
+use crate::models::{
+    AtomicBatchReport, AtomicChunkReport, AtomicChunkStatus, BatchCompensation, CompensationReport,
+    Event, EventPayload, KeyHashing, PartitioningStrategy, SendMessageResponse,
+};
+
+/// Event type of the tombstones compensating a partially sent atomic batch.
+pub const COMPENSATION_EVENT_TYPE: &str = "batch.compensated";
 
 /// Service for producing messages to Iggy streams.
 ///
@@ -20,44 +36,195 @@ use crate::models::{Event, EventPayload, SendMessageResponse};
 ///
 /// This differs from `ConnectionState` which uses `SeqCst` because connection
 /// state affects control flow and must be immediately visible across threads.
+///
+/// # Partitioning
+///
+/// Sends take an optional [`PartitioningStrategy`]. `partition_id:<n>` and
+/// `sticky` look up the topic's partition count first, so an out-of-range
+/// partition is rejected with 400 instead of failing inside the server.
+/// Keyed sends with [`KeyHashing::Murmur2`] do the same lookup and compute
+/// the partition in the gateway.
+///
+/// # Coalescing
+///
+/// With `COALESCE_WINDOW_MS` set, single sends are grouped into batches per
+/// destination (see the `coalescer` module); batch sends are unaffected.
+///
+/// # Key Sequencing
+///
+/// With `KEY_SEQUENCING` enabled, sends with a partition key are stamped
+/// with a per-key sequence number (see [`Sequencer`]). Numbers are assigned
+/// just before the send, and sequenced sends bypass coalescing so that a
+/// key's numbers are sent in the order they were assigned.
+///
+/// # Enrichment
+///
+/// Events without a `correlation_id` get the request's (see
+/// [`ProducerService::with_correlation_id`]); it is also sent as the
+/// `correlation_id` user header. With `EVENT_ENRICHMENT` enabled, every
+/// send path (single, batch, delayed, scheduled) additionally stamps each
+/// event before it is encoded: `source` defaults to `SERVICE_NAME` and
+/// `produced_at` is set to the send time. Values the client supplied are
+/// kept, except `produced_at`, which is always the server's.
+///
+/// # Shadowing
+///
+/// With `SHADOW_RULES` set, a sample of the events of every successful send
+/// to a source topic is copied to its shadow topic in the background (see
+/// [`ShadowRule`]).
+///
+/// # Atomic Batches
+///
+/// [`ProducerService::send_batch_atomic_to`] sends a batch in chunks of
+/// `ATOMIC_BATCH_CHUNK_SIZE`, each confirmed before the next, and stops at
+/// the first failure. The confirmed part is then compensated per
+/// `ATOMIC_BATCH_COMPENSATION`: a [`COMPENSATION_EVENT_TYPE`] tombstone
+/// per confirmed event, or the unconfirmed events to `DLQ_TOPIC`.
+/// Chunks without a partition key go to a partition chosen here (spread
+/// over the partitions for `balanced`), recorded in the report so each
+/// tombstone lands next to its event.
+///
+/// # Tap
+///
+/// Every successful send is also offered to the [`MessageTap`] behind
+/// `GET /admin/tap`, which copies it only while someone is listening.
+///
+/// # Client
+///
+/// The service is generic over [`IggyOperations`] and defaults to
+/// [`IggyClientWrapper`]; tests can use the `test-util` feature's
+/// `MockIggyClient` instead.
 #[derive(Clone)]
-pub struct ProducerService {
-    client: IggyClientWrapper,
+pub struct ProducerService<C = IggyClientWrapper> {
+    client: C,
     /// Total messages sent (monotonic counter, eventually consistent).
     messages_sent: Arc<AtomicU64>,
+    /// Sticky partition assignments (shared by request-scoped views).
+    sticky: Arc<StickyPartitioner>,
+    /// Key hashing used when a request does not pick one.
+    key_hashing: KeyHashing,
+    /// Single-send coalescing (None = every send is its own request).
+    coalescer: Option<Arc<Coalescer<C>>>,
+    /// Per-key sequence numbers (None = `KEY_SEQUENCING` disabled).
+    sequencer: Option<Arc<Sequencer>>,
+    /// `source` stamped by enrichment (None = `EVENT_ENRICHMENT` disabled).
+    enrichment: Option<Arc<str>>,
+    /// Default `correlation_id` of this request's events.
+    correlation_id: Option<Uuid>,
+    /// Topics mirrored to shadow topics (empty = `SHADOW_RULES` unset).
+    shadow_rules: Arc<[ShadowRule]>,
+    /// Live tap of sent messages (shared by request-scoped views).
+    tap: Arc<MessageTap>,
 }
 
-impl ProducerService {
+impl<C: IggyOperations> ProducerService<C> {
     /// Create a new producer service.
-    pub fn new(client: IggyClientWrapper) -> Self {
+    pub fn new(client: C) -> Self {
+        let config = client.config();
+        let sticky = StickyPartitioner::new(config.sticky_partition_window);
+        let key_hashing = config.partition_key_hashing;
+        let coalescer = config.coalescing_enabled().then(|| {
+            Arc::new(Coalescer::new(
+                client.clone(),
+                config.coalesce_window,
+                config.coalesce_max_batch,
+            ))
+        });
+        let sequencer = config.key_sequencing.then(|| Arc::new(Sequencer::new()));
+        let enrichment = config
+            .event_enrichment
+            .then(|| Arc::from(config.service_name.as_str()));
+        let shadow_rules = Arc::from(config.shadow_rules.as_slice());
         Self {
             client,
             messages_sent: Arc::new(AtomicU64::new(0)),
+            sticky: Arc::new(sticky),
+            key_hashing,
+            coalescer,
+            sequencer,
+            enrichment,
+            correlation_id: None,
+            shadow_rules,
+            tap: Arc::new(MessageTap::new()),
         }
     }
 
     /// Return a view of this service whose Iggy operations are bounded by
     /// `timeout` (clamped to the configured global — see
-    /// [`IggyClientWrapper::with_timeout`]). The sent-messages counter is
+    /// [`IggyOperations::with_timeout`]). The sent-messages counter is
     /// shared with the parent, so stats stay global.
     #[must_use]
     pub fn with_timeout(&self, timeout: std::time::Duration) -> Self {
         Self {
             client: self.client.with_timeout(timeout),
             messages_sent: Arc::clone(&self.messages_sent),
+            sticky: Arc::clone(&self.sticky),
+            key_hashing: self.key_hashing,
+            coalescer: self.coalescer.clone(),
+            sequencer: self.sequencer.clone(),
+            enrichment: self.enrichment.clone(),
+            correlation_id: self.correlation_id,
+            shadow_rules: Arc::clone(&self.shadow_rules),
+            tap: Arc::clone(&self.tap),
         }
     }
 
+    /// Use `correlation_id` (the request's, see
+    /// [`crate::middleware::CorrelationId`]) for events that carry none.
+    #[must_use]
+    pub fn with_correlation_id(mut self, correlation_id: Option<Uuid>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// The tap receiving every successful send.
+    pub fn tap(&self) -> &Arc<MessageTap> {
+        &self.tap
+    }
+
+    /// Apply enrichment to `event`, borrowing it when there is nothing to
+    /// add.
+    pub fn enrich<'a>(&self, event: &'a Event) -> Cow<'a, Event> {
+        if self.enrichment.is_none() && !self.correlates(event) {
+            return Cow::Borrowed(event);
+        }
+        Cow::Owned(enriched(
+            event,
+            self.enrichment.as_deref(),
+            self.correlation_id,
+        ))
+    }
+
+    /// Apply enrichment to every event of a batch.
+    fn enrich_all<'a>(&self, events: &'a [Event]) -> Cow<'a, [Event]> {
+        if self.enrichment.is_none() && !events.iter().any(|event| self.correlates(event)) {
+            return Cow::Borrowed(events);
+        }
+        Cow::Owned(
+            events
+                .iter()
+                .map(|event| enriched(event, self.enrichment.as_deref(), self.correlation_id))
+                .collect(),
+        )
+    }
+
+    /// Whether `event` would take this request's correlation ID.
+    fn correlates(&self, event: &Event) -> bool {
+        self.correlation_id.is_some() && event.correlation_id.is_none()
+    }
+
     /// Send an event to the default stream and topic.
     #[instrument(skip(self, event), fields(event_id = %event.id))]
     pub async fn send(
         &self,
         event: &Event,
         partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
     ) -> AppResult<SendMessageResponse> {
-        let stream = self.client.default_stream().to_string();
-        let topic = self.client.default_topic().to_string();
-        self.send_to(&stream, &topic, event, partition_key).await
+        let config = self.client.config();
+        let (stream, topic) = (config.default_stream.clone(), config.default_topic.clone());
+        self.send_to(&stream, &topic, event, partition_key, partitioning)
+            .await
     }
 
     /// Send an event to a specific stream and topic.
@@ -68,12 +235,34 @@ impl ProducerService {
         topic: &str,
         event: &Event,
         partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
     ) -> AppResult<SendMessageResponse> {
+        let target = self
+            .resolve_target(stream, topic, partition_key, partitioning)
+            .await?;
+        let event = self.enrich(event);
+        let event = event.as_ref();
+
         let start = std::time::Instant::now();
-        let result = self
-            .client
-            .send_event(stream, topic, event, partition_key)
-            .await;
+        let result = match (&self.sequencer, partition_key, &self.coalescer) {
+            (Some(sequencer), Some(key), _) => {
+                self.send_sequenced(
+                    sequencer,
+                    stream,
+                    topic,
+                    key,
+                    vec![event_message(event)?],
+                    &target.partitioning()?,
+                )
+                .await
+            }
+            (_, _, Some(coalescer)) => coalescer.submit(stream, topic, target, event.clone()).await,
+            (_, _, None) => {
+                self.client
+                    .send_event_partitioned(stream, topic, event, &target.partitioning()?)
+                    .await
+            }
+        };
         crate::metrics::record_send_duration(stream, topic, start.elapsed().as_secs_f64());
         if result.is_err() {
             crate::metrics::record_message_sent(stream, topic, "failure");
@@ -82,6 +271,9 @@ impl ProducerService {
 
         self.messages_sent.fetch_add(1, Ordering::Relaxed);
         crate::metrics::record_message_sent(stream, topic, "success");
+        self.shadow(stream, topic, std::slice::from_ref(event), partition_key);
+        self.tap
+            .publish(stream, topic, std::slice::from_ref(event), partition_key);
 
         Ok(SendMessageResponse {
             success: true,
@@ -89,6 +281,8 @@ impl ProducerService {
             stream: stream.to_string(),
             topic: topic.to_string(),
             timestamp: Utc::now(),
+            correlation_id: event.correlation_id,
+            spooled: false,
         })
     }
 
@@ -99,10 +293,11 @@ impl ProducerService {
         &self,
         events: &[Event],
         partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
     ) -> AppResult<Vec<SendMessageResponse>> {
-        let stream = self.client.default_stream().to_string();
-        let topic = self.client.default_topic().to_string();
-        self.send_batch_to(&stream, &topic, events, partition_key)
+        let config = self.client.config();
+        let (stream, topic) = (config.default_stream.clone(), config.default_topic.clone());
+        self.send_batch_to(&stream, &topic, events, partition_key, partitioning)
             .await
     }
 
@@ -114,12 +309,38 @@ impl ProducerService {
         topic: &str,
         events: &[Event],
         partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
     ) -> AppResult<Vec<SendMessageResponse>> {
+        let partitioning = self
+            .resolve_target(stream, topic, partition_key, partitioning)
+            .await?
+            .partitioning()?;
+        let events = self.enrich_all(events);
+        let events = events.as_ref();
+
         let start = std::time::Instant::now();
-        let result = self
-            .client
-            .send_events_batch(stream, topic, events, partition_key)
-            .await;
+        let result = match (&self.sequencer, partition_key) {
+            (Some(sequencer), Some(key)) => {
+                let config = self.client.config();
+                let messages = events
+                    .iter()
+                    .map(|event| {
+                        batch_message(
+                            event,
+                            config.batch_compression,
+                            config.compression_threshold_bytes,
+                        )
+                    })
+                    .collect::<AppResult<Vec<_>>>()?;
+                self.send_sequenced(sequencer, stream, topic, key, messages, &partitioning)
+                    .await
+            }
+            _ => {
+                self.client
+                    .send_events_batch_partitioned(stream, topic, events, &partitioning)
+                    .await
+            }
+        };
         crate::metrics::record_send_duration(stream, topic, start.elapsed().as_secs_f64());
         if result.is_err() {
             crate::metrics::record_messages_sent_batch(
@@ -134,6 +355,8 @@ impl ProducerService {
         self.messages_sent
             .fetch_add(events.len() as u64, Ordering::Relaxed);
         crate::metrics::record_messages_sent_batch(stream, topic, "success", events.len() as u64);
+        self.shadow(stream, topic, events, partition_key);
+        self.tap.publish(stream, topic, events, partition_key);
 
         let timestamp = Utc::now();
         // Allocate stream/topic once outside the loop to avoid per-event allocation
@@ -148,6 +371,8 @@ impl ProducerService {
                 stream: stream_owned.clone(),
                 topic: topic_owned.clone(),
                 timestamp,
+                correlation_id: event.correlation_id,
+                spooled: false,
             })
             .collect();
 
@@ -160,6 +385,172 @@ impl ProducerService {
         Ok(responses)
     }
 
+    /// Send `events` to `stream`/`topic` in chunks, each confirmed by Iggy
+    /// before the next is sent, compensating the batch if one fails.
+    ///
+    /// Only a batch whose every chunk was confirmed reports `success`. A
+    /// chunk that failed by timing out may still have been written; it
+    /// counts as unconfirmed, so it gets no tombstones and is also copied
+    /// to the DLQ topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only when the target is refused before any chunk
+    /// is sent (e.g. an out-of-range partition); send failures are in the
+    /// report.
+    #[instrument(skip(self, events), fields(batch_size = events.len()))]
+    pub async fn send_batch_atomic_to(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
+    ) -> AppResult<AtomicBatchReport> {
+        let target = self
+            .resolve_target(stream, topic, partition_key, partitioning)
+            .await?;
+        target.partitioning()?;
+        let chunk_size = self.client.config().atomic_batch_chunk_size.max(1);
+        let batch_id = Uuid::new_v4();
+        // Server-side round robin would hide where each chunk went, so
+        // balanced chunks take turns over partitions picked here
+        let balanced_partitions = match target {
+            PartitionTarget::Balanced => Some(self.partitions_count(stream, topic).await?.max(1)),
+            _ => None,
+        };
+
+        let mut chunks = Vec::new();
+        let mut confirmed = 0;
+        let mut failed = false;
+        for (index, chunk) in events.chunks(chunk_size).enumerate() {
+            let partition_id = match (&target, balanced_partitions) {
+                (_, Some(count)) => {
+                    let first = (batch_id.as_u128() % u128::from(count)) as u32;
+                    Some((first + index as u32) % count)
+                }
+                (PartitionTarget::Partition(partition_id), None) => Some(*partition_id),
+                _ => None,
+            };
+            let (status, error) = if failed {
+                (AtomicChunkStatus::NotSent, None)
+            } else {
+                let (partition_key, partitioning) =
+                    chunk_target(partition_key, partitioning, partition_id);
+                match self
+                    .send_batch_to(stream, topic, chunk, partition_key, partitioning)
+                    .await
+                {
+                    Ok(_) => {
+                        confirmed += chunk.len();
+                        (AtomicChunkStatus::Confirmed, None)
+                    }
+                    Err(e) => {
+                        failed = true;
+                        (AtomicChunkStatus::Failed, Some(e.to_string()))
+                    }
+                }
+            };
+            chunks.push(AtomicChunkReport {
+                first_event: index * chunk_size,
+                events: chunk.len(),
+                partition_id,
+                status,
+                error,
+            });
+        }
+
+        let mut report = AtomicBatchReport {
+            batch_id,
+            success: !failed,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            confirmed,
+            failed: events.len() - confirmed,
+            chunks,
+            compensation: None,
+        };
+        if failed {
+            let compensation = self
+                .compensate(&report, events, partition_key, partitioning)
+                .await;
+            warn!(
+                %batch_id,
+                stream,
+                topic,
+                confirmed,
+                failed = report.failed,
+                strategy = %compensation.strategy,
+                compensated = compensation.success,
+                "Atomic batch partially sent; compensated"
+            );
+            report.compensation = Some(compensation);
+        }
+        Ok(report)
+    }
+
+    /// Compensate the partially sent atomic batch of `events` described by
+    /// `report`.
+    async fn compensate(
+        &self,
+        report: &AtomicBatchReport,
+        events: &[Event],
+        partition_key: Option<&str>,
+        partitioning: Option<PartitioningStrategy>,
+    ) -> CompensationReport {
+        let (stream, topic) = (report.stream.as_str(), report.topic.as_str());
+        let (confirmed, unconfirmed) = events.split_at(report.confirmed);
+        let config = self.client.config();
+        let strategy = config.atomic_batch_compensation;
+        let (target, written, result) = match strategy {
+            BatchCompensation::Tombstone => {
+                // Same partition as its chunk (or the same key), so each
+                // tombstone follows its event
+                let mut result = Ok(());
+                for chunk in report
+                    .chunks
+                    .iter()
+                    .filter(|chunk| chunk.status == AtomicChunkStatus::Confirmed)
+                {
+                    let tombstones: Vec<Event> = confirmed
+                        .get(chunk.first_event..chunk.first_event + chunk.events)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|event| tombstone(report.batch_id, event))
+                        .collect();
+                    let (partition_key, partitioning) =
+                        chunk_target(partition_key, partitioning, chunk.partition_id);
+                    if let Err(e) = self
+                        .send_batch_to(stream, topic, &tombstones, partition_key, partitioning)
+                        .await
+                    {
+                        result = Err(e);
+                        break;
+                    }
+                }
+                (topic, confirmed.len(), result)
+            }
+            BatchCompensation::Dlq => {
+                let dlq = config.dlq_topic.as_str();
+                let result = async {
+                    self.client.ensure_topic(stream, dlq, 1).await?;
+                    self.send_batch_to(stream, dlq, unconfirmed, None, None)
+                        .await
+                        .map(drop)
+                }
+                .await;
+                (dlq, unconfirmed.len(), result)
+            }
+        };
+        CompensationReport {
+            strategy,
+            topic: target.to_string(),
+            events: written,
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    }
+
     /// Create and send a generic event with a JSON payload.
     #[instrument(skip(self, payload))]
     pub async fn send_generic(
@@ -169,13 +560,242 @@ impl ProducerService {
         partition_key: Option<&str>,
     ) -> AppResult<SendMessageResponse> {
         let event = Event::new(event_type, EventPayload::Generic(payload));
-        self.send(&event, partition_key).await
+        self.send(&event, partition_key, None).await
     }
 
     /// Get the total number of messages sent.
     pub fn messages_sent(&self) -> u64 {
         self.messages_sent.load(Ordering::Relaxed)
     }
+
+    /// Stamp `messages` with the next sequence numbers of `key` and send
+    /// them. Numbers are reserved even if the send fails, so consumers see
+    /// the failure as a gap.
+    async fn send_sequenced(
+        &self,
+        sequencer: &Sequencer,
+        stream: &str,
+        topic: &str,
+        key: &str,
+        messages: Vec<IggyMessage>,
+        partitioning: &Partitioning,
+    ) -> AppResult<()> {
+        let first = sequencer.reserve(stream, topic, key, messages.len() as u64);
+        let messages = messages
+            .iter()
+            .zip(first..)
+            .map(|(message, sequence)| sequenced_message(message, key, sequencer.epoch(), sequence))
+            .collect::<AppResult<Vec<_>>>()?;
+        self.client
+            .send_raw_messages(stream, topic, &messages, partitioning)
+            .await
+    }
+
+    /// Resolve a request's partitioning choice to a [`PartitionTarget`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` if the strategy and `partition_key`
+    /// conflict, or the partition ID is outside the topic's partitions.
+    async fn resolve_target(
+        &self,
+        stream: &str,
+        topic: &str,
+        partition_key: Option<&str>,
+        strategy: Option<PartitioningStrategy>,
+    ) -> AppResult<PartitionTarget> {
+        let Some(strategy) = strategy else {
+            return match partition_key {
+                Some(key) => {
+                    self.hashed_target(stream, topic, key, self.key_hashing)
+                        .await
+                }
+                None => Ok(PartitionTarget::Balanced),
+            };
+        };
+        check_unused_partition_key(strategy, partition_key)?;
+
+        match strategy {
+            PartitioningStrategy::Balanced => Ok(PartitionTarget::Balanced),
+            PartitioningStrategy::Key(hashing) => {
+                let Some(key) = partition_key else {
+                    return Err(AppError::BadRequest(
+                        "partitioning 'key' requires a partition_key".to_string(),
+                    ));
+                };
+                let hashing = hashing.unwrap_or(self.key_hashing);
+                self.hashed_target(stream, topic, key, hashing).await
+            }
+            PartitioningStrategy::PartitionId(partition_id) => {
+                let partitions_count = self.partitions_count(stream, topic).await?;
+                check_partition_id(partition_id, partitions_count)?;
+                Ok(PartitionTarget::Partition(partition_id))
+            }
+            PartitioningStrategy::Sticky => {
+                let partitions_count = self.partitions_count(stream, topic).await?;
+                let partition_id =
+                    self.sticky
+                        .partition(stream, topic, partitions_count, Instant::now());
+                Ok(PartitionTarget::Partition(partition_id))
+            }
+        }
+    }
+
+    /// Target for a keyed send under `hashing`.
+    async fn hashed_target(
+        &self,
+        stream: &str,
+        topic: &str,
+        key: &str,
+        hashing: KeyHashing,
+    ) -> AppResult<PartitionTarget> {
+        match hashing {
+            KeyHashing::Server => Ok(PartitionTarget::ServerKey(key.to_string())),
+            KeyHashing::Murmur2 => {
+                let partitions_count = self.partitions_count(stream, topic).await?;
+                Ok(PartitionTarget::Partition(murmur2_partition(
+                    key.as_bytes(),
+                    partitions_count,
+                )))
+            }
+        }
+    }
+
+    /// Copy the sampled part of `events`, just sent to `stream`/`topic`,
+    /// to the shadow topic of every matching rule, in the background.
+    fn shadow(&self, stream: &str, topic: &str, events: &[Event], partition_key: Option<&str>) {
+        for rule in self
+            .shadow_rules
+            .iter()
+            .filter(|rule| rule.matches(stream, topic))
+        {
+            let sampled: Vec<Event> = events
+                .iter()
+                .filter(|event| rule.samples(event.id))
+                .cloned()
+                .collect();
+            if sampled.is_empty() {
+                continue;
+            }
+            let producer = self.clone();
+            let rule = rule.clone();
+            let partition_key = partition_key.map(str::to_string);
+            tokio::spawn(async move {
+                let count = sampled.len() as u64;
+                match producer
+                    .send_shadow_copies(&rule, &sampled, partition_key.as_deref())
+                    .await
+                {
+                    Ok(()) => {
+                        debug!(rule = %rule, count, "Sent shadow copies");
+                        crate::metrics::record_shadow_sends(&rule.shadow_topic, "success", count);
+                    }
+                    Err(e) => {
+                        warn!(rule = %rule, count, error = %e, "Failed to send shadow copies");
+                        crate::metrics::record_shadow_sends(&rule.shadow_topic, "failure", count);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Send `events` to the shadow topic of `rule`, keyed like the original
+    /// send but otherwise balanced.
+    async fn send_shadow_copies(
+        &self,
+        rule: &ShadowRule,
+        events: &[Event],
+        partition_key: Option<&str>,
+    ) -> AppResult<()> {
+        let (stream, topic) = (&rule.shadow_stream, &rule.shadow_topic);
+        let partitioning = self
+            .resolve_target(stream, topic, partition_key, None)
+            .await?
+            .partitioning()?;
+        self.client
+            .send_events_batch_partitioned(stream, topic, events, &partitioning)
+            .await
+    }
+
+    async fn partitions_count(&self, stream: &str, topic: &str) -> AppResult<u32> {
+        let partitions_count = self.client.partitions_count(stream, topic).await?;
+        if partitions_count == 0 {
+            return Err(AppError::BadRequest(format!(
+                "Topic '{}' has no partitions",
+                topic
+            )));
+        }
+        Ok(partitions_count)
+    }
+}
+
+/// Copy of `event` with producer metadata filled in (see "Enrichment" on
+/// [`ProducerService`]); `service_name` is `None` unless `EVENT_ENRICHMENT`
+/// is enabled.
+fn enriched(event: &Event, service_name: Option<&str>, correlation_id: Option<Uuid>) -> Event {
+    let mut event = event.clone();
+    if event.correlation_id.is_none() {
+        event.correlation_id = correlation_id;
+    }
+    if let Some(service_name) = service_name {
+        event.source.get_or_insert_with(|| service_name.to_string());
+        event.produced_at = Some(Utc::now());
+    }
+    event
+}
+
+/// Tombstone compensating `event` of atomic batch `batch_id`, with the
+/// event's correlation ID.
+fn tombstone(batch_id: Uuid, event: &Event) -> Event {
+    let mut tombstone = Event::new(
+        COMPENSATION_EVENT_TYPE,
+        EventPayload::Generic(serde_json::json!({
+            "batch_id": batch_id,
+            "compensates": event.id,
+        })),
+    );
+    tombstone.correlation_id = event.correlation_id;
+    tombstone
+}
+
+/// Where an atomic batch chunk sent to `partition_id` goes: that partition,
+/// unless the batch has a partition key, which already picks the same
+/// partition every time.
+fn chunk_target(
+    partition_key: Option<&str>,
+    partitioning: Option<PartitioningStrategy>,
+    partition_id: Option<u32>,
+) -> (Option<&str>, Option<PartitioningStrategy>) {
+    match (partition_key, partition_id) {
+        (None, Some(partition_id)) => (None, Some(PartitioningStrategy::PartitionId(partition_id))),
+        _ => (partition_key, partitioning),
+    }
+}
+
+/// Only `key` uses a partition key; every other explicit strategy would
+/// ignore one, so a key there is rejected rather than silently dropped.
+fn check_unused_partition_key(
+    strategy: PartitioningStrategy,
+    partition_key: Option<&str>,
+) -> AppResult<()> {
+    match (strategy, partition_key) {
+        (PartitioningStrategy::Key(_), _) | (_, None) => Ok(()),
+        (other, Some(_)) => Err(AppError::BadRequest(format!(
+            "partition_key cannot be combined with partitioning '{}'",
+            other
+        ))),
+    }
+}
+
+/// Iggy partitions are 0-indexed: valid IDs are `0..partitions_count`.
+fn check_partition_id(partition_id: u32, partitions_count: u32) -> AppResult<()> {
+    if partition_id >= partitions_count {
+        return Err(AppError::BadRequest(format!(
+            "Partition {} does not exist (topic has {} partitions)",
+            partition_id, partitions_count
+        )));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -190,4 +810,49 @@ mod tests {
         counter.fetch_add(1, Ordering::Relaxed);
         assert_eq!(counter.load(Ordering::Relaxed), 2);
     }
+
+    #[test]
+    fn test_partition_key_rejected_unless_strategy_is_key() {
+        assert!(
+            check_unused_partition_key(PartitioningStrategy::Key(None), Some("user-1")).is_ok()
+        );
+        assert!(check_unused_partition_key(PartitioningStrategy::Sticky, None).is_ok());
+        assert!(matches!(
+            check_unused_partition_key(PartitioningStrategy::PartitionId(1), Some("user-1")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_enrichment_fills_missing_metadata_only() {
+        let request_id = Uuid::new_v4();
+        let event = Event::new("user.created", EventPayload::Generic(serde_json::json!({})));
+        let stamped = enriched(&event, Some("billing"), Some(request_id));
+        assert_eq!(stamped.source.as_deref(), Some("billing"));
+        assert_eq!(stamped.correlation_id, Some(request_id));
+        assert!(stamped.produced_at.is_some());
+
+        // Correlation is propagated even with EVENT_ENRICHMENT off
+        let correlated = enriched(&event, None, Some(request_id));
+        assert_eq!(correlated.correlation_id, Some(request_id));
+        assert!(correlated.source.is_none() && correlated.produced_at.is_none());
+
+        let own_correlation = Uuid::new_v4();
+        let event = event
+            .with_source("checkout")
+            .with_correlation_id(own_correlation);
+        let stamped = enriched(&event, Some("billing"), Some(request_id));
+        assert_eq!(stamped.source.as_deref(), Some("checkout"));
+        assert_eq!(stamped.correlation_id, Some(own_correlation));
+    }
+
+    #[test]
+    fn test_partition_id_validated_against_partition_count() {
+        assert!(check_partition_id(0, 3).is_ok());
+        assert!(check_partition_id(2, 3).is_ok());
+        assert!(matches!(
+            check_partition_id(3, 3),
+            Err(AppError::BadRequest(_))
+        ));
+    }
 }
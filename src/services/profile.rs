@@ -0,0 +1,251 @@
+//! Topic profiles: what flows through a topic, from its newest messages.
+//!
+//! `GET /streams/{stream}/topics/{topic}/profile` samples recent messages
+//! (see [`super::ConsumerService::sample_newest`]; peeks, so no consumer's
+//! offset moves) and summarizes them:
+//!
+//! - the distribution of stored message sizes
+//! - the most frequent event types
+//! - how often each payload field occurs, as a JSONPath with `[]` for the
+//!   elements of an array (`$.payload.data.items[].sku`), counted once per
+//!   message
+//! - the size the sampled events would have with each `BATCH_COMPRESSION`,
+//!   compressing each event on its own above `COMPRESSION_THRESHOLD_BYTES`
+//!   as batch sends do
+//!
+//! A sample of recent messages says little about rare event types or about
+//! older traffic; take it as a first look, not a census.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::iggy_client::{PayloadCompression, compress_payload};
+use crate::models::{
+    CompressionEstimate, FrequencyCount, ReceivedMessage, SizeDistribution, TopicProfile,
+};
+use crate::utils::percentile;
+
+/// Most messages one profile samples.
+pub const MAX_PROFILE_SAMPLE: u32 = 1000;
+
+/// Event types listed by a profile.
+pub const PROFILE_EVENT_TYPES: usize = 10;
+
+/// Payload fields listed by a profile.
+pub const PROFILE_FIELDS: usize = 50;
+
+/// Profile of `stream`/`topic` from the sampled `messages`.
+pub fn profile(
+    stream: &str,
+    topic: &str,
+    messages: &[ReceivedMessage],
+    compression_threshold: usize,
+) -> TopicProfile {
+    let mut event_types: HashMap<&str, usize> = HashMap::new();
+    let mut fields: HashMap<String, usize> = HashMap::new();
+    let mut json_bytes = 0;
+    let mut estimates: Vec<_> = [PayloadCompression::Gzip, PayloadCompression::Zstd]
+        .into_iter()
+        .map(|compression| (compression, 0, 0))
+        .collect();
+
+    for message in messages {
+        *event_types
+            .entry(message.event.event_type.as_str())
+            .or_default() += 1;
+        if let Ok(payload) = serde_json::to_value(&message.event.payload) {
+            let mut paths = HashSet::new();
+            field_paths(&payload, "$.payload".to_string(), &mut paths);
+            for path in paths {
+                *fields.entry(path).or_default() += 1;
+            }
+        }
+        let Ok(json) = serde_json::to_vec(&message.event) else {
+            continue;
+        };
+        json_bytes += json.len() as u64;
+        for (compression, bytes, compressed) in &mut estimates {
+            match compress_payload(*compression, compression_threshold, &json) {
+                Ok(Some(smaller)) => {
+                    *bytes += smaller.len() as u64;
+                    *compressed += 1;
+                }
+                _ => *bytes += json.len() as u64,
+            }
+        }
+    }
+
+    let timestamps = messages.iter().map(|message| message.timestamp);
+    TopicProfile {
+        stream: stream.to_string(),
+        topic: topic.to_string(),
+        sampled: messages.len(),
+        oldest: timestamps.clone().min(),
+        newest: timestamps.max(),
+        payload_bytes: size_distribution(messages.iter().map(|message| message.size as u64)),
+        event_types: most_frequent(event_types, messages.len(), PROFILE_EVENT_TYPES),
+        fields: most_frequent(fields, messages.len(), PROFILE_FIELDS),
+        json_bytes,
+        compression: estimates
+            .into_iter()
+            .map(
+                |(compression, bytes, compressed_messages)| CompressionEstimate {
+                    algorithm: compression.to_string(),
+                    bytes,
+                    ratio: if bytes == 0 {
+                        1.0
+                    } else {
+                        json_bytes as f64 / bytes as f64
+                    },
+                    compressed_messages,
+                },
+            )
+            .collect(),
+    }
+}
+
+/// Add the path of every leaf of `value` (`path` itself for a scalar or an
+/// empty container) to `paths`.
+fn field_paths(value: &Value, path: String, paths: &mut HashSet<String>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                field_paths(value, format!("{path}.{key}"), paths);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            let path = format!("{path}[]");
+            for value in array {
+                field_paths(value, path.clone(), paths);
+            }
+        }
+        _ => {
+            paths.insert(path);
+        }
+    }
+}
+
+/// The `limit` most frequent of `counts`, ties by value, with their share
+/// of `total`.
+fn most_frequent<K: Into<String>>(
+    counts: HashMap<K, usize>,
+    total: usize,
+    limit: usize,
+) -> Vec<FrequencyCount> {
+    let mut counts: Vec<_> = counts
+        .into_iter()
+        .map(|(value, count)| (value.into(), count))
+        .collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
+        .into_iter()
+        .take(limit)
+        .map(|(value, count)| FrequencyCount {
+            value,
+            count,
+            share: count as f64 / total.max(1) as f64,
+        })
+        .collect()
+}
+
+/// Minimum, maximum, mean and nearest-rank percentiles of `sizes`.
+fn size_distribution(sizes: impl Iterator<Item = u64>) -> SizeDistribution {
+    let mut sizes: Vec<u64> = sizes.collect();
+    if sizes.is_empty() {
+        return SizeDistribution::default();
+    }
+    sizes.sort_unstable();
+    SizeDistribution {
+        min: sizes.first().copied().unwrap_or_default(),
+        max: sizes.last().copied().unwrap_or_default(),
+        mean: sizes.iter().sum::<u64>() as f64 / sizes.len() as f64,
+        p50: percentile(&sizes, 0.5),
+        p90: percentile(&sizes, 0.9),
+        p99: percentile(&sizes, 0.99),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::{Event, EventPayload};
+
+    fn message(offset: u64, event_type: &str, data: Value, size: usize) -> ReceivedMessage {
+        ReceivedMessage {
+            partition_id: 0,
+            offset,
+            timestamp: Utc::now() - Duration::seconds(offset as i64),
+            id: u128::from(offset),
+            checksum: 0,
+            headers: BTreeMap::new(),
+            event: Event::new(event_type, EventPayload::Generic(data)),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_size_distribution() {
+        assert_eq!(
+            size_distribution(std::iter::empty()),
+            SizeDistribution::default()
+        );
+        let sizes = size_distribution((1..=100).rev());
+        assert_eq!((sizes.min, sizes.max), (1, 100));
+        assert_eq!((sizes.p50, sizes.p90, sizes.p99), (50, 90, 99));
+        assert!((sizes.mean - 50.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_profile_counts_event_types_and_fields() {
+        let repetitive = json!({ "note": "x".repeat(2000) });
+        let messages = vec![
+            message(
+                0,
+                "order.created",
+                json!({ "id": 1, "items": [{ "sku": "a" }] }),
+                100,
+            ),
+            message(1, "order.created", json!({ "id": 2, "items": [] }), 120),
+            message(2, "order.paid", repetitive, 2100),
+        ];
+        let profile = profile("s", "t", &messages, 1024);
+
+        assert_eq!(profile.sampled, 3);
+        assert!(profile.oldest < profile.newest);
+        assert_eq!(profile.payload_bytes.max, 2100);
+        let types: Vec<_> = profile
+            .event_types
+            .iter()
+            .map(|t| (t.value.as_str(), t.count))
+            .collect();
+        assert_eq!(types, [("order.created", 2), ("order.paid", 1)]);
+
+        let field = |path: &str| {
+            profile
+                .fields
+                .iter()
+                .find(|field| field.value == path)
+                .map(|field| field.count)
+        };
+        assert_eq!(field("$.payload.type"), Some(3));
+        assert_eq!(field("$.payload.data.id"), Some(2));
+        assert_eq!(field("$.payload.data.items[].sku"), Some(1));
+        // An empty array is a leaf of its own
+        assert_eq!(field("$.payload.data.items"), Some(1));
+        assert_eq!(field("$.payload.data.items[]"), None);
+
+        // Only the event above the threshold is compressed
+        for estimate in &profile.compression {
+            assert_eq!(estimate.compressed_messages, 1, "{}", estimate.algorithm);
+            assert!(estimate.ratio > 1.0);
+            assert!(estimate.bytes < profile.json_bytes);
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! Recurring (cron-based) producers.
+//!
+//! `POST /schedules` registers a cron expression and an event template; a
+//! background task (see `AppState`) produces a fresh event from the
+//! template on every tick - handy for heartbeats and synthetic traffic.
+//!
+//! Each produced event gets a new ID and timestamp, and its `source` is set
+//! to `schedule:<name>` so consumers can tell synthetic events apart.
+//!
+//! # Jitter
+//!
+//! With `jitter_ms`, every run is delayed by a random amount up to that
+//! bound, so many replicas (or many schedules on the same tick) do not
+//! produce at the same instant. The next run is computed from the cron
+//! tick after the actual run, so keep the jitter well below the interval:
+//! a run delayed past the following tick skips that tick.
+//!
+//! # Missed Runs
+//!
+//! Runs missed while a schedule was disabled, or while the task was busy,
+//! are not caught up: the schedule resumes at its next tick.
+//!
+//! # Scope
+//!
+//! Schedules are in-memory and per-instance: they are lost on restart
+//! unless persisted (`STATE_BACKEND`, see [`super::StateStore`]) or
+//! carried over with `POST /admin/snapshot` and `POST /admin/restore`.
+//! Every replica a schedule is registered with produces it, unless leader
+//! election is on (see [`LeaderElection`]): then only the leader produces,
+//! and the other replicas skip their runs. Register them from deployment
+//! tooling (re-registering a name replaces it), with every replica.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::{LeaderElection, ProducerService};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::rand_jitter;
+use crate::models::{CreateScheduleRequest, Event, ScheduleInfo, ScheduleRun};
+
+/// Largest accepted `jitter_ms` (one hour).
+pub const MAX_JITTER_MS: u64 = 3_600_000;
+
+/// A registered schedule.
+struct Entry {
+    /// Distinguishes this registration from a later one under the same name
+    id: Uuid,
+    schedule: Schedule,
+    info: ScheduleInfo,
+}
+
+impl Entry {
+    /// Set `next_run_at` to the first tick after `after`, plus jitter.
+    fn plan_next(&mut self, after: DateTime<Utc>) {
+        self.info.next_run_at = if self.info.enabled {
+            next_run(&self.schedule, after, self.info.jitter_ms)
+        } else {
+            None
+        };
+    }
+}
+
+/// A run taken off the schedule, produced outside the lock.
+struct DueRun {
+    id: Uuid,
+    name: String,
+    stream: String,
+    topic: String,
+    partition_key: Option<String>,
+    event: Event,
+}
+
+/// Registered recurring schedules.
+pub struct RecurringSchedules {
+    max_schedules: usize,
+    schedules: Mutex<BTreeMap<String, Entry>>,
+    /// Wakes the run loop when a schedule is added or changed.
+    wake: Notify,
+}
+
+impl RecurringSchedules {
+    /// Create an empty set holding at most `max_schedules` schedules.
+    pub fn new(max_schedules: usize) -> Self {
+        Self {
+            max_schedules,
+            schedules: Mutex::new(BTreeMap::new()),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Register `request` to produce to `stream`/`topic`, replacing any
+    /// schedule with the same name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` for an invalid or never-firing cron
+    /// expression, a `jitter_ms` above [`MAX_JITTER_MS`], or when
+    /// `max_schedules` other schedules are registered.
+    pub fn register(
+        &self,
+        request: CreateScheduleRequest,
+        stream: String,
+        topic: String,
+    ) -> AppResult<ScheduleInfo> {
+        let schedule = parse_cron(&request.cron)?;
+        if request.jitter_ms > MAX_JITTER_MS {
+            return Err(AppError::BadRequest(format!(
+                "jitter_ms must be at most {MAX_JITTER_MS}"
+            )));
+        }
+        let now = Utc::now();
+        if schedule.after(&now).next().is_none() {
+            return Err(AppError::BadRequest(format!(
+                "Cron expression '{}' never fires",
+                request.cron
+            )));
+        }
+
+        let mut entry = Entry {
+            id: Uuid::new_v4(),
+            schedule,
+            info: ScheduleInfo {
+                name: request.name,
+                cron: request.cron,
+                stream,
+                topic,
+                event_type: request.event_type,
+                payload: request.payload,
+                partition_key: request.partition_key,
+                jitter_ms: request.jitter_ms,
+                enabled: request.enabled,
+                created_at: now,
+                next_run_at: None,
+                last_run: None,
+                run_count: 0,
+                failure_count: 0,
+            },
+        };
+        entry.plan_next(now);
+        let info = entry.info.clone();
+
+        let mut schedules = self.lock();
+        if !schedules.contains_key(&info.name) && schedules.len() >= self.max_schedules {
+            return Err(AppError::BadRequest(format!(
+                "Too many schedules (limit {})",
+                self.max_schedules
+            )));
+        }
+        schedules.insert(info.name.clone(), entry);
+        drop(schedules);
+
+        self.wake.notify_one();
+        Ok(info)
+    }
+
+    /// Enable or disable a schedule. Enabling plans the next run from now.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no schedule is named `name`.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> AppResult<ScheduleInfo> {
+        let mut schedules = self.lock();
+        let entry = schedules.get_mut(name).ok_or_else(|| not_found(name))?;
+        if entry.info.enabled != enabled {
+            entry.info.enabled = enabled;
+            entry.plan_next(Utc::now());
+        }
+        let info = entry.info.clone();
+        drop(schedules);
+
+        self.wake.notify_one();
+        Ok(info)
+    }
+
+    /// Remove a schedule and return it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no schedule is named `name`.
+    pub fn remove(&self, name: &str) -> AppResult<ScheduleInfo> {
+        self.lock()
+            .remove(name)
+            .map(|entry| entry.info)
+            .ok_or_else(|| not_found(name))
+    }
+
+    /// One schedule by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no schedule is named `name`.
+    pub fn get(&self, name: &str) -> AppResult<ScheduleInfo> {
+        self.lock()
+            .get(name)
+            .map(|entry| entry.info.clone())
+            .ok_or_else(|| not_found(name))
+    }
+
+    /// Every schedule, ordered by name.
+    pub fn list(&self) -> Vec<ScheduleInfo> {
+        self.lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    /// Number of registered schedules.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// `true` when no schedule is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Produce schedules as they fall due until `cancel` fires. Runs due
+    /// while `leader` says this replica does not lead are skipped.
+    pub async fn run(
+        &self,
+        producer: &ProducerService,
+        leader: &LeaderElection,
+        cancel: CancellationToken,
+    ) {
+        loop {
+            let wait = self
+                .lock()
+                .values()
+                .filter_map(|entry| entry.info.next_run_at)
+                .min()
+                .map(|due| (due - Utc::now()).to_std().unwrap_or(Duration::ZERO));
+            let due = async {
+                match wait {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+                // Re-evaluate: a new or re-enabled schedule may be due sooner
+                _ = self.wake.notified() => continue,
+                _ = due => {}
+            }
+
+            let due = self.take_due(Utc::now());
+            if !leader.is_leader() {
+                debug!(
+                    skipped = due.len(),
+                    "Not the leader; scheduled runs skipped"
+                );
+                continue;
+            }
+            for run in due {
+                let result = producer
+                    .send_to(
+                        &run.stream,
+                        &run.topic,
+                        &run.event,
+                        run.partition_key.as_deref(),
+                        None,
+                    )
+                    .await;
+                if let Err(e) = &result {
+                    warn!(schedule = %run.name, error = %e, "Scheduled run failed");
+                } else {
+                    debug!(schedule = %run.name, topic = %run.topic, "Scheduled run produced");
+                }
+                self.record_run(&run, result.err().map(|e| e.to_string()));
+            }
+        }
+    }
+
+    /// Take every run due at or before `now`, planning each schedule's next
+    /// run.
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<DueRun> {
+        let mut schedules = self.lock();
+        let mut due = Vec::new();
+        for (name, entry) in schedules.iter_mut() {
+            if entry.info.next_run_at.is_none_or(|at| at > now) {
+                continue;
+            }
+            entry.plan_next(now);
+            let event = Event::new(entry.info.event_type.clone(), entry.info.payload.clone())
+                .with_source(format!("schedule:{name}"));
+            due.push(DueRun {
+                id: entry.id,
+                name: name.clone(),
+                stream: entry.info.stream.clone(),
+                topic: entry.info.topic.clone(),
+                partition_key: entry.info.partition_key.clone(),
+                event,
+            });
+        }
+        due
+    }
+
+    /// Record the outcome of `run`, unless its schedule has since been
+    /// removed or replaced.
+    fn record_run(&self, run: &DueRun, error: Option<String>) {
+        let mut schedules = self.lock();
+        let Some(entry) = schedules.get_mut(&run.name).filter(|e| e.id == run.id) else {
+            return;
+        };
+        entry.info.run_count += 1;
+        if error.is_some() {
+            entry.info.failure_count += 1;
+        }
+        entry.info.last_run = Some(ScheduleRun {
+            at: Utc::now(),
+            success: error.is_none(),
+            event_id: run.event.id,
+            error,
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Entry>> {
+        self.schedules
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Parse a cron expression, accepting the common five-field form
+/// (`min hour day month weekday`) by prepending a zero seconds field.
+///
+/// # Errors
+///
+/// Returns `AppError::BadRequest` if the expression does not parse.
+pub fn parse_cron(expression: &str) -> AppResult<Schedule> {
+    let expression = expression.trim();
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {expression}")
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&normalized)
+        .map_err(|e| AppError::BadRequest(format!("Invalid cron expression '{expression}': {e}")))
+}
+
+/// First tick of `schedule` after `after`, delayed by up to `jitter_ms`.
+fn next_run(schedule: &Schedule, after: DateTime<Utc>, jitter_ms: u64) -> Option<DateTime<Utc>> {
+    let tick = schedule.after(&after).next()?;
+    let jitter = (rand_jitter() * jitter_ms as f64) as i64;
+    Some(tick + chrono::Duration::milliseconds(jitter))
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::NotFound(format!("Schedule '{name}' not found"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+    use crate::models::EventPayload;
+
+    fn request(name: &str, cron: &str) -> CreateScheduleRequest {
+        CreateScheduleRequest {
+            name: name.to_string(),
+            cron: cron.to_string(),
+            stream: None,
+            topic: None,
+            event_type: "heartbeat".to_string(),
+            payload: EventPayload::Generic(serde_json::json!({"ok": true})),
+            partition_key: None,
+            jitter_ms: 0,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_cron_accepts_five_and_six_fields() {
+        assert!(parse_cron("*/5 * * * *").is_ok());
+        assert!(parse_cron("0 */5 * * * *").is_ok());
+        let err = parse_cron("every minute").unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+    }
+
+    #[test]
+    fn test_register_validates_and_replaces_by_name() {
+        let schedules = RecurringSchedules::new(1);
+        let info = schedules
+            .register(request("beat", "* * * * *"), "s".into(), "t".into())
+            .unwrap();
+        assert!(info.next_run_at.is_some());
+
+        // Same name replaces, even at the limit
+        let mut disabled = request("beat", "0 0 * * * *");
+        disabled.enabled = false;
+        let info = schedules
+            .register(disabled, "s".into(), "t".into())
+            .unwrap();
+        assert_eq!(info.next_run_at, None);
+        assert_eq!(schedules.len(), 1);
+
+        let result = schedules.register(request("other", "* * * * *"), "s".into(), "t".into());
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let mut jittery = request("beat", "* * * * *");
+        jittery.jitter_ms = MAX_JITTER_MS + 1;
+        let result = schedules.register(jittery, "s".into(), "t".into());
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_take_due_plans_next_run_and_records_outcome() {
+        let schedules = RecurringSchedules::new(10);
+        schedules
+            .register(request("beat", "* * * * * *"), "s".into(), "t".into())
+            .unwrap();
+        schedules
+            .register(request("hourly", "0 * * * *"), "s".into(), "t".into())
+            .unwrap();
+
+        let now = Utc::now() + chrono::Duration::seconds(2);
+        let due = schedules.take_due(now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "beat");
+        assert_eq!(due[0].event.source.as_deref(), Some("schedule:beat"));
+        assert!(schedules.get("beat").unwrap().next_run_at.unwrap() > now);
+
+        schedules.record_run(&due[0], Some("boom".to_string()));
+        let info = schedules.get("beat").unwrap();
+        assert_eq!((info.run_count, info.failure_count), (1, 1));
+        let last_run = info.last_run.unwrap();
+        assert!(!last_run.success);
+        assert_eq!(last_run.event_id, due[0].event.id);
+
+        // Disabled schedules are never due
+        schedules.set_enabled("beat", false).unwrap();
+        let due = schedules.take_due(now + chrono::Duration::hours(2));
+        let names: Vec<&str> = due.iter().map(|run| run.name.as_str()).collect();
+        assert_eq!(names, vec!["hourly"]);
+        assert!(matches!(
+            schedules.set_enabled("missing", true),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+//! Registry of consumers seen through the API.
+//!
+//! Standalone consumers are just numeric IDs to Iggy: a client that polls
+//! once with a random ID leaves a committed offset behind forever. The
+//! registry records every (stream, topic, consumer) that polls through this
+//! service, when it last polled, and the offsets it is known to have
+//! committed, for `GET /consumers`.
+//!
+//! With `CONSUMER_IDLE_TTL_SECS` set, a background task takes entries idle
+//! for longer than the TTL out of the registry and deletes their offsets in
+//! Iggy (see `AppState`). An offset is only deleted while Iggy still stores
+//! the offset last committed here: offsets are shared by every replica, and
+//! one that moved since was committed by the consumer through another
+//! replica. With leader election on, a consumer idle here may still be
+//! polling another replica without committing, so offsets are never
+//! deleted and the task only prunes the registry.
+//!
+//! The registry is in-memory and per-instance: it starts empty on restart
+//! (unless restored from `POST /admin/snapshot` with `POST /admin/restore`),
+//! and consumers that only ever polled another replica are not listed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{ConsumerInfo, ConsumerOffset};
+
+/// Identifies a consumer: (stream, topic, consumer ID).
+type ConsumerKey = (String, String, u32);
+
+/// What is known about one consumer.
+#[derive(Debug, Clone)]
+struct ConsumerEntry {
+    /// Monotonic time of the last poll, for idle checks
+    last_poll: Instant,
+    /// Wall-clock time of the last poll, for display
+    last_poll_at: DateTime<Utc>,
+    /// Partitions polled, with the offset last committed through this
+    /// service (`None` = polled without committing)
+    offsets: BTreeMap<u32, Option<u64>>,
+}
+
+/// A consumer removed from the registry for being idle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdleConsumer {
+    /// Stream name
+    pub stream: String,
+    /// Topic name
+    pub topic: String,
+    /// Consumer ID
+    pub consumer_id: u32,
+    /// Partitions the consumer is known to have committed on, ordered by
+    /// ID, with the offset last committed through this service (those
+    /// polled without committing have no offset to release)
+    pub partitions: Vec<(u32, u64)>,
+}
+
+/// Thread-safe registry of consumers, shared by every view of the consumer
+/// service.
+#[derive(Debug, Default)]
+pub struct ConsumerRegistry {
+    consumers: Mutex<HashMap<ConsumerKey, ConsumerEntry>>,
+}
+
+impl ConsumerRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a poll by `consumer_id` on one partition.
+    ///
+    /// `committed` is the offset the poll committed, if any; a poll that
+    /// did not commit keeps the partition's previously known offset.
+    pub fn record_poll(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        committed: Option<u64>,
+    ) {
+        let mut consumers = self
+            .consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (now, now_utc) = (Instant::now(), Utc::now());
+        let entry = consumers
+            .entry((stream.to_string(), topic.to_string(), consumer_id))
+            .or_insert_with(|| ConsumerEntry {
+                last_poll: now,
+                last_poll_at: now_utc,
+                offsets: BTreeMap::new(),
+            });
+        entry.last_poll = now;
+        entry.last_poll_at = now_utc;
+        let offset = entry.offsets.entry(partition_id).or_default();
+        if committed.is_some() {
+            *offset = committed;
+        }
+    }
+
+    /// Record an explicit commit (acknowledgment) of `offset` by
+    /// `consumer_id` on one partition. Acknowledging counts as activity, so
+    /// it also resets the idle clock.
+    pub fn record_commit(
+        &self,
+        stream: &str,
+        topic: &str,
+        consumer_id: u32,
+        partition_id: u32,
+        offset: u64,
+    ) {
+        self.record_poll(stream, topic, consumer_id, partition_id, Some(offset));
+    }
+
+    /// Number of consumers tracked.
+    pub fn len(&self) -> usize {
+        self.consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// `true` when no consumer has polled (or all have been cleaned up).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Snapshot of every tracked consumer, ordered by stream, topic and ID.
+    pub fn list(&self) -> Vec<ConsumerInfo> {
+        let consumers = self
+            .consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut list: Vec<ConsumerInfo> = consumers
+            .iter()
+            .map(|((stream, topic, consumer_id), entry)| ConsumerInfo {
+                stream: stream.clone(),
+                topic: topic.clone(),
+                consumer_id: *consumer_id,
+                last_poll_at: entry.last_poll_at,
+                idle_seconds: entry.last_poll.elapsed().as_secs(),
+                partitions: entry
+                    .offsets
+                    .iter()
+                    .map(|(&partition_id, &committed_offset)| ConsumerOffset {
+                        partition_id,
+                        committed_offset,
+                    })
+                    .collect(),
+            })
+            .collect();
+        list.sort_by(|a, b| {
+            (&a.stream, &a.topic, a.consumer_id).cmp(&(&b.stream, &b.topic, b.consumer_id))
+        });
+        list
+    }
+
+    /// Add the consumers of a snapshot (`POST /admin/restore`) that are not
+    /// tracked yet, returning how many were added. Their idle clock resumes
+    /// from `last_poll_at`, so the idle TTL still applies to them.
+    pub fn restore(&self, snapshot: Vec<ConsumerInfo>) -> usize {
+        let mut consumers = self
+            .consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (now, now_utc) = (Instant::now(), Utc::now());
+        let mut added = 0;
+        for info in snapshot {
+            let key = (info.stream, info.topic, info.consumer_id);
+            if consumers.contains_key(&key) {
+                continue;
+            }
+            let idle = (now_utc - info.last_poll_at).to_std().unwrap_or_default();
+            consumers.insert(
+                key,
+                ConsumerEntry {
+                    last_poll: now.checked_sub(idle).unwrap_or(now),
+                    last_poll_at: info.last_poll_at,
+                    offsets: info
+                        .partitions
+                        .into_iter()
+                        .map(|p| (p.partition_id, p.committed_offset))
+                        .collect(),
+                },
+            );
+            added += 1;
+        }
+        added
+    }
+
+    /// Remove and return every consumer that has not polled for longer
+    /// than `ttl`.
+    pub fn take_idle(&self, ttl: Duration) -> Vec<IdleConsumer> {
+        let mut consumers = self
+            .consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut idle = Vec::new();
+        consumers.retain(|(stream, topic, consumer_id), entry| {
+            if entry.last_poll.elapsed() <= ttl {
+                return true;
+            }
+            idle.push(IdleConsumer {
+                stream: stream.clone(),
+                topic: topic.clone(),
+                consumer_id: *consumer_id,
+                partitions: entry
+                    .offsets
+                    .iter()
+                    .filter_map(|(&partition_id, committed)| {
+                        committed.map(|offset| (partition_id, offset))
+                    })
+                    .collect(),
+            });
+            false
+        });
+        idle
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_poll_tracks_partitions_and_committed_offsets() {
+        let registry = ConsumerRegistry::new();
+        registry.record_poll("s", "t", 1, 0, Some(4));
+        // A poll without commit keeps the last known offset.
+        registry.record_poll("s", "t", 1, 0, None);
+        registry.record_poll("s", "t", 1, 2, None);
+        registry.record_poll("s", "other", 7, 0, Some(1));
+
+        let list = registry.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].topic, "other");
+        assert_eq!(list[1].consumer_id, 1);
+        let offsets: Vec<_> = list[1]
+            .partitions
+            .iter()
+            .map(|p| (p.partition_id, p.committed_offset))
+            .collect();
+        assert_eq!(offsets, vec![(0, Some(4)), (2, None)]);
+
+        registry.record_commit("s", "t", 1, 2, 8);
+        assert_eq!(registry.list()[1].partitions[1].committed_offset, Some(8));
+    }
+
+    #[test]
+    fn test_take_idle_removes_only_expired_consumers() {
+        let registry = ConsumerRegistry::new();
+        registry.record_poll("s", "t", 1, 3, Some(9));
+        registry.record_poll("s", "t", 1, 1, Some(0));
+        registry.record_poll("s", "t", 1, 2, None);
+
+        assert!(registry.take_idle(Duration::from_secs(60)).is_empty());
+        assert_eq!(registry.len(), 1);
+
+        std::thread::sleep(Duration::from_millis(5));
+        let idle = registry.take_idle(Duration::from_millis(1));
+        assert_eq!(
+            idle,
+            vec![IdleConsumer {
+                stream: "s".to_string(),
+                topic: "t".to_string(),
+                consumer_id: 1,
+                partitions: vec![(1, 0), (3, 9)],
+            }]
+        );
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_restore_adds_only_unknown_consumers() {
+        let source = ConsumerRegistry::new();
+        source.record_poll("s", "t", 1, 0, Some(4));
+        source.record_poll("s", "t", 2, 1, None);
+
+        let registry = ConsumerRegistry::new();
+        registry.record_poll("s", "t", 2, 1, Some(9));
+        assert_eq!(registry.restore(source.list()), 1);
+
+        let list = registry.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].partitions[0].committed_offset, Some(4));
+        // The live entry wins over the snapshot's
+        assert_eq!(list[1].partitions[0].committed_offset, Some(9));
+        assert!(registry.take_idle(Duration::from_secs(60)).is_empty());
+    }
+}
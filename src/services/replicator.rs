@@ -0,0 +1,559 @@
+//! Replication of local topics to a remote Iggy cluster.
+//!
+//! With `REPLICATION_CONNECTION_STRING` set, a background task (see
+//! `AppState`) copies every message appended to each `REPLICATION_TOPICS`
+//! topic to a topic of the remote cluster, every `REPLICATION_INTERVAL_MS`:
+//!
+//! ```text
+//! REPLICATION_TOPICS=orders/created,payments/settled:dr-payments/settled
+//! ```
+//!
+//! A mapping without a target keeps the stream and topic names. Missing
+//! remote streams and topics are created with the local partition count.
+//! Messages are copied as stored (payload and user headers), to the same
+//! partition when the remote topic has it, so per-partition order holds.
+//!
+//! # Loop Prevention
+//!
+//! Each copy carries a [`REPLICATED_FROM_HEADER`] user header naming this
+//! cluster (`REPLICATION_ORIGIN`), and messages that already carry one are
+//! never replicated. Two clusters can therefore replicate the same topic
+//! to each other without copies bouncing back; a copy is not forwarded
+//! further either, so a chain of clusters needs each to replicate from the
+//! origin.
+//!
+//! # Checkpoints
+//!
+//! Progress is committed as local consumer offsets of
+//! `REPLICATION_CONSUMER_ID`, after the remote send of every batch, so a
+//! restarted (or newly elected, see [`super::LeaderElection`]) replicator
+//! resumes where the last one stopped. Delivery is at-least-once: a failed
+//! send is retried from the checkpoint at the next run, and a crash between
+//! send and commit copies the batch again.
+//!
+//! # Lag
+//!
+//! Each run first measures the messages each topic has not replicated yet,
+//! exported as the `iggy_replication_lag` gauge and reported by
+//! `GET /admin/replication`. Counters are per-instance.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use iggy::prelude::{HeaderKey, HeaderValue, IggyMessage, Partitioning};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{IggyClientWrapper, PollParams};
+use crate::metrics;
+use crate::models::{ReplicationStatusResponse, ReplicationTopicStatus};
+
+/// User header naming the cluster a replicated message was copied from.
+pub const REPLICATED_FROM_HEADER: &str = "replicated_from";
+
+/// A local topic and the remote topic it is replicated to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationMapping {
+    /// Local stream read
+    pub source_stream: String,
+    /// Local topic read
+    pub source_topic: String,
+    /// Remote stream written
+    pub target_stream: String,
+    /// Remote topic written
+    pub target_topic: String,
+}
+
+impl FromStr for ReplicationMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (source, target) = match s.split_once(':') {
+            Some((source, target)) => (source.trim(), target.trim()),
+            None => (s.trim(), s.trim()),
+        };
+        let (source_stream, source_topic) = stream_topic(source, s)?;
+        let (target_stream, target_topic) = stream_topic(target, s)?;
+        Ok(Self {
+            source_stream: source_stream.to_string(),
+            source_topic: source_topic.to_string(),
+            target_stream: target_stream.to_string(),
+            target_topic: target_topic.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ReplicationMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}:{}/{}",
+            self.source_stream, self.source_topic, self.target_stream, self.target_topic
+        )
+    }
+}
+
+/// Split `stream/topic` from mapping `mapping`.
+fn stream_topic<'a>(target: &'a str, mapping: &str) -> Result<(&'a str, &'a str), String> {
+    target
+        .split_once('/')
+        .filter(|(stream, topic)| !stream.is_empty() && !topic.is_empty() && !topic.contains('/'))
+        .ok_or_else(|| {
+            format!("Invalid replication mapping '{mapping}': '{target}' is not stream/topic")
+        })
+}
+
+/// Cluster `message` was replicated from, if it is a copy.
+pub fn replicated_from(message: &IggyMessage) -> Option<String> {
+    let Ok(Some(headers)) = message.user_headers_map() else {
+        return None;
+    };
+    headers
+        .iter()
+        .find(|(key, _)| key.as_str() == Ok(REPLICATED_FROM_HEADER))
+        .map(|(_, value)| value.to_string_value())
+}
+
+/// Build the remote copy of `message`, keeping its payload and user
+/// headers and naming `origin` in [`REPLICATED_FROM_HEADER`].
+///
+/// # Errors
+///
+/// Returns `AppError::SendError` if the headers cannot be decoded or the
+/// copy cannot be built.
+pub fn replica_message(message: &IggyMessage, origin: &str) -> AppResult<IggyMessage> {
+    let header = |e: iggy::prelude::IggyError| AppError::SendError(e.to_string());
+    let mut headers: BTreeMap<HeaderKey, HeaderValue> = message
+        .user_headers_map()
+        .map_err(header)?
+        .unwrap_or_default();
+    headers.insert(
+        HeaderKey::from_str(REPLICATED_FROM_HEADER).map_err(header)?,
+        HeaderValue::from_str(origin).map_err(header)?,
+    );
+    IggyMessage::builder()
+        .payload(message.payload.clone())
+        .user_headers(headers)
+        .build()
+        .map_err(|e| AppError::SendError(e.to_string()))
+}
+
+/// Progress of one mapping since startup.
+#[derive(Debug, Default)]
+struct Progress {
+    /// Remote stream and topic exist
+    prepared: bool,
+    offsets: BTreeMap<u32, u64>,
+    lag: u64,
+    replicated: u64,
+    skipped: u64,
+    failures: u64,
+    last_run_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct ReplicatorState {
+    remote: Option<IggyClientWrapper>,
+    connection_error: Option<String>,
+    /// Indexed like `Replicator::mappings`
+    topics: Vec<Progress>,
+}
+
+/// Copies local topics to a remote cluster and tracks their progress.
+pub struct Replicator {
+    local: IggyClientWrapper,
+    mappings: Vec<ReplicationMapping>,
+    origin: String,
+    consumer_id: u32,
+    /// Local messages read per partition per poll
+    batch_size: u32,
+    state: Mutex<ReplicatorState>,
+}
+
+impl Replicator {
+    /// Create a replicator of `mappings`, reading them from `local` under
+    /// `consumer_id`, `batch_size` messages at a time, and stamping copies
+    /// with `origin`.
+    pub fn new(
+        local: IggyClientWrapper,
+        mappings: Vec<ReplicationMapping>,
+        origin: &str,
+        consumer_id: u32,
+        batch_size: u32,
+    ) -> Self {
+        let topics = mappings.iter().map(|_| Progress::default()).collect();
+        Self {
+            local,
+            mappings,
+            origin: origin.to_string(),
+            consumer_id,
+            batch_size,
+            state: Mutex::new(ReplicatorState {
+                topics,
+                ..ReplicatorState::default()
+            }),
+        }
+    }
+
+    /// Check if any topic is replicated.
+    pub fn is_enabled(&self) -> bool {
+        !self.mappings.is_empty()
+    }
+
+    /// Client of the remote cluster, connecting with `config` first unless
+    /// already connected. A failed attempt is logged, reported by
+    /// [`Self::status`] and retried at the next call.
+    pub async fn connect(
+        &self,
+        config: &Config,
+        shutdown: &CancellationToken,
+    ) -> Option<IggyClientWrapper> {
+        if let Some(remote) = self.lock().remote.clone() {
+            return Some(remote);
+        }
+        match IggyClientWrapper::new(config.clone()).await {
+            Ok(remote) => {
+                let remote = remote.with_shutdown(shutdown.clone());
+                info!("Connected to the replication target");
+                let mut state = self.lock();
+                state.remote = Some(remote.clone());
+                state.connection_error = None;
+                Some(remote)
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to connect to the replication target");
+                self.lock().connection_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Status of every mapping, in `REPLICATION_TOPICS` order.
+    pub fn status(&self, interval_ms: u64) -> ReplicationStatusResponse {
+        let state = self.lock();
+        ReplicationStatusResponse {
+            enabled: self.is_enabled(),
+            connected: state
+                .remote
+                .as_ref()
+                .is_some_and(IggyClientWrapper::is_connected),
+            origin: self.origin.clone(),
+            consumer_id: self.consumer_id,
+            interval_ms,
+            connection_error: state.connection_error.clone(),
+            topics: self
+                .mappings
+                .iter()
+                .zip(&state.topics)
+                .map(|(mapping, progress)| ReplicationTopicStatus {
+                    source_stream: mapping.source_stream.clone(),
+                    source_topic: mapping.source_topic.clone(),
+                    target_stream: mapping.target_stream.clone(),
+                    target_topic: mapping.target_topic.clone(),
+                    offsets: progress.offsets.clone(),
+                    lag: progress.lag,
+                    replicated: progress.replicated,
+                    skipped: progress.skipped,
+                    failures: progress.failures,
+                    last_run_at: progress.last_run_at,
+                    last_error: progress.last_error.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Replicate every mapping up to the end of its local topic. Failures
+    /// are logged and recorded on the mapping, which retries from its
+    /// checkpoint at the next run. Returns the number of messages copied.
+    pub async fn run_all(&self, remote: &IggyClientWrapper) -> u64 {
+        let mut replicated = 0;
+        for (index, mapping) in self.mappings.iter().enumerate() {
+            let result = self
+                .run_mapping(remote, index, mapping, &mut replicated)
+                .await;
+            self.record_run(index, mapping, result.err().map(|e| e.to_string()));
+        }
+        replicated
+    }
+
+    /// Replicate one mapping over each local partition, adding the
+    /// messages copied to `replicated`.
+    #[instrument(skip_all, fields(mapping = %mapping))]
+    async fn run_mapping(
+        &self,
+        remote: &IggyClientWrapper,
+        index: usize,
+        mapping: &ReplicationMapping,
+        replicated: &mut u64,
+    ) -> AppResult<()> {
+        let partitions = self
+            .local
+            .get_topic(&mapping.source_stream, &mapping.source_topic)
+            .await?
+            .partitions;
+        if !self.prepared(index) {
+            remote.ensure_stream(&mapping.target_stream).await?;
+            remote
+                .ensure_topic(
+                    &mapping.target_stream,
+                    &mapping.target_topic,
+                    partitions.len() as u32,
+                )
+                .await?;
+            self.with_progress(index, |progress| progress.prepared = true);
+        }
+        let remote_partitions = remote
+            .get_topic(&mapping.target_stream, &mapping.target_topic)
+            .await?
+            .partitions_count;
+
+        // Starting offsets first, for the lag of the whole topic
+        let mut starts = Vec::with_capacity(partitions.len());
+        let mut lag = 0;
+        for partition in &partitions {
+            let stored = self.with_progress(index, |progress| {
+                progress.offsets.get(&partition.id).copied()
+            });
+            let offset = match stored {
+                Some(offset) => offset,
+                None => self
+                    .local
+                    .get_consumer_offset(
+                        &mapping.source_stream,
+                        &mapping.source_topic,
+                        self.consumer_id,
+                        partition.id,
+                    )
+                    .await?
+                    .map_or(0, |committed| committed + 1),
+            };
+            if partition.messages_count > 0 {
+                lag += (partition.current_offset + 1).saturating_sub(offset);
+            }
+            starts.push((partition.id, offset));
+        }
+        self.with_progress(index, |progress| progress.lag = lag);
+        metrics::set_replication_lag(&mapping.source_stream, &mapping.source_topic, lag);
+
+        for (partition_id, mut offset) in starts {
+            let partitioning = if partition_id < remote_partitions {
+                Partitioning::partition_id(partition_id)
+            } else {
+                Partitioning::balanced()
+            };
+            loop {
+                let (next_offset, copied, end_of_partition) = self
+                    .run_batch(remote, index, mapping, partition_id, offset, &partitioning)
+                    .await?;
+                *replicated += copied;
+                offset = next_offset;
+                if end_of_partition {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy one batch from `offset` and commit it. Returns the next offset
+    /// to read, the messages copied and whether the partition's end was
+    /// reached.
+    async fn run_batch(
+        &self,
+        remote: &IggyClientWrapper,
+        index: usize,
+        mapping: &ReplicationMapping,
+        partition_id: u32,
+        offset: u64,
+        partitioning: &Partitioning,
+    ) -> AppResult<(u64, u64, bool)> {
+        let params = PollParams::new(partition_id, self.consumer_id)
+            .with_offset(offset)
+            .with_count(self.batch_size);
+        let polled = self
+            .local
+            .poll_messages(&mapping.source_stream, &mapping.source_topic, params)
+            .await?;
+        let Some(last) = polled.messages.last() else {
+            return Ok((offset, 0, true));
+        };
+        let next_offset = last.header.offset + 1;
+        let end_of_partition = last.header.offset >= polled.current_offset;
+
+        let mut copies = Vec::with_capacity(polled.messages.len());
+        let mut skipped = 0;
+        for message in &polled.messages {
+            if replicated_from(message).is_some() {
+                skipped += 1;
+            } else {
+                copies.push(replica_message(message, &self.origin)?);
+            }
+        }
+        let (stream, topic) = (&mapping.source_stream, &mapping.source_topic);
+        if let Err(e) = remote
+            .send_raw_messages(
+                &mapping.target_stream,
+                &mapping.target_topic,
+                &copies,
+                partitioning,
+            )
+            .await
+        {
+            metrics::record_replicated_messages(stream, topic, "failed", copies.len() as u64);
+            return Err(e);
+        }
+        self.local
+            .store_consumer_offset(
+                stream,
+                topic,
+                self.consumer_id,
+                partition_id,
+                next_offset - 1,
+            )
+            .await?;
+
+        metrics::record_replicated_messages(stream, topic, "replicated", copies.len() as u64);
+        metrics::record_replicated_messages(stream, topic, "skipped", skipped);
+        self.with_progress(index, |progress| {
+            progress.offsets.insert(partition_id, next_offset);
+            progress.lag = progress.lag.saturating_sub(next_offset - offset);
+            progress.replicated += copies.len() as u64;
+            progress.skipped += skipped;
+        });
+        Ok((next_offset, copies.len() as u64, end_of_partition))
+    }
+
+    /// Check if the remote stream and topic of mapping `index` exist.
+    fn prepared(&self, index: usize) -> bool {
+        self.with_progress(index, |progress| progress.prepared)
+    }
+
+    /// Record the end of a run of `mapping`, failed with `error` or not.
+    fn record_run(&self, index: usize, mapping: &ReplicationMapping, error: Option<String>) {
+        if let Some(error) = &error {
+            warn!(mapping = %mapping, error = %error, "Replication run failed");
+        } else {
+            debug!(mapping = %mapping, "Replication caught up");
+        }
+        self.with_progress(index, |progress| {
+            progress.last_run_at = Some(Utc::now());
+            if error.is_some() {
+                progress.failures += 1;
+                // The remote topic may have been deleted since
+                progress.prepared = false;
+            }
+            progress.last_error = error;
+        });
+    }
+
+    /// Run `f` on the progress of mapping `index`.
+    fn with_progress<T: Default>(&self, index: usize, f: impl FnOnce(&mut Progress) -> T) -> T {
+        self.lock().topics.get_mut(index).map(f).unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ReplicatorState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::iggy_client::BrokerBackend;
+    use crate::models::{Event, EventPayload};
+
+    async fn memory_client() -> IggyClientWrapper {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            ..Config::default()
+        };
+        IggyClientWrapper::new(config).await.unwrap()
+    }
+
+    #[test]
+    fn test_mapping_defaults_to_the_same_names() {
+        let mapping: ReplicationMapping = "orders/created".parse().unwrap();
+        assert_eq!(mapping.to_string(), "orders/created:orders/created");
+
+        let mapping: ReplicationMapping = "orders/created:dr/orders".parse().unwrap();
+        assert_eq!(
+            (
+                mapping.target_stream.as_str(),
+                mapping.target_topic.as_str()
+            ),
+            ("dr", "orders")
+        );
+
+        for invalid in ["orders", "orders/", "orders/created:dr", "a/b/c"] {
+            assert!(invalid.parse::<ReplicationMapping>().is_err(), "{invalid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_copies_new_messages_and_skips_replicas() {
+        let local = memory_client().await;
+        let remote = memory_client().await;
+        local.ensure_stream("orders").await.unwrap();
+        local.ensure_topic("orders", "created", 2).await.unwrap();
+        let events: Vec<_> = (0..3)
+            .map(|i| {
+                Event::new(
+                    "order.created",
+                    EventPayload::Generic(serde_json::json!({"n": i})),
+                )
+            })
+            .collect();
+        local
+            .send_events_batch_partitioned("orders", "created", &events, &Partitioning::balanced())
+            .await
+            .unwrap();
+        // A copy from another cluster is not sent back
+        let foreign = replica_message(&IggyMessage::from_str("{}").unwrap(), "east").unwrap();
+        local
+            .send_raw_messages(
+                "orders",
+                "created",
+                &[foreign],
+                &Partitioning::partition_id(0),
+            )
+            .await
+            .unwrap();
+
+        let mapping: ReplicationMapping = "orders/created:dr/orders".parse().unwrap();
+        let replicator = Replicator::new(local.clone(), vec![mapping], "west", 7, 2);
+        assert_eq!(replicator.run_all(&remote).await, 3);
+
+        let status = replicator.status(1000);
+        let topic = status.topics.first().unwrap();
+        assert_eq!((topic.replicated, topic.skipped, topic.lag), (3, 1, 0));
+        assert!(topic.last_error.is_none());
+        let details = remote.get_topic("dr", "orders").await.unwrap();
+        assert_eq!((details.partitions_count, details.messages_count), (2, 3));
+        let copied = remote
+            .poll_messages("dr", "orders", PollParams::new(0, 1).with_offset(0))
+            .await
+            .unwrap();
+        let first = copied.messages.first().unwrap();
+        assert_eq!(replicated_from(first).as_deref(), Some("west"));
+
+        // Checkpointed: a new replicator resumes after the copied messages
+        assert_eq!(replicator.run_all(&remote).await, 0);
+        let resumed = Replicator::new(local, replicator.mappings.clone(), "west", 7, 2);
+        assert_eq!(resumed.run_all(&remote).await, 0);
+        assert_eq!(
+            remote
+                .get_topic("dr", "orders")
+                .await
+                .unwrap()
+                .messages_count,
+            3
+        );
+    }
+}
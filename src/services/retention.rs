@@ -0,0 +1,176 @@
+//! Retention manager for the bootstrap spec's retention policies.
+//!
+//! Topics are created with the retention they are given, and API-created
+//! topics never expire. A `retention` policy in the bootstrap spec (see
+//! [`crate::bootstrap`]) is kept up on the existing topic instead: every
+//! `RETENTION_INTERVAL_SECS` the manager sets the topic's message expiry
+//! and size limit back to the policy's where they differ, and purges
+//! topics with a `purge_interval_secs` once it has elapsed.
+//!
+//! The first purge of a topic comes one purge interval after startup, not
+//! at startup, so a restart does not empty it. Enforcement is
+//! per-instance: with several replicas, each purges on its own schedule,
+//! unless leader election (see [`crate::services::LeaderElection`]) keeps
+//! it to the leader.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use tracing::{instrument, warn};
+
+use crate::bootstrap::{BootstrapSpec, PlannedTopic, RetentionPolicy};
+use crate::error::AppResult;
+use crate::iggy_client::IggyClientWrapper;
+use crate::models::RetentionTopicStatus;
+
+/// What the manager last did to one topic.
+#[derive(Debug, Clone, Default)]
+struct TopicRecord {
+    last_enforced_at: Option<DateTime<Utc>>,
+    last_updated_at: Option<DateTime<Utc>>,
+    last_purged_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Enforcer of the retention policies of a bootstrap spec.
+pub struct RetentionManager {
+    client: IggyClientWrapper,
+    /// Topics with a retention policy, in spec order
+    topics: Vec<PlannedTopic>,
+    started_at: DateTime<Utc>,
+    records: Mutex<HashMap<(String, String), TopicRecord>>,
+}
+
+impl RetentionManager {
+    /// Create a manager of the retention policies in `spec`.
+    pub fn new(client: IggyClientWrapper, spec: Option<&BootstrapSpec>) -> Self {
+        Self {
+            client,
+            topics: spec
+                .map(BootstrapSpec::retention_topics)
+                .unwrap_or_default(),
+            started_at: Utc::now(),
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Enforce every policy once. A topic that fails is logged and
+    /// recorded in its status; the others are still enforced. Returns the
+    /// number of topics that failed.
+    #[instrument(skip(self))]
+    pub async fn enforce(&self) -> usize {
+        let mut failures = 0;
+        for topic in &self.topics {
+            let Some(policy) = topic.retention else {
+                continue;
+            };
+            let now = Utc::now();
+            let result = self.enforce_topic(topic, &policy, now).await;
+
+            let mut records = self.lock();
+            let record = records
+                .entry((topic.stream.clone(), topic.topic.clone()))
+                .or_default();
+            match result {
+                Ok((updated, purged)) => {
+                    record.last_enforced_at = Some(now);
+                    if updated {
+                        record.last_updated_at = Some(now);
+                    }
+                    if purged {
+                        record.last_purged_at = Some(now);
+                    }
+                    record.last_error = None;
+                }
+                Err(e) => {
+                    warn!(
+                        stream = %topic.stream,
+                        topic = %topic.topic,
+                        error = %e,
+                        "Retention enforcement failed"
+                    );
+                    record.last_error = Some(e.to_string());
+                    failures += 1;
+                }
+            }
+        }
+        failures
+    }
+
+    /// Apply `policy` to one topic at `now`: whether its settings were
+    /// updated and whether it was purged.
+    async fn enforce_topic(
+        &self,
+        topic: &PlannedTopic,
+        policy: &RetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> AppResult<(bool, bool)> {
+        let updated = self
+            .client
+            .enforce_topic_retention(
+                &topic.stream,
+                &topic.topic,
+                policy.max_age_secs,
+                policy.max_size_bytes,
+            )
+            .await?;
+        let next_purge = self.next_purge_at(topic, policy);
+        let purged = next_purge.is_some_and(|at| at <= now);
+        if purged {
+            self.client.purge_topic(&topic.stream, &topic.topic).await?;
+        }
+        Ok((updated, purged))
+    }
+
+    /// When `topic` is purged next: one purge interval after its last
+    /// purge, or after startup.
+    fn next_purge_at(
+        &self,
+        topic: &PlannedTopic,
+        policy: &RetentionPolicy,
+    ) -> Option<DateTime<Utc>> {
+        let interval = chrono::Duration::seconds(
+            i64::try_from(policy.purge_interval_secs?).unwrap_or(i64::MAX),
+        );
+        let last = self
+            .lock()
+            .get(&(topic.stream.clone(), topic.topic.clone()))
+            .and_then(|record| record.last_purged_at)
+            .unwrap_or(self.started_at);
+        Some(
+            last.checked_add_signed(interval)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        )
+    }
+
+    /// Every topic with a retention policy and what was last done to it,
+    /// in spec order.
+    pub fn status(&self) -> Vec<RetentionTopicStatus> {
+        self.topics
+            .iter()
+            .filter_map(|topic| {
+                let policy = topic.retention?;
+                let record = self
+                    .lock()
+                    .get(&(topic.stream.clone(), topic.topic.clone()))
+                    .cloned()
+                    .unwrap_or_default();
+                Some(RetentionTopicStatus {
+                    stream: topic.stream.clone(),
+                    topic: topic.topic.clone(),
+                    policy,
+                    last_enforced_at: record.last_enforced_at,
+                    last_updated_at: record.last_updated_at,
+                    last_purged_at: record.last_purged_at,
+                    next_purge_at: self.next_purge_at(topic, &policy),
+                    last_error: record.last_error,
+                })
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), TopicRecord>> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,485 @@
+//! Delayed delivery of single sends.
+//!
+//! A send with `deliver_at` or `delay_ms` is not produced right away: it is
+//! held in an in-memory min-heap ordered by due time, and a background task
+//! (see `AppState`) produces it to its target topic once due.
+//!
+//! # Durability
+//!
+//! Every scheduled message is first appended to the `SCHEDULED_TOPIC`
+//! (single partition, default stream) as a `scheduled` record; delivery or
+//! cancellation appends a `done` tombstone. On startup the topic is replayed
+//! from the beginning before anything is delivered, so pending messages
+//! survive restarts. A crash between delivery and its tombstone delivers
+//! the message again after restart (at-least-once).
+//!
+//! # Replicas
+//!
+//! With leader election on (see [`LeaderElection`]), replicas share one
+//! `SCHEDULED_TOPIC`: each keeps reading the records the others append,
+//! every [`FOLLOW_INTERVAL`], so any replica lists and cancels every pending
+//! message, and only the leader delivers them. A message scheduled through
+//! another replica is delivered up to one interval late, and one delivered
+//! just before leadership moves may be delivered again by the new leader.
+//! Without leader election every replica delivers what it holds, so
+//! replicas sharing a topic would each deliver every message: give each
+//! its own topic.
+//!
+//! # Failed Deliveries
+//!
+//! A message whose send fails stays scheduled and is retried after
+//! [`RETRY_DELAY`]; its persisted record is untouched, so it is also
+//! retried after a restart.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use iggy::prelude::Partitioning;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::{LeaderElection, ProducerService};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{IggyClientWrapper, PollParams, payload_message};
+use crate::models::ScheduledMessage;
+
+/// The schedule topic has a single partition; records are written and
+/// replayed in order.
+const SCHEDULE_PARTITION_ID: u32 = 0;
+
+/// Consumer ID used for replay polls (offset-based; nothing is committed).
+const SCHEDULE_CONSUMER_ID: u32 = 1;
+
+/// Records fetched per replay poll.
+const REPLAY_POLL_COUNT: u32 = 1000;
+
+/// Wait before retrying a failed delivery.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Wait before retrying a failed replay on startup.
+const REPLAY_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How often records appended by other replicas are read, with leader
+/// election on.
+pub const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One entry in the schedule topic.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ScheduleRecord {
+    /// A message was scheduled
+    Scheduled(Box<ScheduledMessage>),
+    /// A message was delivered or cancelled
+    Done { id: Uuid },
+}
+
+/// Messages waiting for delivery.
+#[derive(Default)]
+struct Pending {
+    messages: HashMap<Uuid, ScheduledMessage>,
+    /// Due time per ID; entries for delivered or cancelled IDs are skipped
+    /// lazily when they reach the top.
+    queue: BinaryHeap<Reverse<(DateTime<Utc>, Uuid)>>,
+    /// Offset of the next schedule record to read
+    next_offset: u64,
+}
+
+impl Pending {
+    fn insert(&mut self, message: ScheduledMessage, due: DateTime<Utc>) {
+        self.queue.push(Reverse((due, message.id)));
+        self.messages.insert(message.id, message);
+    }
+
+    /// Due time of the earliest pending message.
+    fn next_due(&mut self) -> Option<DateTime<Utc>> {
+        while let Some(Reverse((due, id))) = self.queue.peek() {
+            if self.messages.contains_key(id) {
+                return Some(*due);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    /// Remove and return every message due at or before `now`.
+    fn take_due(&mut self, now: DateTime<Utc>) -> Vec<ScheduledMessage> {
+        let mut due = Vec::new();
+        while let Some(Reverse((at, id))) = self.queue.peek() {
+            if *at > now {
+                break;
+            }
+            let id = *id;
+            self.queue.pop();
+            if let Some(message) = self.messages.remove(&id) {
+                due.push(message);
+            }
+        }
+        due
+    }
+}
+
+/// Holds delayed sends until they are due.
+pub struct Scheduler {
+    client: IggyClientWrapper,
+    stream: String,
+    topic: String,
+    max_pending: usize,
+    pending: Mutex<Pending>,
+    /// Wakes the delivery loop when a message is scheduled.
+    wake: Notify,
+}
+
+impl Scheduler {
+    /// Create a scheduler persisting to `topic` in `stream`, holding at most
+    /// `max_pending` messages.
+    pub fn new(client: IggyClientWrapper, stream: &str, topic: &str, max_pending: usize) -> Self {
+        Self {
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            max_pending,
+            pending: Mutex::new(Pending::default()),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Schedule `message` for delivery at its `deliver_at`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` when `max_pending` messages are
+    /// already waiting, or the error persisting the record.
+    pub async fn schedule(&self, message: ScheduledMessage) -> AppResult<()> {
+        if self.len() >= self.max_pending {
+            return Err(AppError::BadRequest(format!(
+                "Too many scheduled messages (limit {})",
+                self.max_pending
+            )));
+        }
+        self.persist(&ScheduleRecord::Scheduled(Box::new(message.clone())))
+            .await?;
+
+        let due = message.deliver_at;
+        self.lock().insert(message, due);
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Cancel a pending message and return it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no message with `id` is pending, or
+    /// the error persisting the cancellation (the message is then still
+    /// pending).
+    pub async fn cancel(&self, id: Uuid) -> AppResult<ScheduledMessage> {
+        if !self.lock().messages.contains_key(&id) {
+            return Err(AppError::NotFound(format!(
+                "Scheduled message '{id}' not found"
+            )));
+        }
+        self.persist(&ScheduleRecord::Done { id }).await?;
+        self.lock()
+            .messages
+            .remove(&id)
+            .ok_or_else(|| AppError::NotFound(format!("Scheduled message '{id}' not found")))
+    }
+
+    /// Pending messages, earliest first.
+    pub fn list(&self) -> Vec<ScheduledMessage> {
+        let mut messages: Vec<ScheduledMessage> = self.lock().messages.values().cloned().collect();
+        messages.sort_by_key(|m| (m.deliver_at, m.id));
+        messages
+    }
+
+    /// Number of pending messages.
+    pub fn len(&self) -> usize {
+        self.lock().messages.len()
+    }
+
+    /// `true` when nothing is scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replay the schedule topic, then deliver messages as they fall due
+    /// until `cancel` fires. With leader election on, keep reading the
+    /// topic and deliver only while `leader` says this replica leads.
+    ///
+    /// Nothing is delivered before the replay has completed: a replayed
+    /// `scheduled` record could otherwise resurrect a message delivered in
+    /// the meantime.
+    pub async fn run(
+        &self,
+        producer: &ProducerService,
+        leader: &LeaderElection,
+        cancel: CancellationToken,
+    ) {
+        loop {
+            match self.replay().await {
+                Ok(restored) => {
+                    info!(restored, topic = %self.topic, "Scheduled messages restored");
+                    break;
+                }
+                Err(e) => warn!(error = %e, "Failed to restore scheduled messages, retrying"),
+            }
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(REPLAY_RETRY_DELAY) => {}
+            }
+        }
+
+        let follow = leader.is_enabled();
+        loop {
+            let next_due = if follow && !leader.is_leader() {
+                // Due messages stay until the leader's `done` records arrive
+                None
+            } else {
+                self.lock().next_due()
+            };
+            let mut wait =
+                next_due.map(|due| (due - Utc::now()).to_std().unwrap_or(Duration::ZERO));
+            if follow {
+                wait = Some(wait.map_or(FOLLOW_INTERVAL, |wait| wait.min(FOLLOW_INTERVAL)));
+            }
+            let due = async {
+                match wait {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+                // Re-evaluate: the new message may be due before the current wait ends.
+                // Following, read on, so steady scheduling cannot hold off the reads.
+                _ = self.wake.notified() => if !follow { continue },
+                _ = due => {}
+            }
+
+            if follow {
+                if let Err(e) = self.catch_up().await {
+                    warn!(error = %e, "Failed to read schedule records, delivery paused");
+                    continue;
+                }
+                if !leader.is_leader() {
+                    continue;
+                }
+            }
+            let due = self.lock().take_due(Utc::now());
+            for message in due {
+                self.deliver(producer, message).await;
+            }
+        }
+    }
+
+    /// Produce one due message, then record it as done. A failed send puts
+    /// the message back for another attempt after [`RETRY_DELAY`].
+    async fn deliver(&self, producer: &ProducerService, message: ScheduledMessage) {
+        let result = producer
+            .send_to(
+                &message.stream,
+                &message.topic,
+                &message.event,
+                message.partition_key.as_deref(),
+                message.partitioning,
+            )
+            .await;
+
+        match result {
+            Ok(_) => {
+                debug!(id = %message.id, topic = %message.topic, "Scheduled message delivered");
+                if let Err(e) = self.persist(&ScheduleRecord::Done { id: message.id }).await {
+                    warn!(
+                        id = %message.id,
+                        error = %e,
+                        "Failed to record delivery; message will be redelivered after a restart"
+                    );
+                }
+            }
+            Err(e) => {
+                warn!(
+                    id = %message.id,
+                    error = %e,
+                    retry_in_secs = RETRY_DELAY.as_secs(),
+                    "Scheduled delivery failed"
+                );
+                let retry_at = Utc::now() + RETRY_DELAY;
+                self.lock().insert(message, retry_at);
+            }
+        }
+    }
+
+    /// Rebuild the pending set from the schedule topic; returns how many
+    /// messages are pending afterwards.
+    async fn replay(&self) -> AppResult<usize> {
+        self.client.ensure_stream(&self.stream).await?;
+        self.client
+            .ensure_topic(&self.stream, &self.topic, 1)
+            .await?;
+        self.catch_up().await?;
+        Ok(self.len())
+    }
+
+    /// Apply the schedule records appended since the last read, this
+    /// replica's own included.
+    async fn catch_up(&self) -> AppResult<()> {
+        let mut offset = self.lock().next_offset;
+        loop {
+            let params = PollParams::new(SCHEDULE_PARTITION_ID, SCHEDULE_CONSUMER_ID)
+                .with_offset(offset)
+                .with_count(REPLAY_POLL_COUNT);
+            let polled = self
+                .client
+                .poll_messages(&self.stream, &self.topic, params)
+                .await?;
+            let Some(last) = polled.messages.last() else {
+                break;
+            };
+            offset = last.header.offset + 1;
+
+            let mut pending = self.lock();
+            for message in &polled.messages {
+                match serde_json::from_slice::<ScheduleRecord>(&message.payload) {
+                    Ok(record) => apply(&mut pending, record),
+                    Err(e) => warn!(
+                        offset = message.header.offset,
+                        error = %e,
+                        "Skipping unreadable schedule record"
+                    ),
+                }
+            }
+            pending.next_offset = offset;
+        }
+        Ok(())
+    }
+
+    /// Append a record to the schedule topic.
+    async fn persist(&self, record: &ScheduleRecord) -> AppResult<()> {
+        let message = payload_message(Bytes::from(serde_json::to_vec(record)?))?;
+        self.client
+            .send_raw_messages(
+                &self.stream,
+                &self.topic,
+                &[message],
+                &Partitioning::partition_id(SCHEDULE_PARTITION_ID),
+            )
+            .await
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Apply one replayed record to the pending set. A message already
+/// pending keeps its due time, which may be a retry's.
+fn apply(pending: &mut Pending, record: ScheduleRecord) {
+    match record {
+        ScheduleRecord::Scheduled(message) => {
+            if !pending.messages.contains_key(&message.id) {
+                let due = message.deliver_at;
+                pending.insert(*message, due);
+            }
+        }
+        ScheduleRecord::Done { id } => {
+            pending.messages.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::iggy_client::BrokerBackend;
+    use crate::models::{Event, EventPayload};
+
+    fn message(deliver_at: DateTime<Utc>) -> ScheduledMessage {
+        ScheduledMessage {
+            id: Uuid::new_v4(),
+            stream: "s".to_string(),
+            topic: "t".to_string(),
+            deliver_at,
+            event: Event::new("test.event", EventPayload::Generic(serde_json::json!({}))),
+            partition_key: None,
+            partitioning: None,
+        }
+    }
+
+    #[test]
+    fn test_take_due_returns_earliest_first_and_skips_removed() {
+        let now = Utc::now();
+        let mut pending = Pending::default();
+        let later = message(now + chrono::Duration::seconds(60));
+        let first = message(now - chrono::Duration::seconds(2));
+        let second = message(now - chrono::Duration::seconds(1));
+        let cancelled = message(now - chrono::Duration::seconds(3));
+        for m in [&later, &second, &first, &cancelled] {
+            pending.insert(m.clone(), m.deliver_at);
+        }
+        pending.messages.remove(&cancelled.id);
+
+        let due: Vec<Uuid> = pending.take_due(now).iter().map(|m| m.id).collect();
+        assert_eq!(due, vec![first.id, second.id]);
+        assert_eq!(pending.next_due(), Some(later.deliver_at));
+    }
+
+    #[test]
+    fn test_replayed_records_rebuild_pending_set() {
+        let now = Utc::now();
+        let delivered = message(now);
+        let open = message(now);
+        let records = [
+            ScheduleRecord::Scheduled(Box::new(delivered.clone())),
+            ScheduleRecord::Scheduled(Box::new(open.clone())),
+            ScheduleRecord::Done { id: delivered.id },
+        ];
+
+        let mut pending = Pending::default();
+        for record in records {
+            // Round-trip through the wire format, as a replay would.
+            let json = serde_json::to_vec(&record).unwrap();
+            apply(&mut pending, serde_json::from_slice(&json).unwrap());
+        }
+        assert_eq!(pending.messages.len(), 1);
+        assert!(pending.messages.contains_key(&open.id));
+    }
+
+    #[tokio::test]
+    async fn test_replicas_sharing_a_topic_follow_each_other() {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config).await.unwrap();
+        let first = Scheduler::new(client.clone(), "s", "_scheduled", 10);
+        let second = Scheduler::new(client, "s", "_scheduled", 10);
+        first.replay().await.unwrap();
+        second.replay().await.unwrap();
+
+        let scheduled = message(Utc::now() + chrono::Duration::seconds(60));
+        first.schedule(scheduled.clone()).await.unwrap();
+        second.catch_up().await.unwrap();
+        assert_eq!(second.list().first().map(|m| m.id), Some(scheduled.id));
+
+        second.cancel(scheduled.id).await.unwrap();
+        first.catch_up().await.unwrap();
+        assert!(first.is_empty());
+        // Reading its own records again changes nothing
+        second.catch_up().await.unwrap();
+        assert!(second.is_empty());
+    }
+}
@@ -0,0 +1,283 @@
+//! Server-side search of a partition's messages.
+//!
+//! `GET /streams/{stream}/topics/{topic}/search` reads one partition from
+//! `from_offset` with peeks (see [`ConsumerService::peek_from`]), so no
+//! consumer's offset moves, and returns the events matching either:
+//!
+//! - `q` - a substring of the event's JSON (case-sensitive), or
+//! - `path` and `equals` - the value at a JSONPath into the event, like
+//!   `$.payload.data.items[0].sku`, equal to `equals` (read as JSON when it
+//!   parses, else as a string, so `equals=42` and `equals=abc` both work)
+//!
+//! # Limits
+//!
+//! Iggy has no index on payloads, so every message is read and parsed. A
+//! search stops at whichever comes first: the end of the partition,
+//! `limit` matches, `max_scan` messages read (at most `SEARCH_MAX_SCAN`),
+//! or `SEARCH_TIMEOUT_MS`. The response says which, and its `next_offset`
+//! continues the scan in another request.
+//!
+//! Only dotted paths with array indexes are supported; wildcards, filters
+//! and recursive descent are rejected.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::ConsumerService;
+use crate::error::{AppError, AppResult};
+use crate::models::{Event, SearchResponse, SearchStop};
+
+/// What the events returned by a search match.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchMatcher {
+    /// The event's JSON contains the string
+    Substring(String),
+    /// The value at the JSON pointer equals the value
+    PathEquals {
+        /// JSON pointer converted from the JSONPath
+        pointer: String,
+        /// Expected value
+        value: Value,
+    },
+}
+
+impl SearchMatcher {
+    /// Matcher for the `q`, `path` and `equals` query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` unless exactly one of `q` and `path`
+    /// (with `equals`) is given, or for an unsupported JSONPath.
+    pub fn new(q: Option<&str>, path: Option<&str>, equals: Option<&str>) -> AppResult<Self> {
+        match (q, path, equals) {
+            (Some(q), None, None) if !q.is_empty() => Ok(Self::Substring(q.to_string())),
+            (None, Some(path), Some(equals)) => Ok(Self::PathEquals {
+                pointer: json_path_pointer(path)?,
+                value: serde_json::from_str(equals)
+                    .unwrap_or_else(|_| Value::String(equals.to_string())),
+            }),
+            _ => Err(AppError::BadRequest(
+                "Search needs either a non-empty q, or path with equals".to_string(),
+            )),
+        }
+    }
+
+    /// Check if `event` matches.
+    pub fn matches(&self, event: &Event) -> bool {
+        match self {
+            Self::Substring(needle) => {
+                serde_json::to_string(event).is_ok_and(|json| json.contains(needle.as_str()))
+            }
+            Self::PathEquals { pointer, value } => {
+                serde_json::to_value(event).is_ok_and(|json| json.pointer(pointer) == Some(value))
+            }
+        }
+    }
+}
+
+/// Bounds of one search.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchLimits {
+    /// Most matches returned
+    pub limit: usize,
+    /// Most messages read
+    pub max_scan: u64,
+    /// Messages read per peek
+    pub batch: u32,
+    /// Longest the scan runs
+    pub timeout: Duration,
+}
+
+/// Scan `partition_id` of `stream`/`topic` from `from_offset` for events
+/// `matcher` accepts, within `limits`.
+///
+/// # Errors
+///
+/// Returns the error of a peek; a peek cut short by the timeout ends the
+/// search instead.
+pub async fn search(
+    consumer: &ConsumerService,
+    stream: &str,
+    topic: &str,
+    partition_id: u32,
+    from_offset: u64,
+    matcher: &SearchMatcher,
+    limits: SearchLimits,
+) -> AppResult<SearchResponse> {
+    let deadline = Instant::now() + limits.timeout;
+    let mut matches = Vec::new();
+    let mut offset = from_offset;
+    let mut scanned = 0;
+
+    let stopped = loop {
+        if matches.len() >= limits.limit {
+            break SearchStop::Limit;
+        }
+        let remaining = limits.max_scan.saturating_sub(scanned);
+        if remaining == 0 {
+            break SearchStop::MaxScan;
+        }
+        let count = limits
+            .batch
+            .min(u32::try_from(remaining).unwrap_or(u32::MAX));
+        let peek = consumer.peek_from(stream, topic, partition_id, offset, count);
+        let Ok(response) = tokio::time::timeout_at(deadline, peek).await else {
+            break SearchStop::Timeout;
+        };
+        let response = response?;
+
+        let mut next = match response.messages.last() {
+            Some(last) => last.offset + 1,
+            // A batch of messages that are not events: step over it
+            None if !response.tail.end_of_partition => offset + u64::from(count),
+            None => offset,
+        };
+        scanned += match response.messages.len() {
+            0 => next - offset,
+            read => read as u64,
+        };
+        let mut full = false;
+        for message in response.messages {
+            if matcher.matches(&message.event) {
+                let after = message.offset + 1;
+                matches.push(message);
+                if matches.len() >= limits.limit {
+                    // Continue right after the last match returned
+                    next = after;
+                    full = true;
+                    break;
+                }
+            }
+        }
+        offset = next;
+
+        if full {
+            break SearchStop::Limit;
+        }
+        if response.tail.end_of_partition {
+            break SearchStop::EndOfPartition;
+        }
+        if Instant::now() >= deadline {
+            break SearchStop::Timeout;
+        }
+    };
+
+    debug!(
+        stream,
+        topic,
+        partition_id,
+        scanned,
+        matches = matches.len(),
+        ?stopped,
+        "Search done"
+    );
+    Ok(SearchResponse {
+        count: matches.len(),
+        matches,
+        partition_id,
+        scanned,
+        next_offset: offset,
+        stopped,
+    })
+}
+
+/// JSON pointer of a dotted JSONPath: `$.items[0].sku` -> `/items/0/sku`.
+fn json_path_pointer(path: &str) -> AppResult<String> {
+    let unsupported = || {
+        AppError::BadRequest(format!(
+            "Unsupported JSONPath '{path}': use dotted fields and array indexes, like \
+             $.payload.data.items[0].sku"
+        ))
+    };
+    let rest = path.strip_prefix('$').unwrap_or(path);
+    let rest = rest.strip_prefix('.').unwrap_or(rest);
+    let mut pointer = String::new();
+    for segment in rest.split('.') {
+        let (field, indexes) = match segment.find('[') {
+            Some(bracket) => segment.split_at(bracket),
+            None => (segment, ""),
+        };
+        if (field.is_empty() && indexes.is_empty()) || field.contains(['*', '?', '@', ']']) {
+            return Err(unsupported());
+        }
+        if !field.is_empty() {
+            pointer.push('/');
+            pointer.push_str(&field.replace('~', "~0").replace('/', "~1"));
+        }
+        let mut indexes = indexes;
+        while let Some(index) = indexes.strip_prefix('[') {
+            let (digits, tail) = index.split_once(']').ok_or_else(unsupported)?;
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(unsupported());
+            }
+            pointer.push('/');
+            pointer.push_str(digits);
+            indexes = tail;
+        }
+        if !indexes.is_empty() {
+            return Err(unsupported());
+        }
+    }
+    Ok(pointer)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::EventPayload;
+
+    #[test]
+    fn test_json_path_pointer() {
+        assert_eq!(json_path_pointer("$.event_type").unwrap(), "/event_type");
+        assert_eq!(
+            json_path_pointer("$.payload.data.items[0].sku").unwrap(),
+            "/payload/data/items/0/sku"
+        );
+        assert_eq!(
+            json_path_pointer("payload.data[1][2]").unwrap(),
+            "/payload/data/1/2"
+        );
+        assert_eq!(json_path_pointer("$.a/b").unwrap(), "/a~1b");
+        for path in [
+            "$",
+            "$..data",
+            "$.items[*]",
+            "$.items[?(@.x)]",
+            "$.items[0",
+            "$.a[b]",
+        ] {
+            assert!(json_path_pointer(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_matchers() {
+        let event = Event::new(
+            "order.created",
+            EventPayload::Generic(json!({ "customer": "c-42", "items": [{ "qty": 3 }] })),
+        );
+
+        let matcher = SearchMatcher::new(Some("c-42"), None, None).unwrap();
+        assert!(matcher.matches(&event));
+        let matcher = SearchMatcher::new(Some("c-43"), None, None).unwrap();
+        assert!(!matcher.matches(&event));
+
+        // `equals` is JSON when it parses, a string otherwise
+        let path = |path, equals| SearchMatcher::new(None, Some(path), Some(equals)).unwrap();
+        assert!(path("$.payload.data.items[0].qty", "3").matches(&event));
+        assert!(!path("$.payload.data.items[0].qty", "\"3\"").matches(&event));
+        assert!(path("$.payload.data.customer", "c-42").matches(&event));
+        assert!(!path("$.payload.data.missing", "null").matches(&event));
+
+        assert!(SearchMatcher::new(None, None, None).is_err());
+        assert!(SearchMatcher::new(Some(""), None, None).is_err());
+        assert!(SearchMatcher::new(Some("x"), Some("$.a"), Some("1")).is_err());
+        assert!(SearchMatcher::new(None, Some("$.a"), None).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Traffic shadowing: mirroring sends to a second topic.
+//!
+//! `SHADOW_RULES` lists `source_stream/source_topic:shadow_stream/shadow_topic[:percent]`
+//! rules. After a send to a source topic succeeds, [`ProducerService`]
+//! sends a copy of a sample of its events to the shadow topic, so canary
+//! consumers can read production traffic without any client change:
+//!
+//! ```text
+//! SHADOW_RULES=orders/created:staging/orders-created:10,payments/settled:staging/settled
+//! ```
+//!
+//! # Sampling
+//!
+//! `percent` (1-100, default 100) picks events by their ID, so an event is
+//! mirrored either on every send or never, and a batch mirrors only its
+//! sampled events. Copies keep the event's partition key but not its
+//! partitioning strategy, which may not fit the shadow topic.
+//!
+//! # Delivery
+//!
+//! Copies are sent in the background after the original send returns:
+//! shadowing never delays or fails a send. A failed copy is logged and
+//! counted in `iggy_shadow_sends_total`, and not retried.
+//!
+//! [`ProducerService`]: super::ProducerService
+
+use std::fmt;
+use std::str::FromStr;
+
+use uuid::Uuid;
+
+/// One shadowing rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowRule {
+    /// Stream whose sends are mirrored
+    pub source_stream: String,
+    /// Topic whose sends are mirrored
+    pub source_topic: String,
+    /// Stream receiving the copies
+    pub shadow_stream: String,
+    /// Topic receiving the copies
+    pub shadow_topic: String,
+    /// Share of events mirrored (1-100)
+    pub percent: u8,
+}
+
+impl ShadowRule {
+    /// Check if the rule mirrors sends to `stream`/`topic`.
+    pub fn matches(&self, stream: &str, topic: &str) -> bool {
+        self.source_stream == stream && self.source_topic == topic
+    }
+
+    /// Check if the event with `id` is in the rule's sample.
+    pub fn samples(&self, id: Uuid) -> bool {
+        id.as_u128() % 100 < u128::from(self.percent)
+    }
+}
+
+impl FromStr for ShadowRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':').map(str::trim);
+        let (Some(source), Some(shadow), percent, None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!(
+                "Invalid shadow rule '{s}' (expected stream/topic:stream/topic[:percent])"
+            ));
+        };
+        let (source_stream, source_topic) = stream_topic(source, s)?;
+        let (shadow_stream, shadow_topic) = stream_topic(shadow, s)?;
+        if (source_stream, source_topic) == (shadow_stream, shadow_topic) {
+            return Err(format!("Shadow rule '{s}' mirrors a topic onto itself"));
+        }
+        let percent = match percent {
+            Some(percent) => percent
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| (1..=100).contains(percent))
+                .ok_or_else(|| format!("Shadow rule '{s}' needs a percent from 1 to 100"))?,
+            None => 100,
+        };
+
+        Ok(Self {
+            source_stream: source_stream.to_string(),
+            source_topic: source_topic.to_string(),
+            shadow_stream: shadow_stream.to_string(),
+            shadow_topic: shadow_topic.to_string(),
+            percent,
+        })
+    }
+}
+
+impl fmt::Display for ShadowRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}:{}/{}:{}",
+            self.source_stream,
+            self.source_topic,
+            self.shadow_stream,
+            self.shadow_topic,
+            self.percent
+        )
+    }
+}
+
+/// Split `stream/topic` from rule `rule`.
+fn stream_topic<'a>(target: &'a str, rule: &str) -> Result<(&'a str, &'a str), String> {
+    target
+        .split_once('/')
+        .filter(|(stream, topic)| !stream.is_empty() && !topic.is_empty() && !topic.contains('/'))
+        .ok_or_else(|| format!("Invalid shadow rule '{rule}': '{target}' is not stream/topic"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_parsing() {
+        let rule: ShadowRule = "orders/created:staging/orders-created:10".parse().unwrap();
+        assert!(rule.matches("orders", "created"));
+        assert!(!rule.matches("orders", "orders-created"));
+        assert_eq!(rule.shadow_stream, "staging");
+        assert_eq!(rule.shadow_topic, "orders-created");
+        assert_eq!(rule.percent, 10);
+        assert_eq!(rule.to_string(), "orders/created:staging/orders-created:10");
+
+        let rule: ShadowRule = "orders/created:staging/created".parse().unwrap();
+        assert_eq!(rule.percent, 100);
+
+        for invalid in [
+            "orders/created",
+            "orders:staging/created",
+            "orders/created:staging/created:0",
+            "orders/created:staging/created:101",
+            "orders/created:orders/created",
+            "orders/created:staging/created:10:x",
+        ] {
+            assert!(invalid.parse::<ShadowRule>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_sampling_follows_the_percent() {
+        let rule = |percent| ShadowRule {
+            source_stream: "a".to_string(),
+            source_topic: "b".to_string(),
+            shadow_stream: "c".to_string(),
+            shadow_topic: "d".to_string(),
+            percent,
+        };
+        let ids: Vec<Uuid> = (0..1_000).map(|_| Uuid::new_v4()).collect();
+
+        assert!(ids.iter().all(|&id| rule(100).samples(id)));
+        let sampled = ids.iter().filter(|&&id| rule(10).samples(id)).count();
+        assert!((50..=150).contains(&sampled), "{sampled}");
+
+        // The same event is always in or out
+        let id = ids.first().copied().unwrap();
+        assert_eq!(rule(50).samples(id), rule(50).samples(id));
+    }
+}
@@ -0,0 +1,763 @@
+//! Local write-ahead spool for sends made while Iggy is unreachable.
+//!
+//! With `SPOOL_DIR` set, a send (single or batch) failing because Iggy
+//! cannot be reached (a retryable error: connection lost, circuit open,
+//! timeout, ...) is appended to a log on local disk and answered with
+//! `202 Accepted` and `"spooled": true` instead of an error. A background
+//! task (see `AppState`) drains the spool into Iggy once it is reachable
+//! again.
+//!
+//! # Ordering
+//!
+//! The spool is drained strictly in append order, and while it holds
+//! anything, new sends are appended too instead of overtaking it. Events
+//! sharing a partition key therefore reach Iggy in the order they were
+//! accepted.
+//!
+//! # Format
+//!
+//! The log is a series of segment files `<number>.jsonl` in `SPOOL_DIR`,
+//! one JSON record per line, rolling over at [`SEGMENT_BYTES`]. Appends are
+//! flushed to disk before the response. The drain position (segment and
+//! byte offset) is kept in the `cursor` file, rewritten after every
+//! delivered record; drained segments are deleted. A line torn by a crash
+//! mid-append is cut off on startup.
+//!
+//! # Delivery
+//!
+//! At-least-once: a crash between a delivery and its cursor update, or a
+//! timed-out send that did reach Iggy before being spooled, delivers the
+//! event twice. A record Iggy rejects with a non-retryable error (e.g. its
+//! topic was deleted) is logged and dropped so it cannot block the spool.
+//! Once the spool holds `SPOOL_MAX_BYTES`, further sends fail.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::ProducerService;
+use crate::error::{AppError, AppResult};
+use crate::models::{Event, PartitioningStrategy, SendMessageResponse};
+
+/// Segment files roll over once they reach this size.
+pub const SEGMENT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Records read from the spool per drain pass.
+const DRAIN_BATCH: usize = 100;
+
+/// Wait before draining again after Iggy failed a delivery.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Name of the drain position file.
+const CURSOR_FILE: &str = "cursor";
+
+/// Extension of segment files.
+const SEGMENT_EXTENSION: &str = "jsonl";
+
+/// One spooled send.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpooledSend {
+    /// Target stream
+    pub stream: String,
+    /// Target topic
+    pub topic: String,
+    /// The event to publish
+    pub event: Event,
+    /// Partition key of the send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    /// Partitioning strategy of the send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PartitioningStrategy>,
+}
+
+/// A place in the log: byte `offset` into segment `segment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Position {
+    segment: u64,
+    offset: u64,
+}
+
+/// The files of an open spool. Every method does blocking I/O.
+struct SpoolFiles {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Segment numbers on disk, oldest first
+    segments: VecDeque<u64>,
+    /// Append handle of the last segment, opened on first use
+    writer: Option<File>,
+    /// Size of the last segment
+    tail_bytes: u64,
+    /// Next record to drain
+    cursor: Position,
+    /// Bytes appended and not yet drained (shared with [`Spool`])
+    pending: Arc<AtomicU64>,
+}
+
+impl SpoolFiles {
+    /// Open the spool in `dir`, creating it if needed.
+    fn open(dir: &Path, max_bytes: u64) -> AppResult<Self> {
+        fs::create_dir_all(dir).map_err(|e| io_error("create", dir, &e))?;
+
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| io_error("list", dir, &e))? {
+            let path = entry.map_err(|e| io_error("list", dir, &e))?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION)
+                && let Some(number) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u64>().ok())
+            {
+                segments.push(number);
+            }
+        }
+        segments.sort_unstable();
+
+        let cursor_path = dir.join(CURSOR_FILE);
+        let mut cursor = match fs::read(&cursor_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                AppError::ConfigError(format!(
+                    "Spool cursor {} is corrupt: {e}",
+                    cursor_path.display()
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Position {
+                segment: 0,
+                offset: 0,
+            },
+            Err(e) => return Err(io_error("read", &cursor_path, &e)),
+        };
+
+        // Segments before the cursor were drained; a crash kept them
+        let mut segments: VecDeque<u64> = segments.into();
+        while let Some(&first) = segments.front()
+            && first < cursor.segment
+        {
+            let path = segment_path(dir, first);
+            fs::remove_file(&path).map_err(|e| io_error("remove", &path, &e))?;
+            segments.pop_front();
+        }
+        if let Some(&first) = segments.front()
+            && first > cursor.segment
+        {
+            cursor = Position {
+                segment: first,
+                offset: 0,
+            };
+        }
+
+        let mut tail_bytes = 0;
+        if let Some(&last) = segments.back() {
+            tail_bytes = truncate_torn_line(&segment_path(dir, last))?;
+        }
+        let mut total = 0;
+        for &segment in &segments {
+            total += if segments.back() == Some(&segment) {
+                tail_bytes
+            } else {
+                segment_len(&segment_path(dir, segment))?
+            };
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            segments,
+            writer: None,
+            tail_bytes,
+            cursor,
+            pending: Arc::new(AtomicU64::new(total.saturating_sub(cursor.offset))),
+        })
+    }
+
+    /// Append `records` (complete lines) and flush them to disk.
+    fn append(&mut self, records: &[u8]) -> AppResult<()> {
+        let len = records.len() as u64;
+        if self.pending.load(Ordering::Acquire) + len > self.max_bytes {
+            return Err(AppError::Internal(format!(
+                "Spool is full ({} bytes, SPOOL_MAX_BYTES)",
+                self.max_bytes
+            )));
+        }
+
+        let roll = self.segments.is_empty() || self.tail_bytes + len > SEGMENT_BYTES;
+        if roll && (self.segments.is_empty() || self.tail_bytes > 0) {
+            let next = self
+                .segments
+                .back()
+                .map_or(self.cursor.segment, |last| last + 1);
+            if self.segments.is_empty() {
+                self.cursor = Position {
+                    segment: next,
+                    offset: 0,
+                };
+            }
+            self.segments.push_back(next);
+            self.writer = None;
+            self.tail_bytes = 0;
+        }
+
+        let Some(&last) = self.segments.back() else {
+            return Err(AppError::Internal("Spool has no segment".to_string()));
+        };
+        let path = segment_path(&self.dir, last);
+        let mut writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| io_error("open", &path, &e))?,
+        };
+        if let Err(e) = writer.write_all(records).and_then(|()| writer.sync_data()) {
+            // Drop a partial write so the next append starts on a line
+            let _ = writer.set_len(self.tail_bytes);
+            return Err(io_error("write", &path, &e));
+        }
+        self.writer = Some(writer);
+        self.tail_bytes += len;
+        self.pending.fetch_add(len, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Read up to `max` records from the cursor on, each with the position
+    /// after it, plus the position after the last line read (unreadable
+    /// lines are skipped). Moves on to the next segment, deleting the
+    /// drained one, when the cursor is at a segment's end.
+    fn read_batch(&mut self, max: usize) -> AppResult<(Vec<(SpooledSend, Position)>, Position)> {
+        loop {
+            let path = segment_path(&self.dir, self.cursor.segment);
+            let mut reader = match File::open(&path) {
+                Ok(file) => BufReader::new(file),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok((Vec::new(), self.cursor));
+                }
+                Err(e) => return Err(io_error("open", &path, &e)),
+            };
+            reader
+                .seek(SeekFrom::Start(self.cursor.offset))
+                .map_err(|e| io_error("seek", &path, &e))?;
+
+            let mut records = Vec::new();
+            let mut end = self.cursor;
+            let mut line = Vec::new();
+            while records.len() < max {
+                line.clear();
+                let read = reader
+                    .read_until(b'\n', &mut line)
+                    .map_err(|e| io_error("read", &path, &e))?;
+                if read == 0 || line.last() != Some(&b'\n') {
+                    break;
+                }
+                end.offset += read as u64;
+                match serde_json::from_slice::<SpooledSend>(&line) {
+                    Ok(record) => records.push((record, end)),
+                    Err(e) => warn!(
+                        segment = end.segment,
+                        offset = end.offset - read as u64,
+                        error = %e,
+                        "Skipping unreadable spool record"
+                    ),
+                }
+            }
+
+            let drained = end == self.cursor;
+            let later = self
+                .segments
+                .iter()
+                .find(|&&segment| segment > self.cursor.segment)
+                .copied();
+            match later {
+                Some(next) if drained => {
+                    self.segments.retain(|&segment| segment >= next);
+                    self.cursor = Position {
+                        segment: next,
+                        offset: 0,
+                    };
+                    self.save_cursor()?;
+                    if let Err(e) = fs::remove_file(&path) {
+                        warn!(
+                            path = %path.display(),
+                            error = %e,
+                            "Failed to remove drained spool segment"
+                        );
+                    }
+                }
+                _ => return Ok((records, end)),
+            }
+        }
+    }
+
+    /// Mark everything before `position` as delivered.
+    fn commit(&mut self, position: Position) -> AppResult<()> {
+        if position == self.cursor {
+            return Ok(());
+        }
+        let delivered = position.offset.saturating_sub(self.cursor.offset);
+        self.cursor = position;
+        self.save_cursor()?;
+        self.pending.fetch_sub(delivered, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Replace the cursor file (write and rename, so it is never torn).
+    fn save_cursor(&self) -> AppResult<()> {
+        let path = self.dir.join(CURSOR_FILE);
+        let temp = self.dir.join(format!("{CURSOR_FILE}.tmp"));
+        let bytes = serde_json::to_vec(&self.cursor)?;
+        fs::write(&temp, bytes).map_err(|e| io_error("write", &temp, &e))?;
+        fs::rename(&temp, &path).map_err(|e| io_error("rename", &path, &e))
+    }
+}
+
+/// Durable buffer for sends during Iggy outages, shared by the send
+/// handlers and the drain task. A disabled spool passes every send through.
+pub struct Spool {
+    /// `None` when disabled
+    files: Option<Arc<Mutex<SpoolFiles>>>,
+    /// Bytes waiting to be drained
+    pending: Arc<AtomicU64>,
+    /// Wakes the drain loop when a record is appended.
+    wake: Notify,
+}
+
+impl Spool {
+    /// A spool that never holds anything.
+    pub fn disabled() -> Self {
+        Self {
+            files: None,
+            pending: Arc::new(AtomicU64::new(0)),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Open (or create) the spool in `dir`, holding at most `max_bytes`.
+    /// Records left by a previous run are drained once the drain task runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Internal` if the directory cannot be read or
+    /// written, or `AppError::ConfigError` if its cursor file is corrupt.
+    pub fn open(dir: impl AsRef<Path>, max_bytes: u64) -> AppResult<Self> {
+        let files = SpoolFiles::open(dir.as_ref(), max_bytes)?;
+        let pending = Arc::clone(&files.pending);
+        let spool = Self {
+            files: Some(Arc::new(Mutex::new(files))),
+            pending,
+            wake: Notify::new(),
+        };
+        crate::metrics::set_spool_pending_bytes(spool.pending_bytes());
+        Ok(spool)
+    }
+
+    /// Check if failed sends are spooled.
+    pub fn is_enabled(&self) -> bool {
+        self.files.is_some()
+    }
+
+    /// Bytes waiting to be drained into Iggy.
+    pub fn pending_bytes(&self) -> u64 {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Send one event through `producer`, spooling it if Iggy is
+    /// unreachable or the spool is still draining (see "Ordering").
+    ///
+    /// # Errors
+    ///
+    /// Returns the send's error if it is not retryable, or if it could not
+    /// be spooled (e.g. the spool is full).
+    pub async fn send(
+        &self,
+        producer: &ProducerService,
+        send: SpooledSend,
+    ) -> AppResult<SendMessageResponse> {
+        let failure = if self.is_draining() {
+            None
+        } else {
+            match producer
+                .send_to(
+                    &send.stream,
+                    &send.topic,
+                    &send.event,
+                    send.partition_key.as_deref(),
+                    send.partitioning,
+                )
+                .await
+            {
+                Err(e) if self.is_enabled() && e.is_retryable() => Some(e),
+                result => return result,
+            }
+        };
+        self.spool(producer, vec![send], failure)
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::Internal("Spooled send has no response".to_string()))
+    }
+
+    /// Send a batch through `producer` like [`Self::send`]; a spooled batch
+    /// is kept whole and in order.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::send`].
+    pub async fn send_batch(
+        &self,
+        producer: &ProducerService,
+        stream: &str,
+        topic: &str,
+        events: Vec<Event>,
+        partition_key: Option<String>,
+        partitioning: Option<PartitioningStrategy>,
+    ) -> AppResult<Vec<SendMessageResponse>> {
+        let failure = if self.is_draining() {
+            None
+        } else {
+            match producer
+                .send_batch_to(
+                    stream,
+                    topic,
+                    &events,
+                    partition_key.as_deref(),
+                    partitioning,
+                )
+                .await
+            {
+                Err(e) if self.is_enabled() && e.is_retryable() => Some(e),
+                result => return result,
+            }
+        };
+        let sends = events
+            .into_iter()
+            .map(|event| SpooledSend {
+                stream: stream.to_string(),
+                topic: topic.to_string(),
+                event,
+                partition_key: partition_key.clone(),
+                partitioning,
+            })
+            .collect();
+        self.spool(producer, sends, failure).await
+    }
+
+    /// Deliver spooled records through `producer` until `cancel` is
+    /// cancelled, pausing for [`RETRY_DELAY`] while Iggy is unreachable.
+    pub async fn run(&self, producer: &ProducerService, cancel: CancellationToken) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.pending_bytes() > 0 {
+            info!(
+                pending_bytes = self.pending_bytes(),
+                "Draining sends spooled by a previous run"
+            );
+        }
+
+        loop {
+            if self.pending_bytes() == 0 {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => break,
+                    _ = self.wake.notified() => continue,
+                }
+            }
+
+            let paused = tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+                paused = self.drain_batch(producer) => paused,
+            };
+            crate::metrics::set_spool_pending_bytes(self.pending_bytes());
+            if paused {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(RETRY_DELAY) => {}
+                }
+            }
+        }
+    }
+
+    /// Deliver the next batch of records; `true` if delivery failed and
+    /// should be retried later.
+    async fn drain_batch(&self, producer: &ProducerService) -> bool {
+        let (records, end) = match self.with_files(|files| files.read_batch(DRAIN_BATCH)).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!(error = %e, "Failed to read the spool");
+                return true;
+            }
+        };
+
+        let idle = records.is_empty();
+        for (record, next) in records {
+            let result = producer
+                .send_to(
+                    &record.stream,
+                    &record.topic,
+                    &record.event,
+                    record.partition_key.as_deref(),
+                    record.partitioning,
+                )
+                .await;
+            match result {
+                Ok(_) => crate::metrics::record_spool_events("drained", 1),
+                Err(e) if e.is_retryable() => {
+                    debug!(error = %e, "Iggy still unavailable; spool drain paused");
+                    return true;
+                }
+                Err(e) => {
+                    warn!(
+                        event_id = %record.event.id,
+                        stream = %record.stream,
+                        topic = %record.topic,
+                        error = %e,
+                        "Dropping spooled send rejected by Iggy"
+                    );
+                    crate::metrics::record_spool_events("dropped", 1);
+                }
+            }
+            if let Err(e) = self.commit(next).await {
+                warn!(error = %e, "Failed to save the spool position");
+                return true;
+            }
+        }
+
+        if let Err(e) = self.commit(end).await {
+            warn!(error = %e, "Failed to save the spool position");
+            return true;
+        }
+        // Bytes pending but no record to read: wait rather than spin
+        idle && self.pending_bytes() > 0
+    }
+
+    /// Check if earlier sends are still waiting in the spool.
+    fn is_draining(&self) -> bool {
+        self.is_enabled() && self.pending_bytes() > 0
+    }
+
+    /// Append `sends` (enriched as `producer` would send them now) and
+    /// answer them as spooled. `failure` is the send error that led here,
+    /// returned instead if spooling fails.
+    async fn spool(
+        &self,
+        producer: &ProducerService,
+        sends: Vec<SpooledSend>,
+        failure: Option<AppError>,
+    ) -> AppResult<Vec<SendMessageResponse>> {
+        let count = sends.len();
+        let mut records = Vec::new();
+        let mut responses = Vec::with_capacity(count);
+        let timestamp = Utc::now();
+        for mut send in sends {
+            // Correlation and enrichment come from the request, gone by
+            // the time the record is drained
+            send.event = producer.enrich(&send.event).into_owned();
+            serde_json::to_writer(&mut records, &send)?;
+            records.push(b'\n');
+            responses.push(SendMessageResponse {
+                success: true,
+                event_id: send.event.id,
+                stream: send.stream,
+                topic: send.topic,
+                timestamp,
+                correlation_id: send.event.correlation_id,
+                spooled: true,
+            });
+        }
+
+        match self.with_files(move |files| files.append(&records)).await {
+            Ok(()) => {
+                crate::metrics::record_spool_events("spooled", count as u64);
+                crate::metrics::set_spool_pending_bytes(self.pending_bytes());
+                self.wake.notify_one();
+                match &failure {
+                    Some(e) => warn!(error = %e, count, "Iggy unavailable; spooled send"),
+                    None => debug!(count, "Spool draining; spooled send"),
+                }
+                Ok(responses)
+            }
+            Err(e) => {
+                warn!(error = %e, count, "Failed to spool send");
+                crate::metrics::record_spool_events("rejected", count as u64);
+                Err(failure.unwrap_or_else(|| {
+                    AppError::ConnectionFailed(format!(
+                        "Spool is draining and cannot take more: {e}"
+                    ))
+                }))
+            }
+        }
+    }
+
+    /// Save the drain position.
+    async fn commit(&self, position: Position) -> AppResult<()> {
+        self.with_files(move |files| files.commit(position)).await
+    }
+
+    /// Run `operation` on the files on the blocking thread pool.
+    async fn with_files<T: Send + 'static>(
+        &self,
+        operation: impl FnOnce(&mut SpoolFiles) -> AppResult<T> + Send + 'static,
+    ) -> AppResult<T> {
+        let Some(files) = self.files.clone() else {
+            return Err(AppError::Internal("Spool is disabled".to_string()));
+        };
+        tokio::task::spawn_blocking(move || {
+            operation(&mut files.lock().unwrap_or_else(PoisonError::into_inner))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Spool task failed: {e}")))?
+    }
+}
+
+/// Path of segment `number` in `dir`.
+fn segment_path(dir: &Path, number: u64) -> PathBuf {
+    dir.join(format!("{number:020}.{SEGMENT_EXTENSION}"))
+}
+
+/// Size of the file at `path`.
+fn segment_len(path: &Path) -> AppResult<u64> {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .map_err(|e| io_error("stat", path, &e))
+}
+
+/// Cut an incomplete last line (an append interrupted by a crash) off the
+/// segment at `path`; returns its size afterwards.
+fn truncate_torn_line(path: &Path) -> AppResult<u64> {
+    let bytes = fs::read(path).map_err(|e| io_error("read", path, &e))?;
+    let complete = bytes
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |newline| newline + 1) as u64;
+    if complete < bytes.len() as u64 {
+        warn!(
+            path = %path.display(),
+            bytes = bytes.len() as u64 - complete,
+            "Cutting off a torn spool record"
+        );
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_len(complete))
+            .map_err(|e| io_error("truncate", path, &e))?;
+    }
+    Ok(complete)
+}
+
+fn io_error(action: &str, path: &Path, error: &std::io::Error) -> AppError {
+    AppError::Internal(format!(
+        "Failed to {action} spool file {}: {error}",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::EventPayload;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("iggy-sample-spool-{}", Uuid::new_v4()))
+    }
+
+    fn record(key: &str) -> Vec<u8> {
+        let send = SpooledSend {
+            stream: "orders".to_string(),
+            topic: "created".to_string(),
+            event: Event::new(
+                "order.created",
+                EventPayload::Generic(serde_json::json!({})),
+            ),
+            partition_key: Some(key.to_string()),
+            partitioning: None,
+        };
+        let mut line = serde_json::to_vec(&send).unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    fn keys(records: &[(SpooledSend, Position)]) -> Vec<&str> {
+        records
+            .iter()
+            .filter_map(|(send, _)| send.partition_key.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_records_drain_in_order_across_restarts() {
+        let dir = temp_dir();
+        let mut files = SpoolFiles::open(&dir, 1024 * 1024).unwrap();
+        for key in ["a", "b", "c"] {
+            files.append(&record(key)).unwrap();
+        }
+
+        let (records, _) = files.read_batch(2).unwrap();
+        assert_eq!(keys(&records), ["a", "b"]);
+        files.commit(records[0].1).unwrap();
+        drop(files);
+
+        // "a" was delivered; "b" was read but not delivered
+        let mut files = SpoolFiles::open(&dir, 1024 * 1024).unwrap();
+        let (records, end) = files.read_batch(10).unwrap();
+        assert_eq!(keys(&records), ["b", "c"]);
+        files.commit(end).unwrap();
+        assert_eq!(files.pending.load(Ordering::Acquire), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_record_is_cut_off_on_open() {
+        let dir = temp_dir();
+        let mut files = SpoolFiles::open(&dir, 1024 * 1024).unwrap();
+        files.append(&record("a")).unwrap();
+        let segment = segment_path(&dir, files.cursor.segment);
+        drop(files);
+
+        let partial = record("b");
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(partial.get(..10).unwrap()).unwrap();
+        drop(file);
+
+        let mut files = SpoolFiles::open(&dir, 1024 * 1024).unwrap();
+        files.append(&record("c")).unwrap();
+        let (records, _) = files.read_batch(10).unwrap();
+        assert_eq!(keys(&records), ["a", "c"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_full_spool_rejects_appends() {
+        let dir = temp_dir();
+        let line = record("a");
+        let mut files = SpoolFiles::open(&dir, line.len() as u64 * 2).unwrap();
+        files.append(&line).unwrap();
+        files.append(&line).unwrap();
+        assert!(files.append(&line).is_err());
+
+        // Draining makes room again
+        let (records, _) = files.read_batch(1).unwrap();
+        files.commit(records[0].1).unwrap();
+        assert!(files.append(&line).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
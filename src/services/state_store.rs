@@ -0,0 +1,557 @@
+//! Durable gateway state.
+//!
+//! Recurring schedules, pipelines and (except with the `iggy` backend)
+//! idempotent responses are kept in memory. Every change to them goes to a
+//! [`StateStore`] as the new value
+//! of a key within a namespace, or as a removal; on startup
+//! `AppState::restore_state` loads the latest value of each key back, so
+//! definitions survive restarts and node replacement, and
+//! [`StateStore::get`] looks a single key up. `STATE_BACKEND` selects the
+//! implementation [`open_state_store`] returns:
+//!
+//! - `memory` - [`MemoryStateStore`], nowhere: state is lost on restart
+//!   (default)
+//! - `iggy` - appended to `STATE_TOPIC` (single partition, default stream)
+//!   as [`StateRecord`]s, and replayed from the first record
+//! - `sqlite` - upserted into the `gateway_state` table of the database at
+//!   `STATE_SQLITE_PATH` (`sqlite` feature), where it can also be queried
+//!
+//! # Compaction
+//!
+//! Iggy has no key-based compaction, so the journal topic is compacted on
+//! read: replay keeps the latest record of each key and drops removed
+//! keys. The journal grows with the number of changes, so it only holds
+//! definitions, which change rarely: `AppState` keeps idempotent responses,
+//! which each keyed request would add and later remove, out of it. SQLite
+//! keeps one row per key and holds them too.
+//!
+//! The `iggy` backend answers [`StateStore::get`] from the keys it replayed
+//! and has written since, replaying on the first lookup if needed; SQLite
+//! reads the row.
+//!
+//! # Failure Handling
+//!
+//! Like the audit log, a change is persisted after it is applied and the
+//! store never fails the request: a failed write is logged and the change
+//! is lost on restart. An unreadable record is logged and skipped. The
+//! topic or table is created on first use.
+//!
+//! # Replicas
+//!
+//! Replicas sharing the default stream (or database file) share the state.
+//! Each loads it when it starts; changes made through another running
+//! replica are not picked up until then, except that SQLite lookups read
+//! the shared table.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use iggy::prelude::Partitioning;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::iggy_client::{IggyClientWrapper, PollParams, payload_message};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+/// Namespace of recurring schedule definitions.
+pub const SCHEDULES_NAMESPACE: &str = "schedules";
+
+/// Namespace of pipeline definitions.
+pub const PIPELINES_NAMESPACE: &str = "pipelines";
+
+/// Namespace of responses stored for `Idempotency-Key` replays.
+pub const IDEMPOTENCY_NAMESPACE: &str = "idempotency";
+
+/// Records read per poll while replaying.
+pub const REPLAY_BATCH_SIZE: u32 = 1000;
+
+/// The journal has a single partition; records are replayed in write order.
+const STATE_PARTITION_ID: u32 = 0;
+
+/// Consumer ID used for replay (offset-based; nothing is committed).
+const STATE_CONSUMER_ID: u32 = 1;
+
+/// One change in the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateRecord {
+    /// Subsystem the key belongs to (e.g. `schedules`)
+    pub namespace: String,
+    /// Key within the namespace (e.g. the schedule name)
+    pub key: String,
+    /// New value, or `None` when the key was removed
+    #[serde(default)]
+    pub value: Option<Value>,
+    /// When the change was made
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Latest value of each key, as of the end of the journal.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalState {
+    namespaces: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl JournalState {
+    /// Apply one record on top of the state so far.
+    fn apply(&mut self, record: StateRecord) {
+        let keys = self.namespaces.entry(record.namespace).or_default();
+        match record.value {
+            Some(value) => {
+                keys.insert(record.key, value);
+            }
+            None => {
+                keys.remove(&record.key);
+            }
+        }
+    }
+
+    /// The value of `key` in `namespace`.
+    pub fn get(&self, namespace: &str, key: &str) -> Option<&Value> {
+        self.namespaces.get(namespace)?.get(key)
+    }
+
+    /// Number of live keys across namespaces.
+    pub fn len(&self) -> usize {
+        self.namespaces.values().map(BTreeMap::len).sum()
+    }
+
+    /// `true` when the journal holds no live key.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The values of `namespace` by key, for a migration to rewrite.
+    pub fn namespace_mut(&mut self, namespace: &str) -> &mut BTreeMap<String, Value> {
+        self.namespaces.entry(namespace.to_string()).or_default()
+    }
+
+    /// Keys whose value differs from `earlier`, as `(namespace, key, value)`
+    /// with `None` for a removed key.
+    pub fn changes_since(&self, earlier: &Self) -> Vec<(String, String, Option<Value>)> {
+        let mut changes = Vec::new();
+        for (namespace, keys) in &self.namespaces {
+            let before = earlier.namespaces.get(namespace);
+            for (key, value) in keys {
+                if before.and_then(|before| before.get(key)) != Some(value) {
+                    changes.push((namespace.clone(), key.clone(), Some(value.clone())));
+                }
+            }
+        }
+        for (namespace, keys) in &earlier.namespaces {
+            let after = self.namespaces.get(namespace);
+            for key in keys.keys() {
+                if !after.is_some_and(|after| after.contains_key(key)) {
+                    changes.push((namespace.clone(), key.clone(), None));
+                }
+            }
+        }
+        changes
+    }
+
+    /// Remove and return the values of `namespace`, ordered by key. A value
+    /// that does not deserialize as `T` is logged and skipped.
+    pub fn take<T: DeserializeOwned>(&mut self, namespace: &str) -> Vec<T> {
+        self.namespaces
+            .remove(namespace)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(key, value)| match serde_json::from_value(value) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    warn!(namespace, key = %key, error = %e, "Skipping unreadable state");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where gateway state is persisted (`STATE_BACKEND`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateBackend {
+    /// Nothing is persisted: state lives in memory only (default)
+    #[default]
+    Memory,
+    /// Journaled to `STATE_TOPIC` and replayed on startup
+    Iggy,
+    /// Kept in a table of the SQLite database at `STATE_SQLITE_PATH`
+    /// (`sqlite` feature)
+    Sqlite,
+}
+
+impl FromStr for StateBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "memory" => Ok(Self::Memory),
+            "iggy" => Ok(Self::Iggy),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(format!(
+                "Unknown state backend '{s}' (expected memory, iggy or sqlite)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for StateBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Memory => "memory",
+            Self::Iggy => "iggy",
+            Self::Sqlite => "sqlite",
+        })
+    }
+}
+
+/// Future returned by [`StateStore`] operations.
+pub type StateFuture<'a, T> = BoxFuture<'a, AppResult<T>>;
+
+/// Persists changes to gateway state and loads them back.
+///
+/// Implemented by [`MemoryStateStore`] and the `iggy` and `sqlite`
+/// backends; [`open_state_store`] picks one from `STATE_BACKEND`. Callers
+/// usually go through the `put`, `delete` and `write` helpers on
+/// `dyn StateStore`.
+pub trait StateStore: Send + Sync {
+    /// Check if changes are persisted (`false` for `memory`).
+    fn is_enabled(&self) -> bool;
+
+    /// Persist one change: the record's value, or the removal of its key.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error writing to the backend.
+    fn append<'a>(&'a self, record: &'a StateRecord) -> StateFuture<'a, ()>;
+
+    /// Load the latest value of each key.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error reading the backend.
+    fn replay(&self) -> StateFuture<'_, JournalState>;
+
+    /// The latest value of `key` in `namespace`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error reading the backend.
+    fn get<'a>(&'a self, namespace: &'a str, key: &'a str) -> StateFuture<'a, Option<Value>>;
+}
+
+impl dyn StateStore + '_ {
+    /// Persist `value` as the new value of `key` in `namespace`. Never
+    /// fails: a write error is logged.
+    pub async fn put<T: Serialize>(&self, namespace: &str, key: &str, value: &T) {
+        if !self.is_enabled() {
+            return;
+        }
+        match serde_json::to_value(value) {
+            Ok(value) => self.record(namespace, key, Some(value)).await,
+            Err(e) => warn!(namespace, key, error = %e, "Failed to serialize state"),
+        }
+    }
+
+    /// Persist the removal of `key` from `namespace`. Never fails: a write
+    /// error is logged.
+    pub async fn delete(&self, namespace: &str, key: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.record(namespace, key, None).await;
+    }
+
+    /// Persist `value` (`None` = removal) as the value of `key` in
+    /// `namespace`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error writing to the backend.
+    pub async fn write(&self, namespace: &str, key: &str, value: Option<Value>) -> AppResult<()> {
+        let record = StateRecord {
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            value,
+            timestamp: Utc::now(),
+        };
+        self.append(&record).await
+    }
+
+    /// Write a record, logging a failure.
+    async fn record(&self, namespace: &str, key: &str, value: Option<Value>) {
+        if let Err(e) = self.write(namespace, key, value).await {
+            warn!(namespace, key, error = %e, "Failed to persist state change");
+        }
+    }
+}
+
+/// Create the store selected by `STATE_BACKEND`, journaling to
+/// `STATE_TOPIC` of the default stream through `client` for `iggy`.
+/// SQLite databases are opened on first use.
+pub fn open_state_store(client: IggyClientWrapper, config: &Config) -> Arc<dyn StateStore> {
+    match config.state_backend {
+        StateBackend::Memory => Arc::new(MemoryStateStore),
+        StateBackend::Iggy => Arc::new(IggyJournal::new(
+            client,
+            &config.default_stream,
+            &config.state_topic,
+        )),
+        #[cfg(feature = "sqlite")]
+        StateBackend::Sqlite => Arc::new(sqlite::SqliteState::new(&config.state_sqlite_path)),
+        // Rejected by `Config::validate`
+        #[cfg(not(feature = "sqlite"))]
+        StateBackend::Sqlite => {
+            warn!("STATE_BACKEND=sqlite needs the `sqlite` feature; state is not persisted");
+            Arc::new(MemoryStateStore)
+        }
+    }
+}
+
+/// The `memory` backend: nothing is persisted and nothing loads back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStateStore;
+
+impl StateStore for MemoryStateStore {
+    fn is_enabled(&self) -> bool {
+        false
+    }
+
+    fn append<'a>(&'a self, _record: &'a StateRecord) -> StateFuture<'a, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn replay(&self) -> StateFuture<'_, JournalState> {
+        Box::pin(async { Ok(JournalState::default()) })
+    }
+
+    fn get<'a>(&'a self, _namespace: &'a str, _key: &'a str) -> StateFuture<'a, Option<Value>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+/// The `iggy` backend: a journal topic, compacted on read.
+struct IggyJournal {
+    client: IggyClientWrapper,
+    stream: String,
+    topic: String,
+    /// Set once the journal stream and topic are known to exist
+    ready: OnceCell<()>,
+    /// Latest value of each key, once replayed, kept current by appends
+    index: Mutex<Option<JournalState>>,
+}
+
+impl IggyJournal {
+    fn new(client: IggyClientWrapper, stream: &str, topic: &str) -> Self {
+        Self {
+            client,
+            stream: stream.to_string(),
+            topic: topic.to_string(),
+            ready: OnceCell::new(),
+            index: Mutex::new(None),
+        }
+    }
+
+    fn index(&self) -> MutexGuard<'_, Option<JournalState>> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Read the whole journal and return the latest value of each key.
+    async fn read_journal(&self) -> AppResult<JournalState> {
+        let mut state = JournalState::default();
+        self.ensure_topic().await?;
+
+        let mut offset = 0;
+        loop {
+            let params = PollParams::new(STATE_PARTITION_ID, STATE_CONSUMER_ID)
+                .with_offset(offset)
+                .with_count(REPLAY_BATCH_SIZE);
+            let polled = self
+                .client
+                .poll_messages(&self.stream, &self.topic, params)
+                .await?;
+            let Some(last) = polled.messages.last() else {
+                break;
+            };
+            offset = last.header.offset + 1;
+            for message in &polled.messages {
+                match serde_json::from_slice::<StateRecord>(&message.payload) {
+                    Ok(record) => state.apply(record),
+                    Err(e) => warn!(
+                        offset = message.header.offset,
+                        error = %e,
+                        "Skipping unreadable state record"
+                    ),
+                }
+            }
+        }
+        debug!(
+            records = offset,
+            keys = state.len(),
+            "Replayed state journal"
+        );
+        *self.index() = Some(state.clone());
+        Ok(state)
+    }
+
+    /// Append a record to the journal topic.
+    async fn write_record(&self, record: &StateRecord) -> AppResult<()> {
+        self.ensure_topic().await?;
+        let message = payload_message(Bytes::from(serde_json::to_vec(record)?))?;
+        self.client
+            .send_raw_messages(
+                &self.stream,
+                &self.topic,
+                &[message],
+                &Partitioning::partition_id(STATE_PARTITION_ID),
+            )
+            .await?;
+        if let Some(index) = self.index().as_mut() {
+            index.apply(record.clone());
+        }
+        Ok(())
+    }
+
+    /// Look a key up in the index, replaying the journal first if needed.
+    async fn lookup(&self, namespace: &str, key: &str) -> AppResult<Option<Value>> {
+        if self.index().is_none() {
+            self.read_journal().await?;
+        }
+        Ok(self
+            .index()
+            .as_ref()
+            .and_then(|index| index.get(namespace, key).cloned()))
+    }
+
+    /// Create the journal stream and topic if needed, once per instance.
+    async fn ensure_topic(&self) -> AppResult<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                self.client.ensure_stream(&self.stream).await?;
+                self.client.ensure_topic(&self.stream, &self.topic, 1).await
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl StateStore for IggyJournal {
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn append<'a>(&'a self, record: &'a StateRecord) -> StateFuture<'a, ()> {
+        Box::pin(self.write_record(record))
+    }
+
+    fn replay(&self) -> StateFuture<'_, JournalState> {
+        Box::pin(self.read_journal())
+    }
+
+    fn get<'a>(&'a self, namespace: &'a str, key: &'a str) -> StateFuture<'a, Option<Value>> {
+        Box::pin(self.lookup(namespace, key))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::iggy_client::BrokerBackend;
+
+    /// Write a few changes through `store`, then check what `restarted`
+    /// (a new instance on the same backend) loads back.
+    async fn check_latest_value_per_key(
+        store: Arc<dyn StateStore>,
+        restarted: Arc<dyn StateStore>,
+    ) {
+        store.put(SCHEDULES_NAMESPACE, "a", &1).await;
+        store.put(SCHEDULES_NAMESPACE, "b", &2).await;
+        store.put(SCHEDULES_NAMESPACE, "a", &3).await;
+        store.delete(SCHEDULES_NAMESPACE, "b").await;
+        store.put(PIPELINES_NAMESPACE, "b", &"not a number").await;
+        assert_eq!(
+            store.get(SCHEDULES_NAMESPACE, "a").await.unwrap(),
+            Some(Value::from(3))
+        );
+        assert_eq!(restarted.get(SCHEDULES_NAMESPACE, "b").await.unwrap(), None);
+        assert_eq!(
+            restarted.get(PIPELINES_NAMESPACE, "b").await.unwrap(),
+            Some(Value::from("not a number"))
+        );
+
+        let mut state = restarted.replay().await.unwrap();
+        assert_eq!(state.len(), 2);
+        assert_eq!(state.take::<u32>(SCHEDULES_NAMESPACE), vec![3]);
+        assert!(state.take::<u32>(PIPELINES_NAMESPACE).is_empty());
+        assert!(state.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_the_latest_value_of_each_key() {
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            state_backend: StateBackend::Iggy,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config.clone()).await.unwrap();
+        check_latest_value_per_key(
+            open_state_store(client.clone(), &config),
+            open_state_store(client, &config),
+        )
+        .await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_keeps_the_latest_value_of_each_key() {
+        let path = std::env::temp_dir().join(format!("state-{}.db", uuid::Uuid::new_v4()));
+        let config = Config {
+            broker_backend: BrokerBackend::Memory,
+            state_backend: StateBackend::Sqlite,
+            state_sqlite_path: path.display().to_string(),
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config.clone()).await.unwrap();
+        check_latest_value_per_key(
+            open_state_store(client.clone(), &config),
+            open_state_store(client, &config),
+        )
+        .await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_nothing() {
+        let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore);
+        store.put(SCHEDULES_NAMESPACE, "a", &1).await;
+        assert!(!store.is_enabled());
+        assert_eq!(store.get(SCHEDULES_NAMESPACE, "a").await.unwrap(), None);
+        assert!(store.replay().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_state_backend_names() {
+        for backend in [
+            StateBackend::Memory,
+            StateBackend::Iggy,
+            StateBackend::Sqlite,
+        ] {
+            assert_eq!(
+                backend.to_string().parse::<StateBackend>().unwrap(),
+                backend
+            );
+        }
+        assert!("postgres".parse::<StateBackend>().is_err());
+    }
+}
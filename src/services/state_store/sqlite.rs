@@ -0,0 +1,155 @@
+//! The `sqlite` state backend: one row per key in a SQLite table.
+//!
+//! ```sql
+//! CREATE TABLE gateway_state (
+//!     namespace  TEXT NOT NULL,
+//!     key        TEXT NOT NULL,
+//!     value      TEXT NOT NULL,  -- JSON
+//!     updated_at TEXT NOT NULL,  -- RFC 3339
+//!     PRIMARY KEY (namespace, key)
+//! );
+//! ```
+//!
+//! The database is opened (and created if missing) on first use, through a
+//! single connection, so writes are applied in order.
+
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+use super::{JournalState, StateFuture, StateRecord, StateStore};
+use crate::error::{AppError, AppResult};
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS gateway_state (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (namespace, key)
+)";
+
+const UPSERT: &str = "INSERT INTO gateway_state (namespace, key, value, updated_at)
+    VALUES (?, ?, ?, ?)
+    ON CONFLICT (namespace, key) DO UPDATE
+    SET value = excluded.value, updated_at = excluded.updated_at";
+
+const DELETE: &str = "DELETE FROM gateway_state WHERE namespace = ? AND key = ?";
+
+const SELECT_ALL: &str = "SELECT namespace, key, value FROM gateway_state";
+
+const SELECT_ONE: &str = "SELECT value FROM gateway_state WHERE namespace = ? AND key = ?";
+
+/// Gateway state in a SQLite database.
+pub(super) struct SqliteState {
+    pool: SqlitePool,
+    /// Set once the table is known to exist
+    ready: OnceCell<()>,
+}
+
+impl SqliteState {
+    /// Use the database at `path`, created on first use if missing.
+    pub(super) fn new(path: &str) -> Self {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Self {
+            pool: SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_lazy_with(options),
+            ready: OnceCell::new(),
+        }
+    }
+
+    /// Upsert the record's value, or delete its key for a removal.
+    async fn write(&self, record: &StateRecord) -> AppResult<()> {
+        self.ensure_table().await?;
+        let query = match &record.value {
+            Some(value) => sqlx::query(UPSERT)
+                .bind(&record.namespace)
+                .bind(&record.key)
+                .bind(value.to_string())
+                .bind(record.timestamp.to_rfc3339()),
+            None => sqlx::query(DELETE)
+                .bind(&record.namespace)
+                .bind(&record.key),
+        };
+        query.execute(&self.pool).await.map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    /// Every stored key with its value.
+    async fn load(&self) -> AppResult<JournalState> {
+        self.ensure_table().await?;
+        let rows: Vec<(String, String, String)> = sqlx::query_as(SELECT_ALL)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(sqlite_error)?;
+
+        let mut state = JournalState::default();
+        for (namespace, key, value) in rows {
+            match serde_json::from_str::<Value>(&value) {
+                Ok(value) => {
+                    state
+                        .namespaces
+                        .entry(namespace)
+                        .or_default()
+                        .insert(key, value);
+                }
+                Err(e) => warn!(namespace, key, error = %e, "Skipping unreadable state row"),
+            }
+        }
+        debug!(keys = state.len(), "Loaded state from SQLite");
+        Ok(state)
+    }
+
+    /// The stored value of one key.
+    async fn select(&self, namespace: &str, key: &str) -> AppResult<Option<Value>> {
+        self.ensure_table().await?;
+        let row: Option<(String,)> = sqlx::query_as(SELECT_ONE)
+            .bind(namespace)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(sqlite_error)?;
+        row.map(|(value,)| serde_json::from_str(&value))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Create the table if needed, once per instance.
+    async fn ensure_table(&self) -> AppResult<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                sqlx::query(CREATE_TABLE)
+                    .execute(&self.pool)
+                    .await
+                    .map(|_| ())
+                    .map_err(sqlite_error)
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl StateStore for SqliteState {
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn append<'a>(&'a self, record: &'a StateRecord) -> StateFuture<'a, ()> {
+        Box::pin(self.write(record))
+    }
+
+    fn replay(&self) -> StateFuture<'_, JournalState> {
+        Box::pin(self.load())
+    }
+
+    fn get<'a>(&'a self, namespace: &'a str, key: &'a str) -> StateFuture<'a, Option<Value>> {
+        Box::pin(self.select(namespace, key))
+    }
+}
+
+fn sqlite_error(e: sqlx::Error) -> AppError {
+    AppError::Internal(format!("SQLite state store: {e}"))
+}
@@ -0,0 +1,200 @@
+//! Storage usage alarms.
+//!
+//! `MAX_TOTAL_SIZE_BYTES` bounds the bytes stored across all streams and
+//! `MAX_TOPIC_SIZE_BYTES` those of any one topic. Usage comes from the
+//! stats cache: each refresh re-evaluates the thresholds, logging a WARN
+//! when one is first exceeded and an INFO once it clears, so the alarm
+//! costs no extra Iggy calls and trails the server by up to
+//! `STATS_CACHE_TTL_SECS`.
+//!
+//! The current state is reported as `storage_pressure` by `GET /health`.
+//! With `STORAGE_REJECT_PRODUCES`, sends are refused with
+//! `507 Insufficient Storage` while the total is exceeded, or while the
+//! target topic is over its limit; polls and deletes keep working, so
+//! consumers and retention can bring usage back down.
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use tracing::{info, warn};
+
+use crate::error::{AppError, AppResult};
+use crate::models::StoragePressure;
+
+/// Thresholds on stored bytes, and their last evaluation.
+#[derive(Debug)]
+pub struct StorageAlarm {
+    /// Bytes across all streams (0 = no limit)
+    max_total_size_bytes: u64,
+    /// Bytes of one topic (0 = no limit)
+    max_topic_size_bytes: u64,
+    reject_produces: bool,
+    pressure: Mutex<StoragePressure>,
+}
+
+impl StorageAlarm {
+    /// Create an alarm on `max_total_size_bytes` and `max_topic_size_bytes`
+    /// (0 disables either), refusing produces while exceeded if
+    /// `reject_produces`.
+    pub fn new(
+        max_total_size_bytes: u64,
+        max_topic_size_bytes: u64,
+        reject_produces: bool,
+    ) -> Self {
+        let nonzero = |limit: u64| (limit > 0).then_some(limit);
+        Self {
+            max_total_size_bytes,
+            max_topic_size_bytes,
+            reject_produces,
+            pressure: Mutex::new(StoragePressure {
+                rejecting_produces: reject_produces,
+                max_total_size_bytes: nonzero(max_total_size_bytes),
+                max_topic_size_bytes: nonzero(max_topic_size_bytes),
+                ..StoragePressure::default()
+            }),
+        }
+    }
+
+    /// Check if either threshold is set.
+    pub fn is_enabled(&self) -> bool {
+        self.max_total_size_bytes > 0 || self.max_topic_size_bytes > 0
+    }
+
+    /// Re-evaluate the thresholds against `total_size_bytes` and the size
+    /// of each `(stream, topic, size_bytes)`, logging the thresholds that
+    /// started or stopped being exceeded.
+    pub fn evaluate<'a>(
+        &self,
+        total_size_bytes: u64,
+        topics: impl IntoIterator<Item = (&'a str, &'a str, u64)>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let total_exceeded =
+            self.max_total_size_bytes > 0 && total_size_bytes > self.max_total_size_bytes;
+        let over_limit: BTreeSet<String> = topics
+            .into_iter()
+            .filter(|&(_, _, size)| {
+                self.max_topic_size_bytes > 0 && size > self.max_topic_size_bytes
+            })
+            .map(|(stream, topic, _)| format!("{stream}/{topic}"))
+            .collect();
+
+        let mut pressure = self.lock();
+        let was_total_exceeded = pressure
+            .max_total_size_bytes
+            .is_some_and(|max| pressure.total_size_bytes > max);
+        if total_exceeded && !was_total_exceeded {
+            warn!(
+                total_size_bytes,
+                max_total_size_bytes = self.max_total_size_bytes,
+                reject_produces = self.reject_produces,
+                "Total storage above MAX_TOTAL_SIZE_BYTES"
+            );
+        } else if !total_exceeded && was_total_exceeded {
+            info!(
+                total_size_bytes,
+                "Total storage back under MAX_TOTAL_SIZE_BYTES"
+            );
+        }
+        let previous: BTreeSet<&String> = pressure.topics_over_limit.iter().collect();
+        for topic in over_limit.iter().filter(|topic| !previous.contains(topic)) {
+            warn!(
+                topic = %topic,
+                max_topic_size_bytes = self.max_topic_size_bytes,
+                reject_produces = self.reject_produces,
+                "Topic storage above MAX_TOPIC_SIZE_BYTES"
+            );
+        }
+        for topic in previous
+            .iter()
+            .filter(|topic| !over_limit.contains(**topic))
+        {
+            info!(topic = %topic, "Topic storage back under MAX_TOPIC_SIZE_BYTES");
+        }
+
+        pressure.total_size_bytes = total_size_bytes;
+        pressure.exceeded = total_exceeded || !over_limit.is_empty();
+        pressure.topics_over_limit = over_limit.into_iter().collect();
+    }
+
+    /// Storage use as of the last evaluation.
+    pub fn pressure(&self) -> StoragePressure {
+        self.lock().clone()
+    }
+
+    /// Check that a produce to `stream`/`topic` may go ahead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InsufficientStorage` with `STORAGE_REJECT_PRODUCES`
+    /// while the total is exceeded or the topic is over its limit.
+    pub fn check_produce(&self, stream: &str, topic: &str) -> AppResult<()> {
+        if !self.reject_produces {
+            return Ok(());
+        }
+        let pressure = self.lock();
+        let total_exceeded = pressure
+            .max_total_size_bytes
+            .is_some_and(|max| pressure.total_size_bytes > max);
+        if total_exceeded {
+            return Err(AppError::InsufficientStorage(
+                "Storage limit exceeded; produces are refused until usage drops".to_string(),
+            ));
+        }
+        let name = format!("{stream}/{topic}");
+        if pressure.topics_over_limit.contains(&name) {
+            return Err(AppError::InsufficientStorage(format!(
+                "Topic '{name}' is over its storage limit; produces are refused until usage drops"
+            )));
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StoragePressure> {
+        self.pressure.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds_are_evaluated_per_total_and_topic() {
+        let alarm = StorageAlarm::new(1000, 300, false);
+        alarm.evaluate(900, [("s", "a", 400), ("s", "b", 200)]);
+        let pressure = alarm.pressure();
+        assert!(pressure.exceeded);
+        assert_eq!(pressure.topics_over_limit, ["s/a"]);
+        // Reported but not enforced
+        assert!(alarm.check_produce("s", "a").is_ok());
+
+        alarm.evaluate(1200, [("s", "a", 200)]);
+        let pressure = alarm.pressure();
+        assert!(pressure.exceeded);
+        assert!(pressure.topics_over_limit.is_empty());
+
+        alarm.evaluate(800, []);
+        assert!(!alarm.pressure().exceeded);
+    }
+
+    #[test]
+    fn test_produces_are_refused_while_exceeded() {
+        let alarm = StorageAlarm::new(0, 300, true);
+        alarm.evaluate(900, [("s", "a", 400), ("s", "b", 200)]);
+        assert!(matches!(
+            alarm.check_produce("s", "a"),
+            Err(AppError::InsufficientStorage(_))
+        ));
+        assert!(alarm.check_produce("s", "b").is_ok());
+
+        let alarm = StorageAlarm::new(1000, 0, true);
+        alarm.evaluate(1001, []);
+        assert!(alarm.check_produce("s", "b").is_err());
+        alarm.evaluate(999, []);
+        assert!(alarm.check_produce("s", "b").is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+//! Rolling report of the clients sending the largest request bodies.
+//!
+//! Every request with a body adds its size to its client's tally (client
+//! IP, resolved like rate limiting does; the service has a single API key,
+//! so the IP is what tells producers apart). `GET /admin/top-talkers` lists
+//! the `TOP_TALKERS_LIMIT` clients with the most bytes, to find who is
+//! pushing oversized events.
+//!
+//! # Window
+//!
+//! Tallies are kept in two buckets of `TOP_TALKERS_WINDOW_SECS`: the
+//! current one and the one before it. The report merges both, so it always
+//! covers between one and two windows of traffic and a client that went
+//! quiet drops out after two.
+//!
+//! The report is in-memory and per-instance. At most
+//! [`MAX_TRACKED_CLIENTS`] clients are tallied per window; requests from
+//! further clients are not counted until the window rolls over.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{TopTalker, TopTalkersResponse};
+
+/// Most clients tallied in one window, bounding memory under many IPs.
+pub const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Tally of one client in one window.
+#[derive(Debug, Clone, Copy)]
+struct Tally {
+    requests: u64,
+    total_bytes: u64,
+    largest_bytes: u64,
+    last_seen: DateTime<Utc>,
+}
+
+impl Tally {
+    /// Add `other` (a later or earlier tally of the same client).
+    fn merge(&mut self, other: &Tally) {
+        self.requests += other.requests;
+        self.total_bytes += other.total_bytes;
+        self.largest_bytes = self.largest_bytes.max(other.largest_bytes);
+        self.last_seen = self.last_seen.max(other.last_seen);
+    }
+}
+
+/// The current and previous window's tallies.
+#[derive(Debug)]
+struct Buckets {
+    current_started: Instant,
+    current: HashMap<String, Tally>,
+    previous: HashMap<String, Tally>,
+}
+
+impl Buckets {
+    /// Start a new window if the current one is over.
+    fn roll(&mut self, window: Duration, now: Instant) {
+        let elapsed = now.duration_since(self.current_started);
+        if elapsed < window {
+            return;
+        }
+        self.previous = if elapsed < window * 2 {
+            std::mem::take(&mut self.current)
+        } else {
+            // Nothing was recorded in the window before this one
+            self.current.clear();
+            HashMap::new()
+        };
+        self.current_started = now;
+    }
+}
+
+/// Thread-safe top-talkers report, shared by the recording middleware and
+/// the admin handler.
+#[derive(Debug)]
+pub struct TopTalkers {
+    limit: usize,
+    window: Duration,
+    buckets: Mutex<Buckets>,
+}
+
+impl TopTalkers {
+    /// Create a report of the `limit` largest clients over rolling
+    /// windows of `window`; a limit of 0 records nothing.
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            buckets: Mutex::new(Buckets {
+                current_started: Instant::now(),
+                current: HashMap::new(),
+                previous: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Check if request sizes are recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Record a request body of `bytes` from `client_ip`. Empty bodies are
+    /// not counted.
+    pub fn record(&self, client_ip: &str, bytes: u64) {
+        if !self.is_enabled() || bytes == 0 {
+            return;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        buckets.roll(self.window, Instant::now());
+
+        if buckets.current.len() >= MAX_TRACKED_CLIENTS && !buckets.current.contains_key(client_ip)
+        {
+            return;
+        }
+        let now = Utc::now();
+        let tally = buckets
+            .current
+            .entry(client_ip.to_string())
+            .or_insert(Tally {
+                requests: 0,
+                total_bytes: 0,
+                largest_bytes: 0,
+                last_seen: now,
+            });
+        tally.merge(&Tally {
+            requests: 1,
+            total_bytes: bytes,
+            largest_bytes: bytes,
+            last_seen: now,
+        });
+    }
+
+    /// The clients with the most bytes over the current and previous
+    /// window, largest first.
+    pub fn report(&self) -> TopTalkersResponse {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        buckets.roll(self.window, Instant::now());
+
+        let mut merged = buckets.previous.clone();
+        for (client_ip, tally) in &buckets.current {
+            merged
+                .entry(client_ip.clone())
+                .and_modify(|merged| merged.merge(tally))
+                .or_insert(*tally);
+        }
+        drop(buckets);
+
+        let mut talkers: Vec<TopTalker> = merged
+            .into_iter()
+            .map(|(client_ip, tally)| TopTalker {
+                client_ip,
+                requests: tally.requests,
+                total_bytes: tally.total_bytes,
+                largest_bytes: tally.largest_bytes,
+                last_seen: tally.last_seen,
+            })
+            .collect();
+        talkers.sort_by(|a, b| {
+            b.total_bytes
+                .cmp(&a.total_bytes)
+                .then_with(|| a.client_ip.cmp(&b.client_ip))
+        });
+        talkers.truncate(self.limit);
+
+        TopTalkersResponse {
+            window_secs: self.window.as_secs(),
+            talkers,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_ranks_clients_by_total_bytes() {
+        let talkers = TopTalkers::new(2, Duration::from_secs(60));
+        talkers.record("10.0.0.1", 100);
+        talkers.record("10.0.0.1", 400);
+        talkers.record("10.0.0.2", 1_000);
+        talkers.record("10.0.0.3", 50);
+        talkers.record("10.0.0.3", 0);
+
+        let report = talkers.report();
+        assert_eq!(report.window_secs, 60);
+        assert_eq!(report.talkers.len(), 2);
+        assert_eq!(report.talkers[0].client_ip, "10.0.0.2");
+        assert_eq!(
+            (
+                report.talkers[1].requests,
+                report.talkers[1].total_bytes,
+                report.talkers[1].largest_bytes
+            ),
+            (2, 500, 400)
+        );
+    }
+
+    #[test]
+    fn test_windows_roll_over() {
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let tally = |bytes| Tally {
+            requests: 1,
+            total_bytes: bytes,
+            largest_bytes: bytes,
+            last_seen: Utc::now(),
+        };
+        let mut buckets = Buckets {
+            current_started: start,
+            current: HashMap::from([("a".to_string(), tally(10))]),
+            previous: HashMap::new(),
+        };
+
+        buckets.roll(window, start + Duration::from_secs(30));
+        assert!(buckets.current.contains_key("a"));
+
+        // One window later the tallies move to the previous bucket
+        buckets.roll(window, start + Duration::from_secs(61));
+        assert!(buckets.current.is_empty());
+        assert!(buckets.previous.contains_key("a"));
+
+        // Two idle windows later nothing is left
+        buckets.current.insert("b".to_string(), tally(20));
+        buckets.roll(window, start + Duration::from_secs(300));
+        assert!(buckets.current.is_empty());
+        assert!(buckets.previous.is_empty());
+    }
+
+    #[test]
+    fn test_disabled_records_nothing() {
+        let talkers = TopTalkers::new(0, Duration::from_secs(60));
+        talkers.record("10.0.0.1", 100);
+        assert!(talkers.report().talkers.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+//! Live tap on the messages sent through the producer.
+//!
+//! Every successful send is offered to the tap; `GET /admin/tap` streams a
+//! filtered, sampled copy to whoever is listening (as server-sent events),
+//! to look at real payloads in production without consuming from the topic
+//! or touching any committed offset.
+//!
+//! Nothing is copied while nobody listens. Subscribers read from a shared
+//! ring of [`TAP_CHANNEL_CAPACITY`] messages; one falling further behind
+//! skips the messages it missed and is told how many. Subscriptions end
+//! when the server shuts down.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use futures_util::Stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::models::{Event, TappedMessage};
+
+/// Messages buffered for slow subscribers.
+pub const TAP_CHANNEL_CAPACITY: usize = 1024;
+
+/// Which tapped messages a subscriber wants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapFilter {
+    /// Only this stream (None = any)
+    pub stream: Option<String>,
+    /// Only this topic (None = any)
+    pub topic: Option<String>,
+    /// Share of the matching messages kept, in (0, 1]
+    pub sample: f64,
+}
+
+impl TapFilter {
+    /// Check if `message` goes to the subscriber; sampled at random.
+    fn admits(&self, message: &TappedMessage) -> bool {
+        self.stream.as_ref().is_none_or(|s| *s == message.stream)
+            && self.topic.as_ref().is_none_or(|t| *t == message.topic)
+            && (self.sample >= 1.0 || rand::random::<f64>() < self.sample)
+    }
+}
+
+/// Something a subscriber receives.
+#[derive(Debug, Clone)]
+pub enum TapItem {
+    /// A sent message
+    Message(Arc<TappedMessage>),
+    /// This many messages were skipped because the subscriber fell behind
+    Lagged(u64),
+}
+
+/// Fan-out of sent messages to tap subscribers.
+pub struct MessageTap {
+    sender: broadcast::Sender<Arc<TappedMessage>>,
+    /// Ends every subscription (server shutdown)
+    closed: CancellationToken,
+}
+
+impl Default for MessageTap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageTap {
+    /// Create a tap with no subscribers.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(TAP_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            closed: CancellationToken::new(),
+        }
+    }
+
+    /// Number of open subscriptions.
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Offer `events`, just sent to `stream`/`topic`, to the subscribers.
+    pub fn publish(
+        &self,
+        stream: &str,
+        topic: &str,
+        events: &[Event],
+        partition_key: Option<&str>,
+    ) {
+        if self.subscribers() == 0 {
+            return;
+        }
+        let sent_at = Utc::now();
+        for event in events {
+            // Fails only when the last subscriber left meanwhile
+            let _ = self.sender.send(Arc::new(TappedMessage {
+                stream: stream.to_string(),
+                topic: topic.to_string(),
+                partition_key: partition_key.map(str::to_string),
+                event: event.clone(),
+                sent_at,
+            }));
+        }
+    }
+
+    /// Stream the messages admitted by `filter` from now on, until the tap
+    /// is closed or the stream is dropped.
+    pub fn subscribe(&self, filter: TapFilter) -> impl Stream<Item = TapItem> + Send + use<> {
+        let receiver = self.sender.subscribe();
+        let closed = self.closed.clone();
+        futures_util::stream::unfold(receiver, move |mut receiver| {
+            let filter = filter.clone();
+            let closed = closed.clone();
+            async move {
+                loop {
+                    let received = tokio::select! {
+                        biased;
+
+                        _ = closed.cancelled() => return None,
+                        received = receiver.recv() => received,
+                    };
+                    match received {
+                        Ok(message) if filter.admits(&message) => {
+                            return Some((TapItem::Message(message), receiver));
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            return Some((TapItem::Lagged(skipped), receiver));
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+    }
+
+    /// End every subscription, e.g. so graceful shutdown does not wait on
+    /// open taps.
+    pub fn close(&self) {
+        self.closed.cancel();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::models::EventPayload;
+
+    fn event() -> Event {
+        Event::new(
+            "order.created",
+            EventPayload::Generic(serde_json::json!({})),
+        )
+    }
+
+    fn filter(topic: Option<&str>) -> TapFilter {
+        TapFilter {
+            stream: None,
+            topic: topic.map(str::to_string),
+            sample: 1.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_sees_matching_messages() {
+        let tap = MessageTap::new();
+        let stream = tap.subscribe(filter(Some("created")));
+        let mut stream = std::pin::pin!(stream);
+        assert_eq!(tap.subscribers(), 1);
+
+        tap.publish("orders", "cancelled", &[event()], None);
+        let sent = event();
+        tap.publish("orders", "created", std::slice::from_ref(&sent), Some("k"));
+
+        match stream.next().await {
+            Some(TapItem::Message(message)) => {
+                assert_eq!(message.event.id, sent.id);
+                assert_eq!(message.partition_key.as_deref(), Some("k"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        tap.close();
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_is_told_what_it_missed() {
+        let tap = MessageTap::new();
+        let stream = tap.subscribe(filter(None));
+        let mut stream = std::pin::pin!(stream);
+
+        let events: Vec<Event> = (0..TAP_CHANNEL_CAPACITY + 5).map(|_| event()).collect();
+        tap.publish("orders", "created", &events, None);
+
+        assert!(matches!(stream.next().await, Some(TapItem::Lagged(5))));
+        assert!(matches!(stream.next().await, Some(TapItem::Message(_))));
+    }
+
+    #[test]
+    fn test_publish_without_subscribers_copies_nothing() {
+        let tap = MessageTap::new();
+        tap.publish("orders", "created", &[event()], None);
+        assert_eq!(tap.subscribers(), 0);
+    }
+}
@@ -0,0 +1,416 @@
+//! WASM modules as pipeline transforms (`wasm` feature).
+//!
+//! `PUT /admin/wasm-modules/{name}` uploads a module; each upload of a name
+//! becomes its next version, and the last [`MAX_MODULE_VERSIONS`] are kept.
+//! A pipeline names a module (and optionally a version) in its `wasm`
+//! field; the version is pinned when the pipeline is defined, so uploading
+//! a new one never changes a running pipeline.
+//!
+//! # ABI
+//!
+//! A module imports nothing and exports:
+//!
+//! - `memory` - its linear memory
+//! - `alloc(len: i32) -> i32` - a buffer of `len` bytes for the input
+//! - `transform(ptr: i32, len: i32) -> i64` - transforms the event JSON at
+//!   `ptr`, returning the output's pointer in the high 32 bits and its
+//!   length in the low 32 bits
+//!
+//! The input is the event after the pipeline's built-in transform. The
+//! output is an event in the same JSON shape, or `null` to drop it.
+//!
+//! # Limits
+//!
+//! Every event runs in a fresh instance, so nothing carries over between
+//! events. An invocation may burn at most `WASM_FUEL_PER_EVENT` fuel
+//! (roughly one unit per instruction) and grow its memory to at most
+//! `WASM_MAX_MEMORY_BYTES`; a module that exceeds either traps, and the
+//! pipeline skips the event. A pipeline processes each batch on a blocking
+//! thread (`spawn_blocking`), so guest code never holds a runtime worker;
+//! the fuel limit bounds how long one event holds that thread.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use wasmtime::{
+    Engine, ExternType, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use super::CustomTransform;
+use crate::error::{AppError, AppResult};
+use crate::models::{Event, WasmModuleInfo, WasmModuleVersion};
+use crate::utils::hex;
+
+/// Versions kept per module name. A pipeline pinned to an older version
+/// keeps running it.
+pub const MAX_MODULE_VERSIONS: usize = 10;
+
+/// Limits applied to uploads and to every invocation.
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Fuel one invocation may burn
+    pub fuel: u64,
+    /// Linear memory one instance may grow to, in bytes
+    pub max_memory_bytes: usize,
+    /// Largest accepted module, in bytes
+    pub max_module_bytes: usize,
+}
+
+/// Per-invocation store data.
+struct StoreState {
+    limits: StoreLimits,
+}
+
+/// One uploaded version of a module, compiled and ready to instantiate.
+pub struct WasmModule {
+    info: WasmModuleVersion,
+    pre: InstancePre<StoreState>,
+    limits: WasmLimits,
+}
+
+impl WasmModule {
+    /// The version's metadata.
+    pub fn info(&self) -> &WasmModuleVersion {
+        &self.info
+    }
+
+    /// Run `transform` on `event` in a fresh instance, returning the output
+    /// event (`None` to drop it) and the fuel consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the trap (fuel or memory exhausted, out-of-bounds output,
+    /// ...) or why the output is not an event.
+    pub fn run(&self, event: &Event) -> Result<(Option<Event>, u64), String> {
+        let input = serde_json::to_vec(event).map_err(|e| e.to_string())?;
+        let mut store = Store::new(
+            self.pre.module().engine(),
+            StoreState {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.limits.max_memory_bytes)
+                    .instances(1)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.limits.fuel)
+            .map_err(|e| e.to_string())?;
+
+        let output = invoke(&mut store, &self.pre, &input);
+        let fuel_consumed = self
+            .limits
+            .fuel
+            .saturating_sub(store.get_fuel().unwrap_or(0));
+        let output = output.map_err(|e| format!("{e:#}"))?;
+        let event = serde_json::from_slice(&output)
+            .map_err(|e| format!("Transform output is not an event or null: {e}"))?;
+        Ok((event, fuel_consumed))
+    }
+}
+
+impl CustomTransform for WasmModule {
+    fn apply(&self, event: &Event) -> Result<Option<Event>, String> {
+        self.run(event).map(|(event, _)| event)
+    }
+}
+
+/// Instantiate `pre` and call its `transform` on `input`, returning the
+/// output bytes.
+fn invoke(
+    store: &mut Store<StoreState>,
+    pre: &InstancePre<StoreState>,
+    input: &[u8],
+) -> wasmtime::Result<Vec<u8>> {
+    let len = i32::try_from(input.len())?;
+    let instance = pre.instantiate(&mut *store)?;
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("module does not export `memory`"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+    let transform = instance.get_typed_func::<(i32, i32), i64>(&mut *store, "transform")?;
+
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, input)?;
+    let packed = transform.call(&mut *store, (ptr, len))? as u64;
+
+    let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    if out_ptr.saturating_add(out_len) > memory.data_size(&*store) {
+        return Err(wasmtime::Error::msg("transform output is out of bounds"));
+    }
+    let mut output = vec![0; out_len];
+    memory.read(&*store, out_ptr, &mut output)?;
+    Ok(output)
+}
+
+/// Uploaded modules by name, each with its kept versions (oldest first).
+pub struct WasmModules {
+    /// `None` when the engine could not be created; uploads are refused
+    engine: Option<Engine>,
+    limits: WasmLimits,
+    modules: Mutex<BTreeMap<String, Vec<Arc<WasmModule>>>>,
+}
+
+impl WasmModules {
+    /// Create an empty store applying `limits`.
+    pub fn new(limits: WasmLimits) -> Self {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .inspect_err(|e| warn!(error = %e, "WASM engine unavailable; uploads are refused"))
+            .ok();
+        Self {
+            engine,
+            limits,
+            modules: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Compile `bytes` as the next version of `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` if the module is too large, does not
+    /// compile, imports anything or does not export the ABI, and
+    /// `AppError::Internal` if the engine is unavailable.
+    pub fn upload(&self, name: &str, bytes: &[u8]) -> AppResult<WasmModuleVersion> {
+        let engine = self
+            .engine
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("WASM engine unavailable".to_string()))?;
+        if bytes.len() > self.limits.max_module_bytes {
+            return Err(AppError::BadRequest(format!(
+                "Module is {} bytes; WASM_MAX_MODULE_BYTES is {}",
+                bytes.len(),
+                self.limits.max_module_bytes
+            )));
+        }
+        let module = Module::new(engine, bytes)
+            .map_err(|e| AppError::BadRequest(format!("Invalid WASM module: {e:#}")))?;
+        let pre = Linker::new(engine).instantiate_pre(&module).map_err(|e| {
+            AppError::BadRequest(format!("WASM modules must not import anything: {e:#}"))
+        })?;
+        check_exports(&module)?;
+
+        let mut modules = self.lock();
+        let versions = modules.entry(name.to_string()).or_default();
+        let version = versions.last().map_or(1, |latest| latest.info.version + 1);
+        let info = WasmModuleVersion {
+            version,
+            sha256: hex(&Sha256::digest(bytes)),
+            size_bytes: bytes.len() as u64,
+            uploaded_at: Utc::now(),
+        };
+        versions.push(Arc::new(WasmModule {
+            info: info.clone(),
+            pre,
+            limits: self.limits,
+        }));
+        if versions.len() > MAX_MODULE_VERSIONS {
+            versions.remove(0);
+        }
+        Ok(info)
+    }
+
+    /// Version `version` of `name`, or its latest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if there is no such module or version.
+    pub fn resolve(&self, name: &str, version: Option<u32>) -> AppResult<Arc<WasmModule>> {
+        let modules = self.lock();
+        let versions = modules.get(name).ok_or_else(|| not_found(name))?;
+        let module = match version {
+            Some(version) => versions.iter().find(|m| m.info.version == version),
+            None => versions.last(),
+        };
+        module.cloned().ok_or_else(|| {
+            AppError::NotFound(format!(
+                "WASM module '{name}' has no version {}",
+                version.unwrap_or_default()
+            ))
+        })
+    }
+
+    /// One module and its kept versions.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no module is named `name`.
+    pub fn get(&self, name: &str) -> AppResult<WasmModuleInfo> {
+        self.lock()
+            .get(name)
+            .map(|versions| module_info(name, versions))
+            .ok_or_else(|| not_found(name))
+    }
+
+    /// Every module, ordered by name.
+    pub fn list(&self) -> Vec<WasmModuleInfo> {
+        self.lock()
+            .iter()
+            .map(|(name, versions)| module_info(name, versions))
+            .collect()
+    }
+
+    /// Remove a module with all its versions and return it. Pipelines
+    /// already running a version keep it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::NotFound` if no module is named `name`.
+    pub fn remove(&self, name: &str) -> AppResult<WasmModuleInfo> {
+        self.lock()
+            .remove(name)
+            .map(|versions| module_info(name, &versions))
+            .ok_or_else(|| not_found(name))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Vec<Arc<WasmModule>>>> {
+        self.modules.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Reject a module that does not export the transform ABI.
+fn check_exports(module: &Module) -> AppResult<()> {
+    let matches = |ty: ExternType, is_func: bool| {
+        if is_func {
+            ty.func().is_some()
+        } else {
+            ty.memory().is_some()
+        }
+    };
+    for (export, kind, is_func) in [
+        ("memory", "a memory", false),
+        ("alloc", "a function", true),
+        ("transform", "a function", true),
+    ] {
+        if !module
+            .get_export(export)
+            .is_some_and(|ty| matches(ty, is_func))
+        {
+            return Err(AppError::BadRequest(format!(
+                "WASM module must export `{export}` as {kind}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn module_info(name: &str, versions: &[Arc<WasmModule>]) -> WasmModuleInfo {
+    WasmModuleInfo {
+        name: name.to_string(),
+        versions: versions.iter().map(|m| m.info.clone()).collect(),
+    }
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::NotFound(format!("WASM module '{name}' not found"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::models::EventPayload;
+
+    /// Returns its input unchanged.
+    const ECHO: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    /// Drops every event.
+    const DROP: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "null")
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "transform") (param i32 i32) (result i64) (i64.const 4)))
+    "#;
+
+    /// Never returns.
+    const SPIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "transform") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    fn modules() -> WasmModules {
+        WasmModules::new(WasmLimits {
+            fuel: 1_000_000,
+            max_memory_bytes: 1 << 20,
+            max_module_bytes: 1 << 16,
+        })
+    }
+
+    fn event() -> Event {
+        Event::new(
+            "order.created",
+            EventPayload::Generic(serde_json::json!({ "total": 5 })),
+        )
+    }
+
+    #[test]
+    fn test_modules_transform_drop_and_run_out_of_fuel() {
+        let modules = modules();
+        modules.upload("echo", ECHO.as_bytes()).unwrap();
+        modules.upload("drop", DROP.as_bytes()).unwrap();
+        modules.upload("spin", SPIN.as_bytes()).unwrap();
+        let input = event();
+
+        let (output, fuel) = modules.resolve("echo", None).unwrap().run(&input).unwrap();
+        assert_eq!(output.unwrap().id, input.id);
+        assert!(fuel > 0);
+
+        let (output, _) = modules.resolve("drop", None).unwrap().run(&input).unwrap();
+        assert!(output.is_none());
+
+        let error = modules
+            .resolve("spin", None)
+            .unwrap()
+            .run(&input)
+            .unwrap_err();
+        assert!(error.contains("fuel"), "{error}");
+    }
+
+    #[test]
+    fn test_uploads_are_versioned_and_validated() {
+        let modules = modules();
+        assert_eq!(modules.upload("echo", ECHO.as_bytes()).unwrap().version, 1);
+        let second = modules.upload("echo", ECHO.as_bytes()).unwrap();
+        assert_eq!(second.version, 2);
+        assert_eq!(second.sha256.len(), 64);
+        assert_eq!(modules.resolve("echo", None).unwrap().info().version, 2);
+        assert_eq!(modules.resolve("echo", Some(1)).unwrap().info().version, 1);
+        assert!(matches!(
+            modules.resolve("echo", Some(3)),
+            Err(AppError::NotFound(_))
+        ));
+
+        for invalid in [
+            "not wasm",
+            r#"(module (memory (export "memory") 1))"#,
+            r#"(module (import "env" "f" (func)) (memory (export "memory") 1))"#,
+        ] {
+            let result = modules.upload("bad", invalid.as_bytes());
+            assert!(matches!(result, Err(AppError::BadRequest(_))), "{invalid}");
+        }
+        assert!(modules.get("bad").is_err());
+    }
+}
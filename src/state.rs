@@ -4,9 +4,49 @@
 //! all request handlers. It includes:
 //!
 //! - **Services**: Producer and consumer for message operations
-//! - **Client**: Iggy client wrapper for low-level operations
+//! - **Client**: Iggy client wrapper for low-level operations, plus an
+//!   optional second one for the consume path (`IGGY_READ_CONNECTION_STRING`)
 //! - **Configuration**: Runtime configuration access
-//! - **Stats Cache**: Background-refreshed statistics for `/stats` endpoint
+//! - **Stats Cache**: Background-refreshed totals for `/stats` and per-stream
+//!   snapshots for `/streams/{name}/stats`
+//! - **Topic Stats Cache**: TTL-bounded per-topic partition detail
+//! - **Continuation Tokens**: Signed poll positions resumable on any
+//!   replica (`POLL_CONTINUATION_SECRET`)
+//! - **Poll Dedup**: Event IDs recently returned to each consumer
+//!   (`POLL_DEDUP_WINDOW_SECS`)
+//! - **Consumer Registry**: Consumers seen polling, for `/consumers` and
+//!   idle-consumer cleanup
+//! - **Scheduler**: Sends held for delayed delivery
+//! - **Recurring Schedules**: Cron schedules producing templated events
+//! - **Pipelines**: Filtered, transformed copies of one topic into another
+//! - **Replicator**: Copies of local topics in a remote Iggy cluster
+//!   (`REPLICATION_CONNECTION_STRING`)
+//! - **Expressions**: Rhai predicates of poll filters, fan-out routing and
+//!   pipelines, with their evaluation limits
+//! - **Audit Log**: Record of stream, topic and user changes
+//! - **Top Talkers**: Clients sending the largest request bodies
+//! - **Message Index**: Recent events' positions by ID, for
+//!   `GET /messages/by-id/{id}`
+//! - **Ordering Check**: Per-key ordering violations of a topic, for
+//!   `GET /admin/ordering-report`
+//! - **Retention**: Enforcement of the bootstrap spec's retention policies
+//! - **Storage Alarm**: Storage thresholds, evaluated on each stats refresh
+//! - **Throughput Anomalies**: Per-topic message rate baselines, evaluated
+//!   on each stats refresh
+//! - **Leader Election**: Lease deciding which replica runs retention,
+//!   recurring schedules, pipelines and replication
+//!   (`LEADER_ELECTION_LEASE_SECS`)
+//! - **Notifier**: Connection events and throughput anomalies POSTed to
+//!   `NOTIFY_WEBHOOK_URL`
+//! - **Spool**: Sends held on local disk while Iggy is unreachable
+//! - **Outbox**: Sends held in memory while the send circuit is open
+//! - **Tap**: Live copy of sent messages for `GET /admin/tap`
+//! - **Benchmark**: Load tests run by `POST /admin/benchmark`
+//! - **Chaos**: Fault injection rules of `POST /admin/chaos` (`chaos` feature)
+//! - **WASM Modules**: Versioned pipeline transforms of
+//!   `PUT /admin/wasm-modules/{name}` (`wasm` feature)
+//! - **Leak Check**: Internal collection sizes for `GET /admin/internals`,
+//!   sampled for steady growth during soak tests
 //!
 //! # Thread Safety
 //!
@@ -19,19 +59,47 @@
 //! `CancellationToken` for proper lifecycle management. Call `shutdown()`
 //! to gracefully stop all background tasks before application exit.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
 
+use futures_util::StreamExt;
+use iggy::prelude::{Stream, StreamDetails, TopicDetails};
 use tokio::sync::RwLock;
 use tokio::time::interval;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
-use tracing::{debug, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 use crate::config::Config;
-use crate::iggy_client::IggyClientWrapper;
-use crate::middleware::RequestTimeout;
-use crate::services::{ConsumerService, ProducerService};
+use crate::error::{AppError, AppResult};
+use crate::iggy_client::{IggyClientWrapper, MessageBroker};
+#[cfg(feature = "chaos")]
+use crate::middleware::Chaos;
+use crate::middleware::{IdempotencyStore, RateLimitLayer, RequestTimeout};
+use crate::models::{
+    CreatePipelineRequest, CreateScheduleRequest, InternalsResponse, PartitionStats,
+    StreamStatsResponse, StreamTopicStats, TopicStatsResponse,
+};
+use crate::services::{
+    AuditService, Benchmark, CanaryService, ConsumerRegistry, ConsumerService, ContinuationTokens,
+    EventCounter, Expressions, IdleConsumer, LeaderElection, LeakCheck, MIGRATIONS,
+    MemoryStateStore, MessageIndex, MessageTap, Migrations, OrderingChecker, Outbox,
+    PIPELINES_NAMESPACE, Pipelines, PollDedup, ProducerService, RecurringSchedules, Replicator,
+    RetentionManager, SCHEDULES_NAMESPACE, Scheduler, Spool, StateBackend, StateStore,
+    StorageAlarm, ThroughputAnomalies, TopTalkers, WebhookNotifier, open_state_store,
+};
+#[cfg(feature = "wasm")]
+use crate::services::{WasmLimits, WasmModules};
+
+/// Upper bound on the idle-consumer sweep interval, so a long TTL does not
+/// let idle consumers linger for up to twice as long.
+const MAX_CONSUMER_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest a stream snapshot is kept without re-fetching the stream, even
+/// if its summary did not change, to pick up what the summary does not show
+/// (such as partitions added to a topic).
+const MAX_STREAM_SNAPSHOT_AGE: Duration = Duration::from_secs(60);
 
 /// Cached statistics for efficient `/stats` endpoint.
 ///
@@ -61,6 +129,69 @@ impl CachedStats {
     }
 }
 
+/// Cached statistics for a single stream.
+///
+/// Each stats refresh lists the streams once and re-fetches only those
+/// whose summary changed since their snapshot (or whose snapshot is older
+/// than a minute); the others are confirmed as they are.
+#[derive(Debug, Clone)]
+pub struct CachedStreamStats {
+    /// Statistics as last fetched (`cache_age_seconds` is filled on read)
+    pub stats: StreamStatsResponse,
+    /// When the stream's details were fetched
+    pub fetched_at: Instant,
+    /// When a refresh last confirmed the snapshot current
+    pub checked_at: Instant,
+}
+
+impl CachedStreamStats {
+    fn new(stats: StreamStatsResponse) -> Self {
+        let now = Instant::now();
+        Self {
+            stats,
+            fetched_at: now,
+            checked_at: now,
+        }
+    }
+
+    /// Whether the stream must be re-fetched: `summary`, from the stream
+    /// listing, differs from the snapshot or the snapshot is too old.
+    fn needs_fetch(&self, summary: &Stream) -> bool {
+        self.stats.topics_count != summary.topics_count
+            || self.stats.messages_count != summary.messages_count
+            || self.stats.size_bytes != summary.size.as_bytes_u64()
+            || self.fetched_at.elapsed() > MAX_STREAM_SNAPSHOT_AGE
+    }
+}
+
+/// Totals and per-stream snapshots, replaced together by each refresh.
+#[derive(Debug, Default)]
+struct StatsCache {
+    totals: CachedStats,
+    streams: HashMap<String, CachedStreamStats>,
+}
+
+/// Cached statistics for a single topic.
+///
+/// Unlike the global [`CachedStats`], entries are filled lazily on request
+/// (there is no background task per topic) but share the same TTL, so a
+/// hot dashboard polling one topic costs at most one Iggy lookup per
+/// `STATS_CACHE_TTL_SECS`.
+#[derive(Debug, Clone)]
+pub struct CachedTopicStats {
+    /// Statistics as of `last_updated` (`cache_age_seconds` is filled on read)
+    pub stats: TopicStatsResponse,
+    /// When these stats were fetched
+    pub last_updated: Instant,
+}
+
+impl CachedTopicStats {
+    /// Check if the entry is stale (older than `ttl`).
+    pub fn is_stale(&self, ttl: Duration) -> bool {
+        self.last_updated.elapsed() > ttl
+    }
+}
+
 /// Shared application state for Axum handlers.
 ///
 /// This struct is cloned for each request handler. All internal data
@@ -80,16 +211,89 @@ impl CachedStats {
 pub struct AppState {
     /// Iggy client wrapper for low-level operations
     pub iggy_client: IggyClientWrapper,
+    /// Separate client for polls, acks and consumer lag, with its own
+    /// circuit breakers (`IGGY_READ_CONNECTION_STRING`); `None` when the
+    /// consume path shares `iggy_client`
+    pub read_client: Option<IggyClientWrapper>,
     /// Producer service for sending messages
     pub producer: ProducerService,
     /// Consumer service for receiving messages
     pub consumer: ConsumerService,
+    /// Consumers seen polling through `consumer` (shared with it)
+    pub consumer_registry: Arc<ConsumerRegistry>,
+    /// Issuer and verifier of poll `continuation` tokens (shared with
+    /// `consumer`)
+    pub continuations: Arc<ContinuationTokens>,
+    /// Event IDs recently returned to each consumer
+    /// (`POLL_DEDUP_WINDOW_SECS`; None = disabled)
+    pub poll_dedup: Option<Arc<PollDedup>>,
+    /// Sends held for delayed delivery
+    pub scheduler: Arc<Scheduler>,
+    /// Cron schedules registered via `POST /schedules`
+    pub schedules: Arc<RecurringSchedules>,
+    /// Pipelines defined via `POST /pipelines`
+    pub pipelines: Arc<Pipelines>,
+    /// Replication of `REPLICATION_TOPICS` to a remote cluster
+    pub replicator: Arc<Replicator>,
+    /// Compiles the predicates of poll filters, fan-out routing and
+    /// pipelines (`EXPRESSION_*`)
+    pub expressions: Arc<Expressions>,
+    /// Audit log of admin and destructive operations
+    pub audit: Arc<AuditService>,
+    /// Persisted schedules, pipelines and idempotent responses
+    /// (`STATE_BACKEND`)
+    pub state_store: Arc<dyn StateStore>,
+    /// Migrations of the persisted state, applied by `restore_state`
+    pub migrations: Arc<Migrations>,
+    /// Clients sending the largest request bodies
+    pub top_talkers: Arc<TopTalkers>,
+    /// Positions of the default topic's recent events by ID
+    /// (`MESSAGE_INDEX_TTL_SECS`)
+    pub message_index: Arc<MessageIndex>,
+    /// Rolling counts of a topic's events by type (`EVENT_COUNTS_TOPIC`)
+    pub event_counts: Arc<EventCounter>,
+    /// Per-key ordering check of a topic (`ORDERING_CHECK_TOPIC`)
+    pub ordering: Arc<OrderingChecker>,
+    /// Retention policies of the bootstrap spec, with their enforcement
+    pub retention: Arc<RetentionManager>,
+    /// Storage thresholds (`MAX_TOTAL_SIZE_BYTES`, `MAX_TOPIC_SIZE_BYTES`),
+    /// evaluated against the stats cache
+    pub storage: Arc<StorageAlarm>,
+    /// Per-topic throughput baselines (`THROUGHPUT_ANOMALY_THRESHOLD`),
+    /// evaluated against the stats cache
+    pub anomalies: Arc<ThroughputAnomalies>,
+    /// Leader election for singleton background tasks; this replica always
+    /// leads when disabled
+    pub leader: Arc<LeaderElection>,
+    /// Sends held on local disk while Iggy is unreachable (`SPOOL_DIR`)
+    pub spool: Arc<Spool>,
+    /// Sends held in memory while the send circuit is open
+    /// (`OUTBOX_CAPACITY`)
+    pub outbox: Arc<Outbox>,
+    /// Live copy of the producer's sent messages (shared with it)
+    pub tap: Arc<MessageTap>,
+    /// Load generator of `POST /admin/benchmark`
+    pub benchmark: Arc<Benchmark>,
+    /// Fault injection rules of `POST /admin/chaos`
+    #[cfg(feature = "chaos")]
+    pub chaos: Arc<Chaos>,
+    /// Pipeline transform modules of `PUT /admin/wasm-modules/{name}`
+    #[cfg(feature = "wasm")]
+    pub wasm_modules: Arc<WasmModules>,
+    /// Growth of internal collections across samples (`LEAK_CHECK_*`)
+    pub leak_check: Arc<LeakCheck>,
+    /// Responses replayed to retries carrying an `Idempotency-Key`
+    pub idempotency: Arc<IdempotencyStore>,
     /// Timestamp when the application started
     pub started_at: Instant,
     /// Application configuration
     pub config: Arc<Config>,
-    /// Cached statistics (refreshed in background)
-    stats_cache: Arc<RwLock<CachedStats>>,
+    /// Cached totals and per-stream statistics (refreshed in background)
+    stats_cache: Arc<RwLock<StatsCache>>,
+    /// Per-topic statistics keyed by (stream, topic), filled on demand
+    topic_stats_cache: Arc<RwLock<HashMap<(String, String), CachedTopicStats>>>,
+    /// Rate limiter of the router, once built, for its key count
+    rate_limiter: Arc<Mutex<Option<RateLimitLayer>>>,
     /// Tracks spawned background tasks for graceful shutdown
     task_tracker: TaskTracker,
     /// Cancellation token for signaling background tasks to stop
@@ -110,27 +314,260 @@ impl AppState {
     /// The task runs at the interval specified by `config.stats_cache_ttl`.
     /// Call `shutdown()` to gracefully terminate background tasks.
     pub fn new(iggy_client: IggyClientWrapper, config: Config) -> Self {
+        Self::with_read_client(iggy_client, None, config)
+    }
+
+    /// Create application state served by `broker` instead of an Iggy
+    /// server, such as a [`MemoryBroker`](crate::iggy_client::MemoryBroker)
+    /// or a test double. Requests still go through the wrapper's fair
+    /// queue, deadlines and circuit breakers (see
+    /// [`IggyClientWrapper::with_broker`]). See [`Self::new`].
+    pub fn with_broker(broker: impl MessageBroker, config: Config) -> Self {
+        Self::new(
+            IggyClientWrapper::with_broker(config.clone(), broker),
+            config,
+        )
+    }
+
+    /// Create application state whose consume path (polls, acks, consumer
+    /// lag and idle-consumer cleanup) uses `read_client` when given, such
+    /// as a client of an Iggy read replica. Everything else uses
+    /// `iggy_client`. See [`Self::new`].
+    pub fn with_read_client(
+        iggy_client: IggyClientWrapper,
+        read_client: Option<IggyClientWrapper>,
+        config: Config,
+    ) -> Self {
+        // Created first so every clone of the clients aborts reconnection
+        // on shutdown.
+        let cancellation_token = CancellationToken::new();
+        let iggy_client = iggy_client.with_shutdown(cancellation_token.clone());
+        let read_client =
+            read_client.map(|client| client.with_shutdown(cancellation_token.clone()));
         let producer = ProducerService::new(iggy_client.clone());
-        let consumer = ConsumerService::new(iggy_client.clone());
+        let continuations = Arc::new(ContinuationTokens::new(
+            config.poll_continuation_secret.clone(),
+            config.poll_continuation_ttl,
+        ));
+        let mut consumer =
+            ConsumerService::new(read_client.clone().unwrap_or_else(|| iggy_client.clone()))
+                .with_continuations(Arc::clone(&continuations));
+        let poll_dedup = config.poll_dedup_enabled().then(|| {
+            Arc::new(PollDedup::new(
+                config.poll_dedup_window,
+                config.poll_dedup_max_ids,
+            ))
+        });
+        if let Some(dedup) = &poll_dedup {
+            consumer = consumer.with_dedup(Arc::clone(dedup));
+        }
+        let consumer_registry = Arc::clone(consumer.registry());
+        let tap = Arc::clone(producer.tap());
+        let scheduler = Arc::new(Scheduler::new(
+            iggy_client.clone(),
+            &config.default_stream,
+            &config.scheduled_topic,
+            config.scheduled_max_pending,
+        ));
+        let schedules = Arc::new(RecurringSchedules::new(config.max_schedules));
+        let expressions = Arc::new(Expressions::new(
+            config.expression_max_operations,
+            config.expression_timeout,
+        ));
+        let pipelines = Arc::new(Pipelines::new(
+            iggy_client.clone(),
+            Arc::clone(&expressions),
+            config.max_pipelines,
+            config.pipeline_batch_size,
+        ));
+        let replicator = Arc::new(Replicator::new(
+            iggy_client.clone(),
+            config.replication_topics.clone(),
+            config
+                .replication_origin
+                .as_deref()
+                .unwrap_or(&config.service_name),
+            config.replication_consumer_id,
+            config.replication_batch_size,
+        ));
+        let audit = Arc::new(AuditService::new(
+            iggy_client.clone(),
+            &config.default_stream,
+            &config.audit_topic,
+            config.audit_enabled,
+        ));
+        let state_store = open_state_store(iggy_client.clone(), &config);
+        // The iggy journal is never truncated, and each keyed request would
+        // add two records for every later startup to replay, so idempotent
+        // responses are only persisted to SQLite.
+        let idempotency_store: Arc<dyn StateStore> = match config.state_backend {
+            StateBackend::Iggy => Arc::new(MemoryStateStore),
+            _ => state_store.clone(),
+        };
+        let idempotency = Arc::new(IdempotencyStore::new(
+            config.idempotency_ttl,
+            config.idempotency_max_keys,
+            idempotency_store,
+        ));
+        let migrations = Arc::new(Migrations::new(MIGRATIONS.to_vec(), config.state_backend));
+        let top_talkers = Arc::new(TopTalkers::new(
+            config.top_talkers_limit,
+            config.top_talkers_window,
+        ));
+        let message_index = Arc::new(MessageIndex::new(
+            read_client.clone().unwrap_or_else(|| iggy_client.clone()),
+            &config.default_stream,
+            &config.default_topic,
+            config.message_index_ttl,
+            config.message_index_max_entries,
+        ));
+        let event_counts = Arc::new(EventCounter::new(
+            read_client.clone().unwrap_or_else(|| iggy_client.clone()),
+            &config.default_stream,
+            config.event_counts_topic.as_deref(),
+        ));
+        let ordering = Arc::new(OrderingChecker::new(
+            read_client.clone().unwrap_or_else(|| iggy_client.clone()),
+            &config.default_stream,
+            config.ordering_check_topic.as_deref(),
+            config.ordering_check_window,
+        ));
+        let retention = Arc::new(RetentionManager::new(
+            iggy_client.clone(),
+            config.bootstrap.as_ref(),
+        ));
+        let storage = Arc::new(StorageAlarm::new(
+            config.max_total_size_bytes,
+            config.max_topic_size_bytes,
+            config.storage_reject_produces,
+        ));
+        let anomalies = Arc::new(ThroughputAnomalies::new(
+            config.throughput_anomaly_threshold,
+            config.throughput_anomaly_alpha,
+        ));
+        let leader = Arc::new(LeaderElection::new(
+            iggy_client.clone(),
+            &config.default_stream,
+            &config.leader_election_topic,
+            config.leader_election_id.as_deref(),
+            config.leader_election_lease,
+        ));
+        let spool = Arc::new(open_spool(&config));
+        let outbox = Arc::new(Outbox::new(config.outbox_capacity, config.outbox_overflow));
+        let benchmark = Arc::new(Benchmark::new(
+            iggy_client.clone(),
+            &config.default_stream,
+            &config.benchmark_topic,
+            config.topic_partitions,
+            config.benchmark_max_duration,
+            config.batch_max_size,
+        ));
         let config = Arc::new(config);
-        let stats_cache = Arc::new(RwLock::new(CachedStats::default()));
+        let stats_cache = Arc::new(RwLock::new(StatsCache::default()));
         let task_tracker = TaskTracker::new();
-        let cancellation_token = CancellationToken::new();
 
         let state = Self {
             iggy_client,
+            read_client,
             producer,
             consumer,
+            consumer_registry,
+            continuations,
+            poll_dedup,
+            scheduler,
+            schedules,
+            pipelines,
+            replicator,
+            expressions,
+            audit,
+            state_store,
+            migrations,
+            top_talkers,
+            message_index,
+            event_counts,
+            ordering,
+            retention,
+            storage,
+            anomalies,
+            leader,
+            spool,
+            outbox,
+            tap,
+            benchmark,
+            #[cfg(feature = "chaos")]
+            chaos: Arc::new(Chaos::new()),
+            #[cfg(feature = "wasm")]
+            wasm_modules: Arc::new(WasmModules::new(WasmLimits {
+                fuel: config.wasm_fuel_per_event,
+                max_memory_bytes: config.wasm_max_memory_bytes,
+                max_module_bytes: config.wasm_max_module_bytes,
+            })),
+            leak_check: Arc::new(LeakCheck::new(config.leak_check_window)),
+            idempotency,
             started_at: Instant::now(),
             config,
             stats_cache,
+            topic_stats_cache: Arc::new(RwLock::new(HashMap::new())),
+            rate_limiter: Arc::new(Mutex::new(None)),
             task_tracker,
             cancellation_token,
         };
 
         // Spawn background tasks
         state.spawn_stats_refresh_task();
-        state.spawn_health_check_task();
+        if state.leader.is_enabled() {
+            state.spawn_leader_election_task();
+        }
+        state.spawn_health_check_task(state.iggy_client.clone(), "primary");
+        if let Some(read_client) = &state.read_client {
+            info!("Consume path uses the Iggy read connection");
+            state.spawn_health_check_task(read_client.clone(), "read");
+        }
+        if state.config.lag_monitoring_enabled() {
+            state.spawn_lag_monitor_task();
+        }
+        if state.config.canary_enabled() {
+            state.spawn_canary_task();
+        }
+        if state.config.consumer_cleanup_enabled() {
+            state.spawn_consumer_cleanup_task();
+        }
+        if state.config.scheduling_enabled() {
+            state.spawn_scheduler_task();
+        }
+        if state.config.schedules_enabled() {
+            state.spawn_recurring_schedules_task();
+        }
+        if state.config.pipelines_enabled() {
+            state.spawn_pipelines_task();
+        }
+        if let Some(remote_config) = state.config.replication_config() {
+            state.spawn_replication_task(remote_config);
+        }
+        if let Some(url) = &state.config.notify_webhook_url {
+            state.spawn_notifier_task(url);
+        }
+        if state.spool.is_enabled() {
+            state.spawn_spool_drain_task();
+        }
+        if state.outbox.is_enabled() {
+            state.spawn_outbox_task();
+        }
+        if state.config.leak_check_enabled() {
+            state.spawn_leak_check_task();
+        }
+        if state.message_index.is_enabled() {
+            state.spawn_message_index_task();
+        }
+        if state.event_counts.is_enabled() {
+            state.spawn_event_counts_task();
+        }
+        if state.ordering.is_enabled() {
+            state.spawn_ordering_check_task();
+        }
+        if state.config.retention_enabled() {
+            state.spawn_retention_task();
+        }
 
         state
     }
@@ -173,7 +610,82 @@ impl AppState {
     /// Returns the cached stats without blocking. If the cache is empty,
     /// returns default values.
     pub async fn cached_stats(&self) -> CachedStats {
-        self.stats_cache.read().await.clone()
+        self.stats_cache.read().await.totals.clone()
+    }
+
+    /// Get statistics for one stream, served from the stats cache.
+    ///
+    /// `cache_age_seconds` is the time since a refresh last confirmed the
+    /// snapshot. A stream created since the last refresh is read through
+    /// `client` (typically a request-scoped view) and cached until the next.
+    ///
+    /// # Errors
+    ///
+    /// Propagates the lookup error (e.g. `NotFound`) for a stream not in
+    /// the cache.
+    pub async fn stream_stats(
+        &self,
+        client: &IggyClientWrapper,
+        stream: &str,
+    ) -> AppResult<StreamStatsResponse> {
+        if let Some(snapshot) = self.stats_cache.read().await.streams.get(stream) {
+            let mut stats = snapshot.stats.clone();
+            stats.cache_age_seconds = snapshot.checked_at.elapsed().as_secs();
+            return Ok(stats);
+        }
+
+        let details = client.get_stream(stream).await?;
+        let stats = stream_stats_from_details(&details);
+        self.stats_cache
+            .write()
+            .await
+            .streams
+            .insert(stream.to_string(), CachedStreamStats::new(stats.clone()));
+        Ok(stats)
+    }
+
+    /// Get statistics for one topic, served from the per-topic cache.
+    ///
+    /// A fresh entry (younger than `stats_cache_ttl`) is returned as-is with
+    /// its age; otherwise the topic is re-read through `client` (typically a
+    /// request-scoped view) and the entry replaced. Stale entries for other
+    /// topics are evicted on write, so deleted topics do not accumulate.
+    ///
+    /// # Errors
+    ///
+    /// Propagates the lookup error (e.g. `NotFound`) when the entry must be
+    /// refreshed; a failed refresh leaves no entry behind.
+    pub async fn topic_stats(
+        &self,
+        client: &IggyClientWrapper,
+        stream: &str,
+        topic: &str,
+    ) -> AppResult<TopicStatsResponse> {
+        let ttl = self.config.stats_cache_ttl;
+        let key = (stream.to_string(), topic.to_string());
+
+        if let Some(entry) = self.topic_stats_cache.read().await.get(&key)
+            && !entry.is_stale(ttl)
+        {
+            let mut stats = entry.stats.clone();
+            stats.cache_age_seconds = entry.last_updated.elapsed().as_secs();
+            return Ok(stats);
+        }
+
+        let details = client.get_topic(stream, topic).await?;
+        let stats = topic_stats_from_details(stream, topic, &details);
+
+        let mut cache = self.topic_stats_cache.write().await;
+        cache.retain(|_, entry| !entry.is_stale(ttl));
+        cache.insert(
+            key,
+            CachedTopicStats {
+                stats: stats.clone(),
+                last_updated: Instant::now(),
+            },
+        );
+
+        Ok(stats)
     }
 
     /// Force refresh the stats cache.
@@ -181,21 +693,101 @@ impl AppState {
     /// This is called by the background task, but can also be called
     /// manually if needed.
     pub async fn refresh_stats(&self) {
-        match self.compute_stats().await {
-            Ok(stats) => {
-                let mut cache = self.stats_cache.write().await;
-                *cache = stats;
-                trace!("Stats cache refreshed successfully");
-            }
+        let concurrency = self.config.stats_refresh_concurrency;
+        if let Err(e) = refresh_stats_impl(
+            &self.iggy_client,
+            &self.stats_cache,
+            &self.storage,
+            &self.anomalies,
+            concurrency,
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to refresh stats cache");
+        }
+    }
+
+    /// Load the schedules and pipelines persisted by the `STATE_BACKEND`
+    /// (see [`StateStore`]), returning how many were restored.
+    ///
+    /// Called once at startup, before serving. Pending [`Migrations`] are
+    /// applied first; while an incompatible one is pending, nothing is
+    /// loaded and readiness fails. State that cannot be read is logged and
+    /// the gateway starts without it. Pipelines with a WASM
+    /// transform are skipped: modules are not persisted, so define them
+    /// again once the module is uploaded. Persisted idempotent responses
+    /// stay in the store, looked up per request; expired ones are removed.
+    pub async fn restore_state(&self) -> usize {
+        let mut journal = match self.state_store.replay().await {
+            Ok(journal) => journal,
             Err(e) => {
-                warn!(error = %e, "Failed to refresh stats cache");
+                warn!(error = %e, "Failed to load persisted state");
+                return 0;
+            }
+        };
+        if !self
+            .migrations
+            .run(self.state_store.as_ref(), &mut journal)
+            .await
+        {
+            return 0;
+        }
+        let expired = self.idempotency.remove_expired(&mut journal).await;
+        if expired > 0 {
+            info!(expired, "Removed expired idempotent responses");
+        }
+
+        let mut restored = 0;
+        for schedule in journal.take::<CreateScheduleRequest>(SCHEDULES_NAMESPACE) {
+            let name = schedule.name.clone();
+            let stream = schedule
+                .stream
+                .clone()
+                .unwrap_or_else(|| self.config.default_stream.clone());
+            let topic = schedule
+                .topic
+                .clone()
+                .unwrap_or_else(|| self.config.default_topic.clone());
+            match self.schedules.register(schedule, stream, topic) {
+                Ok(_) => restored += 1,
+                Err(e) => warn!(schedule = %name, error = %e, "Failed to restore schedule"),
+            }
+        }
+        for pipeline in journal.take::<CreatePipelineRequest>(PIPELINES_NAMESPACE) {
+            let name = pipeline.name.clone();
+            if pipeline.wasm.is_some() {
+                warn!(
+                    pipeline = %name,
+                    "Not restoring pipeline with a WASM transform; define it again after \
+                     uploading its module"
+                );
+                continue;
+            }
+            let source_stream = pipeline
+                .source_stream
+                .clone()
+                .unwrap_or_else(|| self.config.default_stream.clone());
+            let sink_stream = pipeline
+                .sink_stream
+                .clone()
+                .unwrap_or_else(|| self.config.default_stream.clone());
+            match self
+                .pipelines
+                .register(pipeline, source_stream, sink_stream, None)
+            {
+                Ok(_) => restored += 1,
+                Err(e) => warn!(pipeline = %name, error = %e, "Failed to restore pipeline"),
             }
         }
-    }
 
-    /// Compute current statistics from Iggy.
-    async fn compute_stats(&self) -> Result<CachedStats, crate::error::AppError> {
-        compute_stats_from_client(&self.iggy_client).await
+        if restored > 0 {
+            info!(
+                restored,
+                backend = %self.config.state_backend,
+                "Restored schedules and pipelines"
+            );
+        }
+        restored
     }
 
     /// Spawn the background stats refresh task.
@@ -205,17 +797,28 @@ impl AppState {
     ///
     /// # Implementation Note
     ///
-    /// We clone only the fields needed by the task (iggy_client, stats_cache)
-    /// rather than the entire AppState to minimize memory overhead.
+    /// We clone only the fields needed by the task (iggy_client, stats_cache,
+    /// storage, anomalies) rather than the entire AppState to minimize memory overhead.
     fn spawn_stats_refresh_task(&self) {
         let iggy_client = self.iggy_client.clone();
         let stats_cache = self.stats_cache.clone();
+        let storage = self.storage.clone();
+        let anomalies = self.anomalies.clone();
         let ttl = self.config.stats_cache_ttl;
+        let concurrency = self.config.stats_refresh_concurrency;
         let cancel = self.cancellation_token.clone();
 
         self.task_tracker.spawn(async move {
             // Initial refresh
-            if let Err(e) = refresh_stats_impl(&iggy_client, &stats_cache).await {
+            if let Err(e) = refresh_stats_impl(
+                &iggy_client,
+                &stats_cache,
+                &storage,
+                &anomalies,
+                concurrency,
+            )
+            .await
+            {
                 warn!(error = %e, "Initial stats refresh failed");
             }
 
@@ -232,7 +835,15 @@ impl AppState {
                         break;
                     }
                     _ = ticker.tick() => {
-                        if let Err(e) = refresh_stats_impl(&iggy_client, &stats_cache).await {
+                        if let Err(e) = refresh_stats_impl(
+                            &iggy_client,
+                            &stats_cache,
+                            &storage,
+                            &anomalies,
+                            concurrency,
+                        )
+                        .await
+                        {
                             warn!(error = %e, "Stats refresh failed");
                         }
                     }
@@ -243,13 +854,14 @@ impl AppState {
         });
     }
 
-    /// Spawn a background health check task.
+    /// Spawn a background health check task for `iggy_client`, logged as
+    /// `connection` (`primary` or `read`).
     ///
-    /// Periodically checks the Iggy connection status and logs warnings
-    /// if the connection is lost. This provides early detection of issues
-    /// before user requests fail.
-    fn spawn_health_check_task(&self) {
-        let iggy_client = self.iggy_client.clone();
+    /// Periodically pings the Iggy server and, when the ping fails, starts
+    /// reconnecting right away (joining a session a request already
+    /// started), so the connection is repaired before user requests fail
+    /// on it. Shutdown aborts the reconnection.
+    fn spawn_health_check_task(&self, iggy_client: IggyClientWrapper, connection: &'static str) {
         let interval_duration = self.config.health_check_interval;
         let cancel = self.cancellation_token.clone();
 
@@ -275,12 +887,31 @@ impl AppState {
                         // transport reconnection hides most mid-operation
                         // failures, so this probe is what keeps the connection
                         // state - and therefore /health and /ready - truthful.
-                        let connected = iggy_client.health_check().await;
+                        let mut connected = iggy_client.health_check().await;
                         match (was_connected, connected) {
-                            (true, false) => warn!("Health check: Iggy connection is down"),
-                            (false, false) => warn!("Health check: Iggy connection still down"),
-                            (false, true) => info!("Health check: Iggy connection restored"),
-                            (true, true) => trace!("Health check: Iggy connection OK"),
+                            (true, false) => {
+                                warn!(connection, "Health check: Iggy connection is down");
+                            }
+                            (false, false) => {
+                                warn!(connection, "Health check: Iggy connection still down");
+                            }
+                            (false, true) => {
+                                info!(connection, "Health check: Iggy connection restored");
+                            }
+                            (true, true) => trace!(connection, "Health check: Iggy connection OK"),
+                        }
+                        if !connected {
+                            match iggy_client.reconnect_now().await {
+                                Ok(()) => {
+                                    info!(connection, "Health check: reconnected to Iggy");
+                                    connected = true;
+                                }
+                                Err(e) => warn!(
+                                    connection,
+                                    error = %e,
+                                    "Health check: reconnection failed"
+                                ),
+                            }
                         }
                         was_connected = connected;
                     }
@@ -291,10 +922,598 @@ impl AppState {
         });
     }
 
+    /// Spawn the consumer lag monitor task.
+    ///
+    /// Samples the lag of each `LAG_MONITOR_CONSUMER_IDS` consumer on the
+    /// default stream/topic every `LAG_MONITOR_INTERVAL_SECS` and exports it
+    /// as the `iggy_consumer_lag` gauge. A failed sample is logged and the
+    /// previous gauge value is left in place until the next tick.
+    fn spawn_lag_monitor_task(&self) {
+        let consumer = self.consumer.clone();
+        let consumer_ids = self.config.lag_monitor_consumer_ids.clone();
+        let stream = self.config.default_stream.clone();
+        let topic = self.config.default_topic.clone();
+        let interval_duration = self.config.lag_monitor_interval;
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            consumers = ?consumer_ids,
+            interval_secs = interval_duration.as_secs(),
+            "Consumer lag monitoring enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Lag monitor task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        export_consumer_lag(&consumer, &stream, &topic, &consumer_ids).await;
+                    }
+                }
+            }
+
+            debug!("Lag monitor task shutting down");
+        });
+    }
+
+    /// Spawn the synthetic canary task.
+    ///
+    /// Every `CANARY_INTERVAL_SECS`, sends a heartbeat to `CANARY_TOPIC` in
+    /// the default stream and reads it back, exporting the round trip as
+    /// `iggy_canary_rtt_seconds`. Failed or timed-out probes increment
+    /// `iggy_canary_failures_total`. The canary topic is created on the first
+    /// tick (and retried on later ticks until that succeeds).
+    fn spawn_canary_task(&self) {
+        let canary = CanaryService::new(
+            self.iggy_client.clone(),
+            &self.config.default_stream,
+            &self.config.canary_topic,
+            self.config.canary_timeout,
+        );
+        let interval_duration = self.config.canary_interval;
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            topic = %self.config.canary_topic,
+            interval_secs = interval_duration.as_secs(),
+            "Synthetic canary enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+            let mut topic_ready = false;
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Canary task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        run_canary_probe(&canary, &mut topic_ready).await;
+                    }
+                }
+            }
+
+            debug!("Canary task shutting down");
+        });
+    }
+
+    /// Spawn the idle-consumer cleanup task.
+    ///
+    /// Sweeps the consumer registry every `CONSUMER_IDLE_TTL_SECS` (at most
+    /// once a minute), removing consumers that have not polled within the
+    /// TTL and deleting their committed offsets in Iggy (see
+    /// [`release_idle_consumer`]). A failed deletion is logged; the consumer
+    /// is not retried, since it is no longer in the registry. Offsets live
+    /// on the read connection when there is one.
+    ///
+    /// The registry only sees polls through this instance. With leader
+    /// election on there are other replicas, where a consumer idle here may
+    /// still be polling, so each replica only drops its idle entries and no
+    /// offset is deleted.
+    fn spawn_consumer_cleanup_task(&self) {
+        let iggy_client = self
+            .read_client
+            .as_ref()
+            .unwrap_or(&self.iggy_client)
+            .clone();
+        let registry = Arc::clone(&self.consumer_registry);
+        let delete_offsets = !self.config.leader_election_enabled();
+        let ttl = self.config.consumer_idle_ttl;
+        let interval_duration = ttl.min(MAX_CONSUMER_CLEANUP_INTERVAL);
+        let cancel = self.cancellation_token.clone();
+
+        if delete_offsets {
+            info!(ttl_secs = ttl.as_secs(), "Idle consumer cleanup enabled");
+        } else {
+            warn!(
+                ttl_secs = ttl.as_secs(),
+                "Idle consumer cleanup enabled, but offsets are kept: with leader election on, \
+                 idle consumers may still be polling other replicas"
+            );
+        }
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Consumer cleanup task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        for idle in registry.take_idle(ttl) {
+                            if delete_offsets {
+                                release_idle_consumer(&iggy_client, &idle).await;
+                            } else {
+                                debug!(
+                                    stream = %idle.stream,
+                                    topic = %idle.topic,
+                                    consumer_id = idle.consumer_id,
+                                    "Idle consumer dropped from the registry"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+
+            debug!("Consumer cleanup task shutting down");
+        });
+    }
+
+    /// Spawn the delayed-delivery task.
+    ///
+    /// Restores pending messages from `SCHEDULED_TOPIC`, then produces each
+    /// through the shared producer once due, on the leader only (see
+    /// [`Scheduler::run`]).
+    fn spawn_scheduler_task(&self) {
+        let scheduler = Arc::clone(&self.scheduler);
+        let leader = Arc::clone(&self.leader);
+        let producer = self.producer.clone();
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            topic = %self.config.scheduled_topic,
+            max_pending = self.config.scheduled_max_pending,
+            "Delayed delivery enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            scheduler.run(&producer, &leader, cancel).await;
+            debug!("Scheduler task shutting down");
+        });
+    }
+
+    /// Spawn the task POSTing connection events and throughput anomalies
+    /// to `url` (see
+    /// [`WebhookNotifier`]). A notifier that cannot be built is logged and
+    /// the gateway runs without notifications.
+    fn spawn_notifier_task(&self, url: &str) {
+        let notifier = match WebhookNotifier::new(url, &self.config.service_name) {
+            Ok(notifier) => notifier,
+            Err(e) => {
+                warn!(error = %e, "Webhook notifications disabled");
+                return;
+            }
+        };
+        self.iggy_client.observe(notifier.observer());
+        self.anomalies.observe(notifier.anomaly_observer());
+        let cancel = self.cancellation_token.clone();
+
+        info!("Webhook notifications enabled");
+
+        self.task_tracker.spawn(async move {
+            notifier.run(cancel).await;
+            debug!("Notifier task shutting down");
+        });
+    }
+
+    /// Spawn the task delivering spooled sends once Iggy is reachable (see
+    /// [`Spool::run`]).
+    fn spawn_spool_drain_task(&self) {
+        let spool = Arc::clone(&self.spool);
+        let producer = self.producer.clone();
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            dir = ?self.config.spool_dir,
+            max_bytes = self.config.spool_max_bytes,
+            pending_bytes = spool.pending_bytes(),
+            "Outage spool enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            spool.run(&producer, cancel).await;
+            debug!("Spool drain task shutting down");
+        });
+    }
+
+    /// Spawn the task delivering the outbox once Iggy takes sends again (see
+    /// [`Outbox::run`]).
+    fn spawn_outbox_task(&self) {
+        let outbox = Arc::clone(&self.outbox);
+        let producer = self.producer.clone();
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            capacity = self.config.outbox_capacity,
+            overflow = %self.config.outbox_overflow,
+            "Outbox enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            outbox.run(&producer, cancel).await;
+            debug!("Outbox task shutting down");
+        });
+    }
+
+    /// Spawn the task producing cron schedules as they fall due, on the
+    /// leader only (see [`RecurringSchedules::run`]).
+    fn spawn_recurring_schedules_task(&self) {
+        let schedules = Arc::clone(&self.schedules);
+        let leader = Arc::clone(&self.leader);
+        let producer = self.producer.clone();
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            max_schedules = self.config.max_schedules,
+            "Recurring schedules enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            schedules.run(&producer, &leader, cancel).await;
+            debug!("Recurring schedules task shutting down");
+        });
+    }
+
+    /// Spawn the pipeline task.
+    ///
+    /// Runs every pipeline that is not paused every `PIPELINE_INTERVAL_MS`,
+    /// on the leader only (see [`Pipelines::run_all`]).
+    fn spawn_pipelines_task(&self) {
+        let pipelines = Arc::clone(&self.pipelines);
+        let leader = Arc::clone(&self.leader);
+        let producer = self.producer.clone();
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.pipeline_interval;
+
+        info!(
+            max_pipelines = self.config.max_pipelines,
+            interval_ms = interval_duration.as_millis() as u64,
+            "Pipelines enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Pipelines task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if leader.is_leader() {
+                            pipelines.run_all(&producer).await;
+                        }
+                    }
+                }
+            }
+
+            debug!("Pipelines task shutting down");
+        });
+    }
+
+    /// Spawn the task replicating `REPLICATION_TOPICS` to the cluster of
+    /// `remote_config` every `REPLICATION_INTERVAL_MS` (see [`Replicator`]).
+    ///
+    /// The remote cluster is connected on the first run and retried at
+    /// every run until it answers, so an unreachable target does not hold
+    /// up startup. With leader election on, only the leader replicates.
+    fn spawn_replication_task(&self, remote_config: Config) {
+        let replicator = Arc::clone(&self.replicator);
+        let leader = Arc::clone(&self.leader);
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.replication_interval;
+
+        info!(
+            topics = self.config.replication_topics.len(),
+            interval_ms = interval_duration.as_millis() as u64,
+            "Replication enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Replication task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if leader.is_leader()
+                            && let Some(remote) = replicator.connect(&remote_config, &cancel).await
+                        {
+                            replicator.run_all(&remote).await;
+                        }
+                    }
+                }
+            }
+
+            debug!("Replication task shutting down");
+        });
+    }
+
+    /// Spawn the leak self-check task.
+    ///
+    /// Samples [`Self::internals`] every `LEAK_CHECK_INTERVAL_SECS` and
+    /// warns about each counter that rose at every one of the last
+    /// `LEAK_CHECK_WINDOW` samples (see [`LeakCheck`]). The task holds a
+    /// clone of the whole state, since the sample spans most of it.
+    fn spawn_leak_check_task(&self) {
+        let state = self.clone();
+        let cancel = self.cancellation_token.clone();
+
+        info!(
+            interval_secs = self.config.leak_check_interval.as_secs(),
+            window = self.config.leak_check_window,
+            "Leak self-check enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(state.config.leak_check_interval);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Leak check task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        let internals = state.internals();
+                        for counter in state.leak_check.record(&internals) {
+                            warn!(
+                                counter,
+                                ?internals,
+                                "Internal counter grew at every leak check sample; possible leak"
+                            );
+                            crate::metrics::record_leak_suspected(counter);
+                        }
+                    }
+                }
+            }
+
+            debug!("Leak check task shutting down");
+        });
+    }
+
+    /// Spawn the message index task.
+    ///
+    /// Reads what was appended to the default topic every
+    /// `MESSAGE_INDEX_INTERVAL_SECS` (see [`MessageIndex::catch_up`]). A
+    /// failed read is logged and resumed on the next tick.
+    fn spawn_message_index_task(&self) {
+        let index = Arc::clone(&self.message_index);
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.message_index_interval;
+
+        info!(
+            ttl_secs = self.config.message_index_ttl.as_secs(),
+            max_entries = self.config.message_index_max_entries,
+            "Message index enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Message index task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if let Err(e) = index.catch_up().await {
+                            warn!(error = %e, "Message index catch-up failed");
+                        }
+                    }
+                }
+            }
+
+            debug!("Message index task shutting down");
+        });
+    }
+
+    /// Spawn the event counting task.
+    ///
+    /// Reads what was appended to `EVENT_COUNTS_TOPIC` every
+    /// `EVENT_COUNTS_INTERVAL_SECS` (see [`EventCounter::catch_up`]). A
+    /// failed read is logged and resumed on the next tick.
+    fn spawn_event_counts_task(&self) {
+        let counter = Arc::clone(&self.event_counts);
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.event_counts_interval;
+
+        info!(
+            topic = ?self.config.event_counts_topic,
+            interval_secs = interval_duration.as_secs(),
+            "Event counts enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Event counts task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if let Err(e) = counter.catch_up().await {
+                            warn!(error = %e, "Event counts catch-up failed");
+                        }
+                    }
+                }
+            }
+
+            debug!("Event counts task shutting down");
+        });
+    }
+
+    /// Spawn the ordering check task.
+    ///
+    /// Checks what was appended to `ORDERING_CHECK_TOPIC` every
+    /// `ORDERING_CHECK_INTERVAL_SECS` (see [`OrderingChecker::catch_up`]).
+    /// A failed read is logged and resumed on the next tick.
+    fn spawn_ordering_check_task(&self) {
+        let checker = Arc::clone(&self.ordering);
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.ordering_check_interval;
+
+        info!(
+            topic = ?self.config.ordering_check_topic,
+            interval_secs = interval_duration.as_secs(),
+            window_secs = self.config.ordering_check_window.as_secs(),
+            "Ordering check enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Ordering check task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if let Err(e) = checker.catch_up().await {
+                            warn!(error = %e, "Ordering check catch-up failed");
+                        }
+                    }
+                }
+            }
+
+            debug!("Ordering check task shutting down");
+        });
+    }
+
+    /// Spawn the retention enforcement task.
+    ///
+    /// Enforces the bootstrap spec's retention policies every
+    /// `RETENTION_INTERVAL_SECS`, starting right away (see
+    /// [`RetentionManager::enforce`]). Ticks on a replica that is not the
+    /// leader are skipped.
+    fn spawn_retention_task(&self) {
+        let retention = Arc::clone(&self.retention);
+        let leader = Arc::clone(&self.leader);
+        let cancel = self.cancellation_token.clone();
+        let interval_duration = self.config.retention_interval;
+
+        info!(
+            interval_secs = interval_duration.as_secs(),
+            topics = retention.status().len(),
+            "Retention enforcement enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(interval_duration);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Retention task received cancellation signal");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if leader.is_leader() {
+                            retention.enforce().await;
+                        }
+                    }
+                }
+            }
+
+            debug!("Retention task shutting down");
+        });
+    }
+
+    /// Spawn the leader election task.
+    ///
+    /// Renews or claims the lease every third of
+    /// `LEADER_ELECTION_LEASE_SECS` (see [`LeaderElection::tick`]), and
+    /// releases it on shutdown. A failed tick is logged and retried on the
+    /// next one.
+    fn spawn_leader_election_task(&self) {
+        let leader = Arc::clone(&self.leader);
+        let cancel = self.cancellation_token.clone();
+        let status = leader.status();
+
+        info!(
+            candidate = %status.candidate,
+            lease_secs = status.lease_secs,
+            topic = %self.config.leader_election_topic,
+            "Leader election enabled"
+        );
+
+        self.task_tracker.spawn(async move {
+            let mut ticker = interval(leader.renew_interval());
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => {
+                        debug!("Leader election task received cancellation signal");
+                        leader.release().await;
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if let Err(e) = leader.tick().await {
+                            warn!(error = %e, "Leader election tick failed");
+                        }
+                    }
+                }
+            }
+
+            debug!("Leader election task shutting down");
+        });
+    }
+
     /// Gracefully shutdown all background tasks.
     ///
     /// This method:
-    /// 1. Signals all tasks to stop via cancellation token
+    /// 1. Signals all tasks to stop via cancellation token, which also
+    ///    aborts any Iggy reconnection in progress
     /// 2. Closes the task tracker (prevents new tasks)
     /// 3. Waits for all tasks to complete
     ///
@@ -319,6 +1538,35 @@ impl AppState {
         info!("All background tasks have completed");
     }
 
+    /// Count the keys of `layer`, the rate limiter of the router built
+    /// from this state, in [`Self::internals`].
+    pub(crate) fn track_rate_limiter(&self, layer: &RateLimitLayer) {
+        *self
+            .rate_limiter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(layer.clone());
+    }
+
+    /// Current sizes of the internal collections that could leak, with the
+    /// counters the leak check suspects.
+    pub fn internals(&self) -> InternalsResponse {
+        let rate_limiter_keys = self
+            .rate_limiter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map_or(0, RateLimitLayer::key_count);
+        InternalsResponse {
+            open_tasks: self.task_tracker.len(),
+            outbox_depth: self.outbox.depth(),
+            rate_limiter_keys,
+            consumer_registry_size: self.consumer_registry.len(),
+            poll_dedup_ids: self.poll_dedup.as_ref().map_or(0, |dedup| dedup.len()),
+            ordering_check_keys: self.ordering.key_count(),
+            suspected_leaks: self.leak_check.suspects(),
+        }
+    }
+
     /// Get the application uptime in seconds.
     pub fn uptime_seconds(&self) -> u64 {
         self.started_at.elapsed().as_secs()
@@ -333,33 +1581,296 @@ impl AppState {
 ///
 /// This is separate from `AppState::refresh_stats` to allow background tasks
 /// to hold only the fields they need, rather than cloning the entire state.
+///
+/// Lists the streams once for the totals, then fetches the details of the
+/// streams whose snapshot is missing or outdated (see
+/// [`CachedStreamStats`]), at most `concurrency` at a time. A stream whose
+/// fetch fails keeps its previous snapshot; one deleted since the listing
+/// is dropped. The refreshed sizes are then checked against `storage`'s
+/// thresholds, and the message counts against `anomalies`' baselines.
 async fn refresh_stats_impl(
     iggy_client: &IggyClientWrapper,
-    stats_cache: &Arc<RwLock<CachedStats>>,
-) -> Result<(), crate::error::AppError> {
-    let stats = compute_stats_from_client(iggy_client).await?;
+    stats_cache: &Arc<RwLock<StatsCache>>,
+    storage: &StorageAlarm,
+    anomalies: &ThroughputAnomalies,
+    concurrency: usize,
+) -> Result<(), AppError> {
+    let refreshed_at = Instant::now();
+    let streams = iggy_client.list_streams().await?;
+    let totals = totals_from_streams(&streams)?;
+
+    let mut snapshots = HashMap::with_capacity(streams.len());
+    let mut outdated = Vec::new();
+    {
+        let cache = stats_cache.read().await;
+        for stream in &streams {
+            match cache.streams.get(&stream.name) {
+                Some(snapshot) if !snapshot.needs_fetch(stream) => {
+                    let mut snapshot = snapshot.clone();
+                    snapshot.checked_at = Instant::now();
+                    snapshots.insert(stream.name.clone(), snapshot);
+                }
+                previous => outdated.push((stream.name.clone(), previous.cloned())),
+            }
+        }
+    }
 
-    let mut cache = stats_cache.write().await;
-    *cache = stats;
-    trace!("Stats cache refreshed successfully");
+    let fetched = outdated.len();
+    let results: Vec<_> = futures_util::stream::iter(outdated)
+        .map(|(name, previous)| async move {
+            let result = iggy_client.get_stream(&name).await;
+            (name, previous, result)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    for (name, previous, result) in results {
+        match result {
+            Ok(details) => {
+                let snapshot = CachedStreamStats::new(stream_stats_from_details(&details));
+                snapshots.insert(name, snapshot);
+            }
+            Err(AppError::NotFound(_)) => {}
+            Err(e) => {
+                warn!(stream = %name, error = %e, "Failed to refresh stream stats");
+                if let Some(previous) = previous {
+                    snapshots.insert(name, previous);
+                }
+            }
+        }
+    }
+
+    storage.evaluate(
+        totals.total_size_bytes,
+        snapshots.values().flat_map(|snapshot| {
+            let stream = snapshot.stats.stream.as_str();
+            snapshot
+                .stats
+                .topics
+                .iter()
+                .map(move |topic| (stream, topic.name.as_str(), topic.size_bytes))
+        }),
+    );
+    // A snapshot kept after a failed fetch has outdated counts
+    anomalies.evaluate(snapshots.values().flat_map(|snapshot| {
+        let stream = snapshot.stats.stream.as_str();
+        let current = snapshot.checked_at >= refreshed_at;
+        snapshot.stats.topics.iter().map(move |topic| {
+            let count = current.then_some(topic.messages_count);
+            (stream, topic.name.as_str(), count)
+        })
+    }));
+    *stats_cache.write().await = StatsCache {
+        totals,
+        streams: snapshots,
+    };
+    trace!(
+        streams = streams.len(),
+        fetched, "Stats cache refreshed successfully"
+    );
 
     Ok(())
 }
 
-/// Compute statistics from an Iggy client.
+/// Sample each consumer's lag and export it as `iggy_consumer_lag` gauges.
 ///
-/// This is the shared implementation used by both `AppState::compute_stats()`
-/// and the background refresh task.
-async fn compute_stats_from_client(
-    iggy_client: &IggyClientWrapper,
-) -> Result<CachedStats, crate::error::AppError> {
-    let streams = iggy_client.list_streams().await?;
+/// Failures are per consumer: one failed sample is logged and does not skip
+/// the remaining IDs.
+async fn export_consumer_lag(
+    consumer: &ConsumerService,
+    stream: &str,
+    topic: &str,
+    consumer_ids: &[u32],
+) {
+    for &consumer_id in consumer_ids {
+        match consumer.consumer_lag(stream, topic, consumer_id).await {
+            Ok(lag) => {
+                for partition in &lag.partitions {
+                    crate::metrics::set_consumer_lag(
+                        stream,
+                        topic,
+                        consumer_id,
+                        partition.partition_id,
+                        partition.lag,
+                    );
+                }
+                trace!(
+                    consumer_id,
+                    total_lag = lag.total_lag,
+                    "Consumer lag sampled"
+                );
+            }
+            Err(e) => warn!(consumer_id, error = %e, "Consumer lag sample failed"),
+        }
+    }
+}
 
+/// Delete an idle consumer's committed offset on each partition it
+/// committed on. Failures are per partition and only logged.
+///
+/// Offsets are shared by every replica, while the registry only knows the
+/// polls through this one. A partition is skipped unless Iggy still stores
+/// the offset last committed here, at least the TTL ago: one that moved was
+/// committed since, through another replica, by a consumer that is not idle.
+async fn release_idle_consumer(iggy_client: &IggyClientWrapper, idle: &IdleConsumer) {
+    let (stream, topic, consumer_id) = (&idle.stream, &idle.topic, idle.consumer_id);
+    for &(partition_id, committed) in &idle.partitions {
+        match iggy_client
+            .get_consumer_offset(stream, topic, consumer_id, partition_id)
+            .await
+        {
+            Ok(Some(stored)) if stored == committed => {}
+            Ok(None) => continue,
+            Ok(Some(stored)) => {
+                info!(
+                    stream = %stream,
+                    topic = %topic,
+                    consumer_id,
+                    partition_id,
+                    committed,
+                    stored,
+                    "Idle consumer offset moved elsewhere, keeping it"
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    stream = %stream,
+                    topic = %topic,
+                    consumer_id,
+                    partition_id,
+                    error = %e,
+                    "Failed to read idle consumer offset, keeping it"
+                );
+                continue;
+            }
+        }
+        if let Err(e) = iggy_client
+            .delete_consumer_offset(stream, topic, consumer_id, partition_id)
+            .await
+        {
+            warn!(
+                stream = %stream,
+                topic = %topic,
+                consumer_id,
+                partition_id,
+                error = %e,
+                "Failed to delete idle consumer offset"
+            );
+        }
+    }
+    info!(
+        stream = %stream,
+        topic = %topic,
+        consumer_id,
+        "Idle consumer released"
+    );
+}
+
+/// Run one canary probe and record its outcome.
+///
+/// `topic_ready` tracks whether the canary topic has been ensured; a failure
+/// to create it counts as a failed probe.
+async fn run_canary_probe(canary: &CanaryService, topic_ready: &mut bool) {
+    if !*topic_ready {
+        match canary.ensure_topic().await {
+            Ok(()) => *topic_ready = true,
+            Err(e) => {
+                crate::metrics::record_canary_failure();
+                warn!(error = %e, "Canary topic setup failed");
+                return;
+            }
+        }
+    }
+
+    match canary.probe().await {
+        Ok(rtt) => crate::metrics::record_canary_rtt(rtt.as_secs_f64()),
+        Err(e) => {
+            crate::metrics::record_canary_failure();
+            warn!(error = %e, "Canary probe failed");
+        }
+    }
+}
+
+/// Open the outage spool in `SPOOL_DIR`. A spool that cannot be opened is
+/// logged and sends fail during outages, as without one.
+fn open_spool(config: &Config) -> Spool {
+    let Some(dir) = &config.spool_dir else {
+        return Spool::disabled();
+    };
+    Spool::open(dir, config.spool_max_bytes).unwrap_or_else(|e| {
+        error!(error = %e, "Outage spool disabled");
+        Spool::disabled()
+    })
+}
+
+/// Build a [`StreamStatsResponse`] from SDK stream details.
+///
+/// Topics are sorted by name so the response is stable regardless of the
+/// order the server returns them in.
+fn stream_stats_from_details(details: &StreamDetails) -> StreamStatsResponse {
+    let mut topics: Vec<StreamTopicStats> = details
+        .topics
+        .iter()
+        .map(|t| StreamTopicStats {
+            name: t.name.clone(),
+            partitions_count: t.partitions_count,
+            messages_count: t.messages_count,
+            size_bytes: t.size.as_bytes_u64(),
+        })
+        .collect();
+    topics.sort_by(|a, b| a.name.cmp(&b.name));
+
+    StreamStatsResponse {
+        stream: details.name.clone(),
+        topics_count: details.topics_count,
+        messages_count: details.messages_count,
+        size_bytes: details.size.as_bytes_u64(),
+        topics,
+        cache_age_seconds: 0,
+    }
+}
+
+/// Build a [`TopicStatsResponse`] from SDK topic details.
+///
+/// Partitions are sorted by ID so the response is stable regardless of the
+/// order the server returns them in.
+fn topic_stats_from_details(
+    stream: &str,
+    topic: &str,
+    details: &TopicDetails,
+) -> TopicStatsResponse {
+    let mut partitions: Vec<PartitionStats> = details
+        .partitions
+        .iter()
+        .map(|p| PartitionStats {
+            id: p.id,
+            messages_count: p.messages_count,
+            size_bytes: p.size.as_bytes_u64(),
+            current_offset: p.current_offset,
+            segments_count: p.segments_count,
+        })
+        .collect();
+    partitions.sort_by_key(|p| p.id);
+
+    TopicStatsResponse {
+        stream: stream.to_string(),
+        topic: topic.to_string(),
+        partitions_count: details.partitions_count,
+        messages_count: details.messages_count,
+        size_bytes: details.size.as_bytes_u64(),
+        partitions,
+        cache_age_seconds: 0,
+    }
+}
+
+/// Totals across `streams`, as listed by the server.
+fn totals_from_streams(streams: &[Stream]) -> AppResult<CachedStats> {
     let mut topics_count = 0u32;
     let mut total_messages = 0u64;
     let mut total_size_bytes = 0u64;
 
-    for stream in &streams {
+    for stream in streams {
         topics_count += stream.topics_count;
         total_messages += stream.messages_count;
         total_size_bytes += stream.size.as_bytes_u64();
@@ -367,7 +1878,7 @@ async fn compute_stats_from_client(
 
     // Use try_into to safely convert stream count, avoiding silent truncation
     let streams_count: u32 = streams.len().try_into().map_err(|_| {
-        crate::error::AppError::Internal(format!("Stream count {} exceeds u32::MAX", streams.len()))
+        AppError::Internal(format!("Stream count {} exceeds u32::MAX", streams.len()))
     })?;
 
     Ok(CachedStats {
@@ -378,3 +1889,136 @@ async fn compute_stats_from_client(
         last_updated: Some(Instant::now()),
     })
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use iggy::prelude::{IggyByteSize, IggyTimestamp, Partitioning};
+
+    use super::*;
+    use crate::models::{Event, EventPayload};
+
+    fn topic_entry(age: Duration) -> CachedTopicStats {
+        CachedTopicStats {
+            stats: TopicStatsResponse {
+                stream: "s".to_string(),
+                topic: "t".to_string(),
+                partitions_count: 0,
+                messages_count: 0,
+                size_bytes: 0,
+                partitions: Vec::new(),
+                cache_age_seconds: 0,
+            },
+            last_updated: Instant::now() - age,
+        }
+    }
+
+    fn stream_entry(age: Duration) -> CachedStreamStats {
+        CachedStreamStats {
+            stats: StreamStatsResponse {
+                stream: "s".to_string(),
+                topics_count: 1,
+                messages_count: 10,
+                size_bytes: 100,
+                topics: Vec::new(),
+                cache_age_seconds: 0,
+            },
+            fetched_at: Instant::now() - age,
+            checked_at: Instant::now(),
+        }
+    }
+
+    fn stream_summary(messages_count: u64) -> Stream {
+        Stream {
+            id: 1,
+            created_at: IggyTimestamp::now(),
+            name: "s".to_string(),
+            size: IggyByteSize::from(100),
+            messages_count,
+            topics_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_stream_snapshot_is_fetched_only_when_changed_or_old() {
+        assert!(!stream_entry(Duration::ZERO).needs_fetch(&stream_summary(10)));
+        assert!(stream_entry(Duration::ZERO).needs_fetch(&stream_summary(11)));
+        assert!(
+            stream_entry(MAX_STREAM_SNAPSHOT_AGE + Duration::from_secs(1))
+                .needs_fetch(&stream_summary(10))
+        );
+    }
+
+    #[test]
+    fn test_totals_sum_the_listed_streams() {
+        let totals = totals_from_streams(&[stream_summary(10), stream_summary(5)]).unwrap();
+        assert_eq!(totals.streams_count, 2);
+        assert_eq!(totals.topics_count, 2);
+        assert_eq!(totals.total_messages, 15);
+        assert_eq!(totals.total_size_bytes, 200);
+        assert!(totals.last_updated.is_some());
+    }
+
+    #[test]
+    fn test_topic_stats_entry_staleness_follows_ttl() {
+        let ttl = Duration::from_secs(5);
+        assert!(!topic_entry(Duration::ZERO).is_stale(ttl));
+        assert!(topic_entry(Duration::from_secs(6)).is_stale(ttl));
+    }
+
+    #[tokio::test]
+    async fn test_idle_consumer_keeps_offsets_moved_elsewhere() {
+        let config = Config {
+            broker_backend: crate::iggy_client::BrokerBackend::Memory,
+            ..Config::default()
+        };
+        let client = IggyClientWrapper::new(config).await.unwrap();
+        client.ensure_stream("s").await.unwrap();
+        client.ensure_topic("s", "t", 2).await.unwrap();
+        let events: Vec<_> = (0..10)
+            .map(|n| {
+                Event::new(
+                    "test.event",
+                    EventPayload::Generic(serde_json::json!({ "n": n })),
+                )
+            })
+            .collect();
+        for partition_id in [0, 1] {
+            client
+                .send_events_batch_partitioned(
+                    "s",
+                    "t",
+                    &events,
+                    &Partitioning::partition_id(partition_id),
+                )
+                .await
+                .unwrap();
+        }
+        // Committed 3 on both partitions here; partition 1 moved on since,
+        // through another replica
+        client
+            .store_consumer_offset("s", "t", 7, 0, 3)
+            .await
+            .unwrap();
+        client
+            .store_consumer_offset("s", "t", 7, 1, 5)
+            .await
+            .unwrap();
+
+        let idle = IdleConsumer {
+            stream: "s".to_string(),
+            topic: "t".to_string(),
+            consumer_id: 7,
+            partitions: vec![(0, 3), (1, 3)],
+        };
+        release_idle_consumer(&client, &idle).await;
+        assert_eq!(
+            client.get_consumer_offset("s", "t", 7, 0).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            client.get_consumer_offset("s", "t", 7, 1).await.unwrap(),
+            Some(5)
+        );
+    }
+}
@@ -1,6 +1,50 @@
+//! Small helpers shared across the crate: shutdown signals, hex encoding,
+//! the Unix clock and percentiles.
+
+use std::fmt::Write as _;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use tokio::signal;
 use tracing::{error, warn};
 
+/// Lowercase hex encoding of `bytes`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        })
+}
+
+/// Decode hex text produced by [`hex`]; `None` on odd length or a non-hex digit.
+pub fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Seconds since the Unix epoch (0 if the clock is before it).
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Nearest-rank percentile `p` (0 to 1) of `sorted`, or the default for an
+/// empty slice.
+pub fn percentile<T: Copy + Default>(sorted: &[T], p: f64) -> T {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
 /// Wait for a shutdown signal (Ctrl+C or SIGTERM).
 ///
 /// # Panics
@@ -43,3 +87,27 @@ pub async fn shutdown_signal() {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trips() {
+        assert_eq!(hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(unhex("00ab7f").unwrap(), vec![0x00, 0xab, 0x7f]);
+        assert_eq!(unhex("abc"), None);
+        assert_eq!(unhex("zz"), None);
+    }
+
+    #[test]
+    fn test_percentile_uses_nearest_rank() {
+        let sorted = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(percentile(&sorted, 0.0), 1);
+        assert_eq!(percentile(&sorted, 0.5), 5);
+        assert_eq!(percentile(&sorted, 0.91), 10);
+        assert_eq!(percentile(&sorted, 1.0), 10);
+        assert_eq!(percentile::<u64>(&[], 0.5), 0);
+    }
+}
@@ -1,3 +1,6 @@
+use regex::Regex;
+
+use crate::config::Config;
 use crate::error::{AppError, AppResult};
 
 // =============================================================================
@@ -34,6 +37,15 @@ pub const MIN_PARTITIONS: u32 = 1;
 /// This value (1 billion) is high enough for any realistic use case.
 pub const MAX_CONSUMER_ID: u32 = 1_000_000_000;
 
+/// Minimum password length for Iggy users.
+///
+/// Matches the Iggy server's own limits, so violations surface as 400
+/// instead of an opaque server error.
+pub const MIN_PASSWORD_LENGTH: usize = 3;
+
+/// Maximum password length for Iggy users.
+pub const MAX_PASSWORD_LENGTH: usize = 100;
+
 /// Validate a resource name (stream or topic).
 ///
 /// Rules:
@@ -99,6 +111,120 @@ pub fn validate_resource_name(name: &str, resource_type: &str) -> AppResult<()>
     Ok(())
 }
 
+/// A configured name pattern; the whole name must match.
+#[derive(Debug, Clone)]
+struct NamePattern {
+    /// Pattern as configured, for error messages
+    source: String,
+    /// `source` anchored at both ends
+    regex: Regex,
+}
+
+/// Site naming rules for streams and topics, checked on top of
+/// [`validate_resource_name`] wherever a stream or topic is created.
+///
+/// Built from `STREAM_NAME_PATTERN`, `TOPIC_NAME_PATTERN` and
+/// `RESERVED_NAME_PREFIXES`; the default policy allows every valid name.
+///
+/// Topics the service creates for itself (`_scheduled`, `_audit`, ...) are
+/// not checked; their leading `_` already keeps clients from creating them.
+#[derive(Debug, Clone, Default)]
+pub struct NamingPolicy {
+    stream_pattern: Option<NamePattern>,
+    topic_pattern: Option<NamePattern>,
+    reserved_prefixes: Vec<String>,
+}
+
+impl NamingPolicy {
+    /// Compile a policy. Patterns must match the whole name, so `[a-z-]+`
+    /// behaves like `^[a-z-]+$`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ConfigError` naming the variable whose pattern
+    /// does not compile.
+    pub fn new(
+        stream_pattern: Option<&str>,
+        topic_pattern: Option<&str>,
+        reserved_prefixes: Vec<String>,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            stream_pattern: compile_pattern(stream_pattern, "STREAM_NAME_PATTERN")?,
+            topic_pattern: compile_pattern(topic_pattern, "TOPIC_NAME_PATTERN")?,
+            reserved_prefixes,
+        })
+    }
+
+    /// Check a stream name against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` naming the violated rule.
+    pub fn check_stream(&self, name: &str) -> AppResult<()> {
+        self.check(
+            name,
+            "Stream",
+            self.stream_pattern.as_ref(),
+            "STREAM_NAME_PATTERN",
+        )
+    }
+
+    /// Check a topic name against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::BadRequest` naming the violated rule.
+    pub fn check_topic(&self, name: &str) -> AppResult<()> {
+        self.check(
+            name,
+            "Topic",
+            self.topic_pattern.as_ref(),
+            "TOPIC_NAME_PATTERN",
+        )
+    }
+
+    fn check(
+        &self,
+        name: &str,
+        resource_type: &str,
+        pattern: Option<&NamePattern>,
+        variable: &str,
+    ) -> AppResult<()> {
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|prefix| name.starts_with(prefix.as_str()))
+        {
+            return Err(AppError::BadRequest(format!(
+                "{resource_type} name '{name}' uses reserved prefix '{prefix}' \
+                 (RESERVED_NAME_PREFIXES)"
+            )));
+        }
+        if let Some(pattern) = pattern
+            && !pattern.regex.is_match(name)
+        {
+            return Err(AppError::BadRequest(format!(
+                "{resource_type} name '{name}' does not match {variable} '{}'",
+                pattern.source
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn compile_pattern(pattern: Option<&str>, variable: &str) -> AppResult<Option<NamePattern>> {
+    pattern
+        .map(|source| {
+            Regex::new(&format!("^(?:{source})$"))
+                .map(|regex| NamePattern {
+                    source: source.to_string(),
+                    regex,
+                })
+                .map_err(|e| AppError::ConfigError(format!("Invalid {variable}: {e}")))
+        })
+        .transpose()
+}
+
 /// Validate partition count for a topic.
 pub fn validate_partition_count(partitions: u32, resource_type: &str) -> AppResult<()> {
     if partitions < MIN_PARTITIONS {
@@ -192,11 +318,133 @@ pub fn validate_poll_count(count: u32) -> AppResult<()> {
     Ok(())
 }
 
+/// Validate a poll's requested event schema version.
+///
+/// Schema versions start at 1, so `target_version=0` cannot be reached.
+pub fn validate_target_version(target_version: Option<u32>) -> AppResult<()> {
+    if target_version == Some(0) {
+        return Err(AppError::BadRequest(
+            "target_version must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check if a stream (`topic: None`) or topic is one the service keeps
+/// for itself, which API users may only write to or delete with the admin
+/// key.
+///
+/// System resources are:
+/// - any topic starting with `_` (e.g. `_scheduled`, `_audit`)
+/// - the `DLQ_TOPIC` and, when set, `NACK_RETRY_TOPIC` of any stream
+/// - `SCHEDULED_TOPIC`, `AUDIT_TOPIC`, `STATE_TOPIC`,
+///   `LEADER_ELECTION_TOPIC` and, with the canary enabled, `CANARY_TOPIC`
+///   in the default stream
+/// - the default stream itself, which holds those topics
+pub fn is_system_resource(config: &Config, stream: &str, topic: Option<&str>) -> bool {
+    let in_default_stream = stream == config.default_stream;
+    let Some(topic) = topic else {
+        return in_default_stream;
+    };
+    topic.starts_with('_')
+        || topic == config.dlq_topic
+        || config.nack_retry_topic.as_deref() == Some(topic)
+        || (in_default_stream
+            && (topic == config.scheduled_topic
+                || topic == config.audit_topic
+                || topic == config.state_topic
+                || topic == config.leader_election_topic
+                || (config.canary_enabled() && topic == config.canary_topic)))
+}
+
+/// Validate an Iggy user password.
+///
+/// Only the length is checked; the password itself is never echoed in the
+/// error message.
+pub fn validate_password(password: &str) -> AppResult<()> {
+    let len = password.chars().count();
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&len) {
+        return Err(AppError::BadRequest(format!(
+            "Password must be between {} and {} characters",
+            MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_system_resource() {
+        let config = Config::default();
+        let stream = config.default_stream.clone();
+
+        assert!(is_system_resource(&config, &stream, None));
+        assert!(!is_system_resource(&config, "orders", None));
+
+        assert!(is_system_resource(&config, &stream, Some("_audit")));
+        assert!(is_system_resource(&config, "orders", Some("_internal")));
+        assert!(is_system_resource(&config, "orders", Some("dlq")));
+        assert!(!is_system_resource(&config, &stream, Some("events")));
+
+        // The canary topic is only reserved while the canary runs
+        assert!(!is_system_resource(&config, &stream, Some("canary")));
+        let config = Config {
+            canary_interval: std::time::Duration::from_secs(30),
+            ..config
+        };
+        assert!(is_system_resource(&config, &stream, Some("canary")));
+        assert!(!is_system_resource(&config, "orders", Some("canary")));
+
+        // State and lease topics are reserved whatever they are named
+        let config = Config {
+            state_topic: "state".to_string(),
+            leader_election_topic: "leader".to_string(),
+            ..config
+        };
+        assert!(is_system_resource(&config, &stream, Some("state")));
+        assert!(is_system_resource(&config, &stream, Some("leader")));
+        assert!(!is_system_resource(&config, "orders", Some("leader")));
+
+        // So is the nack retry topic, in every stream, once configured
+        assert!(!is_system_resource(&config, "orders", Some("orders-retry")));
+        let config = Config {
+            nack_retry_topic: Some("orders-retry".to_string()),
+            ..config
+        };
+        assert!(is_system_resource(&config, "orders", Some("orders-retry")));
+    }
+
+    #[test]
+    fn test_naming_policy() {
+        let policy = NamingPolicy::new(
+            Some("[a-z]+(-[a-z]+)*"),
+            None,
+            vec!["internal-".to_string()],
+        )
+        .unwrap();
+
+        assert!(policy.check_stream("orders-eu").is_ok());
+        let err = policy.check_stream("Orders").unwrap_err().to_string();
+        assert!(err.contains("STREAM_NAME_PATTERN '[a-z]+(-[a-z]+)*'"));
+        // Anchored: a matching substring is not enough
+        assert!(policy.check_stream("orders.v2").is_err());
+
+        assert!(policy.check_topic("Events.V2").is_ok());
+        let err = policy
+            .check_topic("internal-metrics")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("reserved prefix 'internal-'"));
+
+        assert!(NamingPolicy::default().check_stream("Anything").is_ok());
+        let err = NamingPolicy::new(None, Some("("), vec![]).unwrap_err();
+        assert!(matches!(err, AppError::ConfigError(m) if m.contains("TOPIC_NAME_PATTERN")));
+    }
+
     #[test]
     fn test_poll_count_zero_rejected() {
         let result = validate_poll_count(0);
@@ -211,6 +459,16 @@ mod tests {
         assert!(validate_poll_count(u32::MAX).is_ok());
     }
 
+    #[test]
+    fn test_target_version() {
+        assert!(validate_target_version(None).is_ok());
+        assert!(validate_target_version(Some(1)).is_ok());
+        assert!(matches!(
+            validate_target_version(Some(0)),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
     #[test]
     fn test_valid_names() {
         assert!(validate_resource_name("my-stream", "Stream").is_ok());
@@ -392,4 +650,16 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("exceeds maximum"));
     }
+
+    #[test]
+    fn test_password_length_bounds() {
+        assert!(validate_password("abc").is_ok());
+        assert!(validate_password(&"a".repeat(MAX_PASSWORD_LENGTH)).is_ok());
+        assert!(matches!(
+            validate_password("ab"),
+            Err(AppError::BadRequest(_))
+        ));
+        let result = validate_password(&"a".repeat(MAX_PASSWORD_LENGTH + 1));
+        assert!(result.unwrap_err().to_string().contains("between"));
+    }
 }
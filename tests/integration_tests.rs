@@ -141,6 +141,10 @@ impl TestFixture {
     async fn start_server(port: u16, iggy_connection_string: &str) -> Result<(), String> {
         use std::time::Duration;
 
+        use iggy_sample::iggy_client::{BrokerBackend, PayloadCompression};
+        use iggy_sample::middleware::RateLimitMode;
+        use iggy_sample::models::{BatchCompensation, KeyHashing};
+        use iggy_sample::services::StateBackend;
         use iggy_sample::{AppState, Config, IggyClientWrapper, build_router};
         use tokio::net::TcpListener;
 
@@ -148,37 +152,167 @@ impl TestFixture {
             // Server configuration
             host: "127.0.0.1".to_string(),
             port,
+            admin_host: "127.0.0.1".to_string(),
+            admin_port: 0,
+            enable_produce_api: true,
+            enable_consume_api: true,
+            enable_admin_api: true,
             // Iggy connection configuration
             iggy_connection_string: iggy_connection_string.to_string(),
+            broker_backend: BrokerBackend::Iggy,
             default_stream: "test-stream".to_string(),
             default_topic: "test-events".to_string(),
             topic_partitions: 2,
+            iggy_fallback_servers: Vec::new(),
+            iggy_failover_attempts: 3,
+            iggy_read_connection_string: None,
+            iggy_tls_enabled: false,
+            iggy_tls_ca_path: None,
+            iggy_tls_domain: None,
+            iggy_username: None,
+            iggy_password: None,
+            iggy_password_file: None,
             // Connection resilience (relaxed for tests)
             max_reconnect_attempts: 3,
             reconnect_base_delay: Duration::from_millis(100),
             reconnect_max_delay: Duration::from_secs(1),
             health_check_interval: Duration::from_secs(30),
+            health_check_timeout: Duration::from_secs(2),
             operation_timeout: Duration::from_secs(30),
             // Circuit breaker (default settings for tests)
             circuit_breaker_failure_threshold: 5,
+            circuit_breaker_send_failure_threshold: 0,
+            circuit_breaker_poll_failure_threshold: 0,
+            circuit_breaker_admin_failure_threshold: 0,
+            retry_budget_per_sec: 0,
             circuit_breaker_success_threshold: 2,
             circuit_breaker_open_duration: Duration::from_secs(30),
+            fair_queue_max_in_flight: 0,
+            fair_queue_tenant_max_in_flight: 0,
+            fair_queue_weights: Default::default(),
+            fair_queue_starvation_threshold: Duration::from_secs(1),
             // Rate limiting (disabled for tests)
             rate_limit_rps: 0,
             rate_limit_burst: 50,
+            rate_limit_read_rps: 0,
+            rate_limit_write_rps: 0,
+            rate_limit_admin_rps: 0,
+            rate_limit_mode: RateLimitMode::Reject,
+            queue_max_wait: Duration::from_millis(500),
+            adaptive_rate_limit_percent: 0,
+            max_in_flight_requests: 0,
             // Message limits
             batch_max_size: 1000,
             poll_max_count: 100,
+            poll_stream_threshold: 0,
+            sticky_partition_window: Duration::from_secs(10),
+            partition_key_hashing: KeyHashing::Server,
+            coalesce_window: Duration::ZERO,
+            coalesce_max_batch: 100,
+            batch_compression: PayloadCompression::None,
+            compression_threshold_bytes: 1024,
+            key_sequencing: false,
+            atomic_batch_chunk_size: 100,
+            atomic_batch_compensation: BatchCompensation::Tombstone,
             max_request_body_size: 10 * 1024 * 1024, // 10MB
             // Security (disabled for tests)
             api_key: None,
+            admin_api_key: None,
+            request_signing_secret: None,
+            signature_max_age: Duration::from_secs(300),
+            signature_max_seen: 100_000,
             auth_bypass_paths: vec!["/health".to_string(), "/ready".to_string()],
             cors_allowed_origins: vec!["*".to_string()],
+            cors_admin_allowed_origins: None,
+            cors_allow_credentials: false,
+            cors_allowed_methods: vec![],
+            cors_allowed_headers: vec![],
             trusted_proxies: vec![], // Empty = trust all (test mode)
+            ip_allowlist: vec![],
+            ip_denylist: vec![],
+            ip_filter_paths: vec![],
             // Observability
             log_level: "warn".to_string(),
             stats_cache_ttl: Duration::from_secs(5),
+            stats_refresh_concurrency: 4,
+            cache_max_age: Duration::ZERO,
             metrics_port: 0, // Disabled for tests
+            lag_monitor_consumer_ids: vec![],
+            lag_monitor_interval: Duration::from_secs(15),
+            canary_interval: Duration::ZERO,
+            canary_topic: "canary".to_string(),
+            canary_timeout: Duration::from_secs(10),
+            slow_request_threshold: Duration::ZERO,
+            poll_continuation_secret: None,
+            poll_continuation_ttl: Duration::from_secs(3600),
+            poll_dedup_window: Duration::ZERO,
+            poll_dedup_max_ids: 100_000,
+            consumer_idle_ttl: Duration::ZERO,
+            max_redeliveries: 5,
+            nack_retry_topic: None,
+            dlq_topic: "dlq".to_string(),
+            scheduled_topic: "_scheduled".to_string(),
+            scheduled_max_pending: 10_000,
+            max_schedules: 100,
+            max_pipelines: 10,
+            pipeline_interval: Duration::from_secs(1),
+            pipeline_batch_size: 100,
+            wasm_fuel_per_event: 10_000_000,
+            wasm_max_memory_bytes: 16 * 1024 * 1024,
+            wasm_max_module_bytes: 8 * 1024 * 1024,
+            expression_timeout: Duration::from_millis(10),
+            expression_max_operations: 100_000,
+            expression_poll_budget: Duration::from_millis(100),
+            event_enrichment: false,
+            service_name: "iggy-sample".to_string(),
+            audit_enabled: true,
+            audit_topic: "_audit".to_string(),
+            top_talkers_limit: 10,
+            top_talkers_window: Duration::from_secs(300),
+            message_index_ttl: Duration::ZERO,
+            message_index_interval: Duration::from_secs(5),
+            message_index_max_entries: 100_000,
+            search_max_scan: 10_000,
+            search_timeout: Duration::from_secs(5),
+            event_counts_topic: None,
+            event_counts_interval: Duration::from_secs(5),
+            ordering_check_topic: None,
+            ordering_check_interval: Duration::from_secs(5),
+            ordering_check_window: Duration::from_secs(3600),
+            max_total_size_bytes: 0,
+            max_topic_size_bytes: 0,
+            storage_reject_produces: false,
+            throughput_anomaly_threshold: 0.0,
+            throughput_anomaly_alpha: 0.2,
+            replication_connection_string: None,
+            replication_topics: Vec::new(),
+            replication_origin: None,
+            replication_consumer_id: 1,
+            replication_interval: Duration::from_secs(1),
+            replication_batch_size: 100,
+            state_backend: StateBackend::Memory,
+            state_topic: "_state".to_string(),
+            state_sqlite_path: "gateway-state.db".to_string(),
+            leader_election_lease: Duration::ZERO,
+            leader_election_topic: "_leader".to_string(),
+            leader_election_id: None,
+            bootstrap: None,
+            retention_interval: Duration::from_secs(300),
+            naming_policy: Default::default(),
+            notify_webhook_url: None,
+            spool_dir: None,
+            spool_max_bytes: 1024 * 1024 * 1024,
+            outbox_capacity: 0,
+            outbox_overflow: Default::default(),
+            shadow_rules: Vec::new(),
+            fanout_groups: Vec::new(),
+            fanout_max_destinations: 16,
+            benchmark_max_duration: Duration::ZERO,
+            benchmark_topic: "benchmark".to_string(),
+            leak_check_interval: Duration::ZERO,
+            leak_check_window: 10,
+            idempotency_ttl: Duration::from_secs(300),
+            idempotency_max_keys: 10_000,
         };
 
         let iggy_client = IggyClientWrapper::new(config.clone())
@@ -289,6 +423,16 @@ async fn test_health_endpoint() {
             .and_then(|v| v.as_bool())
             .expect("iggy_connected missing")
     );
+    let breakers = body
+        .get("circuit_breakers")
+        .expect("circuit_breakers missing");
+    for class in ["send", "poll", "admin"] {
+        assert_eq!(
+            breakers.get(class).and_then(|v| v.as_str()),
+            Some("closed"),
+            "{class} breaker"
+        );
+    }
     assert!(body.get("version").is_some());
     assert!(body.get("timestamp").is_some());
 }
@@ -557,6 +701,78 @@ async fn test_wrapper_defaults_idempotent_and_health_check_live() {
     assert!(wrapper.is_connected());
 }
 
+/// Injected failures drive reconnect-and-retry, timeouts and the circuit
+/// breaker against a live server, without stopping the container.
+///
+/// Run with: `cargo test --features test-util --test integration_tests`
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn test_injected_failures_drive_retry_timeout_and_breaker() {
+    use std::sync::Arc;
+
+    use iggy_sample::iggy_client::{CircuitState, FailurePolicy, OperationClass};
+    use iggy_sample::{AppError, Config, IggyClientWrapper};
+
+    let (_container, iggy) = IggyContainer::start().await;
+
+    let config = Config {
+        iggy_connection_string: iggy.connection_string(),
+        default_stream: "failure-stream".to_string(),
+        default_topic: "failure-events".to_string(),
+        operation_timeout: Duration::from_secs(2),
+        reconnect_base_delay: Duration::from_millis(100),
+        circuit_breaker_failure_threshold: 2,
+        metrics_port: 0,
+        ..Config::default()
+    };
+    let policy = Arc::new(FailurePolicy::new());
+    let wrapper = IggyClientWrapper::new(config)
+        .await
+        .expect("wrapper should connect")
+        .with_failure_policy(policy.clone());
+    wrapper
+        .initialize_defaults()
+        .await
+        .expect("initialize_defaults");
+
+    // A connection error reconnects, and the retry succeeds
+    let first = policy.operations() + 1;
+    policy.fail_nth(first, AppError::Disconnected("injected".to_string()));
+    wrapper
+        .list_streams()
+        .await
+        .expect("retry after reconnect should succeed");
+    assert_eq!(
+        policy.operations(),
+        first + 1,
+        "one failed attempt, one retry"
+    );
+
+    // Hangs time out at the global deadline and open the breaker
+    let next = policy.operations() + 1;
+    policy.hang_nth(next);
+    policy.hang_nth(next + 1);
+    for _ in 0..2 {
+        let result = wrapper.list_streams().await;
+        assert!(
+            matches!(result, Err(AppError::OperationTimeout(_))),
+            "{result:?}"
+        );
+    }
+    assert_eq!(
+        wrapper.circuit_breaker_state(OperationClass::Admin).await,
+        CircuitState::Open
+    );
+    let rejected = wrapper.list_streams().await;
+    assert!(
+        matches!(
+            rejected.as_ref().map_err(AppError::kind),
+            Err(AppError::CircuitOpen(_))
+        ),
+        "{rejected:?}"
+    );
+}
+
 #[tokio::test]
 async fn test_send_and_poll_message() {
     let fixture = TestFixture::new().await;
@@ -949,6 +1165,37 @@ async fn test_create_topic_validation() {
     assert_eq!(response.status().as_u16(), 400);
 }
 
+#[tokio::test]
+async fn test_system_resources_require_admin_key() {
+    let fixture = TestFixture::new().await;
+
+    // Writing to the dead-letter topic
+    let response = fixture
+        .client
+        .post(fixture.url("/streams/test-stream/topics/dlq/messages"))
+        .json(&json!({
+            "event": {
+                "id": "550e8400-e29b-41d4-a716-446655440030",
+                "event_type": "order.created",
+                "timestamp": "2024-01-15T10:30:00Z",
+                "payload": {"type": "Generic", "data": {}}
+            }
+        }))
+        .send()
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    // Deleting the default stream
+    let response = fixture
+        .client
+        .delete(fixture.url("/streams/test-stream"))
+        .send()
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
 // ============================================================================
 // Message Flow Tests
 // ============================================================================
@@ -1343,42 +1590,176 @@ impl SecureTestFixture {
         iggy_connection_string: &str,
         api_key: &str,
     ) -> Result<(), String> {
+        use iggy_sample::iggy_client::{BrokerBackend, PayloadCompression};
+        use iggy_sample::middleware::RateLimitMode;
+        use iggy_sample::models::{BatchCompensation, KeyHashing};
+        use iggy_sample::services::StateBackend;
         use iggy_sample::{AppState, Config, IggyClientWrapper, build_router};
         use tokio::net::TcpListener;
 
         let config = Config {
             host: "127.0.0.1".to_string(),
             port,
+            admin_host: "127.0.0.1".to_string(),
+            admin_port: 0,
+            enable_produce_api: true,
+            enable_consume_api: true,
+            enable_admin_api: true,
             iggy_connection_string: iggy_connection_string.to_string(),
+            broker_backend: BrokerBackend::Iggy,
             default_stream: "secure-test-stream".to_string(),
             default_topic: "secure-test-events".to_string(),
             topic_partitions: 2,
+            iggy_fallback_servers: Vec::new(),
+            iggy_failover_attempts: 3,
+            iggy_read_connection_string: None,
+            iggy_tls_enabled: false,
+            iggy_tls_ca_path: None,
+            iggy_tls_domain: None,
+            iggy_username: None,
+            iggy_password: None,
+            iggy_password_file: None,
             max_reconnect_attempts: 3,
             reconnect_base_delay: Duration::from_millis(100),
             reconnect_max_delay: Duration::from_secs(1),
             health_check_interval: Duration::from_secs(30),
+            health_check_timeout: Duration::from_secs(2),
             operation_timeout: Duration::from_secs(30),
             // Circuit breaker (default settings for tests)
             circuit_breaker_failure_threshold: 5,
+            circuit_breaker_send_failure_threshold: 0,
+            circuit_breaker_poll_failure_threshold: 0,
+            circuit_breaker_admin_failure_threshold: 0,
+            retry_budget_per_sec: 0,
             circuit_breaker_success_threshold: 2,
             circuit_breaker_open_duration: Duration::from_secs(30),
+            fair_queue_max_in_flight: 0,
+            fair_queue_tenant_max_in_flight: 0,
+            fair_queue_weights: Default::default(),
+            fair_queue_starvation_threshold: Duration::from_secs(1),
             // Rate limiting enabled - 5 RPS with burst of 2 for testing
             rate_limit_rps: 5,
             rate_limit_burst: 2,
+            rate_limit_read_rps: 0,
+            rate_limit_write_rps: 0,
+            rate_limit_admin_rps: 0,
+            rate_limit_mode: RateLimitMode::Reject,
+            queue_max_wait: Duration::from_millis(500),
+            adaptive_rate_limit_percent: 0,
+            max_in_flight_requests: 0,
             batch_max_size: 1000,
             poll_max_count: 100,
+            poll_stream_threshold: 0,
+            sticky_partition_window: Duration::from_secs(10),
+            partition_key_hashing: KeyHashing::Server,
+            coalesce_window: Duration::ZERO,
+            coalesce_max_batch: 100,
+            batch_compression: PayloadCompression::None,
+            compression_threshold_bytes: 1024,
+            key_sequencing: false,
+            atomic_batch_chunk_size: 100,
+            atomic_batch_compensation: BatchCompensation::Tombstone,
             max_request_body_size: 10 * 1024 * 1024,
             // API key authentication enabled
             api_key: Some(api_key.to_string()),
+            admin_api_key: None,
+            request_signing_secret: None,
+            signature_max_age: Duration::from_secs(300),
+            signature_max_seen: 100_000,
             auth_bypass_paths: vec!["/health".to_string(), "/ready".to_string()],
             cors_allowed_origins: vec!["*".to_string()],
+            cors_admin_allowed_origins: None,
+            cors_allow_credentials: false,
+            cors_allowed_methods: vec![],
+            cors_allowed_headers: vec![],
             // Trusted-proxy enforcement ON: the test client's peer address is
             // 127.0.0.1 (untrusted), so spoofed forwarded headers must be
             // ignored - this makes the enforcement path itself wire-tested.
             trusted_proxies: vec!["10.0.0.0/8".to_string()],
+            ip_allowlist: vec![],
+            ip_denylist: vec![],
+            ip_filter_paths: vec![],
             log_level: "warn".to_string(),
             stats_cache_ttl: Duration::from_secs(5),
+            stats_refresh_concurrency: 4,
+            cache_max_age: Duration::ZERO,
             metrics_port: 0, // Disabled for tests
+            lag_monitor_consumer_ids: vec![],
+            lag_monitor_interval: Duration::from_secs(15),
+            canary_interval: Duration::ZERO,
+            canary_topic: "canary".to_string(),
+            canary_timeout: Duration::from_secs(10),
+            slow_request_threshold: Duration::ZERO,
+            poll_continuation_secret: None,
+            poll_continuation_ttl: Duration::from_secs(3600),
+            poll_dedup_window: Duration::ZERO,
+            poll_dedup_max_ids: 100_000,
+            consumer_idle_ttl: Duration::ZERO,
+            max_redeliveries: 5,
+            nack_retry_topic: None,
+            dlq_topic: "dlq".to_string(),
+            scheduled_topic: "_scheduled".to_string(),
+            scheduled_max_pending: 10_000,
+            max_schedules: 100,
+            max_pipelines: 10,
+            pipeline_interval: Duration::from_secs(1),
+            pipeline_batch_size: 100,
+            wasm_fuel_per_event: 10_000_000,
+            wasm_max_memory_bytes: 16 * 1024 * 1024,
+            wasm_max_module_bytes: 8 * 1024 * 1024,
+            expression_timeout: Duration::from_millis(10),
+            expression_max_operations: 100_000,
+            expression_poll_budget: Duration::from_millis(100),
+            event_enrichment: false,
+            service_name: "iggy-sample".to_string(),
+            audit_enabled: true,
+            audit_topic: "_audit".to_string(),
+            top_talkers_limit: 10,
+            top_talkers_window: Duration::from_secs(300),
+            message_index_ttl: Duration::ZERO,
+            message_index_interval: Duration::from_secs(5),
+            message_index_max_entries: 100_000,
+            search_max_scan: 10_000,
+            search_timeout: Duration::from_secs(5),
+            event_counts_topic: None,
+            event_counts_interval: Duration::from_secs(5),
+            ordering_check_topic: None,
+            ordering_check_interval: Duration::from_secs(5),
+            ordering_check_window: Duration::from_secs(3600),
+            max_total_size_bytes: 0,
+            max_topic_size_bytes: 0,
+            storage_reject_produces: false,
+            throughput_anomaly_threshold: 0.0,
+            throughput_anomaly_alpha: 0.2,
+            replication_connection_string: None,
+            replication_topics: Vec::new(),
+            replication_origin: None,
+            replication_consumer_id: 1,
+            replication_interval: Duration::from_secs(1),
+            replication_batch_size: 100,
+            state_backend: StateBackend::Memory,
+            state_topic: "_state".to_string(),
+            state_sqlite_path: "gateway-state.db".to_string(),
+            leader_election_lease: Duration::ZERO,
+            leader_election_topic: "_leader".to_string(),
+            leader_election_id: None,
+            bootstrap: None,
+            retention_interval: Duration::from_secs(300),
+            naming_policy: Default::default(),
+            notify_webhook_url: None,
+            spool_dir: None,
+            spool_max_bytes: 1024 * 1024 * 1024,
+            outbox_capacity: 0,
+            outbox_overflow: Default::default(),
+            shadow_rules: Vec::new(),
+            fanout_groups: Vec::new(),
+            fanout_max_destinations: 16,
+            benchmark_max_duration: Duration::ZERO,
+            benchmark_topic: "benchmark".to_string(),
+            leak_check_interval: Duration::ZERO,
+            leak_check_window: 10,
+            idempotency_ttl: Duration::from_secs(300),
+            idempotency_max_keys: 10_000,
         };
 
         let iggy_client = IggyClientWrapper::new(config.clone())
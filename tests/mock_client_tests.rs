@@ -0,0 +1,341 @@
+//! `ProducerService` and the HTTP API against the in-process
+//! `MockIggyClient`.
+//!
+//! Covers the produce path without a server: sends, batches, partition
+//! checks, coalescing, atomic batches, and injected failures and timeouts;
+//! and the handlers on a mock-backed `AppState`.
+//!
+//! Run with: `cargo test --features test-util --test mock_client_tests`
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use iggy_sample::iggy_client::{MockIggyClient, MockOperation};
+use iggy_sample::models::{Event, EventPayload, PartitioningStrategy};
+use iggy_sample::services::ProducerService;
+use iggy_sample::{AppError, AppState, Config, build_router};
+use serde_json::{Value, json};
+
+fn event(event_type: &str) -> Event {
+    Event::new(
+        event_type,
+        EventPayload::Generic(serde_json::json!({ "n": 1 })),
+    )
+}
+
+fn mock(config: Config) -> MockIggyClient {
+    MockIggyClient::new(config).with_topic("orders", "created", 3)
+}
+
+#[tokio::test]
+async fn send_and_batch_are_recorded_in_order() {
+    let client = mock(Config::default());
+    let producer = ProducerService::new(client.clone());
+
+    let first = event("order.created");
+    producer
+        .send_to("orders", "created", &first, None, None)
+        .await
+        .unwrap();
+    let batch = [event("order.paid"), event("order.shipped")];
+    let responses = producer
+        .send_batch_to("orders", "created", &batch, Some("customer-1"), None)
+        .await
+        .unwrap();
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(producer.messages_sent(), 3);
+    let sent: Vec<_> = client
+        .sent_events("orders", "created")
+        .into_iter()
+        .map(|event| event.event_type)
+        .collect();
+    assert_eq!(sent, ["order.created", "order.paid", "order.shipped"]);
+}
+
+#[tokio::test]
+async fn missing_topic_is_not_found() {
+    let producer = ProducerService::new(mock(Config::default()));
+    let result = producer
+        .send_to("orders", "missing", &event("order.created"), None, None)
+        .await;
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn partition_id_is_checked_against_the_topic() {
+    let producer = ProducerService::new(mock(Config::default()));
+    let send = |partition_id| {
+        let producer = producer.clone();
+        async move {
+            producer
+                .send_to(
+                    "orders",
+                    "created",
+                    &event("order.created"),
+                    None,
+                    Some(PartitioningStrategy::PartitionId(partition_id)),
+                )
+                .await
+        }
+    };
+
+    assert!(send(2).await.is_ok());
+    assert!(matches!(send(3).await, Err(AppError::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn injected_failure_fails_only_the_next_send() {
+    let client = mock(Config::default());
+    let producer = ProducerService::new(client.clone());
+    client.fail_next(
+        MockOperation::Send,
+        1,
+        AppError::Disconnected("injected".to_string()),
+    );
+
+    let failed = producer
+        .send_to("orders", "created", &event("order.created"), None, None)
+        .await;
+    assert!(matches!(failed, Err(AppError::Disconnected(_))));
+    assert!(client.sent_events("orders", "created").is_empty());
+
+    producer
+        .send_to("orders", "created", &event("order.created"), None, None)
+        .await
+        .unwrap();
+    assert_eq!(client.sent_events("orders", "created").len(), 1);
+    assert_eq!(producer.messages_sent(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_send_times_out_under_request_timeout() {
+    let client = mock(Config::default());
+    let producer = ProducerService::new(client.clone());
+    client.delay_next(MockOperation::Send, 1, Duration::from_secs(10));
+
+    let result = producer
+        .with_timeout(Duration::from_secs(2))
+        .send_to("orders", "created", &event("order.created"), None, None)
+        .await;
+
+    assert!(matches!(result, Err(AppError::OperationTimeout(_))));
+    assert!(client.sent_events("orders", "created").is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn coalesced_sends_share_one_batch() {
+    let config = Config {
+        coalesce_window: Duration::from_millis(5),
+        coalesce_max_batch: 10,
+        ..Config::default()
+    };
+    let client = mock(config);
+    let producer = ProducerService::new(client.clone());
+    // One failure for the whole batch fails every caller in it
+    client.fail_next(
+        MockOperation::Send,
+        1,
+        AppError::ConnectionReset("injected".to_string()),
+    );
+
+    let first = event("order.created");
+    let second = event("order.paid");
+    let (a, b) = tokio::join!(
+        producer.send_to("orders", "created", &first, None, None),
+        producer.send_to("orders", "created", &second, None, None),
+    );
+    assert!(matches!(a, Err(AppError::ConnectionReset(_))));
+    assert!(matches!(b, Err(AppError::ConnectionReset(_))));
+
+    let (a, b) = tokio::join!(
+        producer.send_to("orders", "created", &first, None, None),
+        producer.send_to("orders", "created", &second, None, None),
+    );
+    a.unwrap();
+    b.unwrap();
+    assert_eq!(client.sent_events("orders", "created").len(), 2);
+}
+
+#[tokio::test]
+async fn partially_sent_atomic_batch_is_compensated() {
+    use iggy_sample::models::{AtomicChunkStatus, BatchCompensation};
+
+    let batch: Vec<Event> = (0..5).map(|_| event("order.created")).collect();
+    for compensation in [BatchCompensation::Tombstone, BatchCompensation::Dlq] {
+        let client = mock(Config {
+            atomic_batch_chunk_size: 2,
+            atomic_batch_compensation: compensation,
+            ..Config::default()
+        });
+        let producer = ProducerService::new(client.clone());
+        // The first chunk goes through, the second fails
+        client.delay_next(MockOperation::Send, 1, Duration::ZERO);
+        client.fail_next(
+            MockOperation::Send,
+            1,
+            AppError::ConnectionReset("injected".to_string()),
+        );
+
+        let report = producer
+            .send_batch_atomic_to("orders", "created", &batch, None, None)
+            .await
+            .unwrap();
+        assert!(!report.success);
+        assert_eq!((report.confirmed, report.failed), (2, 3));
+        let statuses: Vec<_> = report.chunks.iter().map(|chunk| chunk.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                AtomicChunkStatus::Confirmed,
+                AtomicChunkStatus::Failed,
+                AtomicChunkStatus::NotSent
+            ]
+        );
+        let compensation_report = report.compensation.unwrap();
+        assert!(compensation_report.success);
+
+        let sent = client.sent_events("orders", "created");
+        match compensation {
+            BatchCompensation::Tombstone => {
+                assert_eq!(sent.len(), 4);
+                assert!(
+                    sent[2..]
+                        .iter()
+                        .all(|event| event.event_type == "batch.compensated")
+                );
+            }
+            BatchCompensation::Dlq => {
+                assert_eq!(sent.len(), 2);
+                let dead: Vec<_> = client
+                    .sent_events("orders", "dlq")
+                    .into_iter()
+                    .map(|event| event.id)
+                    .collect();
+                let unconfirmed: Vec<_> = batch[2..].iter().map(|event| event.id).collect();
+                assert_eq!(dead, unconfirmed);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn balanced_atomic_batch_tombstones_follow_their_events() {
+    use std::collections::HashMap;
+
+    use iggy_sample::models::BatchCompensation;
+
+    let client = mock(Config {
+        atomic_batch_chunk_size: 2,
+        atomic_batch_compensation: BatchCompensation::Tombstone,
+        ..Config::default()
+    });
+    let producer = ProducerService::new(client.clone());
+    // Two chunks go through, the third fails
+    client.delay_next(MockOperation::Send, 2, Duration::ZERO);
+    client.fail_next(
+        MockOperation::Send,
+        1,
+        AppError::ConnectionReset("injected".to_string()),
+    );
+
+    let batch: Vec<Event> = (0..6).map(|_| event("order.created")).collect();
+    let report = producer
+        .send_batch_atomic_to(
+            "orders",
+            "created",
+            &batch,
+            None,
+            Some(PartitioningStrategy::Balanced),
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.confirmed, 4);
+    assert!(report.compensation.unwrap().success);
+    // The confirmed chunks were spread over different partitions
+    let chunk_partitions: Vec<_> = report
+        .chunks
+        .iter()
+        .map(|chunk| chunk.partition_id.unwrap())
+        .collect();
+    assert_ne!(chunk_partitions.first(), chunk_partitions.get(1));
+
+    let sent = client.sent_events("orders", "created");
+    let partitions = client.sent_partitions("orders", "created");
+    assert_eq!(sent.len(), 8);
+    let written_to: HashMap<String, u32> = sent
+        .iter()
+        .zip(&partitions)
+        .take(4)
+        .map(|(event, partition)| (event.id.to_string(), *partition))
+        .collect();
+    for (tombstone, partition) in sent.iter().zip(&partitions).skip(4) {
+        assert_eq!(tombstone.event_type, "batch.compensated");
+        let EventPayload::Generic(data) = &tombstone.payload else {
+            panic!("unexpected tombstone payload");
+        };
+        let compensated = data.get("compensates").and_then(|id| id.as_str()).unwrap();
+        assert_eq!(written_to.get(compensated), Some(partition));
+    }
+}
+
+/// Serve the HTTP API backed by `client` on an ephemeral port and return
+/// its base URL.
+async fn start_app_on(client: MockIggyClient) -> String {
+    let state = AppState::with_broker(client, Config::default());
+    state.iggy_client.initialize_defaults().await.unwrap();
+    let app = build_router(state).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    format!("http://{address}")
+}
+
+#[tokio::test]
+async fn handlers_send_and_poll_through_the_mock() {
+    let client = MockIggyClient::new(Config::default());
+    let base = start_app_on(client.clone()).await;
+    let config = Config::default();
+    let http = reqwest::Client::new();
+
+    let response = http
+        .post(format!("{base}/messages"))
+        .json(&json!({
+            "event": {
+                "id": uuid::Uuid::new_v4(),
+                "event_type": "order.created",
+                "timestamp": "2024-01-15T10:30:00Z",
+                "payload": { "type": "Generic", "data": { "n": 1 } }
+            },
+            "partitioning": "partition_id:0"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success(), "{}", response.status());
+    let sent = client.sent_events(&config.default_stream, &config.default_topic);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].event_type, "order.created");
+
+    let poll = format!("{base}/messages?partition_id=0&consumer_id=7&count=10");
+    let body: Value = http.get(&poll).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["messages"][0]["event"]["event_type"], "order.created");
+
+    // Mock faults reach the handler through the wrapper
+    client.fail_next(
+        MockOperation::Poll,
+        1,
+        AppError::BadRequest("injected".into()),
+    );
+    let response = http.get(&poll).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
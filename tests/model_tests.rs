@@ -1,5 +1,5 @@
 //! Unit tests for domain models.
-#![allow(clippy::unwrap_used, clippy::expect_used)]
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 
 use chrono::Utc;
 use serde_json::json;
@@ -11,7 +11,10 @@ use uuid::Uuid;
 /// Event model module containing types for testing
 mod event_tests {
     use super::*;
-    use iggy_sample::models::{Event, EventPayload, OrderEvent, OrderItem, OrderStatus, UserEvent};
+    use iggy_sample::models::{
+        Event, EventPayload, InventoryEvent, OrderEvent, OrderItem, OrderStatus, PaymentEvent,
+        UserEvent,
+    };
     use rust_decimal::Decimal;
     use std::str::FromStr;
 
@@ -158,6 +161,130 @@ mod event_tests {
         }
     }
 
+    #[test]
+    fn test_inventory_events_roundtrip() {
+        let reservation_id = Uuid::new_v4();
+        let events = [
+            InventoryEvent::StockAdjusted {
+                sku: "SKU-1001".to_string(),
+                warehouse_id: "wh-east".to_string(),
+                delta: -3,
+                quantity_on_hand: 42,
+                reason: "damaged".to_string(),
+            },
+            InventoryEvent::Reserved {
+                reservation_id,
+                order_id: Uuid::new_v4(),
+                sku: "SKU-1001".to_string(),
+                warehouse_id: "wh-east".to_string(),
+                quantity: 2,
+                expires_at: Utc::now(),
+            },
+            InventoryEvent::Released {
+                reservation_id,
+                sku: "SKU-1001".to_string(),
+                quantity: 2,
+                reason: "order_cancelled".to_string(),
+            },
+        ];
+
+        for inventory_event in events {
+            let event = Event::new(
+                "inventory.changed",
+                EventPayload::Inventory(inventory_event),
+            );
+            let json = serde_json::to_value(&event).expect("Serialization failed");
+            assert_eq!(json["payload"]["type"], "Inventory");
+            let parsed: Event = serde_json::from_value(json).expect("Deserialization failed");
+            assert!(matches!(parsed.payload, EventPayload::Inventory(_)));
+        }
+
+        let json = json!({
+            "type": "Inventory",
+            "data": {
+                "action": "StockAdjusted",
+                "sku": "SKU-1001",
+                "warehouse_id": "wh-east",
+                "delta": -3,
+                "quantity_on_hand": 42,
+                "reason": "damaged"
+            }
+        });
+        match serde_json::from_value::<EventPayload>(json).expect("Deserialization failed") {
+            EventPayload::Inventory(InventoryEvent::StockAdjusted { delta, .. }) => {
+                assert_eq!(delta, -3);
+            }
+            other => panic!("Wrong payload: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_payment_events_roundtrip() {
+        let payment_id = Uuid::new_v4();
+        let order_id = Uuid::new_v4();
+        let amount = Decimal::from_str("59.98").expect("Invalid decimal");
+        let events = [
+            PaymentEvent::Authorized {
+                payment_id,
+                order_id,
+                amount,
+                currency: "USD".to_string(),
+                method: "card".to_string(),
+                authorization_code: "A1B2C3".to_string(),
+            },
+            PaymentEvent::Captured {
+                payment_id,
+                amount,
+                currency: "USD".to_string(),
+            },
+            PaymentEvent::Refunded {
+                payment_id,
+                refund_id: Uuid::new_v4(),
+                amount: Decimal::from_str("10.00").expect("Invalid decimal"),
+                currency: "USD".to_string(),
+                reason: "item_returned".to_string(),
+            },
+            PaymentEvent::Failed {
+                payment_id,
+                order_id,
+                amount,
+                currency: "USD".to_string(),
+                failure_code: "insufficient_funds".to_string(),
+                message: None,
+            },
+        ];
+
+        for payment_event in events {
+            let event = Event::new("payment.changed", EventPayload::Payment(payment_event));
+            let json = serde_json::to_string(&event).expect("Serialization failed");
+            let parsed: Event = serde_json::from_str(&json).expect("Deserialization failed");
+            assert!(matches!(parsed.payload, EventPayload::Payment(_)));
+        }
+
+        // Amounts stay exact decimal strings on the wire
+        let captured = PaymentEvent::Captured {
+            payment_id,
+            amount,
+            currency: "USD".to_string(),
+        };
+        let json = serde_json::to_value(&captured).expect("Serialization failed");
+        assert_eq!(json["action"], "Captured");
+        assert_eq!(json["amount"], "59.98");
+
+        let failed = json!({
+            "action": "Failed",
+            "payment_id": payment_id,
+            "order_id": order_id,
+            "amount": "59.98",
+            "currency": "USD",
+            "failure_code": "card_declined"
+        });
+        match serde_json::from_value::<PaymentEvent>(failed).expect("Deserialization failed") {
+            PaymentEvent::Failed { message, .. } => assert!(message.is_none()),
+            other => panic!("Wrong payment event: {other:?}"),
+        }
+    }
+
     #[test]
     fn test_generic_payload() {
         let payload = json!({
@@ -217,7 +344,8 @@ mod event_tests {
 mod api_tests {
     use super::*;
     use iggy_sample::models::{
-        CreateStreamRequest, CreateTopicRequest, HealthResponse, StatsResponse,
+        CircuitBreakerStates, CreateStreamRequest, CreateTopicRequest, EnabledFeatures,
+        HealthResponse, StatsResponse,
     };
 
     #[test]
@@ -251,6 +379,20 @@ mod api_tests {
         let response = HealthResponse {
             status: "healthy".to_string(),
             iggy_connected: true,
+            iggy_endpoint: None,
+            iggy_read: None,
+            storage_pressure: None,
+            leadership: None,
+            circuit_breakers: CircuitBreakerStates {
+                send: "closed".to_string(),
+                poll: "closed".to_string(),
+                admin: "closed".to_string(),
+            },
+            features: EnabledFeatures {
+                produce_api: true,
+                consume_api: true,
+                admin_api: true,
+            },
             version: "0.1.0".to_string(),
             timestamp: Utc::now(),
         };
@@ -271,6 +413,8 @@ mod api_tests {
             uptime_seconds: 3600,
             cache_age_seconds: 2,
             cache_stale: false,
+            event_time_lag: Vec::new(),
+            anomalies: Vec::new(),
         };
 
         let json = serde_json::to_string(&response).expect("Serialization failed");
@@ -0,0 +1,136 @@
+//! Property-based tests for request validation and trusted-proxy CIDRs.
+//!
+//! Each validator is run on generated input that is mostly near the rules
+//! (the allowed alphabet plus a few offending characters) and partly
+//! arbitrary, and must never panic and agree with a small reference model.
+//!
+//! Run with: `cargo test --test validation_proptests`
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::LazyLock;
+
+use iggy_sample::middleware::rate_limit::CidrRange;
+use iggy_sample::validation::{
+    MAX_EVENT_TYPE_LENGTH, MAX_NAME_LENGTH, validate_event_type, validate_resource_name,
+};
+use proptest::prelude::*;
+use regex::Regex;
+
+// =============================================================================
+// Reference Models
+// =============================================================================
+
+/// Alphanumeric runs separated by single dots, underscores or hyphens.
+static RESOURCE_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9]+(?:[._-][A-Za-z0-9]+)*$").unwrap());
+
+fn model_resource_name(name: &str) -> bool {
+    name.len() <= MAX_NAME_LENGTH && RESOURCE_NAME.is_match(name)
+}
+
+fn model_event_type(event_type: &str) -> bool {
+    (1..=MAX_EVENT_TYPE_LENGTH).contains(&event_type.len())
+        && !event_type.chars().any(char::is_control)
+}
+
+/// `ip` is in `network/prefix_len` when the first `prefix_len` bits agree.
+fn model_contains(network: IpAddr, prefix_len: u8, ip: IpAddr) -> bool {
+    let differing = match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            (u32::from(network) ^ u32::from(ip)).leading_zeros()
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            (u128::from(network) ^ u128::from(ip)).leading_zeros()
+        }
+        _ => return false,
+    };
+    differing >= u32::from(prefix_len)
+}
+
+fn max_prefix(ip: IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+// =============================================================================
+// Generators
+// =============================================================================
+
+/// Strings over the name alphabet, with some characters names may not use.
+fn name_like() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => "[a-zA-Z0-9._-]{0,20}",
+        2 => "[a-zA-Z0-9._ /é-]{0,20}",
+        1 => "[a-z]{250,260}",
+        1 => any::<String>(),
+    ]
+}
+
+/// Strings around the event-type rules: printable, control characters,
+/// and lengths about the limit.
+fn event_type_like() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => "[a-z]{1,10}\\.[a-z]{1,10}",
+        2 => "[a-z.\\t\\n\\x00\\x7f]{0,12}",
+        1 => "[a-z]{250,260}",
+        1 => any::<String>(),
+    ]
+}
+
+fn ip() -> impl Strategy<Value = IpAddr> {
+    prop_oneof![
+        any::<u32>().prop_map(|bits| IpAddr::V4(Ipv4Addr::from(bits))),
+        any::<u128>().prop_map(|bits| IpAddr::V6(Ipv6Addr::from(bits))),
+    ]
+}
+
+// =============================================================================
+// Properties
+// =============================================================================
+
+proptest! {
+    #[test]
+    fn resource_names_follow_the_model(name in name_like()) {
+        let result = validate_resource_name(&name, "Stream");
+        prop_assert_eq!(result.is_ok(), model_resource_name(&name), "{:?}", name);
+    }
+
+    #[test]
+    fn event_types_follow_the_model(event_type in event_type_like()) {
+        let result = validate_event_type(&event_type);
+        prop_assert_eq!(result.is_ok(), model_event_type(&event_type), "{:?}", event_type);
+    }
+
+    #[test]
+    fn cidr_parsing_never_panics(cidr in any::<String>()) {
+        let _ = CidrRange::parse(&cidr);
+    }
+
+    #[test]
+    fn cidr_accepts_exactly_the_valid_prefixes(network in ip(), prefix_len in 0u8..=140) {
+        let parsed = CidrRange::parse(&format!("{network}/{prefix_len}"));
+        prop_assert_eq!(parsed.is_some(), prefix_len <= max_prefix(network));
+    }
+
+    #[test]
+    fn cidr_membership_follows_the_model(
+        network in ip(),
+        prefix_len in 0u8..=128,
+        addr in ip(),
+    ) {
+        let prefix_len = prefix_len.min(max_prefix(network));
+        let range = CidrRange::parse(&format!("{network}/{prefix_len}")).unwrap();
+
+        prop_assert!(range.contains(&network));
+        prop_assert_eq!(range.contains(&addr), model_contains(network, prefix_len, addr));
+    }
+
+    #[test]
+    fn bare_ip_is_a_single_address_range(network in ip(), addr in ip()) {
+        let range = CidrRange::parse(&network.to_string()).unwrap();
+        prop_assert_eq!(range.contains(&addr), addr == network);
+    }
+}